@@ -1,6 +1,15 @@
-use criterion::{criterion_group, criterion_main, Criterion};
-use netgauze_bgp_pkt::{wire::deserializer::BgpParsingContext, BgpMessage};
-use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+use bytes::BytesMut;
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use ipnet::Ipv4Net;
+use netgauze_bgp_pkt::{
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    path_attribute::{NextHop, Origin, PathAttribute, PathAttributeValue},
+    update::BgpUpdateMessage,
+    wire::deserializer::BgpParsingContext,
+    BgpMessage,
+};
+use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePdu};
+use std::{io::Cursor, net::Ipv4Addr};
 
 const OPEN_COMPLEX_NO_PARAMS: [u8; 29] = [
     0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
@@ -59,5 +68,65 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+pub fn write_cursor(msg: &BgpMessage) -> Vec<u8> {
+    let mut cursor = Cursor::new(Vec::new());
+    msg.write(&mut cursor).unwrap();
+    cursor.into_inner()
+}
+
+pub fn write_bytes_mut(msg: &BgpMessage, buf: &mut BytesMut) {
+    msg.write_into(buf).unwrap();
+}
+
+/// Large update message with many IPv4 NLRI, to exercise buffer growth
+fn large_update() -> BgpMessage {
+    let nlri = (0..1000u32)
+        .map(|i| {
+            let net = Ipv4Net::new(Ipv4Addr::from((10 << 24) | (i << 8)), 24).unwrap();
+            Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(net).unwrap())
+        })
+        .collect();
+    let path_attributes = vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 168, 0, 1))),
+        )
+        .unwrap(),
+    ];
+    BgpMessage::Update(BgpUpdateMessage::new(vec![], path_attributes, nlri))
+}
+
+pub fn serialize_benchmark(c: &mut Criterion) {
+    let (_, open) = BgpMessage::from_wire(
+        Span::new(&OPEN_COMPLEX_RAW),
+        &mut BgpParsingContext::default(),
+    )
+    .unwrap();
+    let update = large_update();
+    for (name, msg) in [("open complex", &open), ("large update", &update)] {
+        c.bench_function(&format!("write {name} cursor"), |b| {
+            b.iter(|| write_cursor(msg))
+        });
+        c.bench_function(&format!("write {name} bytes_mut"), |b| {
+            b.iter_batched_ref(
+                BytesMut::new,
+                |buf| write_bytes_mut(msg, buf),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+criterion_group!(benches, criterion_benchmark, serialize_benchmark);
 criterion_main!(benches);
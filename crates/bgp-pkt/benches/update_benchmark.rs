@@ -0,0 +1,353 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use bytes::BytesMut;
+use criterion::{
+    criterion_group, criterion_main,
+    measurement::{Measurement, ValueFormatter},
+    BatchSize, BenchmarkGroup, Criterion, Throughput,
+};
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use netgauze_bgp_pkt::{
+    iana::BgpLsProtocolId,
+    nlri::{
+        BgpLsLinkDescriptor, BgpLsLocalNodeDescriptors, BgpLsNlri, BgpLsNlriIpPrefix,
+        BgpLsNlriLink, BgpLsNlriValue, BgpLsNodeDescriptorSubTlv, BgpLsNodeDescriptors,
+        BgpLsPrefixDescriptor, BgpLsRemoteNodeDescriptors, IpReachabilityInformationData,
+        Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress,
+    },
+    path_attribute::{
+        As4PathSegment, AsPath, AsPathSegmentType, BgpLsAttribute, BgpLsAttributeValue,
+        LocalPreference, MpReach, MultiExitDiscriminator, NextHop, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+    wire::deserializer::BgpParsingContext,
+    BgpMessage,
+};
+use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePdu};
+
+/// Counts the heap allocations made by the benchmarked code, used by the
+/// [`Allocations`] measurement.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Criterion measurement reporting the number of heap allocations (including
+/// reallocations) instead of the wall time.
+struct Allocations;
+
+impl Measurement for Allocations {
+    type Intermediate = usize;
+    type Value = usize;
+
+    fn start(&self) -> Self::Intermediate {
+        ALLOCATIONS.load(Ordering::SeqCst)
+    }
+
+    fn end(&self, i: Self::Intermediate) -> Self::Value {
+        ALLOCATIONS.load(Ordering::SeqCst) - i
+    }
+
+    fn add(&self, v1: &Self::Value, v2: &Self::Value) -> Self::Value {
+        v1 + v2
+    }
+
+    fn zero(&self) -> Self::Value {
+        0
+    }
+
+    fn to_f64(&self, value: &Self::Value) -> f64 {
+        *value as f64
+    }
+
+    fn formatter(&self) -> &dyn ValueFormatter {
+        &AllocationsFormatter
+    }
+}
+
+struct AllocationsFormatter;
+
+impl ValueFormatter for AllocationsFormatter {
+    fn scale_values(&self, _typical_value: f64, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+
+    fn scale_throughputs(
+        &self,
+        _typical_value: f64,
+        throughput: &Throughput,
+        values: &mut [f64],
+    ) -> &'static str {
+        match throughput {
+            Throughput::Bytes(bytes) | Throughput::BytesDecimal(bytes) => {
+                values.iter_mut().for_each(|value| *value /= *bytes as f64);
+                "allocs/byte"
+            }
+            Throughput::Elements(elements) => {
+                values
+                    .iter_mut()
+                    .for_each(|value| *value /= *elements as f64);
+                "allocs/element"
+            }
+        }
+    }
+
+    fn scale_for_machines(&self, _values: &mut [f64]) -> &'static str {
+        "allocs"
+    }
+}
+
+fn well_known_attributes() -> Vec<PathAttribute> {
+    vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65001, 65002, 65003],
+            )])),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            false,
+            PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(100)),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::LocalPreference(LocalPreference::new(200)),
+        )
+        .unwrap(),
+    ]
+}
+
+/// Update with 1000 IPv4 unicast NLRI
+fn large_update() -> BgpMessage {
+    let nlri = (0..1000u32)
+        .map(|i| {
+            let net = Ipv4Net::new(Ipv4Addr::from((10 << 24) | (i << 8)), 24).unwrap();
+            Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(net).unwrap())
+        })
+        .collect();
+    let mut path_attributes = well_known_attributes();
+    path_attributes.push(
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 168, 0, 1))),
+        )
+        .unwrap(),
+    );
+    BgpMessage::Update(BgpUpdateMessage::new(vec![], path_attributes, nlri))
+}
+
+/// Update carrying 500 IPv6 unicast NLRI in MP_REACH_NLRI
+fn mp_reach_update() -> BgpMessage {
+    let nlri = (0..500u128)
+        .map(|i| {
+            let net = Ipv6Net::new(Ipv6Addr::from((0x2001_0db8 << 96) | (i << 80)), 48).unwrap();
+            Ipv6UnicastAddress::new(None, Ipv6Unicast::from_net(net).unwrap())
+        })
+        .collect();
+    let mut path_attributes = well_known_attributes();
+    path_attributes.push(
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            true,
+            PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                next_hop_global: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+                next_hop_local: Some(Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 1)),
+                nlri,
+            }),
+        )
+        .unwrap(),
+    );
+    BgpMessage::Update(BgpUpdateMessage::new(vec![], path_attributes, vec![]))
+}
+
+/// Update carrying BGP-LS link and prefix NLRI along with a BGP-LS attribute
+fn bgp_ls_update() -> BgpMessage {
+    let local_node_descriptors = BgpLsLocalNodeDescriptors(BgpLsNodeDescriptors(vec![
+        BgpLsNodeDescriptorSubTlv::AutonomousSystem(65001),
+        BgpLsNodeDescriptorSubTlv::BgpLsIdentifier(0),
+        BgpLsNodeDescriptorSubTlv::IgpRouterId(vec![0, 0, 0, 0, 0, 1]),
+    ]));
+    let nlri = (0..20u32)
+        .flat_map(|i| {
+            let link = BgpLsNlri {
+                path_id: None,
+                value: BgpLsNlriValue::Link(BgpLsNlriLink {
+                    protocol_id: BgpLsProtocolId::IsIsLevel2,
+                    identifier: 0,
+                    local_node_descriptors: local_node_descriptors.clone(),
+                    remote_node_descriptors: BgpLsRemoteNodeDescriptors(BgpLsNodeDescriptors(
+                        vec![
+                            BgpLsNodeDescriptorSubTlv::AutonomousSystem(65001),
+                            BgpLsNodeDescriptorSubTlv::IgpRouterId(vec![0, 0, 0, 0, 0, 2]),
+                        ],
+                    )),
+                    link_descriptors: vec![
+                        BgpLsLinkDescriptor::LinkLocalRemoteIdentifiers {
+                            link_local_identifier: i,
+                            link_remote_identifier: i + 1,
+                        },
+                        BgpLsLinkDescriptor::IPv4InterfaceAddress(Ipv4Addr::from(
+                            (10 << 24) | (i << 2) | 1,
+                        )),
+                        BgpLsLinkDescriptor::IPv4NeighborAddress(Ipv4Addr::from(
+                            (10 << 24) | (i << 2) | 2,
+                        )),
+                    ],
+                }),
+            };
+            let prefix = BgpLsNlri {
+                path_id: None,
+                value: BgpLsNlriValue::Ipv4Prefix(BgpLsNlriIpPrefix {
+                    protocol_id: BgpLsProtocolId::IsIsLevel2,
+                    identifier: 0,
+                    local_node_descriptors: local_node_descriptors.clone(),
+                    prefix_descriptors: vec![BgpLsPrefixDescriptor::IpReachabilityInformation(
+                        IpReachabilityInformationData(IpNet::V4(
+                            Ipv4Net::new(Ipv4Addr::from((172 << 24) | (i << 8)), 24).unwrap(),
+                        )),
+                    )],
+                }),
+            };
+            [link, prefix]
+        })
+        .collect();
+    let attribute = BgpLsAttribute {
+        attributes: vec![
+            BgpLsAttributeValue::LocalNodeIpv4RouterId(Ipv4Addr::new(192, 0, 2, 1)),
+            BgpLsAttributeValue::RemoteNodeIpv4RouterId(Ipv4Addr::new(192, 0, 2, 2)),
+            BgpLsAttributeValue::MaximumLinkBandwidth(1_250_000_000.0),
+            BgpLsAttributeValue::MaximumReservableLinkBandwidth(1_000_000_000.0),
+            BgpLsAttributeValue::UnreservedBandwidth([1_000_000_000.0; 8]),
+            BgpLsAttributeValue::TeDefaultMetric(10),
+            BgpLsAttributeValue::IgpMetric(vec![0, 0, 10]),
+            BgpLsAttributeValue::LinkName("core-link-1".to_string()),
+        ],
+    };
+    let mut path_attributes = well_known_attributes();
+    path_attributes.push(
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            true,
+            PathAttributeValue::MpReach(MpReach::BgpLs {
+                next_hop: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                nlri,
+            }),
+        )
+        .unwrap(),
+    );
+    path_attributes.push(
+        PathAttribute::from(
+            true,
+            false,
+            false,
+            false,
+            PathAttributeValue::BgpLs(attribute),
+        )
+        .unwrap(),
+    );
+    BgpMessage::Update(BgpUpdateMessage::new(vec![], path_attributes, vec![]))
+}
+
+fn updates() -> Vec<(&'static str, BgpMessage, Vec<u8>)> {
+    [
+        ("large update", large_update()),
+        ("mp_reach update", mp_reach_update()),
+        ("bgp-ls update", bgp_ls_update()),
+    ]
+    .into_iter()
+    .map(|(name, msg)| {
+        let mut wire = Vec::with_capacity(msg.len());
+        msg.write(&mut wire).unwrap();
+        (name, msg, wire)
+    })
+    .collect()
+}
+
+fn bench_updates<M: Measurement>(group: &mut BenchmarkGroup<'_, M>) {
+    for (name, msg, wire) in updates() {
+        group.throughput(Throughput::Bytes(wire.len() as u64));
+        group.bench_function(format!("parse {name}"), |b| {
+            b.iter(|| {
+                let mut ctx = BgpParsingContext::default();
+                BgpMessage::from_wire(Span::new(&wire), &mut ctx).unwrap()
+            })
+        });
+        group.bench_function(format!("write {name}"), |b| {
+            b.iter_batched_ref(
+                || BytesMut::with_capacity(msg.len()),
+                |buf| msg.write_into(buf).unwrap(),
+                BatchSize::SmallInput,
+            )
+        });
+    }
+}
+
+fn update_time_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update");
+    bench_updates(&mut group);
+    group.finish();
+}
+
+fn update_allocations_benchmark(c: &mut Criterion<Allocations>) {
+    let mut group = c.benchmark_group("update allocations");
+    bench_updates(&mut group);
+    group.finish();
+}
+
+criterion_group!(time_benches, update_time_benchmark);
+criterion_group!(
+    name = allocation_benches;
+    config = Criterion::default().with_measurement(Allocations);
+    targets = update_allocations_benchmark
+);
+criterion_main!(time_benches, allocation_benches);
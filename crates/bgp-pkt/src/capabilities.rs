@@ -16,7 +16,9 @@
 //! BGP Capabilities advertised in BGP Open Messages.
 //! See [RFC5492 Capabilities Advertisement with BGP-4](https://datatracker.ietf.org/doc/html/rfc5492)
 
+use crate::collections::{HashMap, HashSet};
 use crate::iana::{BgpCapabilityCode, BgpRoleValue};
+use alloc::vec::Vec;
 use netgauze_iana::address_family::{AddressFamily, AddressType};
 use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
@@ -507,3 +509,307 @@ impl BgpRoleCapability {
         self.role
     }
 }
+
+/// Capabilities agreed upon by both BGP speakers of a session, computed by
+/// [`negotiate`] from the capabilities advertised in the
+/// [`crate::open::BgpOpenMessage`] of each speaker.
+///
+/// Directional values, such as ADD-PATH send/receive, are from the point of
+/// view of the local speaker.
+#[derive(Debug, Clone, Eq, PartialEq, Default, Serialize, Deserialize)]
+pub struct NegotiatedCapabilities {
+    address_types: HashSet<AddressType>,
+    add_path: HashMap<AddressType, AddPathAddressFamily>,
+    multiple_labels: HashMap<AddressType, u8>,
+    extended_next_hop_encodings: Vec<ExtendedNextHopEncoding>,
+    four_octet_as: bool,
+    extended_message: bool,
+    route_refresh: bool,
+    enhanced_route_refresh: bool,
+    graceful_restart: bool,
+}
+
+impl NegotiatedCapabilities {
+    /// Address types (AFI/SAFI) both speakers can exchange. As defined in
+    /// [RFC4760](https://datatracker.ietf.org/doc/html/rfc4760), a speaker that
+    /// doesn't advertise any [`BgpCapability::MultiProtocolExtensions`] is
+    /// assumed to support [`AddressType::Ipv4Unicast`] only.
+    pub const fn address_types(&self) -> &HashSet<AddressType> {
+        &self.address_types
+    }
+
+    /// ADD-PATH directions enabled per address type as defined in
+    /// [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911). Only the
+    /// address types where at least one direction is enabled are included.
+    pub const fn add_path(&self) -> &HashMap<AddressType, AddPathAddressFamily> {
+        &self.add_path
+    }
+
+    /// Max number of labels per address type as defined in
+    /// [RFC8277](https://datatracker.ietf.org/doc/html/rfc8277)
+    pub const fn multiple_labels(&self) -> &HashMap<AddressType, u8> {
+        &self.multiple_labels
+    }
+
+    /// [RFC8950](https://datatracker.ietf.org/doc/html/rfc8950) next hop
+    /// encodings advertised by both speakers
+    pub const fn extended_next_hop_encodings(&self) -> &Vec<ExtendedNextHopEncoding> {
+        &self.extended_next_hop_encodings
+    }
+
+    pub const fn four_octet_as(&self) -> bool {
+        self.four_octet_as
+    }
+
+    pub const fn extended_message(&self) -> bool {
+        self.extended_message
+    }
+
+    pub const fn route_refresh(&self) -> bool {
+        self.route_refresh
+    }
+
+    pub const fn enhanced_route_refresh(&self) -> bool {
+        self.enhanced_route_refresh
+    }
+
+    pub const fn graceful_restart(&self) -> bool {
+        self.graceful_restart
+    }
+
+    /// Check if ADD-PATH is enabled for receiving routes of the given address
+    /// type, i.e., NLRI received from the peer carry a path identifier.
+    pub fn add_path_receive(&self, address_type: AddressType) -> bool {
+        self.add_path
+            .get(&address_type)
+            .map(|family| family.receive())
+            .unwrap_or(false)
+    }
+
+    /// Check if ADD-PATH is enabled for sending routes of the given address
+    /// type, i.e., NLRI sent to the peer must carry a path identifier.
+    pub fn add_path_send(&self, address_type: AddressType) -> bool {
+        self.add_path
+            .get(&address_type)
+            .map(|family| family.send())
+            .unwrap_or(false)
+    }
+}
+
+fn address_types(capabilities: &[BgpCapability]) -> HashSet<AddressType> {
+    let address_types: HashSet<AddressType> = capabilities
+        .iter()
+        .filter_map(|cap| match cap {
+            BgpCapability::MultiProtocolExtensions(mp) => Some(mp.address_type()),
+            _ => None,
+        })
+        .collect();
+    if address_types.is_empty() {
+        HashSet::from([AddressType::Ipv4Unicast])
+    } else {
+        address_types
+    }
+}
+
+fn add_path(capabilities: &[BgpCapability]) -> HashMap<AddressType, (bool, bool)> {
+    let mut add_path = HashMap::new();
+    for cap in capabilities {
+        if let BgpCapability::AddPath(cap) = cap {
+            for family in cap.address_families() {
+                let (send, receive) = add_path
+                    .entry(family.address_type())
+                    .or_insert((false, false));
+                *send |= family.send();
+                *receive |= family.receive();
+            }
+        }
+    }
+    add_path
+}
+
+fn multiple_labels(capabilities: &[BgpCapability]) -> HashMap<AddressType, u8> {
+    let mut multiple_labels = HashMap::new();
+    for cap in capabilities {
+        if let BgpCapability::MultipleLabels(labels) = cap {
+            for label in labels {
+                multiple_labels.insert(label.address_type(), label.count());
+            }
+        }
+    }
+    multiple_labels
+}
+
+fn has_capability(capabilities: &[BgpCapability], f: impl Fn(&BgpCapability) -> bool) -> bool {
+    capabilities.iter().any(f)
+}
+
+/// Compute the [`NegotiatedCapabilities`] of a BGP session from the
+/// capabilities advertised by the `local` speaker and the `remote` peer.
+pub fn negotiate(local: &[BgpCapability], remote: &[BgpCapability]) -> NegotiatedCapabilities {
+    let remote_address_types = address_types(remote);
+    let address_types: HashSet<AddressType> = address_types(local)
+        .into_iter()
+        .filter(|address_type| remote_address_types.contains(address_type))
+        .collect();
+
+    let remote_add_path = add_path(remote);
+    let add_path = add_path(local)
+        .into_iter()
+        .filter(|(address_type, _)| address_types.contains(address_type))
+        .filter_map(|(address_type, (local_send, local_receive))| {
+            let (remote_send, remote_receive) = remote_add_path.get(&address_type)?;
+            let send = local_send && *remote_receive;
+            let receive = local_receive && *remote_send;
+            if send || receive {
+                Some((
+                    address_type,
+                    AddPathAddressFamily::new(address_type, send, receive),
+                ))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    let remote_multiple_labels = multiple_labels(remote);
+    let multiple_labels = multiple_labels(local)
+        .into_iter()
+        .filter(|(address_type, _)| address_types.contains(address_type))
+        .filter_map(|(address_type, count)| {
+            let remote_count = remote_multiple_labels.get(&address_type)?;
+            Some((address_type, count.min(*remote_count)))
+        })
+        .collect();
+
+    let extended_next_hop_encodings = |capabilities: &[BgpCapability]| {
+        capabilities
+            .iter()
+            .filter_map(|cap| match cap {
+                BgpCapability::ExtendedNextHopEncoding(cap) => Some(cap.encodings().clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>()
+    };
+    let remote_encodings = extended_next_hop_encodings(remote);
+    let extended_next_hop_encodings = extended_next_hop_encodings(local)
+        .into_iter()
+        .filter(|encoding| {
+            address_types.contains(&encoding.address_type()) && remote_encodings.contains(encoding)
+        })
+        .collect();
+
+    let both =
+        |f: fn(&BgpCapability) -> bool| has_capability(local, f) && has_capability(remote, f);
+    NegotiatedCapabilities {
+        address_types,
+        add_path,
+        multiple_labels,
+        extended_next_hop_encodings,
+        four_octet_as: both(|cap| matches!(cap, BgpCapability::FourOctetAs(_))),
+        extended_message: both(|cap| matches!(cap, BgpCapability::ExtendedMessage)),
+        route_refresh: both(|cap| {
+            matches!(
+                cap,
+                BgpCapability::RouteRefresh | BgpCapability::CiscoRouteRefresh
+            )
+        }),
+        enhanced_route_refresh: both(|cap| matches!(cap, BgpCapability::EnhancedRouteRefresh)),
+        graceful_restart: both(|cap| matches!(cap, BgpCapability::GracefulRestartCapability(_))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let local = vec![
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv4Unicast,
+            )),
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv6Unicast,
+            )),
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv4MplsLabeledVpn,
+            )),
+            BgpCapability::FourOctetAs(FourOctetAsCapability::new(65000)),
+            BgpCapability::ExtendedMessage,
+            BgpCapability::RouteRefresh,
+            BgpCapability::AddPath(AddPathCapability::new(vec![
+                AddPathAddressFamily::new(AddressType::Ipv4Unicast, true, true),
+                AddPathAddressFamily::new(AddressType::Ipv6Unicast, true, false),
+            ])),
+            BgpCapability::MultipleLabels(vec![MultipleLabel::new(
+                AddressType::Ipv4MplsLabeledVpn,
+                3,
+            )]),
+        ];
+        let remote = vec![
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv4Unicast,
+            )),
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv6Unicast,
+            )),
+            BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+                AddressType::Ipv4MplsLabeledVpn,
+            )),
+            BgpCapability::FourOctetAs(FourOctetAsCapability::new(65001)),
+            BgpCapability::CiscoRouteRefresh,
+            BgpCapability::AddPath(AddPathCapability::new(vec![
+                AddPathAddressFamily::new(AddressType::Ipv4Unicast, false, true),
+                AddPathAddressFamily::new(AddressType::Ipv6Unicast, true, false),
+            ])),
+            BgpCapability::MultipleLabels(vec![MultipleLabel::new(
+                AddressType::Ipv4MplsLabeledVpn,
+                2,
+            )]),
+        ];
+        let negotiated = negotiate(&local, &remote);
+        assert_eq!(
+            negotiated.address_types(),
+            &HashSet::from([
+                AddressType::Ipv4Unicast,
+                AddressType::Ipv6Unicast,
+                AddressType::Ipv4MplsLabeledVpn
+            ])
+        );
+        assert_eq!(
+            negotiated.add_path(),
+            &HashMap::from([(
+                AddressType::Ipv4Unicast,
+                AddPathAddressFamily::new(AddressType::Ipv4Unicast, true, false)
+            )])
+        );
+        assert!(negotiated.add_path_send(AddressType::Ipv4Unicast));
+        assert!(!negotiated.add_path_receive(AddressType::Ipv4Unicast));
+        assert!(!negotiated.add_path_send(AddressType::Ipv6Unicast));
+        assert_eq!(
+            negotiated.multiple_labels(),
+            &HashMap::from([(AddressType::Ipv4MplsLabeledVpn, 2)])
+        );
+        assert!(negotiated.four_octet_as());
+        assert!(!negotiated.extended_message());
+        assert!(negotiated.route_refresh());
+        assert!(!negotiated.enhanced_route_refresh());
+        assert!(!negotiated.graceful_restart());
+    }
+
+    #[test]
+    fn test_negotiate_implicit_ipv4_unicast() {
+        let local = vec![BgpCapability::MultiProtocolExtensions(
+            MultiProtocolExtensionsCapability::new(AddressType::Ipv6Unicast),
+        )];
+        let negotiated = negotiate(&local, &[]);
+        assert!(negotiated.address_types().is_empty());
+        let negotiated = negotiate(&[], &[]);
+        assert_eq!(
+            negotiated.address_types(),
+            &HashSet::from([AddressType::Ipv4Unicast])
+        );
+        assert!(!negotiated.four_octet_as());
+    }
+}
@@ -13,31 +13,61 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+//! Codec to frame, decode, and encode BGP messages from raw TCP streams using
+//! [`tokio_util::codec::Framed`].
+
 use byteorder::{ByteOrder, NetworkEndian};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BytesMut};
 use nom::Needed;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use tokio_util::codec::{Decoder, Encoder};
 
 use crate::{
-    capabilities::BgpCapability,
+    capabilities::{negotiate, BgpCapability},
     wire::{
-        deserializer::{BgpMessageParsingError, BgpParsingContext, BgpParsingIgnoredErrors},
+        deserializer::{
+            BgpMessageParsingError, BgpParsingContext, BgpParsingIgnoredErrors,
+            BGP_EXTENDED_MAX_MESSAGE_LENGTH, BGP_MAX_MESSAGE_LENGTH, BGP_MIN_MESSAGE_LENGTH,
+        },
         serializer::BgpMessageWritingError,
     },
     BgpMessage,
 };
+use netgauze_iana::address_family::AddressType;
 use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu};
 
+/// Length of the BGP synchronization marker
+const BGP_MARKER_LENGTH: usize = 16;
+
 pub trait BgpCodecInitializer<Peer> {
     fn new(peer: &Peer) -> Self;
 }
 
+/// Encoder and Decoder for [`BgpMessage`].
+///
+/// The decoder frames messages based on the 16-octets marker and the 2-octets
+/// length field of the BGP header. It waits until a full message is received
+/// before decoding it, and fails early if the marker is not synchronized or
+/// the length is outside the allowed range. The max allowed length is
+/// [`BGP_MAX_MESSAGE_LENGTH`], and raised to
+/// [`BGP_EXTENDED_MAX_MESSAGE_LENGTH`] once both peers announce
+/// [`BgpCapability::ExtendedMessage`] as defined in
+/// [RFC8654](https://datatracker.ietf.org/doc/html/rfc8654).
+///
+/// AS number length (2 or 4 octets) and extended message support are tracked
+/// from the `OPEN` messages passing through the codec in each direction. Once
+/// both `OPEN` messages are seen, the received messages are parsed with the
+/// negotiated ADD-PATH ([RFC7911](https://datatracker.ietf.org/doc/html/rfc7911))
+/// and multiple labels settings.
 #[derive(Debug, Clone, Default)]
 pub struct BgpCodec {
     asn4_sent: Option<bool>,
     asn4_received: Option<bool>,
+    extended_message_sent: Option<bool>,
+    extended_message_received: Option<bool>,
+    capabilities_sent: Option<Vec<BgpCapability>>,
+    capabilities_received: Option<Vec<BgpCapability>>,
     ctx: BgpParsingContext,
 }
 
@@ -46,6 +76,10 @@ impl BgpCodec {
         Self {
             asn4_sent: Some(asn4),
             asn4_received: Some(asn4),
+            extended_message_sent: None,
+            extended_message_received: None,
+            capabilities_sent: None,
+            capabilities_received: None,
             ctx: BgpParsingContext::new(
                 true,
                 HashMap::new(),
@@ -57,6 +91,39 @@ impl BgpCodec {
             ),
         }
     }
+
+    /// Explicitly enable or disable extended messages support in both
+    /// directions, e.g., when decoding a stream in the middle of a session
+    /// where the `OPEN` messages are not seen by the codec.
+    pub fn set_extended_message(&mut self, extended_message: bool) {
+        self.extended_message_sent = Some(extended_message);
+        self.extended_message_received = Some(extended_message);
+    }
+
+    /// Max length of a BGP message accepted by the codec.
+    pub fn max_message_length(&self) -> u16 {
+        // Extended messages are used only when both peers agree on enabling them
+        if self.extended_message_received.unwrap_or(false)
+            && self.extended_message_sent.unwrap_or(false)
+        {
+            BGP_EXTENDED_MAX_MESSAGE_LENGTH
+        } else {
+            BGP_MAX_MESSAGE_LENGTH
+        }
+    }
+
+    /// Paths IDs are expected in the received messages of the address types
+    /// set to `true`, see [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911)
+    pub fn add_path(&self) -> &HashMap<AddressType, bool> {
+        self.ctx.add_path()
+    }
+
+    fn update_negotiated_capabilities(&mut self) {
+        if let (Some(sent), Some(received)) = (&self.capabilities_sent, &self.capabilities_received)
+        {
+            self.ctx.update_capabilities(&negotiate(sent, received));
+        }
+    }
 }
 
 impl<Peer> BgpCodecInitializer<Peer> for BgpCodec {
@@ -83,57 +150,80 @@ impl Decoder for BgpCodec {
     type Error = BgpCodecDecoderError;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if buf.len() >= 19 {
-            let length: u16 = NetworkEndian::read_u16(&buf[16..19]);
-            let length = length as usize;
-            if buf.len() < length {
-                Ok(None)
-            } else {
-                if log::log_enabled!(log::Level::Debug) {
-                    log::debug!("Decoding buffer message: {buf:?}")
+        if buf.len() < BGP_MIN_MESSAGE_LENGTH as usize {
+            // We don't have enough data yet to start processing
+            return Ok(None);
+        }
+        // Framing errors are not recoverable, since we don't know where the next
+        // message starts. Hence, the buffer is left as is.
+        let marker = NetworkEndian::read_u128(&buf[..BGP_MARKER_LENGTH]);
+        if marker != u128::MAX {
+            return Err(BgpCodecDecoderError::BgpMessageParsingError(
+                BgpMessageParsingError::ConnectionNotSynchronized(marker),
+            ));
+        }
+        let length = NetworkEndian::read_u16(&buf[BGP_MARKER_LENGTH..]);
+        if !(BGP_MIN_MESSAGE_LENGTH..=self.max_message_length()).contains(&length) {
+            return Err(BgpCodecDecoderError::BgpMessageParsingError(
+                BgpMessageParsingError::BadMessageLength(length),
+            ));
+        }
+        let length = length as usize;
+        if buf.len() < length {
+            // We still didn't read all the bytes for the message yet
+            buf.reserve(length - buf.len());
+            return Ok(None);
+        }
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("Decoding buffer message: {:?}", &buf[..length])
+        }
+        // ASN4 capability is used only when both peers agree on enabling ASN4
+        let asn4 = self.asn4_received.unwrap_or(false) && self.asn4_sent.unwrap_or(false);
+        self.ctx.set_asn4(asn4);
+        let ret = BgpMessage::from_wire(Span::new(&buf[..length]), &mut self.ctx);
+        let decoding_result = match ret {
+            Ok((_span, msg)) => {
+                buf.advance(length);
+                if let BgpMessage::Open(ref open) = msg {
+                    let capabilities = open.capabilities();
+                    let asn4 = capabilities
+                        .iter()
+                        .any(|cap| matches!(cap, BgpCapability::FourOctetAs(_)));
+                    let extended_message = capabilities
+                        .iter()
+                        .any(|cap| matches!(cap, BgpCapability::ExtendedMessage));
+                    log::debug!("Sending ASN4 received to: {asn4}");
+                    self.asn4_received = Some(asn4);
+                    self.extended_message_received = Some(extended_message);
+                    self.capabilities_received = Some(capabilities.into_iter().cloned().collect());
+                    self.update_negotiated_capabilities();
                 }
-                // ASN4 capability is used only when both peers agree on enabling ASN4
-                let asn4 = self.asn4_received.unwrap_or(false) && self.asn4_sent.unwrap_or(false);
-                self.ctx.set_asn4(asn4);
-                let ret = BgpMessage::from_wire(Span::new(buf), &mut self.ctx);
-                let decoding_result = match ret {
-                    Ok((_span, msg)) => {
-                        buf.advance(length);
-                        if let BgpMessage::Open(ref open) = msg {
-                            let asn4 = open
-                                .capabilities()
-                                .into_iter()
-                                .any(|cap| matches!(cap, BgpCapability::FourOctetAs(_)));
-                            log::debug!("Sending ASN4 received to: {asn4}");
-                            self.asn4_received = Some(asn4);
-                        }
-                        Ok(Some((msg, self.ctx.reset_parsing_errors())))
-                    }
-                    Err(error) => {
-                        log::error!("Error: {:?} buf: {:?}", error, buf.to_vec());
-                        let err = match error {
-                            nom::Err::Incomplete(needed) => {
-                                let needed = match needed {
-                                    Needed::Unknown => None,
-                                    Needed::Size(size) => Some(size.get()),
-                                };
-                                BgpCodecDecoderError::Incomplete(needed)
-                            }
-                            nom::Err::Error(error) | nom::Err::Failure(error) => {
-                                BgpCodecDecoderError::BgpMessageParsingError(error.error().clone())
-                            }
+                Ok(Some((msg, self.ctx.reset_parsing_errors())))
+            }
+            Err(error) => {
+                log::error!("Error: {:?} buf: {:?}", error, &buf[..length]);
+                let err = match error {
+                    nom::Err::Incomplete(needed) => {
+                        let needed = match needed {
+                            Needed::Unknown => None,
+                            Needed::Size(size) => Some(size.get()),
                         };
-                        Err(err)
+                        BgpCodecDecoderError::Incomplete(needed)
+                    }
+                    nom::Err::Error(error) | nom::Err::Failure(error) => {
+                        BgpCodecDecoderError::BgpMessageParsingError(error.error().clone())
                     }
                 };
-                if log::log_enabled!(log::Level::Debug) {
-                    log::debug!("Decoding buffer result is: {decoding_result:?}");
-                }
-                decoding_result
+                // The framing is still valid, skip the malformed message so the next
+                // message can be decoded if the user chooses to carry on.
+                buf.advance(length);
+                Err(err)
             }
-        } else {
-            Ok(None)
+        };
+        if log::log_enabled!(log::Level::Debug) {
+            log::debug!("Decoding buffer result is: {decoding_result:?}");
         }
+        decoding_result
     }
 }
 
@@ -145,13 +235,177 @@ impl Encoder<BgpMessage> for BgpCodec {
             log::debug!("Encoding message: {msg:?}")
         }
         if let BgpMessage::Open(ref open) = msg {
-            let asn4 = open
-                .capabilities()
-                .into_iter()
+            let capabilities = open.capabilities();
+            let asn4 = capabilities
+                .iter()
                 .any(|cap| matches!(cap, BgpCapability::FourOctetAs(_)));
+            let extended_message = capabilities
+                .iter()
+                .any(|cap| matches!(cap, BgpCapability::ExtendedMessage));
             log::debug!("Sending ASN4 sent to: {asn4}");
             self.asn4_sent = Some(asn4);
+            self.extended_message_sent = Some(extended_message);
+            self.capabilities_sent = Some(capabilities.into_iter().cloned().collect());
+            self.update_negotiated_capabilities();
         }
-        msg.write(&mut dst.writer())
+        let len = msg.len();
+        if len > self.max_message_length() as usize {
+            return Err(BgpMessageWritingError::BgpMessageLengthOverflow(len));
+        }
+        msg.write_into(dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        capabilities::{AddPathAddressFamily, AddPathCapability},
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        open::BgpOpenMessageParameter,
+        update::BgpUpdateMessage,
+        BgpOpenMessage,
+    };
+    use std::net::Ipv4Addr;
+
+    const KEEPALIVE: [u8; 19] = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x13, 0x04,
+    ];
+
+    fn header(length: u16) -> BytesMut {
+        let mut buf = BytesMut::from(&KEEPALIVE[..16]);
+        buf.extend_from_slice(&length.to_be_bytes());
+        buf.extend_from_slice(&[0x02]);
+        buf
+    }
+
+    #[test]
+    fn test_decode_partial_reads() {
+        let mut codec = BgpCodec::new(true);
+        let mut buf = BytesMut::from(&KEEPALIVE[..10]);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(&KEEPALIVE[10..]);
+        buf.extend_from_slice(&KEEPALIVE[..17]);
+        assert_eq!(
+            codec.decode(&mut buf),
+            Ok(Some((
+                BgpMessage::KeepAlive,
+                BgpParsingIgnoredErrors::default()
+            )))
+        );
+        assert_eq!(buf.len(), 17);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        buf.extend_from_slice(&KEEPALIVE[17..]);
+        assert_eq!(
+            codec.decode(&mut buf),
+            Ok(Some((
+                BgpMessage::KeepAlive,
+                BgpParsingIgnoredErrors::default()
+            )))
+        );
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_framing_errors() {
+        let mut codec = BgpCodec::new(true);
+        let mut bad_marker = BytesMut::from(&[0x00; 19][..]);
+        let mut too_short = header(BGP_MIN_MESSAGE_LENGTH - 1);
+        let mut too_long = header(BGP_MAX_MESSAGE_LENGTH + 1);
+        assert_eq!(
+            codec.decode(&mut bad_marker),
+            Err(BgpCodecDecoderError::BgpMessageParsingError(
+                BgpMessageParsingError::ConnectionNotSynchronized(0)
+            ))
+        );
+        assert_eq!(
+            codec.decode(&mut too_short),
+            Err(BgpCodecDecoderError::BgpMessageParsingError(
+                BgpMessageParsingError::BadMessageLength(BGP_MIN_MESSAGE_LENGTH - 1)
+            ))
+        );
+        assert_eq!(
+            codec.decode(&mut too_long),
+            Err(BgpCodecDecoderError::BgpMessageParsingError(
+                BgpMessageParsingError::BadMessageLength(BGP_MAX_MESSAGE_LENGTH + 1)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_extended_message_negotiation() -> Result<(), BgpMessageWritingError> {
+        let open = BgpMessage::Open(BgpOpenMessage::new(
+            100,
+            180,
+            Ipv4Addr::new(192, 0, 2, 1),
+            vec![BgpOpenMessageParameter::Capabilities(vec![
+                BgpCapability::ExtendedMessage,
+            ])],
+        ));
+        let mut codec = BgpCodec::new(true);
+        let mut buf = BytesMut::new();
+        codec.encode(open.clone(), &mut buf)?;
+        assert_eq!(codec.max_message_length(), BGP_MAX_MESSAGE_LENGTH);
+
+        // Received OPEN from the peer
+        let decoded = codec.decode(&mut buf);
+        assert_eq!(
+            decoded,
+            Ok(Some((open, BgpParsingIgnoredErrors::default())))
+        );
+        assert_eq!(codec.max_message_length(), BGP_EXTENDED_MAX_MESSAGE_LENGTH);
+
+        // Wait for the rest of the message instead of failing
+        let mut buf = header(BGP_MAX_MESSAGE_LENGTH + 1);
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        assert!(buf.capacity() > BGP_MAX_MESSAGE_LENGTH as usize);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_path_negotiation() -> Result<(), BgpMessageWritingError> {
+        let open = |send, receive| {
+            BgpMessage::Open(BgpOpenMessage::new(
+                100,
+                180,
+                Ipv4Addr::new(192, 0, 2, 1),
+                vec![BgpOpenMessageParameter::Capabilities(vec![
+                    BgpCapability::AddPath(AddPathCapability::new(vec![
+                        AddPathAddressFamily::new(AddressType::Ipv4Unicast, send, receive),
+                    ])),
+                ])],
+            ))
+        };
+        let mut codec = BgpCodec::new(true);
+        let mut buf = BytesMut::new();
+        codec.encode(open(false, true), &mut buf)?;
+        assert!(codec.add_path().is_empty());
+
+        // Received OPEN from the peer
+        let mut buf = BytesMut::new();
+        BgpCodec::new(true).encode(open(true, false), &mut buf)?;
+        assert!(codec.decode(&mut buf).is_ok());
+        assert_eq!(
+            codec.add_path(),
+            &HashMap::from([(AddressType::Ipv4Unicast, true)])
+        );
+
+        // Path ID is parsed from the NLRI
+        let update = BgpMessage::Update(BgpUpdateMessage::new(
+            vec![],
+            vec![],
+            vec![Ipv4UnicastAddress::new(
+                Some(10),
+                Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+            )],
+        ));
+        let mut buf = BytesMut::new();
+        update.write_into(&mut buf)?;
+        assert_eq!(
+            codec.decode(&mut buf),
+            Ok(Some((update, BgpParsingIgnoredErrors::default())))
+        );
+        Ok(())
     }
 }
@@ -17,9 +17,15 @@
 
 #[cfg(feature = "fuzz")]
 use crate::{arbitrary_ipv4, arbitrary_ipv6};
-use crate::{iana::WellKnownCommunity, nlri::MacAddress};
+use crate::{
+    iana::{
+        is_extended_community_type_experimental, is_extended_community_type_transitive,
+        BgpExtendedCommunityIpv6Type, BgpExtendedCommunityType, WellKnownCommunity,
+    },
+    nlri::MacAddress,
+};
+use core::net::{Ipv4Addr, Ipv6Addr};
 use serde::{Deserialize, Serialize};
-use std::net::{Ipv4Addr, Ipv6Addr};
 
 /// Four octet values to specify a community.
 ///
@@ -193,6 +199,83 @@ impl ExtendedCommunityProperties for ExtendedCommunity {
     }
 }
 
+impl ExtendedCommunity {
+    /// The high-order octet of the type field
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::TransitiveTwoOctet(_) => BgpExtendedCommunityType::TransitiveTwoOctet as u8,
+            Self::NonTransitiveTwoOctet(_) => BgpExtendedCommunityType::NonTransitiveTwoOctet as u8,
+            Self::TransitiveIpv4(_) => BgpExtendedCommunityType::TransitiveIpv4 as u8,
+            Self::NonTransitiveIpv4(_) => BgpExtendedCommunityType::NonTransitiveIpv4 as u8,
+            Self::TransitiveFourOctet(_) => BgpExtendedCommunityType::TransitiveFourOctet as u8,
+            Self::NonTransitiveFourOctet(_) => {
+                BgpExtendedCommunityType::NonTransitiveFourOctet as u8
+            }
+            Self::TransitiveOpaque(_) => BgpExtendedCommunityType::TransitiveOpaque as u8,
+            Self::NonTransitiveOpaque(_) => BgpExtendedCommunityType::NonTransitiveOpaque as u8,
+            Self::Evpn(_) => BgpExtendedCommunityType::Evpn as u8,
+            Self::Experimental(value) => value.code(),
+            Self::Unknown(value) => value.code(),
+        }
+    }
+
+    /// The IANA type of the community, [`None`] if the type code is not
+    /// registered.
+    pub const fn community_type(&self) -> Option<BgpExtendedCommunityType> {
+        BgpExtendedCommunityType::from_repr(self.code())
+    }
+
+    /// Name of the community type as registered by IANA, or `Unassigned` for
+    /// type codes that are not registered.
+    pub fn type_name(&self) -> &'static str {
+        match self.community_type() {
+            Some(community_type) => community_type.type_name(),
+            None => "Unassigned",
+        }
+    }
+
+    /// The community is transitive across Autonomous Systems based on the
+    /// Transitive bit of the type field
+    pub const fn is_transitive(&self) -> bool {
+        is_extended_community_type_transitive(self.code())
+    }
+
+    /// The type code is in one of the Experimental Use ranges
+    pub const fn is_experimental(&self) -> bool {
+        is_extended_community_type_experimental(self.code())
+    }
+
+    /// Route Target community, regardless of the Global Administrator format
+    /// (Two-Octet AS, IPv4 address, or Four-Octet AS)
+    pub const fn is_route_target(&self) -> bool {
+        matches!(
+            self,
+            Self::TransitiveTwoOctet(TransitiveTwoOctetExtendedCommunity::RouteTarget { .. })
+                | Self::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteTarget { .. })
+                | Self::TransitiveFourOctet(
+                    TransitiveFourOctetExtendedCommunity::RouteTarget { .. }
+                )
+        )
+    }
+
+    /// Route Origin (Site of Origin) community, regardless of the Global
+    /// Administrator format (Two-Octet AS, IPv4 address, or Four-Octet AS)
+    pub const fn is_route_origin(&self) -> bool {
+        matches!(
+            self,
+            Self::TransitiveTwoOctet(TransitiveTwoOctetExtendedCommunity::RouteOrigin { .. })
+                | Self::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteOrigin { .. })
+                | Self::TransitiveFourOctet(
+                    TransitiveFourOctetExtendedCommunity::RouteOrigin { .. }
+                )
+        )
+    }
+
+    pub const fn is_evpn(&self) -> bool {
+        matches!(self, Self::Evpn(_))
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveTwoOctetExtendedCommunity {
@@ -382,7 +465,7 @@ impl ExtendedCommunityProperties for NonTransitiveFourOctetExtendedCommunity {
     }
 
     fn transitive(&self) -> bool {
-        true
+        false
     }
 }
 
@@ -553,7 +636,7 @@ impl ExtendedCommunityProperties for TransitiveOpaqueExtendedCommunity {
     }
 
     fn transitive(&self) -> bool {
-        false
+        true
     }
 }
 
@@ -701,6 +784,52 @@ impl ExtendedCommunityProperties for ExtendedCommunityIpv6 {
     }
 }
 
+impl ExtendedCommunityIpv6 {
+    /// The high-order octet of the type field
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::TransitiveIpv6(_) => BgpExtendedCommunityIpv6Type::TransitiveIpv6 as u8,
+            Self::NonTransitiveIpv6(_) => BgpExtendedCommunityIpv6Type::NonTransitiveIpv6 as u8,
+            Self::Unknown(value) => value.code(),
+        }
+    }
+
+    /// The IANA type of the community, [`None`] if the type code is not
+    /// registered.
+    pub const fn community_type(&self) -> Option<BgpExtendedCommunityIpv6Type> {
+        BgpExtendedCommunityIpv6Type::from_repr(self.code())
+    }
+
+    /// Name of the community type as registered by IANA, or `Unassigned` for
+    /// type codes that are not registered.
+    pub fn type_name(&self) -> &'static str {
+        match self.community_type() {
+            Some(community_type) => community_type.type_name(),
+            None => "Unassigned",
+        }
+    }
+
+    /// The community is transitive across Autonomous Systems based on the
+    /// Transitive bit of the type field
+    pub const fn is_transitive(&self) -> bool {
+        is_extended_community_type_transitive(self.code())
+    }
+
+    pub const fn is_route_target(&self) -> bool {
+        matches!(
+            self,
+            Self::TransitiveIpv6(TransitiveIpv6ExtendedCommunity::RouteTarget { .. })
+        )
+    }
+
+    pub const fn is_route_origin(&self) -> bool {
+        matches!(
+            self,
+            Self::TransitiveIpv6(TransitiveIpv6ExtendedCommunity::RouteOrigin { .. })
+        )
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum TransitiveIpv6ExtendedCommunity {
@@ -989,4 +1118,57 @@ mod tests {
         assert_eq!(comm.collection_asn(), 0x1001);
         assert_eq!(comm.collection_value(), 0x2003);
     }
+
+    #[test]
+    fn test_extended_community_classification() {
+        let rt = ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteTarget {
+            global_admin: Ipv4Addr::new(192, 0, 2, 1),
+            local_admin: 100,
+        });
+        let soo = ExtendedCommunity::TransitiveFourOctet(
+            TransitiveFourOctetExtendedCommunity::RouteOrigin {
+                global_admin: 65536,
+                local_admin: 1,
+            },
+        );
+        let link_bandwidth = ExtendedCommunity::NonTransitiveTwoOctet(
+            NonTransitiveTwoOctetExtendedCommunity::LinkBandwidth {
+                global_admin: 65000,
+                local_admin: 0,
+            },
+        );
+        let default_gateway =
+            ExtendedCommunity::TransitiveOpaque(TransitiveOpaqueExtendedCommunity::DefaultGateway);
+        let experimental =
+            ExtendedCommunity::Experimental(ExperimentalExtendedCommunity::new(0xc1, 0x00, [0; 6]));
+        let unassigned =
+            ExtendedCommunity::Unknown(UnknownExtendedCommunity::new(0x70, 0x00, [0; 6]));
+
+        assert_eq!(rt.code(), 0x01);
+        assert!(rt.is_route_target());
+        assert!(!rt.is_route_origin());
+        assert!(rt.is_transitive());
+        assert_eq!(
+            rt.type_name(),
+            "Transitive IPv4-Address-Specific Extended Community"
+        );
+        assert!(soo.is_route_origin());
+        assert!(!soo.is_route_target());
+        assert!(!link_bandwidth.is_transitive());
+        assert_eq!(
+            link_bandwidth.community_type(),
+            Some(BgpExtendedCommunityType::NonTransitiveTwoOctet)
+        );
+        assert!(default_gateway.is_transitive());
+        assert_eq!(
+            default_gateway.is_transitive(),
+            default_gateway.transitive()
+        );
+        assert!(experimental.is_experimental());
+        assert!(!experimental.is_transitive());
+        assert_eq!(experimental.type_name(), "Experimental Use");
+        assert_eq!(unassigned.community_type(), None);
+        assert_eq!(unassigned.type_name(), "Unassigned");
+        assert!(!unassigned.is_experimental());
+    }
 }
@@ -0,0 +1,810 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Alternative JSON representation of [`BgpUpdateMessage`] that follows the
+//! schema of [ExaBGP](https://github.com/Exa-Networks/exabgp) JSON API, so it
+//! can be consumed by tools written for ExaBGP.
+//!
+//! Announced and withdrawn routes are reported for the IPv4/IPv6 unicast and
+//! multicast address families. Routes of other address families are skipped.
+//!
+//! ```text
+//! {
+//!   "exabgp": "4.0.1", "time": 1700000000.0, "type": "update",
+//!   "neighbor": {
+//!     "address": { "local": "192.0.2.2", "peer": "192.0.2.1" },
+//!     "asn": { "local": 65000, "peer": 65001 },
+//!     "direction": "receive",
+//!     "message": {
+//!       "update": {
+//!         "attribute": { "origin": "igp", "as-path": { "0": { "element": "as-sequence", "value": [ 65001 ] } } },
+//!         "announce": { "ipv4 unicast": { "192.0.2.1": [ { "nlri": "198.51.100.0/24" } ] } },
+//!         "withdraw": { "ipv4 unicast": [ { "nlri": "203.0.113.0/24" } ] }
+//!       }
+//!     }
+//!   }
+//! }
+//! ```
+
+use crate::{
+    community::{
+        ExtendedCommunity, TransitiveFourOctetExtendedCommunity, TransitiveIpv4ExtendedCommunity,
+        TransitiveTwoOctetExtendedCommunity,
+    },
+    path_attribute::{
+        Aggregator, Aigp, AsPath, AsPathSegmentType, MpReach, MpUnreach, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    BgpUpdateMessage,
+};
+use ipnet::IpNet;
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::WritablePdu;
+use serde::{ser::SerializeMap, Serialize, Serializer};
+use std::{
+    collections::BTreeMap,
+    net::{IpAddr, Ipv4Addr},
+};
+
+/// Version of ExaBGP JSON API reported in the `exabgp` field
+pub const EXABGP_VERSION: &str = "4.0.1";
+
+/// Direction of the message relative to the local BGP speaker
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExaBgpDirection {
+    Receive,
+    Send,
+}
+
+/// The BGP session information reported in the `neighbor` field
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ExaBgpNeighbor {
+    local_address: IpAddr,
+    peer_address: IpAddr,
+    local_as: u32,
+    peer_as: u32,
+    direction: ExaBgpDirection,
+}
+
+impl ExaBgpNeighbor {
+    pub const fn new(
+        local_address: IpAddr,
+        peer_address: IpAddr,
+        local_as: u32,
+        peer_as: u32,
+        direction: ExaBgpDirection,
+    ) -> Self {
+        Self {
+            local_address,
+            peer_address,
+            local_as,
+            peer_as,
+            direction,
+        }
+    }
+
+    pub const fn local_address(&self) -> IpAddr {
+        self.local_address
+    }
+
+    pub const fn peer_address(&self) -> IpAddr {
+        self.peer_address
+    }
+
+    pub const fn local_as(&self) -> u32 {
+        self.local_as
+    }
+
+    pub const fn peer_as(&self) -> u32 {
+        self.peer_as
+    }
+
+    pub const fn direction(&self) -> ExaBgpDirection {
+        self.direction
+    }
+}
+
+/// Serialize a [`BgpUpdateMessage`] using ExaBGP JSON API schema.
+///
+/// End-of-RIB markers are serialized as ExaBGP `eor` messages.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ExaBgpUpdate<'a> {
+    time: f64,
+    neighbor: &'a ExaBgpNeighbor,
+    update: &'a BgpUpdateMessage,
+}
+
+impl<'a> ExaBgpUpdate<'a> {
+    /// `time` is the UNIX timestamp, in seconds, when the message was
+    /// received or sent.
+    pub const fn new(
+        time: f64,
+        neighbor: &'a ExaBgpNeighbor,
+        update: &'a BgpUpdateMessage,
+    ) -> Self {
+        Self {
+            time,
+            neighbor,
+            update,
+        }
+    }
+
+    pub const fn time(&self) -> f64 {
+        self.time
+    }
+
+    pub const fn neighbor(&self) -> &ExaBgpNeighbor {
+        self.neighbor
+    }
+
+    pub const fn update(&self) -> &BgpUpdateMessage {
+        self.update
+    }
+}
+
+impl<'a> Serialize for ExaBgpUpdate<'a> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let message = match self.update.end_of_rib() {
+            Some(address_type) => {
+                let (afi, safi) = family(address_type);
+                ExaBgpMessageRepr::Eor(ExaBgpEorRepr { afi, safi })
+            }
+            None => ExaBgpMessageRepr::Update(Box::new(ExaBgpUpdateRepr::from(self.update))),
+        };
+        ExaBgpEnvelopeRepr {
+            exabgp: EXABGP_VERSION,
+            time: self.time,
+            message_type: "update",
+            neighbor: ExaBgpNeighborRepr {
+                address: ExaBgpPairRepr {
+                    local: self.neighbor.local_address,
+                    peer: self.neighbor.peer_address,
+                },
+                asn: ExaBgpPairRepr {
+                    local: self.neighbor.local_as,
+                    peer: self.neighbor.peer_as,
+                },
+                direction: self.neighbor.direction,
+                message,
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// ExaBGP names of the AFI and SAFI
+const fn family(address_type: AddressType) -> (&'static str, &'static str) {
+    match address_type {
+        AddressType::Ipv4Unicast => ("ipv4", "unicast"),
+        AddressType::Ipv4Multicast => ("ipv4", "multicast"),
+        AddressType::Ipv4NlriMplsLabels => ("ipv4", "nlri-mpls"),
+        AddressType::Ipv4MplsLabeledVpn => ("ipv4", "mpls-vpn"),
+        AddressType::Ipv4MulticastBgpMplsVpn => ("ipv4", "mcast-vpn"),
+        AddressType::Ipv4FlowSpec => ("ipv4", "flow"),
+        AddressType::Ipv4FlowSpecL3Vpn => ("ipv4", "flow-vpn"),
+        AddressType::Ipv6Unicast => ("ipv6", "unicast"),
+        AddressType::Ipv6Multicast => ("ipv6", "multicast"),
+        AddressType::Ipv6NlriMplsLabels => ("ipv6", "nlri-mpls"),
+        AddressType::Ipv6MplsLabeledVpn => ("ipv6", "mpls-vpn"),
+        AddressType::Ipv6MulticastBgpMplsVpn => ("ipv6", "mcast-vpn"),
+        AddressType::Ipv6FlowSpec => ("ipv6", "flow"),
+        AddressType::Ipv6FlowSpecL3Vpn => ("ipv6", "flow-vpn"),
+        AddressType::L2VpnBgpEvpn => ("l2vpn", "evpn"),
+        AddressType::RouteTargetConstrains => ("ipv4", "rtc"),
+        AddressType::BgpLs => ("bgp-ls", "bgp-ls"),
+        AddressType::BgpLsVpn => ("bgp-ls", "bgp-ls-vpn"),
+        AddressType::Ipv4Bgp4over6 => ("ipv4", "4over6"),
+        AddressType::Ipv6Bgp6over4 => ("ipv6", "6over4"),
+    }
+}
+
+#[derive(Serialize)]
+struct ExaBgpEnvelopeRepr {
+    exabgp: &'static str,
+    time: f64,
+    #[serde(rename = "type")]
+    message_type: &'static str,
+    neighbor: ExaBgpNeighborRepr,
+}
+
+#[derive(Serialize)]
+struct ExaBgpNeighborRepr {
+    address: ExaBgpPairRepr<IpAddr>,
+    asn: ExaBgpPairRepr<u32>,
+    direction: ExaBgpDirection,
+    message: ExaBgpMessageRepr,
+}
+
+#[derive(Serialize)]
+struct ExaBgpPairRepr<T> {
+    local: T,
+    peer: T,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+enum ExaBgpMessageRepr {
+    Update(Box<ExaBgpUpdateRepr>),
+    Eor(ExaBgpEorRepr),
+}
+
+#[derive(Serialize)]
+struct ExaBgpEorRepr {
+    afi: &'static str,
+    safi: &'static str,
+}
+
+#[derive(Serialize)]
+struct ExaBgpUpdateRepr {
+    #[serde(skip_serializing_if = "ExaBgpAttributeRepr::is_empty")]
+    attribute: ExaBgpAttributeRepr,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    announce: BTreeMap<String, BTreeMap<IpAddr, Vec<ExaBgpNlriRepr>>>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    withdraw: BTreeMap<String, Vec<ExaBgpNlriRepr>>,
+}
+
+#[derive(Serialize)]
+struct ExaBgpNlriRepr {
+    nlri: IpNet,
+    #[serde(rename = "path-information", skip_serializing_if = "Option::is_none")]
+    path_information: Option<Ipv4Addr>,
+}
+
+impl ExaBgpNlriRepr {
+    fn new(path_id: Option<u32>, nlri: impl Into<IpNet>) -> Self {
+        Self {
+            nlri: nlri.into(),
+            path_information: path_id.map(Ipv4Addr::from),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ExaBgpAsPathSegmentRepr {
+    element: &'static str,
+    value: Vec<u32>,
+}
+
+/// ExaBGP represents AS_PATH as a map of the segment index to the segment
+struct ExaBgpAsPathRepr(Vec<ExaBgpAsPathSegmentRepr>);
+
+impl Serialize for ExaBgpAsPathRepr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (index, segment) in self.0.iter().enumerate() {
+            map.serialize_entry(&index.to_string(), segment)?;
+        }
+        map.end()
+    }
+}
+
+#[derive(Serialize)]
+struct ExaBgpExtendedCommunityRepr {
+    value: u64,
+    string: String,
+}
+
+#[derive(Default, Serialize)]
+struct ExaBgpAttributeRepr {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    origin: Option<&'static str>,
+    #[serde(rename = "as-path", skip_serializing_if = "Option::is_none")]
+    as_path: Option<ExaBgpAsPathRepr>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    med: Option<u32>,
+    #[serde(rename = "local-preference", skip_serializing_if = "Option::is_none")]
+    local_preference: Option<u32>,
+    #[serde(rename = "atomic-aggregate", skip_serializing_if = "Option::is_none")]
+    atomic_aggregate: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aggregator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    community: Option<Vec<[u16; 2]>>,
+    #[serde(rename = "originator-id", skip_serializing_if = "Option::is_none")]
+    originator_id: Option<Ipv4Addr>,
+    #[serde(rename = "cluster-list", skip_serializing_if = "Option::is_none")]
+    cluster_list: Option<Vec<Ipv4Addr>>,
+    #[serde(rename = "extended-community", skip_serializing_if = "Option::is_none")]
+    extended_community: Option<Vec<ExaBgpExtendedCommunityRepr>>,
+    #[serde(rename = "large-community", skip_serializing_if = "Option::is_none")]
+    large_community: Option<Vec<[u32; 3]>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aigp: Option<u64>,
+    /// Attributes without an ExaBGP name are reported as hex values
+    #[serde(flatten)]
+    generic: BTreeMap<String, String>,
+}
+
+impl ExaBgpAttributeRepr {
+    fn is_empty(&self) -> bool {
+        self.origin.is_none()
+            && self.as_path.is_none()
+            && self.med.is_none()
+            && self.local_preference.is_none()
+            && self.atomic_aggregate.is_none()
+            && self.aggregator.is_none()
+            && self.community.is_none()
+            && self.originator_id.is_none()
+            && self.cluster_list.is_none()
+            && self.extended_community.is_none()
+            && self.large_community.is_none()
+            && self.aigp.is_none()
+            && self.generic.is_empty()
+    }
+
+    fn add(&mut self, attr: &PathAttribute) {
+        match attr.value() {
+            PathAttributeValue::Origin(origin) => {
+                self.origin = Some(match origin {
+                    Origin::IGP => "igp",
+                    Origin::EGP => "egp",
+                    Origin::Incomplete => "incomplete",
+                })
+            }
+            PathAttributeValue::AsPath(as_path) => {
+                let segments = match as_path {
+                    AsPath::As2PathSegments(segments) => segments
+                        .iter()
+                        .map(|segment| {
+                            as_path_segment(
+                                segment.segment_type(),
+                                segment.as_numbers().iter().map(|asn| *asn as u32).collect(),
+                            )
+                        })
+                        .collect(),
+                    AsPath::As4PathSegments(segments) => segments
+                        .iter()
+                        .map(|segment| {
+                            as_path_segment(segment.segment_type(), segment.as_numbers().clone())
+                        })
+                        .collect(),
+                };
+                self.as_path = Some(ExaBgpAsPathRepr(segments));
+            }
+            PathAttributeValue::MultiExitDiscriminator(med) => self.med = Some(med.metric()),
+            PathAttributeValue::LocalPreference(local_pref) => {
+                self.local_preference = Some(local_pref.metric())
+            }
+            PathAttributeValue::AtomicAggregate(_) => self.atomic_aggregate = Some(true),
+            PathAttributeValue::Aggregator(aggregator) => {
+                self.aggregator = Some(match aggregator {
+                    Aggregator::As2Aggregator(agg) => format!("{}:{}", agg.asn(), agg.origin()),
+                    Aggregator::As4Aggregator(agg) => format!("{}:{}", agg.asn(), agg.origin()),
+                })
+            }
+            PathAttributeValue::Communities(communities) => {
+                self.community = Some(
+                    communities
+                        .communities()
+                        .iter()
+                        .map(|c| [c.collection_asn(), c.collection_value()])
+                        .collect(),
+                )
+            }
+            PathAttributeValue::ExtendedCommunities(communities) => {
+                self.extended_community = Some(
+                    communities
+                        .communities()
+                        .iter()
+                        .map(extended_community)
+                        .collect(),
+                )
+            }
+            PathAttributeValue::LargeCommunities(communities) => {
+                self.large_community = Some(
+                    communities
+                        .communities()
+                        .iter()
+                        .map(|c| [c.global_admin(), c.local_data1(), c.local_data2()])
+                        .collect(),
+                )
+            }
+            PathAttributeValue::Originator(originator) => {
+                self.originator_id = Some(originator.id())
+            }
+            PathAttributeValue::ClusterList(cluster_list) => {
+                self.cluster_list = Some(
+                    cluster_list
+                        .cluster_list()
+                        .iter()
+                        .map(|cluster| cluster.id())
+                        .collect(),
+                )
+            }
+            PathAttributeValue::Aigp(Aigp::AccumulatedIgpMetric(metric)) => {
+                self.aigp = Some(*metric)
+            }
+            // Next hops and NLRI are reported in the announce and withdraw sections
+            PathAttributeValue::NextHop(_)
+            | PathAttributeValue::MpReach(_)
+            | PathAttributeValue::MpUnreach(_) => {}
+            // Merged by ExaBGP into the as-path and the aggregator
+            PathAttributeValue::As4Path(_) | PathAttributeValue::As4Aggregator(_) => {}
+            PathAttributeValue::ExtendedCommunitiesIpv6(_)
+            | PathAttributeValue::BgpLs(_)
+            | PathAttributeValue::OnlyToCustomer(_)
+            | PathAttributeValue::UnknownAttribute(_) => {
+                if let Some((name, value)) = generic_attribute(attr) {
+                    self.generic.insert(name, value);
+                }
+            }
+        }
+    }
+}
+
+fn as_path_segment(segment_type: AsPathSegmentType, value: Vec<u32>) -> ExaBgpAsPathSegmentRepr {
+    let element = match segment_type {
+        AsPathSegmentType::AsSet => "as-set",
+        AsPathSegmentType::AsSequence => "as-sequence",
+        AsPathSegmentType::AsConfedSequence => "confed-sequence",
+        AsPathSegmentType::AsConfedSet => "confed-set",
+    };
+    ExaBgpAsPathSegmentRepr { element, value }
+}
+
+/// ExaBGP reports attributes it doesn't understand as
+/// `"attribute-0x<code>-0x<flags>": "0x<value>"`
+fn generic_attribute(attr: &PathAttribute) -> Option<(String, String)> {
+    let code = match attr.path_attribute_type() {
+        Ok(code) => code.into(),
+        Err(code) => code,
+    };
+    let flags = (attr.optional() as u8) << 7
+        | (attr.transitive() as u8) << 6
+        | (attr.partial() as u8) << 5
+        | (attr.extended_length() as u8) << 4;
+    let mut buf = Vec::with_capacity(attr.len());
+    attr.write(&mut buf).ok()?;
+    // Skip the attribute flags, type, and length
+    let header_len = if attr.extended_length() { 4 } else { 3 };
+    let value = hex(buf.get(header_len..)?);
+    Some((format!("attribute-0x{code:02X}-0x{flags:02X}"), value))
+}
+
+fn extended_community(community: &ExtendedCommunity) -> ExaBgpExtendedCommunityRepr {
+    let mut buf = Vec::with_capacity(community.len());
+    let value = match community.write(&mut buf) {
+        Ok(_) => buf
+            .iter()
+            .take(8)
+            .fold(0u64, |acc, byte| acc << 8 | *byte as u64),
+        Err(_) => 0,
+    };
+    let string = match community {
+        ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                global_admin,
+                local_admin,
+            },
+        ) => format!("target:{global_admin}:{local_admin}"),
+        ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteOrigin {
+                global_admin,
+                local_admin,
+            },
+        ) => format!("origin:{global_admin}:{local_admin}"),
+        ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteTarget {
+            global_admin,
+            local_admin,
+        }) => format!("target:{global_admin}:{local_admin}"),
+        ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteOrigin {
+            global_admin,
+            local_admin,
+        }) => format!("origin:{global_admin}:{local_admin}"),
+        ExtendedCommunity::TransitiveFourOctet(
+            TransitiveFourOctetExtendedCommunity::RouteTarget {
+                global_admin,
+                local_admin,
+            },
+        ) => format!("target:{global_admin}L:{local_admin}"),
+        ExtendedCommunity::TransitiveFourOctet(
+            TransitiveFourOctetExtendedCommunity::RouteOrigin {
+                global_admin,
+                local_admin,
+            },
+        ) => format!("origin:{global_admin}L:{local_admin}"),
+        _ => format!("0x{value:016X}"),
+    };
+    ExaBgpExtendedCommunityRepr { value, string }
+}
+
+fn hex(value: &[u8]) -> String {
+    value
+        .iter()
+        .fold(String::from("0x"), |acc, byte| format!("{acc}{byte:02X}"))
+}
+
+fn family_name(address_type: AddressType) -> String {
+    let (afi, safi) = family(address_type);
+    format!("{afi} {safi}")
+}
+
+impl From<&BgpUpdateMessage> for ExaBgpUpdateRepr {
+    fn from(update: &BgpUpdateMessage) -> Self {
+        let mut attribute = ExaBgpAttributeRepr::default();
+        let mut announce: BTreeMap<String, BTreeMap<IpAddr, Vec<ExaBgpNlriRepr>>> = BTreeMap::new();
+        let mut withdraw: BTreeMap<String, Vec<ExaBgpNlriRepr>> = BTreeMap::new();
+        let mut next_hop = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+        for attr in update.path_attributes() {
+            attribute.add(attr);
+            match attr.value() {
+                PathAttributeValue::NextHop(value) => next_hop = IpAddr::V4(value.next_hop()),
+                PathAttributeValue::MpReach(mp_reach) => {
+                    let (next_hop, nlri): (IpAddr, Vec<ExaBgpNlriRepr>) = match mp_reach {
+                        MpReach::Ipv4Unicast { next_hop, nlri, .. } => (
+                            *next_hop,
+                            nlri.iter()
+                                .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                                .collect(),
+                        ),
+                        MpReach::Ipv4Multicast { next_hop, nlri, .. } => (
+                            *next_hop,
+                            nlri.iter()
+                                .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                                .collect(),
+                        ),
+                        MpReach::Ipv6Unicast {
+                            next_hop_global,
+                            nlri,
+                            ..
+                        } => (
+                            IpAddr::V6(*next_hop_global),
+                            nlri.iter()
+                                .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                                .collect(),
+                        ),
+                        MpReach::Ipv6Multicast {
+                            next_hop_global,
+                            nlri,
+                            ..
+                        } => (
+                            IpAddr::V6(*next_hop_global),
+                            nlri.iter()
+                                .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                                .collect(),
+                        ),
+                        _ => continue,
+                    };
+                    if let Ok(address_type) = mp_reach.address_type() {
+                        announce
+                            .entry(family_name(address_type))
+                            .or_default()
+                            .entry(next_hop)
+                            .or_default()
+                            .extend(nlri);
+                    }
+                }
+                PathAttributeValue::MpUnreach(mp_unreach) => {
+                    let nlri: Vec<ExaBgpNlriRepr> = match mp_unreach {
+                        MpUnreach::Ipv4Unicast { nlri } => nlri
+                            .iter()
+                            .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                            .collect(),
+                        MpUnreach::Ipv4Multicast { nlri } => nlri
+                            .iter()
+                            .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                            .collect(),
+                        MpUnreach::Ipv6Unicast { nlri } => nlri
+                            .iter()
+                            .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                            .collect(),
+                        MpUnreach::Ipv6Multicast { nlri } => nlri
+                            .iter()
+                            .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address()))
+                            .collect(),
+                        _ => continue,
+                    };
+                    if let Ok(address_type) = mp_unreach.address_type() {
+                        withdraw
+                            .entry(family_name(address_type))
+                            .or_default()
+                            .extend(nlri);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if !update.nlri().is_empty() {
+            announce
+                .entry(family_name(AddressType::Ipv4Unicast))
+                .or_default()
+                .entry(next_hop)
+                .or_default()
+                .extend(
+                    update
+                        .nlri()
+                        .iter()
+                        .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address())),
+                );
+        }
+        if !update.withdraw_routes().is_empty() {
+            withdraw
+                .entry(family_name(AddressType::Ipv4Unicast))
+                .or_default()
+                .extend(
+                    update
+                        .withdraw_routes()
+                        .iter()
+                        .map(|x| ExaBgpNlriRepr::new(x.path_id(), x.network().address())),
+                );
+        }
+        Self {
+            attribute,
+            announce,
+            withdraw,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        community::{Community, LargeCommunity},
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+        path_attribute::{
+            As4PathSegment, Communities, ExtendedCommunities, LargeCommunities, LocalPreference,
+            MultiExitDiscriminator, NextHop,
+        },
+    };
+    use ipnet::{Ipv4Net, Ipv6Net};
+    use std::{net::Ipv6Addr, str::FromStr};
+
+    fn neighbor() -> ExaBgpNeighbor {
+        ExaBgpNeighbor::new(
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            65000,
+            65001,
+            ExaBgpDirection::Receive,
+        )
+    }
+
+    #[test]
+    fn test_exabgp_update() {
+        let update = BgpUpdateMessage::new(
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("203.0.113.0/24").unwrap()).unwrap(),
+            )],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![
+                        As4PathSegment::new(AsPathSegmentType::AsSequence, vec![65001, 65002]),
+                        As4PathSegment::new(AsPathSegmentType::AsSet, vec![65003]),
+                    ])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(10)),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::LocalPreference(LocalPreference::new(100)),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Communities(Communities::new(vec![Community::new(
+                        0xfde80064,
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::ExtendedCommunities(ExtendedCommunities::new(vec![
+                        ExtendedCommunity::TransitiveTwoOctet(
+                            TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                                global_admin: 65000,
+                                local_admin: 100,
+                            },
+                        ),
+                    ])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::LargeCommunities(LargeCommunities::new(vec![
+                        LargeCommunity::new(65000, 1, 2),
+                    ])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    false,
+                    PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                        next_hop_global: Ipv6Addr::from_str("2001:db8::1").unwrap(),
+                        next_hop_local: None,
+                        nlri: vec![Ipv6UnicastAddress::new(
+                            Some(1),
+                            Ipv6Unicast::from_net(Ipv6Net::from_str("2001:db8:1::/48").unwrap())
+                                .unwrap(),
+                        )],
+                    }),
+                )
+                .unwrap(),
+            ],
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+        );
+        let neighbor = neighbor();
+        let serialized =
+            serde_json::to_string(&ExaBgpUpdate::new(1.5, &neighbor, &update)).unwrap();
+        let expected = r#"{"exabgp":"4.0.1","time":1.5,"type":"update","neighbor":{"address":{"local":"192.0.2.2","peer":"192.0.2.1"},"asn":{"local":65000,"peer":65001},"direction":"receive","message":{"update":{"attribute":{"origin":"igp","as-path":{"0":{"element":"as-sequence","value":[65001,65002]},"1":{"element":"as-set","value":[65003]}},"med":10,"local-preference":100,"community":[[65000,100]],"extended-community":[{"value":842122827661412,"string":"target:65000:100"}],"large-community":[[65000,1,2]]},"announce":{"ipv4 unicast":{"192.0.2.1":[{"nlri":"198.51.100.0/24"}]},"ipv6 unicast":{"2001:db8::1":[{"nlri":"2001:db8:1::/48","path-information":"0.0.0.1"}]}},"withdraw":{"ipv4 unicast":[{"nlri":"203.0.113.0/24"}]}}}}}"#;
+        assert_eq!(serialized, expected);
+    }
+
+    #[test]
+    fn test_exabgp_eor() {
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast { nlri: vec![] }),
+            )
+            .unwrap()],
+            vec![],
+        );
+        let neighbor = neighbor();
+        let serialized =
+            serde_json::to_string(&ExaBgpUpdate::new(1.0, &neighbor, &update)).unwrap();
+        let expected = r#"{"exabgp":"4.0.1","time":1.0,"type":"update","neighbor":{"address":{"local":"192.0.2.2","peer":"192.0.2.1"},"asn":{"local":65000,"peer":65001},"direction":"receive","message":{"eor":{"afi":"ipv6","safi":"unicast"}}}}"#;
+        assert_eq!(serialized, expected);
+    }
+}
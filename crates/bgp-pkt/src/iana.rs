@@ -16,7 +16,7 @@
 //! Contains BGP codes that are registered at IANA [BGP Parameters](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml)
 
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, FromRepr};
+use strum_macros::{Display, FromRepr, IntoStaticStr};
 
 /// BGP Message types as registered in IANA [BGP Message Types](https://www.iana.org/assignments/bgp-parameters/bgp-parameters.xhtml#bgp-parameters-1)
 #[repr(u8)]
@@ -180,24 +180,31 @@ impl TryFrom<u8> for PathAttributeType {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpErrorNotificationCode {
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "Message Header Error")]
     MessageHeaderError = 1,
 
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "OPEN Message Error")]
     OpenMessageError = 2,
 
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "UPDATE Message Error")]
     UpdateMessageError = 3,
 
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "Hold Timer Expired")]
     HoldTimerExpired = 4,
 
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "Finite State Machine Error")]
     FiniteStateMachineError = 5,
 
     /// [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "Cease")]
     Cease = 6,
 
     /// [RFC7313](https://datatracker.ietf.org/doc/html/rfc7313)
+    #[strum(to_string = "ROUTE-REFRESH Message Error")]
     RouteRefreshMessageError = 7,
 }
 
@@ -228,9 +235,13 @@ impl TryFrom<u8> for BgpErrorNotificationCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MessageHeaderErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
+    #[strum(to_string = "Unspecific")]
     Unspecific = 0,
+    #[strum(to_string = "Connection Not Synchronized")]
     ConnectionNotSynchronized = 1,
+    #[strum(to_string = "Bad Message Length")]
     BadMessageLength = 2,
+    #[strum(to_string = "Bad Message Type")]
     BadMessageType = 3,
 }
 
@@ -261,17 +272,30 @@ impl TryFrom<u8> for MessageHeaderErrorSubCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum OpenMessageErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
+    #[strum(to_string = "Unspecific")]
     Unspecific = 0,
+    #[strum(to_string = "Unsupported Version Number")]
     UnsupportedVersionNumber = 1,
+    #[strum(to_string = "Bad Peer AS")]
     BadPeerAs = 2,
+    #[strum(to_string = "Bad BGP Identifier")]
     BadBgpIdentifier = 3,
+    #[strum(to_string = "Unsupported Optional Parameter")]
     UnsupportedOptionalParameter = 4,
+
+    /// Deprecated in [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "Authentication Failure")]
+    AuthenticationFailure = 5,
+
+    #[strum(to_string = "Unacceptable Hold Time")]
     UnacceptableHoldTime = 6,
 
     /// [RFC5492](https://datatracker.ietf.org/doc/html/rfc5492)
+    #[strum(to_string = "Unsupported Capability")]
     UnsupportedCapability = 7,
 
     /// [RFC9234](https://datatracker.ietf.org/doc/html/rfc9234)
+    #[strum(to_string = "Role Mismatch")]
     RoleMismatch = 11,
 }
 
@@ -302,16 +326,32 @@ impl TryFrom<u8> for OpenMessageErrorSubCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum UpdateMessageErrorSubCode {
     /// [RFC Errata 4493](https://www.rfc-editor.org/errata_search.php?eid=4493)
+    #[strum(to_string = "Unspecific")]
     Unspecific = 0,
+    #[strum(to_string = "Malformed Attribute List")]
     MalformedAttributeList = 1,
+    #[strum(to_string = "Unrecognized Well-known Attribute")]
     UnrecognizedWellKnownAttribute = 2,
+    #[strum(to_string = "Missing Well-known Attribute")]
     MissingWellKnownAttribute = 3,
+    #[strum(to_string = "Attribute Flags Error")]
     AttributeFlagsError = 4,
+    #[strum(to_string = "Attribute Length Error")]
     AttributeLengthError = 5,
+    #[strum(to_string = "Invalid ORIGIN Attribute")]
     InvalidOriginAttribute = 6,
+
+    /// Deprecated in [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271)
+    #[strum(to_string = "AS Routing Loop")]
+    AsRoutingLoop = 7,
+
+    #[strum(to_string = "Invalid NEXT_HOP Attribute")]
     InvalidNextHopAttribute = 8,
+    #[strum(to_string = "Optional Attribute Error")]
     OptionalAttributeError = 9,
+    #[strum(to_string = "Invalid Network Field")]
     InvalidNetworkField = 10,
+    #[strum(to_string = "Malformed AS_PATH")]
     MalformedAsPath = 11,
 }
 
@@ -342,15 +382,19 @@ impl TryFrom<u8> for UpdateMessageErrorSubCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum FiniteStateMachineErrorSubCode {
     /// [RFC6608](https://datatracker.ietf.org/doc/html/rfc6608)
+    #[strum(to_string = "Unspecified Error")]
     UnspecifiedError = 0,
 
     /// [RFC6608](https://datatracker.ietf.org/doc/html/rfc6608)
+    #[strum(to_string = "Receive Unexpected Message in OpenSent State")]
     ReceiveUnexpectedMessageInOpenSentState = 1,
 
     /// [RFC6608](https://datatracker.ietf.org/doc/html/rfc6608)
+    #[strum(to_string = "Receive Unexpected Message in OpenConfirm State")]
     ReceiveUnexpectedMessageInOpenConfirmState = 2,
 
     /// [RFC6608](https://datatracker.ietf.org/doc/html/rfc6608)
+    #[strum(to_string = "Receive Unexpected Message in Established State")]
     ReceiveUnexpectedMessageInEstablishedState = 3,
 }
 
@@ -381,33 +425,43 @@ impl TryFrom<u8> for FiniteStateMachineErrorSubCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum CeaseErrorSubCode {
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Maximum Number of Prefixes Reached")]
     MaximumNumberOfPrefixesReached = 1,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486) and [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003)
+    #[strum(to_string = "Administrative Shutdown")]
     AdministrativeShutdown = 2,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Peer De-configured")]
     PeerDeConfigured = 3,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486) and [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003)
+    #[strum(to_string = "Administrative Reset")]
     AdministrativeReset = 4,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Connection Rejected")]
     ConnectionRejected = 5,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Other Configuration Change")]
     OtherConfigurationChange = 6,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Connection Collision Resolution")]
     ConnectionCollisionResolution = 7,
 
     /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486)
+    #[strum(to_string = "Out of Resources")]
     OutOfResources = 8,
 
     /// [RFC8538](https://datatracker.ietf.org/doc/html/rfc8538)
+    #[strum(to_string = "Hard Reset")]
     HardReset = 9,
 
-    /// [draft-ietf-idr-bfd-subcode](https://datatracker.ietf.org/doc/html/draft-ietf-idr-bfd-subcode)
+    /// [RFC9384](https://datatracker.ietf.org/doc/html/rfc9384)
+    #[strum(to_string = "BFD Down")]
     BfdDown = 10,
 }
 
@@ -438,6 +492,7 @@ impl TryFrom<u8> for CeaseErrorSubCode {
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteRefreshMessageErrorSubCode {
     /// [RFC7313](https://datatracker.ietf.org/doc/html/rfc7313)
+    #[strum(to_string = "Invalid Message Length")]
     InvalidMessageLength = 1,
 }
 
@@ -754,40 +809,56 @@ pub enum BgpDataCollectionCommunityRegionIdentifierCode {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+/// BGP Extended Community Types, the high-order octet of the type field.
+/// See [IANA](https://www.iana.org/assignments/bgp-extended-communities/bgp-extended-communities.xhtml)
+#[derive(
+    Display, FromRepr, IntoStaticStr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpExtendedCommunityType {
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Transitive Two-Octet AS-Specific Extended Community")]
     TransitiveTwoOctet = 0x00,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Non-Transitive Two-Octet AS-Specific Extended Community")]
     NonTransitiveTwoOctet = 0x40,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Transitive IPv4-Address-Specific Extended Community")]
     TransitiveIpv4 = 0x01,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Non-Transitive IPv4-Address-Specific Extended Community")]
     NonTransitiveIpv4 = 0x41,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Transitive Four-Octet AS-Specific Extended Community")]
     TransitiveFourOctet = 0x02,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Non-Transitive Four-Octet AS-Specific Extended Community")]
     NonTransitiveFourOctet = 0x42,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Transitive Opaque Extended Community")]
     TransitiveOpaque = 0x03,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Non-Transitive Opaque Extended Community")]
     NonTransitiveOpaque = 0x43,
 
+    #[strum(to_string = "QoS Marking")]
     TransitiveQosMarking = 0x04,
 
+    #[strum(to_string = "Non-Transitive QoS Marking")]
     NonTransitiveQosMarking = 0x44,
 
+    #[strum(to_string = "CoS Capability")]
     CosCapability = 0x05,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "EVPN")]
     Evpn = 0x06,
 
     // Expired draft
@@ -795,93 +866,164 @@ pub enum BgpExtendedCommunityType {
     //TransitiveFlowSpec = 0x07,
     //NonTransitiveFlowSpec = 0x47,
     /// [draft-simpson-idr-flowspec-redirect](https://datatracker.ietf.org/doc/draft-simpson-idr-flowspec-redirect/)
+    #[strum(to_string = "Flow spec redirect/mirror to IP next-hop")]
     FlowSpecNextHop = 0x08,
 
     /// [draft-ietf-idr-flowspec-path-redirect](https://datatracker.ietf.org/doc/draft-ietf-idr-flowspec-path-redirect/)
+    #[strum(to_string = "FlowSpec Redirect to indirection-id Extended Community")]
     FlowSpecIndirectionId = 0x09,
 
     /// [draft-kaliraj-idr-bgp-classful-transport-planes](https://datatracker.ietf.org/doc/draft-kaliraj-idr-bgp-classful-transport-planes/)
+    #[strum(to_string = "Transitive Transport Class")]
     TransitiveTransportClass = 0x0a,
 
     /// [draft-kaliraj-idr-bgp-classful-transport-planes](https://datatracker.ietf.org/doc/draft-kaliraj-idr-bgp-classful-transport-planes/)
+    #[strum(to_string = "Non-Transitive Transport Class")]
     NonTransitiveTransportClass = 0x4a,
 
     /// [RFC9015](https://datatracker.ietf.org/doc/html/rfc9015)
+    #[strum(to_string = "SFC (Service Function Chain) Transitive Extended Community")]
     ServiceFunctionChain = 0x0b,
 
     /// [draft-mpmz-bess-mup-safi](https://datatracker.ietf.org/doc/draft-mpmz-bess-mup-safi/)
+    #[strum(to_string = "SRv6 Mobile User Plane")]
     Srv6MobileUserPlane = 0x0c,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
     /// and [RFC9184](https://datatracker.ietf.org/doc/html/rfc9184)
+    #[strum(to_string = "Generic Transitive Experimental Use Extended Community Part 1")]
     GenericPart1 = 0x80,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
     /// and [RFC9184](https://datatracker.ietf.org/doc/html/rfc9184)
+    #[strum(to_string = "Generic Transitive Experimental Use Extended Community Part 2")]
     GenericPart2 = 0x81,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
     /// and [RFC9184](https://datatracker.ietf.org/doc/html/rfc9184)
+    #[strum(to_string = "Generic Transitive Experimental Use Extended Community Part 3")]
     GenericPart3 = 0x82,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental83 = 0x83,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental84 = 0x84,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental85 = 0x85,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental86 = 0x86,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental87 = 0x87,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental88 = 0x88,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental89 = 0x89,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8A = 0x8a,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8B = 0x8b,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8C = 0x8c,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8D = 0x8d,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8E = 0x8e,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     Experimental8F = 0x8f,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC0 = 0xc0,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC1 = 0xc1,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC2 = 0xc2,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC3 = 0xc3,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC4 = 0xc4,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC5 = 0xc5,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC6 = 0xc6,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC7 = 0xc7,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC8 = 0xc8,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalC9 = 0xc9,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCa = 0xca,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCb = 0xcb,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCc = 0xcc,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCd = 0xcd,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCe = 0xce,
+    #[strum(to_string = "Experimental Use")]
     ExperimentalCf = 0xcf,
 }
 
+impl BgpExtendedCommunityType {
+    /// Name of the type as registered by IANA
+    pub fn type_name(&self) -> &'static str {
+        (*self).into()
+    }
+
+    /// The Transitive bit (0x40) is not set, see
+    /// [RFC4360](https://datatracker.ietf.org/doc/html/rfc4360#section-2)
+    pub const fn is_transitive(&self) -> bool {
+        is_extended_community_type_transitive(*self as u8)
+    }
+
+    /// Types reserved for Experimental Use (0x80-0x8F and 0xC0-0xCF), see
+    /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153#section-4)
+    pub const fn is_experimental(&self) -> bool {
+        is_extended_community_type_experimental(*self as u8)
+    }
+}
+
+/// Check the Transitive bit (0x40) of a raw extended community type octet,
+/// works also for types that are not defined in [`BgpExtendedCommunityType`]
+#[inline]
+pub const fn is_extended_community_type_transitive(code: u8) -> bool {
+    code & 0x40 == 0
+}
+
+/// Check if a raw extended community type octet is in the Experimental Use
+/// ranges (0x80-0x8F and 0xC0-0xCF)
+#[inline]
+pub const fn is_extended_community_type_experimental(code: u8) -> bool {
+    matches!(code, 0x80..=0x8f | 0xc0..=0xcf)
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpExtendedCommunityType(pub u8);
@@ -898,16 +1040,33 @@ impl TryFrom<u8> for BgpExtendedCommunityType {
 }
 
 #[repr(u8)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(
+    Display, FromRepr, IntoStaticStr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpExtendedCommunityIpv6Type {
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Transitive IPv6-Address-Specific Extended Community")]
     TransitiveIpv6 = 0x00,
 
     /// [RFC7153](https://datatracker.ietf.org/doc/html/rfc7153)
+    #[strum(to_string = "Non-Transitive IPv6-Address-Specific Extended Community")]
     NonTransitiveIpv6 = 0x40,
 }
 
+impl BgpExtendedCommunityIpv6Type {
+    /// Name of the type as registered by IANA
+    pub fn type_name(&self) -> &'static str {
+        (*self).into()
+    }
+
+    /// The Transitive bit (0x40) is not set, see
+    /// [RFC5701](https://datatracker.ietf.org/doc/html/rfc5701#section-2)
+    pub const fn is_transitive(&self) -> bool {
+        is_extended_community_type_transitive(*self as u8)
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct UndefinedBgpExtendedCommunityIpv6Type(pub u8);
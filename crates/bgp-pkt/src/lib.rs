@@ -14,6 +14,14 @@
 // limitations under the License.
 
 //! BGP PDU data representation
+//!
+//! Disabling the default `std` feature builds the crate as `no_std` (it still
+//! requires `alloc`), which allows using the parsers and serializers in
+//! embedded environments.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 use crate::{
     iana::BgpMessageType, notification::BgpNotificationMessage, open::BgpOpenMessage,
@@ -36,6 +44,18 @@ pub mod wire;
 #[cfg(feature = "codec")]
 pub mod codec;
 
+#[cfg(feature = "exabgp")]
+pub mod exabgp;
+
+/// Hash based collections used in the API, [`std::collections`] are used when
+/// the `std` feature is enabled and [`hashbrown`] otherwise.
+pub mod collections {
+    #[cfg(not(feature = "std"))]
+    pub use hashbrown::{HashMap, HashSet};
+    #[cfg(feature = "std")]
+    pub use std::collections::{HashMap, HashSet};
+}
+
 /// BGP message wire format as defined by [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271#section-4.1)
 /// Here we don't keep the length and type in memory. The type is inferred by
 /// the enum value, while the length is computed a serialization time.
@@ -56,7 +76,6 @@ pub mod codec;
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BgpMessage {
     Open(BgpOpenMessage),
     Update(BgpUpdateMessage),
@@ -65,6 +84,28 @@ pub enum BgpMessage {
     RouteRefresh(BgpRouteRefreshMessage),
 }
 
+/// Messages that don't fit in the maximum BGP message length are rejected, so
+/// every generated message can be written and parsed back.
+#[cfg(feature = "fuzz")]
+impl<'a> arbitrary::Arbitrary<'a> for BgpMessage {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let msg = match u.int_in_range(0..=4u8)? {
+            0 => Self::Open(u.arbitrary()?),
+            1 => Self::Update(u.arbitrary()?),
+            2 => Self::Notification(u.arbitrary()?),
+            3 => Self::KeepAlive,
+            _ => Self::RouteRefresh(u.arbitrary()?),
+        };
+        #[cfg(feature = "serde")]
+        if netgauze_parse_utils::WritablePdu::len(&msg)
+            > wire::deserializer::BGP_MAX_MESSAGE_LENGTH as usize
+        {
+            return Err(arbitrary::Error::IncorrectFormat);
+        }
+        Ok(msg)
+    }
+}
+
 impl BgpMessage {
     /// Get the BGP message IANA type
     pub const fn get_type(&self) -> BgpMessageType {
@@ -82,9 +123,9 @@ impl BgpMessage {
 #[cfg(feature = "fuzz")]
 pub(crate) fn arbitrary_ipv4(
     u: &mut arbitrary::Unstructured<'_>,
-) -> arbitrary::Result<std::net::Ipv4Addr> {
+) -> arbitrary::Result<core::net::Ipv4Addr> {
     let value = u.int_in_range(0..=u32::MAX)?;
-    Ok(std::net::Ipv4Addr::from(value))
+    Ok(core::net::Ipv4Addr::from(value))
 }
 
 // Custom function to generate arbitrary ipv4 network address
@@ -95,7 +136,7 @@ pub(crate) fn arbitrary_ipv4net(
     loop {
         let value = u.int_in_range(0..=u32::MAX)?;
         let mask = u.int_in_range(0..=u8::MAX)?;
-        let addr = std::net::Ipv4Addr::from(value);
+        let addr = core::net::Ipv4Addr::from(value);
         if let Ok(net) = ipnet::Ipv4Net::new(addr, mask) {
             return Ok(net);
         }
@@ -106,9 +147,9 @@ pub(crate) fn arbitrary_ipv4net(
 #[cfg(feature = "fuzz")]
 pub(crate) fn arbitrary_ipv6(
     u: &mut arbitrary::Unstructured<'_>,
-) -> arbitrary::Result<std::net::Ipv6Addr> {
+) -> arbitrary::Result<core::net::Ipv6Addr> {
     let value = u.int_in_range(0..=u128::MAX)?;
-    Ok(std::net::Ipv6Addr::from(value))
+    Ok(core::net::Ipv6Addr::from(value))
 }
 
 // Custom function to generate arbitrary ipv6 network address
@@ -119,7 +160,7 @@ pub(crate) fn arbitrary_ipv6net(
     loop {
         let value = u.int_in_range(0..=u128::MAX)?;
         let mask = u.int_in_range(0..=u8::MAX)?;
-        let addr = std::net::Ipv6Addr::from(value);
+        let addr = core::net::Ipv6Addr::from(value);
         if let Ok(net) = ipnet::Ipv6Net::new(addr, mask) {
             return Ok(net);
         }
@@ -130,10 +171,10 @@ pub(crate) fn arbitrary_ipv6net(
 #[cfg(feature = "fuzz")]
 pub(crate) fn arbitrary_ip(
     u: &mut arbitrary::Unstructured<'_>,
-) -> arbitrary::Result<std::net::IpAddr> {
+) -> arbitrary::Result<core::net::IpAddr> {
     let ipv4 = arbitrary_ipv4(u)?;
     let ipv6 = arbitrary_ipv6(u)?;
-    let choices = [std::net::IpAddr::V4(ipv4), std::net::IpAddr::V6(ipv6)];
+    let choices = [core::net::IpAddr::V4(ipv4), core::net::IpAddr::V6(ipv6)];
     let addr = u.choose(&choices)?;
     Ok(*addr)
 }
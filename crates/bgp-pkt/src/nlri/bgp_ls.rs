@@ -20,13 +20,14 @@ use crate::{
     },
     nlri::RouteDistinguisher,
 };
-use ipnet::IpNet;
-use netgauze_parse_utils::WritablePdu;
-use serde::{Deserialize, Serialize};
-use std::{
+use alloc::{vec, vec::Vec};
+use core::{
     net::{Ipv4Addr, Ipv6Addr},
     ops::BitAnd,
 };
+use ipnet::IpNet;
+use netgauze_parse_utils::WritablePdu;
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
 /// ```text
@@ -198,6 +199,43 @@ impl BgpLsNlriValue {
             Err(code) => code,
         }
     }
+
+    /// Descriptor TLVs that are not recognized by this library as `(type,
+    /// value)` pairs, collected from the local and remote node descriptors,
+    /// then the link or prefix descriptors. An NLRI of an unknown type is
+    /// returned as a single entry.
+    pub fn unknown_tlvs(&self) -> Vec<(u16, &[u8])> {
+        match self {
+            BgpLsNlriValue::Node(node) => node.local_node_descriptors.0.unknown_tlvs().collect(),
+            BgpLsNlriValue::Link(link) => link
+                .local_node_descriptors
+                .0
+                .unknown_tlvs()
+                .chain(link.remote_node_descriptors.0.unknown_tlvs())
+                .chain(link.link_descriptors.iter().filter_map(|tlv| match tlv {
+                    BgpLsLinkDescriptor::Unknown { code, value } => Some((*code, value.as_slice())),
+                    _ => None,
+                }))
+                .collect(),
+            BgpLsNlriValue::Ipv4Prefix(prefix) | BgpLsNlriValue::Ipv6Prefix(prefix) => prefix
+                .local_node_descriptors
+                .0
+                .unknown_tlvs()
+                .chain(
+                    prefix
+                        .prefix_descriptors
+                        .iter()
+                        .filter_map(|tlv| match tlv {
+                            BgpLsPrefixDescriptor::Unknown { code, value } => {
+                                Some((*code, value.as_slice()))
+                            }
+                            _ => None,
+                        }),
+                )
+                .collect(),
+            BgpLsNlriValue::Unknown { code, value } => vec![(*code, value.as_slice())],
+        }
+    }
 }
 
 /// ```text
@@ -486,6 +524,15 @@ impl BgpLsNodeDescriptors {
     pub fn subtlvs_len(&self) -> usize {
         self.subtlvs().iter().map(|tlv| tlv.len()).sum()
     }
+
+    /// Sub-TLVs that are not recognized by this library as `(type, value)`
+    /// pairs.
+    pub fn unknown_tlvs(&self) -> impl Iterator<Item = (u16, &[u8])> {
+        self.subtlvs().iter().filter_map(|tlv| match tlv {
+            BgpLsNodeDescriptorSubTlv::Unknown { code, value } => Some((*code, value.as_slice())),
+            _ => None,
+        })
+    }
 }
 
 /// see [RFC7752 Section 3.2.2](https://www.rfc-editor.org/rfc/rfc7752#section-3.2.2)
@@ -16,11 +16,22 @@
 //! Data types to represent various Network Layer Reachability Information
 //! (`NLRI`)
 
-use crate::iana::{L2EvpnRouteTypeCode, RouteDistinguisherTypeCode};
+use crate::{
+    community::{
+        ExtendedCommunity, TransitiveFourOctetExtendedCommunity, TransitiveIpv4ExtendedCommunity,
+        TransitiveTwoOctetExtendedCommunity,
+    },
+    iana::{
+        BgpExtendedCommunityType, L2EvpnRouteTypeCode, RouteDistinguisherTypeCode,
+        TransitiveFourOctetExtendedCommunitySubType, TransitiveIpv4ExtendedCommunitySubType,
+        TransitiveTwoOctetExtendedCommunitySubType,
+    },
+};
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use ipnet::{Ipv4Net, Ipv6Net};
 use netgauze_iana::address_family::AddressType;
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 /// Get the [`AddressType`] of a given NLRI
 pub trait NlriAddressType {
@@ -1052,6 +1063,12 @@ impl L2EvpnIpv6PrefixRoute {
     }
 }
 
+/// Route Target Membership NLRI as advertised in the
+/// [`AddressType::RouteTargetConstrains`] address family.
+///
+/// A `membership` of [`None`] is the default route target membership
+/// (zero-length prefix), a peer advertising it is interested in all the VPN
+/// routes regardless of their route targets.
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct RouteTargetMembershipAddress {
@@ -1067,6 +1084,11 @@ impl RouteTargetMembershipAddress {
         }
     }
 
+    /// Default route target membership, i.e., zero-length prefix
+    pub const fn default_membership(path_id: Option<u32>) -> Self {
+        Self::new(path_id, None)
+    }
+
     pub const fn path_id(&self) -> Option<u32> {
         self.path_id
     }
@@ -1074,10 +1096,45 @@ impl RouteTargetMembershipAddress {
     pub const fn membership(&self) -> Option<&RouteTargetMembership> {
         self.membership.as_ref()
     }
+
+    /// Check if this is the default route target membership
+    pub const fn is_default(&self) -> bool {
+        self.membership.is_none()
+    }
+
+    /// Length of the NLRI prefix in bits as encoded on the wire
+    pub const fn prefix_len(&self) -> u8 {
+        match &self.membership {
+            Some(membership) => membership.prefix_len(),
+            None => 0,
+        }
+    }
+
+    /// Check if a route carrying the given route target extended community is
+    /// covered by this membership. The default membership covers all route
+    /// targets.
+    pub fn matches(&self, route_target: &ExtendedCommunity) -> bool {
+        match &self.membership {
+            Some(membership) => membership.matches(route_target),
+            None => route_target_value(route_target).is_some(),
+        }
+    }
+
+    /// Decide if a VPN route carrying the given extended communities should be
+    /// sent to a peer that advertised this membership. Only the route target
+    /// communities are considered.
+    pub fn matches_any<'a, I: IntoIterator<Item = &'a ExtendedCommunity>>(
+        &self,
+        communities: I,
+    ) -> bool {
+        communities
+            .into_iter()
+            .any(|community| self.matches(community))
+    }
 }
 
 /// Route Target Membership NLRI
-/// [RFC9136](https://datatracker.ietf.org/doc/html/rfc9136)
+/// [RFC4684](https://datatracker.ietf.org/doc/html/rfc4684)
 /// ```text
 /// +-------------------------------+
 /// | origin as        (4 octets)   |
@@ -1105,6 +1162,19 @@ impl RouteTargetMembership {
         }
     }
 
+    /// Membership of the given origin AS with a wildcard route target (prefix
+    /// length of 32), i.e., covering all route targets
+    pub const fn origin_as_wildcard(origin_as: u32) -> Self {
+        Self::new(origin_as, Vec::new())
+    }
+
+    /// Membership of the given origin AS for exactly one route target
+    /// extended community. Returns [`None`] if the community is not a route
+    /// target.
+    pub fn from_route_target(origin_as: u32, route_target: &ExtendedCommunity) -> Option<Self> {
+        route_target_value(route_target).map(|value| Self::new(origin_as, value.to_vec()))
+    }
+
     pub const fn origin_as(&self) -> u32 {
         self.origin_as
     }
@@ -1112,6 +1182,70 @@ impl RouteTargetMembership {
     pub const fn route_target(&self) -> &Vec<u8> {
         &self.route_target
     }
+
+    /// The route target part is fully wildcarded, only the origin AS is
+    /// specified
+    pub fn is_route_target_wildcard(&self) -> bool {
+        self.route_target.is_empty()
+    }
+
+    /// Length of the NLRI prefix in bits as encoded on the wire
+    pub const fn prefix_len(&self) -> u8 {
+        32 + (self.route_target.len() as u8) * 8
+    }
+
+    /// Check if a route carrying the given route target extended community is
+    /// covered by this membership, the route target prefix is compared against
+    /// the 8-octet encoding of the community. The origin AS is not considered,
+    /// since it identifies the speaker that advertised the membership rather
+    /// than the VPN routes.
+    pub fn matches(&self, route_target: &ExtendedCommunity) -> bool {
+        match route_target_value(route_target) {
+            Some(value) => value.starts_with(&self.route_target),
+            None => false,
+        }
+    }
+}
+
+/// Get the 8-octet wire encoding of a route target extended community, or
+/// [`None`] if the community is not a route target.
+fn route_target_value(community: &ExtendedCommunity) -> Option<[u8; 8]> {
+    let mut value = [0u8; 8];
+    match community {
+        ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                global_admin,
+                local_admin,
+            },
+        ) => {
+            value[0] = BgpExtendedCommunityType::TransitiveTwoOctet as u8;
+            value[1] = TransitiveTwoOctetExtendedCommunitySubType::RouteTarget as u8;
+            value[2..4].copy_from_slice(&global_admin.to_be_bytes());
+            value[4..8].copy_from_slice(&local_admin.to_be_bytes());
+        }
+        ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteTarget {
+            global_admin,
+            local_admin,
+        }) => {
+            value[0] = BgpExtendedCommunityType::TransitiveIpv4 as u8;
+            value[1] = TransitiveIpv4ExtendedCommunitySubType::RouteTarget as u8;
+            value[2..6].copy_from_slice(&global_admin.octets());
+            value[6..8].copy_from_slice(&local_admin.to_be_bytes());
+        }
+        ExtendedCommunity::TransitiveFourOctet(
+            TransitiveFourOctetExtendedCommunity::RouteTarget {
+                global_admin,
+                local_admin,
+            },
+        ) => {
+            value[0] = BgpExtendedCommunityType::TransitiveFourOctet as u8;
+            value[1] = TransitiveFourOctetExtendedCommunitySubType::RouteTarget as u8;
+            value[2..6].copy_from_slice(&global_admin.to_be_bytes());
+            value[6..8].copy_from_slice(&local_admin.to_be_bytes());
+        }
+        _ => return None,
+    }
+    Some(value)
 }
 
 impl NlriAddressType for RouteTargetMembershipAddress {
@@ -1313,7 +1447,7 @@ impl NlriAddressType for Ipv6NlriMplsLabelsAddress {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::str::FromStr;
+    use core::str::FromStr;
 
     #[test]
     fn test_ipv4_unicast() {
@@ -1378,4 +1512,65 @@ mod tests {
         );
         assert_eq!(unicast, Err(InvalidIpv6MulticastNetwork(unicast_addr)));
     }
+
+    #[test]
+    fn test_route_target_membership_matching() {
+        let rt = ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteTarget {
+                global_admin: 65000,
+                local_admin: 100,
+            },
+        );
+        let other_rt =
+            ExtendedCommunity::TransitiveIpv4(TransitiveIpv4ExtendedCommunity::RouteTarget {
+                global_admin: Ipv4Addr::new(192, 0, 2, 1),
+                local_admin: 100,
+            });
+        let not_rt = ExtendedCommunity::TransitiveTwoOctet(
+            TransitiveTwoOctetExtendedCommunity::RouteOrigin {
+                global_admin: 65000,
+                local_admin: 100,
+            },
+        );
+
+        let default = RouteTargetMembershipAddress::default_membership(None);
+        let wildcard = RouteTargetMembershipAddress::new(
+            None,
+            Some(RouteTargetMembership::origin_as_wildcard(65001)),
+        );
+        let exact = RouteTargetMembershipAddress::new(
+            None,
+            RouteTargetMembership::from_route_target(65001, &rt),
+        );
+        let type_prefix = RouteTargetMembershipAddress::new(
+            None,
+            Some(RouteTargetMembership::new(65001, vec![0x00, 0x02])),
+        );
+
+        assert!(default.is_default());
+        assert_eq!(default.prefix_len(), 0);
+        assert!(!wildcard.is_default());
+        assert_eq!(wildcard.prefix_len(), 32);
+        assert!(wildcard.membership().unwrap().is_route_target_wildcard());
+        assert_eq!(exact.prefix_len(), 96);
+        assert_eq!(type_prefix.prefix_len(), 48);
+        assert_eq!(
+            RouteTargetMembership::from_route_target(65001, &not_rt),
+            None
+        );
+
+        assert!(default.matches(&rt));
+        assert!(default.matches(&other_rt));
+        assert!(!default.matches(&not_rt));
+        assert!(wildcard.matches(&rt));
+        assert!(wildcard.matches(&other_rt));
+        assert!(exact.matches(&rt));
+        assert!(!exact.matches(&other_rt));
+        assert!(type_prefix.matches(&rt));
+        assert!(!type_prefix.matches(&other_rt));
+
+        assert!(exact.matches_any(&[not_rt, rt]));
+        assert!(!exact.matches_any(&[not_rt, other_rt]));
+        assert!(!default.matches_any(&[]));
+    }
 }
@@ -15,6 +15,16 @@
 
 //! Representations for BGP Notification message
 
+use crate::iana::{
+    BgpErrorNotificationCode, CeaseErrorSubCode, FiniteStateMachineErrorSubCode,
+    MessageHeaderErrorSubCode, OpenMessageErrorSubCode, RouteRefreshMessageErrorSubCode,
+    UpdateMessageErrorSubCode,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Display, Formatter};
 use serde::{Deserialize, Serialize};
 
 /// BGP Notification message
@@ -38,6 +48,62 @@ pub enum BgpNotificationMessage {
     RouteRefreshError(RouteRefreshError),
 }
 
+impl BgpNotificationMessage {
+    pub const fn code(&self) -> BgpErrorNotificationCode {
+        match self {
+            Self::MessageHeaderError(_) => BgpErrorNotificationCode::MessageHeaderError,
+            Self::OpenMessageError(_) => BgpErrorNotificationCode::OpenMessageError,
+            Self::UpdateMessageError(_) => BgpErrorNotificationCode::UpdateMessageError,
+            Self::HoldTimerExpiredError(_) => BgpErrorNotificationCode::HoldTimerExpired,
+            Self::FiniteStateMachineError(_) => BgpErrorNotificationCode::FiniteStateMachineError,
+            Self::CeaseError(_) => BgpErrorNotificationCode::Cease,
+            Self::RouteRefreshError(_) => BgpErrorNotificationCode::RouteRefreshMessageError,
+        }
+    }
+
+    /// Error subcode value as carried on the wire
+    pub fn sub_code(&self) -> u8 {
+        match self {
+            Self::MessageHeaderError(value) => value.sub_code().into(),
+            Self::OpenMessageError(value) => value.sub_code().into(),
+            Self::UpdateMessageError(value) => value.sub_code().into(),
+            Self::HoldTimerExpiredError(value) => value.sub_code(),
+            Self::FiniteStateMachineError(value) => value.sub_code().into(),
+            Self::CeaseError(value) => value.sub_code().into(),
+            Self::RouteRefreshError(value) => value.sub_code().into(),
+        }
+    }
+
+    /// Diagnostic data carried after the error subcode
+    pub fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::MessageHeaderError(value) => value.value(),
+            Self::OpenMessageError(value) => value.value(),
+            Self::UpdateMessageError(value) => value.value(),
+            Self::HoldTimerExpiredError(value) => value.value(),
+            Self::FiniteStateMachineError(value) => value.value(),
+            Self::CeaseError(value) => value.value(),
+            Self::RouteRefreshError(value) => value.value(),
+        }
+    }
+}
+
+/// Human-readable error code and subcode names as defined in the RFCs, e.g.,
+/// `Cease: Administrative Shutdown`.
+impl Display for BgpNotificationMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MessageHeaderError(value) => write!(f, "{}: {value}", self.code()),
+            Self::OpenMessageError(value) => write!(f, "{}: {value}", self.code()),
+            Self::UpdateMessageError(value) => write!(f, "{}: {value}", self.code()),
+            Self::HoldTimerExpiredError(value) => write!(f, "{}: {value}", self.code()),
+            Self::FiniteStateMachineError(value) => write!(f, "{}: {value}", self.code()),
+            Self::CeaseError(value) => write!(f, "{}: {value}", self.code()),
+            Self::RouteRefreshError(value) => write!(f, "{}: {value}", self.code()),
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MessageHeaderError {
@@ -47,6 +113,34 @@ pub enum MessageHeaderError {
     BadMessageType { value: Vec<u8> },
 }
 
+impl MessageHeaderError {
+    pub const fn sub_code(&self) -> MessageHeaderErrorSubCode {
+        match self {
+            Self::Unspecific { .. } => MessageHeaderErrorSubCode::Unspecific,
+            Self::ConnectionNotSynchronized { .. } => {
+                MessageHeaderErrorSubCode::ConnectionNotSynchronized
+            }
+            Self::BadMessageLength { .. } => MessageHeaderErrorSubCode::BadMessageLength,
+            Self::BadMessageType { .. } => MessageHeaderErrorSubCode::BadMessageType,
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::Unspecific { value }
+            | Self::ConnectionNotSynchronized { value }
+            | Self::BadMessageLength { value }
+            | Self::BadMessageType { value } => value,
+        }
+    }
+}
+
+impl Display for MessageHeaderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.sub_code())
+    }
+}
+
 /// See [`crate::iana::OpenMessageErrorSubCode`] for full documentation
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
@@ -56,11 +150,52 @@ pub enum OpenMessageError {
     BadPeerAs { value: Vec<u8> },
     BadBgpIdentifier { value: Vec<u8> },
     UnsupportedOptionalParameter { value: Vec<u8> },
+    AuthenticationFailure { value: Vec<u8> },
     UnacceptableHoldTime { value: Vec<u8> },
     UnsupportedCapability { value: Vec<u8> },
     RoleMismatch { value: Vec<u8> },
 }
 
+impl OpenMessageError {
+    pub const fn sub_code(&self) -> OpenMessageErrorSubCode {
+        match self {
+            Self::Unspecific { .. } => OpenMessageErrorSubCode::Unspecific,
+            Self::UnsupportedVersionNumber { .. } => {
+                OpenMessageErrorSubCode::UnsupportedVersionNumber
+            }
+            Self::BadPeerAs { .. } => OpenMessageErrorSubCode::BadPeerAs,
+            Self::BadBgpIdentifier { .. } => OpenMessageErrorSubCode::BadBgpIdentifier,
+            Self::UnsupportedOptionalParameter { .. } => {
+                OpenMessageErrorSubCode::UnsupportedOptionalParameter
+            }
+            Self::AuthenticationFailure { .. } => OpenMessageErrorSubCode::AuthenticationFailure,
+            Self::UnacceptableHoldTime { .. } => OpenMessageErrorSubCode::UnacceptableHoldTime,
+            Self::UnsupportedCapability { .. } => OpenMessageErrorSubCode::UnsupportedCapability,
+            Self::RoleMismatch { .. } => OpenMessageErrorSubCode::RoleMismatch,
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::Unspecific { value }
+            | Self::UnsupportedVersionNumber { value }
+            | Self::BadPeerAs { value }
+            | Self::BadBgpIdentifier { value }
+            | Self::UnsupportedOptionalParameter { value }
+            | Self::AuthenticationFailure { value }
+            | Self::UnacceptableHoldTime { value }
+            | Self::UnsupportedCapability { value }
+            | Self::RoleMismatch { value } => value,
+        }
+    }
+}
+
+impl Display for OpenMessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.sub_code())
+    }
+}
+
 /// See [`crate::iana::UpdateMessageErrorSubCode`] for full documentation
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
@@ -72,18 +207,97 @@ pub enum UpdateMessageError {
     AttributeFlagsError { value: Vec<u8> },
     AttributeLengthError { value: Vec<u8> },
     InvalidOriginAttribute { value: Vec<u8> },
+    AsRoutingLoop { value: Vec<u8> },
     InvalidNextHopAttribute { value: Vec<u8> },
     OptionalAttributeError { value: Vec<u8> },
     InvalidNetworkField { value: Vec<u8> },
     MalformedAsPath { value: Vec<u8> },
 }
 
+impl UpdateMessageError {
+    pub const fn sub_code(&self) -> UpdateMessageErrorSubCode {
+        match self {
+            Self::Unspecific { .. } => UpdateMessageErrorSubCode::Unspecific,
+            Self::MalformedAttributeList { .. } => {
+                UpdateMessageErrorSubCode::MalformedAttributeList
+            }
+            Self::UnrecognizedWellKnownAttribute { .. } => {
+                UpdateMessageErrorSubCode::UnrecognizedWellKnownAttribute
+            }
+            Self::MissingWellKnownAttribute { .. } => {
+                UpdateMessageErrorSubCode::MissingWellKnownAttribute
+            }
+            Self::AttributeFlagsError { .. } => UpdateMessageErrorSubCode::AttributeFlagsError,
+            Self::AttributeLengthError { .. } => UpdateMessageErrorSubCode::AttributeLengthError,
+            Self::InvalidOriginAttribute { .. } => {
+                UpdateMessageErrorSubCode::InvalidOriginAttribute
+            }
+            Self::AsRoutingLoop { .. } => UpdateMessageErrorSubCode::AsRoutingLoop,
+            Self::InvalidNextHopAttribute { .. } => {
+                UpdateMessageErrorSubCode::InvalidNextHopAttribute
+            }
+            Self::OptionalAttributeError { .. } => {
+                UpdateMessageErrorSubCode::OptionalAttributeError
+            }
+            Self::InvalidNetworkField { .. } => UpdateMessageErrorSubCode::InvalidNetworkField,
+            Self::MalformedAsPath { .. } => UpdateMessageErrorSubCode::MalformedAsPath,
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::Unspecific { value }
+            | Self::MalformedAttributeList { value }
+            | Self::UnrecognizedWellKnownAttribute { value }
+            | Self::MissingWellKnownAttribute { value }
+            | Self::AttributeFlagsError { value }
+            | Self::AttributeLengthError { value }
+            | Self::InvalidOriginAttribute { value }
+            | Self::AsRoutingLoop { value }
+            | Self::InvalidNextHopAttribute { value }
+            | Self::OptionalAttributeError { value }
+            | Self::InvalidNetworkField { value }
+            | Self::MalformedAsPath { value } => value,
+        }
+    }
+}
+
+impl Display for UpdateMessageError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.sub_code())
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum HoldTimerExpiredError {
     Unspecific { sub_code: u8, value: Vec<u8> },
 }
 
+impl HoldTimerExpiredError {
+    /// No subcodes are defined for Hold Timer Expired, the sub code is
+    /// returned as is.
+    pub const fn sub_code(&self) -> u8 {
+        match self {
+            Self::Unspecific { sub_code, .. } => *sub_code,
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::Unspecific { value, .. } => value,
+        }
+    }
+}
+
+impl Display for HoldTimerExpiredError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Unspecific { sub_code, .. } => write!(f, "Unspecific ({sub_code})"),
+        }
+    }
+}
+
 /// See [`crate::iana::FiniteStateMachineErrorSubCode`] for full documentation
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
@@ -94,6 +308,57 @@ pub enum FiniteStateMachineError {
     ReceiveUnexpectedMessageInEstablishedState { value: Vec<u8> },
 }
 
+impl FiniteStateMachineError {
+    pub const fn sub_code(&self) -> FiniteStateMachineErrorSubCode {
+        match self {
+            Self::Unspecific { .. } => FiniteStateMachineErrorSubCode::UnspecifiedError,
+            Self::ReceiveUnexpectedMessageInOpenSentState { .. } => {
+                FiniteStateMachineErrorSubCode::ReceiveUnexpectedMessageInOpenSentState
+            }
+            Self::ReceiveUnexpectedMessageInOpenConfirmState { .. } => {
+                FiniteStateMachineErrorSubCode::ReceiveUnexpectedMessageInOpenConfirmState
+            }
+            Self::ReceiveUnexpectedMessageInEstablishedState { .. } => {
+                FiniteStateMachineErrorSubCode::ReceiveUnexpectedMessageInEstablishedState
+            }
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::Unspecific { value }
+            | Self::ReceiveUnexpectedMessageInOpenSentState { value }
+            | Self::ReceiveUnexpectedMessageInOpenConfirmState { value }
+            | Self::ReceiveUnexpectedMessageInEstablishedState { value } => value,
+        }
+    }
+}
+
+impl Display for FiniteStateMachineError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.sub_code())
+    }
+}
+
+/// Maximum length in octets of the Shutdown Communication carried in
+/// Administrative Shutdown and Administrative Reset Cease notifications,
+/// see [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003)
+pub const SHUTDOWN_COMMUNICATION_MAX_LENGTH: usize = 255;
+
+/// Raised when the Shutdown Communication of a Cease notification is malformed
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum InvalidShutdownCommunication {
+    /// The message is longer than [`SHUTDOWN_COMMUNICATION_MAX_LENGTH`] octets
+    TooLong(usize),
+
+    /// The length octet claims more octets than carried in the notification
+    InvalidLength { declared: u8, available: usize },
+
+    /// The message is not a valid UTF-8 string
+    InvalidUtf8(String),
+}
+
 /// See [`crate::iana::CeaseErrorSubCode`] for full documentation
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
@@ -110,9 +375,226 @@ pub enum CeaseError {
     BfdDown { value: Vec<u8> },
 }
 
+impl CeaseError {
+    /// Administrative Shutdown with a Shutdown Communication
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003) to be sent to
+    /// the peer.
+    pub fn administrative_shutdown(
+        communication: &str,
+    ) -> Result<Self, InvalidShutdownCommunication> {
+        Ok(Self::AdministrativeShutdown {
+            value: Self::encode_shutdown_communication(communication)?,
+        })
+    }
+
+    /// Administrative Reset with a Shutdown Communication
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003) to be sent to
+    /// the peer.
+    pub fn administrative_reset(communication: &str) -> Result<Self, InvalidShutdownCommunication> {
+        Ok(Self::AdministrativeReset {
+            value: Self::encode_shutdown_communication(communication)?,
+        })
+    }
+
+    fn encode_shutdown_communication(
+        communication: &str,
+    ) -> Result<Vec<u8>, InvalidShutdownCommunication> {
+        let len = communication.len();
+        if len > SHUTDOWN_COMMUNICATION_MAX_LENGTH {
+            return Err(InvalidShutdownCommunication::TooLong(len));
+        }
+        let mut value = Vec::with_capacity(len + 1);
+        value.push(len as u8);
+        value.extend_from_slice(communication.as_bytes());
+        Ok(value)
+    }
+
+    /// Shutdown Communication carried in Administrative Shutdown and
+    /// Administrative Reset notifications as defined by
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003).
+    ///
+    /// Returns `Ok(None)` for other sub codes or when the peer didn't include
+    /// any data. Octets following the communication, if any, are ignored.
+    pub fn shutdown_communication(&self) -> Result<Option<&str>, InvalidShutdownCommunication> {
+        let value = match self {
+            Self::AdministrativeShutdown { value } | Self::AdministrativeReset { value } => value,
+            _ => return Ok(None),
+        };
+        let (declared, data) = match value.split_first() {
+            Some((declared, data)) => (*declared, data),
+            None => return Ok(None),
+        };
+        let communication =
+            data.get(..declared as usize)
+                .ok_or(InvalidShutdownCommunication::InvalidLength {
+                    declared,
+                    available: data.len(),
+                })?;
+        core::str::from_utf8(communication)
+            .map(Some)
+            .map_err(|err| InvalidShutdownCommunication::InvalidUtf8(err.to_string()))
+    }
+
+    pub const fn sub_code(&self) -> CeaseErrorSubCode {
+        match self {
+            Self::MaximumNumberOfPrefixesReached { .. } => {
+                CeaseErrorSubCode::MaximumNumberOfPrefixesReached
+            }
+            Self::AdministrativeShutdown { .. } => CeaseErrorSubCode::AdministrativeShutdown,
+            Self::PeerDeConfigured { .. } => CeaseErrorSubCode::PeerDeConfigured,
+            Self::AdministrativeReset { .. } => CeaseErrorSubCode::AdministrativeReset,
+            Self::ConnectionRejected { .. } => CeaseErrorSubCode::ConnectionRejected,
+            Self::OtherConfigurationChange { .. } => CeaseErrorSubCode::OtherConfigurationChange,
+            Self::ConnectionCollisionResolution { .. } => {
+                CeaseErrorSubCode::ConnectionCollisionResolution
+            }
+            Self::OutOfResources { .. } => CeaseErrorSubCode::OutOfResources,
+            Self::HardReset { .. } => CeaseErrorSubCode::HardReset,
+            Self::BfdDown { .. } => CeaseErrorSubCode::BfdDown,
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::MaximumNumberOfPrefixesReached { value }
+            | Self::AdministrativeShutdown { value }
+            | Self::PeerDeConfigured { value }
+            | Self::AdministrativeReset { value }
+            | Self::ConnectionRejected { value }
+            | Self::OtherConfigurationChange { value }
+            | Self::ConnectionCollisionResolution { value }
+            | Self::OutOfResources { value }
+            | Self::HardReset { value }
+            | Self::BfdDown { value } => value,
+        }
+    }
+}
+
+impl Display for CeaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.shutdown_communication() {
+            Ok(Some(communication)) => write!(f, "{} ({communication:?})", self.sub_code()),
+            _ => write!(f, "{}", self.sub_code()),
+        }
+    }
+}
+
 /// See [`crate::iana::RouteRefreshMessageErrorSubCode`] for full documentation
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum RouteRefreshError {
     InvalidMessageLength { value: Vec<u8> },
 }
+
+impl RouteRefreshError {
+    pub const fn sub_code(&self) -> RouteRefreshMessageErrorSubCode {
+        match self {
+            Self::InvalidMessageLength { .. } => {
+                RouteRefreshMessageErrorSubCode::InvalidMessageLength
+            }
+        }
+    }
+
+    pub const fn value(&self) -> &Vec<u8> {
+        match self {
+            Self::InvalidMessageLength { value } => value,
+        }
+    }
+}
+
+impl Display for RouteRefreshError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.sub_code())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notification_display() {
+        let shutdown = BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+            value: vec![],
+        });
+        let fsm = BgpNotificationMessage::FiniteStateMachineError(
+            FiniteStateMachineError::ReceiveUnexpectedMessageInOpenSentState { value: vec![] },
+        );
+        let hold_timer =
+            BgpNotificationMessage::HoldTimerExpiredError(HoldTimerExpiredError::Unspecific {
+                sub_code: 0,
+                value: vec![],
+            });
+        let as_path =
+            BgpNotificationMessage::UpdateMessageError(UpdateMessageError::MalformedAsPath {
+                value: vec![1],
+            });
+        assert_eq!(shutdown.to_string(), "Cease: Administrative Shutdown");
+        assert_eq!(
+            fsm.to_string(),
+            "Finite State Machine Error: Receive Unexpected Message in OpenSent State"
+        );
+        assert_eq!(hold_timer.to_string(), "Hold Timer Expired: Unspecific (0)");
+        assert_eq!(
+            as_path.to_string(),
+            "UPDATE Message Error: Malformed AS_PATH"
+        );
+        assert_eq!(shutdown.code(), BgpErrorNotificationCode::Cease);
+        assert_eq!(shutdown.sub_code(), 2);
+        assert_eq!(as_path.value(), &vec![1]);
+    }
+
+    #[test]
+    fn test_shutdown_communication() {
+        let shutdown = CeaseError::administrative_shutdown("maintenance").unwrap();
+        let reset = CeaseError::administrative_reset("").unwrap();
+        let empty = CeaseError::AdministrativeShutdown { value: vec![] };
+        let trailing = CeaseError::AdministrativeReset {
+            value: vec![0x02, b'o', b'k', 0xff],
+        };
+        let other = CeaseError::PeerDeConfigured {
+            value: vec![0x02, b'o', b'k'],
+        };
+        let bad_length = CeaseError::AdministrativeShutdown {
+            value: vec![0x05, b'o', b'k'],
+        };
+        let bad_utf8 = CeaseError::AdministrativeShutdown {
+            value: vec![0x02, 0xc3, 0x28],
+        };
+
+        assert_eq!(
+            shutdown,
+            CeaseError::AdministrativeShutdown {
+                value: b"\x0bmaintenance".to_vec()
+            }
+        );
+        assert_eq!(shutdown.shutdown_communication(), Ok(Some("maintenance")));
+        assert_eq!(reset.shutdown_communication(), Ok(Some("")));
+        assert_eq!(empty.shutdown_communication(), Ok(None));
+        assert_eq!(trailing.shutdown_communication(), Ok(Some("ok")));
+        assert_eq!(other.shutdown_communication(), Ok(None));
+        assert_eq!(
+            bad_length.shutdown_communication(),
+            Err(InvalidShutdownCommunication::InvalidLength {
+                declared: 5,
+                available: 2
+            })
+        );
+        assert!(matches!(
+            bad_utf8.shutdown_communication(),
+            Err(InvalidShutdownCommunication::InvalidUtf8(_))
+        ));
+        assert_eq!(
+            CeaseError::administrative_shutdown(&"x".repeat(256)),
+            Err(InvalidShutdownCommunication::TooLong(256))
+        );
+        assert_eq!(
+            BgpNotificationMessage::CeaseError(shutdown).to_string(),
+            "Cease: Administrative Shutdown (\"maintenance\")"
+        );
+        assert_eq!(
+            BgpNotificationMessage::CeaseError(bad_utf8).to_string(),
+            "Cease: Administrative Shutdown"
+        );
+    }
+}
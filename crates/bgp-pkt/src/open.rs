@@ -15,7 +15,8 @@
 
 //! Representations for BGP Open message
 use crate::{capabilities::BgpCapability, Deserialize, Serialize};
-use std::net::Ipv4Addr;
+use alloc::vec::Vec;
+use core::net::Ipv4Addr;
 
 pub const BGP_VERSION: u8 = 4;
 
@@ -18,11 +18,12 @@ use crate::{
     nlri::{MplsLabel, MultiTopologyIdData, SharedRiskLinkGroupValue},
     path_attribute::PathAttributeValueProperties,
 };
-use serde::{Deserialize, Serialize};
-use std::{
+use alloc::{string::String, vec::Vec};
+use core::{
     net::{IpAddr, Ipv4Addr, Ipv6Addr},
     ops::{BitAnd, BitOr},
 };
+use serde::{Deserialize, Serialize};
 use strum_macros::{Display, FromRepr};
 
 /// The BGP Link-State Attribute. see [RFC7752 Section 3.3](https://www.rfc-editor.org/rfc/rfc7752#section-3.3)
@@ -32,6 +33,19 @@ pub struct BgpLsAttribute {
     pub attributes: Vec<BgpLsAttributeValue>,
 }
 
+impl BgpLsAttribute {
+    /// TLVs that are not recognized by this library as `(type, value)` pairs.
+    /// They are kept in order and written back as is.
+    pub fn unknown_tlvs(&self) -> impl Iterator<Item = (u16, &[u8])> {
+        self.attributes
+            .iter()
+            .filter_map(|attribute| match attribute {
+                BgpLsAttributeValue::Unknown { code, value } => Some((*code, value.as_slice())),
+                _ => None,
+            })
+    }
+}
+
 impl PathAttributeValueProperties for BgpLsAttribute {
     /// see [RFC7752 Section 3.3](https://www.rfc-editor.org/rfc/rfc7752#section-3.3)
     fn can_be_optional() -> Option<bool> {
@@ -24,9 +24,10 @@ use crate::{
     nlri::*,
     path_attribute::BgpLsAttribute,
 };
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use netgauze_iana::address_family::{AddressFamily, AddressType, SubsequentAddressFamily};
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use strum_macros::{Display, FromRepr};
 
 /// General properties to check the validity of a given path attribute value
@@ -68,7 +69,6 @@ pub enum InvalidPathAttribute {
 /// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
 /// ```
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
-#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct PathAttribute {
     /// Optional bit defines whether the attribute is optional (if set to
     /// `true`) or well-known (if set to `false`).
@@ -164,6 +164,42 @@ impl PathAttribute {
     }
 }
 
+/// Generates only the flag combinations allowed by the
+/// [`PathAttributeValueProperties`] of the value. Flags that are not fixed by
+/// the value are chosen arbitrarily, except for the partial bit that is only
+/// set on optional transitive attributes.
+#[cfg(feature = "fuzz")]
+impl<'a> arbitrary::Arbitrary<'a> for PathAttribute {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let value = PathAttributeValue::arbitrary(u)?;
+        let optional = match value.can_be_optional() {
+            Some(optional) => optional,
+            None => u.arbitrary()?,
+        };
+        let transitive = match value.can_be_transitive() {
+            Some(transitive) => transitive,
+            None => u.arbitrary()?,
+        };
+        let partial = match value.can_be_partial() {
+            Some(partial) => partial,
+            None => optional && transitive && u.arbitrary()?,
+        };
+        let extended_length = u.arbitrary()?;
+        #[allow(unused_mut)]
+        let mut attribute =
+            PathAttribute::from(optional, transitive, partial, extended_length, value)
+                .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        // The value won't fit in a one octet length
+        #[cfg(feature = "serde")]
+        if !attribute.extended_length
+            && netgauze_parse_utils::WritablePdu::len(&attribute) - 3 > u8::MAX as usize
+        {
+            attribute.extended_length = true;
+        }
+        Ok(attribute)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum PathAttributeValue {
@@ -175,6 +211,7 @@ pub enum PathAttributeValue {
     LocalPreference(LocalPreference),
     AtomicAggregate(AtomicAggregate),
     Aggregator(Aggregator),
+    As4Aggregator(As4Aggregator),
     Communities(Communities),
     ExtendedCommunities(ExtendedCommunities),
     ExtendedCommunitiesIpv6(ExtendedCommunitiesIpv6),
@@ -201,6 +238,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_optional(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_optional(),
             Self::Aggregator(_) => Aggregator::can_be_optional(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_optional(),
             Self::Communities(_) => Communities::can_be_optional(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_optional(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_optional(),
@@ -226,6 +264,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_transitive(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_transitive(),
             Self::Aggregator(_) => Aggregator::can_be_transitive(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_transitive(),
             Self::Communities(_) => Communities::can_be_transitive(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_transitive(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_transitive(),
@@ -251,6 +290,7 @@ impl PathAttributeValue {
             Self::LocalPreference(_) => LocalPreference::can_be_partial(),
             Self::AtomicAggregate(_) => AtomicAggregate::can_be_partial(),
             Self::Aggregator(_) => Aggregator::can_be_partial(),
+            Self::As4Aggregator(_) => As4Aggregator::can_be_partial(),
             Self::Communities(_) => Communities::can_be_partial(),
             Self::ExtendedCommunities(_) => ExtendedCommunities::can_be_partial(),
             Self::ExtendedCommunitiesIpv6(_) => ExtendedCommunitiesIpv6::can_be_partial(),
@@ -278,6 +318,7 @@ impl PathAttributeValue {
             PathAttributeValue::LocalPreference(_) => Ok(PathAttributeType::LocalPreference),
             PathAttributeValue::AtomicAggregate(_) => Ok(PathAttributeType::AtomicAggregate),
             PathAttributeValue::Aggregator(_) => Ok(PathAttributeType::Aggregator),
+            PathAttributeValue::As4Aggregator(_) => Ok(PathAttributeType::As4Aggregator),
             PathAttributeValue::Communities(_) => Ok(PathAttributeType::Communities),
             PathAttributeValue::ExtendedCommunities(_) => {
                 Ok(PathAttributeType::ExtendedCommunities)
@@ -379,13 +420,16 @@ impl PathAttributeValueProperties for AsPath {
     }
 }
 
-/// AS Path Segment Type
+/// AS Path Segment Type, the confederation segment types are defined in
+/// [RFC5065](https://datatracker.ietf.org/doc/html/rfc5065)
 ///
 /// ```text
 /// 0
 /// 0 1 2 3 4 5 6 7 8
 /// +-+-+-+-+-+-+-+-+
 /// | set=1 or seq=2|
+/// | confed seq=3  |
+/// | confed set=4  |
 /// +-+-+-+-+-+-+-+-+
 /// ```
 #[repr(u8)]
@@ -394,6 +438,15 @@ impl PathAttributeValueProperties for AsPath {
 pub enum AsPathSegmentType {
     AsSet = 1,
     AsSequence = 2,
+    AsConfedSequence = 3,
+    AsConfedSet = 4,
+}
+
+impl AsPathSegmentType {
+    /// `AS_CONFED_SEQUENCE` or `AS_CONFED_SET`
+    pub const fn is_confederation(&self) -> bool {
+        matches!(self, Self::AsConfedSequence | Self::AsConfedSet)
+    }
 }
 
 impl From<AsPathSegmentType> for u8 {
@@ -667,6 +720,11 @@ impl As2Aggregator {
     }
 }
 
+/// Four-octet AS aggregator, used as the value of the [`Aggregator`] attribute
+/// between four-octet AS speakers, and as the value of the `AS4_AGGREGATOR`
+/// optional transitive attribute sent along with an `AGGREGATOR` carrying
+/// `AS_TRANS` to the other speakers.
+/// See [RFC6793](https://datatracker.ietf.org/doc/html/RFC6793)
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub struct As4Aggregator {
@@ -687,6 +745,20 @@ impl As4Aggregator {
     }
 }
 
+impl PathAttributeValueProperties for As4Aggregator {
+    fn can_be_optional() -> Option<bool> {
+        Some(true)
+    }
+
+    fn can_be_transitive() -> Option<bool> {
+        Some(true)
+    }
+
+    fn can_be_partial() -> Option<bool> {
+        None
+    }
+}
+
 /// AGGREGATOR is an optional transitive attribute. The attribute contains the
 /// last AS number that formed the aggregate route, followed by the IP
 /// address of the BGP speaker that formed the aggregate route.
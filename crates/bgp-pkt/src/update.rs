@@ -16,10 +16,19 @@
 //! Representations for BGP Update message
 
 use crate::nlri::Ipv4UnicastAddress;
+use alloc::vec::Vec;
 use netgauze_iana::address_family::AddressType;
 use serde::{Deserialize, Serialize};
 
 use crate::path_attribute::{MpUnreach, PathAttribute, PathAttributeValue};
+#[cfg(feature = "serde")]
+use crate::wire::serializer::update::BgpUpdateMessageWritingError;
+#[cfg(feature = "serde")]
+use netgauze_parse_utils::WritablePdu;
+
+/// Extended length bit in the path attribute flags
+#[cfg(feature = "serde")]
+const PATH_ATTRIBUTE_EXTENDED_LENGTH_FLAG: u8 = 0b00010000;
 
 /// UPDATE messages are used to transfer routing information between BGP peers
 /// as defined by [RFC4271](https://datatracker.ietf.org/doc/html/RFC4271).
@@ -176,6 +185,76 @@ impl BgpUpdateMessage {
     }
 }
 
+#[cfg(feature = "serde")]
+impl BgpUpdateMessage {
+    /// Stable 64-bit digest of the update's content, suitable for
+    /// de-duplication and change detection.
+    ///
+    /// The digest is computed with XXH3 over a canonical form of the update:
+    /// path attributes are ordered by their type code and hashed without the
+    /// extended length flag, then withdrawn routes and NLRI are each ordered
+    /// by their wire encoding. Hence, the same content encoded in a different
+    /// order yields the same digest. The digest only depends on the BGP wire
+    /// format and doesn't change across versions of this library. Note, the
+    /// order of NLRI inside `MP_REACH_NLRI` and `MP_UNREACH_NLRI` is hashed as
+    /// is.
+    pub fn content_hash(&self) -> Result<u64, BgpUpdateMessageWritingError> {
+        Ok(xxhash_rust::xxh3::xxh3_64(&self.canonical_bytes()?))
+    }
+
+    /// 128-bit variant of [`BgpUpdateMessage::content_hash`]
+    pub fn content_hash128(&self) -> Result<u128, BgpUpdateMessageWritingError> {
+        Ok(xxhash_rust::xxh3::xxh3_128(&self.canonical_bytes()?))
+    }
+
+    fn canonical_bytes(&self) -> Result<Vec<u8>, BgpUpdateMessageWritingError> {
+        let mut attributes = Vec::with_capacity(self.path_attributes.len());
+        for attribute in &self.path_attributes {
+            let mut buf = Vec::with_capacity(attribute.len());
+            attribute.write(&mut buf)?;
+            // Rewrite the header as (flags without extended length, type, 4-octets length)
+            let flags = buf[0];
+            let header_len = if flags & PATH_ATTRIBUTE_EXTENDED_LENGTH_FLAG == 0 {
+                3
+            } else {
+                4
+            };
+            let value_len = (buf.len() - header_len) as u32;
+            let mut canonical = Vec::with_capacity(buf.len() + 4);
+            canonical.push(flags & !PATH_ATTRIBUTE_EXTENDED_LENGTH_FLAG);
+            canonical.push(buf[1]);
+            canonical.extend_from_slice(&value_len.to_be_bytes());
+            canonical.extend_from_slice(&buf[header_len..]);
+            attributes.push(canonical);
+        }
+        attributes.sort_unstable_by(|a, b| a[1].cmp(&b[1]).then_with(|| a.cmp(b)));
+        let withdrawn_routes = Self::sorted_wire(&self.withdrawn_routes)?;
+        let nlri = Self::sorted_wire(&self.nlri)?;
+
+        let mut buf = Vec::with_capacity(self.len());
+        for section in [attributes, withdrawn_routes, nlri] {
+            buf.extend_from_slice(&(section.len() as u32).to_be_bytes());
+            for item in section {
+                buf.extend_from_slice(&item);
+            }
+        }
+        Ok(buf)
+    }
+
+    fn sorted_wire(
+        addresses: &[Ipv4UnicastAddress],
+    ) -> Result<Vec<Vec<u8>>, BgpUpdateMessageWritingError> {
+        let mut sorted = Vec::with_capacity(addresses.len());
+        for address in addresses {
+            let mut buf = Vec::with_capacity(address.len());
+            address.write(&mut buf)?;
+            sorted.push(buf);
+        }
+        sorted.sort_unstable();
+        Ok(sorted)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -320,4 +399,76 @@ mod tests {
             Some(AddressType::Ipv6Multicast)
         );
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_content_hash() {
+        use crate::path_attribute::{MultiExitDiscriminator, NextHop, Origin};
+        use core::net::Ipv4Addr;
+
+        let origin = |extended| {
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                extended,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap()
+        };
+        let next_hop = PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+        )
+        .unwrap();
+        let med = |metric| {
+            PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(metric)),
+            )
+            .unwrap()
+        };
+        let prefix_1 = Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        );
+        let prefix_2 = Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("203.0.113.0/24".parse().unwrap()).unwrap(),
+        );
+
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![origin(false), next_hop.clone(), med(100)],
+            vec![prefix_1, prefix_2],
+        );
+        let reordered = BgpUpdateMessage::new(
+            vec![],
+            vec![med(100), next_hop.clone(), origin(true)],
+            vec![prefix_2, prefix_1],
+        );
+        let changed = BgpUpdateMessage::new(
+            vec![],
+            vec![origin(false), next_hop, med(200)],
+            vec![prefix_1, prefix_2],
+        );
+
+        // The digest must be stable across releases, don't update these values
+        assert_eq!(update.content_hash(), Ok(12233542323053884368));
+        assert_eq!(
+            update.content_hash128(),
+            Ok(319337574276471353774043426446025017706)
+        );
+        assert_eq!(update.content_hash(), reordered.content_hash());
+        assert_eq!(update.content_hash128(), reordered.content_hash128());
+        assert_ne!(update.content_hash(), changed.content_hash());
+        assert_ne!(
+            BgpUpdateMessage::new(vec![prefix_1], vec![], vec![]).content_hash(),
+            BgpUpdateMessage::new(vec![], vec![], vec![prefix_1]).content_hash()
+        );
+    }
 }
@@ -13,13 +13,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::net::{Ipv4Addr, Ipv6Addr};
 use nom::{
     error::ErrorKind,
     number::complete::{be_u128, be_u16, be_u32, be_u8},
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::net::{Ipv4Addr, Ipv6Addr};
 
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, ErrorKindSerdeDeref, ReadablePdu,
@@ -24,11 +24,9 @@ pub mod path_attribute;
 pub mod route_refresh;
 pub mod update;
 
+use alloc::{vec, vec::Vec};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use ipnet::{Ipv4Net, Ipv6Net};
-use std::{
-    collections::HashMap,
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
-};
 
 use netgauze_iana::address_family::AddressType;
 use nom::{
@@ -44,6 +42,8 @@ use netgauze_parse_utils::{
 };
 
 use crate::{
+    capabilities::NegotiatedCapabilities,
+    collections::HashMap,
     iana::{BgpMessageType, UndefinedBgpMessageType},
     notification::{BgpNotificationMessage, FiniteStateMachineError, MessageHeaderError},
     wire::{
@@ -70,6 +70,10 @@ pub const BGP_MIN_MESSAGE_LENGTH: u16 = 19;
 /// [RFC8654 Extended Message Support for BGP](https://datatracker.ietf.org/doc/html/rfc8654)
 pub const BGP_MAX_MESSAGE_LENGTH: u16 = 4096;
 
+/// Max length of a BGP message when both peers support extended messages as
+/// defined in [RFC8654](https://datatracker.ietf.org/doc/html/rfc8654).
+pub const BGP_EXTENDED_MAX_MESSAGE_LENGTH: u16 = 65535;
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct BgpParsingIgnoredErrors {
     non_unicast_withdraw_nlri: Vec<Ipv4Net>,
@@ -166,6 +170,19 @@ impl BgpParsingContext {
         &mut self.add_path
     }
 
+    /// Update the AS number length, ADD-PATH, and multiple labels settings to
+    /// parse messages received from the peer of a session with the given
+    /// negotiated capabilities.
+    pub fn update_capabilities(&mut self, negotiated: &NegotiatedCapabilities) {
+        self.asn4 = negotiated.four_octet_as();
+        self.add_path = negotiated
+            .add_path()
+            .iter()
+            .map(|(address_type, family)| (*address_type, family.receive()))
+            .collect();
+        self.multiple_labels = negotiated.multiple_labels().clone();
+    }
+
     pub const fn fail_on_non_unicast_withdraw_nlri(&self) -> bool {
         self.fail_on_non_unicast_withdraw_nlri
     }
@@ -188,7 +205,7 @@ impl BgpParsingContext {
 
     // Move out existing parsing errors and replace it with a new empty instant
     pub fn reset_parsing_errors(&mut self) -> BgpParsingIgnoredErrors {
-        std::mem::take(&mut self.parsing_errors)
+        core::mem::take(&mut self.parsing_errors)
     }
 }
 
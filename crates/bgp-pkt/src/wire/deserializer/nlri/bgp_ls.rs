@@ -33,6 +33,7 @@ use crate::{
         Ipv6PrefixParsingError,
     },
 };
+use core::net::{Ipv4Addr, Ipv6Addr};
 use ipnet::IpNet;
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_till_empty_into_located,
@@ -46,7 +47,6 @@ use nom::{
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::net::{Ipv4Addr, Ipv6Addr};
 
 /// BGP Link-State NLRI Parsing Errors
 #[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
@@ -85,11 +85,11 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedBgpLsNlriParsingError<'a>> for
         } else {
             (buf, None)
         };
-        let (buf, nlri_type) = nom::combinator::map_res(be_u16, BgpLsNlriType::try_from)(buf)?;
+        let (buf, nlri_type) = be_u16(buf)?;
         let (buf, nlri_len) = be_u16(buf)?;
         let (buf, data) = nom::bytes::complete::take(nlri_len)(buf)?;
 
-        let (_, value) = BgpLsNlriValue::from_wire(data, nlri_type)?;
+        let (_, value) = parse_bgp_ls_nlri_value(data, nlri_type)?;
 
         Ok((buf, BgpLsNlri { path_id, value }))
     }
@@ -110,12 +110,12 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedBgpLsNlriParsingError<'a>> for
             (buf, None)
         };
 
-        let (buf, nlri_type) = nom::combinator::map_res(be_u16, BgpLsNlriType::try_from)(buf)?;
+        let (buf, nlri_type) = be_u16(buf)?;
         let (buf, nlri_len) = be_u16(buf)?;
         let (buf, data) = nom::bytes::complete::take(nlri_len)(buf)?;
 
         let (data, rd) = parse_into_located(data)?;
-        let (_, nlri) = BgpLsNlriValue::from_wire(data, nlri_type)?;
+        let (_, nlri) = parse_bgp_ls_nlri_value(data, nlri_type)?;
 
         Ok((
             buf,
@@ -128,6 +128,27 @@ impl<'a> ReadablePduWithOneInput<'a, bool, LocatedBgpLsNlriParsingError<'a>> for
     }
 }
 
+/// NLRI types that are not registered are kept as [`BgpLsNlriValue::Unknown`]
+/// to be propagated as is.
+fn parse_bgp_ls_nlri_value(
+    buf: Span<'_>,
+    nlri_type: u16,
+) -> IResult<Span<'_>, BgpLsNlriValue, LocatedBgpLsNlriParsingError<'_>> {
+    match BgpLsNlriType::try_from(nlri_type) {
+        Ok(nlri_type) => BgpLsNlriValue::from_wire(buf, nlri_type),
+        Err(UnknownBgpLsNlriType(code)) => {
+            let (buf, value) = nom::bytes::complete::take(buf.len())(buf)?;
+            Ok((
+                buf,
+                BgpLsNlriValue::Unknown {
+                    code,
+                    value: value.to_vec(),
+                },
+            ))
+        }
+    }
+}
+
 impl<'a> ReadablePduWithOneInput<'a, BgpLsNlriType, LocatedBgpLsNlriParsingError<'a>>
     for BgpLsNlriValue
 {
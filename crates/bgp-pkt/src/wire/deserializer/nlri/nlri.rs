@@ -24,6 +24,8 @@ use crate::{
         },
     },
 };
+use alloc::vec::Vec;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use ipnet::{Ipv4Net, Ipv6Net};
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_into_located_two_inputs,
@@ -37,7 +39,6 @@ use nom::{
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
 /// An IP Prefix route type for IPv4 has the Length field set to 34
 /// [RFC9136](https://datatracker.ietf.org/doc/html/rfc9136)
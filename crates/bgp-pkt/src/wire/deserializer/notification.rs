@@ -185,6 +185,12 @@ impl<'a> ReadablePdu<'a, LocatedOpenMessageErrorParsingError<'a>> for OpenMessag
                     value: (*value.fragment()).into(),
                 },
             )),
+            OpenMessageErrorSubCode::AuthenticationFailure => Ok((
+                buf,
+                OpenMessageError::AuthenticationFailure {
+                    value: (*value.fragment()).into(),
+                },
+            )),
             OpenMessageErrorSubCode::UnacceptableHoldTime => Ok((
                 buf,
                 OpenMessageError::UnacceptableHoldTime {
@@ -267,6 +273,12 @@ impl<'a> ReadablePdu<'a, LocatedUpdateMessageErrorParsingError<'a>> for UpdateMe
                     value: (*value.fragment()).into(),
                 },
             )),
+            UpdateMessageErrorSubCode::AsRoutingLoop => Ok((
+                buf,
+                Self::AsRoutingLoop {
+                    value: (*value.fragment()).into(),
+                },
+            )),
             UpdateMessageErrorSubCode::InvalidNextHopAttribute => Ok((
                 buf,
                 Self::InvalidNextHopAttribute {
@@ -21,6 +21,8 @@ use crate::{
     wire::deserializer::{capabilities::BgpCapabilityParsingError, BgpParsingContext},
     BgpOpenMessage,
 };
+use alloc::{vec, vec::Vec};
+use core::net::Ipv4Addr;
 use netgauze_parse_utils::{
     parse_into_located_one_input, ErrorKindSerdeDeref, LocatedParsingError, ReadablePdu,
     ReadablePduWithOneInput, Span,
@@ -32,7 +34,6 @@ use nom::{
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::net::Ipv4Addr;
 
 /// BGP Open Message Parsing errors
 #[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
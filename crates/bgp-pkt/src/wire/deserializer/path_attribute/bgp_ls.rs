@@ -24,6 +24,11 @@ use crate::{
         serializer::nlri::{IPV4_LEN, IPV6_LEN},
     },
 };
+use alloc::string::{FromUtf8Error, String, ToString};
+use core::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    ops::BitAnd,
+};
 use netgauze_parse_utils::{
     parse_into_located, parse_into_located_one_input, parse_till_empty_into_located,
     ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput, Span,
@@ -35,11 +40,6 @@ use nom::{
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::{
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
-    ops::BitAnd,
-    string::FromUtf8Error,
-};
 
 /// BGP Link-State Attribute Parsing Errors
 #[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
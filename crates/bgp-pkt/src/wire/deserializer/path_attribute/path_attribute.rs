@@ -17,6 +17,7 @@
 //! Deserializer for BGP Path Attributes
 
 use crate::{
+    collections::HashMap,
     iana::{
         AigpAttributeType, PathAttributeType, UndefinedAigpAttributeType,
         UndefinedPathAttributeType,
@@ -32,6 +33,7 @@ use crate::{
         ACCUMULATED_IGP_METRIC,
     },
 };
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use netgauze_iana::address_family::{
     AddressFamily, AddressType, SubsequentAddressFamily, UndefinedAddressFamily,
     UndefinedSubsequentAddressFamily,
@@ -50,14 +52,10 @@ use nom::{
     IResult,
 };
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::HashMap,
-    net::{IpAddr, Ipv4Addr, Ipv6Addr},
-};
 
-const OPTIONAL_PATH_ATTRIBUTE_MASK: u8 = 0x80;
-const TRANSITIVE_PATH_ATTRIBUTE_MASK: u8 = 0x40;
-const PARTIAL_PATH_ATTRIBUTE_MASK: u8 = 0x20;
+pub(crate) const OPTIONAL_PATH_ATTRIBUTE_MASK: u8 = 0x80;
+pub(crate) const TRANSITIVE_PATH_ATTRIBUTE_MASK: u8 = 0x40;
+pub(crate) const PARTIAL_PATH_ATTRIBUTE_MASK: u8 = 0x20;
 pub(crate) const EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK: u8 = 0x10;
 const ORIGIN_LEN: u16 = 1;
 const NEXT_HOP_LEN: u16 = 4;
@@ -172,6 +170,11 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedPathAttribut
                 let value = PathAttributeValue::Aggregator(value);
                 (buf, value)
             }
+            Ok(PathAttributeType::As4Aggregator) => {
+                let (buf, value) = parse_into_located_one_input(buf, extended_length)?;
+                let value = PathAttributeValue::As4Aggregator(value);
+                (buf, value)
+            }
             Ok(PathAttributeType::Communities) => {
                 let (buf, value) = parse_into_located_one_input(buf, extended_length)?;
                 let value = PathAttributeValue::Communities(value);
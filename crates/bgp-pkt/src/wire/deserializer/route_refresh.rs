@@ -19,6 +19,7 @@ use crate::{
     iana::{RouteRefreshSubcode, UndefinedRouteRefreshSubcode},
     BgpRouteRefreshMessage,
 };
+use alloc::vec;
 use netgauze_iana::address_family::{
     AddressFamily, AddressType, InvalidAddressType, SubsequentAddressFamily,
     UndefinedAddressFamily, UndefinedSubsequentAddressFamily,
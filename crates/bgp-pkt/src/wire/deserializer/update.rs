@@ -16,19 +16,21 @@
 //! Deserializer for BGP Update message
 
 use crate::{
+    iana::{PathAttributeType, UndefinedPathAttributeType},
     wire::deserializer::path_attribute::{
         LocatedPathAttributeParsingError, PathAttributeParsingError,
     },
     BgpUpdateMessage,
 };
+use alloc::{vec, vec::Vec};
 use ipnet::Ipv4Net;
 use netgauze_iana::address_family::AddressType;
 use netgauze_parse_utils::{
-    parse_into_located, LocatedParsingError, ReadablePduWithOneInput, Span,
+    parse_into_located, LocatedParsingError, ReadablePdu, ReadablePduWithOneInput, Span,
 };
 use nom::{
     number::complete::{be_u16, be_u32, be_u8},
-    IResult,
+    IResult, Slice,
 };
 use serde::{Deserialize, Serialize};
 
@@ -37,7 +39,10 @@ use crate::{
     notification::UpdateMessageError,
     path_attribute::PathAttribute,
     wire::deserializer::{
-        path_attribute::{OriginParsingError, EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK},
+        path_attribute::{
+            OriginParsingError, EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK, OPTIONAL_PATH_ATTRIBUTE_MASK,
+            PARTIAL_PATH_ATTRIBUTE_MASK, TRANSITIVE_PATH_ATTRIBUTE_MASK,
+        },
         BgpParsingContext, Ipv4PrefixParsingError,
     },
 };
@@ -296,6 +301,198 @@ fn handle_path_error<'a>(
     Ok((buf, ()))
 }
 
+/// A path attribute whose boundaries are known, but its value is decoded only
+/// on demand. See [`LazyBgpUpdateMessage`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LazyPathAttribute<'a> {
+    flags: u8,
+    code: u8,
+    /// The full attribute on the wire, including the flags, type code, and
+    /// length fields
+    span: Span<'a>,
+}
+
+impl<'a> LazyPathAttribute<'a> {
+    pub const fn optional(&self) -> bool {
+        self.flags & OPTIONAL_PATH_ATTRIBUTE_MASK == OPTIONAL_PATH_ATTRIBUTE_MASK
+    }
+
+    pub const fn transitive(&self) -> bool {
+        self.flags & TRANSITIVE_PATH_ATTRIBUTE_MASK == TRANSITIVE_PATH_ATTRIBUTE_MASK
+    }
+
+    pub const fn partial(&self) -> bool {
+        self.flags & PARTIAL_PATH_ATTRIBUTE_MASK == PARTIAL_PATH_ATTRIBUTE_MASK
+    }
+
+    pub const fn extended_length(&self) -> bool {
+        self.flags & EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK == EXTENDED_LENGTH_PATH_ATTRIBUTE_MASK
+    }
+
+    /// Raw attribute type code as seen on the wire
+    pub const fn code(&self) -> u8 {
+        self.code
+    }
+
+    pub fn path_attribute_type(&self) -> Result<PathAttributeType, UndefinedPathAttributeType> {
+        PathAttributeType::try_from(self.code)
+    }
+
+    /// The full attribute as seen on the wire, including the flags, type code,
+    /// and length fields
+    pub const fn span(&self) -> Span<'a> {
+        self.span
+    }
+
+    /// Only the attribute value, without the flags, type code, and length
+    pub fn value_bytes(&self) -> &'a [u8] {
+        let header_len = if self.extended_length() { 4 } else { 3 };
+        &self.span.fragment()[header_len..]
+    }
+
+    /// Decode the attribute value, the parsing context must be the same one
+    /// (or equivalent to) the one used to index the update message.
+    pub fn decode(
+        &self,
+        ctx: &mut BgpParsingContext,
+    ) -> Result<PathAttribute, LocatedPathAttributeParsingError<'a>> {
+        match PathAttribute::from_wire(self.span, ctx) {
+            Ok((_, attr)) => Ok(attr),
+            Err(nom::Err::Error(err)) | Err(nom::Err::Failure(err)) => Err(err),
+            Err(nom::Err::Incomplete(_)) => Err(LocatedPathAttributeParsingError::new(
+                self.span,
+                PathAttributeParsingError::NomError(nom::error::ErrorKind::Eof),
+            )),
+        }
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedBgpUpdateMessageParsingError<'a>> for LazyPathAttribute<'a> {
+    fn from_wire(
+        buf: Span<'a>,
+    ) -> IResult<Span<'a>, Self, LocatedBgpUpdateMessageParsingError<'a>> {
+        let attr_begin = buf;
+        let (buf, _) = advance_attr_buffer(buf)?;
+        let attr_len = attr_begin.len() - buf.len();
+        Ok((
+            buf,
+            LazyPathAttribute {
+                flags: attr_begin[0],
+                code: attr_begin[1],
+                span: attr_begin.slice(..attr_len),
+            },
+        ))
+    }
+}
+
+/// A variant of [`BgpUpdateMessage`] that only indexes the path attribute
+/// boundaries when parsed, and decodes the attribute value when it's
+/// accessed. Useful for pipelines that drop most of the updates after looking
+/// at a handful of attributes (e.g., the NLRI or communities).
+///
+/// The withdrawn routes and the NLRI fields are decoded eagerly, since they're
+/// often needed to make a decision about the message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LazyBgpUpdateMessage<'a> {
+    withdrawn_routes: Vec<Ipv4UnicastAddress>,
+    path_attributes: Vec<LazyPathAttribute<'a>>,
+    nlri: Vec<Ipv4UnicastAddress>,
+}
+
+impl<'a> LazyBgpUpdateMessage<'a> {
+    pub const fn withdraw_routes(&self) -> &Vec<Ipv4UnicastAddress> {
+        &self.withdrawn_routes
+    }
+
+    pub const fn path_attributes(&self) -> &Vec<LazyPathAttribute<'a>> {
+        &self.path_attributes
+    }
+
+    #[inline]
+    pub const fn nlri(&self) -> &Vec<Ipv4UnicastAddress> {
+        &self.nlri
+    }
+
+    /// Get the first path attribute of the given type without decoding it
+    pub fn get(&self, attr_type: PathAttributeType) -> Option<&LazyPathAttribute<'a>> {
+        let code: u8 = attr_type.into();
+        self.path_attributes.iter().find(|attr| attr.code == code)
+    }
+
+    /// Find and decode the first path attribute of the given type
+    pub fn decode_attribute(
+        &self,
+        attr_type: PathAttributeType,
+        ctx: &mut BgpParsingContext,
+    ) -> Option<Result<PathAttribute, LocatedPathAttributeParsingError<'a>>> {
+        self.get(attr_type).map(|attr| attr.decode(ctx))
+    }
+
+    /// Decode all the path attributes and convert into a [`BgpUpdateMessage`].
+    ///
+    /// Malformed attributes are handled as in the eager parser: if
+    /// [`BgpParsingContext::fail_on_malformed_path_attr`] is set, the first
+    /// error is returned, otherwise the attribute is dropped and the error is
+    /// recorded in [`BgpParsingContext::parsing_errors`].
+    pub fn decode(
+        &self,
+        ctx: &mut BgpParsingContext,
+    ) -> Result<BgpUpdateMessage, LocatedPathAttributeParsingError<'a>> {
+        let mut path_attributes = Vec::with_capacity(self.path_attributes.len());
+        for attr in &self.path_attributes {
+            match attr.decode(ctx) {
+                Ok(value) => path_attributes.push(value),
+                Err(err) => {
+                    if ctx.fail_on_malformed_path_attr {
+                        return Err(err);
+                    }
+                    ctx.parsing_errors
+                        .path_attr_errors
+                        .push(err.error().clone());
+                }
+            }
+        }
+        Ok(BgpUpdateMessage::new(
+            self.withdrawn_routes.clone(),
+            path_attributes,
+            self.nlri.clone(),
+        ))
+    }
+}
+
+impl<'a>
+    ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedBgpUpdateMessageParsingError<'a>>
+    for LazyBgpUpdateMessage<'a>
+{
+    fn from_wire(
+        buf: Span<'a>,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedBgpUpdateMessageParsingError<'a>> {
+        let add_path = ctx
+            .add_path
+            .get(&AddressType::Ipv4Unicast)
+            .is_some_and(|x| *x);
+        let (buf, withdrawn_buf) = nom::multi::length_data(be_u16)(buf)?;
+        let (_, withdrawn_routes) = parse_nlri(withdrawn_buf, add_path, false, ctx)?;
+        let (buf, mut path_attributes_buf) = nom::multi::length_data(be_u16)(buf)?;
+        let mut path_attributes = Vec::new();
+        while !path_attributes_buf.is_empty() {
+            let (tmp, attr) = LazyPathAttribute::from_wire(path_attributes_buf)?;
+            path_attributes.push(attr);
+            path_attributes_buf = tmp;
+        }
+        let (buf, nlri) = parse_nlri(buf, add_path, true, ctx)?;
+        Ok((
+            buf,
+            LazyBgpUpdateMessage {
+                withdrawn_routes,
+                path_attributes,
+                nlri,
+            },
+        ))
+    }
+}
+
 impl From<BgpUpdateMessageParsingError> for UpdateMessageError {
     fn from(value: BgpUpdateMessageParsingError) -> Self {
         // For EoF errors we follow: RFC 4271 Error checking of an UPDATE message begins
@@ -22,10 +22,11 @@ use crate::{
         ROUTE_REFRESH_CAPABILITY_LENGTH,
     },
 };
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
-use std::io::Write;
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum BGPCapabilityWritingError {
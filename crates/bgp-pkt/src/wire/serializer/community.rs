@@ -23,7 +23,9 @@ use crate::{
     },
     wire::serializer::nlri::MacAddressWritingError,
 };
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
@@ -40,7 +42,7 @@ impl WritablePdu<CommunityWritingError> for Community {
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), CommunityWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CommunityWritingError> {
         writer.write_u32::<NetworkEndian>(self.value())?;
         Ok(())
     }
@@ -92,10 +94,7 @@ impl WritablePdu<ExtendedCommunityWritingError> for ExtendedCommunity {
             }
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), ExtendedCommunityWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), ExtendedCommunityWritingError> {
         match self {
             ExtendedCommunity::TransitiveTwoOctet(value) => {
                 writer.write_u8(BgpExtendedCommunityType::TransitiveTwoOctet as u8)?;
@@ -166,10 +165,7 @@ impl WritablePdu<ExtendedCommunityIpv6WritingError> for ExtendedCommunityIpv6 {
             }
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), ExtendedCommunityIpv6WritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), ExtendedCommunityIpv6WritingError> {
         match self {
             Self::TransitiveIpv6(value) => {
                 writer.write_u8(BgpExtendedCommunityIpv6Type::TransitiveIpv6 as u8)?;
@@ -202,7 +198,7 @@ impl WritablePdu<LargeCommunityWritingError> for LargeCommunity {
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), LargeCommunityWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), LargeCommunityWritingError> {
         writer.write_u32::<NetworkEndian>(self.global_admin())?;
         writer.write_u32::<NetworkEndian>(self.local_data1())?;
         writer.write_u32::<NetworkEndian>(self.local_data2())?;
@@ -225,7 +221,7 @@ impl WritablePdu<TransitiveTwoOctetExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), TransitiveTwoOctetExtendedCommunityWritingError> {
@@ -338,7 +334,7 @@ impl WritablePdu<NonTransitiveTwoOctetExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), NonTransitiveTwoOctetExtendedCommunityWritingError> {
@@ -385,7 +381,7 @@ impl WritablePdu<TransitiveIpv4ExtendedCommunityWritingError> for TransitiveIpv4
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), TransitiveIpv4ExtendedCommunityWritingError> {
@@ -538,7 +534,7 @@ impl WritablePdu<NonTransitiveIpv4ExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), NonTransitiveIpv4ExtendedCommunityWritingError> {
@@ -571,7 +567,7 @@ impl WritablePdu<TransitiveFourOctetExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), TransitiveFourOctetExtendedCommunityWritingError> {
@@ -668,7 +664,7 @@ impl WritablePdu<NonTransitiveFourOctetExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), NonTransitiveFourOctetExtendedCommunityWritingError> {
@@ -701,7 +697,7 @@ impl WritablePdu<TransitiveOpaqueExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), TransitiveOpaqueExtendedCommunityWritingError> {
@@ -735,7 +731,7 @@ impl WritablePdu<NonTransitiveOpaqueExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), NonTransitiveOpaqueExtendedCommunityWritingError> {
@@ -762,7 +758,7 @@ impl WritablePdu<ExperimentalExtendedCommunityWritingError> for ExperimentalExte
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), ExperimentalExtendedCommunityWritingError> {
@@ -785,10 +781,7 @@ impl WritablePdu<UnknownExtendedCommunityWritingError> for UnknownExtendedCommun
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), UnknownExtendedCommunityWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), UnknownExtendedCommunityWritingError> {
         writer.write_u8(self.sub_type())?;
         writer.write_all(self.value())?;
         Ok(())
@@ -808,7 +801,7 @@ impl WritablePdu<TransitiveIpv6ExtendedCommunityWritingError> for TransitiveIpv6
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), TransitiveIpv6ExtendedCommunityWritingError> {
@@ -913,7 +906,7 @@ impl WritablePdu<NonTransitiveIpv6ExtendedCommunityWritingError>
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), NonTransitiveIpv6ExtendedCommunityWritingError> {
@@ -944,7 +937,7 @@ impl WritablePdu<UnknownExtendedCommunityIpv6WritingError> for UnknownExtendedCo
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
     ) -> Result<(), UnknownExtendedCommunityIpv6WritingError> {
@@ -968,10 +961,7 @@ impl WritablePdu<EvpnExtendedCommunityWritingError> for EvpnExtendedCommunity {
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), EvpnExtendedCommunityWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), EvpnExtendedCommunityWritingError> {
         match self {
             Self::MacMobility { flags, seq_no } => {
                 writer.write_u8(EvpnExtendedCommunitySubType::MacMobility as u8)?;
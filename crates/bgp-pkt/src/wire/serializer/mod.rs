@@ -24,9 +24,11 @@ pub mod path_attribute;
 pub mod route_refresh;
 pub mod update;
 
-use byteorder::{NetworkEndian, WriteBytesExt};
-use std::{io::Write, net::IpAddr};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use core::net::IpAddr;
 
+use netgauze_parse_utils::io::{self, Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
@@ -48,7 +50,7 @@ use crate::{
 /// Helper method to round up the number of bytes based on a given length
 #[inline]
 pub(crate) fn round_len(len: u8) -> u8 {
-    (len as f32 / 8.0).ceil() as u8
+    len.div_ceil(8)
 }
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
@@ -83,7 +85,7 @@ impl WritablePdu<BgpMessageWritingError> for BgpMessage {
         Self::BASE_LENGTH + body_len
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), BgpMessageWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BgpMessageWritingError> {
         let len = self.len();
         match self {
             Self::Open(_) | Self::KeepAlive => {
@@ -136,7 +138,7 @@ impl WritablePdu<IpAddrWritingError> for IpAddr {
             } as usize
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), IpAddrWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), IpAddrWritingError> {
         match self {
             IpAddr::V4(value) => {
                 writer.write_u8(IPV4_LEN)?;
@@ -171,7 +173,7 @@ fn write_tlv_header<T: Write>(
     writer: &mut T,
     tlv_type: u16,
     tlv_length: u16,
-) -> Result<(), std::io::Error> {
+) -> Result<(), io::Error> {
     // do not account for the tlv type u16 and tlv length u16
     let effective_length = tlv_length - 4;
 
@@ -25,10 +25,12 @@ use crate::{
         nlri::nlri::RouteDistinguisherWritingError, write_tlv_header, MultiTopologyIdWritingError,
     },
 };
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use core::net::IpAddr;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
 use netgauze_serde_macros::WritingError;
-use std::{io::Write, net::IpAddr};
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum BgpLsNlriWritingError {
@@ -14,10 +14,12 @@
 // limitations under the License.
 
 use crate::{nlri::*, wire::serializer::round_len};
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use core::net::IpAddr;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
-use std::{io::Write, net::IpAddr};
 
 /// Length for Route Distinguisher
 pub(crate) const RD_LEN: u8 = 8;
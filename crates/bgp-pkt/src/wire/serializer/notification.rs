@@ -25,7 +25,8 @@ use crate::{
     },
     BgpNotificationMessage,
 };
-use byteorder::WriteBytesExt;
+use alloc::string::String;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
@@ -58,10 +59,7 @@ impl WritablePdu<BgpNotificationMessageWritingError> for BgpNotificationMessage
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), BgpNotificationMessageWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BgpNotificationMessageWritingError> {
         match self {
             Self::MessageHeaderError(value) => {
                 writer.write_u8(BgpErrorNotificationCode::MessageHeaderError.into())?;
@@ -115,10 +113,7 @@ impl WritablePdu<MessageHeaderErrorWritingError> for MessageHeaderError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), MessageHeaderErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), MessageHeaderErrorWritingError> {
         match self {
             Self::Unspecific { value } => {
                 writer.write_u8(MessageHeaderErrorSubCode::Unspecific.into())?;
@@ -157,6 +152,7 @@ impl WritablePdu<OpenMessageErrorWritingError> for OpenMessageError {
             Self::BadPeerAs { value } => value.len(),
             Self::BadBgpIdentifier { value } => value.len(),
             Self::UnsupportedOptionalParameter { value } => value.len(),
+            Self::AuthenticationFailure { value } => value.len(),
             Self::UnacceptableHoldTime { value } => value.len(),
             Self::UnsupportedCapability { value } => value.len(),
             Self::RoleMismatch { value } => value.len(),
@@ -164,7 +160,7 @@ impl WritablePdu<OpenMessageErrorWritingError> for OpenMessageError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), OpenMessageErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), OpenMessageErrorWritingError> {
         match self {
             OpenMessageError::Unspecific { value } => {
                 writer.write_u8(OpenMessageErrorSubCode::Unspecific.into())?;
@@ -186,6 +182,10 @@ impl WritablePdu<OpenMessageErrorWritingError> for OpenMessageError {
                 writer.write_u8(OpenMessageErrorSubCode::UnsupportedOptionalParameter.into())?;
                 writer.write_all(value)?;
             }
+            OpenMessageError::AuthenticationFailure { value } => {
+                writer.write_u8(OpenMessageErrorSubCode::AuthenticationFailure.into())?;
+                writer.write_all(value)?;
+            }
             OpenMessageError::UnacceptableHoldTime { value } => {
                 writer.write_u8(OpenMessageErrorSubCode::UnacceptableHoldTime.into())?;
                 writer.write_all(value)?;
@@ -221,6 +221,7 @@ impl WritablePdu<UpdateMessageErrorWritingError> for UpdateMessageError {
             Self::AttributeFlagsError { value } => value.len(),
             Self::AttributeLengthError { value } => value.len(),
             Self::InvalidOriginAttribute { value } => value.len(),
+            Self::AsRoutingLoop { value } => value.len(),
             Self::InvalidNextHopAttribute { value } => value.len(),
             Self::OptionalAttributeError { value } => value.len(),
             Self::InvalidNetworkField { value } => value.len(),
@@ -229,10 +230,7 @@ impl WritablePdu<UpdateMessageErrorWritingError> for UpdateMessageError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), UpdateMessageErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), UpdateMessageErrorWritingError> {
         match self {
             Self::Unspecific { value } => {
                 writer.write_u8(UpdateMessageErrorSubCode::Unspecific.into())?;
@@ -263,6 +261,10 @@ impl WritablePdu<UpdateMessageErrorWritingError> for UpdateMessageError {
                 writer.write_u8(UpdateMessageErrorSubCode::InvalidOriginAttribute.into())?;
                 writer.write_all(value)?;
             }
+            Self::AsRoutingLoop { value } => {
+                writer.write_u8(UpdateMessageErrorSubCode::AsRoutingLoop.into())?;
+                writer.write_all(value)?;
+            }
             Self::InvalidNextHopAttribute { value } => {
                 writer.write_u8(UpdateMessageErrorSubCode::InvalidNextHopAttribute.into())?;
                 writer.write_all(value)?;
@@ -300,10 +302,7 @@ impl WritablePdu<HoldTimerExpiredErrorWritingError> for HoldTimerExpiredError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), HoldTimerExpiredErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), HoldTimerExpiredErrorWritingError> {
         match self {
             Self::Unspecific { sub_code, value } => {
                 writer.write_u8(*sub_code)?;
@@ -333,10 +332,7 @@ impl WritablePdu<FiniteStateMachineErrorWritingError> for FiniteStateMachineErro
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), FiniteStateMachineErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FiniteStateMachineErrorWritingError> {
         match self {
             Self::Unspecific { value } => {
                 writer.write_u8(FiniteStateMachineErrorSubCode::UnspecifiedError.into())?;
@@ -392,7 +388,7 @@ impl WritablePdu<CeaseErrorWritingError> for CeaseError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), CeaseErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CeaseErrorWritingError> {
         match self {
             Self::MaximumNumberOfPrefixesReached { value } => {
                 writer.write_u8(CeaseErrorSubCode::MaximumNumberOfPrefixesReached.into())?;
@@ -455,10 +451,7 @@ impl WritablePdu<RouteRefreshErrorWritingError> for RouteRefreshError {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), RouteRefreshErrorWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), RouteRefreshErrorWritingError> {
         match self {
             Self::InvalidMessageLength { value } => {
                 writer.write_u8(RouteRefreshMessageErrorSubCode::InvalidMessageLength.into())?;
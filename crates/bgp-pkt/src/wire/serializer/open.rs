@@ -13,10 +13,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::io::Write;
-
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
 
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
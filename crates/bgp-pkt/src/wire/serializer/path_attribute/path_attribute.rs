@@ -26,10 +26,12 @@ use crate::{
         ACCUMULATED_IGP_METRIC,
     },
 };
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use core::net::IpAddr;
+use netgauze_parse_utils::io::{self, Write, WriteBytesExt};
 use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
 use netgauze_serde_macros::WritingError;
-use std::net::IpAddr;
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum PathAttributeWritingError {
@@ -68,6 +70,7 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
             PathAttributeValue::LocalPreference(value) => value.len(self.extended_length()),
             PathAttributeValue::AtomicAggregate(value) => value.len(self.extended_length()),
             PathAttributeValue::Aggregator(value) => value.len(self.extended_length()),
+            PathAttributeValue::As4Aggregator(value) => value.len(self.extended_length()),
             PathAttributeValue::Communities(value) => value.len(self.extended_length()),
             PathAttributeValue::ExtendedCommunities(value) => value.len(self.extended_length()),
             PathAttributeValue::ExtendedCommunitiesIpv6(value) => value.len(self.extended_length()),
@@ -84,7 +87,7 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
         Self::BASE_LENGTH + value_len
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), PathAttributeWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), PathAttributeWritingError> {
         let mut attributes = 0x00u8;
         if self.optional() {
             attributes |= 0b10000000;
@@ -127,6 +130,9 @@ impl WritablePdu<PathAttributeWritingError> for PathAttribute {
             PathAttributeValue::Aggregator(value) => {
                 value.write(writer, self.extended_length())?;
             }
+            PathAttributeValue::As4Aggregator(value) => {
+                value.write(writer, self.extended_length())?;
+            }
             PathAttributeValue::Communities(value) => {
                 value.write(writer, self.extended_length())?;
             }
@@ -185,7 +191,7 @@ impl WritablePduWithOneInput<bool, OriginWritingError> for Origin {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -210,7 +216,7 @@ impl WritablePdu<AsPathWritingError> for As2PathSegment {
         Self::BASE_LENGTH + (self.as_numbers().len() * 2)
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
         writer.write_u8(self.segment_type() as u8)?;
         writer.write_u8(self.as_numbers().len() as u8)?;
         for as_num in self.as_numbers() {
@@ -229,7 +235,7 @@ impl WritablePdu<AsPathWritingError> for As4PathSegment {
         Self::BASE_LENGTH + (self.as_numbers().len() * 4)
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), AsPathWritingError> {
         writer.write_u8(self.segment_type() as u8)?;
         writer.write_u8(self.as_numbers().len() as u8)?;
         for as_num in self.as_numbers() {
@@ -256,7 +262,7 @@ impl WritablePduWithOneInput<bool, AsPathWritingError> for AsPath {
         base + segment_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -291,7 +297,7 @@ impl WritablePduWithOneInput<bool, AsPathWritingError> for As4Path {
         base + segment_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -321,7 +327,7 @@ impl WritablePduWithOneInput<bool, NextHopWritingError> for NextHop {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -349,7 +355,7 @@ impl WritablePduWithOneInput<bool, MultiExitDiscriminatorWritingError> for Multi
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -377,7 +383,7 @@ impl WritablePduWithOneInput<bool, LocalPreferenceWritingError> for LocalPrefere
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -405,7 +411,7 @@ impl WritablePduWithOneInput<bool, AtomicAggregateWritingError> for AtomicAggreg
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -428,7 +434,7 @@ impl WritablePduWithOneInput<bool, AggregatorWritingError> for As2Aggregator {
         Self::BASE_LENGTH + usize::from(extended_length)
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -448,7 +454,7 @@ impl WritablePduWithOneInput<bool, AggregatorWritingError> for As4Aggregator {
         Self::BASE_LENGTH + usize::from(extended_length)
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -470,7 +476,7 @@ impl WritablePduWithOneInput<bool, AggregatorWritingError> for Aggregator {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -499,7 +505,7 @@ impl WritablePduWithOneInput<bool, OriginatorWritingError> for Originator {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -523,7 +529,7 @@ impl WritablePdu<ClusterIdWritingError> for ClusterId {
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), ClusterIdWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), ClusterIdWritingError> {
         writer.write_all(&self.id().octets())?;
         Ok(())
     }
@@ -547,7 +553,7 @@ impl WritablePduWithOneInput<bool, ClusterListWritingError> for ClusterList {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -573,7 +579,7 @@ impl WritablePduWithOneInput<bool, UnknownAttributeWritingError> for UnknownAttr
         Self::BASE_LENGTH + self.value().len() + usize::from(extended_length)
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -606,7 +612,7 @@ impl WritablePduWithOneInput<bool, CommunitiesWritingError> for Communities {
         base + value_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -635,7 +641,7 @@ impl WritablePduWithOneInput<bool, ExtendedCommunitiesWritingError> for Extended
         base + value_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -666,7 +672,7 @@ impl WritablePduWithOneInput<bool, ExtendedCommunitiesIpv6WritingError>
         base + value_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -695,7 +701,7 @@ impl WritablePduWithOneInput<bool, LargeCommunitiesWritingError> for LargeCommun
         base + value_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -878,7 +884,7 @@ impl WritablePduWithOneInput<bool, MpReachWritingError> for MpReach {
         Self::BASE_LENGTH + usize::from(extended_length) + payload_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -1175,7 +1181,7 @@ impl WritablePduWithOneInput<bool, MpUnreachWritingError> for MpUnreach {
         Self::BASE_LENGTH + usize::from(extended_length) + payload_len
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -1297,7 +1303,7 @@ impl WritablePduWithOneInput<bool, OnlyToCustomerWritingError> for OnlyToCustome
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -1328,7 +1334,7 @@ impl WritablePduWithOneInput<bool, AigpWritingError> for Aigp {
         }
     }
 
-    fn write<T: std::io::Write>(
+    fn write<T: Write>(
         &self,
         writer: &mut T,
         extended_length: bool,
@@ -1347,13 +1353,13 @@ impl WritablePduWithOneInput<bool, AigpWritingError> for Aigp {
 
 // TODO restore original visibility
 #[inline]
-pub(crate) fn write_length<T: Sized + WritablePduWithOneInput<bool, E>, E, W: std::io::Write>(
+pub(crate) fn write_length<T: Sized + WritablePduWithOneInput<bool, E>, E, W: Write>(
     attribute: &T,
     extended_length: bool,
     writer: &mut W,
 ) -> Result<(), E>
 where
-    E: From<std::io::Error>,
+    E: From<io::Error>,
 {
     let len = attribute.len(extended_length) - 1;
     if extended_length || len > u8::MAX.into() {
@@ -16,7 +16,9 @@
 //! Serializer for BGP Route Refresh message
 
 use crate::BgpRouteRefreshMessage;
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
@@ -33,10 +35,7 @@ impl WritablePdu<BgpRouteRefreshMessageWritingError> for BgpRouteRefreshMessage
         Self::BASE_LENGTH
     }
 
-    fn write<T: std::io::Write>(
-        &self,
-        writer: &mut T,
-    ) -> Result<(), BgpRouteRefreshMessageWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BgpRouteRefreshMessageWritingError> {
         writer.write_u16::<NetworkEndian>(self.address_type().address_family().into())?;
         writer.write_u8(self.operation_type().into())?;
         writer.write_u8(self.address_type().subsequent_address_family().into())?;
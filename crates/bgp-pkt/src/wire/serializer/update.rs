@@ -21,7 +21,9 @@ use crate::{
     },
     BgpUpdateMessage,
 };
-use byteorder::{NetworkEndian, WriteBytesExt};
+use alloc::string::String;
+use byteorder::NetworkEndian;
+use netgauze_parse_utils::io::{Write, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
 
@@ -50,7 +52,7 @@ impl WritablePdu<BgpUpdateMessageWritingError> for BgpUpdateMessage {
         Self::BASE_LENGTH + withdrawn_len + path_attrs_len + nlri
     }
 
-    fn write<T: std::io::Write>(&self, writer: &mut T) -> Result<(), BgpUpdateMessageWritingError> {
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BgpUpdateMessageWritingError> {
         let withdrawn_len = self
             .withdraw_routes()
             .iter()
@@ -437,3 +437,84 @@ pub fn test_bgp_ls_sid() -> Result<(), BgpLsAttributeWritingError> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_bgp_ls_unknown_tlvs() -> Result<(), BgpLsNlriWritingError> {
+    let good_link_wire = [
+        0, 2, 0, 48, 1, 0, 0, 0, 0, 0, 0, 0, 69, 1, 0, 0, 15, 2, 2, 0, 4, 0, 0, 0, 18, 2, 88, 0, 3,
+        1, 2, 3, 1, 1, 0, 8, 2, 2, 0, 4, 0, 0, 0, 21, 1, 144, 0, 4, 1, 2, 3, 4,
+    ];
+    let good_unknown_nlri_wire = [0, 99, 0, 3, 1, 2, 3];
+
+    let good_link = BgpLsNlri {
+        path_id: None,
+        value: BgpLsNlriValue::Link(BgpLsNlriLink {
+            protocol_id: BgpLsProtocolId::IsIsLevel1,
+            identifier: 69,
+            local_node_descriptors: BgpLsLocalNodeDescriptors(BgpLsNodeDescriptors(vec![
+                BgpLsNodeDescriptorSubTlv::OspfAreaId(18),
+                BgpLsNodeDescriptorSubTlv::Unknown {
+                    code: 600,
+                    value: vec![1, 2, 3],
+                },
+            ])),
+            remote_node_descriptors: BgpLsRemoteNodeDescriptors(BgpLsNodeDescriptors(vec![
+                BgpLsNodeDescriptorSubTlv::OspfAreaId(21),
+            ])),
+            link_descriptors: vec![BgpLsLinkDescriptor::Unknown {
+                code: 400,
+                value: vec![1, 2, 3, 4],
+            }],
+        }),
+    };
+    let good_unknown_nlri = BgpLsNlri {
+        path_id: None,
+        value: BgpLsNlriValue::Unknown {
+            code: 99,
+            value: vec![1, 2, 3],
+        },
+    };
+
+    test_parsed_completely_with_one_input(&good_link_wire, false, &good_link);
+    test_parsed_completely_with_one_input(&good_unknown_nlri_wire, false, &good_unknown_nlri);
+    test_write(&good_link, &good_link_wire)?;
+    test_write(&good_unknown_nlri, &good_unknown_nlri_wire)?;
+    assert_eq!(
+        good_link.nlri().unknown_tlvs(),
+        vec![(600, [1, 2, 3].as_slice()), (400, [1, 2, 3, 4].as_slice())]
+    );
+    assert_eq!(
+        good_unknown_nlri.nlri().unknown_tlvs(),
+        vec![(99, [1, 2, 3].as_slice())]
+    );
+    Ok(())
+}
+
+#[test]
+pub fn test_bgp_ls_attr_unknown_tlvs() -> Result<(), BgpLsAttributeWritingError> {
+    let good_wire = [
+        19, 4, 74, 0, 4, 76, 105, 110, 107, 4, 255, 0, 3, 1, 2, 3, 4, 254, 0, 0,
+    ];
+
+    let good = BgpLsAttribute {
+        attributes: vec![
+            BgpLsAttributeValue::LinkName("Link".to_string()),
+            BgpLsAttributeValue::Unknown {
+                code: 1279,
+                value: vec![1, 2, 3],
+            },
+            BgpLsAttributeValue::Unknown {
+                code: 1278,
+                value: vec![],
+            },
+        ],
+    };
+
+    test_parsed_completely_with_one_input(&good_wire, false, &good);
+    test_write_with_one_input(&good, false, &good_wire)?;
+    assert_eq!(
+        good.unknown_tlvs().collect::<Vec<_>>(),
+        vec![(1279, [1, 2, 3].as_slice()), (1278, [].as_slice())]
+    );
+    Ok(())
+}
@@ -394,3 +394,31 @@ fn test_experimental_capabilities() -> Result<(), BGPCapabilityWritingError> {
     }
     Ok(())
 }
+
+#[test]
+fn test_parsing_context_update_capabilities() {
+    let local = vec![
+        BgpCapability::FourOctetAs(FourOctetAsCapability::new(65000)),
+        BgpCapability::AddPath(AddPathCapability::new(vec![AddPathAddressFamily::new(
+            AddressType::Ipv4Unicast,
+            true,
+            true,
+        )])),
+    ];
+    let remote = vec![
+        BgpCapability::FourOctetAs(FourOctetAsCapability::new(65001)),
+        BgpCapability::AddPath(AddPathCapability::new(vec![AddPathAddressFamily::new(
+            AddressType::Ipv4Unicast,
+            true,
+            false,
+        )])),
+    ];
+    let mut ctx = crate::wire::deserializer::BgpParsingContext::asn2_default();
+    ctx.update_capabilities(&negotiate(&local, &remote));
+    assert!(ctx.asn4());
+    assert_eq!(
+        ctx.add_path(),
+        &std::collections::HashMap::from([(AddressType::Ipv4Unicast, true)])
+    );
+    assert!(ctx.multiple_labels().is_empty());
+}
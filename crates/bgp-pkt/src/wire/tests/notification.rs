@@ -141,6 +141,7 @@ fn test_open_message_error() -> Result<(), OpenMessageErrorWritingError> {
     let good_peer_wire = [0x02, 0x01, 0x01];
     let good_bgp_id_wire = [0x03, 0x02, 0x02];
     let good_optional_wire = [0x04, 0x03, 0x03];
+    let good_authentication_wire = [0x05, 0x03, 0x03];
     let good_hold_time_wire = [0x06, 0x04, 0x04];
     let good_capability_wire = [0x07, 0x01, 0x04];
     let good_role_mismatch_wire = [0x0b, 0x09, 0x04];
@@ -162,6 +163,9 @@ fn test_open_message_error() -> Result<(), OpenMessageErrorWritingError> {
     let good_optional = OpenMessageError::UnsupportedOptionalParameter {
         value: good_optional_wire[1..].to_vec(),
     };
+    let good_authentication = OpenMessageError::AuthenticationFailure {
+        value: good_authentication_wire[1..].to_vec(),
+    };
     let good_hold_time = OpenMessageError::UnacceptableHoldTime {
         value: good_hold_time_wire[1..].to_vec(),
     };
@@ -189,6 +193,7 @@ fn test_open_message_error() -> Result<(), OpenMessageErrorWritingError> {
     test_parsed_completely(&good_peer_wire, &good_peer);
     test_parsed_completely(&good_bgp_id_wire, &good_bgp_id);
     test_parsed_completely(&good_optional_wire, &good_optional);
+    test_parsed_completely(&good_authentication_wire, &good_authentication);
     test_parsed_completely(&good_hold_time_wire, &good_hold_time);
     test_parsed_completely(&good_capability_wire, &good_capability);
     test_parsed_completely(&good_role_mismatch_wire, &good_role_mismatch);
@@ -205,6 +210,7 @@ fn test_open_message_error() -> Result<(), OpenMessageErrorWritingError> {
     test_write(&good_peer, &good_peer_wire)?;
     test_write(&good_bgp_id, &good_bgp_id_wire)?;
     test_write(&good_optional, &good_optional_wire)?;
+    test_write(&good_authentication, &good_authentication_wire)?;
     test_write(&good_hold_time, &good_hold_time_wire)?;
     test_write(&good_capability, &good_capability_wire)?;
     test_write(&good_role_mismatch, &good_role_mismatch_wire)?;
@@ -259,6 +265,7 @@ fn test_update_message_error() -> Result<(), UpdateMessageErrorWritingError> {
     let good_attribute_flags_wire = [0x04, 0x03, 0x03];
     let good_attribute_length_wire = [0x05, 0x04, 0x04];
     let good_invalid_origin_wire = [0x06, 0x05, 0x05];
+    let good_as_routing_loop_wire = [0x07, 0x06, 0x06];
     let good_next_hop_wire = [0x08, 0x06, 0x06];
     let good_optional_attribute_wire = [0x09, 0x07, 0x07];
     let good_network_field_wire = [0x0A, 0x08, 0x08];
@@ -288,6 +295,9 @@ fn test_update_message_error() -> Result<(), UpdateMessageErrorWritingError> {
     let good_invalid_origin = UpdateMessageError::InvalidOriginAttribute {
         value: good_invalid_origin_wire[1..].to_vec(),
     };
+    let good_as_routing_loop = UpdateMessageError::AsRoutingLoop {
+        value: good_as_routing_loop_wire[1..].to_vec(),
+    };
     let good_next_hop = UpdateMessageError::InvalidNextHopAttribute {
         value: good_next_hop_wire[1..].to_vec(),
     };
@@ -325,6 +335,7 @@ fn test_update_message_error() -> Result<(), UpdateMessageErrorWritingError> {
     test_parsed_completely(&good_attribute_flags_wire, &good_attribute_flags);
     test_parsed_completely(&good_attribute_length_wire, &good_attribute_length);
     test_parsed_completely(&good_invalid_origin_wire, &good_invalid_origin);
+    test_parsed_completely(&good_as_routing_loop_wire, &good_as_routing_loop);
     test_parsed_completely(&good_next_hop_wire, &good_next_hop);
     test_parsed_completely(&good_optional_attribute_wire, &good_optional_attribute);
     test_parsed_completely(&good_network_field_wire, &good_network_field);
@@ -351,6 +362,7 @@ fn test_update_message_error() -> Result<(), UpdateMessageErrorWritingError> {
     test_write(&good_attribute_flags, &good_attribute_flags_wire)?;
     test_write(&good_attribute_length, &good_attribute_length_wire)?;
     test_write(&good_invalid_origin, &good_invalid_origin_wire)?;
+    test_write(&good_as_routing_loop, &good_as_routing_loop_wire)?;
     test_write(&good_next_hop, &good_next_hop_wire)?;
     test_write(&good_optional_attribute, &good_optional_attribute_wire)?;
     test_write(&good_network_field, &good_network_field_wire)?;
@@ -668,6 +680,25 @@ fn test_bgp_notification_cease() -> Result<(), BgpNotificationMessageWritingErro
     Ok(())
 }
 
+#[test]
+fn test_bgp_notification_cease_shutdown_communication(
+) -> Result<(), BgpNotificationMessageWritingError> {
+    let good_wire = [
+        0x06, 0x02, 0x0b, 0x6d, 0x61, 0x69, 0x6e, 0x74, 0x65, 0x6e, 0x61, 0x6e, 0x63, 0x65,
+    ];
+
+    let good = BgpNotificationMessage::CeaseError(
+        CeaseError::administrative_shutdown("maintenance").unwrap(),
+    );
+
+    test_parsed_completely(&good_wire, &good);
+    test_write(&good, &good_wire)?;
+    if let BgpNotificationMessage::CeaseError(cease) = &good {
+        assert_eq!(cease.shutdown_communication(), Ok(Some("maintenance")));
+    }
+    Ok(())
+}
+
 #[test]
 fn test_route_refresh_error() -> Result<(), RouteRefreshErrorWritingError> {
     let good_wire = [0x01, 0x02, 0x02];
@@ -222,11 +222,15 @@ fn test_as2_path_segment() -> Result<(), AsPathWritingError> {
 fn test_as4_path_segment() -> Result<(), AsPathWritingError> {
     let good_set_wire = [0x01, 0x01, 0x00, 0x00, 0x00, 0x01];
     let good_seq_wire = [0x02, 0x01, 0x00, 0x00, 0x00, 0x01];
+    let good_confed_seq_wire = [0x03, 0x01, 0x00, 0x00, 0x00, 0x01];
+    let good_confed_set_wire = [0x04, 0x01, 0x00, 0x00, 0x00, 0x01];
     let bad_empty_wire = [0x01, 0x00];
     let undefined_segment_type_wire = [0x00, 0x01, 0x00, 0x00, 0x00, 0x01];
 
     let set = As4PathSegment::new(AsPathSegmentType::AsSet, vec![1]);
     let seq = As4PathSegment::new(AsPathSegmentType::AsSequence, vec![1]);
+    let confed_seq = As4PathSegment::new(AsPathSegmentType::AsConfedSequence, vec![1]);
+    let confed_set = As4PathSegment::new(AsPathSegmentType::AsConfedSet, vec![1]);
 
     let bad_empty = LocatedAsPathParsingError::new(
         unsafe { Span::new_from_raw_offset(1, &bad_empty_wire[1..]) },
@@ -240,6 +244,8 @@ fn test_as4_path_segment() -> Result<(), AsPathWritingError> {
 
     test_parsed_completely(&good_set_wire, &set);
     test_parsed_completely(&good_seq_wire, &seq);
+    test_parsed_completely(&good_confed_seq_wire, &confed_seq);
+    test_parsed_completely(&good_confed_set_wire, &confed_set);
 
     test_parse_error::<As4PathSegment, LocatedAsPathParsingError<'_>>(&bad_empty_wire, &bad_empty);
     test_parse_error::<As4PathSegment, LocatedAsPathParsingError<'_>>(
@@ -249,6 +255,8 @@ fn test_as4_path_segment() -> Result<(), AsPathWritingError> {
 
     test_write(&set, &good_set_wire)?;
     test_write(&seq, &good_seq_wire)?;
+    test_write(&confed_seq, &good_confed_seq_wire)?;
+    test_write(&confed_set, &good_confed_set_wire)?;
     Ok(())
 }
 
@@ -1034,6 +1042,56 @@ fn test_path_attribute_as2_aggregator() -> Result<(), PathAttributeWritingError>
     Ok(())
 }
 
+#[test]
+fn test_path_attribute_as4_aggregator() -> Result<(), PathAttributeWritingError> {
+    let good_wire = [
+        0xc0, 0x12, 0x08, 0xfa, 0x56, 0xea, 0x00, 0xac, 0x10, 0x00, 0x0a,
+    ];
+    let bad_length_wire = [
+        0xc0, 0x12, 0x06, 0xfa, 0x56, 0xea, 0x00, 0xac, 0x10, 0x00, 0x0a,
+    ];
+
+    let good = PathAttribute::from(
+        true,
+        true,
+        false,
+        false,
+        PathAttributeValue::As4Aggregator(As4Aggregator::new(
+            4_200_000_000,
+            Ipv4Addr::new(172, 16, 0, 10),
+        )),
+    )
+    .unwrap();
+
+    let bad_length = LocatedPathAttributeParsingError::new(
+        unsafe { Span::new_from_raw_offset(2, &bad_length_wire[2..]) },
+        PathAttributeParsingError::AggregatorError(AggregatorParsingError::InvalidLength(
+            PathAttributeLength::U8(6),
+        )),
+    );
+
+    // AS4_AGGREGATOR is parsed the same regardless of the four-octet AS
+    // capability
+    test_parsed_completely_with_one_input(
+        &good_wire,
+        &mut BgpParsingContext::asn2_default(),
+        &good,
+    );
+    test_parsed_completely_with_one_input(&good_wire, &mut BgpParsingContext::default(), &good);
+    test_parse_error_with_one_input::<
+        PathAttribute,
+        &mut BgpParsingContext,
+        LocatedPathAttributeParsingError<'_>,
+    >(
+        &bad_length_wire,
+        &mut BgpParsingContext::asn2_default(),
+        &bad_length,
+    );
+
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_parse_path_attribute_as4_aggregator() -> Result<(), PathAttributeWritingError> {
     let good_wire = [
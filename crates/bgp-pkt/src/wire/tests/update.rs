@@ -14,10 +14,11 @@
 // limitations under the License.
 
 use crate::{
+    iana::PathAttributeType,
     nlri::{InvalidIpv4UnicastNetwork, Ipv4Unicast, Ipv4UnicastAddress},
     path_attribute::{
         As4PathSegment, AsPath, AsPathSegmentType, NextHop, Origin, PathAttribute,
-        PathAttributeValue,
+        PathAttributeValue, UndefinedOrigin,
     },
     wire::{
         deserializer::{
@@ -25,7 +26,13 @@ use crate::{
                 Ipv4UnicastAddressParsingError, Ipv4UnicastParsingError,
                 LocatedIpv4UnicastAddressParsingError,
             },
-            update::BgpUpdateMessageParsingError,
+            path_attribute::{
+                LocatedPathAttributeParsingError, OriginParsingError, PathAttributeParsingError,
+            },
+            update::{
+                BgpUpdateMessageParsingError, LazyBgpUpdateMessage,
+                LocatedBgpUpdateMessageParsingError,
+            },
             BgpMessageParsingError, BgpParsingContext, Ipv4PrefixParsingError,
             LocatedBgpMessageParsingError,
         },
@@ -39,7 +46,7 @@ use netgauze_parse_utils::{
         test_parse_error_with_one_input, test_parsed_completely,
         test_parsed_completely_with_one_input, test_write,
     },
-    Span,
+    ReadablePduWithOneInput, Span,
 };
 use nom::error::ErrorKind;
 use std::{collections::HashMap, net::Ipv4Addr, str::FromStr};
@@ -329,3 +336,125 @@ fn test_update_bad_length() -> Result<(), BgpMessageWritingError> {
     );
     Ok(())
 }
+
+#[test]
+fn test_lazy_update() {
+    let good_update_wire = [
+        0x00, 0x08, 0x18, 0xac, 0x10, 0x03, 0x18, 0xac, 0x10, 0x04, 0x00, 0x19, 0x40, 0x01, 0x01,
+        0x00, 0x50, 0x02, 0x00, 0x0a, 0x02, 0x02, 0x00, 0x00, 0x00, 0xc8, 0x00, 0x00, 0x00, 0x64,
+        0x40, 0x03, 0x04, 0xac, 0x10, 0x00, 0x14, 0x18, 0xac, 0x10, 0x01, 0x18, 0xac, 0x10, 0x02,
+    ];
+    let bad_origin_wire = [
+        0x00, 0x00, 0x00, 0x04, 0x40, 0x01, 0x01, 0x05, 0x18, 0xac, 0x10, 0x01,
+    ];
+    let bad_attr_length_wire = [0x00, 0x00, 0x00, 0x04, 0x40, 0x01, 0x05, 0x00];
+
+    let origin = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::Origin(Origin::IGP),
+    )
+    .unwrap();
+    let as_path = PathAttribute::from(
+        false,
+        true,
+        false,
+        true,
+        PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            vec![200, 100],
+        )])),
+    )
+    .unwrap();
+    let next_hop = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(172, 16, 0, 20))),
+    )
+    .unwrap();
+    let good_update = BgpUpdateMessage::new(
+        vec![
+            Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::new(Ipv4Addr::new(172, 16, 3, 0), 24).unwrap())
+                    .unwrap(),
+            ),
+            Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::new(Ipv4Addr::new(172, 16, 4, 0), 24).unwrap())
+                    .unwrap(),
+            ),
+        ],
+        vec![origin, as_path, next_hop.clone()],
+        vec![
+            Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::new(Ipv4Addr::new(172, 16, 1, 0), 24).unwrap())
+                    .unwrap(),
+            ),
+            Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(Ipv4Net::new(Ipv4Addr::new(172, 16, 2, 0), 24).unwrap())
+                    .unwrap(),
+            ),
+        ],
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    let (remainder, lazy) =
+        LazyBgpUpdateMessage::from_wire(Span::new(&good_update_wire), &mut ctx).unwrap();
+    assert!(remainder.is_empty());
+    assert_eq!(lazy.withdraw_routes(), good_update.withdraw_routes());
+    assert_eq!(lazy.nlri(), good_update.nlri());
+    assert_eq!(lazy.path_attributes().len(), 3);
+    let lazy_as_path = lazy.get(PathAttributeType::AsPath).unwrap();
+    assert!(lazy_as_path.extended_length());
+    assert!(lazy_as_path.transitive());
+    assert!(!lazy_as_path.optional());
+    assert_eq!(lazy_as_path.value_bytes(), &good_update_wire[20..30]);
+    assert!(lazy.get(PathAttributeType::Communities).is_none());
+    assert_eq!(
+        lazy.decode_attribute(PathAttributeType::NextHop, &mut ctx),
+        Some(Ok(next_hop))
+    );
+    assert_eq!(lazy.decode(&mut ctx), Ok(good_update));
+
+    // Malformed attribute values are only detected when decoded
+    let (_, lazy_bad_origin) =
+        LazyBgpUpdateMessage::from_wire(Span::new(&bad_origin_wire), &mut ctx).unwrap();
+    let bad_origin = LocatedPathAttributeParsingError::new(
+        unsafe { Span::new_from_raw_offset(7, &bad_origin_wire[7..8]) },
+        PathAttributeParsingError::OriginError(OriginParsingError::UndefinedOrigin(
+            UndefinedOrigin(5),
+        )),
+    );
+    assert_eq!(
+        lazy_bad_origin.decode_attribute(PathAttributeType::Origin, &mut ctx),
+        Some(Err(bad_origin.clone()))
+    );
+    assert_eq!(lazy_bad_origin.decode(&mut ctx), Err(bad_origin));
+    let mut lenient_ctx = BgpParsingContext::new(
+        true,
+        HashMap::new(),
+        HashMap::new(),
+        true,
+        true,
+        true,
+        false,
+    );
+    let lenient = lazy_bad_origin.decode(&mut lenient_ctx).unwrap();
+    assert!(lenient.path_attributes().is_empty());
+    assert_eq!(lenient.nlri().len(), 1);
+    assert_eq!(lenient_ctx.parsing_errors().path_attr_errors().len(), 1);
+
+    // Attribute boundaries are still validated when indexing
+    let bad_attr_length = LocatedBgpUpdateMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(7, &bad_attr_length_wire[7..]) },
+        BgpUpdateMessageParsingError::NomError(ErrorKind::Eof),
+    );
+    test_parse_error_with_one_input::<
+        LazyBgpUpdateMessage<'_>,
+        &mut BgpParsingContext,
+        LocatedBgpUpdateMessageParsingError<'_>,
+    >(&bad_attr_length_wire, &mut ctx, &bad_attr_length);
+}
@@ -0,0 +1,286 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! HTTP frontend of an [`AdminHandle`] with JSON bodies, the peers are
+//! identified by their IP address.
+//!
+//! | Method   | Path                        | Body                                 |
+//! |----------|-----------------------------|--------------------------------------|
+//! | `GET`    | `/peers`                    |                                      |
+//! | `POST`   | `/peers`                    | `{"address": _, "asn": _, "group": _}` |
+//! | `GET`    | `/peers/{ip}`               |                                      |
+//! | `DELETE` | `/peers/{ip}`               |                                      |
+//! | `POST`   | `/peers/{ip}/start`         |                                      |
+//! | `POST`   | `/peers/{ip}/shutdown`      | Optional Shutdown Communication text |
+//! | `GET`    | `/peers/{ip}/adj-rib-in`    |                                      |
+//! | `PUT`    | `/peers/{ip}/import-policy` | [`Policy`]                           |
+//! | `DELETE` | `/peers/{ip}/import-policy` |                                      |
+//! | `PUT`    | `/peers/{ip}/export-policy` | [`Policy`]                           |
+//! | `DELETE` | `/peers/{ip}/export-policy` |                                      |
+
+use std::{
+    convert::Infallible,
+    io,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::{
+    body::Incoming,
+    header::{HeaderValue, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use serde_json::{json, Value};
+use tokio::net::TcpListener;
+
+use crate::{
+    admin::{AdminError, AdminHandle},
+    metrics::PeerMetrics,
+    rib::AdjRibIn,
+    route_policy::{Policy, RoutePolicy},
+    supervisor::{PeerOverrides, PeersSupervisorError},
+};
+
+type Response = hyper::Response<Full<Bytes>>;
+
+#[derive(Debug, serde::Deserialize)]
+struct AddPeerRequest {
+    address: SocketAddr,
+    asn: u32,
+    group: String,
+}
+
+/// Serve the admin requests received on the listener until accepting a
+/// connection fails
+pub async fn serve(
+    listener: TcpListener,
+    admin: AdminHandle<IpAddr, SocketAddr>,
+) -> io::Result<()> {
+    loop {
+        let (stream, remote_addr) = listener.accept().await?;
+        let admin = admin.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| handle(admin.clone(), request));
+            if let Err(err) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                log::warn!("[{remote_addr}] Admin HTTP connection error: {err}");
+            }
+        });
+    }
+}
+
+async fn handle(
+    admin: AdminHandle<IpAddr, SocketAddr>,
+    request: Request<Incoming>,
+) -> Result<Response, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(err) => return Ok(bad_request(&err.to_string())),
+    };
+    let result = match (&method, segments.as_slice()) {
+        (&Method::GET, ["peers"]) => list_peers(&admin).await,
+        (&Method::POST, ["peers"]) => {
+            let peer: AddPeerRequest = match serde_json::from_slice(&body) {
+                Ok(peer) => peer,
+                Err(err) => return Ok(bad_request(&err.to_string())),
+            };
+            admin
+                .add_peer(
+                    peer.address.ip(),
+                    peer.address,
+                    peer.asn,
+                    &peer.group,
+                    PeerOverrides::new(),
+                )
+                .await
+                .map(|_| json!({}))
+        }
+        (_, ["peers", peer_key, rest @ ..]) => {
+            let Ok(peer_key) = peer_key.parse::<IpAddr>() else {
+                return Ok(bad_request("invalid peer address"));
+            };
+            match (&method, rest) {
+                (&Method::GET, []) => admin
+                    .peer_metrics(peer_key)
+                    .await
+                    .map(|metrics| metrics_json(&metrics)),
+                (&Method::DELETE, []) => admin.remove_peer(peer_key).await.map(|_| json!({})),
+                (&Method::POST, ["start"]) => admin.start_peer(peer_key).await.map(|_| json!({})),
+                (&Method::POST, ["shutdown"]) => {
+                    let Ok(communication) = std::str::from_utf8(&body) else {
+                        return Ok(bad_request("the shutdown communication is not UTF-8"));
+                    };
+                    let communication = Some(communication.trim()).filter(|c| !c.is_empty());
+                    admin
+                        .shutdown_peer(peer_key, communication)
+                        .await
+                        .map(|_| json!({}))
+                }
+                (&Method::GET, ["adj-rib-in"]) => admin
+                    .adj_rib_in(peer_key)
+                    .await
+                    .map(|adj_rib_in| adj_rib_in_json(&adj_rib_in)),
+                (&Method::PUT, [kind @ ("import-policy" | "export-policy")]) => {
+                    let policy: Policy = match serde_json::from_slice(&body) {
+                        Ok(policy) => policy,
+                        Err(err) => return Ok(bad_request(&err.to_string())),
+                    };
+                    set_policy(
+                        &admin,
+                        peer_key,
+                        *kind == "import-policy",
+                        Some(Arc::new(policy)),
+                    )
+                    .await
+                }
+                (&Method::DELETE, [kind @ ("import-policy" | "export-policy")]) => {
+                    set_policy(&admin, peer_key, *kind == "import-policy", None).await
+                }
+                _ => return Ok(not_found()),
+            }
+        }
+        _ => return Ok(not_found()),
+    };
+    Ok(match result {
+        Ok(value) => json_response(StatusCode::OK, value),
+        Err(err) => error_response(err),
+    })
+}
+
+async fn list_peers(admin: &AdminHandle<IpAddr, SocketAddr>) -> Result<Value, AdminError> {
+    let mut peers = vec![];
+    for peer_key in admin.peers().await? {
+        let state = admin
+            .peer_metrics(peer_key)
+            .await
+            .ok()
+            .map(|metrics| metrics.fsm_state().to_string());
+        peers.push(json!({"peer": peer_key.to_string(), "state": state}));
+    }
+    Ok(Value::Array(peers))
+}
+
+async fn set_policy(
+    admin: &AdminHandle<IpAddr, SocketAddr>,
+    peer_key: IpAddr,
+    import: bool,
+    policy: Option<Arc<dyn RoutePolicy>>,
+) -> Result<Value, AdminError> {
+    if import {
+        admin.set_import_policy(peer_key, policy).await?;
+    } else {
+        admin.set_export_policy(peer_key, policy).await?;
+    }
+    Ok(json!({}))
+}
+
+fn metrics_json(metrics: &PeerMetrics) -> Value {
+    let stats = metrics.stats();
+    json!({
+        "state": metrics.fsm_state().to_string(),
+        "fsm_transitions": stats.fsm_transitions(),
+        "established_transitions": stats.established_transitions(),
+        "flaps": stats.flaps(),
+        "last_state_change": stats.last_state_change().map(|time| time.to_rfc3339()),
+        "last_error": stats.last_error().map(|err| json!({
+            "code": err.code() as u8,
+            "sub_code": err.sub_code(),
+            "direction": if err.received() { "received" } else { "sent" },
+        })),
+        "prefixes_received": metrics.prefixes_received(),
+        "prefixes_stale": metrics.prefixes_stale(),
+        "messages": metrics.connection_stats().map(|connection| json!({
+            "received": {
+                "open": connection.open_received(),
+                "update": connection.update_received(),
+                "keepalive": connection.keepalive_received(),
+                "notification": connection.notification_received(),
+                "route_refresh": connection.route_refresh_received(),
+            },
+            "sent": {
+                "open": connection.open_sent(),
+                "update": connection.update_sent(),
+                "keepalive": connection.keepalive_sent(),
+                "notification": connection.notification_sent(),
+                "route_refresh": connection.route_refresh_sent(),
+            },
+        })),
+    })
+}
+
+fn adj_rib_in_json(adj_rib_in: &AdjRibIn) -> Value {
+    adj_rib_in
+        .routes()
+        .map(|route| {
+            json!({
+                "address_type": format!("{:?}", route.key().address_type()),
+                "prefix": route.key().prefix().to_string(),
+                "path_id": route.key().path_id(),
+                "next_hop": route.next_hop().map(|next_hop| next_hop.to_string()),
+                "local_preference": route.local_preference(),
+                "med": route.med(),
+                "as_path_length": route.as_path_length(),
+                "stale": adj_rib_in.is_stale(route.key()),
+            })
+        })
+        .collect()
+}
+
+fn json_response(status: StatusCode, value: Value) -> Response {
+    let mut response = Response::new(Full::new(Bytes::from(value.to_string())));
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+fn error_response(err: AdminError) -> Response {
+    let status = match err {
+        AdminError::Closed | AdminError::Supervisor(PeersSupervisorError::PeerNotRunning) => {
+            StatusCode::SERVICE_UNAVAILABLE
+        }
+        AdminError::Supervisor(
+            PeersSupervisorError::PeerNotFound | PeersSupervisorError::GroupNotFound,
+        ) => StatusCode::NOT_FOUND,
+        AdminError::Supervisor(
+            PeersSupervisorError::PeerExists
+            | PeersSupervisorError::GroupExists
+            | PeersSupervisorError::GroupHasMembers,
+        ) => StatusCode::CONFLICT,
+        AdminError::Supervisor(PeersSupervisorError::InvalidShutdownCommunication) => {
+            StatusCode::BAD_REQUEST
+        }
+    };
+    json_response(status, json!({"error": format!("{err:?}")}))
+}
+
+fn bad_request(message: &str) -> Response {
+    json_response(StatusCode::BAD_REQUEST, json!({"error": message}))
+}
+
+fn not_found() -> Response {
+    json_response(StatusCode::NOT_FOUND, json!({"error": "not found"}))
+}
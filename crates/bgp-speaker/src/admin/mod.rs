@@ -0,0 +1,190 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime administration of the peers of a [`crate::supervisor::PeersSupervisor`].
+//!
+//! An [`AdminHandle`] sends [`AdminRequest`]s to the task owning the
+//! supervisor, which passes them to
+//! [`crate::supervisor::PeersSupervisor::handle_admin_request`].
+//! The [`crate::listener::BgpListener`] handles the requests of the handle
+//! returned by [`crate::listener::BgpListener::admin_handle`] while running.
+//! The handle is cheap to clone and can be shared with any frontend, an HTTP
+//! one is provided by the `admin-http` feature.
+
+#[cfg(feature = "admin-http")]
+pub mod http;
+
+use std::sync::Arc;
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    metrics::PeerMetrics,
+    rib::AdjRibIn,
+    route_policy::RoutePolicy,
+    supervisor::{PeerGroup, PeerOverrides, PeersSupervisorError},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AdminError {
+    /// The supervisor doesn't handle admin requests anymore
+    Closed,
+    Supervisor(PeersSupervisorError),
+}
+
+impl From<PeersSupervisorError> for AdminError {
+    fn from(err: PeersSupervisorError) -> Self {
+        Self::Supervisor(err)
+    }
+}
+
+pub type AdminReply<T> = oneshot::Sender<Result<T, PeersSupervisorError>>;
+
+#[derive(Debug)]
+pub enum AdminRequest<K, A> {
+    ListPeers(AdminReply<Vec<K>>),
+    /// Create and start a peer with the configuration of a group, see
+    /// [`crate::supervisor::PeersSupervisor::create_group_peer`]
+    AddPeer {
+        peer_key: K,
+        peer_addr: A,
+        peer_asn: u32,
+        group: String,
+        overrides: PeerOverrides,
+        reply: AdminReply<()>,
+    },
+    RemovePeer(K, AdminReply<()>),
+    StartPeer(K, AdminReply<()>),
+    /// See [`crate::supervisor::PeersSupervisor::shutdown_peer`]
+    ShutdownPeer(K, Option<String>, AdminReply<()>),
+    GetPeerMetrics(K, AdminReply<PeerMetrics>),
+    GetAdjRibIn(K, AdminReply<AdjRibIn>),
+    /// See [`crate::supervisor::PeersSupervisor::set_import_policy`]
+    SetImportPolicy(K, Option<Arc<dyn RoutePolicy>>, AdminReply<()>),
+    /// See [`crate::supervisor::PeersSupervisor::set_export_policy`]
+    SetExportPolicy(K, Option<Arc<dyn RoutePolicy>>, AdminReply<()>),
+    /// See [`crate::supervisor::PeersSupervisor::update_group`]
+    UpdateGroup(String, PeerGroup, AdminReply<Vec<K>>),
+}
+
+/// Create an [`AdminHandle`] and the receiver of its requests
+pub fn admin_channel<K, A>() -> (
+    AdminHandle<K, A>,
+    mpsc::UnboundedReceiver<AdminRequest<K, A>>,
+) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    (AdminHandle { tx }, rx)
+}
+
+#[derive(Debug)]
+pub struct AdminHandle<K, A> {
+    tx: mpsc::UnboundedSender<AdminRequest<K, A>>,
+}
+
+impl<K, A> Clone for AdminHandle<K, A> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+        }
+    }
+}
+
+impl<K, A> AdminHandle<K, A> {
+    async fn request<T>(
+        &self,
+        request: impl FnOnce(AdminReply<T>) -> AdminRequest<K, A>,
+    ) -> Result<T, AdminError> {
+        let (tx, rx) = oneshot::channel();
+        self.tx.send(request(tx)).map_err(|_| AdminError::Closed)?;
+        Ok(rx.await.map_err(|_| AdminError::Closed)??)
+    }
+
+    pub async fn peers(&self) -> Result<Vec<K>, AdminError> {
+        self.request(AdminRequest::ListPeers).await
+    }
+
+    pub async fn add_peer(
+        &self,
+        peer_key: K,
+        peer_addr: A,
+        peer_asn: u32,
+        group: &str,
+        overrides: PeerOverrides,
+    ) -> Result<(), AdminError> {
+        self.request(|reply| AdminRequest::AddPeer {
+            peer_key,
+            peer_addr,
+            peer_asn,
+            group: group.to_string(),
+            overrides,
+            reply,
+        })
+        .await
+    }
+
+    pub async fn remove_peer(&self, peer_key: K) -> Result<(), AdminError> {
+        self.request(|reply| AdminRequest::RemovePeer(peer_key, reply))
+            .await
+    }
+
+    pub async fn start_peer(&self, peer_key: K) -> Result<(), AdminError> {
+        self.request(|reply| AdminRequest::StartPeer(peer_key, reply))
+            .await
+    }
+
+    pub async fn shutdown_peer(
+        &self,
+        peer_key: K,
+        communication: Option<&str>,
+    ) -> Result<(), AdminError> {
+        let communication = communication.map(|communication| communication.to_string());
+        self.request(|reply| AdminRequest::ShutdownPeer(peer_key, communication, reply))
+            .await
+    }
+
+    pub async fn peer_metrics(&self, peer_key: K) -> Result<PeerMetrics, AdminError> {
+        self.request(|reply| AdminRequest::GetPeerMetrics(peer_key, reply))
+            .await
+    }
+
+    pub async fn adj_rib_in(&self, peer_key: K) -> Result<AdjRibIn, AdminError> {
+        self.request(|reply| AdminRequest::GetAdjRibIn(peer_key, reply))
+            .await
+    }
+
+    pub async fn set_import_policy(
+        &self,
+        peer_key: K,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), AdminError> {
+        self.request(|reply| AdminRequest::SetImportPolicy(peer_key, policy, reply))
+            .await
+    }
+
+    pub async fn set_export_policy(
+        &self,
+        peer_key: K,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), AdminError> {
+        self.request(|reply| AdminRequest::SetExportPolicy(peer_key, policy, reply))
+            .await
+    }
+
+    /// Returns the keys of the members that are not running anymore
+    pub async fn update_group(&self, name: &str, group: PeerGroup) -> Result<Vec<K>, AdminError> {
+        self.request(|reply| AdminRequest::UpdateGroup(name.to_string(), group, reply))
+            .await
+    }
+}
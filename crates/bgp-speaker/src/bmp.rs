@@ -0,0 +1,672 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export of the peers to a BGP Monitoring Protocol station as defined in
+//! [RFC7854](https://datatracker.ietf.org/doc/html/rfc7854).
+//!
+//! A [`BmpStationClient`] maintains the connection to the monitoring station
+//! and sends it the messages of the peers given a [`BmpPeerExporter`], see
+//! [`crate::peer_controller::PeerHandle::set_bmp_exporter`] and
+//! [`crate::supervisor::PeersSupervisor::set_bmp_exporter`]. Each peer reports:
+//! * a Peer Up notification once its session is established, and a Peer Down
+//!   notification when the session goes down,
+//! * Route Monitoring of the received UPDATE messages (pre-policy) and of the
+//!   changes of its routes after the import policy (post-policy),
+//! * periodic Statistics Reports of its Adj-RIB-In.
+//!
+//! Every time the client connects to the station, the established peers send
+//! their Peer Up notification again followed by their current routes and the
+//! End-of-RIB markers.
+
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::Utc;
+use futures::StreamExt;
+use futures_util::SinkExt;
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+    notification::BgpNotificationMessage,
+    open::BgpOpenMessage,
+    path_attribute::{MpReach, MpUnreach, PathAttributeValue},
+    update::BgpUpdateMessage,
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{
+    codec::BmpCodec, BmpMessage, BmpMessageValue, BmpPeerType, CounterU32, GaugeU64,
+    InitiationInformation, InitiationMessage, PeerDownNotificationMessage,
+    PeerDownNotificationReason, PeerHeader, PeerUpNotificationMessage, RouteMonitoringMessage,
+    StatisticsCounter, StatisticsReportMessage,
+};
+use netgauze_iana::address_family::AddressType;
+use tokio::{
+    net::TcpStream,
+    sync::{mpsc, watch},
+    time::Interval,
+};
+use tokio_util::codec::Framed;
+
+use crate::{
+    events::ConnectionEvent,
+    rib::{AdjRibIn, Route, RouteChange},
+    update::path_attribute,
+};
+
+pub const DEFAULT_BMP_RECONNECT_DURATION: Duration = Duration::from_secs(30);
+pub const DEFAULT_BMP_STATISTICS_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Address of a peer as reported in the BMP messages
+pub trait BmpPeerAddress {
+    fn bmp_address(&self) -> IpAddr;
+
+    /// Remote port of the BGP session, when known
+    fn bmp_port(&self) -> Option<u16>;
+}
+
+impl BmpPeerAddress for SocketAddr {
+    fn bmp_address(&self) -> IpAddr {
+        self.ip()
+    }
+
+    fn bmp_port(&self) -> Option<u16> {
+        Some(self.port())
+    }
+}
+
+impl BmpPeerAddress for IpAddr {
+    fn bmp_address(&self) -> IpAddr {
+        *self
+    }
+
+    fn bmp_port(&self) -> Option<u16> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BmpStationConfig {
+    sys_name: String,
+    sys_descr: String,
+    reconnect_duration: Duration,
+    statistics_interval: Duration,
+}
+
+impl BmpStationConfig {
+    /// `sys_name` and `sys_descr` are sent to the station in the Initiation
+    /// message
+    pub fn new(sys_name: &str, sys_descr: &str) -> Self {
+        Self {
+            sys_name: sys_name.to_string(),
+            sys_descr: sys_descr.to_string(),
+            reconnect_duration: DEFAULT_BMP_RECONNECT_DURATION,
+            statistics_interval: DEFAULT_BMP_STATISTICS_INTERVAL,
+        }
+    }
+
+    /// Time to wait before connecting again to the station
+    pub fn with_reconnect_duration(mut self, reconnect_duration: Duration) -> Self {
+        self.reconnect_duration = reconnect_duration;
+        self
+    }
+
+    /// Interval between two Statistics Reports of a peer, zero disables the
+    /// reports
+    pub fn with_statistics_interval(mut self, statistics_interval: Duration) -> Self {
+        self.statistics_interval = statistics_interval;
+        self
+    }
+
+    pub fn sys_name(&self) -> &str {
+        &self.sys_name
+    }
+
+    pub fn sys_descr(&self) -> &str {
+        &self.sys_descr
+    }
+
+    pub const fn reconnect_duration(&self) -> Duration {
+        self.reconnect_duration
+    }
+
+    pub const fn statistics_interval(&self) -> Duration {
+        self.statistics_interval
+    }
+}
+
+/// Create a [`BmpStationClient`] and the [`BmpExporter`] sending it the
+/// messages of the peers
+pub fn bmp_channel(config: BmpStationConfig) -> (BmpExporter, BmpStationClient) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (session_tx, session) = watch::channel(None);
+    let exporter = BmpExporter {
+        tx,
+        session,
+        statistics_interval: config.statistics_interval,
+    };
+    let client = BmpStationClient {
+        config,
+        rx,
+        session_tx,
+    };
+    (exporter, client)
+}
+
+/// Sender of BMP messages to a [`BmpStationClient`], the messages are tagged
+/// with the session with the station they were produced for, so the messages
+/// of a previous session are not sent to the station after it reconnects.
+#[derive(Debug, Clone)]
+pub struct BmpExporter {
+    tx: mpsc::UnboundedSender<(u64, BmpMessage)>,
+    session: watch::Receiver<Option<u64>>,
+    statistics_interval: Duration,
+}
+
+impl BmpExporter {
+    /// Current session with the station, `None` while disconnected
+    pub fn session(&self) -> Option<u64> {
+        *self.session.borrow()
+    }
+
+    /// Returns `false` if the client is not running anymore
+    pub fn send(&self, session: u64, msg: BmpMessage) -> bool {
+        self.tx.send((session, msg)).is_ok()
+    }
+
+    /// Exporter for a peer with addresses of type `A`
+    pub fn peer_exporter<A: BmpPeerAddress>(&self) -> BmpPeerExporter<A> {
+        BmpPeerExporter {
+            exporter: self.clone(),
+            address: A::bmp_address,
+            port: A::bmp_port,
+        }
+    }
+}
+
+/// [`BmpExporter`] of a peer, knowing how to report the address of the peer
+pub struct BmpPeerExporter<A> {
+    exporter: BmpExporter,
+    address: fn(&A) -> IpAddr,
+    port: fn(&A) -> Option<u16>,
+}
+
+impl<A> Clone for BmpPeerExporter<A> {
+    fn clone(&self) -> Self {
+        Self {
+            exporter: self.exporter.clone(),
+            address: self.address,
+            port: self.port,
+        }
+    }
+}
+
+impl<A> Debug for BmpPeerExporter<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BmpPeerExporter")
+            .field("exporter", &self.exporter)
+            .finish()
+    }
+}
+
+impl<A> BmpPeerExporter<A> {
+    pub const fn exporter(&self) -> &BmpExporter {
+        &self.exporter
+    }
+}
+
+/// Maintains the connection to the BMP station, see the [module](self) docs
+#[derive(Debug)]
+pub struct BmpStationClient {
+    config: BmpStationConfig,
+    rx: mpsc::UnboundedReceiver<(u64, BmpMessage)>,
+    session_tx: watch::Sender<Option<u64>>,
+}
+
+impl BmpStationClient {
+    pub const fn config(&self) -> &BmpStationConfig {
+        &self.config
+    }
+
+    /// Send the messages of the peers to the station, connecting again after
+    /// [`BmpStationConfig::reconnect_duration`] when the connection fails or
+    /// is closed by the station. Returns once all the exporters are dropped.
+    pub async fn run(mut self, station: SocketAddr) {
+        let mut session = 0;
+        loop {
+            match TcpStream::connect(station).await {
+                Ok(stream) => {
+                    session += 1;
+                    log::info!("[{station}] Connected to the BMP station");
+                    let closed = self.serve(station, session, stream).await;
+                    self.session_tx.send_replace(None);
+                    if closed {
+                        return;
+                    }
+                }
+                Err(err) => {
+                    log::warn!("[{station}] Couldn't connect to the BMP station: {err}");
+                }
+            }
+            let reconnect = tokio::time::sleep(self.config.reconnect_duration);
+            tokio::pin!(reconnect);
+            loop {
+                tokio::select! {
+                    _ = &mut reconnect => break,
+                    // Messages produced while disconnected are dropped
+                    msg = self.rx.recv() => if msg.is_none() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns `true` once all the exporters are dropped
+    async fn serve(&mut self, station: SocketAddr, session: u64, stream: TcpStream) -> bool {
+        let mut framed = Framed::new(stream, BmpCodec::default());
+        let initiation = InitiationMessage::new(vec![
+            InitiationInformation::SystemName(self.config.sys_name.clone()),
+            InitiationInformation::SystemDescription(self.config.sys_descr.clone()),
+        ]);
+        let initiation = BmpMessage::V3(BmpMessageValue::Initiation(initiation));
+        if let Err(err) = framed.send(initiation).await {
+            log::warn!("[{station}] Couldn't send the BMP Initiation message: {err:?}");
+            return false;
+        }
+        self.session_tx.send_replace(Some(session));
+        loop {
+            tokio::select! {
+                msg = self.rx.recv() => match msg {
+                    Some((msg_session, msg)) => {
+                        if msg_session != session {
+                            continue;
+                        }
+                        if let Err(err) = framed.send(msg).await {
+                            log::warn!("[{station}] Couldn't send BMP message: {err:?}");
+                            return false;
+                        }
+                    }
+                    None => {
+                        log::info!("[{station}] No more BMP exporters, closing the connection");
+                        return true;
+                    }
+                },
+                // The station is not expected to send any message
+                msg = framed.next() => match msg {
+                    Some(Ok(msg)) => {
+                        log::debug!("[{station}] Ignoring message from the BMP station: {msg:?}");
+                    }
+                    Some(Err(err)) => {
+                        log::warn!("[{station}] Error reading from the BMP station: {err:?}");
+                        return false;
+                    }
+                    None => {
+                        log::info!("[{station}] The BMP station closed the connection");
+                        return false;
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// Peer Header fields of a peer reported up to the station
+#[derive(Debug, Clone, Copy)]
+struct BmpPeerUp {
+    session: u64,
+    address: IpAddr,
+    peer_asn: u32,
+    peer_bgp_id: Ipv4Addr,
+    asn2: bool,
+}
+
+impl BmpPeerUp {
+    fn peer_header(&self, post_policy: bool) -> PeerHeader {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: self.address.is_ipv6(),
+                post_policy,
+                asn2: self.asn2,
+                adj_rib_out: false,
+            },
+            None,
+            Some(self.address),
+            self.peer_asn,
+            self.peer_bgp_id,
+            Some(Utc::now()),
+        )
+    }
+}
+
+/// Events of the BMP export handled by the peer
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub(crate) enum BmpMonitorEvent {
+    StationChanged,
+    StatisticsTimerExpires,
+}
+
+/// State of the BMP export of a peer
+#[derive(Debug)]
+pub(crate) struct BmpMonitor<A> {
+    exporter: BmpPeerExporter<A>,
+    session: watch::Receiver<Option<u64>>,
+    up: Option<BmpPeerUp>,
+    statistics_timer: Option<Interval>,
+    down_reason: Option<PeerDownNotificationReason>,
+    rejected_prefixes: u32,
+}
+
+impl<A> BmpMonitor<A> {
+    pub(crate) fn new(exporter: BmpPeerExporter<A>) -> Self {
+        let mut session = exporter.exporter.session.clone();
+        // The current session is handled when the peer is established
+        session.mark_unchanged();
+        Self {
+            exporter,
+            session,
+            up: None,
+            statistics_timer: None,
+            down_reason: None,
+            rejected_prefixes: 0,
+        }
+    }
+
+    /// Station session that the peer is not yet reported up in
+    pub(crate) fn pending_session(&self) -> Option<u64> {
+        let session = *self.session.borrow();
+        session.filter(|session| self.up.map(|up| up.session) != Some(*session))
+    }
+
+    pub(crate) async fn next_event(&mut self) -> BmpMonitorEvent {
+        tokio::select! {
+            changed = self.session.changed() => {
+                if changed.is_err() {
+                    // The client is not running anymore
+                    std::future::pending::<()>().await;
+                }
+                BmpMonitorEvent::StationChanged
+            }
+            _ = async {
+                match self.statistics_timer.as_mut() {
+                    Some(interval) => {
+                        interval.tick().await;
+                    }
+                    None => std::future::pending().await,
+                }
+            } => BmpMonitorEvent::StatisticsTimerExpires,
+        }
+    }
+
+    /// Forget the peer was reported up in the previous station session
+    pub(crate) fn station_changed(&mut self) {
+        self.session.borrow_and_update();
+        self.up.take();
+        self.statistics_timer.take();
+    }
+
+    fn send(&self, msg: BmpMessageValue) {
+        if let Some(up) = &self.up {
+            self.exporter.exporter.send(up.session, BmpMessage::V3(msg));
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn peer_up(
+        &mut self,
+        session: u64,
+        peer_addr: &A,
+        peer_asn: u32,
+        peer_bgp_id: Ipv4Addr,
+        asn2: bool,
+        sent_open: BgpOpenMessage,
+        received_open: BgpOpenMessage,
+    ) {
+        let up = BmpPeerUp {
+            session,
+            address: (self.exporter.address)(peer_addr),
+            peer_asn,
+            peer_bgp_id,
+            asn2,
+        };
+        let peer_up = PeerUpNotificationMessage::build(
+            up.peer_header(false),
+            None,
+            None,
+            (self.exporter.port)(peer_addr),
+            BgpMessage::Open(sent_open),
+            BgpMessage::Open(received_open),
+            vec![],
+        );
+        let peer_up = match peer_up {
+            Ok(peer_up) => peer_up,
+            Err(err) => {
+                log::warn!("Couldn't build the BMP Peer Up message: {err:?}");
+                return;
+            }
+        };
+        self.up.replace(up);
+        self.down_reason.take();
+        self.send(BmpMessageValue::PeerUpNotification(peer_up));
+        let statistics_interval = self.exporter.exporter.statistics_interval;
+        if !statistics_interval.is_zero() {
+            let mut interval = tokio::time::interval(statistics_interval);
+            interval.reset();
+            self.statistics_timer.replace(interval);
+        }
+    }
+
+    /// Reason reported in the Peer Down message when the session goes down,
+    /// closing the session locally without a NOTIFICATION message is
+    /// assumed otherwise
+    pub(crate) fn set_down_reason(&mut self, reason: PeerDownNotificationReason) {
+        self.down_reason.replace(reason);
+    }
+
+    pub(crate) fn peer_down(&mut self) {
+        let reason = self
+            .down_reason
+            .take()
+            .unwrap_or(PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(0));
+        self.report_down(reason);
+    }
+
+    /// Report the peer down as it's not exported anymore
+    pub(crate) fn deconfigure(&mut self) {
+        self.report_down(PeerDownNotificationReason::PeerDeConfigured);
+    }
+
+    fn report_down(&mut self, reason: PeerDownNotificationReason) {
+        let Some(up) = self.up else {
+            return;
+        };
+        match PeerDownNotificationMessage::build(up.peer_header(false), reason) {
+            Ok(peer_down) => self.send(BmpMessageValue::PeerDownNotification(peer_down)),
+            Err(err) => log::warn!("Couldn't build the BMP Peer Down message: {err:?}"),
+        }
+        self.up.take();
+        self.statistics_timer.take();
+    }
+
+    /// Route Monitoring of an UPDATE message as received from the peer
+    pub(crate) fn pre_policy_update(&self, update: &BgpUpdateMessage) {
+        let Some(up) = &self.up else {
+            return;
+        };
+        self.route_monitoring(up.peer_header(false), update.clone());
+    }
+
+    /// Route Monitoring of the changes of the routes of the peer, the
+    /// unsupported address types are skipped
+    pub(crate) fn route_changes(&self, post_policy: bool, changes: &[RouteChange]) {
+        let Some(up) = &self.up else {
+            return;
+        };
+        for change in changes {
+            if let Some(update) = route_change_update(change) {
+                self.route_monitoring(up.peer_header(post_policy), update);
+            }
+        }
+    }
+
+    fn route_monitoring(&self, peer_header: PeerHeader, update: BgpUpdateMessage) {
+        match RouteMonitoringMessage::build(peer_header, BgpMessage::Update(update)) {
+            Ok(msg) => self.send(BmpMessageValue::RouteMonitoring(msg)),
+            Err(err) => log::warn!("Couldn't build the BMP Route Monitoring message: {err:?}"),
+        }
+    }
+
+    /// Count the routes rejected by the import policy, reported in the
+    /// Statistics Reports
+    pub(crate) fn add_rejected_prefixes(&mut self, rejected: usize) {
+        self.rejected_prefixes = self.rejected_prefixes.wrapping_add(rejected as u32);
+    }
+
+    pub(crate) fn statistics_report(&self, adj_rib_in: &AdjRibIn) {
+        let Some(up) = &self.up else {
+            return;
+        };
+        let mut per_address_type = HashMap::<AddressType, u64>::new();
+        for route in adj_rib_in.routes() {
+            *per_address_type
+                .entry(route.key().address_type())
+                .or_default() += 1;
+        }
+        let mut per_address_type: Vec<_> = per_address_type.into_iter().collect();
+        per_address_type.sort_by_key(|(address_type, _)| {
+            (
+                u16::from(address_type.address_family()),
+                u8::from(address_type.subsequent_address_family()),
+            )
+        });
+        let mut counters = vec![
+            StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(CounterU32::new(
+                self.rejected_prefixes,
+            )),
+            StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(adj_rib_in.len() as u64)),
+        ];
+        counters.extend(per_address_type.into_iter().map(|(address_type, routes)| {
+            StatisticsCounter::NumberOfRoutesInPerAfiSafiAdjRibIn(
+                address_type,
+                GaugeU64::new(routes),
+            )
+        }));
+        let report = StatisticsReportMessage::new(up.peer_header(false), counters);
+        self.send(BmpMessageValue::StatisticsReport(report));
+    }
+}
+
+/// Reason of a session going down after handling the connection event
+pub(crate) fn peer_down_reason<A>(
+    event: &ConnectionEvent<A>,
+    sent_notification: Option<&BgpNotificationMessage>,
+) -> PeerDownNotificationReason {
+    match event {
+        ConnectionEvent::NotifMsg(notif) => {
+            PeerDownNotificationReason::RemoteSystemClosedNotificationPduFollows(
+                BgpMessage::Notification(notif.clone()),
+            )
+        }
+        ConnectionEvent::TcpConnectionFails | ConnectionEvent::NotifMsgVerErr => {
+            PeerDownNotificationReason::RemoteSystemClosedNoData
+        }
+        _ => match sent_notification {
+            Some(notif) => PeerDownNotificationReason::LocalSystemClosedNotificationPduFollows(
+                BgpMessage::Notification(notif.clone()),
+            ),
+            None => PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(0),
+        },
+    }
+}
+
+/// UPDATE message carrying a single route change of the unicast address
+/// types
+fn route_change_update(change: &RouteChange) -> Option<BgpUpdateMessage> {
+    match change {
+        RouteChange::Announced(route) => announce_update(route),
+        RouteChange::Withdrawn(route) => withdraw_update(route),
+        RouteChange::EndOfRib(AddressType::Ipv4Unicast) => {
+            Some(BgpUpdateMessage::new(vec![], vec![], vec![]))
+        }
+        RouteChange::EndOfRib(AddressType::Ipv6Unicast) => {
+            let unreach = MpUnreach::Ipv6Unicast { nlri: vec![] };
+            let unreach =
+                path_attribute(true, false, PathAttributeValue::MpUnreach(unreach)).ok()?;
+            Some(BgpUpdateMessage::new(vec![], vec![unreach], vec![]))
+        }
+        RouteChange::EndOfRib(_) => None,
+    }
+}
+
+fn announce_update(route: &Route) -> Option<BgpUpdateMessage> {
+    let key = route.key();
+    let mut path_attributes = route.path_attributes().to_vec();
+    let has_next_hop = path_attributes
+        .iter()
+        .any(|attr| matches!(attr.value(), PathAttributeValue::NextHop(_)));
+    let mp_reach = match (key.address_type(), key.prefix(), route.next_hop()) {
+        (AddressType::Ipv4Unicast, IpNet::V4(net), _) if has_next_hop => {
+            let nlri = Ipv4UnicastAddress::new(key.path_id(), Ipv4Unicast::from_net(net).ok()?);
+            return Some(BgpUpdateMessage::new(vec![], path_attributes, vec![nlri]));
+        }
+        (AddressType::Ipv4Unicast, IpNet::V4(net), Some(next_hop)) => MpReach::Ipv4Unicast {
+            next_hop,
+            next_hop_local: None,
+            nlri: vec![Ipv4UnicastAddress::new(
+                key.path_id(),
+                Ipv4Unicast::from_net(net).ok()?,
+            )],
+        },
+        (AddressType::Ipv6Unicast, IpNet::V6(net), Some(IpAddr::V6(next_hop_global))) => {
+            MpReach::Ipv6Unicast {
+                next_hop_global,
+                next_hop_local: None,
+                nlri: vec![Ipv6UnicastAddress::new(
+                    key.path_id(),
+                    Ipv6Unicast::from_net(net).ok()?,
+                )],
+            }
+        }
+        _ => return None,
+    };
+    let mp_reach = path_attribute(true, false, PathAttributeValue::MpReach(mp_reach)).ok()?;
+    path_attributes.insert(0, mp_reach);
+    Some(BgpUpdateMessage::new(vec![], path_attributes, vec![]))
+}
+
+fn withdraw_update(route: &Route) -> Option<BgpUpdateMessage> {
+    let key = route.key();
+    match (key.address_type(), key.prefix()) {
+        (AddressType::Ipv4Unicast, IpNet::V4(net)) => {
+            let nlri = Ipv4UnicastAddress::new(key.path_id(), Ipv4Unicast::from_net(net).ok()?);
+            Some(BgpUpdateMessage::new(vec![nlri], vec![], vec![]))
+        }
+        (AddressType::Ipv6Unicast, IpNet::V6(net)) => {
+            let unreach = MpUnreach::Ipv6Unicast {
+                nlri: vec![Ipv6UnicastAddress::new(
+                    key.path_id(),
+                    Ipv6Unicast::from_net(net).ok()?,
+                )],
+            };
+            let unreach =
+                path_attribute(true, false, PathAttributeValue::MpUnreach(unreach)).ok()?;
+            Some(BgpUpdateMessage::new(vec![], vec![unreach], vec![]))
+        }
+        _ => None,
+    }
+}
@@ -30,19 +30,22 @@ use std::{
 };
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    net::TcpStream,
+    net::{TcpSocket, TcpStream},
 };
 use tokio_util::codec::{Decoder, Encoder, Framed};
 
 use netgauze_bgp_pkt::{
-    capabilities::BgpCapability,
+    capabilities::{
+        negotiate, BgpCapability, GracefulRestartAddressFamily, GracefulRestartCapability,
+        NegotiatedCapabilities,
+    },
     codec::{BgpCodec, BgpCodecDecoderError},
     iana::PathAttributeType,
     notification::{
         BgpNotificationMessage, FiniteStateMachineError, HoldTimerExpiredError, OpenMessageError,
         UpdateMessageError,
     },
-    open::BgpOpenMessage,
+    open::{BgpOpenMessage, BgpOpenMessageParameter},
     path_attribute::{InvalidPathAttribute, PathAttributeValue},
     update::BgpUpdateMessage,
     wire::{
@@ -56,12 +59,14 @@ use netgauze_bgp_pkt::{
     },
     BgpMessage,
 };
-use netgauze_iana::address_family::{AddressFamily, SubsequentAddressFamily};
+use netgauze_iana::address_family::{AddressFamily, AddressType, SubsequentAddressFamily};
 
 use crate::{
     events::{ConnectionEvent, UpdateTreatment},
     fsm::FsmStateError,
+    metrics::NotificationError,
     peer::{PeerConfig, PeerPolicy, PeerProperties},
+    socket::{bind_interface, set_dscp, set_tcp_keepalive, set_tcp_md5_key},
 };
 
 #[derive(Debug, Default, Copy, Clone)]
@@ -81,6 +86,7 @@ pub struct ConnectionStats {
     route_refresh_sent: u64,
     last_received: Option<DateTime<Utc>>,
     last_sent: Option<DateTime<Utc>>,
+    last_error: Option<NotificationError>,
 }
 
 impl ConnectionStats {
@@ -143,6 +149,11 @@ impl ConnectionStats {
     pub const fn last_sent(&self) -> Option<DateTime<Utc>> {
         self.last_sent
     }
+
+    /// Last NOTIFICATION message sent or received on the connection
+    pub const fn last_error(&self) -> Option<NotificationError> {
+        self.last_error
+    }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, strum_macros::Display)]
@@ -188,6 +199,8 @@ pub struct ConnectionConfig {
     hold_timer_duration_large_value: u16,
     keepalive_timer_duration: u16,
     idle_hold_duration: u16,
+    graceful_restart: bool,
+    graceful_restart_time: u16,
 }
 
 impl ConnectionConfig {
@@ -206,6 +219,12 @@ impl ConnectionConfig {
     pub const fn idle_hold_duration(&self) -> Duration {
         Duration::from_secs(self.idle_hold_duration as u64)
     }
+    pub const fn graceful_restart(&self) -> bool {
+        self.graceful_restart
+    }
+    pub const fn graceful_restart_time(&self) -> Duration {
+        Duration::from_secs(self.graceful_restart_time as u64)
+    }
 }
 impl From<&PeerConfig> for ConnectionConfig {
     fn from(peer_config: &PeerConfig) -> Self {
@@ -216,6 +235,8 @@ impl From<&PeerConfig> for ConnectionConfig {
             hold_timer_duration_large_value: peer_config.hold_timer_duration_large_value,
             keepalive_timer_duration: peer_config.keepalive_timer_duration,
             idle_hold_duration: peer_config.idle_hold_duration,
+            graceful_restart: peer_config.graceful_restart(),
+            graceful_restart_time: peer_config.graceful_restart_time,
         }
     }
 }
@@ -230,6 +251,8 @@ impl Default for ConnectionConfig {
             hold_timer_duration_large_value: 240,
             keepalive_timer_duration: 30,
             idle_hold_duration: 1,
+            graceful_restart: false,
+            graceful_restart_time: 120,
         }
     }
 }
@@ -276,6 +299,16 @@ impl ConnectionConfigBuilder {
         self
     }
 
+    pub const fn graceful_restart(mut self, value: bool) -> Self {
+        self.config.graceful_restart = value;
+        self
+    }
+
+    pub const fn graceful_restart_time(mut self, value: u16) -> Self {
+        self.config.graceful_restart_time = value;
+        self
+    }
+
     pub const fn build(self) -> ConnectionConfig {
         self.config
     }
@@ -303,6 +336,9 @@ pub struct Connection<
     #[pin]
     sent_capabilities: Option<Vec<BgpCapability>>,
     received_capabilities: Option<Vec<BgpCapability>>,
+    sent_open: Option<BgpOpenMessage>,
+    received_open: Option<BgpOpenMessage>,
+    sent_notification: Option<BgpNotificationMessage>,
     peer_hold_time: Option<u16>,
     remote_bgp_id: Option<Ipv4Addr>,
     #[pin]
@@ -352,6 +388,9 @@ impl<
             peer_bgp_id: None,
             sent_capabilities: None,
             received_capabilities: None,
+            sent_open: None,
+            received_open: None,
+            sent_notification: None,
             peer_hold_time: None,
             remote_bgp_id: None,
             inner,
@@ -415,12 +454,40 @@ impl<
         self.received_capabilities.as_ref()
     }
 
+    pub const fn sent_open(&self) -> Option<&BgpOpenMessage> {
+        self.sent_open.as_ref()
+    }
+
+    pub const fn received_open(&self) -> Option<&BgpOpenMessage> {
+        self.received_open.as_ref()
+    }
+
+    /// Last NOTIFICATION message sent to the peer
+    pub const fn sent_notification(&self) -> Option<&BgpNotificationMessage> {
+        self.sent_notification.as_ref()
+    }
+
+    /// Configured peer ASN, or the one received in the peer's open message
+    pub const fn peer_asn(&self) -> Option<u32> {
+        self.peer_asn
+    }
+
+    /// Capabilities negotiated with the peer, available once both open
+    /// messages are exchanged
+    pub fn negotiated_capabilities(&self) -> Option<NegotiatedCapabilities> {
+        Some(negotiate(
+            self.sent_capabilities.as_ref()?,
+            self.received_capabilities.as_ref()?,
+        ))
+    }
+
     fn read_open_msg(&mut self, open: &BgpOpenMessage) {
         self.peer_asn = Some(open.my_asn4());
         self.peer_bgp_id = Some(open.bgp_id());
         self.received_capabilities =
             Some(open.capabilities().iter().map(|x| (*x).clone()).collect());
         self.peer_hold_time = Some(open.hold_time());
+        self.received_open = Some(open.clone());
     }
 
     fn set_negotiated_timers(&mut self) {
@@ -499,6 +566,51 @@ impl<
         Ok(post_event)
     }
 
+    /// Add the Graceful Restart capability to the open message of the policy
+    /// when it's enabled and not advertised by the policy already. The routes
+    /// originated by the speaker are kept across sessions, hence the
+    /// forwarding state is preserved for all the advertised address types.
+    fn with_graceful_restart(&self, open: BgpOpenMessage) -> BgpOpenMessage {
+        let capabilities = open.capabilities();
+        if !self.config.graceful_restart
+            || capabilities
+                .iter()
+                .any(|cap| matches!(cap, BgpCapability::GracefulRestartCapability(_)))
+        {
+            return open;
+        }
+        let mut address_types = capabilities
+            .iter()
+            .filter_map(|cap| match cap {
+                BgpCapability::MultiProtocolExtensions(mp) => Some(mp.address_type()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if address_types.is_empty() {
+            address_types.push(AddressType::Ipv4Unicast);
+        }
+        let graceful_restart =
+            BgpCapability::GracefulRestartCapability(GracefulRestartCapability::new(
+                false,
+                false,
+                self.config.graceful_restart_time,
+                address_types
+                    .into_iter()
+                    .map(|address_type| GracefulRestartAddressFamily::new(true, address_type))
+                    .collect(),
+            ));
+        let mut params = open.params().clone();
+        match params.last_mut() {
+            Some(BgpOpenMessageParameter::Capabilities(capabilities)) => {
+                capabilities.push(graceful_restart)
+            }
+            None => params.push(BgpOpenMessageParameter::Capabilities(vec![
+                graceful_restart,
+            ])),
+        }
+        BgpOpenMessage::new(open.my_as(), open.hold_time(), open.bgp_id(), params)
+    }
+
     async fn handle_connected_event<P: PeerPolicy<A, I, D>>(
         &mut self,
         policy: &mut P,
@@ -508,6 +620,7 @@ impl<
             ConnectionEvent::DelayOpenTimerExpires => {
                 self.start_hold_timer();
                 let open = policy.open_message().await;
+                let open = self.with_graceful_restart(open);
                 self.send(BgpMessage::Open(open)).await?;
                 self.state = ConnectionState::OpenSent;
             }
@@ -516,6 +629,7 @@ impl<
                 if self.config.open_delay_timer_duration == 0 {
                     self.start_hold_timer();
                     let open = policy.open_message().await;
+                    let open = self.with_graceful_restart(open);
                     self.send(BgpMessage::Open(open)).await?;
                     self.state = ConnectionState::OpenSent;
                 } else {
@@ -536,6 +650,7 @@ impl<
                 self.read_open_msg(open);
                 self.set_negotiated_timers();
                 let open = policy.open_message().await;
+                let open = self.with_graceful_restart(open);
                 if !self.keepalive_timer_duration.is_zero() {
                     let mut interval = tokio::time::interval(self.keepalive_timer_duration);
                     interval.reset();
@@ -1263,9 +1378,6 @@ fn handle_update_message<A>(
     let mut bgp_mp_reach_count = 0;
     let mut bgp_mp_unreach_count = 0;
     for attr in update.path_attributes() {
-        if has_origin && has_asn_path {
-            break;
-        }
         if let PathAttributeValue::Origin(_) = attr.value() {
             has_origin = true;
         } else if let PathAttributeValue::AsPath(_) = attr.value() {
@@ -1395,6 +1507,7 @@ where
                                 }
                                 BgpMessage::Notification(notif) => {
                                     this.stats.notification_received += 1;
+                                    this.stats.last_error = Some(NotificationError::new(&notif, true));
                                     // Version error have a special event in BGP FSM
                                     if let BgpNotificationMessage::OpenMessageError(OpenMessageError::UnsupportedVersionNumber {value: _}) = &notif {
                                         Some(ConnectionEvent::NotifMsgVerErr)
@@ -1451,6 +1564,7 @@ impl<
                 this.stats.open_sent += 1;
                 this.sent_capabilities
                     .replace(open.capabilities().iter().map(|x| (*x).clone()).collect());
+                this.sent_open.replace(open.clone());
             }
             BgpMessage::Update(_) => {
                 match *this.keepalive_timer.as_mut() {
@@ -1459,8 +1573,10 @@ impl<
                 }
                 this.stats.update_sent += 1;
             }
-            BgpMessage::Notification(_) => {
+            BgpMessage::Notification(notif) => {
                 this.stats.notification_sent += 1;
+                this.stats.last_error = Some(NotificationError::new(notif, false));
+                this.sent_notification.replace(notif.clone());
             }
             BgpMessage::KeepAlive => {
                 match *this.keepalive_timer.as_mut() {
@@ -1496,7 +1612,9 @@ pub trait ActiveConnect<
         + Encoder<BgpMessage, Error = BgpMessageWritingError>,
 >
 {
-    async fn connect(&mut self, peer_addr: P) -> io::Result<I>;
+    /// Connect to the peer, the socket options are set according to the
+    /// configuration of the peer
+    async fn connect(&mut self, peer_addr: P, config: &PeerConfig) -> io::Result<I>;
 }
 
 #[derive(Debug, Clone)]
@@ -1504,7 +1622,35 @@ pub struct TcpActiveConnect;
 
 #[async_trait]
 impl ActiveConnect<SocketAddr, TcpStream, BgpCodec> for TcpActiveConnect {
-    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<TcpStream> {
-        TcpStream::connect(peer_addr).await
+    async fn connect(
+        &mut self,
+        peer_addr: SocketAddr,
+        config: &PeerConfig,
+    ) -> io::Result<TcpStream> {
+        let socket = if peer_addr.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        if let Some(key) = config.tcp_md5_key() {
+            set_tcp_md5_key(&socket, peer_addr.ip(), Some(&key))?;
+        }
+        // The DSCP marking is best effort, the session is established without it
+        if let Err(err) = set_dscp(&socket, peer_addr.ip(), config.dscp()) {
+            log::warn!(
+                "[{peer_addr}] Couldn't set DSCP {} on the connection: {err}",
+                config.dscp()
+            );
+        }
+        if let Some(keepalive) = config.tcp_keepalive() {
+            set_tcp_keepalive(&socket, &keepalive)?;
+        }
+        if let Some(interface) = config.bind_interface() {
+            bind_interface(&socket, &interface)?;
+        }
+        if let Some(local_address) = config.local_address() {
+            socket.bind(SocketAddr::new(local_address, 0))?;
+        }
+        socket.connect(peer_addr).await
     }
 }
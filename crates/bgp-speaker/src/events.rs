@@ -316,6 +316,38 @@ pub enum BgpEvent<A> {
     RouteRefresh(BgpRouteRefreshMessage),
 
     RouteRefreshErr(RouteRefreshError),
+
+    /// The Restart Time advertised by the peer elapsed before the session is
+    /// established again, the stale routes of the peer are removed.
+    ///
+    /// This event is not defined in RFC4271.
+    /// See [RFC4724 Section 4.2](https://datatracker.ietf.org/doc/html/rfc4724#section-4.2).
+    GracefulRestartTimerExpires,
+
+    /// The End-of-RIB markers of the restarting peer were not all received
+    /// after the session is established again, the remaining stale routes of
+    /// the peer are removed.
+    ///
+    /// This event is not defined in RFC4271.
+    /// See [RFC4724 Section 4.2](https://datatracker.ietf.org/doc/html/rfc4724#section-4.2).
+    StaleRoutesTimerExpires,
+
+    /// The peer closed for exceeding its max number of prefixes waited long
+    /// enough, it is started again with an [`BgpEvent::AutomaticStart`].
+    ///
+    /// This event is not defined in RFC4271.
+    MaxPrefixesRestartTimerExpires,
+
+    /// The peer was connected to or disconnected from a BMP station, when
+    /// connected the established session is reported to the station.
+    ///
+    /// This event is not defined in RFC4271, see [`crate::bmp`].
+    BmpStationChanged,
+
+    /// The interval between two BMP Statistics Reports of the peer elapsed.
+    ///
+    /// This event is not defined in RFC4271, see [`crate::bmp`].
+    BmpStatisticsTimerExpires,
 }
 
 /// Subset of BGP Events defined [RFC4271](https://datatracker.ietf.org/doc/html/rfc4271) that
@@ -20,13 +20,21 @@ use netgauze_bgp_pkt::codec::BgpCodec;
 
 pub type BgpFramed = Framed<TcpStream, BgpCodec>;
 
+pub mod admin;
+pub mod bmp;
 pub mod connection;
 pub mod events;
 pub mod fsm;
 pub mod listener;
+pub mod metrics;
 pub mod peer;
 pub mod peer_controller;
+pub mod rib;
+pub mod route_policy;
+pub mod socket;
 pub mod supervisor;
+pub mod update;
+pub mod update_group;
 
 #[cfg(test)]
 mod tests;
@@ -15,23 +15,32 @@
 
 use futures_core::Stream;
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fmt::{Debug, Display},
     io,
     net::{IpAddr, SocketAddr},
+    ops::RangeInclusive,
     pin::Pin,
     task::{Context, Poll},
 };
 
 use crate::connection::TcpActiveConnect;
 use futures_util::stream::FuturesUnordered;
+use ipnet::IpNet;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
     net::{TcpListener, TcpStream},
+    sync::mpsc,
 };
 use tokio_stream::StreamExt;
 
-use crate::{fsm::FsmState, peer_controller::PeerHandle, supervisor::PeersSupervisor};
+use crate::{
+    admin::{admin_channel, AdminHandle, AdminRequest},
+    fsm::FsmState,
+    peer_controller::PeerHandle,
+    socket::{set_tcp_md5_key, TcpMd5Key},
+    supervisor::PeersSupervisor,
+};
 
 /// A modified version of Tokio's TcpListenerStream wrapper that returns the
 /// peer socket along the incoming stream
@@ -64,6 +73,50 @@ impl Stream for TcpListenerStream {
     }
 }
 
+/// Unconfigured peers accepted by the [`BgpListener`], e.g., the clients of a
+/// route server. A peer connecting from one of the prefixes is created with
+/// the configuration of the [`crate::supervisor::PeerGroup`] and removed once
+/// its session goes back to Idle.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DynamicPeerRange {
+    prefixes: Vec<IpNet>,
+    asns: Vec<RangeInclusive<u32>>,
+    group: String,
+}
+
+impl DynamicPeerRange {
+    pub fn new(prefixes: Vec<IpNet>, group: &str) -> Self {
+        Self {
+            prefixes,
+            asns: vec![],
+            group: group.to_string(),
+        }
+    }
+
+    /// Accept only peers with an ASN in the range, any ASN is accepted when no
+    /// range is given
+    pub fn with_asns(mut self, asns: RangeInclusive<u32>) -> Self {
+        self.asns.push(asns);
+        self
+    }
+
+    pub const fn prefixes(&self) -> &Vec<IpNet> {
+        &self.prefixes
+    }
+
+    pub const fn asns(&self) -> &Vec<RangeInclusive<u32>> {
+        &self.asns
+    }
+
+    pub fn group(&self) -> &str {
+        &self.group
+    }
+
+    pub fn contains(&self, peer_ip: &IpAddr) -> bool {
+        self.prefixes.iter().any(|prefix| prefix.contains(peer_ip))
+    }
+}
+
 #[derive(Debug)]
 pub struct BgpListener<A: Display, I: AsyncWrite + AsyncRead> {
     sockets: Vec<SocketAddr>,
@@ -72,6 +125,16 @@ pub struct BgpListener<A: Display, I: AsyncWrite + AsyncRead> {
     peers: HashMap<IpAddr, PeerHandle<A, I>>,
     // TODO: change the flag to a policy trait
     allow_dynamic_peers: bool,
+    dynamic_peer_ranges: Vec<DynamicPeerRange>,
+    /// Peers created by the listener, removed once back to Idle
+    dynamic_peers: HashSet<IpAddr>,
+    idle_tx: mpsc::UnboundedSender<IpAddr>,
+    idle_rx: mpsc::UnboundedReceiver<IpAddr>,
+    admin: AdminHandle<IpAddr, A>,
+    admin_rx: mpsc::UnboundedReceiver<AdminRequest<IpAddr, A>>,
+    /// Keys of the TCP MD5 signatures set on the listening sockets, indexed by
+    /// ip address of the peer
+    tcp_md5_keys: HashMap<IpAddr, TcpMd5Key>,
 }
 
 impl<
@@ -80,16 +143,55 @@ impl<
     > BgpListener<A, I>
 {
     pub fn new(sockets: Vec<SocketAddr>, allow_dynamic_peers: bool) -> Self {
+        let (idle_tx, idle_rx) = mpsc::unbounded_channel();
+        let (admin, admin_rx) = admin_channel();
         Self {
             sockets,
             peers: HashMap::new(),
             allow_dynamic_peers,
+            dynamic_peer_ranges: vec![],
+            dynamic_peers: HashSet::new(),
+            idle_tx,
+            idle_rx,
+            admin,
+            admin_rx,
+            tcp_md5_keys: HashMap::new(),
         }
     }
 
+    /// Handle administrating the peers while the listener is running, the
+    /// peers added by the handle are registered in the listener
+    pub fn admin_handle(&self) -> AdminHandle<IpAddr, A> {
+        self.admin.clone()
+    }
+
     pub fn reg_peer(&mut self, peer_ip: IpAddr, peer_handle: PeerHandle<A, I>) {
         self.peers.insert(peer_ip, peer_handle);
     }
+
+    /// Accept the connections of unconfigured peers in the range. The ranges
+    /// are checked in the order they are added.
+    pub fn reg_dynamic_peer_range(&mut self, range: DynamicPeerRange) {
+        self.dynamic_peer_ranges.push(range);
+    }
+
+    pub const fn dynamic_peer_ranges(&self) -> &Vec<DynamicPeerRange> {
+        &self.dynamic_peer_ranges
+    }
+
+    /// The first range containing the peer
+    pub fn dynamic_peer_range(&self, peer_ip: &IpAddr) -> Option<&DynamicPeerRange> {
+        self.dynamic_peer_ranges
+            .iter()
+            .find(|range| range.contains(peer_ip))
+    }
+
+    /// Accept only the connections from the peer signed with the key, usually
+    /// the [`crate::peer::PeerConfig::tcp_md5_key`] of the peer. The keys are
+    /// set on the listening sockets when [`BgpListener::run`] starts.
+    pub fn reg_peer_tcp_md5_key(&mut self, peer_ip: IpAddr, key: TcpMd5Key) {
+        self.tcp_md5_keys.insert(peer_ip, key);
+    }
 }
 
 impl BgpListener<SocketAddr, TcpStream> {
@@ -100,55 +202,111 @@ impl BgpListener<SocketAddr, TcpStream> {
         stream: TcpStream,
         peer_supervisor: &mut PeersSupervisor<IpAddr, SocketAddr, TcpStream>,
     ) {
-        match self.peers.get_mut(&peer_key) {
-            Some(peer_handle) => {
-                log::info!("Accepted Connection for peer {peer_key}");
-                if let Err(err) = peer_handle.accept_connection(peer_addr, stream) {
-                    log::error!("Error sending event to peer: {err:?}");
-                }
+        if let Some(peer_handle) = self.peers.get_mut(&peer_key) {
+            log::info!("Accepted Connection for peer {peer_key}");
+            if let Err(err) = peer_handle.accept_connection(peer_addr, stream) {
+                log::error!("Error sending event to peer: {err:?}");
+            }
+            return;
+        }
+        let dynamic_peer = match self.dynamic_peer_range(&peer_key) {
+            Some(range) => {
+                log::info!(
+                    "[{peer_addr}] Dynamic connection in the range of group {}",
+                    range.group()
+                );
+                peer_supervisor.dynamic_group_peer(
+                    peer_key,
+                    peer_addr,
+                    range.group(),
+                    range.asns().clone(),
+                    TcpActiveConnect,
+                )
+            }
+            None if self.allow_dynamic_peers => {
+                // TODO: rewrite for more clear logic and dynamic peer handling factory
+                peer_supervisor.dynamic_peer(peer_key, peer_addr, TcpActiveConnect)
             }
             None => {
-                if !self.allow_dynamic_peers {
-                    log::info!("No peer configured for: {peer_addr}");
-                } else {
-                    // TODO: rewrite for more clear logic and dynamic peer handling factory
-                    if let Ok((mut rx, mut peer_handle)) =
-                        peer_supervisor.dynamic_peer(peer_key, peer_addr, TcpActiveConnect)
-                    {
-                        if let Err(err) = peer_handle.start() {
-                            log::error!("Error starting dynamic peer: {err:?}");
-                            return;
-                        }
-                        rx.recv().await;
-                        if let Err(err) = peer_handle.accept_connection(peer_addr, stream) {
-                            log::error!("[{peer_addr}] Dynamic connection error sending event to peer: {err:?}");
+                log::info!("No peer configured for: {peer_addr}");
+                return;
+            }
+        };
+        let (mut rx, mut peer_handle) = match dynamic_peer {
+            Ok(peer) => peer,
+            Err(err) => {
+                log::error!("[{peer_addr}] Error creating dynamic peer: {err:?}");
+                return;
+            }
+        };
+        if let Err(err) = peer_handle.start() {
+            log::error!("Error starting dynamic peer: {err:?}");
+            peer_supervisor.remove_peer(&peer_key);
+            return;
+        }
+        rx.recv().await;
+        if let Err(err) = peer_handle.accept_connection(peer_addr, stream) {
+            log::error!("[{peer_addr}] Dynamic connection error sending event to peer: {err:?}");
+            peer_supervisor.remove_peer(&peer_key);
+            return;
+        }
+        while let Some(Ok((state, event))) = rx.recv().await {
+            log::info!("[{peer_addr}] Dynamic connection at state {state} GOT EVENT: {event:?}");
+            if state == FsmState::Idle {
+                log::warn!(
+                    "[{peer_addr}] Dynamic Connection failed before reaching OpenConfirm state"
+                );
+                peer_supervisor.remove_peer(&peer_key);
+                return;
+            }
+            if state == FsmState::OpenConfirm {
+                log::info!("[{peer_addr}] Accepted Dynamic Connection: {peer_addr}");
+                self.peers.insert(peer_key, peer_handle);
+                self.dynamic_peers.insert(peer_key);
+                let idle_tx = self.idle_tx.clone();
+                tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        log::info!("[{peer_addr}] dynamic connection got event: {event:?}");
+                        if matches!(event, Ok((FsmState::Idle, _))) {
+                            let _ = idle_tx.send(peer_key);
                             return;
                         }
-                        while let Some(Ok((state, event))) = rx.recv().await {
-                            log::info!("[{peer_addr}] Dynamic connection at state {state} GOT EVENT: {event:?}");
-                            if state == FsmState::Idle {
-                                log::warn!("[{peer_addr}] Dynamic Connection failed before reaching OpenConfirm state");
-                                return;
-                            }
-                            if state == FsmState::OpenConfirm {
-                                log::info!(
-                                    "[{peer_addr}] Accepted Dynamic Connection: {peer_addr}"
-                                );
-                                self.peers.insert(peer_key, peer_handle);
-                                tokio::spawn(async move {
-                                    while let Some(event) = rx.recv().await {
-                                        log::info!(
-                                            "[{peer_addr}] dynamic connection got event: {event:?}"
-                                        );
-                                    }
-                                });
-                                return;
-                            }
-                        }
                     }
-                }
+                });
+                return;
             }
         }
+        peer_supervisor.remove_peer(&peer_key);
+    }
+
+    /// Tear down a peer created by the listener once its session is Idle
+    fn remove_dynamic_peer(
+        &mut self,
+        peer_key: IpAddr,
+        peer_supervisor: &mut PeersSupervisor<IpAddr, SocketAddr, TcpStream>,
+    ) {
+        if self.dynamic_peers.remove(&peer_key) {
+            log::info!("[{peer_key}] Removing idle dynamic peer");
+            self.peers.remove(&peer_key);
+            peer_supervisor.remove_peer(&peer_key);
+        }
+    }
+
+    async fn handle_admin_request(
+        &mut self,
+        request: AdminRequest<IpAddr, SocketAddr>,
+        peer_supervisor: &mut PeersSupervisor<IpAddr, SocketAddr, TcpStream>,
+    ) {
+        if let AdminRequest::RemovePeer(peer_key, _) = &request {
+            self.peers.remove(peer_key);
+            self.dynamic_peers.remove(peer_key);
+        }
+        if let Some(peer_handle) = peer_supervisor
+            .handle_admin_request(request, TcpActiveConnect)
+            .await
+        {
+            self.reg_peer(peer_handle.peer_addr().ip(), peer_handle);
+        }
     }
 
     pub async fn run(
@@ -160,6 +318,15 @@ impl BgpListener<SocketAddr, TcpStream> {
 
         for socket in &self.sockets {
             let listener = TcpListener::bind(socket).await?;
+            for (peer_ip, key) in &self.tcp_md5_keys {
+                let peer_ip = match (socket, peer_ip) {
+                    (SocketAddr::V4(_), IpAddr::V6(_)) => continue,
+                    // IPv4 peers connect to dual-stack sockets with mapped addresses
+                    (SocketAddr::V6(_), IpAddr::V4(ip)) => IpAddr::V6(ip.to_ipv6_mapped()),
+                    _ => *peer_ip,
+                };
+                set_tcp_md5_key(&listener, peer_ip, Some(key))?;
+            }
             let listener_stream = TcpListenerStream::new(listener);
             listening_sockets.push(listener_stream);
         }
@@ -169,9 +336,22 @@ impl BgpListener<SocketAddr, TcpStream> {
                 listen_futures.push(incoming.next());
             }
             log::info!("BGP Listener listening on sockets: {:?}", self.sockets);
-            while let Some(Some(Ok((stream, peer_addr)))) = listen_futures.next().await {
-                self.accept_peer_connection(peer_addr.ip(), peer_addr, stream, peer_supervisor)
-                    .await;
+            loop {
+                tokio::select! {
+                    incoming = listen_futures.next() => {
+                        let Some(Some(Ok((stream, peer_addr)))) = incoming else {
+                            break;
+                        };
+                        self.accept_peer_connection(peer_addr.ip(), peer_addr, stream, peer_supervisor)
+                            .await;
+                    }
+                    Some(peer_key) = self.idle_rx.recv() => {
+                        self.remove_dynamic_peer(peer_key, peer_supervisor);
+                    }
+                    Some(request) = self.admin_rx.recv() => {
+                        self.handle_admin_request(request, peer_supervisor).await;
+                    }
+                }
             }
         }
     }
@@ -0,0 +1,134 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Metrics of the peers modeled after the BGP MIB
+//! [RFC4273](https://datatracker.ietf.org/doc/html/rfc4273).
+//!
+//! A [`PeerMetrics`] snapshot is available on demand with
+//! [`crate::peer_controller::PeerHandle::peer_metrics`], or pushed to a
+//! [`MetricsRecorder`] after every BGP event of the peer. With the
+//! `prometheus` feature, [`prometheus::PrometheusMetrics`] exports the metrics
+//! of the peers to a Prometheus registry.
+
+use std::fmt::{Debug, Formatter};
+
+use netgauze_bgp_pkt::{iana::BgpErrorNotificationCode, notification::BgpNotificationMessage};
+
+use crate::{connection::ConnectionStats, fsm::FsmState, peer::PeerStats};
+
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+
+/// Error code and subcode of the last NOTIFICATION message sent or received,
+/// see `bgpPeerLastError` in RFC4273
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NotificationError {
+    code: BgpErrorNotificationCode,
+    sub_code: u8,
+    received: bool,
+}
+
+impl NotificationError {
+    pub fn new(notification: &BgpNotificationMessage, received: bool) -> Self {
+        Self {
+            code: notification.code(),
+            sub_code: notification.sub_code(),
+            received,
+        }
+    }
+
+    pub const fn code(&self) -> BgpErrorNotificationCode {
+        self.code
+    }
+
+    pub const fn sub_code(&self) -> u8 {
+        self.sub_code
+    }
+
+    /// The NOTIFICATION message was received from the peer, otherwise it was
+    /// sent to the peer
+    pub const fn received(&self) -> bool {
+        self.received
+    }
+}
+
+/// Snapshot of the metrics of a peer
+#[derive(Debug, Copy, Clone)]
+pub struct PeerMetrics {
+    fsm_state: FsmState,
+    stats: PeerStats,
+    connection_stats: Option<ConnectionStats>,
+    prefixes_received: usize,
+    prefixes_stale: usize,
+}
+
+impl PeerMetrics {
+    pub const fn new(
+        fsm_state: FsmState,
+        stats: PeerStats,
+        connection_stats: Option<ConnectionStats>,
+        prefixes_received: usize,
+        prefixes_stale: usize,
+    ) -> Self {
+        Self {
+            fsm_state,
+            stats,
+            connection_stats,
+            prefixes_received,
+            prefixes_stale,
+        }
+    }
+
+    pub const fn fsm_state(&self) -> FsmState {
+        self.fsm_state
+    }
+
+    /// FSM transitions, flaps and last error of the peer
+    pub const fn stats(&self) -> &PeerStats {
+        &self.stats
+    }
+
+    /// Messages exchanged by type on the main connection, the counters start
+    /// from zero with every new connection
+    pub const fn connection_stats(&self) -> Option<&ConnectionStats> {
+        self.connection_stats.as_ref()
+    }
+
+    /// Routes in the Adj-RIB-In of the peer, including the stale routes
+    pub const fn prefixes_received(&self) -> usize {
+        self.prefixes_received
+    }
+
+    /// Routes retained as stale while the peer restarts
+    pub const fn prefixes_stale(&self) -> usize {
+        self.prefixes_stale
+    }
+}
+
+/// Receive the metrics of the peers, see
+/// [`crate::peer_controller::PeerHandle::set_metrics_recorder`]. `peer` is the
+/// peer key formatted with [`std::fmt::Display`].
+pub trait MetricsRecorder: Send + Sync {
+    fn record(&self, peer: &str, metrics: &PeerMetrics);
+
+    /// The peer is removed or doesn't report to the recorder anymore
+    fn remove(&self, peer: &str);
+}
+
+impl Debug for dyn MetricsRecorder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "MetricsRecorder")
+    }
+}
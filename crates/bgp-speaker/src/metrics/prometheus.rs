@@ -0,0 +1,235 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Export the metrics of the peers to Prometheus.
+//!
+//! [`PrometheusMetrics`] is registered once in a [`::prometheus::Registry`]
+//! and a clone of it is set as the [`MetricsRecorder`] of the peers. The
+//! metrics are labeled with the peer key, and the counters of the messages
+//! restart from zero with every new connection.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use ::prometheus::{
+    core::{Collector, Desc},
+    proto::MetricFamily,
+    IntCounterVec, IntGaugeVec, Opts,
+};
+
+use crate::metrics::{MetricsRecorder, PeerMetrics};
+
+const NAMESPACE: &str = "bgp_peer";
+
+/// Clones share the recorded metrics
+#[derive(Debug, Clone)]
+pub struct PrometheusMetrics {
+    peers: Arc<Mutex<HashMap<String, PeerMetrics>>>,
+    fsm_state: IntGaugeVec,
+    fsm_transitions: IntCounterVec,
+    established_transitions: IntCounterVec,
+    flaps: IntCounterVec,
+    messages_received: IntCounterVec,
+    messages_sent: IntCounterVec,
+    last_error: IntGaugeVec,
+    prefixes_received: IntGaugeVec,
+    prefixes_stale: IntGaugeVec,
+}
+
+impl PrometheusMetrics {
+    pub fn new() -> Result<Self, ::prometheus::Error> {
+        let opts = |name: &str, help: &str| Opts::new(name, help).namespace(NAMESPACE);
+        Ok(Self {
+            peers: Arc::new(Mutex::new(HashMap::new())),
+            fsm_state: IntGaugeVec::new(
+                opts("fsm_state", "Current FSM state of the peer"),
+                &["peer", "state"],
+            )?,
+            fsm_transitions: IntCounterVec::new(
+                opts("fsm_transitions_total", "FSM state transitions"),
+                &["peer"],
+            )?,
+            established_transitions: IntCounterVec::new(
+                opts(
+                    "established_transitions_total",
+                    "Transitions to the Established state",
+                ),
+                &["peer"],
+            )?,
+            flaps: IntCounterVec::new(
+                opts("flaps_total", "Transitions out of the Established state"),
+                &["peer"],
+            )?,
+            messages_received: IntCounterVec::new(
+                opts(
+                    "messages_received_total",
+                    "Messages received on the current connection",
+                ),
+                &["peer", "type"],
+            )?,
+            messages_sent: IntCounterVec::new(
+                opts(
+                    "messages_sent_total",
+                    "Messages sent on the current connection",
+                ),
+                &["peer", "type"],
+            )?,
+            last_error: IntGaugeVec::new(
+                opts(
+                    "last_error",
+                    "Code and subcode of the last NOTIFICATION message",
+                ),
+                &["peer", "code", "sub_code", "direction"],
+            )?,
+            prefixes_received: IntGaugeVec::new(
+                opts("prefixes_received", "Routes in the Adj-RIB-In"),
+                &["peer"],
+            )?,
+            prefixes_stale: IntGaugeVec::new(
+                opts("prefixes_stale", "Stale routes of a restarting peer"),
+                &["peer"],
+            )?,
+        })
+    }
+
+    fn counters(&self) -> [&IntCounterVec; 5] {
+        [
+            &self.fsm_transitions,
+            &self.established_transitions,
+            &self.flaps,
+            &self.messages_received,
+            &self.messages_sent,
+        ]
+    }
+
+    fn gauges(&self) -> [&IntGaugeVec; 4] {
+        [
+            &self.fsm_state,
+            &self.last_error,
+            &self.prefixes_received,
+            &self.prefixes_stale,
+        ]
+    }
+
+    fn update(&self, peer: &str, metrics: &PeerMetrics) {
+        let stats = metrics.stats();
+        let state = metrics.fsm_state().to_string();
+        self.fsm_state.with_label_values(&[peer, &state]).set(1);
+        self.fsm_transitions
+            .with_label_values(&[peer])
+            .inc_by(stats.fsm_transitions());
+        self.established_transitions
+            .with_label_values(&[peer])
+            .inc_by(stats.established_transitions());
+        self.flaps.with_label_values(&[peer]).inc_by(stats.flaps());
+        if let Some(last_error) = stats.last_error() {
+            let code = (last_error.code() as u8).to_string();
+            let sub_code = last_error.sub_code().to_string();
+            let direction = if last_error.received() {
+                "received"
+            } else {
+                "sent"
+            };
+            self.last_error
+                .with_label_values(&[peer, &code, &sub_code, direction])
+                .set(1);
+        }
+        self.prefixes_received
+            .with_label_values(&[peer])
+            .set(metrics.prefixes_received() as i64);
+        self.prefixes_stale
+            .with_label_values(&[peer])
+            .set(metrics.prefixes_stale() as i64);
+        if let Some(connection) = metrics.connection_stats() {
+            for (message_type, received, sent) in [
+                ("open", connection.open_received(), connection.open_sent()),
+                (
+                    "update",
+                    connection.update_received(),
+                    connection.update_sent(),
+                ),
+                (
+                    "keepalive",
+                    connection.keepalive_received(),
+                    connection.keepalive_sent(),
+                ),
+                (
+                    "notification",
+                    connection.notification_received(),
+                    connection.notification_sent(),
+                ),
+                (
+                    "route_refresh",
+                    connection.route_refresh_received(),
+                    connection.route_refresh_sent(),
+                ),
+            ] {
+                self.messages_received
+                    .with_label_values(&[peer, message_type])
+                    .inc_by(received);
+                self.messages_sent
+                    .with_label_values(&[peer, message_type])
+                    .inc_by(sent);
+            }
+        }
+    }
+}
+
+impl MetricsRecorder for PrometheusMetrics {
+    fn record(&self, peer: &str, metrics: &PeerMetrics) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.insert(peer.to_string(), *metrics);
+        }
+    }
+
+    fn remove(&self, peer: &str) {
+        if let Ok(mut peers) = self.peers.lock() {
+            peers.remove(peer);
+        }
+    }
+}
+
+impl Collector for PrometheusMetrics {
+    fn desc(&self) -> Vec<&Desc> {
+        self.counters()
+            .into_iter()
+            .flat_map(|counter| counter.desc())
+            .chain(self.gauges().into_iter().flat_map(|gauge| gauge.desc()))
+            .collect()
+    }
+
+    /// The metrics are built from the last snapshot recorded for each peer
+    fn collect(&self) -> Vec<MetricFamily> {
+        let Ok(peers) = self.peers.lock() else {
+            return vec![];
+        };
+        for counter in self.counters() {
+            counter.reset();
+        }
+        for gauge in self.gauges() {
+            gauge.reset();
+        }
+        for (peer, metrics) in peers.iter() {
+            self.update(peer, metrics);
+        }
+        self.counters()
+            .into_iter()
+            .flat_map(|counter| counter.collect())
+            .chain(self.gauges().into_iter().flat_map(|gauge| gauge.collect()))
+            .collect()
+    }
+}
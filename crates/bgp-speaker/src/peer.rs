@@ -14,37 +14,54 @@
 // limitations under the License.
 
 use std::{
+    collections::BTreeMap,
     fmt::{Debug, Display, Formatter},
     marker::PhantomData,
-    net::Ipv4Addr,
-    ops::Add,
+    net::{IpAddr, Ipv4Addr},
+    ops::{Add, RangeInclusive},
+    sync::Arc,
     time::Duration,
 };
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use futures::StreamExt;
 use futures_util::SinkExt;
+use ipnet::IpNet;
 use tokio::{
     io::{AsyncRead, AsyncWrite},
-    sync::oneshot,
+    sync::{mpsc, oneshot},
     time::Interval,
 };
 use tokio_util::codec::{Decoder, Encoder, Framed};
 
 use netgauze_bgp_pkt::{
-    capabilities::{BgpCapability, FourOctetAsCapability},
+    capabilities::{
+        AddPathAddressFamily, AddPathCapability, BgpCapability, FourOctetAsCapability,
+        GracefulRestartCapability,
+    },
     codec::{BgpCodecDecoderError, BgpCodecInitializer},
     iana::{BgpCapabilityCode, AS_TRANS},
     notification::{BgpNotificationMessage, CeaseError, OpenMessageError},
     open::{BgpOpenMessage, BgpOpenMessageParameter},
+    update::BgpUpdateMessage,
     wire::{deserializer::BgpParsingIgnoredErrors, serializer::BgpMessageWritingError},
     BgpMessage,
 };
+use netgauze_bmp_pkt::PeerDownNotificationReason;
+use netgauze_iana::address_family::AddressType;
 
 use crate::{
+    bmp::{peer_down_reason, BmpMonitor, BmpMonitorEvent, BmpPeerExporter},
     connection::{ActiveConnect, Connection, ConnectionState, ConnectionStats, ConnectionType},
     events::{BgpEvent, ConnectionEvent},
     fsm::{FsmState, FsmStateError},
+    metrics::{MetricsRecorder, NotificationError, PeerMetrics},
+    rib::{AdjRibIn, Route, RouteChange},
+    route_policy::{PolicyResult, RoutePolicy},
+    socket::{InterfaceName, TcpKeepalive, TcpMd5Key, DSCP_CS6},
+    update::{OriginatedRoute, RouteAttributes, UpdateBuilder},
+    update_group::{SharedUpdates, UpdateGroup},
 };
 
 pub type PeerResult<A> = Result<BgpEvent<A>, FsmStateError<A>>;
@@ -86,6 +103,13 @@ pub trait PeerPolicy<
 /// capability and rejecting some capabilities. For this policy to be effective,
 /// OpenDelayTimer must be set to large enough value. Otherwise, only initially
 /// defined `capabilities` are sent to the peer.
+///
+/// The ADD-PATH capability of the peer is not echoed back when ADD-PATH is
+/// configured with [`EchoCapabilitiesPolicy::set_add_path`].
+///
+/// The OPEN messages of peers with an ASN outside of
+/// [`EchoCapabilitiesPolicy::set_allowed_peer_asns`] are rejected with a Bad
+/// Peer AS error.
 #[derive(Debug, Clone)]
 pub struct EchoCapabilitiesPolicy<A, I, D> {
     my_asn: u32,
@@ -95,6 +119,8 @@ pub struct EchoCapabilitiesPolicy<A, I, D> {
     hold_timer_duration: u16,
     capabilities: Vec<BgpCapability>,
     reject_capabilities: Vec<BgpCapability>,
+    add_path: Vec<AddPathAddressFamily>,
+    allowed_peer_asns: Vec<RangeInclusive<u32>>,
     peer_capabilities: Vec<BgpCapability>,
     _address_marker: PhantomData<A>,
     _inner_marker: PhantomData<I>,
@@ -118,6 +144,8 @@ impl<A, I, D> EchoCapabilitiesPolicy<A, I, D> {
             hold_timer_duration,
             capabilities,
             reject_capabilities,
+            add_path: Vec::new(),
+            allowed_peer_asns: Vec::new(),
             peer_capabilities: Vec::new(),
             _address_marker: PhantomData,
             _inner_marker: PhantomData,
@@ -132,6 +160,30 @@ impl<A, I, D> EchoCapabilitiesPolicy<A, I, D> {
     pub fn send_asn4_cap_by_default(&mut self, value: bool) {
         self.send_asn4_cap_by_default = value;
     }
+
+    /// Address types advertised with the ADD-PATH capability and whether
+    /// sending and receiving multiple paths is enabled for each of them
+    pub const fn add_path(&self) -> &Vec<AddPathAddressFamily> {
+        &self.add_path
+    }
+
+    /// Advertise the ADD-PATH capability defined in
+    /// [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911), an empty list
+    /// doesn't advertise the capability
+    pub fn set_add_path(&mut self, add_path: Vec<AddPathAddressFamily>) {
+        self.add_path = add_path;
+    }
+
+    /// ASNs accepted from the peer, any ASN is accepted when empty
+    pub const fn allowed_peer_asns(&self) -> &Vec<RangeInclusive<u32>> {
+        &self.allowed_peer_asns
+    }
+
+    /// Restrict the ASNs accepted from peers with
+    /// [`PeerProperties::allow_dynamic_as`]
+    pub fn set_allowed_peer_asns(&mut self, asns: Vec<RangeInclusive<u32>>) {
+        self.allowed_peer_asns = asns;
+    }
 }
 
 #[async_trait]
@@ -167,9 +219,23 @@ impl<
             capabilities.insert(0, asn4_cap);
         }
 
+        let add_path = !self.add_path.is_empty();
+        if add_path
+            && !capabilities
+                .iter()
+                .any(|cap| matches!(cap, BgpCapability::AddPath(_)))
+        {
+            capabilities.push(BgpCapability::AddPath(AddPathCapability::new(
+                self.add_path.clone(),
+            )));
+        }
+
         for cap in &self.peer_capabilities {
             // Check that the capability has not been added before and not in the reject
             // list
+            if add_path && matches!(cap, BgpCapability::AddPath(_)) {
+                continue;
+            }
             if !self.capabilities.contains(cap) && !self.reject_capabilities.contains(cap) {
                 capabilities.push(cap.clone());
             }
@@ -194,6 +260,16 @@ impl<
         match &event {
             ConnectionEvent::BGPOpen(open) | ConnectionEvent::BGPOpenWithDelayOpenTimer(open) => {
                 let asn = open.my_asn4();
+                if !self.allowed_peer_asns.is_empty()
+                    && !self
+                        .allowed_peer_asns
+                        .iter()
+                        .any(|asns| asns.contains(&asn))
+                {
+                    return ConnectionEvent::BGPOpenMsgErr(OpenMessageError::BadPeerAs {
+                        value: asn.to_be_bytes().to_vec(),
+                    });
+                }
                 self.remote_as.replace(asn);
                 self.peer_capabilities = open
                     .capabilities()
@@ -235,6 +311,9 @@ impl<
 pub enum PeerAdminEvents<A, I: AsyncWrite + AsyncRead> {
     ManualStart,
     ManualStop,
+    /// [`PeerAdminEvents::ManualStop`] sending a Shutdown Communication
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003) to the peer
+    ManualStopWithCommunication(String),
 
     AutomaticStart,
     AutomaticStop,
@@ -247,6 +326,9 @@ impl<A: Display, I: AsyncWrite + AsyncRead> Display for PeerAdminEvents<A, I> {
         match self {
             PeerAdminEvents::ManualStart => write!(f, "ManualStart"),
             PeerAdminEvents::ManualStop => write!(f, "ManualStop"),
+            PeerAdminEvents::ManualStopWithCommunication(communication) => {
+                write!(f, "ManualStopWithCommunication({communication:?})")
+            }
             PeerAdminEvents::AutomaticStart => write!(f, "AutomaticStart"),
             PeerAdminEvents::AutomaticStop => write!(f, "AutomaticStop"),
             PeerAdminEvents::TcpConnectionConfirmed(_) => write!(f, "TcpConnectionConfirmed"),
@@ -257,7 +339,24 @@ impl<A: Display, I: AsyncWrite + AsyncRead> Display for PeerAdminEvents<A, I> {
 pub enum PeerEvent<A, I: AsyncWrite + AsyncRead> {
     Admin(PeerAdminEvents<A, I>),
     BgpMessage(BgpMessage),
+    /// Originate the prefixes with the attributes, see
+    /// [`Peer::announce_shared_routes`]
+    Announce(Vec<IpNet>, RouteAttributes, Option<SharedUpdates>),
+    /// Withdraw originated prefixes, see [`Peer::withdraw_shared_routes`]
+    Withdraw(Vec<IpNet>, Option<SharedUpdates>),
+    GetAdjRibIn(oneshot::Sender<AdjRibIn>),
+    /// Receive the changes of the Adj-RIB-In, see [`Peer::adj_rib_in`]
+    SubscribeRouteChanges(mpsc::UnboundedSender<Vec<RouteChange>>),
+    /// See [`Peer::set_import_policy`]
+    SetImportPolicy(Option<Arc<dyn RoutePolicy>>),
+    /// See [`Peer::set_export_policy`]
+    SetExportPolicy(Option<Arc<dyn RoutePolicy>>),
+    /// See [`Peer::set_metrics_recorder`]
+    SetMetricsRecorder(Option<Arc<dyn MetricsRecorder>>),
+    /// See [`Peer::set_bmp_exporter`]
+    SetBmpExporter(Option<BmpPeerExporter<A>>),
     GetPeerStats(oneshot::Sender<PeerStats>),
+    GetPeerMetrics(oneshot::Sender<PeerMetrics>),
     GetConnectionStats(oneshot::Sender<Option<ConnectionStats>>),
     GetTrackedConnectionStats(oneshot::Sender<Option<ConnectionStats>>),
     ConnectionSentCapabilities(oneshot::Sender<Option<Vec<BgpCapability>>>),
@@ -271,7 +370,16 @@ impl<A: Display, I: AsyncWrite + AsyncRead> Display for PeerEvent<A, I> {
         match self {
             PeerEvent::Admin(admin) => write!(f, "Admin({admin})"),
             PeerEvent::BgpMessage(msg) => write!(f, "BgpMessage({msg:?})"),
+            PeerEvent::Announce(prefixes, _, _) => write!(f, "Announce({prefixes:?})"),
+            PeerEvent::Withdraw(prefixes, _) => write!(f, "Withdraw({prefixes:?})"),
+            PeerEvent::GetAdjRibIn(_) => write!(f, "GetAdjRibIn"),
+            PeerEvent::SubscribeRouteChanges(_) => write!(f, "SubscribeRouteChanges"),
+            PeerEvent::SetImportPolicy(_) => write!(f, "SetImportPolicy"),
+            PeerEvent::SetExportPolicy(_) => write!(f, "SetExportPolicy"),
+            PeerEvent::SetMetricsRecorder(_) => write!(f, "SetMetricsRecorder"),
+            PeerEvent::SetBmpExporter(_) => write!(f, "SetBmpExporter"),
             PeerEvent::GetPeerStats(_) => write!(f, "GetPeerStats"),
+            PeerEvent::GetPeerMetrics(_) => write!(f, "GetPeerMetrics"),
             PeerEvent::GetConnectionStats(_) => write!(f, "GetConnectionStats"),
             PeerEvent::GetTrackedConnectionStats(_) => write!(f, "GetTrackedConnectionStats"),
             PeerEvent::ConnectionSentCapabilities(_) => write!(f, "ConnectionSentCapabilities"),
@@ -329,12 +437,40 @@ enum ConnectionNextEvent<A> {
 #[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
 pub struct PeerStats {
     connect_retry_counter: u32,
+    fsm_transitions: u64,
+    established_transitions: u64,
+    flaps: u64,
+    last_state_change: Option<DateTime<Utc>>,
+    last_error: Option<NotificationError>,
 }
 
 impl PeerStats {
     pub const fn connect_retry_counter(&self) -> u32 {
         self.connect_retry_counter
     }
+
+    pub const fn fsm_transitions(&self) -> u64 {
+        self.fsm_transitions
+    }
+
+    /// Number of times the session was established
+    pub const fn established_transitions(&self) -> u64 {
+        self.established_transitions
+    }
+
+    /// Number of times the session went down after being established
+    pub const fn flaps(&self) -> u64 {
+        self.flaps
+    }
+
+    pub const fn last_state_change(&self) -> Option<DateTime<Utc>> {
+        self.last_state_change
+    }
+
+    /// Last NOTIFICATION message sent or received on the main connection
+    pub const fn last_error(&self) -> Option<NotificationError> {
+        self.last_error
+    }
 }
 
 /// Peer Configurations that are allowed to change without needing to restart
@@ -351,8 +487,22 @@ pub struct PeerConfig {
     pub(crate) hold_timer_duration_large_value: u16,
     pub(crate) keepalive_timer_duration: u16,
     pub(crate) idle_hold_duration: u16,
+    max_idle_hold_duration: u16,
+    damp_peer_oscillations: bool,
     passive_tcp_establishment: bool,
     collision_detect_established_state: bool,
+    graceful_restart: bool,
+    pub(crate) graceful_restart_time: u16,
+    graceful_restart_stale_routes_time: u16,
+    tcp_md5_key: Option<TcpMd5Key>,
+    dscp: u8,
+    tcp_keepalive: Option<TcpKeepalive>,
+    local_address: Option<IpAddr>,
+    bind_interface: Option<InterfaceName>,
+    max_prefixes: Option<u32>,
+    max_prefixes_warning_threshold: u8,
+    max_prefixes_teardown: bool,
+    max_prefixes_restart_time: u16,
 }
 
 impl Default for PeerConfig {
@@ -368,8 +518,23 @@ impl Default for PeerConfig {
             hold_timer_duration_large_value: 240,
             keepalive_timer_duration: 30,
             idle_hold_duration: 1,
+            max_idle_hold_duration: 300,
+            damp_peer_oscillations: false,
             passive_tcp_establishment: false,
             collision_detect_established_state: false,
+            graceful_restart: false,
+            // RFC 4724 suggests the restart time to be less than the hold time
+            graceful_restart_time: 120,
+            graceful_restart_stale_routes_time: 360,
+            tcp_md5_key: None,
+            dscp: DSCP_CS6,
+            tcp_keepalive: None,
+            local_address: None,
+            bind_interface: None,
+            max_prefixes: None,
+            max_prefixes_warning_threshold: 75,
+            max_prefixes_teardown: true,
+            max_prefixes_restart_time: 0,
         }
     }
 }
@@ -395,6 +560,13 @@ impl PeerConfig {
         }
     }
 
+    /// DelayOpen attribute: wait for the OPEN message of the peer for
+    /// [`PeerConfig::open_delay_timer_duration`] before sending ours
+    pub const fn delay_open(&self) -> bool {
+        self.open_delay_timer_duration != 0
+    }
+
+    /// DelayOpenTime, DelayOpen is disabled when zero
     pub const fn open_delay_timer_duration(&self) -> Duration {
         Duration::from_secs(self.open_delay_timer_duration as u64)
     }
@@ -411,13 +583,107 @@ impl PeerConfig {
         Duration::from_secs(self.keepalive_timer_duration as u64)
     }
 
+    /// Initial IdleHoldTime the peer stays in Idle before being started again
+    /// when [`PeerConfig::damp_peer_oscillations`] is set
     pub const fn idle_hold_duration(&self) -> Duration {
         Duration::from_secs(self.idle_hold_duration as u64)
     }
 
+    /// Upper bound of the IdleHoldTime, which doubles every time the peer
+    /// falls back to Idle. The IdleHoldTime is set back to
+    /// [`PeerConfig::idle_hold_duration`] once a session stayed established at
+    /// least this long.
+    pub const fn max_idle_hold_duration(&self) -> Duration {
+        Duration::from_secs(self.max_idle_hold_duration as u64)
+    }
+
+    /// DampPeerOscillations attribute: the peer falling back to Idle on errors
+    /// is started again automatically after the IdleHoldTimer expires, see
+    /// [`crate::events::BgpEvent::IdleHoldTimerExpires`]. Requires
+    /// [`PeerConfig::allow_auto_start`].
+    pub const fn damp_peer_oscillations(&self) -> bool {
+        self.damp_peer_oscillations
+    }
+
+    /// PassiveTcpEstablishment attribute: wait for the peer to initiate the
+    /// connection instead of connecting to it
     pub const fn passive_tcp_establishment(&self) -> bool {
         self.passive_tcp_establishment
     }
+
+    /// Advertise the Graceful Restart capability, see
+    /// [RFC4724](https://datatracker.ietf.org/doc/html/rfc4724)
+    pub const fn graceful_restart(&self) -> bool {
+        self.graceful_restart
+    }
+
+    /// Restart Time advertised in the Graceful Restart capability
+    pub const fn graceful_restart_time(&self) -> Duration {
+        Duration::from_secs(self.graceful_restart_time as u64)
+    }
+
+    /// Upper bound on the time the stale routes of a restarting peer are kept
+    /// after the session is established again, waiting for its End-of-RIB
+    /// markers
+    pub const fn graceful_restart_stale_routes_time(&self) -> Duration {
+        Duration::from_secs(self.graceful_restart_stale_routes_time as u64)
+    }
+
+    /// Key of the TCP MD5 signatures of the connections to the peer, see
+    /// [`crate::socket`]. Applies to the connections initiated to the peer, the
+    /// listener is configured with [`crate::listener::BgpListener::reg_peer_tcp_md5_key`].
+    pub const fn tcp_md5_key(&self) -> Option<TcpMd5Key> {
+        self.tcp_md5_key
+    }
+
+    /// DSCP of the packets of the connections initiated to the peer,
+    /// [`DSCP_CS6`] by default, see [`crate::socket`]
+    pub const fn dscp(&self) -> u8 {
+        self.dscp
+    }
+
+    /// TCP keepalives of the connections initiated to the peer, disabled by
+    /// default
+    pub const fn tcp_keepalive(&self) -> Option<TcpKeepalive> {
+        self.tcp_keepalive
+    }
+
+    /// Source address of the connections initiated to the peer, chosen by the
+    /// OS when `None`
+    pub const fn local_address(&self) -> Option<IpAddr> {
+        self.local_address
+    }
+
+    /// Network interface the connections initiated to the peer are bound to
+    pub const fn bind_interface(&self) -> Option<InterfaceName> {
+        self.bind_interface
+    }
+
+    /// Max number of routes received from the peer, counted in the
+    /// Adj-RIB-In before applying the import policy
+    pub const fn max_prefixes(&self) -> Option<u32> {
+        self.max_prefixes
+    }
+
+    /// Percentage of [`PeerConfig::max_prefixes`] above which a warning is
+    /// logged
+    pub const fn max_prefixes_warning_threshold(&self) -> u8 {
+        self.max_prefixes_warning_threshold
+    }
+
+    /// Close the session with a Maximum Number of Prefixes Reached Cease
+    /// NOTIFICATION when [`PeerConfig::max_prefixes`] is exceeded, otherwise
+    /// only a warning is logged
+    pub const fn max_prefixes_teardown(&self) -> bool {
+        self.max_prefixes_teardown
+    }
+
+    /// Time after which a peer closed for exceeding
+    /// [`PeerConfig::max_prefixes`] is started again, the peer stays down when
+    /// zero
+    pub const fn max_prefixes_restart_time(&self) -> Duration {
+        Duration::from_secs(self.max_prefixes_restart_time as u64)
+    }
 }
 
 #[derive(Debug, Default)]
@@ -425,6 +691,14 @@ pub struct PeerConfigBuilder {
     config: PeerConfig,
 }
 
+/// Start from an existing configuration, e.g., to override some values of the
+/// configuration of a [`crate::supervisor::PeerGroup`]
+impl From<PeerConfig> for PeerConfigBuilder {
+    fn from(config: PeerConfig) -> Self {
+        Self { config }
+    }
+}
+
 impl PeerConfigBuilder {
     pub fn new() -> Self {
         Self::default()
@@ -475,6 +749,16 @@ impl PeerConfigBuilder {
         self
     }
 
+    pub const fn max_idle_hold_duration(mut self, value: u16) -> Self {
+        self.config.max_idle_hold_duration = value;
+        self
+    }
+
+    pub const fn damp_peer_oscillations(mut self, value: bool) -> Self {
+        self.config.damp_peer_oscillations = value;
+        self
+    }
+
     pub const fn passive_tcp_establishment(mut self, value: bool) -> Self {
         self.config.passive_tcp_establishment = value;
         self
@@ -485,11 +769,99 @@ impl PeerConfigBuilder {
         self
     }
 
+    pub const fn graceful_restart(mut self, value: bool) -> Self {
+        self.config.graceful_restart = value;
+        self
+    }
+
+    pub const fn graceful_restart_time(mut self, value: u16) -> Self {
+        self.config.graceful_restart_time = value;
+        self
+    }
+
+    pub const fn graceful_restart_stale_routes_time(mut self, value: u16) -> Self {
+        self.config.graceful_restart_stale_routes_time = value;
+        self
+    }
+
+    pub const fn tcp_md5_key(mut self, value: TcpMd5Key) -> Self {
+        self.config.tcp_md5_key = Some(value);
+        self
+    }
+
+    pub const fn dscp(mut self, value: u8) -> Self {
+        self.config.dscp = value;
+        self
+    }
+
+    pub const fn tcp_keepalive(mut self, value: TcpKeepalive) -> Self {
+        self.config.tcp_keepalive = Some(value);
+        self
+    }
+
+    pub const fn local_address(mut self, value: IpAddr) -> Self {
+        self.config.local_address = Some(value);
+        self
+    }
+
+    pub const fn bind_interface(mut self, value: InterfaceName) -> Self {
+        self.config.bind_interface = Some(value);
+        self
+    }
+
+    pub const fn max_prefixes(mut self, value: u32) -> Self {
+        self.config.max_prefixes = Some(value);
+        self
+    }
+
+    pub const fn max_prefixes_warning_threshold(mut self, value: u8) -> Self {
+        self.config.max_prefixes_warning_threshold = value;
+        self
+    }
+
+    pub const fn max_prefixes_teardown(mut self, value: bool) -> Self {
+        self.config.max_prefixes_teardown = value;
+        self
+    }
+
+    pub const fn max_prefixes_restart_time(mut self, value: u16) -> Self {
+        self.config.max_prefixes_restart_time = value;
+        self
+    }
+
     pub const fn build(self) -> PeerConfig {
         self.config
     }
 }
 
+/// BGP confederation the speaker is a member of, see
+/// [RFC5065](https://datatracker.ietf.org/doc/html/rfc5065)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct Confederation {
+    identifier: u32,
+    member_asn: u32,
+}
+
+impl Confederation {
+    /// The identifier is the AS of the confederation as seen by the external
+    /// peers, the member AS is the one of the speaker inside the confederation
+    pub const fn new(identifier: u32, member_asn: u32) -> Self {
+        Self {
+            identifier,
+            member_asn,
+        }
+    }
+
+    pub const fn identifier(&self) -> u32 {
+        self.identifier
+    }
+
+    pub const fn member_asn(&self) -> u32 {
+        self.member_asn
+    }
+}
+
 /// Peer configurations that are not changed without restarting the peer
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
@@ -499,6 +871,7 @@ pub struct PeerProperties<A> {
     my_bgp_id: Ipv4Addr,
     peer_addr: A,
     allow_dynamic_as: bool,
+    confederation: Option<Confederation>,
 }
 
 impl<A: Clone> PeerProperties<A> {
@@ -515,9 +888,17 @@ impl<A: Clone> PeerProperties<A> {
             my_bgp_id,
             peer_addr,
             allow_dynamic_as,
+            confederation: None,
         }
     }
 
+    /// The peer is inside the confederation when `my_asn` is the member AS,
+    /// otherwise `my_asn` is expected to be the confederation identifier
+    pub const fn with_confederation(mut self, confederation: Confederation) -> Self {
+        self.confederation = Some(confederation);
+        self
+    }
+
     pub const fn my_asn(&self) -> u32 {
         self.my_asn
     }
@@ -533,6 +914,9 @@ impl<A: Clone> PeerProperties<A> {
     pub const fn allow_dynamic_as(&self) -> bool {
         self.allow_dynamic_as
     }
+    pub const fn confederation(&self) -> Option<Confederation> {
+        self.confederation
+    }
 }
 
 #[derive(Debug)]
@@ -558,6 +942,53 @@ pub struct Peer<
     active_connect: C,
     allowed_to_active_connect: bool,
     waiting_admin_events: Vec<PeerAdminEvents<A, I>>,
+    originated_routes: BTreeMap<IpNet, RouteAttributes>,
+    adj_rib_in: AdjRibIn,
+    import_policy: Option<Arc<dyn RoutePolicy>>,
+    export_policy: Option<Arc<dyn RoutePolicy>>,
+    route_change_subscribers: Vec<mpsc::UnboundedSender<Vec<RouteChange>>>,
+    /// Graceful Restart capability of the peer when negotiated in the
+    /// current or the last established session
+    peer_graceful_restart: Option<GracefulRestartCapability>,
+    /// Runs while waiting for the restarting peer to establish the session
+    /// again
+    restart_timer: Option<Interval>,
+    /// Runs while waiting for the End-of-RIB markers of the restarted peer
+    stale_routes_timer: Option<Interval>,
+    /// Whether the number of received routes is above the warning threshold
+    /// of [`PeerConfig::max_prefixes`]
+    max_prefixes_warning: bool,
+    /// Runs while waiting to start again the peer closed for exceeding
+    /// [`PeerConfig::max_prefixes`]
+    max_prefixes_restart_timer: Option<Interval>,
+    /// Runs while the peer that fell back to Idle waits to be started again,
+    /// see [`PeerConfig::damp_peer_oscillations`]
+    idle_hold_timer: Option<Interval>,
+    /// IdleHoldTime of the next time the peer falls back to Idle
+    idle_hold_time: Duration,
+    /// When the current session was established
+    established_at: Option<tokio::time::Instant>,
+    /// Recorder of the metrics of the peer along the formatted peer key
+    metrics_recorder: Option<(String, Arc<dyn MetricsRecorder>)>,
+    /// Export of the peer to a BMP station, see [`crate::bmp`]
+    bmp: Option<BmpMonitor<A>>,
+}
+
+impl<
+        K,
+        A,
+        I: AsyncWrite + AsyncRead,
+        D: Decoder<Item = (BgpMessage, BgpParsingIgnoredErrors), Error = BgpCodecDecoderError>
+            + Encoder<BgpMessage, Error = BgpMessageWritingError>,
+        C: ActiveConnect<A, I, D>,
+        P: PeerPolicy<A, I, D>,
+    > Drop for Peer<K, A, I, D, C, P>
+{
+    fn drop(&mut self) {
+        if let Some((peer, recorder)) = self.metrics_recorder.take() {
+            recorder.remove(&peer);
+        }
+    }
 }
 
 impl<
@@ -592,6 +1023,21 @@ impl<
             active_connect,
             allowed_to_active_connect: false,
             waiting_admin_events: vec![],
+            originated_routes: BTreeMap::new(),
+            adj_rib_in: AdjRibIn::new(),
+            import_policy: None,
+            export_policy: None,
+            route_change_subscribers: vec![],
+            peer_graceful_restart: None,
+            restart_timer: None,
+            stale_routes_timer: None,
+            max_prefixes_warning: false,
+            max_prefixes_restart_timer: None,
+            idle_hold_timer: None,
+            idle_hold_time: config.idle_hold_duration(),
+            established_at: None,
+            metrics_recorder: None,
+            bmp: None,
         }
     }
 
@@ -615,6 +1061,27 @@ impl<
         self.connect_retry_timer.as_ref()
     }
 
+    pub const fn restart_timer(&self) -> Option<&Interval> {
+        self.restart_timer.as_ref()
+    }
+
+    pub const fn stale_routes_timer(&self) -> Option<&Interval> {
+        self.stale_routes_timer.as_ref()
+    }
+
+    pub const fn max_prefixes_restart_timer(&self) -> Option<&Interval> {
+        self.max_prefixes_restart_timer.as_ref()
+    }
+
+    pub const fn idle_hold_timer(&self) -> Option<&Interval> {
+        self.idle_hold_timer.as_ref()
+    }
+
+    /// IdleHoldTime used the next time the peer falls back to Idle
+    pub const fn idle_hold_time(&self) -> Duration {
+        self.idle_hold_time
+    }
+
     pub const fn stats(&self) -> PeerStats {
         self.stats
     }
@@ -623,6 +1090,37 @@ impl<
         &self.config
     }
 
+    /// Routes originated by the speaker to this peer, they are announced
+    /// again every time the session is established
+    pub const fn originated_routes(&self) -> &BTreeMap<IpNet, RouteAttributes> {
+        &self.originated_routes
+    }
+
+    /// Routes received from the peer in the current session before applying
+    /// the import policy, the RIB is cleared when the session goes down. When
+    /// Graceful Restart is negotiated, the routes are retained as stale
+    /// instead if the session goes down without a NOTIFICATION message.
+    pub const fn adj_rib_in(&self) -> &AdjRibIn {
+        &self.adj_rib_in
+    }
+
+    /// Send the changes of the Adj-RIB-In to the subscriber after applying
+    /// the import policy, the subscriber is dropped once its receiver is closed
+    pub fn subscribe_route_changes(&mut self, tx: mpsc::UnboundedSender<Vec<RouteChange>>) {
+        self.route_change_subscribers.push(tx);
+    }
+
+    fn notify_route_changes(&mut self, changes: Vec<RouteChange>) {
+        if changes.is_empty() {
+            return;
+        }
+        if let Some(bmp) = &self.bmp {
+            bmp.route_changes(true, &changes);
+        }
+        self.route_change_subscribers
+            .retain(|tx| tx.send(changes.clone()).is_ok());
+    }
+
     // Central method for transitioning to make it easier for consistent logging
     #[inline]
     fn fsm_transition(&mut self, new_state: FsmState) {
@@ -631,6 +1129,23 @@ impl<
         }
         let before = self.fsm_state;
         self.fsm_state = new_state;
+        self.stats.fsm_transitions += 1;
+        self.stats.last_state_change = Some(Utc::now());
+        if new_state == FsmState::Established {
+            self.stats.established_transitions += 1;
+            self.established_at = Some(tokio::time::Instant::now());
+        }
+        if before == FsmState::Established {
+            self.stats.flaps += 1;
+            if let Some(bmp) = self.bmp.as_mut() {
+                bmp.peer_down();
+            }
+        }
+        if before == FsmState::Established && self.restart_timer.is_none() {
+            self.stale_routes_timer.take();
+            let changes = self.adj_rib_in.clear();
+            self.notify_route_changes(changes);
+        }
         log::info!(
             "[{}][{}] FSM state transitions from {} to {}",
             self.peer_key,
@@ -638,6 +1153,35 @@ impl<
             before,
             new_state
         );
+        if new_state == FsmState::Idle {
+            self.start_idle_hold_timer();
+        }
+    }
+
+    /// Delay starting again the peer that fell back to Idle while still
+    /// administratively up, doubling the delay on every flap
+    fn start_idle_hold_timer(&mut self) {
+        let established_at = self.established_at.take();
+        if self.peer_state != PeerState::AdminUp
+            || !self.config.allow_auto_start()
+            || !self.config.damp_peer_oscillations()
+        {
+            return;
+        }
+        let max_idle_hold_time = self.config.max_idle_hold_duration();
+        if established_at.is_some_and(|at| at.elapsed() >= max_idle_hold_time) {
+            self.idle_hold_time = self.config.idle_hold_duration();
+        }
+        let idle_hold_time = self.idle_hold_time.min(max_idle_hold_time);
+        log::info!(
+            "[{}][{}] Starting the peer again in {idle_hold_time:?}",
+            self.peer_key,
+            self.fsm_state,
+        );
+        let mut interval = tokio::time::interval(idle_hold_time.max(Duration::from_millis(1)));
+        interval.reset();
+        self.idle_hold_timer.replace(interval);
+        self.idle_hold_time = (idle_hold_time * 2).min(max_idle_hold_time);
     }
     fn add_connection(&mut self, connection: Connection<A, I, D>) {
         if self.connection.is_some() {
@@ -755,6 +1299,622 @@ impl<
         self.stats
     }
 
+    pub fn metrics(&self) -> PeerMetrics {
+        PeerMetrics::new(
+            self.fsm_state,
+            self.stats,
+            self.main_connection_stats(),
+            self.adj_rib_in.len(),
+            self.adj_rib_in.stale_len(),
+        )
+    }
+
+    /// Push the metrics of the peer to the recorder after every BGP event,
+    /// `None` stops reporting to the current recorder
+    pub fn set_metrics_recorder(&mut self, recorder: Option<Arc<dyn MetricsRecorder>>) {
+        if let Some((peer, recorder)) = self.metrics_recorder.take() {
+            recorder.remove(&peer);
+        }
+        self.metrics_recorder = recorder.map(|recorder| (self.peer_key.to_string(), recorder));
+        self.record_metrics();
+    }
+
+    pub fn record_metrics(&self) {
+        if let Some((peer, recorder)) = &self.metrics_recorder {
+            recorder.record(peer, &self.metrics());
+        }
+    }
+
+    /// Export the peer to a BMP station, see [`crate::bmp`]. When the session
+    /// is established, it's reported right away to the station. `None` stops
+    /// the export, reporting the peer as de-configured to the current station.
+    pub fn set_bmp_exporter(&mut self, exporter: Option<BmpPeerExporter<A>>) {
+        if let Some(mut bmp) = self.bmp.take() {
+            bmp.deconfigure();
+        }
+        self.bmp = exporter.map(BmpMonitor::new);
+        self.bmp_peer_up();
+    }
+
+    /// Report the established session to the BMP station followed by the
+    /// routes of the peer, unless it's already reported in the current
+    /// session with the station
+    fn bmp_peer_up(&mut self) {
+        if self.fsm_state != FsmState::Established {
+            return;
+        }
+        let Some(session) = self.bmp.as_ref().and_then(|bmp| bmp.pending_session()) else {
+            return;
+        };
+        let Some(connection) = self.connection.as_ref() else {
+            return;
+        };
+        let (Some(sent_open), Some(received_open)) =
+            (connection.sent_open(), connection.received_open())
+        else {
+            return;
+        };
+        let (sent_open, received_open) = (sent_open.clone(), received_open.clone());
+        let peer_addr = *connection.peer_addr();
+        let peer_asn = connection.peer_asn().unwrap_or(self.properties.peer_asn());
+        let peer_bgp_id = connection.peer_bgp_id().unwrap_or(Ipv4Addr::UNSPECIFIED);
+        let negotiated = connection.negotiated_capabilities();
+        let asn2 = !negotiated
+            .as_ref()
+            .is_some_and(|negotiated| negotiated.four_octet_as());
+        let mut address_types: Vec<AddressType> = negotiated
+            .iter()
+            .flat_map(|negotiated| negotiated.address_types().iter().copied())
+            .collect();
+        address_types.sort_by_key(|address_type| {
+            (
+                u16::from(address_type.address_family()),
+                u8::from(address_type.subsequent_address_family()),
+            )
+        });
+        let end_of_rib: Vec<RouteChange> = address_types
+            .into_iter()
+            .map(RouteChange::EndOfRib)
+            .collect();
+        let pre_policy: Vec<RouteChange> = self
+            .adj_rib_in
+            .routes()
+            .cloned()
+            .map(RouteChange::Announced)
+            .collect();
+        let post_policy: Vec<RouteChange> = self
+            .import_routes(pre_policy.clone())
+            .into_iter()
+            .filter(|change| matches!(change, RouteChange::Announced(_)))
+            .collect();
+        let Some(bmp) = self.bmp.as_mut() else {
+            return;
+        };
+        bmp.peer_up(
+            session,
+            &peer_addr,
+            peer_asn,
+            peer_bgp_id,
+            asn2,
+            sent_open,
+            received_open,
+        );
+        bmp.route_changes(false, &pre_policy);
+        bmp.route_changes(false, &end_of_rib);
+        bmp.route_changes(true, &post_policy);
+        bmp.route_changes(true, &end_of_rib);
+    }
+
+    fn update_last_error(&mut self) {
+        if let Some(last_error) = self
+            .connection
+            .as_ref()
+            .and_then(|connection| connection.stats().last_error())
+        {
+            self.stats.last_error = Some(last_error);
+        }
+    }
+
+    /// Builder of the Update messages for the capabilities negotiated on the
+    /// main connection, only available when the session is established
+    pub fn update_builder(&self) -> Option<UpdateBuilder> {
+        if self.fsm_state != FsmState::Established {
+            return None;
+        }
+        let connection = self.connection.as_ref()?;
+        let peer_asn = connection.peer_asn().unwrap_or(self.properties.peer_asn());
+        let builder = UpdateBuilder::new(
+            self.properties.my_asn(),
+            peer_asn,
+            connection.negotiated_capabilities()?,
+        );
+        Some(match self.properties.confederation() {
+            Some(confederation) => builder.with_confederation(confederation),
+            None => builder,
+        })
+    }
+
+    /// Update group of the peer for the capabilities negotiated on the main
+    /// connection, only available when the session is established, see
+    /// [`crate::update_group`]
+    pub fn update_group(&self) -> Option<UpdateGroup> {
+        self.update_builder()
+            .map(|builder| UpdateGroup::new(builder, self.export_policy.clone()))
+    }
+
+    /// Originate the prefixes with the given attributes, replacing the
+    /// attributes of the prefixes that are already originated. The routes are
+    /// sent right away if the session is established, otherwise once it is.
+    /// The export policy is applied before sending the routes, a prefix that
+    /// was sent before and is now rejected is withdrawn.
+    /// Routes that can't be encoded for the capabilities of the session, e.g.,
+    /// an address type that is not negotiated, are logged and not sent.
+    pub async fn announce_routes(
+        &mut self,
+        prefixes: Vec<IpNet>,
+        attributes: RouteAttributes,
+    ) -> Result<(), FsmStateError<A>> {
+        self.originate_routes(prefixes, attributes, None).await
+    }
+
+    /// Same as [`Peer::announce_routes`], the Update messages are shared with
+    /// the other peers of the same update group the routes are originated to
+    pub async fn announce_shared_routes(
+        &mut self,
+        prefixes: Vec<IpNet>,
+        attributes: RouteAttributes,
+        shared: &SharedUpdates,
+    ) -> Result<(), FsmStateError<A>> {
+        self.originate_routes(prefixes, attributes, Some(shared))
+            .await
+    }
+
+    async fn originate_routes(
+        &mut self,
+        prefixes: Vec<IpNet>,
+        attributes: RouteAttributes,
+        shared: Option<&SharedUpdates>,
+    ) -> Result<(), FsmStateError<A>> {
+        let prefixes: Vec<IpNet> = prefixes.iter().map(IpNet::trunc).collect();
+        let mut announced = vec![];
+        let mut withdrawn = vec![];
+        for prefix in prefixes {
+            let previous = self.originated_routes.insert(prefix, attributes.clone());
+            let was_exported =
+                previous.is_some_and(|previous| self.export_route(prefix, &previous).is_some());
+            match self.export_route(prefix, &attributes) {
+                Some(exported) => announced.push((prefix, exported)),
+                None if was_exported => withdrawn.push(prefix),
+                None => {}
+            }
+        }
+        self.send_routes(announced, withdrawn, shared).await
+    }
+
+    /// Withdraw previously originated prefixes, the other prefixes are ignored
+    pub async fn withdraw_routes(&mut self, prefixes: Vec<IpNet>) -> Result<(), FsmStateError<A>> {
+        self.remove_originated_routes(prefixes, None).await
+    }
+
+    /// Same as [`Peer::withdraw_routes`], the Update messages are shared with
+    /// the other peers of the same update group the routes are withdrawn from
+    pub async fn withdraw_shared_routes(
+        &mut self,
+        prefixes: Vec<IpNet>,
+        shared: &SharedUpdates,
+    ) -> Result<(), FsmStateError<A>> {
+        self.remove_originated_routes(prefixes, Some(shared)).await
+    }
+
+    async fn remove_originated_routes(
+        &mut self,
+        prefixes: Vec<IpNet>,
+        shared: Option<&SharedUpdates>,
+    ) -> Result<(), FsmStateError<A>> {
+        let removed: Vec<(IpNet, RouteAttributes)> = prefixes
+            .iter()
+            .map(IpNet::trunc)
+            .filter_map(|prefix| {
+                self.originated_routes
+                    .remove(&prefix)
+                    .map(|attributes| (prefix, attributes))
+            })
+            .collect();
+        // Only the prefixes accepted by the export policy were sent to the peer
+        let withdrawn = removed
+            .iter()
+            .filter(|(prefix, attributes)| self.export_route(*prefix, attributes).is_some())
+            .map(|(prefix, _)| *prefix)
+            .collect();
+        self.send_routes(vec![], withdrawn, shared).await
+    }
+
+    /// Announce all the originated routes accepted by the export policy
+    async fn announce_originated_routes(&mut self) -> Result<(), FsmStateError<A>> {
+        let announced = self.exported_routes();
+        self.send_routes(announced, vec![], None).await
+    }
+
+    /// Retain the routes of the peer as stale when the session goes down
+    /// without a NOTIFICATION message, if the peer advertised the Graceful
+    /// Restart capability, see
+    /// [RFC4724 Section 4.2](https://datatracker.ietf.org/doc/html/rfc4724#section-4.2).
+    /// The routes of the address families missing from the capability are
+    /// removed.
+    fn start_graceful_restart(&mut self) {
+        let Some(graceful_restart) = self.peer_graceful_restart.as_ref() else {
+            return;
+        };
+        if graceful_restart.time() == 0 {
+            return;
+        }
+        let restart_time = Duration::from_secs(graceful_restart.time() as u64);
+        let address_types: Vec<AddressType> = graceful_restart
+            .address_families()
+            .iter()
+            .map(|family| family.address_type())
+            .collect();
+        log::info!(
+            "[{}][{}] Retaining the routes of {address_types:?} as stale for {restart_time:?}",
+            self.peer_key,
+            self.fsm_state,
+        );
+        self.stale_routes_timer.take();
+        let changes = self.adj_rib_in.mark_stale(&address_types);
+        self.notify_route_changes(changes);
+        let mut interval = tokio::time::interval(restart_time);
+        interval.reset();
+        self.restart_timer.replace(interval);
+    }
+
+    /// Check the number of routes received from the peer against
+    /// [`PeerConfig::max_prefixes`]. When the limit is exceeded and
+    /// [`PeerConfig::max_prefixes_teardown`] is set, the session is closed
+    /// with a Cease NOTIFICATION carrying the address type of the last
+    /// received routes as defined in
+    /// [RFC4486](https://datatracker.ietf.org/doc/html/rfc4486), and `true` is
+    /// returned.
+    async fn check_max_prefixes(&mut self, address_type: AddressType) -> bool {
+        let Some(max_prefixes) = self.config.max_prefixes else {
+            return false;
+        };
+        let received = self.adj_rib_in.len() as u64;
+        let threshold = max_prefixes as u64 * self.config.max_prefixes_warning_threshold as u64;
+        let warning = received * 100 >= threshold;
+        if warning && !self.max_prefixes_warning {
+            log::warn!(
+                "[{}][{}] Received {received} routes, reaching {}% of the limit of {max_prefixes}",
+                self.peer_key,
+                self.fsm_state,
+                self.config.max_prefixes_warning_threshold,
+            );
+        }
+        self.max_prefixes_warning = warning;
+        if received <= max_prefixes as u64 {
+            return false;
+        }
+        log::warn!(
+            "[{}][{}] Received {received} routes, exceeding the limit of {max_prefixes}",
+            self.peer_key,
+            self.fsm_state,
+        );
+        if !self.config.max_prefixes_teardown {
+            return false;
+        }
+        let mut value = Vec::with_capacity(7);
+        value.extend(u16::from(address_type.address_family()).to_be_bytes());
+        value.push(u8::from(address_type.subsequent_address_family()));
+        value.extend(max_prefixes.to_be_bytes());
+        let notif =
+            BgpNotificationMessage::CeaseError(CeaseError::MaximumNumberOfPrefixesReached {
+                value,
+            });
+        if let Some(bmp) = self.bmp.as_mut() {
+            bmp.set_down_reason(
+                PeerDownNotificationReason::LocalSystemClosedNotificationPduFollows(
+                    BgpMessage::Notification(notif.clone()),
+                ),
+            );
+        }
+        if let Some(conn) = self.connection.as_mut() {
+            let _ = conn.send(BgpMessage::Notification(notif)).await;
+        }
+        self.update_last_error();
+        self.connection.take();
+        self.connect_retry_timer.take();
+        self.stats.connect_retry_counter += 1;
+        self.peer_state = PeerState::AdminDown;
+        self.fsm_transition(FsmState::Idle);
+        self.max_prefixes_warning = false;
+        let restart_time = self.config.max_prefixes_restart_time();
+        if !restart_time.is_zero() {
+            log::info!(
+                "[{}][{}] Starting the peer again in {restart_time:?}",
+                self.peer_key,
+                self.fsm_state,
+            );
+            let mut interval = tokio::time::interval(restart_time);
+            interval.reset();
+            self.max_prefixes_restart_timer.replace(interval);
+        }
+        true
+    }
+
+    /// Called once the session is established: the stale routes of the
+    /// address families that the restarted peer didn't preserve are removed,
+    /// then the originated routes are announced followed by the End-of-RIB
+    /// markers when Graceful Restart is negotiated.
+    async fn session_established(&mut self) -> Result<(), FsmStateError<A>> {
+        self.restart_timer.take();
+        let negotiated = self
+            .connection
+            .as_ref()
+            .and_then(|connection| connection.negotiated_capabilities());
+        self.peer_graceful_restart = negotiated
+            .as_ref()
+            .filter(|negotiated| negotiated.graceful_restart())
+            .and_then(|_| self.connection.as_ref()?.received_capabilities())
+            .and_then(|capabilities| {
+                capabilities.iter().find_map(|capability| match capability {
+                    BgpCapability::GracefulRestartCapability(graceful_restart) => {
+                        Some(graceful_restart.clone())
+                    }
+                    _ => None,
+                })
+            });
+        let preserved: Vec<AddressType> = self
+            .peer_graceful_restart
+            .iter()
+            .flat_map(|graceful_restart| graceful_restart.address_families())
+            .filter(|family| family.forwarding_state())
+            .map(|family| family.address_type())
+            .collect();
+        let mut changes = vec![];
+        for address_type in self.adj_rib_in.stale_address_types() {
+            if !preserved.contains(&address_type) {
+                changes.extend(self.adj_rib_in.remove_stale(address_type));
+            }
+        }
+        self.notify_route_changes(changes);
+        if self.adj_rib_in.stale_len() > 0 {
+            let stale_routes_time = self.config.graceful_restart_stale_routes_time();
+            if stale_routes_time.is_zero() {
+                let changes = self.adj_rib_in.remove_all_stale();
+                self.notify_route_changes(changes);
+            } else {
+                let mut interval = tokio::time::interval(stale_routes_time);
+                interval.reset();
+                self.stale_routes_timer.replace(interval);
+            }
+        }
+        self.bmp_peer_up();
+        self.announce_originated_routes().await?;
+        if self.peer_graceful_restart.is_none() {
+            return Ok(());
+        }
+        let Some(builder) = self.update_builder() else {
+            return Ok(());
+        };
+        let mut address_types: Vec<AddressType> = builder
+            .capabilities()
+            .address_types()
+            .iter()
+            .copied()
+            .collect();
+        address_types.sort_by_key(|address_type| {
+            (
+                u16::from(address_type.address_family()),
+                u8::from(address_type.subsequent_address_family()),
+            )
+        });
+        let mut updates = vec![];
+        for address_type in address_types {
+            match builder.end_of_rib(address_type) {
+                Ok(update) => updates.push(update),
+                Err(err) => log::warn!(
+                    "[{}][{}] Couldn't send End-of-RIB for {address_type:?}: {err:?}",
+                    self.peer_key,
+                    self.fsm_state,
+                ),
+            }
+        }
+        self.send_updates(updates).await
+    }
+
+    /// Policy applied on the routes received from the peer, see
+    /// [`crate::route_policy`]
+    pub fn import_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.import_policy.as_ref()
+    }
+
+    /// Policy applied on the routes originated to the peer, see
+    /// [`crate::route_policy`]
+    pub fn export_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.export_policy.as_ref()
+    }
+
+    /// Replace the import policy, `None` accepts all the routes. The routes of
+    /// the Adj-RIB-In are evaluated again with the new policy, and reported to
+    /// the subscribers as announced or withdrawn.
+    pub fn set_import_policy(&mut self, policy: Option<Arc<dyn RoutePolicy>>) {
+        self.import_policy = policy;
+        let changes = self
+            .adj_rib_in
+            .routes()
+            .cloned()
+            .map(RouteChange::Announced)
+            .collect();
+        let changes = self.import_routes(changes);
+        self.notify_route_changes(changes);
+    }
+
+    /// Replace the export policy, `None` accepts all the routes. The
+    /// originated routes are sent again with the new policy, and the ones
+    /// that are now rejected are withdrawn.
+    pub async fn set_export_policy(
+        &mut self,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), FsmStateError<A>> {
+        let previous = self.exported_routes();
+        self.export_policy = policy;
+        let announced = self.exported_routes();
+        let withdrawn = previous
+            .into_iter()
+            .map(|(prefix, _)| prefix)
+            .filter(|prefix| announced.iter().all(|(exported, _)| exported != prefix))
+            .collect();
+        self.send_routes(announced, withdrawn, None).await
+    }
+
+    /// Apply the AS loop detection and the import policy on the announced
+    /// routes, the rejected routes are reported as withdrawn since they might
+    /// have been accepted before
+    fn import_routes(&self, changes: Vec<RouteChange>) -> Vec<RouteChange> {
+        changes
+            .into_iter()
+            .map(|change| match change {
+                RouteChange::Announced(route) if self.is_as_loop(&route) => {
+                    RouteChange::Withdrawn(route)
+                }
+                RouteChange::Announced(route) => {
+                    let Some(policy) = &self.import_policy else {
+                        return RouteChange::Announced(route);
+                    };
+                    let mut imported = route.clone();
+                    match policy.apply(&mut imported) {
+                        PolicyResult::Accept => RouteChange::Announced(imported),
+                        PolicyResult::Reject => RouteChange::Withdrawn(route),
+                    }
+                }
+                change => change,
+            })
+            .collect()
+    }
+
+    /// The AS path contains the local AS. For a member of a confederation,
+    /// the member AS is looked up in the confederation segments and the
+    /// confederation identifier in the other segments, see
+    /// [RFC5065](https://datatracker.ietf.org/doc/html/rfc5065#section-5).
+    /// The routes received from external peers with confederation segments
+    /// are rejected as well.
+    fn is_as_loop(&self, route: &Route) -> bool {
+        let my_asn = self.properties.my_asn();
+        let Some(confederation) = self.properties.confederation() else {
+            return route.as_path_numbers(false).contains(&my_asn);
+        };
+        if route
+            .as_path_numbers(false)
+            .contains(&confederation.identifier())
+        {
+            return true;
+        }
+        let confederation_asns = route.as_path_numbers(true);
+        if my_asn == confederation.member_asn() {
+            confederation_asns.contains(&my_asn)
+        } else {
+            !confederation_asns.is_empty()
+        }
+    }
+
+    /// Attributes of the originated route after applying the export policy,
+    /// `None` when the route is rejected
+    fn export_route(&self, prefix: IpNet, attributes: &RouteAttributes) -> Option<RouteAttributes> {
+        let Some(policy) = &self.export_policy else {
+            return Some(attributes.clone());
+        };
+        let mut route = OriginatedRoute::new(prefix, attributes.clone());
+        match policy.apply(&mut route) {
+            PolicyResult::Accept => Some(route.into_attributes()),
+            PolicyResult::Reject => None,
+        }
+    }
+
+    fn exported_routes(&self) -> Vec<(IpNet, RouteAttributes)> {
+        self.originated_routes
+            .iter()
+            .filter_map(|(prefix, attributes)| {
+                self.export_route(*prefix, attributes)
+                    .map(|exported| (*prefix, exported))
+            })
+            .collect()
+    }
+
+    /// Send the withdrawn prefixes and the announced routes grouped by their
+    /// attributes, only when the session is established. The messages are
+    /// taken from `shared` when they were generated for the update group of
+    /// the peer already.
+    async fn send_routes(
+        &mut self,
+        announced: Vec<(IpNet, RouteAttributes)>,
+        withdrawn: Vec<IpNet>,
+        shared: Option<&SharedUpdates>,
+    ) -> Result<(), FsmStateError<A>> {
+        let Some(builder) = self.update_builder() else {
+            return Ok(());
+        };
+        let updates = match shared {
+            Some(shared) => {
+                let group = UpdateGroup::new(builder.clone(), self.export_policy.clone());
+                shared.get_or_generate(&group, &announced, &withdrawn, || {
+                    self.build_updates(&builder, &announced, &withdrawn)
+                })
+            }
+            None => Arc::new(self.build_updates(&builder, &announced, &withdrawn)),
+        };
+        self.send_updates(updates.iter().cloned().collect()).await
+    }
+
+    fn build_updates(
+        &self,
+        builder: &UpdateBuilder,
+        announced: &[(IpNet, RouteAttributes)],
+        withdrawn: &[IpNet],
+    ) -> Vec<BgpUpdateMessage> {
+        let mut updates = vec![];
+        if !withdrawn.is_empty() {
+            match builder.withdraw(withdrawn) {
+                Ok(withdraw) => updates.extend(withdraw),
+                Err(err) => log::warn!(
+                    "[{}][{}] Couldn't withdraw routes {withdrawn:?}: {err:?}",
+                    self.peer_key,
+                    self.fsm_state,
+                ),
+            }
+        }
+        let mut groups: Vec<(&RouteAttributes, Vec<IpNet>)> = vec![];
+        for (prefix, attributes) in announced {
+            match groups.iter_mut().find(|(attrs, _)| *attrs == attributes) {
+                Some((_, prefixes)) => prefixes.push(*prefix),
+                None => groups.push((attributes, vec![*prefix])),
+            }
+        }
+        for (attributes, prefixes) in groups {
+            match builder.announce(&prefixes, attributes) {
+                Ok(announce) => updates.extend(announce),
+                Err(err) => log::warn!(
+                    "[{}][{}] Couldn't announce routes {prefixes:?}: {err:?}",
+                    self.peer_key,
+                    self.fsm_state,
+                ),
+            }
+        }
+        updates
+    }
+
+    /// Updates are only sent on the main connection, since the tracked one is
+    /// never established
+    async fn send_updates(
+        &mut self,
+        updates: Vec<BgpUpdateMessage>,
+    ) -> Result<(), FsmStateError<A>> {
+        if let Some(connection) = self.connection.as_mut() {
+            for update in updates {
+                connection.send(BgpMessage::Update(update)).await?;
+            }
+        }
+        Ok(())
+    }
+
     pub fn waiting_admin_events(&self) -> &Vec<PeerAdminEvents<A, I>> {
         &self.waiting_admin_events
     }
@@ -791,28 +1951,61 @@ impl<
             .and_then(|c| c.received_capabilities().cloned())
     }
 
-    async fn shutdown(&mut self) {
-        log::info!("[{}][{}] Shutting down peer", self.peer_key, self.fsm_state);
+    /// Stop the peer, the optional `communication` is sent to the peer in the
+    /// Administrative Shutdown notification as defined by
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003)
+    async fn shutdown(&mut self, communication: Option<&str>) {
+        log::info!(
+            "[{}][{}] Shutting down peer: {communication:?}",
+            self.peer_key,
+            self.fsm_state
+        );
+        let cease = communication
+            .and_then(|communication| {
+                CeaseError::administrative_shutdown(communication)
+                    .inspect_err(|err| {
+                        log::warn!(
+                            "[{}][{}] Invalid shutdown communication: {err:?}",
+                            self.peer_key,
+                            self.fsm_state
+                        )
+                    })
+                    .ok()
+            })
+            .unwrap_or(CeaseError::AdministrativeShutdown { value: vec![] });
+        if let Some(bmp) = self.bmp.as_mut() {
+            // Reported before the routes are removed
+            bmp.set_down_reason(
+                PeerDownNotificationReason::LocalSystemClosedNotificationPduFollows(
+                    BgpMessage::Notification(BgpNotificationMessage::CeaseError(cease.clone())),
+                ),
+            );
+            bmp.peer_down();
+        }
         self.connect_retry_timer.take();
+        self.restart_timer.take();
+        self.stale_routes_timer.take();
+        self.max_prefixes_restart_timer.take();
+        self.idle_hold_timer.take();
+        self.idle_hold_time = self.config.idle_hold_duration();
+        let changes = self.adj_rib_in.clear();
+        self.notify_route_changes(changes);
         self.peer_state = PeerState::AdminDown;
         self.fsm_transition(FsmState::Idle);
         // Dropping connections
         if let Some(conn) = self.connection.as_mut() {
             let _ = conn
                 .send(BgpMessage::Notification(
-                    BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
-                        value: vec![],
-                    }),
+                    BgpNotificationMessage::CeaseError(cease.clone()),
                 ))
                 .await;
         }
+        self.update_last_error();
         self.connection.take();
         if let Some(conn) = self.tracked_connection.as_mut() {
             let _ = conn
                 .send(BgpMessage::Notification(
-                    BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
-                        value: vec![],
-                    }),
+                    BgpNotificationMessage::CeaseError(cease),
                 ))
                 .await;
         }
@@ -821,6 +2014,8 @@ impl<
 
     fn start(&mut self) {
         self.peer_state = PeerState::AdminUp;
+        self.max_prefixes_restart_timer.take();
+        self.idle_hold_timer.take();
         self.stats.connect_retry_counter = 0;
         if self.fsm_state != FsmState::Idle {
             // Start events are ignored in already started peer
@@ -927,6 +2122,16 @@ impl<
             .post_handle_connection_event_hook(event, Some(conn))
             .await;
         let conn_state_after = conn.state();
+        if conn_state_before == ConnectionState::Established
+            && conn_state_after == ConnectionState::Terminate
+        {
+            if let Some(bmp) = self.bmp.as_mut() {
+                bmp.set_down_reason(peer_down_reason(&event, conn.sent_notification()));
+            }
+        }
+        if let Some(last_error) = conn.stats().last_error() {
+            self.stats.last_error = Some(last_error);
+        }
         match (conn_state_before, conn_state_after, &event) {
             (ConnectionState::Connected, ConnectionState::Connected, event) => match event {
                 ConnectionEvent::TcpConnectionRequestAcked(_)
@@ -1241,9 +2446,39 @@ impl<
             }
             (ConnectionState::Established, ConnectionState::Established, event) => {
                 match event {
+                    ConnectionEvent::UpdateMsg(update, treatment) => {
+                        // stay in the same FSM state
+                        if let Some(bmp) = &self.bmp {
+                            bmp.pre_policy_update(update);
+                        }
+                        let changes = self.adj_rib_in.update(update, treatment);
+                        let announced = changes.iter().rev().find_map(|change| match change {
+                            RouteChange::Announced(route) => Some(route.key().address_type()),
+                            _ => None,
+                        });
+                        let announced_count = |changes: &[RouteChange]| {
+                            changes
+                                .iter()
+                                .filter(|change| matches!(change, RouteChange::Announced(_)))
+                                .count()
+                        };
+                        let received = announced_count(&changes);
+                        let changes = self.import_routes(changes);
+                        if let Some(bmp) = self.bmp.as_mut() {
+                            bmp.add_rejected_prefixes(received - announced_count(&changes));
+                        }
+                        self.notify_route_changes(changes);
+                        if self.adj_rib_in.stale_len() == 0 {
+                            self.stale_routes_timer.take();
+                        }
+                        if let Some(address_type) = announced {
+                            if self.check_max_prefixes(address_type).await {
+                                return Ok(BgpEvent::AutomaticStop);
+                            }
+                        }
+                    }
                     ConnectionEvent::KeepAliveTimerExpires
                     | ConnectionEvent::KeepAliveMsg
-                    | ConnectionEvent::UpdateMsg(_, _)
                     | ConnectionEvent::RouteRefresh(_)
                     | ConnectionEvent::NotifMsgErr(_) => {
                         // stay in the same FSM state
@@ -1276,6 +2511,9 @@ impl<
                 | ConnectionEvent::RouteRefreshErr(_)
                 | ConnectionEvent::NotifMsg(_)
                 | ConnectionEvent::NotifMsgVerErr => {
+                    if let ConnectionEvent::TcpConnectionFails = event {
+                        self.start_graceful_restart();
+                    }
                     self.connection.take();
                     self.connect_retry_timer.take();
                     self.stats.connect_retry_counter += 1;
@@ -1305,6 +2543,7 @@ impl<
         peer_key: K,
         peer_addr: A,
         active_connect: &mut C,
+        config: &PeerConfig,
         fsm_state: FsmState,
         connect_timeout: Duration,
         allowed_to_active_connect: &mut bool,
@@ -1313,7 +2552,11 @@ impl<
             (FsmState::Connect, true) => {
                 log::info!("[{peer_key}][{fsm_state}] Connecting to peer: {peer_addr}");
                 *allowed_to_active_connect = false;
-                match tokio::time::timeout(connect_timeout, active_connect.connect(peer_addr)).await
+                match tokio::time::timeout(
+                    connect_timeout,
+                    active_connect.connect(peer_addr, config),
+                )
+                .await
                 {
                     Ok(Ok(stream)) => Ok(stream),
                     Ok(Err(err)) => {
@@ -1555,28 +2798,41 @@ impl<
                     }
                 }
                 PeerAdminEvents::ManualStop => {
-                    self.shutdown().await;
+                    self.shutdown(None).await;
+                    self.stats.connect_retry_counter = 0;
+                    Some(BgpEvent::ManualStop)
+                }
+                PeerAdminEvents::ManualStopWithCommunication(communication) => {
+                    self.shutdown(Some(&communication)).await;
                     self.stats.connect_retry_counter = 0;
                     Some(BgpEvent::ManualStop)
                 }
                 PeerAdminEvents::AutomaticStart => {
-                    if self.fsm_state != FsmState::Idle {
+                    if self.fsm_state != FsmState::Idle || self.idle_hold_timer.is_some() {
+                        // Automatic starts wait for the IdleHoldTimer
                         None
                     } else {
                         self.start();
+                        let damp = self.config.damp_peer_oscillations;
                         if self.config.passive_tcp_establishment {
                             let mut interval =
                                 tokio::time::interval(self.config.connect_retry_duration());
                             interval.reset();
                             self.connect_retry_timer.replace(interval);
-                            Some(BgpEvent::AutomaticStartWithPassiveTcp)
+                            if damp {
+                                Some(BgpEvent::AutomaticStartWithDampPeerOscillationsPassiveTcp)
+                            } else {
+                                Some(BgpEvent::AutomaticStartWithPassiveTcp)
+                            }
+                        } else if damp {
+                            Some(BgpEvent::AutomaticStartWithDampPeerOscillations)
                         } else {
                             Some(BgpEvent::AutomaticStart)
                         }
                     }
                 }
                 PeerAdminEvents::AutomaticStop => {
-                    self.shutdown().await;
+                    self.shutdown(None).await;
                     self.stats.connect_retry_counter += 1;
                     Some(BgpEvent::AutomaticStop)
                 }
@@ -1593,6 +2849,7 @@ impl<
                 self.peer_key,
                 self.properties.peer_addr,
                 &mut self.active_connect,
+                &self.config,
                 self.fsm_state,
                 // Arbitrary one second timeout if connect retry duration is very small
                 self.config.connect_retry_duration().add(Duration::from_secs(1)),
@@ -1623,7 +2880,99 @@ impl<
                 self.connection.as_mut(),
                 self.tracked_connection.as_mut())
             => {
-               self.handle_connect_event(value).await
+                let before = self.fsm_state;
+                let event = self.handle_connect_event(value).await?;
+                if before != FsmState::Established && self.fsm_state == FsmState::Established {
+                    self.session_established().await?;
+                }
+                Ok(event)
+            }
+            _ = async {
+                    match self.restart_timer.as_mut() {
+                        Some(interval) => {
+                            interval.tick().await;
+                        },
+                        None => std::future::pending().await,
+                    }
+                }
+            => {
+                log::info!(
+                    "[{}][{}] Restart time expired, removing the stale routes",
+                    self.peer_key,
+                    self.fsm_state,
+                );
+                self.restart_timer.take();
+                let changes = self.adj_rib_in.remove_all_stale();
+                self.notify_route_changes(changes);
+                Ok(BgpEvent::GracefulRestartTimerExpires)
+            }
+            _ = async {
+                    match self.stale_routes_timer.as_mut() {
+                        Some(interval) => {
+                            interval.tick().await;
+                        },
+                        None => std::future::pending().await,
+                    }
+                }
+            => {
+                log::info!(
+                    "[{}][{}] End-of-RIB not received in time, removing the stale routes",
+                    self.peer_key,
+                    self.fsm_state,
+                );
+                self.stale_routes_timer.take();
+                let changes = self.adj_rib_in.remove_all_stale();
+                self.notify_route_changes(changes);
+                Ok(BgpEvent::StaleRoutesTimerExpires)
+            }
+            _ = async {
+                    match self.max_prefixes_restart_timer.as_mut() {
+                        Some(interval) => {
+                            interval.tick().await;
+                        },
+                        None => std::future::pending().await,
+                    }
+                }
+            => {
+                self.max_prefixes_restart_timer.take();
+                self.waiting_admin_events.push(PeerAdminEvents::AutomaticStart);
+                Ok(BgpEvent::MaxPrefixesRestartTimerExpires)
+            }
+            _ = async {
+                    match self.idle_hold_timer.as_mut() {
+                        Some(interval) => {
+                            interval.tick().await;
+                        },
+                        None => std::future::pending().await,
+                    }
+                }
+            => {
+                self.idle_hold_timer.take();
+                self.waiting_admin_events.push(PeerAdminEvents::AutomaticStart);
+                Ok(BgpEvent::IdleHoldTimerExpires)
+            }
+            event = async {
+                    match self.bmp.as_mut() {
+                        Some(bmp) => bmp.next_event().await,
+                        None => std::future::pending().await,
+                    }
+                }
+            => {
+                match event {
+                    BmpMonitorEvent::StationChanged => {
+                        if let Some(bmp) = self.bmp.as_mut() {
+                            bmp.station_changed();
+                        }
+                        self.bmp_peer_up();
+                        Ok(BgpEvent::BmpStationChanged)
+                    }
+                    BmpMonitorEvent::StatisticsTimerExpires => {
+                        if let Some(bmp) = &self.bmp {
+                            bmp.statistics_report(&self.adj_rib_in);
+                        }
+                        Ok(BgpEvent::BmpStatisticsTimerExpires)
+                    }
+                }
             }
         }
     }
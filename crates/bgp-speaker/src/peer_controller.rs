@@ -14,11 +14,18 @@
 // limitations under the License.
 
 use crate::{
+    bmp::BmpPeerExporter,
     connection::{ActiveConnect, ConnectionStats},
     events::BgpEvent,
     fsm::{FsmState, FsmStateError},
+    metrics::{MetricsRecorder, PeerMetrics},
     peer::*,
+    rib::{AdjRibIn, RouteChange},
+    route_policy::RoutePolicy,
+    update::RouteAttributes,
+    update_group::SharedUpdates,
 };
+use ipnet::IpNet;
 use netgauze_bgp_pkt::{
     capabilities::BgpCapability,
     codec::{BgpCodecDecoderError, BgpCodecInitializer},
@@ -29,6 +36,7 @@ use std::{
     error::Error,
     fmt::{Debug, Display},
     marker::PhantomData,
+    sync::Arc,
 };
 use tokio::{
     io::{AsyncRead, AsyncWrite},
@@ -105,12 +113,48 @@ impl<
                 PeerEvent::BgpMessage(msg) => {
                     peer.send_bgp_message(msg).await?;
                 }
+                PeerEvent::Announce(prefixes, attributes, shared) => match shared {
+                    Some(shared) => {
+                        peer.announce_shared_routes(prefixes, attributes, &shared)
+                            .await?
+                    }
+                    None => peer.announce_routes(prefixes, attributes).await?,
+                },
+                PeerEvent::Withdraw(prefixes, shared) => match shared {
+                    Some(shared) => peer.withdraw_shared_routes(prefixes, &shared).await?,
+                    None => peer.withdraw_routes(prefixes).await?,
+                },
+                PeerEvent::GetAdjRibIn(tx) => {
+                    if let Err(err) = tx.send(peer.adj_rib_in().clone()) {
+                        log::error!("Error sending Adj-RIB-In: {err:?}");
+                    }
+                }
+                PeerEvent::SubscribeRouteChanges(tx) => {
+                    peer.subscribe_route_changes(tx);
+                }
+                PeerEvent::SetImportPolicy(policy) => {
+                    peer.set_import_policy(policy);
+                }
+                PeerEvent::SetExportPolicy(policy) => {
+                    peer.set_export_policy(policy).await?;
+                }
+                PeerEvent::SetMetricsRecorder(recorder) => {
+                    peer.set_metrics_recorder(recorder);
+                }
+                PeerEvent::SetBmpExporter(exporter) => {
+                    peer.set_bmp_exporter(exporter);
+                }
                 PeerEvent::GetPeerStats(tx) => {
                     let stats = peer.peer_stats();
                     if let Err(err) = tx.send(stats) {
                         log::error!("Error sending peer stats: {err:?}");
                     }
                 }
+                PeerEvent::GetPeerMetrics(tx) => {
+                    if let Err(err) = tx.send(peer.metrics()) {
+                        log::error!("Error sending peer metrics: {err:?}");
+                    }
+                }
                 PeerEvent::GetConnectionStats(tx) => {
                     let stats = peer.main_connection_stats();
                     if let Err(err) = tx.send(stats) {
@@ -230,6 +274,7 @@ impl<
                         }
                     }
                     bgp_event = peer.run() => {
+                        peer.record_metrics();
                         let ret = Self::handle_bgp_event(bgp_event, peer_key,peer.fsm_state(), rec_tx.clone());
                         if ret.is_err() {
                             // Errors should be logged in [Self::handle_bgp_event]
@@ -295,6 +340,18 @@ impl<A: Display + Debug + 'static, I: AsyncWrite + AsyncRead + 'static> PeerHand
             .send(PeerEvent::Admin(PeerAdminEvents::ManualStop))
     }
 
+    /// Shutdown the peer with a Shutdown Communication
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003), see
+    /// [`netgauze_bgp_pkt::notification::CeaseError::administrative_shutdown`]
+    pub fn shutdown_with_communication(
+        &self,
+        communication: &str,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx.send(PeerEvent::Admin(
+            PeerAdminEvents::ManualStopWithCommunication(communication.to_string()),
+        ))
+    }
+
     pub fn accept_connection(
         &mut self,
         peer_addr: A,
@@ -306,12 +363,117 @@ impl<A: Display + Debug + 'static, I: AsyncWrite + AsyncRead + 'static> PeerHand
             ))))
     }
 
+    /// Originate the prefixes with the attributes to the peer, see
+    /// [`Peer::announce_routes`]
+    pub fn announce(
+        &self,
+        prefixes: Vec<IpNet>,
+        attributes: RouteAttributes,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::Announce(prefixes, attributes, None))
+    }
+
+    /// Originate the prefixes with the attributes to the peer, sharing the
+    /// Update messages with the peers of its update group, see
+    /// [`Peer::announce_shared_routes`]
+    pub fn announce_shared(
+        &self,
+        prefixes: Vec<IpNet>,
+        attributes: RouteAttributes,
+        shared: SharedUpdates,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::Announce(prefixes, attributes, Some(shared)))
+    }
+
+    /// Withdraw originated prefixes from the peer, see
+    /// [`Peer::withdraw_routes`]
+    pub fn withdraw(&self, prefixes: Vec<IpNet>) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::Withdraw(prefixes, None))
+    }
+
+    /// Withdraw originated prefixes from the peer, sharing the Update messages
+    /// with the peers of its update group, see [`Peer::withdraw_shared_routes`]
+    pub fn withdraw_shared(
+        &self,
+        prefixes: Vec<IpNet>,
+        shared: SharedUpdates,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::Withdraw(prefixes, Some(shared)))
+    }
+
+    /// Snapshot of the routes received from the peer
+    pub async fn adj_rib_in(&mut self) -> Result<AdjRibIn, Box<dyn Error>> {
+        let (tx, rx) = oneshot::channel();
+        self.peer_events_tx.send(PeerEvent::GetAdjRibIn(tx))?;
+        Ok(rx.await?)
+    }
+
+    /// Receive the changes of the Adj-RIB-In, a batch of changes per update
+    /// message received from the peer. All the routes are withdrawn when the
+    /// session goes down.
+    pub fn subscribe_route_changes(
+        &self,
+    ) -> Result<mpsc::UnboundedReceiver<Vec<RouteChange>>, SendError<PeerEvent<A, I>>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.peer_events_tx
+            .send(PeerEvent::SubscribeRouteChanges(tx))?;
+        Ok(rx)
+    }
+
+    /// Replace the policy applied on the routes received from the peer, see
+    /// [`Peer::set_import_policy`]
+    pub fn set_import_policy(
+        &self,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx.send(PeerEvent::SetImportPolicy(policy))
+    }
+
+    /// Replace the policy applied on the routes originated to the peer, see
+    /// [`Peer::set_export_policy`]
+    pub fn set_export_policy(
+        &self,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx.send(PeerEvent::SetExportPolicy(policy))
+    }
+
     pub async fn peer_stats(&mut self) -> Result<PeerStats, Box<dyn Error>> {
         let (tx, rx) = oneshot::channel();
         self.peer_events_tx.send(PeerEvent::GetPeerStats(tx))?;
         Ok(rx.await?)
     }
 
+    /// Snapshot of the metrics of the peer, see [`crate::metrics`]
+    pub async fn peer_metrics(&mut self) -> Result<PeerMetrics, Box<dyn Error>> {
+        let (tx, rx) = oneshot::channel();
+        self.peer_events_tx.send(PeerEvent::GetPeerMetrics(tx))?;
+        Ok(rx.await?)
+    }
+
+    /// Push the metrics of the peer to the recorder after every BGP event,
+    /// see [`Peer::set_metrics_recorder`]
+    pub fn set_metrics_recorder(
+        &self,
+        recorder: Option<Arc<dyn MetricsRecorder>>,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::SetMetricsRecorder(recorder))
+    }
+
+    /// Export the peer to a BMP station, see [`Peer::set_bmp_exporter`]
+    pub fn set_bmp_exporter(
+        &self,
+        exporter: Option<BmpPeerExporter<A>>,
+    ) -> Result<(), SendError<PeerEvent<A, I>>> {
+        self.peer_events_tx
+            .send(PeerEvent::SetBmpExporter(exporter))
+    }
+
     pub async fn connection_stats(&mut self) -> Result<Option<ConnectionStats>, Box<dyn Error>> {
         let (tx, rx) = oneshot::channel();
         self.peer_events_tx
@@ -0,0 +1,1088 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Routing Information Bases of the speaker.
+//!
+//! [`AdjRibIn`] stores the routes received from a peer before any import
+//! policy is applied, as defined in
+//! [RFC4271 Section 3.2](https://datatracker.ietf.org/doc/html/rfc4271#section-3.2).
+//! The routes are keyed by [`RouteKey`], and every change made to the RIB is
+//! returned as a [`RouteChange`]. Only the IP unicast and multicast address
+//! types are stored, the routes of the other address types are ignored.
+//!
+//! [`LocRib`] gathers the routes of all the peers and selects the best path of
+//! every prefix with the decision process of
+//! [RFC4271 Section 9.1.2](https://datatracker.ietf.org/doc/html/rfc4271#section-9.1.2).
+//! It's fed with the [`RouteChange`]s of the peers, e.g., from
+//! [`crate::peer_controller::PeerHandle::subscribe_route_changes`], and
+//! returns the changes of the best paths as [`LocRibChange`]s.
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    community::Community,
+    iana::{PathAttributeType, AS_TRANS},
+    path_attribute::{
+        Aggregator, As4Aggregator, As4PathSegment, AsPath, AsPathSegmentType, Communities,
+        LocalPreference, MpReach, MpUnreach, MultiExitDiscriminator, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+};
+use netgauze_iana::address_family::{AddressFamily, AddressType, SubsequentAddressFamily};
+
+use crate::{events::UpdateTreatment, route_policy::PolicyRoute, update::path_attribute};
+
+/// Key of a route in a RIB. The path identifier is only present when
+/// ADD-PATH is used to receive multiple paths for the same prefix.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct RouteKey {
+    address_type: AddressType,
+    prefix: IpNet,
+    path_id: Option<u32>,
+}
+
+impl RouteKey {
+    pub const fn new(address_type: AddressType, prefix: IpNet, path_id: Option<u32>) -> Self {
+        Self {
+            address_type,
+            prefix,
+            path_id,
+        }
+    }
+
+    pub const fn address_type(&self) -> AddressType {
+        self.address_type
+    }
+
+    pub const fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    pub const fn path_id(&self) -> Option<u32> {
+        self.path_id
+    }
+}
+
+/// Route received from a peer. The path attributes are shared between all
+/// the routes received in the same update message, and don't include the
+/// `MP_REACH_NLRI` and `MP_UNREACH_NLRI` attributes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    key: RouteKey,
+    next_hop: Option<IpAddr>,
+    path_attributes: Arc<Vec<PathAttribute>>,
+}
+
+impl Route {
+    pub const fn new(
+        key: RouteKey,
+        next_hop: Option<IpAddr>,
+        path_attributes: Arc<Vec<PathAttribute>>,
+    ) -> Self {
+        Self {
+            key,
+            next_hop,
+            path_attributes,
+        }
+    }
+
+    pub const fn key(&self) -> &RouteKey {
+        &self.key
+    }
+
+    /// Next hop of the `NEXT_HOP` attribute for IPv4 unicast NLRI, or the
+    /// global next hop of the `MP_REACH_NLRI` attribute
+    pub const fn next_hop(&self) -> Option<IpAddr> {
+        self.next_hop
+    }
+
+    pub fn path_attributes(&self) -> &[PathAttribute] {
+        &self.path_attributes
+    }
+
+    fn find_attribute<T>(&self, f: impl Fn(&PathAttributeValue) -> Option<T>) -> Option<T> {
+        self.path_attributes.iter().find_map(|attr| f(attr.value()))
+    }
+
+    pub fn origin(&self) -> Option<Origin> {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::Origin(origin) => Some(*origin),
+            _ => None,
+        })
+    }
+
+    pub fn local_preference(&self) -> Option<u32> {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::LocalPreference(local_pref) => Some(local_pref.metric()),
+            _ => None,
+        })
+    }
+
+    pub fn med(&self) -> Option<u32> {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::MultiExitDiscriminator(med) => Some(med.metric()),
+            _ => None,
+        })
+    }
+
+    pub fn originator_id(&self) -> Option<Ipv4Addr> {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::Originator(originator) => Some(originator.id()),
+            _ => None,
+        })
+    }
+
+    pub fn cluster_list_length(&self) -> usize {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::ClusterList(cluster_list) => {
+                Some(cluster_list.cluster_list().len())
+            }
+            _ => None,
+        })
+        .unwrap_or(0)
+    }
+
+    /// Segments of the `AS_PATH` attribute with four-octet AS numbers. The
+    /// `AS_PATH` of a peer without four-octet AS support is merged with the
+    /// `AS4_PATH` attribute, see [`merge_as4_path`].
+    fn as_path_segments(&self) -> Vec<(AsPathSegmentType, Vec<u32>)> {
+        let as_path = self.find_attribute(|value| match value {
+            PathAttributeValue::AsPath(AsPath::As2PathSegments(segments)) => Some((
+                false,
+                segments
+                    .iter()
+                    .map(|segment| {
+                        let as_numbers = segment.as_numbers().iter().map(|asn| *asn as u32);
+                        (segment.segment_type(), as_numbers.collect())
+                    })
+                    .collect(),
+            )),
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(segments)) => Some((
+                true,
+                segments
+                    .iter()
+                    .map(|segment| (segment.segment_type(), segment.as_numbers().clone()))
+                    .collect(),
+            )),
+            _ => None,
+        });
+        let Some((asn4, as_path)) = as_path else {
+            return vec![];
+        };
+        // The AS4 attributes are only meaningful when received from a peer
+        // without four-octet AS support
+        if asn4 || self.ignore_as4_attributes() {
+            return as_path;
+        }
+        let as4_path = self.find_attribute(|value| match value {
+            PathAttributeValue::As4Path(as4_path) => Some(
+                as4_path
+                    .segments()
+                    .iter()
+                    .map(|segment| (segment.segment_type(), segment.as_numbers().clone()))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        });
+        match as4_path {
+            Some(as4_path) => merge_as4_path(as_path, as4_path),
+            None => as_path,
+        }
+    }
+
+    /// The `AS4_PATH` and `AS4_AGGREGATOR` attributes are ignored when the
+    /// `AGGREGATOR` doesn't carry `AS_TRANS`, see
+    /// [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793#section-4.2.3)
+    fn ignore_as4_attributes(&self) -> bool {
+        let as2_aggregator = self.find_attribute(|value| match value {
+            PathAttributeValue::Aggregator(Aggregator::As2Aggregator(aggregator)) => {
+                Some(*aggregator.asn())
+            }
+            _ => None,
+        });
+        let as4_aggregator = self.find_attribute(|value| match value {
+            PathAttributeValue::As4Aggregator(_) => Some(()),
+            _ => None,
+        });
+        as4_aggregator.is_some() && as2_aggregator.is_some_and(|asn| asn != AS_TRANS)
+    }
+
+    /// The `AGGREGATOR` attribute with a four-octet AS number, the
+    /// `AS4_AGGREGATOR` attribute is used instead when the `AGGREGATOR` of a
+    /// peer without four-octet AS support carries `AS_TRANS`
+    pub fn aggregator(&self) -> Option<As4Aggregator> {
+        let (asn4, aggregator) = self.find_attribute(|value| match value {
+            PathAttributeValue::Aggregator(Aggregator::As2Aggregator(aggregator)) => Some((
+                false,
+                As4Aggregator::new(*aggregator.asn() as u32, aggregator.origin()),
+            )),
+            PathAttributeValue::Aggregator(Aggregator::As4Aggregator(aggregator)) => {
+                Some((true, aggregator.clone()))
+            }
+            _ => None,
+        })?;
+        if asn4 || *aggregator.asn() != AS_TRANS as u32 {
+            return Some(aggregator);
+        }
+        self.find_attribute(|value| match value {
+            PathAttributeValue::As4Aggregator(as4_aggregator) => Some(as4_aggregator.clone()),
+            _ => None,
+        })
+        .or(Some(aggregator))
+    }
+
+    /// Length of the `AS_PATH` attribute, an `AS_SET` counts as one AS and
+    /// the confederation segments are not counted, see
+    /// [RFC5065](https://datatracker.ietf.org/doc/html/rfc5065#section-5.3)
+    pub fn as_path_length(&self) -> usize {
+        as_numbers_count(&self.as_path_segments())
+    }
+
+    /// AS numbers of the `AS_SEQUENCE` and `AS_SET` segments of the
+    /// `AS_PATH`, or of its `AS_CONFED_SEQUENCE` and `AS_CONFED_SET` segments
+    /// when `confederation` is set
+    pub fn as_path_numbers(&self, confederation: bool) -> Vec<u32> {
+        self.as_path_segments()
+            .into_iter()
+            .filter(|(segment_type, _)| segment_type.is_confederation() == confederation)
+            .flat_map(|(_, as_numbers)| as_numbers)
+            .collect()
+    }
+
+    /// The AS the route was received from, i.e., the first AS of the
+    /// `AS_PATH` after the confederation segments
+    pub fn neighbor_as(&self) -> Option<u32> {
+        match self
+            .as_path_segments()
+            .into_iter()
+            .find(|(segment_type, _)| !segment_type.is_confederation())
+        {
+            Some((AsPathSegmentType::AsSequence, as_numbers)) => as_numbers.first().copied(),
+            _ => None,
+        }
+    }
+}
+
+/// Number of AS numbers in the segments, an `AS_SET` counts as one AS and the
+/// confederation segments are not counted
+fn as_numbers_count(segments: &[(AsPathSegmentType, Vec<u32>)]) -> usize {
+    segments
+        .iter()
+        .map(|(segment_type, as_numbers)| match segment_type {
+            AsPathSegmentType::AsSet => 1,
+            AsPathSegmentType::AsSequence => as_numbers.len(),
+            AsPathSegmentType::AsConfedSequence | AsPathSegmentType::AsConfedSet => 0,
+        })
+        .sum()
+}
+
+/// Merge the `AS_PATH` received from a peer without four-octet AS support with
+/// the `AS4_PATH`, as defined in
+/// [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793#section-4.2.3): the
+/// leading AS numbers of the `AS_PATH` that are not in the `AS4_PATH` are
+/// prepended to the `AS4_PATH`. The `AS4_PATH` is ignored when it has more AS
+/// numbers than the `AS_PATH`, and its confederation segments are discarded.
+fn merge_as4_path(
+    as_path: Vec<(AsPathSegmentType, Vec<u32>)>,
+    as4_path: Vec<(AsPathSegmentType, Vec<u32>)>,
+) -> Vec<(AsPathSegmentType, Vec<u32>)> {
+    let as4_path: Vec<_> = as4_path
+        .into_iter()
+        .filter(|(segment_type, _)| !segment_type.is_confederation())
+        .collect();
+    let as_path_count = as_numbers_count(&as_path);
+    let as4_path_count = as_numbers_count(&as4_path);
+    if as_path_count < as4_path_count {
+        return as_path;
+    }
+    let mut leading = as_path_count - as4_path_count;
+    let mut merged = vec![];
+    for (segment_type, as_numbers) in as_path {
+        match segment_type {
+            AsPathSegmentType::AsConfedSequence | AsPathSegmentType::AsConfedSet => {
+                merged.push((segment_type, as_numbers));
+            }
+            AsPathSegmentType::AsSet if leading > 0 => {
+                merged.push((segment_type, as_numbers));
+                leading -= 1;
+            }
+            AsPathSegmentType::AsSequence if leading > 0 => {
+                let len = leading.min(as_numbers.len());
+                merged.push((segment_type, as_numbers[..len].to_vec()));
+                leading -= len;
+            }
+            AsPathSegmentType::AsSet | AsPathSegmentType::AsSequence => {}
+        }
+    }
+    merged.extend(as4_path);
+    merged
+}
+
+impl PolicyRoute for Route {
+    fn prefix(&self) -> IpNet {
+        self.key.prefix
+    }
+
+    fn as_path(&self) -> Vec<As4PathSegment> {
+        self.as_path_segments()
+            .into_iter()
+            .map(|(segment_type, as_numbers)| As4PathSegment::new(segment_type, as_numbers))
+            .collect()
+    }
+
+    fn communities(&self) -> Vec<Community> {
+        self.find_attribute(|value| match value {
+            PathAttributeValue::Communities(communities) => Some(communities.communities().clone()),
+            _ => None,
+        })
+        .unwrap_or_default()
+    }
+
+    fn med(&self) -> Option<u32> {
+        Route::med(self)
+    }
+
+    fn local_preference(&self) -> Option<u32> {
+        Route::local_preference(self)
+    }
+
+    fn set_communities(&mut self, communities: Vec<Community>) {
+        let value = (!communities.is_empty())
+            .then(|| PathAttributeValue::Communities(Communities::new(communities)));
+        self.set_attribute(PathAttributeType::Communities, true, true, value);
+    }
+
+    fn set_med(&mut self, med: Option<u32>) {
+        let value = med.map(|med| {
+            PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(med))
+        });
+        self.set_attribute(
+            PathAttributeType::MultiExitDiscriminator,
+            true,
+            false,
+            value,
+        );
+    }
+
+    fn set_local_preference(&mut self, local_preference: Option<u32>) {
+        let value = local_preference.map(|local_preference| {
+            PathAttributeValue::LocalPreference(LocalPreference::new(local_preference))
+        });
+        self.set_attribute(PathAttributeType::LocalPreference, false, true, value);
+    }
+}
+
+impl Route {
+    /// Replace the attribute of the type, or remove it when `value` is
+    /// `None`. The attributes are kept in the order of their type codes.
+    fn set_attribute(
+        &mut self,
+        attribute_type: PathAttributeType,
+        optional: bool,
+        transitive: bool,
+        value: Option<PathAttributeValue>,
+    ) {
+        let code = |attr: &PathAttribute| {
+            attr.path_attribute_type()
+                .map_or_else(|code| code, |attribute_type| attribute_type as u8)
+        };
+        let path_attributes = Arc::make_mut(&mut self.path_attributes);
+        path_attributes.retain(|attr| attr.path_attribute_type() != Ok(attribute_type));
+        let Some(value) = value else {
+            return;
+        };
+        let Ok(attribute) = path_attribute(optional, transitive, value) else {
+            return;
+        };
+        let index = path_attributes
+            .iter()
+            .position(|attr| code(attr) > attribute_type as u8)
+            .unwrap_or(path_attributes.len());
+        path_attributes.insert(index, attribute);
+    }
+}
+
+/// Typed change of a RIB
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteChange {
+    /// New route, or new attributes of an existing route
+    Announced(Route),
+    /// Removed route with its last attributes
+    Withdrawn(Route),
+    /// The peer has sent all its routes of the address type, see
+    /// [RFC4724](https://datatracker.ietf.org/doc/html/rfc4724)
+    EndOfRib(AddressType),
+}
+
+/// Routes received from a peer, see the [module](self) docs
+#[derive(Debug, Clone, Default)]
+pub struct AdjRibIn {
+    routes: HashMap<RouteKey, Route>,
+    /// Routes retained from the previous session of a restarting peer, see
+    /// [RFC4724](https://datatracker.ietf.org/doc/html/rfc4724)
+    stale: HashSet<RouteKey>,
+}
+
+impl AdjRibIn {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    pub fn get(&self, key: &RouteKey) -> Option<&Route> {
+        self.routes.get(key)
+    }
+
+    pub fn routes(&self) -> impl Iterator<Item = &Route> {
+        self.routes.values()
+    }
+
+    /// Routes of the address type
+    pub fn address_type_routes(&self, address_type: AddressType) -> impl Iterator<Item = &Route> {
+        self.routes
+            .values()
+            .filter(move |route| route.key.address_type == address_type)
+    }
+
+    pub fn is_stale(&self, key: &RouteKey) -> bool {
+        self.stale.contains(key)
+    }
+
+    /// Number of stale routes
+    pub fn stale_len(&self) -> usize {
+        self.stale.len()
+    }
+
+    /// Address types that still have stale routes
+    pub fn stale_address_types(&self) -> HashSet<AddressType> {
+        self.stale.iter().map(|key| key.address_type).collect()
+    }
+
+    /// Mark the routes of the address types as stale, and remove the routes
+    /// of the other address types. The stale routes are replaced by the ones
+    /// received again from the peer, and removed by the End-of-RIB marker of
+    /// their address type.
+    pub fn mark_stale(&mut self, address_types: &[AddressType]) -> Vec<RouteChange> {
+        let mut changes = vec![];
+        self.routes.retain(|key, route| {
+            let retained = address_types.contains(&key.address_type);
+            if !retained {
+                changes.push(RouteChange::Withdrawn(route.clone()));
+            }
+            retained
+        });
+        self.stale = self.routes.keys().copied().collect();
+        changes
+    }
+
+    /// Remove the stale routes of the address type
+    pub fn remove_stale(&mut self, address_type: AddressType) -> Vec<RouteChange> {
+        let keys = self
+            .stale
+            .iter()
+            .filter(|key| key.address_type == address_type)
+            .copied()
+            .collect::<Vec<_>>();
+        keys.iter()
+            .filter_map(|key| {
+                self.stale.remove(key);
+                self.routes.remove(key)
+            })
+            .map(RouteChange::Withdrawn)
+            .collect()
+    }
+
+    /// Remove all the stale routes
+    pub fn remove_all_stale(&mut self) -> Vec<RouteChange> {
+        self.stale
+            .drain()
+            .filter_map(|key| self.routes.remove(&key))
+            .map(RouteChange::Withdrawn)
+            .collect()
+    }
+
+    /// Apply an update message received from the peer according to the
+    /// [`UpdateTreatment`] of its errors as defined in
+    /// [RFC7606](https://datatracker.ietf.org/doc/html/rfc7606). The
+    /// withdrawn routes are processed before the announced ones. The
+    /// End-of-RIB marker removes the stale routes of its address type.
+    pub fn update(
+        &mut self,
+        update: &BgpUpdateMessage,
+        treatment: &UpdateTreatment,
+    ) -> Vec<RouteChange> {
+        if let Some(address_type) = update.end_of_rib() {
+            let mut changes = self.remove_stale(address_type);
+            changes.push(RouteChange::EndOfRib(address_type));
+            return changes;
+        }
+        let mut changes = vec![];
+        let (mut withdrawn, mut announced) = update_nlri(update);
+        match treatment {
+            UpdateTreatment::Normal | UpdateTreatment::AttributeDiscard => {}
+            UpdateTreatment::TreatAsWithdraw => {
+                withdrawn.extend(announced.drain(..).map(|(key, _)| key));
+            }
+            UpdateTreatment::ResetAddressFamily(afi, safi) => {
+                let address_type = AddressFamily::try_from(*afi)
+                    .ok()
+                    .zip(SubsequentAddressFamily::try_from(*safi).ok())
+                    .and_then(|(afi, safi)| AddressType::from_afi_safi(afi, safi).ok());
+                if let Some(address_type) = address_type {
+                    changes.extend(self.withdraw_address_type(address_type));
+                    withdrawn.retain(|key| key.address_type != address_type);
+                    announced.retain(|(key, _)| key.address_type != address_type);
+                }
+            }
+            // The session is terminated, and the RIB cleared along with it
+            UpdateTreatment::SessionReset => return vec![],
+        }
+        for key in withdrawn {
+            self.stale.remove(&key);
+            if let Some(route) = self.routes.remove(&key) {
+                changes.push(RouteChange::Withdrawn(route));
+            }
+        }
+        if !announced.is_empty() {
+            let path_attributes = Arc::new(
+                update
+                    .path_attributes()
+                    .iter()
+                    .filter(|attr| {
+                        !matches!(
+                            attr.value(),
+                            PathAttributeValue::MpReach(_) | PathAttributeValue::MpUnreach(_)
+                        )
+                    })
+                    .cloned()
+                    .collect::<Vec<_>>(),
+            );
+            for (key, next_hop) in announced {
+                let route = Route::new(key, next_hop, path_attributes.clone());
+                self.stale.remove(&key);
+                self.routes.insert(key, route.clone());
+                changes.push(RouteChange::Announced(route));
+            }
+        }
+        changes
+    }
+
+    /// Remove all the routes of the address type
+    pub fn withdraw_address_type(&mut self, address_type: AddressType) -> Vec<RouteChange> {
+        let keys = self
+            .address_type_routes(address_type)
+            .map(|route| route.key)
+            .collect::<Vec<_>>();
+        keys.iter()
+            .filter_map(|key| {
+                self.stale.remove(key);
+                self.routes.remove(key)
+            })
+            .map(RouteChange::Withdrawn)
+            .collect()
+    }
+
+    /// Remove all the routes, e.g., when the session goes down
+    pub fn clear(&mut self) -> Vec<RouteChange> {
+        self.stale.clear();
+        self.routes
+            .drain()
+            .map(|(_, route)| RouteChange::Withdrawn(route))
+            .collect()
+    }
+}
+
+/// `LOCAL_PREF` of the routes received without the attribute, e.g., from
+/// eBGP peers
+pub const DEFAULT_LOCAL_PREFERENCE: u32 = 100;
+
+/// Peer a route of the [`LocRib`] is received from, with the properties used
+/// by the decision process
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct RibPeer<K> {
+    key: K,
+    ebgp: bool,
+    bgp_id: Ipv4Addr,
+    address: IpAddr,
+}
+
+impl<K> RibPeer<K> {
+    pub const fn new(key: K, ebgp: bool, bgp_id: Ipv4Addr, address: IpAddr) -> Self {
+        Self {
+            key,
+            ebgp,
+            bgp_id,
+            address,
+        }
+    }
+
+    pub const fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub const fn ebgp(&self) -> bool {
+        self.ebgp
+    }
+
+    pub const fn bgp_id(&self) -> Ipv4Addr {
+        self.bgp_id
+    }
+
+    pub const fn address(&self) -> IpAddr {
+        self.address
+    }
+}
+
+/// Route of the [`LocRib`] and the peer it's received from
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocRibRoute<K> {
+    peer: RibPeer<K>,
+    route: Route,
+}
+
+impl<K> LocRibRoute<K> {
+    pub const fn new(peer: RibPeer<K>, route: Route) -> Self {
+        Self { peer, route }
+    }
+
+    pub const fn peer(&self) -> &RibPeer<K> {
+        &self.peer
+    }
+
+    pub const fn route(&self) -> &Route {
+        &self.route
+    }
+}
+
+/// Change of the best path of a prefix in the [`LocRib`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocRibChange<K> {
+    /// New best path of the prefix
+    Best(LocRibRoute<K>),
+    /// The prefix isn't reachable anymore, carries the last best path
+    Withdrawn(LocRibRoute<K>),
+}
+
+/// Cost to reach the next hop of a route, used to break the ties between the
+/// routes by the decision process
+pub trait IgpMetric {
+    /// `None` when the cost is unknown, such routes are less preferred than
+    /// the ones with a known cost
+    fn igp_metric(&self, next_hop: IpAddr) -> Option<u32>;
+}
+
+/// The IGP cost is unknown for all the next hops, hence not used by the
+/// decision process
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoIgpMetric;
+
+impl IgpMetric for NoIgpMetric {
+    fn igp_metric(&self, _next_hop: IpAddr) -> Option<u32> {
+        None
+    }
+}
+
+/// Routes of the destination (address type and prefix), and the index of the
+/// best one
+#[derive(Debug, Clone)]
+struct Destination<K> {
+    paths: Vec<LocRibRoute<K>>,
+    best: Option<usize>,
+}
+
+/// Routes of all the peers and their best paths, see the [module](self) docs
+#[derive(Debug, Clone)]
+pub struct LocRib<K, M = NoIgpMetric> {
+    always_compare_med: bool,
+    igp_metric: M,
+    destinations: HashMap<(AddressType, IpNet), Destination<K>>,
+}
+
+impl<K: Copy + Eq> Default for LocRib<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Copy + Eq> LocRib<K> {
+    pub fn new() -> Self {
+        Self {
+            always_compare_med: false,
+            igp_metric: NoIgpMetric,
+            destinations: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Copy + Eq, M: IgpMetric> LocRib<K, M> {
+    /// Compare the MED of routes received from different neighbor AS
+    pub fn with_always_compare_med(mut self, always_compare_med: bool) -> Self {
+        self.always_compare_med = always_compare_med;
+        self
+    }
+
+    pub fn with_igp_metric<N: IgpMetric>(self, igp_metric: N) -> LocRib<K, N> {
+        LocRib {
+            always_compare_med: self.always_compare_med,
+            igp_metric,
+            destinations: self.destinations,
+        }
+    }
+
+    pub const fn always_compare_med(&self) -> bool {
+        self.always_compare_med
+    }
+
+    pub const fn igp_metric(&self) -> &M {
+        &self.igp_metric
+    }
+
+    /// Changing the IGP costs requires running [`LocRib::reselect`]
+    pub fn igp_metric_mut(&mut self) -> &mut M {
+        &mut self.igp_metric
+    }
+
+    /// Number of prefixes with a best path
+    pub fn len(&self) -> usize {
+        self.destinations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.destinations.is_empty()
+    }
+
+    pub fn best(&self, address_type: AddressType, prefix: IpNet) -> Option<&LocRibRoute<K>> {
+        self.destinations
+            .get(&(address_type, prefix))
+            .and_then(|destination| destination.best.map(|index| &destination.paths[index]))
+    }
+
+    /// All the paths of the prefix, including the best one
+    pub fn paths(&self, address_type: AddressType, prefix: IpNet) -> &[LocRibRoute<K>] {
+        self.destinations
+            .get(&(address_type, prefix))
+            .map(|destination| destination.paths.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Best paths of all the prefixes
+    pub fn routes(&self) -> impl Iterator<Item = &LocRibRoute<K>> {
+        self.destinations
+            .values()
+            .filter_map(|destination| destination.best.map(|index| &destination.paths[index]))
+    }
+
+    /// Apply the changes of the Adj-RIB-In of a peer, and return the changes
+    /// of the best paths
+    pub fn update(&mut self, peer: &RibPeer<K>, changes: &[RouteChange]) -> Vec<LocRibChange<K>> {
+        let mut updated = vec![];
+        let mut seen = HashSet::new();
+        for change in changes {
+            let route = match change {
+                RouteChange::Announced(route) | RouteChange::Withdrawn(route) => route,
+                RouteChange::EndOfRib(_) => continue,
+            };
+            let destination_key = (route.key.address_type, route.key.prefix);
+            let destination =
+                self.destinations
+                    .entry(destination_key)
+                    .or_insert_with(|| Destination {
+                        paths: vec![],
+                        best: None,
+                    });
+            if seen.insert(destination_key) {
+                let previous = destination
+                    .best
+                    .map(|index| destination.paths[index].clone());
+                updated.push((destination_key, previous));
+            }
+            // The best path is selected again once all the changes are applied
+            destination.best = None;
+            let position = destination.paths.iter().position(|path| {
+                path.peer.key == peer.key && path.route.key.path_id == route.key.path_id
+            });
+            match (change, position) {
+                (RouteChange::Announced(_), Some(index)) => {
+                    destination.paths[index] = LocRibRoute::new(*peer, route.clone());
+                }
+                (RouteChange::Announced(_), None) => {
+                    destination
+                        .paths
+                        .push(LocRibRoute::new(*peer, route.clone()));
+                }
+                (_, Some(index)) => {
+                    destination.paths.remove(index);
+                }
+                (_, None) => {}
+            }
+        }
+        updated
+            .into_iter()
+            .filter_map(|(key, previous)| self.select(key, previous))
+            .collect()
+    }
+
+    /// Remove all the routes of the peer, e.g., when it's removed
+    pub fn remove_peer(&mut self, peer_key: &K) -> Vec<LocRibChange<K>> {
+        let withdrawn = self
+            .destinations
+            .values()
+            .flat_map(|destination| destination.paths.iter())
+            .filter(|path| &path.peer.key == peer_key)
+            .map(|path| RouteChange::Withdrawn(path.route.clone()))
+            .collect::<Vec<_>>();
+        let Some(peer) = self
+            .destinations
+            .values()
+            .flat_map(|destination| destination.paths.iter())
+            .find(|path| &path.peer.key == peer_key)
+            .map(|path| path.peer)
+        else {
+            return vec![];
+        };
+        self.update(&peer, &withdrawn)
+    }
+
+    /// Run the decision process for all the prefixes again, e.g., after the
+    /// IGP costs changed
+    pub fn reselect(&mut self) -> Vec<LocRibChange<K>> {
+        let destinations = self
+            .destinations
+            .iter()
+            .map(|(key, destination)| {
+                let previous = destination
+                    .best
+                    .map(|index| destination.paths[index].clone());
+                (*key, previous)
+            })
+            .collect::<Vec<_>>();
+        destinations
+            .into_iter()
+            .filter_map(|(key, previous)| self.select(key, previous))
+            .collect()
+    }
+
+    /// Select the best path of the destination, and return the change
+    /// compared to the previous best path
+    fn select(
+        &mut self,
+        key: (AddressType, IpNet),
+        previous: Option<LocRibRoute<K>>,
+    ) -> Option<LocRibChange<K>> {
+        let destination = self.destinations.get_mut(&key)?;
+        if destination.paths.is_empty() {
+            self.destinations.remove(&key);
+            return previous.map(LocRibChange::Withdrawn);
+        }
+        let best = decision_process(
+            &destination.paths,
+            self.always_compare_med,
+            &self.igp_metric,
+        );
+        destination.best = Some(best);
+        let best = &destination.paths[best];
+        if previous.as_ref() == Some(best) {
+            return None;
+        }
+        Some(LocRibChange::Best(best.clone()))
+    }
+}
+
+/// Index of the best path according to
+/// [RFC4271 Section 9.1.2.2](https://datatracker.ietf.org/doc/html/rfc4271#section-9.1.2.2),
+/// with the tie-breakers of
+/// [RFC4456 Section 9](https://datatracker.ietf.org/doc/html/rfc4456#section-9).
+/// Every step only keeps the most preferred routes of the previous one.
+fn decision_process<K, M: IgpMetric>(
+    paths: &[LocRibRoute<K>],
+    always_compare_med: bool,
+    igp_metric: &M,
+) -> usize {
+    fn keep_min<K, T: Ord>(
+        paths: &[LocRibRoute<K>],
+        candidates: &mut Vec<usize>,
+        f: impl Fn(&LocRibRoute<K>) -> T,
+    ) {
+        if let Some(min) = candidates.iter().map(|index| f(&paths[*index])).min() {
+            candidates.retain(|index| f(&paths[*index]) == min);
+        }
+    }
+
+    let mut candidates = (0..paths.len()).collect::<Vec<_>>();
+    // a) Highest LOCAL_PREF
+    keep_min(paths, &mut candidates, |path| {
+        std::cmp::Reverse(
+            path.route
+                .local_preference()
+                .unwrap_or(DEFAULT_LOCAL_PREFERENCE),
+        )
+    });
+    // b) Shortest AS_PATH
+    keep_min(paths, &mut candidates, |path| path.route.as_path_length());
+    // c) Lowest ORIGIN, a missing ORIGIN is the least preferred
+    keep_min(paths, &mut candidates, |path| {
+        path.route
+            .origin()
+            .map(|origin| origin as u8)
+            .unwrap_or(u8::MAX)
+    });
+    // d) Lowest MED among the routes from the same neighbor AS, a missing MED
+    // is the lowest possible value
+    let med = |index: &usize| paths[*index].route.med().unwrap_or(0);
+    if always_compare_med {
+        keep_min(paths, &mut candidates, |path| path.route.med().unwrap_or(0));
+    } else {
+        let neighbor_as = |index: &usize| paths[*index].route.neighbor_as();
+        let kept = candidates
+            .iter()
+            .filter(|index| {
+                !candidates.iter().any(|other| {
+                    neighbor_as(other) == neighbor_as(index) && med(other) < med(index)
+                })
+            })
+            .copied()
+            .collect();
+        candidates = kept;
+    }
+    // e) Routes from eBGP peers over the routes from iBGP peers
+    keep_min(paths, &mut candidates, |path| !path.peer.ebgp);
+    // f) Lowest IGP cost to the next hop
+    keep_min(paths, &mut candidates, |path| {
+        path.route
+            .next_hop
+            .and_then(|next_hop| igp_metric.igp_metric(next_hop))
+            .map_or(u64::MAX, u64::from)
+    });
+    // g) Lowest BGP Identifier, or ORIGINATOR_ID of reflected routes
+    keep_min(paths, &mut candidates, |path| {
+        path.route.originator_id().unwrap_or(path.peer.bgp_id)
+    });
+    // Shortest CLUSTER_LIST
+    keep_min(paths, &mut candidates, |path| {
+        path.route.cluster_list_length()
+    });
+    // h) Lowest peer address, then the lowest path identifier
+    keep_min(paths, &mut candidates, |path| path.peer.address);
+    keep_min(paths, &mut candidates, |path| path.route.key.path_id);
+    candidates[0]
+}
+
+/// The keys of the withdrawn routes, and the keys and next hops of the
+/// announced routes of an update message
+#[allow(clippy::type_complexity)]
+fn update_nlri(update: &BgpUpdateMessage) -> (Vec<RouteKey>, Vec<(RouteKey, Option<IpAddr>)>) {
+    let mut withdrawn = update
+        .withdraw_routes()
+        .iter()
+        .map(|addr| {
+            RouteKey::new(
+                AddressType::Ipv4Unicast,
+                addr.network().address().into(),
+                addr.path_id(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut next_hop = None;
+    let mut announced = vec![];
+    for attr in update.path_attributes() {
+        match attr.value() {
+            PathAttributeValue::NextHop(value) => next_hop = Some(IpAddr::V4(value.next_hop())),
+            PathAttributeValue::MpReach(mp_reach) => match mp_reach {
+                MpReach::Ipv4Unicast { next_hop, nlri, .. } => {
+                    announced.extend(nlri.iter().map(|addr| {
+                        let prefix = addr.network().address().into();
+                        (
+                            RouteKey::new(AddressType::Ipv4Unicast, prefix, addr.path_id()),
+                            Some(*next_hop),
+                        )
+                    }))
+                }
+                MpReach::Ipv4Multicast { next_hop, nlri, .. } => {
+                    announced.extend(nlri.iter().map(|addr| {
+                        let prefix = addr.network().address().into();
+                        (
+                            RouteKey::new(AddressType::Ipv4Multicast, prefix, addr.path_id()),
+                            Some(*next_hop),
+                        )
+                    }))
+                }
+                MpReach::Ipv6Unicast {
+                    next_hop_global,
+                    nlri,
+                    ..
+                } => announced.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    (
+                        RouteKey::new(AddressType::Ipv6Unicast, prefix, addr.path_id()),
+                        Some(IpAddr::V6(*next_hop_global)),
+                    )
+                })),
+                MpReach::Ipv6Multicast {
+                    next_hop_global,
+                    nlri,
+                    ..
+                } => announced.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    (
+                        RouteKey::new(AddressType::Ipv6Multicast, prefix, addr.path_id()),
+                        Some(IpAddr::V6(*next_hop_global)),
+                    )
+                })),
+                _ => {}
+            },
+            PathAttributeValue::MpUnreach(mp_unreach) => match mp_unreach {
+                MpUnreach::Ipv4Unicast { nlri } => withdrawn.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    RouteKey::new(AddressType::Ipv4Unicast, prefix, addr.path_id())
+                })),
+                MpUnreach::Ipv4Multicast { nlri } => withdrawn.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    RouteKey::new(AddressType::Ipv4Multicast, prefix, addr.path_id())
+                })),
+                MpUnreach::Ipv6Unicast { nlri } => withdrawn.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    RouteKey::new(AddressType::Ipv6Unicast, prefix, addr.path_id())
+                })),
+                MpUnreach::Ipv6Multicast { nlri } => withdrawn.extend(nlri.iter().map(|addr| {
+                    let prefix = addr.network().address().into();
+                    RouteKey::new(AddressType::Ipv6Multicast, prefix, addr.path_id())
+                })),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    announced.extend(update.nlri().iter().map(|addr| {
+        let prefix = addr.network().address().into();
+        (
+            RouteKey::new(AddressType::Ipv4Unicast, prefix, addr.path_id()),
+            next_hop,
+        )
+    }));
+    (withdrawn, announced)
+}
@@ -0,0 +1,374 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Import and export policies of the routes exchanged with a peer.
+//!
+//! A [`RoutePolicy`] accepts or rejects a route, and can modify its attributes
+//! on the way. The import policy of a peer is applied on the routes received
+//! from the peer before they're reported as [`crate::rib::RouteChange`]s, the
+//! Adj-RIB-In keeps the routes as received. The export policy is applied on
+//! the routes originated to the peer before they're sent.
+//!
+//! [`Policy`] is the declarative implementation: a list of [`PolicyTerm`]s
+//! evaluated in order, every term matching the route applies its actions
+//! until one terminates the evaluation with a [`PolicyResult`]. Programmatic
+//! policies are supplied by implementing [`RoutePolicy`], closures with the
+//! same signature as [`RoutePolicy::apply`] implement it as well.
+
+use std::fmt::{Debug, Formatter};
+
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    community::Community,
+    path_attribute::{As4PathSegment, AsPathSegmentType},
+};
+use regex::Regex;
+
+/// Route as seen by the policies, the attributes not listed here are left
+/// as they are
+pub trait PolicyRoute {
+    fn prefix(&self) -> IpNet;
+
+    /// AS path with four-octet AS numbers
+    fn as_path(&self) -> Vec<As4PathSegment>;
+
+    fn communities(&self) -> Vec<Community>;
+
+    fn med(&self) -> Option<u32>;
+
+    fn local_preference(&self) -> Option<u32>;
+
+    fn set_communities(&mut self, communities: Vec<Community>);
+
+    /// `None` removes the `MULTI_EXIT_DISC` attribute
+    fn set_med(&mut self, med: Option<u32>);
+
+    /// `None` removes the `LOCAL_PREF` attribute
+    fn set_local_preference(&mut self, local_preference: Option<u32>);
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PolicyResult {
+    Accept,
+    Reject,
+}
+
+/// Filter and modify the routes received from or sent to a peer, see the
+/// [module](self) docs
+pub trait RoutePolicy: Send + Sync {
+    fn apply(&self, route: &mut dyn PolicyRoute) -> PolicyResult;
+}
+
+impl Debug for dyn RoutePolicy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "RoutePolicy")
+    }
+}
+
+impl<F: Fn(&mut dyn PolicyRoute) -> PolicyResult + Send + Sync> RoutePolicy for F {
+    fn apply(&self, route: &mut dyn PolicyRoute) -> PolicyResult {
+        self(route)
+    }
+}
+
+/// Entry of a prefix list, matching the prefixes covered by `prefix` with a
+/// length between `ge` and `le`. Without `ge` and `le` only `prefix` itself
+/// is matched.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PrefixListEntry {
+    prefix: IpNet,
+    ge: Option<u8>,
+    le: Option<u8>,
+}
+
+impl PrefixListEntry {
+    pub fn new(prefix: IpNet) -> Self {
+        Self {
+            prefix: prefix.trunc(),
+            ge: None,
+            le: None,
+        }
+    }
+
+    pub const fn with_ge(mut self, ge: u8) -> Self {
+        self.ge = Some(ge);
+        self
+    }
+
+    pub const fn with_le(mut self, le: u8) -> Self {
+        self.le = Some(le);
+        self
+    }
+
+    pub const fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    pub const fn ge(&self) -> Option<u8> {
+        self.ge
+    }
+
+    pub const fn le(&self) -> Option<u8> {
+        self.le
+    }
+
+    pub fn matches(&self, prefix: &IpNet) -> bool {
+        if !self.prefix.contains(prefix) {
+            return false;
+        }
+        let len = prefix.prefix_len();
+        match (self.ge, self.le) {
+            (None, None) => len == self.prefix.prefix_len(),
+            (ge, le) => {
+                len >= ge.unwrap_or(self.prefix.prefix_len())
+                    && len <= le.unwrap_or(self.prefix.max_prefix_len())
+            }
+        }
+    }
+}
+
+/// Regular expression matched against the AS path written as its AS numbers
+/// separated by a space, the members of an `AS_SET` are enclosed in braces,
+/// e.g., `65001 65002 {65003 65004}`. The confederation segments are enclosed
+/// in parentheses for an `AS_CONFED_SEQUENCE` and in brackets for an
+/// `AS_CONFED_SET`, e.g., `(64512 64513) 65001`. The empty AS path of locally
+/// originated routes is matched by `^$`.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AsPathRegex(Regex);
+
+impl AsPathRegex {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self(Regex::new(pattern)?))
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+
+    pub fn matches(&self, as_path: &[As4PathSegment]) -> bool {
+        self.0.is_match(&as_path_string(as_path))
+    }
+}
+
+impl PartialEq for AsPathRegex {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for AsPathRegex {}
+
+impl TryFrom<String> for AsPathRegex {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Self::new(&pattern)
+    }
+}
+
+impl From<AsPathRegex> for String {
+    fn from(regex: AsPathRegex) -> Self {
+        regex.as_str().to_string()
+    }
+}
+
+fn as_path_string(as_path: &[As4PathSegment]) -> String {
+    let as_numbers = |segment: &As4PathSegment| {
+        segment
+            .as_numbers()
+            .iter()
+            .map(|asn| asn.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    as_path
+        .iter()
+        .map(|segment| match segment.segment_type() {
+            AsPathSegmentType::AsSequence => as_numbers(segment),
+            AsPathSegmentType::AsSet => format!("{{{}}}", as_numbers(segment)),
+            AsPathSegmentType::AsConfedSequence => format!("({})", as_numbers(segment)),
+            AsPathSegmentType::AsConfedSet => format!("[{}]", as_numbers(segment)),
+        })
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Condition of a [`PolicyTerm`]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PolicyMatch {
+    /// The prefix matches any of the entries
+    PrefixList(Vec<PrefixListEntry>),
+    /// The route carries any of the communities
+    Community(Vec<Community>),
+    AsPath(AsPathRegex),
+    /// The route doesn't satisfy the condition
+    Not(Box<PolicyMatch>),
+}
+
+impl PolicyMatch {
+    pub fn matches(&self, route: &dyn PolicyRoute) -> bool {
+        match self {
+            Self::PrefixList(entries) => {
+                let prefix = route.prefix();
+                entries.iter().any(|entry| entry.matches(&prefix))
+            }
+            Self::Community(communities) => {
+                let route_communities = route.communities();
+                communities
+                    .iter()
+                    .any(|community| route_communities.contains(community))
+            }
+            Self::AsPath(regex) => regex.matches(&route.as_path()),
+            Self::Not(inner) => !inner.matches(route),
+        }
+    }
+}
+
+/// Modification of the route attributes by a [`PolicyTerm`]
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PolicyAction {
+    SetMed(u32),
+    RemoveMed,
+    SetLocalPreference(u32),
+    /// Replace the communities of the route
+    SetCommunities(Vec<Community>),
+    /// Add the communities that the route doesn't carry yet
+    AddCommunities(Vec<Community>),
+    RemoveCommunities(Vec<Community>),
+}
+
+impl PolicyAction {
+    pub fn apply(&self, route: &mut dyn PolicyRoute) {
+        match self {
+            Self::SetMed(med) => route.set_med(Some(*med)),
+            Self::RemoveMed => route.set_med(None),
+            Self::SetLocalPreference(local_preference) => {
+                route.set_local_preference(Some(*local_preference))
+            }
+            Self::SetCommunities(communities) => route.set_communities(communities.clone()),
+            Self::AddCommunities(communities) => {
+                let mut route_communities = route.communities();
+                for community in communities {
+                    if !route_communities.contains(community) {
+                        route_communities.push(*community);
+                    }
+                }
+                route.set_communities(route_communities);
+            }
+            Self::RemoveCommunities(communities) => {
+                let mut route_communities = route.communities();
+                route_communities.retain(|community| !communities.contains(community));
+                route.set_communities(route_communities);
+            }
+        }
+    }
+}
+
+/// Actions applied on the routes satisfying all the conditions of the term.
+/// The evaluation of the policy stops at the first matching term with a
+/// result.
+#[derive(Debug, Clone, Default, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PolicyTerm {
+    matches: Vec<PolicyMatch>,
+    actions: Vec<PolicyAction>,
+    result: Option<PolicyResult>,
+}
+
+impl PolicyTerm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_match(mut self, policy_match: PolicyMatch) -> Self {
+        self.matches.push(policy_match);
+        self
+    }
+
+    pub fn with_action(mut self, action: PolicyAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub const fn with_result(mut self, result: PolicyResult) -> Self {
+        self.result = Some(result);
+        self
+    }
+
+    pub const fn matches(&self) -> &Vec<PolicyMatch> {
+        &self.matches
+    }
+
+    pub const fn actions(&self) -> &Vec<PolicyAction> {
+        &self.actions
+    }
+
+    pub const fn result(&self) -> Option<PolicyResult> {
+        self.result
+    }
+}
+
+/// Declarative [`RoutePolicy`], see the [module](self) docs
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Policy {
+    terms: Vec<PolicyTerm>,
+    default_result: PolicyResult,
+}
+
+impl Policy {
+    /// Policy without terms, the routes get the `default_result`
+    pub const fn new(default_result: PolicyResult) -> Self {
+        Self {
+            terms: vec![],
+            default_result,
+        }
+    }
+
+    pub fn with_term(mut self, term: PolicyTerm) -> Self {
+        self.terms.push(term);
+        self
+    }
+
+    pub const fn terms(&self) -> &Vec<PolicyTerm> {
+        &self.terms
+    }
+
+    /// Result of the routes that are not accepted or rejected by any term
+    pub const fn default_result(&self) -> PolicyResult {
+        self.default_result
+    }
+}
+
+impl RoutePolicy for Policy {
+    fn apply(&self, route: &mut dyn PolicyRoute) -> PolicyResult {
+        for term in &self.terms {
+            if !term
+                .matches
+                .iter()
+                .all(|policy_match| policy_match.matches(route))
+            {
+                continue;
+            }
+            for action in &term.actions {
+                action.apply(route);
+            }
+            if let Some(result) = term.result {
+                return result;
+            }
+        }
+        self.default_result
+    }
+}
@@ -0,0 +1,394 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Options of the TCP sockets carrying the BGP sessions.
+//!
+//! TCP MD5 signatures as defined in
+//! [RFC2385](https://datatracker.ietf.org/doc/html/rfc2385) are set with the
+//! Linux `TCP_MD5SIG` socket option, on the connecting sockets and on the
+//! listening sockets for each peer address.
+//!
+//! The sockets connecting to a peer are also marked with the DSCP of the peer,
+//! [`DSCP_CS6`] by default as recommended for the network control traffic by
+//! [RFC4594](https://datatracker.ietf.org/doc/html/rfc4594#section-3.2), and
+//! are optionally set with [`TcpKeepalive`] parameters and bound to a network
+//! interface.
+//!
+//! Setting these options on other platforms than Linux fails with
+//! [`io::ErrorKind::Unsupported`].
+
+use std::{
+    fmt::{Debug, Formatter},
+    io,
+    net::IpAddr,
+    time::Duration,
+};
+
+/// Max length of a TCP MD5 key supported by Linux
+pub const TCP_MD5_MAX_KEY_LENGTH: usize = 80;
+
+/// Max length of a network interface name supported by Linux, without the
+/// terminating NUL
+pub const INTERFACE_NAME_MAX_LENGTH: usize = 15;
+
+/// Class Selector 6 DSCP, used for the network control traffic
+pub const DSCP_CS6: u8 = 48;
+
+/// Max value of the 6 bits DSCP
+pub const DSCP_MAX: u8 = 63;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum TcpMd5KeyError {
+    Empty,
+    TooLong(usize),
+}
+
+/// Key of the TCP MD5 signatures, the key isn't shown by [`Debug`]
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct TcpMd5Key {
+    key: [u8; TCP_MD5_MAX_KEY_LENGTH],
+    len: u8,
+}
+
+impl TcpMd5Key {
+    pub const fn new(key: &[u8]) -> Result<Self, TcpMd5KeyError> {
+        if key.is_empty() {
+            return Err(TcpMd5KeyError::Empty);
+        }
+        if key.len() > TCP_MD5_MAX_KEY_LENGTH {
+            return Err(TcpMd5KeyError::TooLong(key.len()));
+        }
+        let mut buf = [0; TCP_MD5_MAX_KEY_LENGTH];
+        let mut i = 0;
+        while i < key.len() {
+            buf[i] = key[i];
+            i += 1;
+        }
+        Ok(Self {
+            key: buf,
+            len: key.len() as u8,
+        })
+    }
+
+    pub fn key(&self) -> &[u8] {
+        &self.key[..self.len as usize]
+    }
+}
+
+impl TryFrom<&str> for TcpMd5Key {
+    type Error = TcpMd5KeyError;
+
+    fn try_from(key: &str) -> Result<Self, Self::Error> {
+        Self::new(key.as_bytes())
+    }
+}
+
+impl Debug for TcpMd5Key {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TcpMd5Key(***)")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum InterfaceNameError {
+    Empty,
+    TooLong(usize),
+}
+
+/// Name of the network interface a socket is bound to
+#[derive(Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct InterfaceName {
+    name: [u8; INTERFACE_NAME_MAX_LENGTH],
+    len: u8,
+}
+
+impl InterfaceName {
+    pub const fn new(name: &[u8]) -> Result<Self, InterfaceNameError> {
+        if name.is_empty() {
+            return Err(InterfaceNameError::Empty);
+        }
+        if name.len() > INTERFACE_NAME_MAX_LENGTH {
+            return Err(InterfaceNameError::TooLong(name.len()));
+        }
+        let mut buf = [0; INTERFACE_NAME_MAX_LENGTH];
+        let mut i = 0;
+        while i < name.len() {
+            buf[i] = name[i];
+            i += 1;
+        }
+        Ok(Self {
+            name: buf,
+            len: name.len() as u8,
+        })
+    }
+
+    pub fn name(&self) -> &[u8] {
+        &self.name[..self.len as usize]
+    }
+}
+
+impl TryFrom<&str> for InterfaceName {
+    type Error = InterfaceNameError;
+
+    fn try_from(name: &str) -> Result<Self, Self::Error> {
+        Self::new(name.as_bytes())
+    }
+}
+
+impl Debug for InterfaceName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "InterfaceName({:?})",
+            String::from_utf8_lossy(self.name())
+        )
+    }
+}
+
+/// Parameters of the TCP keepalives, detecting a dead connection before the
+/// BGP hold timer expires
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct TcpKeepalive {
+    idle: u16,
+    interval: u16,
+    count: u8,
+}
+
+impl TcpKeepalive {
+    /// The first probe is sent after `idle` seconds without traffic, then
+    /// every `interval` seconds, the connection is closed after `count`
+    /// unanswered probes
+    pub const fn new(idle: u16, interval: u16, count: u8) -> Self {
+        Self {
+            idle,
+            interval,
+            count,
+        }
+    }
+
+    pub const fn idle(&self) -> Duration {
+        Duration::from_secs(self.idle as u64)
+    }
+
+    pub const fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval as u64)
+    }
+
+    pub const fn count(&self) -> u8 {
+        self.count
+    }
+}
+
+/// Set the key signing the segments exchanged with the peer on the socket, or
+/// remove it when `key` is `None`. On a listening socket, the key applies to
+/// the connections accepted from the peer.
+#[cfg(target_os = "linux")]
+pub fn set_tcp_md5_key<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    peer: IpAddr,
+    key: Option<&TcpMd5Key>,
+) -> io::Result<()> {
+    // `struct tcp_md5sig` from linux/tcp.h
+    #[repr(C)]
+    struct TcpMd5Sig {
+        tcpm_addr: libc::sockaddr_storage,
+        tcpm_flags: u8,
+        tcpm_prefixlen: u8,
+        tcpm_keylen: u16,
+        tcpm_ifindex: libc::c_int,
+        tcpm_key: [u8; TCP_MD5_MAX_KEY_LENGTH],
+    }
+
+    // SAFETY: all-zero is a valid value of the C struct
+    let mut sig: TcpMd5Sig = unsafe { std::mem::zeroed() };
+    match peer {
+        IpAddr::V4(addr) => {
+            let sin = libc::sockaddr_in {
+                sin_family: libc::AF_INET as libc::sa_family_t,
+                sin_port: 0,
+                sin_addr: libc::in_addr {
+                    s_addr: u32::from_ne_bytes(addr.octets()),
+                },
+                sin_zero: [0; 8],
+            };
+            // SAFETY: sockaddr_storage is large enough to hold any sockaddr
+            unsafe { std::ptr::write(&mut sig.tcpm_addr as *mut _ as *mut libc::sockaddr_in, sin) };
+        }
+        IpAddr::V6(addr) => {
+            let sin6 = libc::sockaddr_in6 {
+                sin6_family: libc::AF_INET6 as libc::sa_family_t,
+                sin6_port: 0,
+                sin6_flowinfo: 0,
+                sin6_addr: libc::in6_addr {
+                    s6_addr: addr.octets(),
+                },
+                sin6_scope_id: 0,
+            };
+            // SAFETY: sockaddr_storage is large enough to hold any sockaddr
+            unsafe {
+                std::ptr::write(
+                    &mut sig.tcpm_addr as *mut _ as *mut libc::sockaddr_in6,
+                    sin6,
+                )
+            };
+        }
+    }
+    if let Some(key) = key {
+        sig.tcpm_keylen = key.len as u16;
+        sig.tcpm_key = key.key;
+    }
+    // SAFETY: the option value is a valid `struct tcp_md5sig` of the given length
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_MD5SIG,
+            &sig as *const _ as *const libc::c_void,
+            std::mem::size_of::<TcpMd5Sig>() as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_tcp_md5_key<S>(_socket: &S, _peer: IpAddr, _key: Option<&TcpMd5Key>) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TCP MD5 signatures are only supported on Linux",
+    ))
+}
+
+#[cfg(target_os = "linux")]
+fn set_int_option<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    level: libc::c_int,
+    name: libc::c_int,
+    value: libc::c_int,
+) -> io::Result<()> {
+    // SAFETY: the option value is a valid int of the given length
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &value as *const _ as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Mark the packets sent on the socket connecting to the peer with the DSCP,
+/// in the IPv4 TOS or the IPv6 Traffic Class field
+#[cfg(target_os = "linux")]
+pub fn set_dscp<S: std::os::fd::AsRawFd>(socket: &S, peer: IpAddr, dscp: u8) -> io::Result<()> {
+    if dscp > DSCP_MAX {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid DSCP {dscp}"),
+        ));
+    }
+    let value = (dscp as libc::c_int) << 2;
+    match peer {
+        IpAddr::V4(_) => set_int_option(socket, libc::IPPROTO_IP, libc::IP_TOS, value),
+        IpAddr::V6(_) => set_int_option(socket, libc::IPPROTO_IPV6, libc::IPV6_TCLASS, value),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_dscp<S>(_socket: &S, _peer: IpAddr, _dscp: u8) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "DSCP marking is only supported on Linux",
+    ))
+}
+
+/// Enable the TCP keepalives on the socket with the given parameters
+#[cfg(target_os = "linux")]
+pub fn set_tcp_keepalive<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    keepalive: &TcpKeepalive,
+) -> io::Result<()> {
+    set_int_option(socket, libc::SOL_SOCKET, libc::SO_KEEPALIVE, 1)?;
+    set_int_option(
+        socket,
+        libc::IPPROTO_TCP,
+        libc::TCP_KEEPIDLE,
+        keepalive.idle as libc::c_int,
+    )?;
+    set_int_option(
+        socket,
+        libc::IPPROTO_TCP,
+        libc::TCP_KEEPINTVL,
+        keepalive.interval as libc::c_int,
+    )?;
+    set_int_option(
+        socket,
+        libc::IPPROTO_TCP,
+        libc::TCP_KEEPCNT,
+        keepalive.count as libc::c_int,
+    )
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_tcp_keepalive<S>(_socket: &S, _keepalive: &TcpKeepalive) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TCP keepalive parameters are only supported on Linux",
+    ))
+}
+
+/// Bind the socket to the network interface, the packets are only sent and
+/// received through it
+#[cfg(target_os = "linux")]
+pub fn bind_interface<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    interface: &InterfaceName,
+) -> io::Result<()> {
+    // SAFETY: the option value is the interface name of the given length
+    let ret = unsafe {
+        libc::setsockopt(
+            socket.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_BINDTODEVICE,
+            interface.name.as_ptr() as *const libc::c_void,
+            interface.len as libc::socklen_t,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn bind_interface<S>(_socket: &S, _interface: &InterfaceName) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Binding to an interface is only supported on Linux",
+    ))
+}
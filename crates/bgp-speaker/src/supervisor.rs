@@ -13,9 +13,16 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{connection::ActiveConnect, peer::*, peer_controller::*};
+use crate::{
+    admin::AdminRequest, bmp::BmpPeerExporter, connection::ActiveConnect, peer::*,
+    peer_controller::*, route_policy::RoutePolicy, update::RouteAttributes,
+    update_group::SharedUpdates,
+};
+use ipnet::IpNet;
 use netgauze_bgp_pkt::{
+    capabilities::{AddPathAddressFamily, BgpCapability},
     codec::{BgpCodecDecoderError, BgpCodecInitializer},
+    notification::SHUTDOWN_COMMUNICATION_MAX_LENGTH,
     wire::{deserializer::BgpParsingIgnoredErrors, serializer::BgpMessageWritingError},
     BgpMessage,
 };
@@ -24,6 +31,8 @@ use std::{
     fmt::{Debug, Display},
     hash::Hash,
     net::Ipv4Addr,
+    ops::RangeInclusive,
+    sync::Arc,
 };
 
 use tokio::{
@@ -35,6 +44,168 @@ use tokio_util::codec::{Decoder, Encoder};
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum PeersSupervisorError {
     PeerExists,
+    PeerNotFound,
+    GroupExists,
+    GroupNotFound,
+    /// A group can't be removed while it still has members
+    GroupHasMembers,
+    /// The peer is not running anymore
+    PeerNotRunning,
+    /// The Shutdown Communication is longer than
+    /// [`SHUTDOWN_COMMUNICATION_MAX_LENGTH`] octets
+    InvalidShutdownCommunication,
+}
+
+/// Configuration shared by the peers of a group. The peers are created with
+/// an [`EchoCapabilitiesPolicy`] advertising the capabilities of the group.
+#[derive(Debug, Clone)]
+pub struct PeerGroup {
+    config: PeerConfig,
+    send_asn4_cap_by_default: bool,
+    capabilities: Vec<BgpCapability>,
+    reject_capabilities: Vec<BgpCapability>,
+    add_path: Vec<AddPathAddressFamily>,
+    import_policy: Option<Arc<dyn RoutePolicy>>,
+    export_policy: Option<Arc<dyn RoutePolicy>>,
+}
+
+impl PeerGroup {
+    pub const fn new(config: PeerConfig) -> Self {
+        Self {
+            config,
+            send_asn4_cap_by_default: true,
+            capabilities: vec![],
+            reject_capabilities: vec![],
+            add_path: vec![],
+            import_policy: None,
+            export_policy: None,
+        }
+    }
+
+    pub const fn with_send_asn4_cap_by_default(mut self, value: bool) -> Self {
+        self.send_asn4_cap_by_default = value;
+        self
+    }
+
+    pub fn with_capabilities(mut self, capabilities: Vec<BgpCapability>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    pub fn with_reject_capabilities(mut self, capabilities: Vec<BgpCapability>) -> Self {
+        self.reject_capabilities = capabilities;
+        self
+    }
+
+    pub fn with_add_path(mut self, add_path: Vec<AddPathAddressFamily>) -> Self {
+        self.add_path = add_path;
+        self
+    }
+
+    pub fn with_import_policy(mut self, policy: Arc<dyn RoutePolicy>) -> Self {
+        self.import_policy = Some(policy);
+        self
+    }
+
+    pub fn with_export_policy(mut self, policy: Arc<dyn RoutePolicy>) -> Self {
+        self.export_policy = Some(policy);
+        self
+    }
+
+    pub const fn config(&self) -> &PeerConfig {
+        &self.config
+    }
+
+    pub const fn send_asn4_cap_by_default(&self) -> bool {
+        self.send_asn4_cap_by_default
+    }
+
+    pub const fn capabilities(&self) -> &Vec<BgpCapability> {
+        &self.capabilities
+    }
+
+    pub const fn reject_capabilities(&self) -> &Vec<BgpCapability> {
+        &self.reject_capabilities
+    }
+
+    pub const fn add_path(&self) -> &Vec<AddPathAddressFamily> {
+        &self.add_path
+    }
+
+    pub fn import_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.import_policy.as_ref()
+    }
+
+    pub fn export_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.export_policy.as_ref()
+    }
+
+    fn capabilities_policy<A, I, D>(
+        &self,
+        my_asn: u32,
+        my_bgp_id: Ipv4Addr,
+        config: &PeerConfig,
+    ) -> EchoCapabilitiesPolicy<A, I, D> {
+        let mut policy = EchoCapabilitiesPolicy::new(
+            my_asn,
+            self.send_asn4_cap_by_default,
+            my_bgp_id,
+            config.hold_timer_duration,
+            self.capabilities.clone(),
+            self.reject_capabilities.clone(),
+        );
+        policy.set_add_path(self.add_path.clone());
+        policy
+    }
+}
+
+/// Values of a group member replacing the ones of its [`PeerGroup`]. A
+/// config overriding some values only can start from the group's one with
+/// [`PeerConfigBuilder::from`].
+#[derive(Debug, Clone, Default)]
+pub struct PeerOverrides {
+    config: Option<PeerConfig>,
+    import_policy: Option<Arc<dyn RoutePolicy>>,
+    export_policy: Option<Arc<dyn RoutePolicy>>,
+}
+
+impl PeerOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub const fn with_config(mut self, config: PeerConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn with_import_policy(mut self, policy: Arc<dyn RoutePolicy>) -> Self {
+        self.import_policy = Some(policy);
+        self
+    }
+
+    pub fn with_export_policy(mut self, policy: Arc<dyn RoutePolicy>) -> Self {
+        self.export_policy = Some(policy);
+        self
+    }
+
+    pub const fn config(&self) -> Option<&PeerConfig> {
+        self.config.as_ref()
+    }
+
+    pub fn import_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.import_policy.as_ref()
+    }
+
+    pub fn export_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.export_policy.as_ref()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PeerGroupMember {
+    group: String,
+    overrides: PeerOverrides,
 }
 
 /// Peer lifetime management
@@ -44,6 +215,11 @@ pub struct PeersSupervisor<K: Hash + Eq + PartialEq, A, I: AsyncWrite + AsyncRea
     my_asn: u32,
     my_bgp_id: Ipv4Addr,
     peers: HashMap<K, PeerController<K, A, I>>,
+    groups: HashMap<String, PeerGroup>,
+    members: HashMap<K, PeerGroupMember>,
+    bmp_exporter: Option<BmpPeerExporter<A>>,
+    confederation: Option<Confederation>,
+    confederation_peers: Vec<u32>,
 }
 
 impl<
@@ -57,9 +233,63 @@ impl<
             my_asn,
             my_bgp_id,
             peers: HashMap::new(),
+            groups: HashMap::new(),
+            members: HashMap::new(),
+            bmp_exporter: None,
+            confederation: None,
+            confederation_peers: Vec::new(),
         }
     }
 
+    /// Make the speaker a member of a BGP confederation, the ASN of the
+    /// supervisor is the member AS. The peers in the member AS and in the
+    /// `confederation_peers` ASes are peered with the member AS, the others
+    /// with the confederation identifier. Applies to the peers created
+    /// afterward with [`Self::peer_properties`].
+    pub fn set_confederation(&mut self, identifier: u32, confederation_peers: Vec<u32>) {
+        self.confederation = Some(Confederation::new(identifier, self.my_asn));
+        self.confederation_peers = confederation_peers;
+    }
+
+    pub const fn confederation(&self) -> Option<Confederation> {
+        self.confederation
+    }
+
+    /// Properties of a peer with the local ASN chosen for the confederation,
+    /// if any. Peers with a dynamic ASN are expected inside the confederation.
+    pub fn peer_properties(
+        &self,
+        peer_asn: u32,
+        peer_addr: A,
+        allow_dynamic_as: bool,
+    ) -> PeerProperties<A> {
+        let Some(confederation) = self.confederation else {
+            return PeerProperties::new(
+                self.my_asn,
+                peer_asn,
+                self.my_bgp_id,
+                peer_addr,
+                allow_dynamic_as,
+            );
+        };
+        let my_asn = if allow_dynamic_as
+            || peer_asn == self.my_asn
+            || self.confederation_peers.contains(&peer_asn)
+        {
+            self.my_asn
+        } else {
+            confederation.identifier()
+        };
+        PeerProperties::new(
+            my_asn,
+            peer_asn,
+            self.my_bgp_id,
+            peer_addr,
+            allow_dynamic_as,
+        )
+        .with_confederation(confederation)
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn create_peer<
         D: BgpCodecInitializer<Peer<K, A, I, D, C, P>>
@@ -91,11 +321,23 @@ impl<
             active_connect,
         );
         let peer_handle = peer_controller.get_new_handle();
+        if self.bmp_exporter.is_some() {
+            let _ = peer_handle.set_bmp_exporter(self.bmp_exporter.clone());
+        }
         self.peers.insert(peer_key, peer_controller);
         Ok((rx, peer_handle))
     }
 
+    pub const fn my_asn(&self) -> u32 {
+        self.my_asn
+    }
+
+    pub const fn my_bgp_id(&self) -> Ipv4Addr {
+        self.my_bgp_id
+    }
+
     pub fn remove_peer(&mut self, peer_key: &K) -> Option<PeerController<K, A, I>> {
+        self.members.remove(peer_key);
         self.peers.remove(peer_key).map(|controller| {
             let handler = controller.get_new_handle();
             let _ = handler.shutdown();
@@ -111,6 +353,332 @@ impl<
         self.peers.keys().cloned().collect()
     }
 
+    fn running_peer(&self, peer_key: &K) -> Result<PeerHandle<A, I>, PeersSupervisorError> {
+        self.peers
+            .get(peer_key)
+            .map(|ctrl| ctrl.get_new_handle())
+            .ok_or(PeersSupervisorError::PeerNotFound)
+    }
+
+    pub fn start_peer(&self, peer_key: &K) -> Result<(), PeersSupervisorError> {
+        self.running_peer(peer_key)?
+            .start()
+            .map_err(|_| PeersSupervisorError::PeerNotRunning)
+    }
+
+    /// Administratively shutdown the peer, the optional `communication` is sent
+    /// to the peer as defined by
+    /// [RFC9003](https://datatracker.ietf.org/doc/html/rfc9003)
+    pub fn shutdown_peer(
+        &self,
+        peer_key: &K,
+        communication: Option<&str>,
+    ) -> Result<(), PeersSupervisorError> {
+        let handle = self.running_peer(peer_key)?;
+        let sent = match communication {
+            Some(communication) => {
+                if communication.len() > SHUTDOWN_COMMUNICATION_MAX_LENGTH {
+                    return Err(PeersSupervisorError::InvalidShutdownCommunication);
+                }
+                handle.shutdown_with_communication(communication)
+            }
+            None => handle.shutdown(),
+        };
+        sent.map_err(|_| PeersSupervisorError::PeerNotRunning)
+    }
+
+    /// Replace the import policy of a running peer. For group members, the
+    /// policy overrides the one of the group and `None` falls back to it.
+    pub fn set_import_policy(
+        &mut self,
+        peer_key: &K,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), PeersSupervisorError> {
+        let handle = self.running_peer(peer_key)?;
+        let sent = match self.members.get_mut(peer_key) {
+            Some(member) => {
+                member.overrides.import_policy = policy;
+                self.apply_route_policies(peer_key)
+            }
+            None => handle.set_import_policy(policy).is_ok(),
+        };
+        sent.then_some(())
+            .ok_or(PeersSupervisorError::PeerNotRunning)
+    }
+
+    /// Replace the export policy of a running peer. For group members, the
+    /// policy overrides the one of the group and `None` falls back to it.
+    pub fn set_export_policy(
+        &mut self,
+        peer_key: &K,
+        policy: Option<Arc<dyn RoutePolicy>>,
+    ) -> Result<(), PeersSupervisorError> {
+        let handle = self.running_peer(peer_key)?;
+        let sent = match self.members.get_mut(peer_key) {
+            Some(member) => {
+                member.overrides.export_policy = policy;
+                self.apply_route_policies(peer_key)
+            }
+            None => handle.set_export_policy(policy).is_ok(),
+        };
+        sent.then_some(())
+            .ok_or(PeersSupervisorError::PeerNotRunning)
+    }
+
+    /// Originate the prefixes with the attributes to all the peers, the
+    /// updates are generated once per update group, see
+    /// [`crate::update_group`]. Returns the keys of the peers that are not
+    /// running anymore.
+    pub fn announce(&self, prefixes: &[IpNet], attributes: &RouteAttributes) -> Vec<K> {
+        let shared = SharedUpdates::new();
+        self.peers
+            .iter()
+            .filter(|(_, ctrl)| {
+                ctrl.get_new_handle()
+                    .announce_shared(prefixes.to_vec(), attributes.clone(), shared.clone())
+                    .is_err()
+            })
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Withdraw originated prefixes from all the peers, the updates are
+    /// generated once per update group. Returns the keys of the peers that
+    /// are not running anymore.
+    pub fn withdraw(&self, prefixes: &[IpNet]) -> Vec<K> {
+        let shared = SharedUpdates::new();
+        self.peers
+            .iter()
+            .filter(|(_, ctrl)| {
+                ctrl.get_new_handle()
+                    .withdraw_shared(prefixes.to_vec(), shared.clone())
+                    .is_err()
+            })
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Export all the peers, including the ones created later, to a BMP
+    /// station, see [`crate::bmp`]. `None` stops the export. Returns the keys
+    /// of the peers that are not running anymore.
+    pub fn set_bmp_exporter(&mut self, exporter: Option<BmpPeerExporter<A>>) -> Vec<K> {
+        self.bmp_exporter = exporter;
+        self.peers
+            .iter()
+            .filter(|(_, ctrl)| {
+                ctrl.get_new_handle()
+                    .set_bmp_exporter(self.bmp_exporter.clone())
+                    .is_err()
+            })
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    pub fn add_group(&mut self, name: &str, group: PeerGroup) -> Result<(), PeersSupervisorError> {
+        if self.groups.contains_key(name) {
+            return Err(PeersSupervisorError::GroupExists);
+        }
+        self.groups.insert(name.to_string(), group);
+        Ok(())
+    }
+
+    /// Replace the configuration of a group. The route policies are applied
+    /// right away to the running members, the timers and capabilities only to
+    /// the members created afterward. Returns the keys of the members that
+    /// are not running anymore.
+    pub fn update_group(
+        &mut self,
+        name: &str,
+        group: PeerGroup,
+    ) -> Result<Vec<K>, PeersSupervisorError> {
+        let Some(current) = self.groups.get_mut(name) else {
+            return Err(PeersSupervisorError::GroupNotFound);
+        };
+        *current = group;
+        let members: Vec<K> = self
+            .members
+            .iter()
+            .filter(|(_, member)| member.group == name)
+            .map(|(key, _)| *key)
+            .collect();
+        Ok(members
+            .into_iter()
+            .filter(|key| !self.apply_route_policies(key))
+            .collect())
+    }
+
+    pub fn remove_group(&mut self, name: &str) -> Result<PeerGroup, PeersSupervisorError> {
+        if !self.groups.contains_key(name) {
+            return Err(PeersSupervisorError::GroupNotFound);
+        }
+        if self.members.values().any(|member| member.group == name) {
+            return Err(PeersSupervisorError::GroupHasMembers);
+        }
+        self.groups
+            .remove(name)
+            .ok_or(PeersSupervisorError::GroupNotFound)
+    }
+
+    pub fn group(&self, name: &str) -> Option<&PeerGroup> {
+        self.groups.get(name)
+    }
+
+    /// Name of the group of the peer
+    pub fn peer_group(&self, peer_key: &K) -> Option<&str> {
+        self.members
+            .get(peer_key)
+            .map(|member| member.group.as_str())
+    }
+
+    /// Create a peer with the configuration of the group, the values set in
+    /// `overrides` replace the ones of the group
+    #[allow(clippy::type_complexity)]
+    pub fn create_group_peer<
+        D: BgpCodecInitializer<Peer<K, A, I, D, C, EchoCapabilitiesPolicy<A, I, D>>>
+            + Decoder<Item = (BgpMessage, BgpParsingIgnoredErrors), Error = BgpCodecDecoderError>
+            + Encoder<BgpMessage, Error = BgpMessageWritingError>
+            + Send
+            + Sync
+            + 'static,
+        C: ActiveConnect<A, I, D> + Send + Sync + 'static,
+    >(
+        &mut self,
+        peer_key: K,
+        peer_properties: PeerProperties<A>,
+        group: &str,
+        overrides: PeerOverrides,
+        active_connect: C,
+    ) -> Result<(UnboundedReceiver<PeerStateResult<A>>, PeerHandle<A, I>), PeersSupervisorError>
+    {
+        let Some(peer_group) = self.groups.get(group) else {
+            return Err(PeersSupervisorError::GroupNotFound);
+        };
+        let config = overrides.config.unwrap_or(peer_group.config);
+        let policy =
+            peer_group.capabilities_policy(peer_properties.my_asn(), self.my_bgp_id, &config);
+        let (rx, peer_handle) =
+            self.create_peer(peer_key, peer_properties, config, active_connect, policy)?;
+        self.members.insert(
+            peer_key,
+            PeerGroupMember {
+                group: group.to_string(),
+                overrides,
+            },
+        );
+        self.apply_route_policies(&peer_key);
+        Ok((rx, peer_handle))
+    }
+
+    /// Create a peer with the configuration of the group for a connection
+    /// accepted from an unconfigured peer. The peer waits passively for
+    /// connections and is accepted only with an ASN in `allowed_asns`, or with
+    /// any ASN when empty.
+    #[allow(clippy::type_complexity)]
+    pub fn dynamic_group_peer<
+        D: BgpCodecInitializer<Peer<K, A, I, D, C, EchoCapabilitiesPolicy<A, I, D>>>
+            + Decoder<Item = (BgpMessage, BgpParsingIgnoredErrors), Error = BgpCodecDecoderError>
+            + Encoder<BgpMessage, Error = BgpMessageWritingError>
+            + Send
+            + Sync
+            + 'static,
+        C: ActiveConnect<A, I, D> + Send + Sync + 'static,
+    >(
+        &mut self,
+        peer_key: K,
+        peer_addr: A,
+        group: &str,
+        allowed_asns: Vec<RangeInclusive<u32>>,
+        active_connect: C,
+    ) -> Result<(UnboundedReceiver<PeerStateResult<A>>, PeerHandle<A, I>), PeersSupervisorError>
+    {
+        let Some(peer_group) = self.groups.get(group) else {
+            return Err(PeersSupervisorError::GroupNotFound);
+        };
+        let peer_properties = self.peer_properties(self.my_asn, peer_addr, true);
+        let config = PeerConfigBuilder::from(peer_group.config)
+            .passive_tcp_establishment(true)
+            .build();
+        let mut policy = peer_group.capabilities_policy(self.my_asn, self.my_bgp_id, &config);
+        policy.set_allowed_peer_asns(allowed_asns);
+        let (rx, peer_handle) =
+            self.create_peer(peer_key, peer_properties, config, active_connect, policy)?;
+        self.members.insert(
+            peer_key,
+            PeerGroupMember {
+                group: group.to_string(),
+                overrides: PeerOverrides::default(),
+            },
+        );
+        self.apply_route_policies(&peer_key);
+        Ok((rx, peer_handle))
+    }
+
+    /// Move a running peer to another group, or out of its group with `None`.
+    /// The route policies of the new group are applied right away unless
+    /// overridden, the timers and capabilities of the peer are not changed.
+    pub fn set_peer_group(
+        &mut self,
+        peer_key: &K,
+        group: Option<&str>,
+    ) -> Result<(), PeersSupervisorError> {
+        if !self.peers.contains_key(peer_key) {
+            return Err(PeersSupervisorError::PeerNotFound);
+        }
+        match group {
+            Some(group) => {
+                if !self.groups.contains_key(group) {
+                    return Err(PeersSupervisorError::GroupNotFound);
+                }
+                let overrides = self
+                    .members
+                    .remove(peer_key)
+                    .map(|member| member.overrides)
+                    .unwrap_or_default();
+                self.members.insert(
+                    *peer_key,
+                    PeerGroupMember {
+                        group: group.to_string(),
+                        overrides,
+                    },
+                );
+            }
+            None => {
+                self.members.remove(peer_key);
+            }
+        }
+        self.apply_route_policies(peer_key);
+        Ok(())
+    }
+
+    /// Apply the route policies of the group of the peer, returns `false` if
+    /// the peer is not running anymore
+    fn apply_route_policies(&self, peer_key: &K) -> bool {
+        let Some(controller) = self.peers.get(peer_key) else {
+            return false;
+        };
+        let (import_policy, export_policy) = match self.members.get(peer_key) {
+            Some(member) => {
+                let group = self.groups.get(&member.group);
+                (
+                    member
+                        .overrides
+                        .import_policy
+                        .clone()
+                        .or_else(|| group.and_then(|group| group.import_policy.clone())),
+                    member
+                        .overrides
+                        .export_policy
+                        .clone()
+                        .or_else(|| group.and_then(|group| group.export_policy.clone())),
+                )
+            }
+            None => (None, None),
+        };
+        let handle = controller.get_new_handle();
+        handle.set_import_policy(import_policy).is_ok()
+            && handle.set_export_policy(export_policy).is_ok()
+    }
+
     #[allow(clippy::type_complexity)]
     pub fn dynamic_peer<
         D: BgpCodecInitializer<Peer<K, A, I, D, C, EchoCapabilitiesPolicy<A, I, D>>>
@@ -127,8 +695,7 @@ impl<
         active_connect: C,
     ) -> Result<(UnboundedReceiver<PeerStateResult<A>>, PeerHandle<A, I>), PeersSupervisorError>
     {
-        let peer_properties =
-            PeerProperties::new(self.my_asn, self.my_asn, self.my_bgp_id, peer_addr, true);
+        let peer_properties = self.peer_properties(self.my_asn, peer_addr, true);
         let peer_config = PeerConfigBuilder::new()
             // set open_delay_Timer to max, to allow the peer to communicate it's open message first
             .open_delay_timer_duration(u16::MAX)
@@ -151,4 +718,106 @@ impl<
         )?;
         Ok((rx, peer_handle))
     }
+
+    /// Handle a request of an [`crate::admin::AdminHandle`]. A peer added by
+    /// the request is started with the `active_connect` and its handle is
+    /// returned to register it, e.g., in the [`crate::listener::BgpListener`].
+    #[allow(clippy::type_complexity)]
+    pub async fn handle_admin_request<
+        D: BgpCodecInitializer<Peer<K, A, I, D, C, EchoCapabilitiesPolicy<A, I, D>>>
+            + Decoder<Item = (BgpMessage, BgpParsingIgnoredErrors), Error = BgpCodecDecoderError>
+            + Encoder<BgpMessage, Error = BgpMessageWritingError>
+            + Send
+            + Sync
+            + 'static,
+        C: ActiveConnect<A, I, D> + Send + Sync + 'static,
+    >(
+        &mut self,
+        request: AdminRequest<K, A>,
+        active_connect: C,
+    ) -> Option<PeerHandle<A, I>> {
+        match request {
+            AdminRequest::ListPeers(reply) => {
+                let _ = reply.send(Ok(self.peer_keys()));
+            }
+            AdminRequest::AddPeer {
+                peer_key,
+                peer_addr,
+                peer_asn,
+                group,
+                overrides,
+                reply,
+            } => {
+                let peer_properties = self.peer_properties(peer_asn, peer_addr, false);
+                let (mut rx, peer_handle) = match self.create_group_peer(
+                    peer_key,
+                    peer_properties,
+                    &group,
+                    overrides,
+                    active_connect,
+                ) {
+                    Ok(peer) => peer,
+                    Err(err) => {
+                        let _ = reply.send(Err(err));
+                        return None;
+                    }
+                };
+                if peer_handle.start().is_err() {
+                    self.remove_peer(&peer_key);
+                    let _ = reply.send(Err(PeersSupervisorError::PeerNotRunning));
+                    return None;
+                }
+                tokio::spawn(async move {
+                    while let Some(event) = rx.recv().await {
+                        log::info!("[{peer_key}] Peer got event: {event:?}");
+                    }
+                });
+                let _ = reply.send(Ok(()));
+                return Some(peer_handle);
+            }
+            AdminRequest::RemovePeer(peer_key, reply) => {
+                let removed = self
+                    .remove_peer(&peer_key)
+                    .map(|_| ())
+                    .ok_or(PeersSupervisorError::PeerNotFound);
+                let _ = reply.send(removed);
+            }
+            AdminRequest::StartPeer(peer_key, reply) => {
+                let _ = reply.send(self.start_peer(&peer_key));
+            }
+            AdminRequest::ShutdownPeer(peer_key, communication, reply) => {
+                let _ = reply.send(self.shutdown_peer(&peer_key, communication.as_deref()));
+            }
+            AdminRequest::GetPeerMetrics(peer_key, reply) => {
+                let metrics = match self.running_peer(&peer_key) {
+                    Ok(mut handle) => handle
+                        .peer_metrics()
+                        .await
+                        .map_err(|_| PeersSupervisorError::PeerNotRunning),
+                    Err(err) => Err(err),
+                };
+                let _ = reply.send(metrics);
+            }
+            AdminRequest::GetAdjRibIn(peer_key, reply) => {
+                let adj_rib_in = match self.running_peer(&peer_key) {
+                    Ok(mut handle) => handle
+                        .adj_rib_in()
+                        .await
+                        .map_err(|_| PeersSupervisorError::PeerNotRunning),
+                    Err(err) => Err(err),
+                };
+                let _ = reply.send(adj_rib_in);
+            }
+            AdminRequest::SetImportPolicy(peer_key, policy, reply) => {
+                let _ = reply.send(self.set_import_policy(&peer_key, policy));
+            }
+            AdminRequest::SetExportPolicy(peer_key, policy, reply) => {
+                let _ = reply.send(self.set_export_policy(&peer_key, policy));
+            }
+            AdminRequest::UpdateGroup(name, group, reply) => {
+                let _ = reply.send(self.update_group(&name, group));
+            }
+        }
+        None
+    }
 }
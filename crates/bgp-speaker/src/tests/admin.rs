@@ -0,0 +1,149 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    admin::{admin_channel, AdminError, AdminRequest},
+    connection::TcpActiveConnect,
+    peer::PeerConfigBuilder,
+    peer_controller::PeerHandle,
+    route_policy::{Policy, PolicyResult},
+    supervisor::{PeerGroup, PeerOverrides, PeersSupervisor, PeersSupervisorError},
+    tests::{MY_AS, MY_BGP_ID, PEER_ADDR, PEER_AS, PEER_KEY},
+};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio::{net::TcpStream, sync::mpsc::UnboundedReceiver};
+
+/// Handle the next admin request
+async fn handle_request(
+    supervisor: &mut PeersSupervisor<IpAddr, SocketAddr, TcpStream>,
+    rx: &mut UnboundedReceiver<AdminRequest<IpAddr, SocketAddr>>,
+) -> Option<PeerHandle<SocketAddr, TcpStream>> {
+    let request = rx.recv().await.unwrap();
+    supervisor
+        .handle_admin_request(request, TcpActiveConnect)
+        .await
+}
+
+#[test_log::test(tokio::test)]
+async fn test_admin_requests() -> Result<(), PeersSupervisorError> {
+    let mut supervisor = PeersSupervisor::new(MY_AS, MY_BGP_ID);
+    let config = PeerConfigBuilder::new()
+        .passive_tcp_establishment(true)
+        .build();
+    supervisor.add_group("passive", PeerGroup::new(config))?;
+    let (admin, mut rx) = admin_channel();
+
+    let (added, peer_handle) = tokio::join!(
+        admin.add_peer(
+            PEER_KEY,
+            PEER_ADDR,
+            PEER_AS,
+            "passive",
+            PeerOverrides::new()
+        ),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(added, Ok(()));
+    assert_eq!(
+        peer_handle.map(|handle| *handle.peer_addr()),
+        Some(PEER_ADDR)
+    );
+    assert_eq!(supervisor.peer_group(&PEER_KEY), Some("passive"));
+
+    let (added, peer_handle) = tokio::join!(
+        admin.add_peer(
+            PEER_KEY,
+            PEER_ADDR,
+            PEER_AS,
+            "passive",
+            PeerOverrides::new()
+        ),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(
+        added,
+        Err(AdminError::Supervisor(PeersSupervisorError::PeerExists))
+    );
+    assert!(peer_handle.is_none());
+
+    let (peers, _) = tokio::join!(admin.peers(), handle_request(&mut supervisor, &mut rx));
+    assert_eq!(peers, Ok(vec![PEER_KEY]));
+
+    let (metrics, _) = tokio::join!(
+        admin.peer_metrics(PEER_KEY),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert!(metrics.is_ok());
+
+    let (adj_rib_in, _) = tokio::join!(
+        admin.adj_rib_in(PEER_KEY),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(adj_rib_in.map(|adj_rib_in| adj_rib_in.len()), Ok(0));
+
+    let too_long = "x".repeat(256);
+    let (shutdown, _) = tokio::join!(
+        admin.shutdown_peer(PEER_KEY, Some(&too_long)),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(
+        shutdown,
+        Err(AdminError::Supervisor(
+            PeersSupervisorError::InvalidShutdownCommunication
+        ))
+    );
+    let (shutdown, _) = tokio::join!(
+        admin.shutdown_peer(PEER_KEY, Some("maintenance")),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(shutdown, Ok(()));
+    let (started, _) = tokio::join!(
+        admin.start_peer(PEER_KEY),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(started, Ok(()));
+
+    let (import_policy, _) = tokio::join!(
+        admin.set_import_policy(PEER_KEY, Some(Arc::new(Policy::new(PolicyResult::Reject)))),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(import_policy, Ok(()));
+    let (updated, _) = tokio::join!(
+        admin.update_group("passive", PeerGroup::new(config)),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(updated, Ok(vec![]));
+
+    let (removed, _) = tokio::join!(
+        admin.remove_peer(PEER_KEY),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(removed, Ok(()));
+    let (export_policy, _) = tokio::join!(
+        admin.set_export_policy(PEER_KEY, None),
+        handle_request(&mut supervisor, &mut rx)
+    );
+    assert_eq!(
+        export_policy,
+        Err(AdminError::Supervisor(PeersSupervisorError::PeerNotFound))
+    );
+
+    drop(rx);
+    assert_eq!(admin.peers().await, Err(AdminError::Closed));
+    Ok(())
+}
@@ -0,0 +1,297 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use futures::StreamExt;
+use netgauze_bgp_pkt::{
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    notification::{BgpNotificationMessage, CeaseError},
+    open::BgpOpenMessage,
+    path_attribute::{
+        As2PathSegment, AsPath, AsPathSegmentType, NextHop, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+};
+use netgauze_bmp_pkt::{
+    codec::BmpCodec, BmpMessage, BmpMessageValue, CounterU32, GaugeU64, PeerDownNotificationReason,
+    StatisticsCounter,
+};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_util::codec::Framed;
+
+use crate::{
+    bmp::{bmp_channel, BmpExporter, BmpStationConfig},
+    events::{BgpEvent, UpdateTreatment},
+    fsm::{FsmState, FsmStateError},
+    peer::*,
+    tests::*,
+};
+
+type Station = Framed<TcpStream, BmpCodec>;
+
+/// Accept the connection of the client and check its Initiation message
+async fn accept_station(listener: &TcpListener) -> Station {
+    let (stream, _) = listener.accept().await.unwrap();
+    let mut station = Framed::new(stream, BmpCodec::default());
+    match next_message(&mut station).await {
+        BmpMessageValue::Initiation(initiation) => {
+            assert_eq!(initiation.information().len(), 2);
+        }
+        value => panic!("Unexpected BMP message: {value:?}"),
+    }
+    station
+}
+
+async fn next_message(station: &mut Station) -> BmpMessageValue {
+    let BmpMessage::V3(value) = station.next().await.unwrap().unwrap();
+    value
+}
+
+async fn wait_session(exporter: &BmpExporter, session: u64) {
+    while exporter.session() != Some(session) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+/// Check the message is the pre or post policy End-of-RIB of IPv4 unicast
+fn assert_end_of_rib(value: BmpMessageValue, post_policy: bool) {
+    let BmpMessageValue::RouteMonitoring(route_monitoring) = value else {
+        panic!("Unexpected BMP message: {value:?}");
+    };
+    assert_eq!(route_monitoring.peer_header().is_post_policy(), post_policy);
+    assert_eq!(
+        route_monitoring.update_message(),
+        &BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![], vec![]))
+    );
+}
+
+fn peer_update() -> BgpUpdateMessage {
+    let path_attributes = vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![PEER_AS as u16],
+            )])),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 168, 0, 2))),
+        )
+        .unwrap(),
+    ];
+    let nlri = Ipv4UnicastAddress::new_no_path_id(
+        Ipv4Unicast::from_net("10.0.0.0/24".parse().unwrap()).unwrap(),
+    );
+    BgpUpdateMessage::new(vec![], path_attributes, vec![nlri])
+}
+
+#[test_log::test(tokio::test)]
+async fn test_bmp_peer_lifecycle() -> Result<(), FsmStateError<SocketAddr>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = BmpStationConfig::new("speaker", "NetGauze BGP speaker")
+        .with_statistics_interval(Duration::ZERO);
+    let (exporter, client) = bmp_channel(config);
+    tokio::spawn(client.run(listener.local_addr().unwrap()));
+    let mut station = accept_station(&listener).await;
+    wait_session(&exporter, 1).await;
+
+    let my_open = BgpOpenMessage::new(MY_AS as u16, HOLD_TIME, MY_BGP_ID, vec![]);
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let update = peer_update();
+    let notif =
+        BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown { value: vec![] });
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(my_open.clone()))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(update.clone()))
+        .read(BgpMessage::Notification(notif.clone()));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let mut peer = Peer::new(
+        PEER_KEY,
+        PROPERTIES,
+        PeerConfig::default(),
+        POLICY,
+        active_connect,
+    );
+    peer.set_bmp_exporter(Some(exporter.peer_exporter()));
+    peer.add_admin_event(PeerAdminEvents::ManualStart);
+    assert_eq!(peer.run().await?, BgpEvent::ManualStart);
+    assert_eq!(
+        peer.run().await?,
+        BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+    );
+    assert_eq!(peer.run().await?, BgpEvent::BGPOpen(peer_open.clone()));
+    assert_eq!(peer.run().await?, BgpEvent::KeepAliveMsg);
+    assert_eq!(peer.fsm_state(), FsmState::Established);
+
+    let BmpMessageValue::PeerUpNotification(peer_up) = next_message(&mut station).await else {
+        panic!("Expecting a Peer Up message");
+    };
+    assert_eq!(peer_up.peer_header().address(), Some(PEER_ADDR.ip()));
+    assert_eq!(peer_up.peer_header().peer_as(), PEER_AS);
+    assert_eq!(peer_up.peer_header().bgp_id(), PEER_BGP_ID);
+    assert!(!peer_up.peer_header().is_post_policy());
+    assert_eq!(peer_up.remote_port(), Some(PEER_ADDR.port()));
+    assert_eq!(peer_up.sent_message(), &BgpMessage::Open(my_open));
+    assert_eq!(peer_up.received_message(), &BgpMessage::Open(peer_open));
+    // The Adj-RIB-In is empty
+    assert_end_of_rib(next_message(&mut station).await, false);
+    assert_end_of_rib(next_message(&mut station).await, true);
+
+    assert_eq!(
+        peer.run().await?,
+        BgpEvent::UpdateMsg(update.clone(), UpdateTreatment::Normal)
+    );
+    for post_policy in [false, true] {
+        let BmpMessageValue::RouteMonitoring(route_monitoring) = next_message(&mut station).await
+        else {
+            panic!("Expecting a Route Monitoring message");
+        };
+        assert_eq!(route_monitoring.peer_header().is_post_policy(), post_policy);
+        assert_eq!(
+            route_monitoring.update_message(),
+            &BgpMessage::Update(update.clone())
+        );
+    }
+
+    assert_eq!(peer.run().await?, BgpEvent::NotifMsg(notif.clone()));
+    assert_eq!(peer.fsm_state(), FsmState::Idle);
+    let BmpMessageValue::PeerDownNotification(peer_down) = next_message(&mut station).await else {
+        panic!("Expecting a Peer Down message");
+    };
+    assert_eq!(
+        peer_down.reason(),
+        &PeerDownNotificationReason::RemoteSystemClosedNotificationPduFollows(
+            BgpMessage::Notification(notif)
+        )
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_bmp_station_reconnect() -> Result<(), FsmStateError<SocketAddr>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let config = BmpStationConfig::new("speaker", "NetGauze BGP speaker")
+        .with_reconnect_duration(Duration::from_millis(10))
+        .with_statistics_interval(Duration::from_millis(100));
+    let (exporter, client) = bmp_channel(config);
+    tokio::spawn(client.run(listener.local_addr().unwrap()));
+    let station = accept_station(&listener).await;
+    wait_session(&exporter, 1).await;
+
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .wait(Duration::from_secs(10));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let mut peer = Peer::new(
+        PEER_KEY,
+        PROPERTIES,
+        PeerConfig::default(),
+        POLICY,
+        active_connect,
+    );
+    peer.add_admin_event(PeerAdminEvents::ManualStart);
+    assert_eq!(peer.run().await?, BgpEvent::ManualStart);
+    assert_eq!(
+        peer.run().await?,
+        BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+    );
+    assert_eq!(peer.run().await?, BgpEvent::BGPOpen(peer_open));
+    assert_eq!(peer.run().await?, BgpEvent::KeepAliveMsg);
+    assert_eq!(peer.fsm_state(), FsmState::Established);
+
+    // The station closes the connection, the peer is reported again once the
+    // client is connected back
+    drop(station);
+    let mut station = accept_station(&listener).await;
+    wait_session(&exporter, 2).await;
+    peer.set_bmp_exporter(Some(exporter.peer_exporter()));
+    assert!(matches!(
+        next_message(&mut station).await,
+        BmpMessageValue::PeerUpNotification(_)
+    ));
+    assert_end_of_rib(next_message(&mut station).await, false);
+    assert_end_of_rib(next_message(&mut station).await, true);
+
+    drop(station);
+    let mut station = accept_station(&listener).await;
+    wait_session(&exporter, 3).await;
+    while peer.run().await? != BgpEvent::BmpStationChanged {}
+    assert!(matches!(
+        next_message(&mut station).await,
+        BmpMessageValue::PeerUpNotification(_)
+    ));
+    assert_end_of_rib(next_message(&mut station).await, false);
+    assert_end_of_rib(next_message(&mut station).await, true);
+
+    while peer.run().await? != BgpEvent::BmpStatisticsTimerExpires {}
+    let BmpMessageValue::StatisticsReport(report) = next_message(&mut station).await else {
+        panic!("Expecting a Statistics Report message");
+    };
+    assert_eq!(
+        report.counters(),
+        &vec![
+            StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(CounterU32::new(0)),
+            StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(0)),
+        ]
+    );
+
+    peer.set_bmp_exporter(None);
+    let BmpMessageValue::PeerDownNotification(peer_down) = next_message(&mut station).await else {
+        panic!("Expecting a Peer Down message");
+    };
+    assert_eq!(
+        peer_down.reason(),
+        &PeerDownNotificationReason::PeerDeConfigured
+    );
+    Ok(())
+}
@@ -0,0 +1,53 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::listener::{BgpListener, DynamicPeerRange};
+use std::net::{IpAddr, SocketAddr};
+use tokio::net::TcpStream;
+
+#[test]
+fn test_dynamic_peer_ranges() {
+    let mut listener: BgpListener<SocketAddr, TcpStream> =
+        BgpListener::new(vec!["[::]:179".parse().unwrap()], false);
+    let clients = DynamicPeerRange::new(
+        vec![
+            "192.0.2.0/24".parse().unwrap(),
+            "2001:db8::/32".parse().unwrap(),
+        ],
+        "clients",
+    )
+    .with_asns(64512..=65534);
+    let ixp = DynamicPeerRange::new(vec!["192.0.0.0/16".parse().unwrap()], "ixp");
+    listener.reg_dynamic_peer_range(clients.clone());
+    listener.reg_dynamic_peer_range(ixp.clone());
+
+    let ip = |value: &str| value.parse::<IpAddr>().unwrap();
+    assert_eq!(
+        listener.dynamic_peer_ranges(),
+        &vec![clients.clone(), ixp.clone()]
+    );
+    assert_eq!(
+        listener.dynamic_peer_range(&ip("192.0.2.10")),
+        Some(&clients)
+    );
+    assert_eq!(
+        listener.dynamic_peer_range(&ip("2001:db8::1")),
+        Some(&clients)
+    );
+    assert_eq!(listener.dynamic_peer_range(&ip("192.0.3.10")), Some(&ixp));
+    assert_eq!(listener.dynamic_peer_range(&ip("198.51.100.1")), None);
+    assert_eq!(clients.asns(), &vec![64512..=65534]);
+    assert_eq!(ixp.group(), "ixp");
+}
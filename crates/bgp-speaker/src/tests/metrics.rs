@@ -0,0 +1,65 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    connection::ConnectionStats,
+    fsm::FsmState,
+    metrics::{prometheus::PrometheusMetrics, MetricsRecorder, PeerMetrics},
+    peer::PeerStats,
+};
+use prometheus::{Encoder, Registry, TextEncoder};
+
+fn encode(registry: &Registry) -> String {
+    let mut buf = vec![];
+    TextEncoder::new()
+        .encode(&registry.gather(), &mut buf)
+        .unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn test_prometheus_metrics() {
+    let metrics = PrometheusMetrics::new().unwrap();
+    let registry = Registry::new();
+    registry.register(Box::new(metrics.clone())).unwrap();
+
+    let peer_metrics = PeerMetrics::new(
+        FsmState::Established,
+        PeerStats::default(),
+        Some(ConnectionStats::default()),
+        10,
+        2,
+    );
+    metrics.record("192.0.2.1", &peer_metrics);
+    let output = encode(&registry);
+    assert!(output.contains(r#"bgp_peer_fsm_state{peer="192.0.2.1",state="Established"} 1"#));
+    assert!(output.contains(r#"bgp_peer_prefixes_received{peer="192.0.2.1"} 10"#));
+    assert!(output.contains(r#"bgp_peer_prefixes_stale{peer="192.0.2.1"} 2"#));
+    assert!(output.contains(r#"bgp_peer_flaps_total{peer="192.0.2.1"} 0"#));
+    assert!(
+        output.contains(r#"bgp_peer_messages_received_total{peer="192.0.2.1",type="update"} 0"#)
+    );
+
+    // Only the current state is reported
+    let peer_metrics = PeerMetrics::new(FsmState::Idle, PeerStats::default(), None, 0, 0);
+    metrics.record("192.0.2.1", &peer_metrics);
+    let output = encode(&registry);
+    assert!(output.contains(r#"bgp_peer_fsm_state{peer="192.0.2.1",state="Idle"} 1"#));
+    assert!(!output.contains(r#"state="Established""#));
+    assert!(!output.contains("bgp_peer_messages_received_total"));
+
+    metrics.remove("192.0.2.1");
+    assert!(!encode(&registry).contains("192.0.2.1"));
+}
@@ -13,7 +13,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::peer::{EchoCapabilitiesPolicy, PeerProperties};
+use crate::peer::{EchoCapabilitiesPolicy, PeerConfig, PeerProperties};
 use std::net::{IpAddr, Ipv4Addr};
 
 use async_trait::async_trait;
@@ -23,10 +23,20 @@ use crate::connection::ActiveConnect;
 use netgauze_bgp_pkt::{codec::BgpCodec, BgpMessage};
 use netgauze_parse_utils::WritablePdu;
 
+mod admin;
+mod bmp;
 mod connection;
+mod listener;
+#[cfg(feature = "prometheus")]
+mod metrics;
 mod peer;
 mod peer_controller;
+mod rib;
+mod route_policy;
+mod socket;
 mod supervisor;
+mod update;
+mod update_group;
 
 pub(crate) const MY_AS: u32 = 100;
 pub(crate) const PEER_AS: u32 = 200;
@@ -109,7 +119,11 @@ pub struct MockActiveConnect {
 
 #[async_trait]
 impl ActiveConnect<SocketAddr, tokio_test::io::Mock, BgpCodec> for MockActiveConnect {
-    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<tokio_test::io::Mock> {
+    async fn connect(
+        &mut self,
+        peer_addr: SocketAddr,
+        _config: &PeerConfig,
+    ) -> io::Result<tokio_test::io::Mock> {
         assert_eq!(self.peer_addr, peer_addr);
         if !self.connect_delay.is_zero() {
             tokio::time::sleep(self.connect_delay).await;
@@ -126,7 +140,11 @@ pub struct MockFailedActiveConnect {
 
 #[async_trait]
 impl ActiveConnect<SocketAddr, tokio_test::io::Mock, BgpCodec> for MockFailedActiveConnect {
-    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<tokio_test::io::Mock> {
+    async fn connect(
+        &mut self,
+        peer_addr: SocketAddr,
+        _config: &PeerConfig,
+    ) -> io::Result<tokio_test::io::Mock> {
         assert_eq!(self.peer_addr, peer_addr);
         if !self.connect_delay.is_zero() {
             tokio::time::sleep(self.connect_delay).await;
@@ -737,6 +737,43 @@ async fn test_connect_automatic_stop() {
     assert!(peer.tracked_connection().is_none());
 }
 
+#[test_log::test(tokio::test)]
+async fn test_connect_manual_stop_with_communication() {
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder.write(BgpMessage::Notification(
+        BgpNotificationMessage::CeaseError(
+            CeaseError::administrative_shutdown("maintenance").unwrap(),
+        ),
+    ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new()
+        .open_delay_timer_duration(1)
+        .send_notif_without_open(true)
+        .build();
+    let mut peer = Peer::new(PEER_KEY, PROPERTIES, config, POLICY, active_connect);
+    peer.add_admin_event(PeerAdminEvents::ManualStart);
+    let event = peer.run().await;
+    assert_eq!(event, Ok(BgpEvent::ManualStart));
+
+    let event = peer.run().await;
+    assert_eq!(event, Ok(BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)));
+    assert_eq!(peer.fsm_state(), FsmState::Connect);
+
+    peer.add_admin_event(PeerAdminEvents::ManualStopWithCommunication(
+        "maintenance".to_string(),
+    ));
+    let event = peer.run().await;
+
+    assert_eq!(event, Ok(BgpEvent::ManualStop));
+    assert_eq!(peer.fsm_state(), FsmState::Idle);
+    assert_eq!(peer.peer_state(), PeerState::AdminDown);
+    assert!(peer.connection().is_none());
+}
+
 #[ignore]
 #[test_log::test(tokio::test)]
 async fn test_connect_hold_timer_expires() {
@@ -4180,6 +4217,86 @@ async fn test_established_notif_msg() -> Result<(), FsmStateError<SocketAddr>> {
     assert_eq!(peer.stats().connect_retry_counter(), 1);
     assert!(peer.connection().is_none());
     assert!(peer.tracked_connection().is_none());
+    assert!(peer.idle_hold_timer().is_none());
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_established_notif_msg_damp_peer_oscillations() -> Result<(), FsmStateError<SocketAddr>>
+{
+    let hold_time = 3;
+    let policy =
+        EchoCapabilitiesPolicy::new(MY_AS, false, MY_BGP_ID, hold_time, Vec::new(), Vec::new());
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, hold_time, PEER_BGP_ID, vec![]);
+    let notif =
+        BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown { value: vec![] });
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            hold_time,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Notification(notif.clone()))
+        .wait(Duration::from_secs(3));
+
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new()
+        .damp_peer_oscillations(true)
+        .idle_hold_duration(1)
+        .max_idle_hold_duration(2)
+        .build();
+    let mut peer = Peer::new(PEER_KEY, PROPERTIES, config, policy, active_connect);
+    peer.add_admin_event(PeerAdminEvents::ManualStart);
+    let event = peer.run().await?;
+    assert_eq!(event, BgpEvent::ManualStart);
+    assert_eq!(peer.fsm_state(), FsmState::Connect);
+    assert_eq!(peer.idle_hold_time(), Duration::from_secs(1));
+
+    for idle_hold_time in [2, 2] {
+        if peer.fsm_state() == FsmState::Idle {
+            // Automatic starts are delayed until the IdleHoldTimer expires
+            peer.add_admin_event(PeerAdminEvents::AutomaticStart);
+            let event = peer.run().await?;
+            assert_eq!(event, BgpEvent::IdleHoldTimerExpires);
+            assert_eq!(peer.fsm_state(), FsmState::Idle);
+            assert!(peer.idle_hold_timer().is_none());
+            let event = peer.run().await?;
+            assert_eq!(event, BgpEvent::AutomaticStartWithDampPeerOscillations);
+            assert_eq!(peer.fsm_state(), FsmState::Connect);
+        }
+        let event = peer.run().await?;
+        assert_eq!(event, BgpEvent::TcpConnectionRequestAcked(PEER_ADDR));
+        let event = peer.run().await?;
+        assert_eq!(event, BgpEvent::BGPOpen(peer_open.clone()));
+        let event = peer.run().await?;
+        assert_eq!(event, BgpEvent::KeepAliveMsg);
+        assert_eq!(peer.fsm_state(), FsmState::Established);
+
+        let event = peer.run().await?;
+        assert_eq!(event, BgpEvent::NotifMsg(notif.clone()));
+        assert_eq!(peer.fsm_state(), FsmState::Idle);
+        assert!(peer.idle_hold_timer().is_some());
+        // The IdleHoldTime doubles on every flap up to the configured max
+        assert_eq!(peer.idle_hold_time(), Duration::from_secs(idle_hold_time));
+    }
+    assert_eq!(peer.stats().flaps(), 2);
+
+    // Stopping the peer cancels the IdleHoldTimer and resets the IdleHoldTime
+    peer.add_admin_event(PeerAdminEvents::ManualStop);
+    let event = peer.run().await?;
+    assert_eq!(event, BgpEvent::ManualStop);
+    assert_eq!(peer.peer_state(), PeerState::AdminDown);
+    assert!(peer.idle_hold_timer().is_none());
+    assert_eq!(peer.idle_hold_time(), Duration::from_secs(1));
     Ok(())
 }
 
@@ -14,24 +14,44 @@
 // limitations under the License.
 
 use crate::{
-    events::BgpEvent,
+    events::{BgpEvent, UpdateTreatment},
     fsm::FsmState,
+    metrics::{MetricsRecorder, NotificationError, PeerMetrics},
     peer::*,
     peer_controller::PeerController,
+    rib::{RouteChange, RouteKey},
+    route_policy::{
+        Policy, PolicyAction, PolicyMatch, PolicyResult, PolicyRoute, PolicyTerm, PrefixListEntry,
+        RoutePolicy,
+    },
     tests::{
         BgpIoMockBuilder, MockActiveConnect, HOLD_TIME, MY_AS, MY_BGP_ID, PEER_ADDR, PEER_AS,
         PEER_BGP_ID, PEER_KEY, POLICY, PROPERTIES,
     },
+    update::RouteAttributes,
 };
 use netgauze_bgp_pkt::{
-    capabilities::{BgpCapability, FourOctetAsCapability, MultiProtocolExtensionsCapability},
+    capabilities::{
+        AddPathAddressFamily, AddPathCapability, BgpCapability, FourOctetAsCapability,
+        GracefulRestartAddressFamily, GracefulRestartCapability, MultiProtocolExtensionsCapability,
+    },
     iana::AS_TRANS,
-    notification::{BgpNotificationMessage, CeaseError},
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    notification::{BgpNotificationMessage, CeaseError, OpenMessageError},
     open::{BgpOpenMessage, BgpOpenMessageParameter},
+    path_attribute::{
+        As2PathSegment, AsPath, AsPathSegmentType, NextHop, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
     BgpMessage,
 };
 use netgauze_iana::address_family::AddressType;
-use std::{net::SocketAddr, time::Duration};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use tokio::sync::mpsc;
 
 #[test_log::test(tokio::test)]
@@ -396,3 +416,1100 @@ async fn test_get_exchanged_capabilities_tracked_connection(
     );
     Ok(())
 }
+
+#[test_log::test(tokio::test)]
+async fn test_announce_withdraw(
+) -> Result<(), mpsc::error::SendError<PeerEvent<SocketAddr, tokio_test::io::Mock>>> {
+    let next_hop = Ipv4Addr::new(192, 0, 2, 1);
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(next_hop));
+    let nlri = |net: &str| {
+        Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(net.parse().unwrap()).unwrap())
+    };
+    let announce = |net: &str| {
+        BgpMessage::Update(BgpUpdateMessage::new(
+            vec![],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        vec![MY_AS as u16],
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(next_hop)),
+                )
+                .unwrap(),
+            ],
+            vec![nlri(net)],
+        ))
+    };
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        // Routes originated before the session is established
+        .write(announce("198.51.100.0/24"))
+        .write(announce("203.0.113.0/24"))
+        .write(BgpMessage::Update(BgpUpdateMessage::new(
+            vec![nlri("198.51.100.0/24")],
+            vec![],
+            vec![],
+        )))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let handle = controller.get_new_handle();
+
+    handle.announce(vec!["198.51.100.1/24".parse().unwrap()], attributes.clone())?;
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+
+    handle.announce(vec!["203.0.113.0/24".parse().unwrap()], attributes)?;
+    handle.withdraw(vec![
+        "198.51.100.0/24".parse().unwrap(),
+        // Not originated, hence ignored
+        "192.0.2.0/24".parse().unwrap(),
+    ])?;
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_adj_rib_in() -> Result<(), Box<dyn std::error::Error>> {
+    let update = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![PEER_AS as u16],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+            )
+            .unwrap(),
+        ],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )],
+    );
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(update.clone()))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let mut handle = controller.get_new_handle();
+
+    let mut route_changes = handle.subscribe_route_changes()?;
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::Established,
+            BgpEvent::UpdateMsg(update, UpdateTreatment::Normal)
+        )))
+    );
+
+    let key = RouteKey::new(
+        AddressType::Ipv4Unicast,
+        "198.51.100.0/24".parse().unwrap(),
+        None,
+    );
+    let adj_rib_in = handle.adj_rib_in().await?;
+    assert_eq!(adj_rib_in.len(), 1);
+    let route = adj_rib_in.get(&key).unwrap().clone();
+    assert_eq!(
+        route.next_hop(),
+        Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)))
+    );
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Announced(route.clone())])
+    );
+
+    // The received routes are withdrawn when the session goes down
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Withdrawn(route)])
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_route_policies() -> Result<(), Box<dyn std::error::Error>> {
+    let next_hop = Ipv4Addr::new(192, 0, 2, 1);
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(next_hop));
+    let nlri = |net: &str| {
+        Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(net.parse().unwrap()).unwrap())
+    };
+    let update = |asn: u16, next_hop: Ipv4Addr, nets: &[&str]| {
+        BgpUpdateMessage::new(
+            vec![],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                        AsPathSegmentType::AsSequence,
+                        vec![asn],
+                    )])),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(next_hop)),
+                )
+                .unwrap(),
+            ],
+            nets.iter().map(|net| nlri(net)).collect(),
+        )
+    };
+    let received = update(
+        PEER_AS as u16,
+        Ipv4Addr::new(192, 0, 2, 2),
+        &["192.0.2.0/24"],
+    );
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        // 203.0.113.0/24 is rejected by the export policy
+        .write(BgpMessage::Update(update(
+            MY_AS as u16,
+            next_hop,
+            &["198.51.100.0/24"],
+        )))
+        .read(BgpMessage::Update(received.clone()))
+        // All the routes are sent once the export policy is removed
+        .write(BgpMessage::Update(update(
+            MY_AS as u16,
+            next_hop,
+            &["198.51.100.0/24", "203.0.113.0/24"],
+        )))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let mut handle = controller.get_new_handle();
+
+    let import_policy = Policy::new(PolicyResult::Accept)
+        .with_term(PolicyTerm::new().with_action(PolicyAction::SetMed(10)));
+    let export_policy = Policy::new(PolicyResult::Accept).with_term(
+        PolicyTerm::new()
+            .with_match(PolicyMatch::PrefixList(vec![PrefixListEntry::new(
+                "203.0.113.0/24".parse().unwrap(),
+            )]))
+            .with_result(PolicyResult::Reject),
+    );
+    handle.set_import_policy(Some(Arc::new(import_policy)))?;
+    handle.set_export_policy(Some(Arc::new(export_policy)))?;
+    handle.announce(
+        vec![
+            "198.51.100.0/24".parse().unwrap(),
+            "203.0.113.0/24".parse().unwrap(),
+        ],
+        attributes,
+    )?;
+    let mut route_changes = handle.subscribe_route_changes()?;
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::Established,
+            BgpEvent::UpdateMsg(received, UpdateTreatment::Normal)
+        )))
+    );
+
+    // The Adj-RIB-In keeps the route as received
+    let key = RouteKey::new(
+        AddressType::Ipv4Unicast,
+        "192.0.2.0/24".parse().unwrap(),
+        None,
+    );
+    let route = handle.adj_rib_in().await?.get(&key).unwrap().clone();
+    assert_eq!(route.med(), None);
+    let mut imported = route.clone();
+    imported.set_med(Some(10));
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Announced(imported)])
+    );
+
+    // The route is evaluated again with the new import policy
+    let reject_all: Arc<dyn RoutePolicy> = Arc::new(|_: &mut dyn PolicyRoute| PolicyResult::Reject);
+    handle.set_import_policy(Some(reject_all))?;
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Withdrawn(route)])
+    );
+
+    handle.set_export_policy(None)?;
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_confederation_loop_detection() -> Result<(), Box<dyn std::error::Error>> {
+    const CONFEDERATION_ID: u16 = 65000;
+    let update = |segments: Vec<As2PathSegment>, net: &str| {
+        BgpUpdateMessage::new(
+            vec![],
+            vec![
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::Origin(Origin::IGP),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::AsPath(AsPath::As2PathSegments(segments)),
+                )
+                .unwrap(),
+                PathAttribute::from(
+                    false,
+                    true,
+                    false,
+                    false,
+                    PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+                )
+                .unwrap(),
+            ],
+            vec![Ipv4UnicastAddress::new_no_path_id(
+                Ipv4Unicast::from_net(net.parse().unwrap()).unwrap(),
+            )],
+        )
+    };
+    let confed_sequence =
+        |as_numbers| As2PathSegment::new(AsPathSegmentType::AsConfedSequence, as_numbers);
+    let sequence = |as_numbers| As2PathSegment::new(AsPathSegmentType::AsSequence, as_numbers);
+    let accepted = update(
+        vec![confed_sequence(vec![PEER_AS as u16]), sequence(vec![300])],
+        "198.51.100.0/24",
+    );
+    // The member AS in a confederation segment
+    let member_loop = update(
+        vec![
+            confed_sequence(vec![PEER_AS as u16, MY_AS as u16]),
+            sequence(vec![300]),
+        ],
+        "203.0.113.0/24",
+    );
+    // The confederation identifier outside the confederation segments
+    let identifier_loop = update(
+        vec![
+            confed_sequence(vec![PEER_AS as u16]),
+            sequence(vec![300, CONFEDERATION_ID]),
+        ],
+        "192.0.2.0/24",
+    );
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(accepted.clone()))
+        .read(BgpMessage::Update(member_loop.clone()))
+        .read(BgpMessage::Update(identifier_loop.clone()))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let properties =
+        PROPERTIES.with_confederation(Confederation::new(CONFEDERATION_ID as u32, MY_AS));
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, properties, config, tx, POLICY, active_connect);
+    let mut handle = controller.get_new_handle();
+
+    let mut route_changes = handle.subscribe_route_changes()?;
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+    for update in [accepted, member_loop, identifier_loop] {
+        assert_eq!(
+            rx.recv().await,
+            Some(Ok((
+                FsmState::Established,
+                BgpEvent::UpdateMsg(update, UpdateTreatment::Normal)
+            )))
+        );
+    }
+
+    // The Adj-RIB-In keeps the routes with a loop, they are not imported
+    let adj_rib_in = handle.adj_rib_in().await?;
+    let route = |net: &str| {
+        let key = RouteKey::new(AddressType::Ipv4Unicast, net.parse().unwrap(), None);
+        adj_rib_in.get(&key).unwrap().clone()
+    };
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Announced(route("198.51.100.0/24"))])
+    );
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Withdrawn(route("203.0.113.0/24"))])
+    );
+    assert_eq!(
+        route_changes.recv().await,
+        Some(vec![RouteChange::Withdrawn(route("192.0.2.0/24"))])
+    );
+    assert_eq!(route("198.51.100.0/24").as_path_length(), 1);
+
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_graceful_restart_end_of_rib(
+) -> Result<(), mpsc::error::SendError<PeerEvent<SocketAddr, tokio_test::io::Mock>>> {
+    let graceful_restart = |time| {
+        BgpOpenMessageParameter::Capabilities(vec![BgpCapability::GracefulRestartCapability(
+            GracefulRestartCapability::new(
+                false,
+                false,
+                time,
+                vec![GracefulRestartAddressFamily::new(
+                    true,
+                    AddressType::Ipv4Unicast,
+                )],
+            ),
+        )])
+    };
+    let peer_open = BgpOpenMessage::new(
+        PEER_AS as u16,
+        HOLD_TIME,
+        PEER_BGP_ID,
+        vec![graceful_restart(90)],
+    );
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![graceful_restart(60)],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        // End-of-RIB marker once the originated routes are sent
+        .write(BgpMessage::Update(BgpUpdateMessage::new(
+            vec![],
+            vec![],
+            vec![],
+        )))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new()
+        .graceful_restart(true)
+        .graceful_restart_time(60)
+        .build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let handle = controller.get_new_handle();
+
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_add_path() -> Result<(), Box<dyn std::error::Error>> {
+    let add_path = |send, receive| {
+        BgpOpenMessageParameter::Capabilities(vec![BgpCapability::AddPath(AddPathCapability::new(
+            vec![AddPathAddressFamily::new(
+                AddressType::Ipv4Unicast,
+                send,
+                receive,
+            )],
+        ))])
+    };
+    let nlri = |path_id| {
+        Ipv4UnicastAddress::new(
+            Some(path_id),
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )
+    };
+    let update = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![PEER_AS as u16],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+            )
+            .unwrap(),
+        ],
+        vec![nlri(1), nlri(2)],
+    );
+    let peer_open = BgpOpenMessage::new(
+        PEER_AS as u16,
+        HOLD_TIME,
+        PEER_BGP_ID,
+        vec![add_path(true, false)],
+    );
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![add_path(false, true)],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(update.clone()))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown {
+                value: vec![],
+            }),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let mut policy = POLICY;
+    policy.set_add_path(vec![AddPathAddressFamily::new(
+        AddressType::Ipv4Unicast,
+        false,
+        true,
+    )]);
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, policy, active_connect);
+    let mut handle = controller.get_new_handle();
+
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::Established,
+            BgpEvent::UpdateMsg(update, UpdateTreatment::Normal)
+        )))
+    );
+
+    // Both paths of the prefix are kept
+    let adj_rib_in = handle.adj_rib_in().await?;
+    assert_eq!(adj_rib_in.len(), 2);
+    for path_id in [1, 2] {
+        let key = RouteKey::new(
+            AddressType::Ipv4Unicast,
+            "198.51.100.0/24".parse().unwrap(),
+            Some(path_id),
+        );
+        assert!(adj_rib_in.get(&key).is_some());
+    }
+
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_allowed_peer_asns(
+) -> Result<(), mpsc::error::SendError<PeerEvent<SocketAddr, tokio_test::io::Mock>>> {
+    let properties = PeerProperties::new(MY_AS, MY_AS, MY_BGP_ID, PEER_ADDR, true);
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let bad_peer_as = OpenMessageError::BadPeerAs {
+        value: PEER_AS.to_be_bytes().to_vec(),
+    };
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open))
+        .write(BgpMessage::Notification(
+            BgpNotificationMessage::OpenMessageError(bad_peer_as.clone()),
+        ));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let mut policy = POLICY;
+    policy.set_allowed_peer_asns(vec![64512..=65534]);
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, properties, config, tx, policy, active_connect);
+    let handle = controller.get_new_handle();
+
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::BGPOpenMsgErr(bad_peer_as))))
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_max_prefixes() -> Result<(), Box<dyn std::error::Error>> {
+    let nlri = |prefix: &str| {
+        Ipv4UnicastAddress::new_no_path_id(Ipv4Unicast::from_net(prefix.parse().unwrap()).unwrap())
+    };
+    let update = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![PEER_AS as u16],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+            )
+            .unwrap(),
+        ],
+        vec![nlri("198.51.100.0/24"), nlri("203.0.113.0/24")],
+    );
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    // AFI 1, SAFI 1 and the limit
+    let max_prefixes_reached =
+        BgpNotificationMessage::CeaseError(CeaseError::MaximumNumberOfPrefixesReached {
+            value: vec![0, 1, 1, 0, 0, 0, 1],
+        });
+    // The same session is established again after the restart time
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(update))
+        .write(BgpMessage::Notification(max_prefixes_reached));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new()
+        .max_prefixes(1)
+        .max_prefixes_restart_time(1)
+        .build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let mut handle = controller.get_new_handle();
+
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    for start_event in [None, Some(BgpEvent::AutomaticStart)] {
+        if let Some(start_event) = start_event {
+            assert_eq!(
+                rx.recv().await,
+                Some(Ok((
+                    FsmState::Idle,
+                    BgpEvent::MaxPrefixesRestartTimerExpires
+                )))
+            );
+            assert_eq!(rx.recv().await, Some(Ok((FsmState::Connect, start_event))));
+        }
+        assert_eq!(
+            rx.recv().await,
+            Some(Ok((
+                FsmState::OpenSent,
+                BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+            )))
+        );
+        assert_eq!(
+            rx.recv().await,
+            Some(Ok((
+                FsmState::OpenConfirm,
+                BgpEvent::BGPOpen(peer_open.clone())
+            )))
+        );
+        assert_eq!(
+            rx.recv().await,
+            Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+        );
+        assert_eq!(
+            rx.recv().await,
+            Some(Ok((FsmState::Idle, BgpEvent::AutomaticStop)))
+        );
+        assert!(handle.adj_rib_in().await?.is_empty());
+    }
+
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct TestMetricsRecorder {
+    states: Mutex<Vec<FsmState>>,
+}
+
+impl MetricsRecorder for TestMetricsRecorder {
+    fn record(&self, peer: &str, metrics: &PeerMetrics) {
+        assert_eq!(peer, PEER_KEY.to_string());
+        self.states.lock().unwrap().push(metrics.fsm_state());
+    }
+
+    fn remove(&self, _peer: &str) {}
+}
+
+#[test_log::test(tokio::test)]
+async fn test_peer_metrics() -> Result<(), Box<dyn std::error::Error>> {
+    let update = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![PEER_AS as u16],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 2))),
+            )
+            .unwrap(),
+        ],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )],
+    );
+    let peer_open = BgpOpenMessage::new(PEER_AS as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let shutdown =
+        BgpNotificationMessage::CeaseError(CeaseError::AdministrativeShutdown { value: vec![] });
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive)
+        .read(BgpMessage::Update(update.clone()))
+        .write(BgpMessage::Notification(shutdown.clone()));
+    let active_connect = MockActiveConnect {
+        peer_addr: PEER_ADDR,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let config = PeerConfigBuilder::new().build();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let controller = PeerController::new(PEER_KEY, PROPERTIES, config, tx, POLICY, active_connect);
+    let mut handle = controller.get_new_handle();
+    let recorder = Arc::new(TestMetricsRecorder::default());
+
+    handle.set_metrics_recorder(Some(recorder.clone()))?;
+    handle.start()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Connect, BgpEvent::ManualStart)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::OpenSent,
+            BgpEvent::TcpConnectionRequestAcked(PEER_ADDR)
+        )))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::OpenConfirm, BgpEvent::BGPOpen(peer_open))))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Established, BgpEvent::KeepAliveMsg)))
+    );
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((
+            FsmState::Established,
+            BgpEvent::UpdateMsg(update, UpdateTreatment::Normal)
+        )))
+    );
+
+    let metrics = handle.peer_metrics().await?;
+    assert_eq!(metrics.fsm_state(), FsmState::Established);
+    assert_eq!(metrics.stats().fsm_transitions(), 4);
+    assert_eq!(metrics.stats().established_transitions(), 1);
+    assert_eq!(metrics.stats().flaps(), 0);
+    assert_eq!(metrics.stats().last_error(), None);
+    assert_eq!(metrics.prefixes_received(), 1);
+    let connection_stats = metrics.connection_stats().unwrap();
+    assert_eq!(connection_stats.open_sent(), 1);
+    assert_eq!(connection_stats.update_received(), 1);
+    assert_eq!(connection_stats.keepalive_received(), 1);
+
+    handle.shutdown()?;
+    assert_eq!(
+        rx.recv().await,
+        Some(Ok((FsmState::Idle, BgpEvent::ManualStop)))
+    );
+    let metrics = handle.peer_metrics().await?;
+    assert_eq!(metrics.fsm_state(), FsmState::Idle);
+    assert_eq!(metrics.stats().flaps(), 1);
+    assert_eq!(
+        metrics.stats().last_error(),
+        Some(NotificationError::new(&shutdown, false))
+    );
+    assert_eq!(metrics.prefixes_received(), 0);
+    assert!(metrics.connection_stats().is_none());
+    assert_eq!(
+        recorder.states.lock().unwrap().as_slice(),
+        &[
+            FsmState::Idle,
+            FsmState::Connect,
+            FsmState::OpenSent,
+            FsmState::OpenConfirm,
+            FsmState::Established,
+            FsmState::Established,
+            FsmState::Idle,
+        ]
+    );
+    Ok(())
+}
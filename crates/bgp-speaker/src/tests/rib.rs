@@ -0,0 +1,617 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    events::UpdateTreatment,
+    rib::{
+        AdjRibIn, IgpMetric, LocRib, LocRibChange, LocRibRoute, RibPeer, Route, RouteChange,
+        RouteKey,
+    },
+};
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    iana::AS_TRANS,
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+    path_attribute::{
+        Aggregator, As2Aggregator, As2PathSegment, As4Aggregator, As4Path, As4PathSegment, AsPath,
+        AsPathSegmentType, LocalPreference, MpReach, MpUnreach, MultiExitDiscriminator, NextHop,
+        Origin, PathAttribute, PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+};
+use netgauze_iana::address_family::{AddressType, SubsequentAddressFamily};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::Arc,
+};
+
+fn prefix(value: &str) -> IpNet {
+    value.parse().unwrap()
+}
+
+fn ipv4_nlri(net: &str, path_id: Option<u32>) -> Ipv4UnicastAddress {
+    Ipv4UnicastAddress::new(
+        path_id,
+        Ipv4Unicast::from_net(net.parse().unwrap()).unwrap(),
+    )
+}
+
+fn ipv6_nlri(net: &str) -> Ipv6UnicastAddress {
+    Ipv6UnicastAddress::new(None, Ipv6Unicast::from_net(net.parse().unwrap()).unwrap())
+}
+
+fn origin() -> PathAttribute {
+    PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::Origin(Origin::IGP),
+    )
+    .unwrap()
+}
+
+fn next_hop(next_hop: Ipv4Addr) -> PathAttribute {
+    PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::NextHop(NextHop::new(next_hop)),
+    )
+    .unwrap()
+}
+
+fn med(value: u32) -> PathAttribute {
+    PathAttribute::from(
+        true,
+        false,
+        false,
+        false,
+        PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(value)),
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_adj_rib_in_ipv4() {
+    let mut rib = AdjRibIn::new();
+    let next_hop_addr = Ipv4Addr::new(192, 0, 2, 1);
+    let announce = BgpUpdateMessage::new(
+        vec![],
+        vec![origin(), next_hop(next_hop_addr)],
+        vec![
+            ipv4_nlri("198.51.100.0/24", None),
+            ipv4_nlri("203.0.113.0/24", None),
+        ],
+    );
+    let changes = rib.update(&announce, &UpdateTreatment::Normal);
+    assert_eq!(changes.len(), 2);
+    assert_eq!(rib.len(), 2);
+    let key = RouteKey::new(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"), None);
+    let route = rib.get(&key).unwrap();
+    assert_eq!(changes[0], RouteChange::Announced(route.clone()));
+    assert_eq!(route.next_hop(), Some(IpAddr::V4(next_hop_addr)));
+    assert_eq!(
+        route.path_attributes(),
+        &[origin(), next_hop(next_hop_addr)]
+    );
+
+    // Implicit withdraw of the old attributes, and an explicit withdraw
+    let replace = BgpUpdateMessage::new(
+        vec![ipv4_nlri("203.0.113.0/24", None)],
+        vec![origin(), next_hop(next_hop_addr), med(10)],
+        vec![ipv4_nlri("198.51.100.0/24", None)],
+    );
+    let withdrawn = rib
+        .get(&RouteKey::new(
+            AddressType::Ipv4Unicast,
+            prefix("203.0.113.0/24"),
+            None,
+        ))
+        .unwrap()
+        .clone();
+    let changes = rib.update(&replace, &UpdateTreatment::Normal);
+    assert_eq!(rib.len(), 1);
+    assert_eq!(
+        changes,
+        vec![
+            RouteChange::Withdrawn(withdrawn),
+            RouteChange::Announced(rib.get(&key).unwrap().clone()),
+        ]
+    );
+    assert_eq!(
+        rib.get(&key).unwrap().path_attributes(),
+        &[origin(), next_hop(next_hop_addr), med(10)]
+    );
+
+    // Routes with errors are treated as withdrawn
+    let changes = rib.update(&replace, &UpdateTreatment::TreatAsWithdraw);
+    assert_eq!(changes.len(), 1);
+    assert!(rib.is_empty());
+
+    // Withdrawing unknown routes doesn't change the RIB
+    assert_eq!(
+        rib.update(&replace, &UpdateTreatment::TreatAsWithdraw),
+        vec![]
+    );
+}
+
+#[test]
+fn test_adj_rib_in_add_path() {
+    let mut rib = AdjRibIn::new();
+    let update = BgpUpdateMessage::new(
+        vec![],
+        vec![origin(), next_hop(Ipv4Addr::new(192, 0, 2, 1))],
+        vec![
+            ipv4_nlri("198.51.100.0/24", Some(1)),
+            ipv4_nlri("198.51.100.0/24", Some(2)),
+        ],
+    );
+    rib.update(&update, &UpdateTreatment::Normal);
+    assert_eq!(rib.len(), 2);
+
+    let withdraw =
+        BgpUpdateMessage::new(vec![ipv4_nlri("198.51.100.0/24", Some(1))], vec![], vec![]);
+    rib.update(&withdraw, &UpdateTreatment::Normal);
+    assert_eq!(
+        rib.routes().map(|route| *route.key()).collect::<Vec<_>>(),
+        vec![RouteKey::new(
+            AddressType::Ipv4Unicast,
+            prefix("198.51.100.0/24"),
+            Some(2)
+        )]
+    );
+}
+
+#[test]
+fn test_adj_rib_in_mp() {
+    let mut rib = AdjRibIn::new();
+    let next_hop_global = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    let mp_reach = PathAttribute::from(
+        true,
+        false,
+        false,
+        true,
+        PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+            next_hop_global,
+            next_hop_local: None,
+            nlri: vec![ipv6_nlri("2001:db8:1::/48"), ipv6_nlri("2001:db8:2::/48")],
+        }),
+    )
+    .unwrap();
+    let announce = BgpUpdateMessage::new(vec![], vec![mp_reach, origin()], vec![]);
+    rib.update(&announce, &UpdateTreatment::Normal);
+    let ipv4 = BgpUpdateMessage::new(
+        vec![],
+        vec![origin(), next_hop(Ipv4Addr::new(192, 0, 2, 1))],
+        vec![ipv4_nlri("198.51.100.0/24", None)],
+    );
+    rib.update(&ipv4, &UpdateTreatment::Normal);
+    assert_eq!(rib.len(), 3);
+
+    let key = RouteKey::new(AddressType::Ipv6Unicast, prefix("2001:db8:1::/48"), None);
+    let route = rib.get(&key).unwrap();
+    assert_eq!(route.next_hop(), Some(IpAddr::V6(next_hop_global)));
+    // The MP_REACH_NLRI attribute isn't stored with the route
+    assert_eq!(route.path_attributes(), &[origin()]);
+
+    let mp_unreach = PathAttribute::from(
+        true,
+        false,
+        false,
+        true,
+        PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast {
+            nlri: vec![ipv6_nlri("2001:db8:1::/48")],
+        }),
+    )
+    .unwrap();
+    let withdraw = BgpUpdateMessage::new(vec![], vec![mp_unreach], vec![]);
+    let changes = rib.update(&withdraw, &UpdateTreatment::Normal);
+    assert!(matches!(changes.as_slice(), [RouteChange::Withdrawn(route)] if route.key() == &key));
+    assert_eq!(rib.address_type_routes(AddressType::Ipv6Unicast).count(), 1);
+
+    // A malformed update flushes all the routes of its address family
+    let changes = rib.update(
+        &announce,
+        &UpdateTreatment::ResetAddressFamily(
+            AddressType::Ipv6Unicast.address_family().into(),
+            SubsequentAddressFamily::Unicast.into(),
+        ),
+    );
+    assert_eq!(changes.len(), 1);
+    assert_eq!(rib.address_type_routes(AddressType::Ipv6Unicast).count(), 0);
+    assert_eq!(rib.len(), 1);
+
+    assert_eq!(rib.clear().len(), 1);
+    assert!(rib.is_empty());
+}
+
+#[test]
+fn test_adj_rib_in_end_of_rib() {
+    let mut rib = AdjRibIn::new();
+    let end_of_rib = BgpUpdateMessage::new(vec![], vec![], vec![]);
+    assert_eq!(
+        rib.update(&end_of_rib, &UpdateTreatment::Normal),
+        vec![RouteChange::EndOfRib(AddressType::Ipv4Unicast)]
+    );
+    assert!(rib.is_empty());
+}
+
+#[test]
+fn test_adj_rib_in_stale_routes() {
+    let mut rib = AdjRibIn::new();
+    let announce = BgpUpdateMessage::new(
+        vec![],
+        vec![origin(), next_hop(Ipv4Addr::new(192, 0, 2, 1))],
+        vec![
+            ipv4_nlri("198.51.100.0/24", None),
+            ipv4_nlri("203.0.113.0/24", None),
+        ],
+    );
+    rib.update(&announce, &UpdateTreatment::Normal);
+    let mp_reach = PathAttribute::from(
+        true,
+        false,
+        false,
+        true,
+        PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+            next_hop_global: Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1),
+            next_hop_local: None,
+            nlri: vec![ipv6_nlri("2001:db8:1::/48")],
+        }),
+    )
+    .unwrap();
+    rib.update(
+        &BgpUpdateMessage::new(vec![], vec![mp_reach, origin()], vec![]),
+        &UpdateTreatment::Normal,
+    );
+    assert_eq!(rib.len(), 3);
+
+    // The routes of the address types not retained are withdrawn
+    let changes = rib.mark_stale(&[AddressType::Ipv4Unicast]);
+    assert!(matches!(changes.as_slice(), [RouteChange::Withdrawn(route)]
+        if route.key().address_type() == AddressType::Ipv6Unicast));
+    assert_eq!(rib.len(), 2);
+    assert_eq!(rib.stale_len(), 2);
+
+    // Announcing a route again replaces the stale one
+    let key = RouteKey::new(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"), None);
+    let announce_again = BgpUpdateMessage::new(
+        vec![],
+        vec![origin(), next_hop(Ipv4Addr::new(192, 0, 2, 1))],
+        vec![ipv4_nlri("198.51.100.0/24", None)],
+    );
+    rib.update(&announce_again, &UpdateTreatment::Normal);
+    assert!(!rib.is_stale(&key));
+    assert_eq!(rib.stale_len(), 1);
+
+    // End-of-RIB removes the remaining stale routes of its address type
+    let end_of_rib = BgpUpdateMessage::new(vec![], vec![], vec![]);
+    let changes = rib.update(&end_of_rib, &UpdateTreatment::Normal);
+    assert_eq!(changes.len(), 2);
+    assert!(matches!(&changes[0], RouteChange::Withdrawn(route)
+        if route.key().prefix() == prefix("203.0.113.0/24")));
+    assert_eq!(changes[1], RouteChange::EndOfRib(AddressType::Ipv4Unicast));
+    assert_eq!(rib.len(), 1);
+    assert_eq!(rib.stale_len(), 0);
+
+    rib.mark_stale(&[AddressType::Ipv4Unicast]);
+    assert_eq!(rib.remove_all_stale().len(), 1);
+    assert!(rib.is_empty());
+}
+
+fn as_path(as_numbers: Vec<u32>) -> PathAttribute {
+    PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            as_numbers,
+        )])),
+    )
+    .unwrap()
+}
+
+fn local_preference(value: u32) -> PathAttribute {
+    PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::LocalPreference(LocalPreference::new(value)),
+    )
+    .unwrap()
+}
+
+fn rib_peer(key: u8, ebgp: bool) -> RibPeer<u8> {
+    RibPeer::new(
+        key,
+        ebgp,
+        Ipv4Addr::new(10, 0, 0, key),
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, key)),
+    )
+}
+
+fn announced(peer: &RibPeer<u8>, path_attributes: Vec<PathAttribute>) -> RouteChange {
+    let key = RouteKey::new(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"), None);
+    RouteChange::Announced(Route::new(
+        key,
+        Some(peer.address()),
+        Arc::new(path_attributes),
+    ))
+}
+
+struct StaticIgpMetric(HashMap<IpAddr, u32>);
+
+impl IgpMetric for StaticIgpMetric {
+    fn igp_metric(&self, next_hop: IpAddr) -> Option<u32> {
+        self.0.get(&next_hop).copied()
+    }
+}
+
+/// Announce the routes of the peers one by one, and return the peer of the
+/// selected best path
+fn best_peer<M: IgpMetric>(
+    loc_rib: &mut LocRib<u8, M>,
+    routes: Vec<(RibPeer<u8>, Vec<PathAttribute>)>,
+) -> u8 {
+    for (peer, path_attributes) in routes {
+        loc_rib.update(&peer, &[announced(&peer, path_attributes)]);
+    }
+    *loc_rib
+        .best(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"))
+        .unwrap()
+        .peer()
+        .key()
+}
+
+#[test]
+fn test_loc_rib_decision_process() {
+    let attributes = |local_pref: u32, path: Vec<u32>, origin_value: Origin, med_value: u32| {
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(origin_value),
+            )
+            .unwrap(),
+            as_path(path),
+            local_preference(local_pref),
+            med(med_value),
+        ]
+    };
+    let ibgp_1 = rib_peer(1, false);
+    let ibgp_2 = rib_peer(2, false);
+    let ebgp_3 = rib_peer(3, true);
+
+    // Highest LOCAL_PREF first, even with a longer AS_PATH
+    let mut loc_rib = LocRib::new();
+    let best = best_peer(
+        &mut loc_rib,
+        vec![
+            (ibgp_1, attributes(100, vec![300], Origin::IGP, 0)),
+            (ibgp_2, attributes(200, vec![300, 400], Origin::IGP, 0)),
+        ],
+    );
+    assert_eq!(best, 2);
+
+    // Shortest AS_PATH, then the lowest ORIGIN
+    let mut loc_rib = LocRib::new();
+    let best = best_peer(
+        &mut loc_rib,
+        vec![
+            (ibgp_1, attributes(100, vec![300, 400], Origin::IGP, 0)),
+            (ibgp_2, attributes(100, vec![300], Origin::Incomplete, 0)),
+            (ebgp_3, attributes(100, vec![400], Origin::EGP, 0)),
+        ],
+    );
+    assert_eq!(best, 3);
+
+    // MED is only compared between the routes from the same neighbor AS, the
+    // route from AS 300 with the highest MED is removed before preferring
+    // eBGP over iBGP
+    let routes = vec![
+        (ibgp_1, attributes(100, vec![300], Origin::IGP, 10)),
+        (ibgp_2, attributes(100, vec![400], Origin::IGP, 5)),
+        (ebgp_3, attributes(100, vec![300], Origin::IGP, 20)),
+    ];
+    let mut loc_rib = LocRib::new();
+    assert_eq!(best_peer(&mut loc_rib, routes.clone()), 1);
+    let mut loc_rib = LocRib::new().with_always_compare_med(true);
+    assert_eq!(best_peer(&mut loc_rib, routes), 2);
+
+    // eBGP over iBGP
+    let mut loc_rib = LocRib::new();
+    let best = best_peer(
+        &mut loc_rib,
+        vec![
+            (ibgp_1, attributes(100, vec![300], Origin::IGP, 0)),
+            (ebgp_3, attributes(100, vec![400], Origin::IGP, 0)),
+        ],
+    );
+    assert_eq!(best, 3);
+
+    // Lowest IGP cost, then the lowest BGP Identifier
+    let routes = vec![
+        (ibgp_1, attributes(100, vec![300], Origin::IGP, 0)),
+        (ibgp_2, attributes(100, vec![400], Origin::IGP, 0)),
+    ];
+    let mut loc_rib = LocRib::new();
+    assert_eq!(best_peer(&mut loc_rib, routes.clone()), 1);
+    let igp_metric = StaticIgpMetric(HashMap::from([
+        (ibgp_1.address(), 20),
+        (ibgp_2.address(), 10),
+    ]));
+    let mut loc_rib = LocRib::new().with_igp_metric(igp_metric);
+    assert_eq!(best_peer(&mut loc_rib, routes), 2);
+
+    // Reselect when the IGP costs change
+    loc_rib.igp_metric_mut().0.insert(ibgp_2.address(), 30);
+    let changes = loc_rib.reselect();
+    assert!(matches!(changes.as_slice(), [LocRibChange::Best(route)] if *route.peer().key() == 1));
+    assert_eq!(loc_rib.reselect(), vec![]);
+}
+
+#[test]
+fn test_loc_rib_changes() {
+    let ibgp_1 = rib_peer(1, false);
+    let ibgp_2 = rib_peer(2, false);
+    let attributes =
+        |local_pref: u32| vec![origin(), as_path(vec![300]), local_preference(local_pref)];
+    let mut loc_rib = LocRib::new();
+
+    let route_1 = announced(&ibgp_1, attributes(100));
+    let changes = loc_rib.update(&ibgp_1, std::slice::from_ref(&route_1));
+    let RouteChange::Announced(route_1) = route_1 else {
+        unreachable!()
+    };
+    let best_1 = LocRibRoute::new(ibgp_1, route_1.clone());
+    assert_eq!(changes, vec![LocRibChange::Best(best_1.clone())]);
+
+    // A less preferred path doesn't change the best path
+    let route_2 = announced(&ibgp_2, attributes(50));
+    assert_eq!(
+        loc_rib.update(&ibgp_2, std::slice::from_ref(&route_2)),
+        vec![]
+    );
+    assert_eq!(
+        loc_rib
+            .paths(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"))
+            .len(),
+        2
+    );
+
+    // The best path is withdrawn, the other path is selected
+    let changes = loc_rib.update(&ibgp_1, &[RouteChange::Withdrawn(route_1)]);
+    let RouteChange::Announced(route_2) = route_2 else {
+        unreachable!()
+    };
+    let best_2 = LocRibRoute::new(ibgp_2, route_2);
+    assert_eq!(changes, vec![LocRibChange::Best(best_2.clone())]);
+    assert_eq!(loc_rib.len(), 1);
+    assert_eq!(loc_rib.routes().collect::<Vec<_>>(), vec![&best_2]);
+
+    // Withdraw and announce of the same route in a batch
+    let change = announced(&ibgp_1, attributes(100));
+    let changes = loc_rib.update(
+        &ibgp_1,
+        &[
+            change.clone(),
+            RouteChange::Withdrawn(best_1.route().clone()),
+            change,
+        ],
+    );
+    assert_eq!(changes, vec![LocRibChange::Best(best_1.clone())]);
+
+    assert_eq!(
+        loc_rib.remove_peer(&1),
+        vec![LocRibChange::Best(best_2.clone())]
+    );
+    assert_eq!(
+        loc_rib.remove_peer(&2),
+        vec![LocRibChange::Withdrawn(best_2)]
+    );
+    assert_eq!(loc_rib.remove_peer(&2), vec![]);
+    assert!(loc_rib.is_empty());
+}
+
+fn as2_route(as_path: Vec<As2PathSegment>, attributes: Vec<PathAttributeValue>) -> Route {
+    let key = RouteKey::new(AddressType::Ipv4Unicast, prefix("198.51.100.0/24"), None);
+    let mut path_attributes = vec![PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::AsPath(AsPath::As2PathSegments(as_path)),
+    )
+    .unwrap()];
+    for value in attributes {
+        let transitive = value.can_be_transitive().unwrap_or(true);
+        path_attributes.push(PathAttribute::from(true, transitive, false, false, value).unwrap());
+    }
+    Route::new(key, None, Arc::new(path_attributes))
+}
+
+#[test]
+fn test_route_as4_path() {
+    let as4_aggregator = As4Aggregator::new(4_200_000_000, Ipv4Addr::new(10, 0, 0, 1));
+    let as_path = vec![
+        As2PathSegment::new(AsPathSegmentType::AsConfedSequence, vec![65001]),
+        As2PathSegment::new(AsPathSegmentType::AsSequence, vec![100, AS_TRANS, 300]),
+        As2PathSegment::new(AsPathSegmentType::AsSet, vec![400, AS_TRANS]),
+    ];
+    let as4_path = PathAttributeValue::As4Path(As4Path::new(vec![
+        As4PathSegment::new(AsPathSegmentType::AsSequence, vec![4_200_000_000, 300]),
+        As4PathSegment::new(AsPathSegmentType::AsSet, vec![400, 4_200_000_001]),
+    ]));
+
+    // The leading AS numbers of the AS_PATH are merged with the AS4_PATH
+    let route = as2_route(
+        as_path.clone(),
+        vec![
+            PathAttributeValue::Aggregator(Aggregator::As2Aggregator(As2Aggregator::new(
+                AS_TRANS,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))),
+            as4_path.clone(),
+            PathAttributeValue::As4Aggregator(as4_aggregator.clone()),
+        ],
+    );
+    assert_eq!(route.as_path_numbers(true), vec![65001]);
+    assert_eq!(
+        route.as_path_numbers(false),
+        vec![100, 4_200_000_000, 300, 400, 4_200_000_001]
+    );
+    assert_eq!(route.as_path_length(), 4);
+    assert_eq!(route.neighbor_as(), Some(100));
+    assert_eq!(route.aggregator(), Some(as4_aggregator.clone()));
+
+    // The AS4_PATH is ignored when longer than the AS_PATH
+    let route = as2_route(
+        vec![As2PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            vec![AS_TRANS],
+        )],
+        vec![as4_path.clone()],
+    );
+    assert_eq!(route.as_path_numbers(false), vec![AS_TRANS as u32]);
+
+    // The AS4 attributes are ignored when the AGGREGATOR isn't AS_TRANS
+    let route = as2_route(
+        as_path,
+        vec![
+            PathAttributeValue::Aggregator(Aggregator::As2Aggregator(As2Aggregator::new(
+                500,
+                Ipv4Addr::new(10, 0, 0, 1),
+            ))),
+            as4_path,
+            PathAttributeValue::As4Aggregator(as4_aggregator),
+        ],
+    );
+    assert_eq!(
+        route.as_path_numbers(false),
+        vec![100, AS_TRANS as u32, 300, 400, AS_TRANS as u32]
+    );
+    assert_eq!(
+        route.aggregator(),
+        Some(As4Aggregator::new(500, Ipv4Addr::new(10, 0, 0, 1)))
+    );
+}
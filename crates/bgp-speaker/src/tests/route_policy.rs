@@ -0,0 +1,245 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    rib::{Route, RouteKey},
+    route_policy::{
+        AsPathRegex, Policy, PolicyAction, PolicyMatch, PolicyResult, PolicyRoute, PolicyTerm,
+        PrefixListEntry, RoutePolicy,
+    },
+    update::{OriginatedRoute, RouteAttributes},
+};
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    community::Community,
+    path_attribute::{
+        As4PathSegment, AsPath, AsPathSegmentType, Communities, LocalPreference,
+        MultiExitDiscriminator, Origin, PathAttribute, PathAttributeValue,
+    },
+};
+use netgauze_iana::address_family::AddressType;
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    sync::Arc,
+};
+
+fn prefix(value: &str) -> IpNet {
+    value.parse().unwrap()
+}
+
+fn received_route(value: &str, as_path: Vec<u32>, communities: Vec<Community>) -> Route {
+    let mut path_attributes = vec![
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::Origin(Origin::IGP),
+        )
+        .unwrap(),
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                as_path,
+            )])),
+        )
+        .unwrap(),
+    ];
+    if !communities.is_empty() {
+        path_attributes.push(
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::Communities(Communities::new(communities)),
+            )
+            .unwrap(),
+        );
+    }
+    Route::new(
+        RouteKey::new(AddressType::Ipv4Unicast, prefix(value), None),
+        Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+        Arc::new(path_attributes),
+    )
+}
+
+#[test]
+fn test_prefix_list_entry() {
+    let exact = PrefixListEntry::new(prefix("198.51.100.0/24"));
+    assert!(exact.matches(&prefix("198.51.100.0/24")));
+    assert!(!exact.matches(&prefix("198.51.100.0/25")));
+    assert!(!exact.matches(&prefix("198.51.0.0/16")));
+
+    let range = PrefixListEntry::new(prefix("198.51.0.0/16"))
+        .with_ge(20)
+        .with_le(24);
+    assert!(!range.matches(&prefix("198.51.0.0/16")));
+    assert!(range.matches(&prefix("198.51.16.0/20")));
+    assert!(range.matches(&prefix("198.51.100.0/24")));
+    assert!(!range.matches(&prefix("198.51.100.0/25")));
+    assert!(!range.matches(&prefix("203.0.113.0/24")));
+
+    let or_longer = PrefixListEntry::new(prefix("198.51.0.0/16")).with_ge(16);
+    assert!(or_longer.matches(&prefix("198.51.0.0/16")));
+    assert!(or_longer.matches(&prefix("198.51.100.1/32")));
+}
+
+#[test]
+fn test_as_path_regex() {
+    let regex = AsPathRegex::new("^65001 ").unwrap();
+    let sequence = |as_numbers| As4PathSegment::new(AsPathSegmentType::AsSequence, as_numbers);
+    assert!(regex.matches(&[sequence(vec![65001, 65002])]));
+    assert!(!regex.matches(&[sequence(vec![65002, 65001])]));
+
+    let set = AsPathRegex::new(r"\{65003 65004\}$").unwrap();
+    assert!(set.matches(&[
+        sequence(vec![65001]),
+        As4PathSegment::new(AsPathSegmentType::AsSet, vec![65003, 65004]),
+    ]));
+
+    let empty = AsPathRegex::new("^$").unwrap();
+    assert!(empty.matches(&[]));
+    assert!(!empty.matches(&[sequence(vec![65001])]));
+
+    assert!(AsPathRegex::new("(").is_err());
+}
+
+#[test]
+fn test_policy_received_route() {
+    let blackhole = Community::new(0xFFFF029A);
+    let policy = Policy::new(PolicyResult::Reject)
+        .with_term(
+            PolicyTerm::new()
+                .with_match(PolicyMatch::Community(vec![blackhole]))
+                .with_result(PolicyResult::Reject),
+        )
+        .with_term(
+            PolicyTerm::new()
+                .with_match(PolicyMatch::AsPath(AsPathRegex::new("^65001").unwrap()))
+                .with_action(PolicyAction::SetLocalPreference(200))
+                .with_action(PolicyAction::SetMed(10)),
+        )
+        .with_term(
+            PolicyTerm::new()
+                .with_match(PolicyMatch::PrefixList(vec![PrefixListEntry::new(prefix(
+                    "198.51.0.0/16",
+                ))
+                .with_le(24)]))
+                .with_action(PolicyAction::AddCommunities(vec![Community::new(
+                    65000 << 16 | 1,
+                )]))
+                .with_result(PolicyResult::Accept),
+        );
+
+    let mut route = received_route("198.51.100.0/24", vec![65001, 65002], vec![]);
+    assert_eq!(policy.apply(&mut route), PolicyResult::Accept);
+    assert_eq!(route.local_preference(), Some(200));
+    assert_eq!(route.med(), Some(10));
+    assert_eq!(
+        PolicyRoute::communities(&route),
+        vec![Community::new(65000 << 16 | 1)]
+    );
+    // The attributes are kept in the order of their type codes
+    assert_eq!(
+        route
+            .path_attributes()
+            .iter()
+            .map(|attr| attr.path_attribute_type().map(|t| t as u8))
+            .collect::<Vec<_>>(),
+        vec![Ok(1), Ok(2), Ok(4), Ok(5), Ok(8)]
+    );
+    assert_eq!(
+        route.path_attributes()[2].value(),
+        &PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(10))
+    );
+    assert_eq!(
+        route.path_attributes()[3].value(),
+        &PathAttributeValue::LocalPreference(LocalPreference::new(200))
+    );
+
+    let mut route = received_route("198.51.100.0/24", vec![65002], vec![blackhole]);
+    assert_eq!(policy.apply(&mut route), PolicyResult::Reject);
+
+    // Not matched by any term with a result
+    let mut route = received_route("203.0.113.0/24", vec![65001], vec![]);
+    assert_eq!(policy.apply(&mut route), PolicyResult::Reject);
+}
+
+#[test]
+fn test_policy_originated_route() {
+    let communities = vec![Community::new(1), Community::new(2)];
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+        .with_med(50)
+        .with_communities(communities.clone());
+    let policy = Policy::new(PolicyResult::Accept).with_term(
+        PolicyTerm::new()
+            .with_match(PolicyMatch::Not(Box::new(PolicyMatch::AsPath(
+                AsPathRegex::new(".").unwrap(),
+            ))))
+            .with_action(PolicyAction::RemoveMed)
+            .with_action(PolicyAction::RemoveCommunities(vec![Community::new(1)])),
+    );
+
+    let mut route = OriginatedRoute::new(prefix("198.51.100.0/24"), attributes.clone());
+    assert_eq!(policy.apply(&mut route), PolicyResult::Accept);
+    assert_eq!(
+        route.into_attributes(),
+        RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+            .with_communities(vec![Community::new(2)])
+    );
+
+    // Routes with a non-empty AS path don't match the term
+    let attributes = attributes.with_as_path(vec![65001]);
+    let mut route = OriginatedRoute::new(prefix("198.51.100.0/24"), attributes.clone());
+    assert_eq!(policy.apply(&mut route), PolicyResult::Accept);
+    assert_eq!(route.into_attributes(), attributes);
+}
+
+#[test]
+fn test_programmatic_policy() {
+    let policy: Arc<dyn RoutePolicy> = Arc::new(|route: &mut dyn PolicyRoute| {
+        if route.prefix().prefix_len() > 24 {
+            return PolicyResult::Reject;
+        }
+        route.set_local_preference(route.local_preference().map(|value| value + 1));
+        PolicyResult::Accept
+    });
+    let mut route = received_route("198.51.100.0/25", vec![65001], vec![]);
+    assert_eq!(policy.apply(&mut route), PolicyResult::Reject);
+
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+        .with_local_preference(100);
+    let mut route = OriginatedRoute::new(prefix("198.51.100.0/24"), attributes);
+    assert_eq!(policy.apply(&mut route), PolicyResult::Accept);
+    assert_eq!(route.attributes().local_preference(), Some(101));
+}
+
+#[test]
+fn test_policy_serde() {
+    let policy = Policy::new(PolicyResult::Reject).with_term(
+        PolicyTerm::new()
+            .with_match(PolicyMatch::AsPath(AsPathRegex::new("^65001$").unwrap()))
+            .with_action(PolicyAction::SetLocalPreference(200))
+            .with_result(PolicyResult::Accept),
+    );
+    let serialized = serde_json::to_string(&policy).unwrap();
+    let deserialized: Policy = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(deserialized, policy);
+}
@@ -0,0 +1,118 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::socket::{
+    InterfaceName, InterfaceNameError, TcpMd5Key, TcpMd5KeyError, INTERFACE_NAME_MAX_LENGTH,
+    TCP_MD5_MAX_KEY_LENGTH,
+};
+
+#[test]
+fn test_tcp_md5_key() {
+    let key = TcpMd5Key::try_from("secret").unwrap();
+    assert_eq!(key.key(), b"secret");
+    // The key isn't leaked in the logs
+    assert_eq!(format!("{key:?}"), "TcpMd5Key(***)");
+
+    let max = [b'a'; TCP_MD5_MAX_KEY_LENGTH];
+    assert_eq!(TcpMd5Key::new(&max).unwrap().key(), &max);
+    assert_eq!(
+        TcpMd5Key::new(&[b'a'; TCP_MD5_MAX_KEY_LENGTH + 1]),
+        Err(TcpMd5KeyError::TooLong(TCP_MD5_MAX_KEY_LENGTH + 1))
+    );
+    assert_eq!(TcpMd5Key::new(b""), Err(TcpMd5KeyError::Empty));
+}
+
+#[test]
+fn test_interface_name() {
+    let name = InterfaceName::try_from("eth0").unwrap();
+    assert_eq!(name.name(), b"eth0");
+    assert_eq!(format!("{name:?}"), "InterfaceName(\"eth0\")");
+
+    let max = [b'a'; INTERFACE_NAME_MAX_LENGTH];
+    assert_eq!(InterfaceName::new(&max).unwrap().name(), &max);
+    assert_eq!(
+        InterfaceName::new(&[b'a'; INTERFACE_NAME_MAX_LENGTH + 1]),
+        Err(InterfaceNameError::TooLong(INTERFACE_NAME_MAX_LENGTH + 1))
+    );
+    assert_eq!(InterfaceName::new(b""), Err(InterfaceNameError::Empty));
+}
+
+#[cfg(target_os = "linux")]
+fn get_int_option<S: std::os::fd::AsRawFd>(
+    socket: &S,
+    level: libc::c_int,
+    name: libc::c_int,
+) -> libc::c_int {
+    let mut value: libc::c_int = 0;
+    let mut len = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // SAFETY: the option value is a valid int of the given length
+    let ret = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            level,
+            name,
+            &mut value as *mut _ as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    assert_eq!(ret, 0);
+    value
+}
+
+#[cfg(target_os = "linux")]
+#[test_log::test(tokio::test)]
+async fn test_connect_socket_options() {
+    use crate::{
+        connection::{ActiveConnect, TcpActiveConnect},
+        peer::PeerConfigBuilder,
+        socket::{TcpKeepalive, DSCP_CS6},
+    };
+    use std::net::{IpAddr, Ipv4Addr};
+    use tokio::net::TcpListener;
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let local_address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let config = PeerConfigBuilder::new()
+        .tcp_keepalive(TcpKeepalive::new(30, 5, 3))
+        .local_address(local_address)
+        .build();
+    assert_eq!(config.dscp(), DSCP_CS6);
+    let stream = TcpActiveConnect
+        .connect(listener.local_addr().unwrap(), &config)
+        .await
+        .unwrap();
+
+    assert_eq!(stream.local_addr().unwrap().ip(), local_address);
+    assert_eq!(
+        get_int_option(&stream, libc::IPPROTO_IP, libc::IP_TOS),
+        (DSCP_CS6 as libc::c_int) << 2
+    );
+    assert_eq!(
+        get_int_option(&stream, libc::SOL_SOCKET, libc::SO_KEEPALIVE),
+        1
+    );
+    assert_eq!(
+        get_int_option(&stream, libc::IPPROTO_TCP, libc::TCP_KEEPIDLE),
+        30
+    );
+    assert_eq!(
+        get_int_option(&stream, libc::IPPROTO_TCP, libc::TCP_KEEPINTVL),
+        5
+    );
+    assert_eq!(
+        get_int_option(&stream, libc::IPPROTO_TCP, libc::TCP_KEEPCNT),
+        3
+    );
+}
@@ -15,12 +15,17 @@
 
 use crate::{
     connection::TcpActiveConnect,
-    peer::{EchoCapabilitiesPolicy, PeerConfig},
-    supervisor::{PeersSupervisor, PeersSupervisorError},
-    tests::{HOLD_TIME, MY_AS, MY_BGP_ID, PEER_ADDR, PROPERTIES},
+    peer::{EchoCapabilitiesPolicy, PeerConfig, PeerConfigBuilder, PeerProperties},
+    route_policy::{Policy, PolicyResult},
+    supervisor::{PeerGroup, PeerOverrides, PeersSupervisor, PeersSupervisorError},
+    tests::{HOLD_TIME, MY_AS, MY_BGP_ID, PEER_ADDR, PEER_AS, PROPERTIES},
+};
+use netgauze_bgp_pkt::{capabilities::AddPathAddressFamily, codec::BgpCodec};
+use netgauze_iana::address_family::AddressType;
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::Arc,
 };
-use netgauze_bgp_pkt::codec::BgpCodec;
-use std::net::SocketAddr;
 
 const TCP_STREAM_POLICY: EchoCapabilitiesPolicy<SocketAddr, tokio::net::TcpStream, BgpCodec> =
     EchoCapabilitiesPolicy::new(MY_AS, false, MY_BGP_ID, HOLD_TIME, Vec::new(), Vec::new());
@@ -72,3 +77,134 @@ async fn test_dynamic_peers() -> Result<(), PeersSupervisorError> {
     assert!(non_existing_peer.is_none());
     Ok(())
 }
+
+#[test_log::test(tokio::test)]
+async fn test_peer_groups() -> Result<(), PeersSupervisorError> {
+    let mut supervisor = PeersSupervisor::new(MY_AS, MY_BGP_ID);
+    let config = PeerConfigBuilder::new().hold_timer_duration(90).build();
+    let group = PeerGroup::new(config)
+        .with_add_path(vec![AddPathAddressFamily::new(
+            AddressType::Ipv4Unicast,
+            true,
+            true,
+        )])
+        .with_import_policy(Arc::new(Policy::new(PolicyResult::Accept)));
+    supervisor.add_group("ibgp", group.clone())?;
+    assert_eq!(
+        supervisor.add_group("ibgp", group.clone()),
+        Err(PeersSupervisorError::GroupExists)
+    );
+    assert_eq!(
+        supervisor
+            .group("ibgp")
+            .map(|group| group.config().hold_timer_duration()),
+        Some(config.hold_timer_duration())
+    );
+
+    let second_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 3)), 179);
+    let second_properties = PeerProperties::new(MY_AS, PEER_AS, MY_BGP_ID, second_addr, false);
+    let overrides = PeerOverrides::new()
+        .with_config(
+            PeerConfigBuilder::from(config)
+                .hold_timer_duration(30)
+                .build(),
+        )
+        .with_export_policy(Arc::new(Policy::new(PolicyResult::Reject)));
+    let (_rx, _peer_handle) = supervisor.create_group_peer(
+        PEER_ADDR.ip(),
+        PROPERTIES,
+        "ibgp",
+        PeerOverrides::new(),
+        TcpActiveConnect,
+    )?;
+    let (_second_rx, _second_handle) = supervisor.create_group_peer(
+        second_addr.ip(),
+        second_properties,
+        "ibgp",
+        overrides,
+        TcpActiveConnect,
+    )?;
+    let unknown_group = supervisor.create_group_peer(
+        PEER_ADDR.ip(),
+        PROPERTIES,
+        "ebgp",
+        PeerOverrides::new(),
+        TcpActiveConnect,
+    );
+    assert_eq!(
+        unknown_group.err(),
+        Some(PeersSupervisorError::GroupNotFound)
+    );
+    assert_eq!(supervisor.peer_group(&PEER_ADDR.ip()), Some("ibgp"));
+    assert_eq!(supervisor.peer_group(&second_addr.ip()), Some("ibgp"));
+
+    // Policies of the updated group are applied to the running members
+    let stopped = supervisor.update_group(
+        "ibgp",
+        group.with_import_policy(Arc::new(Policy::new(PolicyResult::Reject))),
+    )?;
+    assert!(stopped.is_empty());
+
+    // Runtime membership changes
+    supervisor.add_group("ebgp", PeerGroup::new(PeerConfig::default()))?;
+    supervisor.set_peer_group(&PEER_ADDR.ip(), Some("ebgp"))?;
+    assert_eq!(supervisor.peer_group(&PEER_ADDR.ip()), Some("ebgp"));
+    assert_eq!(
+        supervisor.set_peer_group(&PEER_ADDR.ip(), Some("unknown")),
+        Err(PeersSupervisorError::GroupNotFound)
+    );
+    supervisor.set_peer_group(&second_addr.ip(), None)?;
+    assert_eq!(supervisor.peer_group(&second_addr.ip()), None);
+
+    assert!(supervisor.remove_group("ibgp").is_ok());
+    assert_eq!(
+        supervisor.remove_group("ebgp").err(),
+        Some(PeersSupervisorError::GroupHasMembers)
+    );
+    assert!(supervisor.remove_peer(&PEER_ADDR.ip()).is_some());
+    assert_eq!(supervisor.peer_group(&PEER_ADDR.ip()), None);
+    assert!(supervisor.remove_group("ebgp").is_ok());
+    assert_eq!(
+        supervisor.set_peer_group(&PEER_ADDR.ip(), None),
+        Err(PeersSupervisorError::PeerNotFound)
+    );
+    Ok(())
+}
+
+#[test_log::test(tokio::test)]
+async fn test_dynamic_group_peers() -> Result<(), PeersSupervisorError> {
+    let mut supervisor = PeersSupervisor::new(MY_AS, MY_BGP_ID);
+    let unknown_group = supervisor.dynamic_group_peer(
+        PEER_ADDR.ip(),
+        PEER_ADDR,
+        "clients",
+        vec![],
+        TcpActiveConnect,
+    );
+    assert_eq!(
+        unknown_group.err(),
+        Some(PeersSupervisorError::GroupNotFound)
+    );
+
+    supervisor.add_group("clients", PeerGroup::new(PeerConfig::default()))?;
+    let (_rx, _peer_handle) = supervisor.dynamic_group_peer(
+        PEER_ADDR.ip(),
+        PEER_ADDR,
+        "clients",
+        vec![64512..=65534],
+        TcpActiveConnect,
+    )?;
+    assert_eq!(supervisor.peer_group(&PEER_ADDR.ip()), Some("clients"));
+    let second_create = supervisor.dynamic_group_peer(
+        PEER_ADDR.ip(),
+        PEER_ADDR,
+        "clients",
+        vec![],
+        TcpActiveConnect,
+    );
+    assert_eq!(second_create.err(), Some(PeersSupervisorError::PeerExists));
+
+    assert!(supervisor.remove_peer(&PEER_ADDR.ip()).is_some());
+    assert_eq!(supervisor.peer_group(&PEER_ADDR.ip()), None);
+    Ok(())
+}
@@ -0,0 +1,422 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{
+    peer::Confederation,
+    tests::{MY_AS, PEER_AS},
+    update::{RouteAttributes, UpdateBuilder, UpdateBuilderError, ORIGINATED_PATH_ID},
+};
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    capabilities::{
+        negotiate, AddPathAddressFamily, AddPathCapability, BgpCapability, FourOctetAsCapability,
+        MultiProtocolExtensionsCapability,
+    },
+    community::Community,
+    iana::AS_TRANS,
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress, Ipv6Unicast, Ipv6UnicastAddress},
+    path_attribute::{
+        Aggregator, As2Aggregator, As2PathSegment, As4Aggregator, As4Path, As4PathSegment, AsPath,
+        AsPathSegmentType, Communities, LocalPreference, MpReach, MpUnreach,
+        MultiExitDiscriminator, NextHop, Origin, PathAttribute, PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+    BgpMessage,
+};
+use netgauze_iana::address_family::AddressType;
+use netgauze_parse_utils::WritablePdu;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+fn prefix(value: &str) -> IpNet {
+    value.parse().unwrap()
+}
+
+fn builder(my_asn: u32, peer_asn: u32, capabilities: &[BgpCapability]) -> UpdateBuilder {
+    UpdateBuilder::new(my_asn, peer_asn, negotiate(capabilities, capabilities))
+}
+
+#[test]
+fn test_announce_asn2_peer() {
+    let builder = builder(MY_AS, PEER_AS, &[]);
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+        .with_as_path(vec![4_200_000_000, 300])
+        .with_med(10)
+        .with_local_preference(200)
+        .with_aggregator(As4Aggregator::new(
+            4_200_000_000,
+            Ipv4Addr::new(192, 0, 2, 2),
+        ))
+        .with_communities(vec![Community::new(0x00640001)]);
+    let updates = builder
+        .announce(&[prefix("198.51.100.1/24")], &attributes)
+        .unwrap();
+
+    // Local preference isn't sent to eBGP peers
+    let expected = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![MY_AS as u16, AS_TRANS, 300],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                false,
+                false,
+                false,
+                PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(10)),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::Aggregator(Aggregator::As2Aggregator(As2Aggregator::new(
+                    AS_TRANS,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                ))),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::Communities(Communities::new(vec![Community::new(0x00640001)])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::As4Path(As4Path::new(vec![As4PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![MY_AS, 4_200_000_000, 300],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::As4Aggregator(As4Aggregator::new(
+                    4_200_000_000,
+                    Ipv4Addr::new(192, 0, 2, 2),
+                )),
+            )
+            .unwrap(),
+        ],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )],
+    );
+    assert_eq!(updates, vec![expected]);
+}
+
+#[test]
+fn test_announce_confederation() {
+    let confederation = Confederation::new(65000, MY_AS);
+    let capabilities = [BgpCapability::FourOctetAs(FourOctetAsCapability::new(
+        MY_AS,
+    ))];
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)))
+        .with_as_path(vec![300])
+        .with_local_preference(200);
+    let as_path = |segments| {
+        PathAttribute::from(
+            false,
+            true,
+            false,
+            false,
+            PathAttributeValue::AsPath(AsPath::As4PathSegments(segments)),
+        )
+        .unwrap()
+    };
+    let origin = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::Origin(Origin::IGP),
+    )
+    .unwrap();
+    let next_hop = PathAttribute::from(
+        false,
+        true,
+        false,
+        false,
+        PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+    )
+    .unwrap();
+    let nlri = vec![Ipv4UnicastAddress::new_no_path_id(
+        Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+    )];
+
+    // The member AS is prepended in a confederation segment and the local
+    // preference is kept for the peers in another member AS
+    let confederation_peer =
+        builder(MY_AS, PEER_AS, &capabilities).with_confederation(confederation);
+    assert!(confederation_peer.is_confederation_peer());
+    let updates = confederation_peer
+        .announce(&[prefix("198.51.100.0/24")], &attributes)
+        .unwrap();
+    let expected = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            origin.clone(),
+            as_path(vec![
+                As4PathSegment::new(AsPathSegmentType::AsConfedSequence, vec![MY_AS]),
+                As4PathSegment::new(AsPathSegmentType::AsSequence, vec![300]),
+            ]),
+            next_hop.clone(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::LocalPreference(LocalPreference::new(200)),
+            )
+            .unwrap(),
+        ],
+        nlri.clone(),
+    );
+    assert_eq!(updates, vec![expected]);
+
+    // The external peers only see the confederation identifier
+    let external_peer = builder(65000, 400, &capabilities).with_confederation(confederation);
+    assert!(!external_peer.is_confederation_peer());
+    let updates = external_peer
+        .announce(&[prefix("198.51.100.0/24")], &attributes)
+        .unwrap();
+    let expected = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            origin,
+            as_path(vec![As4PathSegment::new(
+                AsPathSegmentType::AsSequence,
+                vec![65000, 300],
+            )]),
+            next_hop,
+        ],
+        nlri,
+    );
+    assert_eq!(updates, vec![expected]);
+}
+
+#[test]
+fn test_announce_ipv6_add_path() {
+    let capabilities = [
+        BgpCapability::FourOctetAs(FourOctetAsCapability::new(MY_AS)),
+        BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv6Unicast,
+        )),
+        BgpCapability::AddPath(AddPathCapability::new(vec![AddPathAddressFamily::new(
+            AddressType::Ipv6Unicast,
+            true,
+            true,
+        )])),
+    ];
+    let builder = builder(MY_AS, MY_AS, &capabilities);
+    let next_hop = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+    let attributes =
+        RouteAttributes::new(Origin::Incomplete, IpAddr::V6(next_hop)).with_local_preference(200);
+    let updates = builder
+        .announce(&[prefix("2001:db8:1::/48")], &attributes)
+        .unwrap();
+
+    let expected = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                true,
+                false,
+                false,
+                true,
+                PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                    next_hop_global: next_hop,
+                    next_hop_local: None,
+                    nlri: vec![Ipv6UnicastAddress::new(
+                        Some(ORIGINATED_PATH_ID),
+                        Ipv6Unicast::from_net("2001:db8:1::/48".parse().unwrap()).unwrap(),
+                    )],
+                }),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::Incomplete),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::LocalPreference(LocalPreference::new(200)),
+            )
+            .unwrap(),
+        ],
+        vec![],
+    );
+    assert_eq!(updates, vec![expected]);
+
+    // IPv4 unicast is not negotiated when other address types are advertised
+    assert_eq!(
+        builder.announce(&[prefix("198.51.100.0/24")], &attributes),
+        Err(UpdateBuilderError::AddressTypeNotNegotiated(
+            AddressType::Ipv4Unicast
+        ))
+    );
+    let ipv4_next_hop = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+    assert_eq!(
+        builder.announce(&[prefix("2001:db8:1::/48")], &ipv4_next_hop),
+        Err(UpdateBuilderError::UnsupportedNextHop(
+            AddressType::Ipv6Unicast,
+            ipv4_next_hop.next_hop()
+        ))
+    );
+}
+
+#[test]
+fn test_announce_packing() {
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+    let prefixes = (0..2000u32)
+        .map(|i| IpNet::new(Ipv4Addr::from((10 << 24) | (i << 8)).into(), 24).unwrap())
+        .collect::<Vec<_>>();
+
+    let builder = builder(MY_AS, PEER_AS, &[]);
+    let updates = builder.announce(&prefixes, &attributes).unwrap();
+    assert_eq!(updates.len(), 2);
+    assert!(updates
+        .iter()
+        .all(|update| BgpMessage::Update(update.clone()).len() <= 4096));
+    assert_eq!(
+        updates
+            .iter()
+            .map(|update| update.nlri().len())
+            .sum::<usize>(),
+        prefixes.len()
+    );
+
+    let builder = self::builder(MY_AS, PEER_AS, &[BgpCapability::ExtendedMessage]);
+    let updates = builder.announce(&prefixes, &attributes).unwrap();
+    assert_eq!(updates.len(), 1);
+}
+
+#[test]
+fn test_withdraw() {
+    let capabilities = [
+        BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv4Unicast,
+        )),
+        BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv6Unicast,
+        )),
+    ];
+    let builder = builder(MY_AS, PEER_AS, &capabilities);
+    let updates = builder
+        .withdraw(&[prefix("198.51.100.0/24"), prefix("2001:db8:1::/48")])
+        .unwrap();
+    assert_eq!(
+        updates,
+        vec![
+            BgpUpdateMessage::new(
+                vec![Ipv4UnicastAddress::new_no_path_id(
+                    Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+                )],
+                vec![],
+                vec![],
+            ),
+            BgpUpdateMessage::new(
+                vec![],
+                vec![PathAttribute::from(
+                    true,
+                    false,
+                    false,
+                    true,
+                    PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast {
+                        nlri: vec![Ipv6UnicastAddress::new(
+                            None,
+                            Ipv6Unicast::from_net("2001:db8:1::/48".parse().unwrap()).unwrap(),
+                        )],
+                    }),
+                )
+                .unwrap()],
+                vec![],
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_end_of_rib() {
+    let capabilities = [
+        BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv4Unicast,
+        )),
+        BgpCapability::MultiProtocolExtensions(MultiProtocolExtensionsCapability::new(
+            AddressType::Ipv6Unicast,
+        )),
+    ];
+    let builder = builder(MY_AS, PEER_AS, &capabilities);
+    let ipv4 = builder.end_of_rib(AddressType::Ipv4Unicast).unwrap();
+    assert_eq!(ipv4, BgpUpdateMessage::new(vec![], vec![], vec![]));
+    assert_eq!(ipv4.end_of_rib(), Some(AddressType::Ipv4Unicast));
+    let ipv6 = builder.end_of_rib(AddressType::Ipv6Unicast).unwrap();
+    assert_eq!(ipv6.end_of_rib(), Some(AddressType::Ipv6Unicast));
+    assert_eq!(
+        builder.end_of_rib(AddressType::L2VpnBgpEvpn),
+        Err(UpdateBuilderError::AddressTypeNotNegotiated(
+            AddressType::L2VpnBgpEvpn
+        ))
+    );
+}
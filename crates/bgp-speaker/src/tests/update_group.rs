@@ -0,0 +1,211 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::sync::Arc;
+
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    capabilities::{negotiate, BgpCapability, FourOctetAsCapability},
+    codec::BgpCodec,
+    nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+    open::BgpOpenMessage,
+    path_attribute::{
+        As2PathSegment, AsPath, AsPathSegmentType, NextHop, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+};
+
+use crate::{
+    events::BgpEvent,
+    fsm::{FsmState, FsmStateError},
+    peer::*,
+    route_policy::{PolicyResult, PolicyRoute, RoutePolicy},
+    tests::*,
+    update::{RouteAttributes, UpdateBuilder},
+    update_group::{SharedUpdates, UpdateGroup},
+};
+
+type MockPeer = Peer<
+    IpAddr,
+    SocketAddr,
+    tokio_test::io::Mock,
+    BgpCodec,
+    MockActiveConnect,
+    EchoCapabilitiesPolicy<SocketAddr, tokio_test::io::Mock, BgpCodec>,
+>;
+
+fn accept_all() -> Arc<dyn RoutePolicy> {
+    Arc::new(|_: &mut dyn PolicyRoute| PolicyResult::Accept)
+}
+
+#[test]
+fn test_update_group_eq() {
+    let asn4 = [BgpCapability::FourOctetAs(FourOctetAsCapability::new(
+        MY_AS,
+    ))];
+    let builder = |peer_asn: u32, capabilities: &[BgpCapability]| {
+        UpdateBuilder::new(MY_AS, peer_asn, negotiate(capabilities, capabilities))
+    };
+    let policy = accept_all();
+
+    // The eBGP peers of different ASes are in the same group
+    assert_eq!(
+        UpdateGroup::new(builder(PEER_AS, &[]), None),
+        UpdateGroup::new(builder(300, &[]), None)
+    );
+    assert_eq!(
+        UpdateGroup::new(builder(PEER_AS, &[]), Some(policy.clone())),
+        UpdateGroup::new(builder(300, &[]), Some(policy.clone()))
+    );
+    assert_ne!(
+        UpdateGroup::new(builder(PEER_AS, &[]), None),
+        UpdateGroup::new(builder(MY_AS, &[]), None)
+    );
+    assert_ne!(
+        UpdateGroup::new(builder(PEER_AS, &[]), None),
+        UpdateGroup::new(builder(PEER_AS, &asn4), None)
+    );
+    // Export policies are compared by instance
+    assert_ne!(
+        UpdateGroup::new(builder(PEER_AS, &[]), Some(policy)),
+        UpdateGroup::new(builder(PEER_AS, &[]), Some(accept_all()))
+    );
+    assert_ne!(
+        UpdateGroup::new(builder(PEER_AS, &[]), None),
+        UpdateGroup::new(builder(PEER_AS, &[]), Some(accept_all()))
+    );
+}
+
+/// Establish a session with a peer without capabilities, expecting the
+/// updates to be sent once established
+async fn established_peer(
+    peer_addr: SocketAddr,
+    peer_asn: u32,
+    updates: &[BgpUpdateMessage],
+) -> Result<MockPeer, FsmStateError<SocketAddr>> {
+    let peer_open = BgpOpenMessage::new(peer_asn as u16, HOLD_TIME, PEER_BGP_ID, vec![]);
+    let mut io_builder = BgpIoMockBuilder::new();
+    io_builder
+        .write(BgpMessage::Open(BgpOpenMessage::new(
+            MY_AS as u16,
+            HOLD_TIME,
+            MY_BGP_ID,
+            vec![],
+        )))
+        .read(BgpMessage::Open(peer_open.clone()))
+        .write(BgpMessage::KeepAlive)
+        .read(BgpMessage::KeepAlive);
+    for update in updates {
+        io_builder.write(BgpMessage::Update(update.clone()));
+    }
+    let active_connect = MockActiveConnect {
+        peer_addr,
+        io_builder,
+        connect_delay: Duration::from_secs(0),
+    };
+    let properties = PeerProperties::new(MY_AS, peer_asn, MY_BGP_ID, peer_addr, false);
+    let mut peer = Peer::new(
+        peer_addr.ip(),
+        properties,
+        PeerConfig::default(),
+        POLICY,
+        active_connect,
+    );
+    peer.add_admin_event(PeerAdminEvents::ManualStart);
+    assert_eq!(peer.run().await?, BgpEvent::ManualStart);
+    assert_eq!(
+        peer.run().await?,
+        BgpEvent::TcpConnectionRequestAcked(peer_addr)
+    );
+    assert_eq!(peer.run().await?, BgpEvent::BGPOpen(peer_open));
+    assert_eq!(peer.run().await?, BgpEvent::KeepAliveMsg);
+    assert_eq!(peer.fsm_state(), FsmState::Established);
+    Ok(peer)
+}
+
+#[test_log::test(tokio::test)]
+async fn test_shared_updates() -> Result<(), FsmStateError<SocketAddr>> {
+    let next_hop = Ipv4Addr::new(192, 0, 2, 1);
+    let attributes = RouteAttributes::new(Origin::IGP, IpAddr::V4(next_hop));
+    let prefixes: Vec<IpNet> = vec!["198.51.100.0/24".parse().unwrap()];
+    let announce = BgpUpdateMessage::new(
+        vec![],
+        vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(vec![As2PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    vec![MY_AS as u16],
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::NextHop(NextHop::new(next_hop)),
+            )
+            .unwrap(),
+        ],
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )],
+    );
+    let withdraw = BgpUpdateMessage::new(
+        vec![Ipv4UnicastAddress::new_no_path_id(
+            Ipv4Unicast::from_net("198.51.100.0/24".parse().unwrap()).unwrap(),
+        )],
+        vec![],
+        vec![],
+    );
+    let updates = [announce, withdraw];
+    let second_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 3)), 179);
+    let third_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 4)), 179);
+    let mut first = established_peer(PEER_ADDR, PEER_AS, &updates).await?;
+    let mut second = established_peer(second_addr, 300, &updates).await?;
+    let mut third = established_peer(third_addr, PEER_AS, &updates).await?;
+    third.set_export_policy(Some(accept_all())).await?;
+    assert_eq!(first.update_group(), second.update_group());
+    assert_ne!(first.update_group(), third.update_group());
+
+    // The first and second peers share the same messages
+    let shared = SharedUpdates::new();
+    for peer in [&mut first, &mut second, &mut third] {
+        peer.announce_shared_routes(prefixes.clone(), attributes.clone(), &shared)
+            .await?;
+    }
+    assert_eq!(shared.generated_len(), 2);
+
+    let shared = SharedUpdates::new();
+    for peer in [&mut first, &mut second, &mut third] {
+        peer.withdraw_shared_routes(prefixes.clone(), &shared)
+            .await?;
+    }
+    assert_eq!(shared.generated_len(), 2);
+    Ok(())
+}
@@ -0,0 +1,711 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Build the BGP Update messages of the routes originated by the speaker.
+//!
+//! [`UpdateBuilder`] encodes prefixes sharing the same [`RouteAttributes`]
+//! according to the capabilities negotiated with a peer:
+//!  - The AS path is encoded with `AS_TRANS` and an `AS4_PATH` attribute for
+//!    peers without four-octet AS support, and likewise the aggregator with an
+//!    `AS4_AGGREGATOR` attribute, see
+//!    [RFC6793](https://datatracker.ietf.org/doc/html/rfc6793).
+//!  - The member AS is prepended in an `AS_CONFED_SEQUENCE` segment for the
+//!    peers in other member ASes of the confederation, see
+//!    [RFC5065](https://datatracker.ietf.org/doc/html/rfc5065).
+//!  - The NLRI carry [`ORIGINATED_PATH_ID`] when sending ADD-PATH is
+//!    negotiated, see [RFC7911](https://datatracker.ietf.org/doc/html/rfc7911).
+//!  - The prefixes are packed into as few messages as the max message length
+//!    allows, the max is raised when extended messages are negotiated.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use ipnet::IpNet;
+use netgauze_bgp_pkt::{
+    capabilities::NegotiatedCapabilities,
+    community::{Community, ExtendedCommunity, LargeCommunity},
+    iana::AS_TRANS,
+    nlri::{
+        InvalidIpv4UnicastNetwork, InvalidIpv6UnicastNetwork, Ipv4Unicast, Ipv4UnicastAddress,
+        Ipv6Unicast, Ipv6UnicastAddress,
+    },
+    path_attribute::{
+        Aggregator, As2Aggregator, As2PathSegment, As4Aggregator, As4Path, As4PathSegment, AsPath,
+        AsPathSegmentType, Communities, ExtendedCommunities, InvalidPathAttribute,
+        LargeCommunities, LocalPreference, MpReach, MpUnreach, MultiExitDiscriminator, NextHop,
+        Origin, PathAttribute, PathAttributeValue,
+    },
+    update::BgpUpdateMessage,
+    wire::deserializer::{
+        BGP_EXTENDED_MAX_MESSAGE_LENGTH, BGP_MAX_MESSAGE_LENGTH, BGP_MIN_MESSAGE_LENGTH,
+    },
+};
+use netgauze_iana::address_family::{AddressFamily, AddressType};
+use netgauze_parse_utils::WritablePdu;
+
+use crate::{peer::Confederation, route_policy::PolicyRoute};
+
+/// Path identifier of the originated routes when ADD-PATH send is negotiated
+/// with the peer. The speaker originates a single path per prefix.
+pub const ORIGINATED_PATH_ID: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateBuilderError {
+    InvalidIpv4Prefix(InvalidIpv4UnicastNetwork),
+    InvalidIpv6Prefix(InvalidIpv6UnicastNetwork),
+    InvalidPathAttribute(InvalidPathAttribute),
+    /// The address type of the prefix is not negotiated with the peer
+    AddressTypeNotNegotiated(AddressType),
+    /// The next hop can't be used for the address type of the prefix
+    UnsupportedNextHop(AddressType, IpAddr),
+}
+
+impl From<InvalidIpv4UnicastNetwork> for UpdateBuilderError {
+    fn from(error: InvalidIpv4UnicastNetwork) -> Self {
+        Self::InvalidIpv4Prefix(error)
+    }
+}
+
+impl From<InvalidIpv6UnicastNetwork> for UpdateBuilderError {
+    fn from(error: InvalidIpv6UnicastNetwork) -> Self {
+        Self::InvalidIpv6Prefix(error)
+    }
+}
+
+impl From<(PathAttributeValue, InvalidPathAttribute)> for UpdateBuilderError {
+    fn from((_, error): (PathAttributeValue, InvalidPathAttribute)) -> Self {
+        Self::InvalidPathAttribute(error)
+    }
+}
+
+/// Attribute set of originated routes. The AS path doesn't include the local
+/// AS, it's prepended when the routes are sent to eBGP peers.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct RouteAttributes {
+    origin: Origin,
+    as_path: Vec<u32>,
+    next_hop: IpAddr,
+    med: Option<u32>,
+    local_preference: Option<u32>,
+    aggregator: Option<As4Aggregator>,
+    communities: Vec<Community>,
+    extended_communities: Vec<ExtendedCommunity>,
+    large_communities: Vec<LargeCommunity>,
+}
+
+impl RouteAttributes {
+    pub const fn new(origin: Origin, next_hop: IpAddr) -> Self {
+        Self {
+            origin,
+            as_path: vec![],
+            next_hop,
+            med: None,
+            local_preference: None,
+            aggregator: None,
+            communities: vec![],
+            extended_communities: vec![],
+            large_communities: vec![],
+        }
+    }
+
+    /// AS numbers of the AS_SEQUENCE, the closest AS first
+    pub fn with_as_path(mut self, as_path: Vec<u32>) -> Self {
+        self.as_path = as_path;
+        self
+    }
+
+    pub const fn with_med(mut self, med: u32) -> Self {
+        self.med = Some(med);
+        self
+    }
+
+    /// Only sent to iBGP and confederation peers
+    pub const fn with_local_preference(mut self, local_preference: u32) -> Self {
+        self.local_preference = Some(local_preference);
+        self
+    }
+
+    /// AS and BGP ID of the speaker that aggregated the routes
+    pub const fn with_aggregator(mut self, aggregator: As4Aggregator) -> Self {
+        self.aggregator = Some(aggregator);
+        self
+    }
+
+    pub fn with_communities(mut self, communities: Vec<Community>) -> Self {
+        self.communities = communities;
+        self
+    }
+
+    pub fn with_extended_communities(mut self, communities: Vec<ExtendedCommunity>) -> Self {
+        self.extended_communities = communities;
+        self
+    }
+
+    pub fn with_large_communities(mut self, communities: Vec<LargeCommunity>) -> Self {
+        self.large_communities = communities;
+        self
+    }
+
+    pub const fn origin(&self) -> Origin {
+        self.origin
+    }
+
+    pub const fn as_path(&self) -> &Vec<u32> {
+        &self.as_path
+    }
+
+    pub const fn next_hop(&self) -> IpAddr {
+        self.next_hop
+    }
+
+    pub const fn med(&self) -> Option<u32> {
+        self.med
+    }
+
+    pub const fn local_preference(&self) -> Option<u32> {
+        self.local_preference
+    }
+
+    pub const fn aggregator(&self) -> Option<&As4Aggregator> {
+        self.aggregator.as_ref()
+    }
+
+    pub const fn communities(&self) -> &Vec<Community> {
+        &self.communities
+    }
+
+    pub const fn extended_communities(&self) -> &Vec<ExtendedCommunity> {
+        &self.extended_communities
+    }
+
+    pub const fn large_communities(&self) -> &Vec<LargeCommunity> {
+        &self.large_communities
+    }
+}
+
+/// Originated route as seen by the export policy of a peer, see
+/// [`crate::route_policy`]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OriginatedRoute {
+    prefix: IpNet,
+    attributes: RouteAttributes,
+}
+
+impl OriginatedRoute {
+    pub const fn new(prefix: IpNet, attributes: RouteAttributes) -> Self {
+        Self { prefix, attributes }
+    }
+
+    pub const fn attributes(&self) -> &RouteAttributes {
+        &self.attributes
+    }
+
+    pub fn into_attributes(self) -> RouteAttributes {
+        self.attributes
+    }
+}
+
+impl PolicyRoute for OriginatedRoute {
+    fn prefix(&self) -> IpNet {
+        self.prefix
+    }
+
+    fn as_path(&self) -> Vec<As4PathSegment> {
+        if self.attributes.as_path.is_empty() {
+            return vec![];
+        }
+        vec![As4PathSegment::new(
+            AsPathSegmentType::AsSequence,
+            self.attributes.as_path.clone(),
+        )]
+    }
+
+    fn communities(&self) -> Vec<Community> {
+        self.attributes.communities.clone()
+    }
+
+    fn med(&self) -> Option<u32> {
+        self.attributes.med
+    }
+
+    fn local_preference(&self) -> Option<u32> {
+        self.attributes.local_preference
+    }
+
+    fn set_communities(&mut self, communities: Vec<Community>) {
+        self.attributes.communities = communities;
+    }
+
+    fn set_med(&mut self, med: Option<u32>) {
+        self.attributes.med = med;
+    }
+
+    fn set_local_preference(&mut self, local_preference: Option<u32>) {
+        self.attributes.local_preference = local_preference;
+    }
+}
+
+/// Build the Update messages sent to a peer, see the [module](self) docs
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct UpdateBuilder {
+    my_asn: u32,
+    peer_asn: u32,
+    capabilities: NegotiatedCapabilities,
+    confederation: Option<Confederation>,
+}
+
+impl UpdateBuilder {
+    pub const fn new(my_asn: u32, peer_asn: u32, capabilities: NegotiatedCapabilities) -> Self {
+        Self {
+            my_asn,
+            peer_asn,
+            capabilities,
+            confederation: None,
+        }
+    }
+
+    /// Build the messages of a member of the confederation, `my_asn` is the
+    /// member AS for the peers inside the confederation and the confederation
+    /// identifier for the external peers
+    pub const fn with_confederation(mut self, confederation: Confederation) -> Self {
+        self.confederation = Some(confederation);
+        self
+    }
+
+    pub const fn my_asn(&self) -> u32 {
+        self.my_asn
+    }
+
+    pub const fn peer_asn(&self) -> u32 {
+        self.peer_asn
+    }
+
+    pub const fn capabilities(&self) -> &NegotiatedCapabilities {
+        &self.capabilities
+    }
+
+    pub const fn confederation(&self) -> Option<Confederation> {
+        self.confederation
+    }
+
+    /// Includes the confederation peers
+    pub const fn is_ebgp(&self) -> bool {
+        self.my_asn != self.peer_asn
+    }
+
+    /// The peer is in another member AS of the confederation
+    pub const fn is_confederation_peer(&self) -> bool {
+        match self.confederation {
+            Some(confederation) => self.my_asn == confederation.member_asn() && self.is_ebgp(),
+            None => false,
+        }
+    }
+
+    /// Max length of the encoded BGP messages
+    pub const fn max_message_length(&self) -> usize {
+        if self.capabilities.extended_message() {
+            BGP_EXTENDED_MAX_MESSAGE_LENGTH as usize
+        } else {
+            BGP_MAX_MESSAGE_LENGTH as usize
+        }
+    }
+
+    /// Update messages announcing the prefixes with the same attributes.
+    /// IPv4 prefixes with an IPv4 next hop are carried in the NLRI field, the
+    /// others in `MP_REACH_NLRI`.
+    pub fn announce(
+        &self,
+        prefixes: &[IpNet],
+        attributes: &RouteAttributes,
+    ) -> Result<Vec<BgpUpdateMessage>, UpdateBuilderError> {
+        let (ipv4, ipv6) = self.nlri(prefixes)?;
+        let mut updates = vec![];
+        if !ipv4.is_empty() {
+            match attributes.next_hop {
+                IpAddr::V4(next_hop) => {
+                    let path_attributes = self.path_attributes(attributes, Some(next_hop))?;
+                    let update =
+                        |nlri| BgpUpdateMessage::new(vec![], path_attributes.clone(), nlri);
+                    let available = self.max_message_length() - message_len(&update(vec![]));
+                    updates.extend(pack(ipv4, available, |x| x.len()).into_iter().map(update));
+                }
+                IpAddr::V6(next_hop) => {
+                    // IPv6 next hop for IPv4 NLRI, see RFC8950
+                    if !self.extended_next_hop(AddressType::Ipv4Unicast, AddressFamily::IPv6) {
+                        return Err(UpdateBuilderError::UnsupportedNextHop(
+                            AddressType::Ipv4Unicast,
+                            attributes.next_hop,
+                        ));
+                    }
+                    let path_attributes = self.path_attributes(attributes, None)?;
+                    updates.extend(self.pack_mp_reach(
+                        ipv4,
+                        &path_attributes,
+                        |x| x.len(),
+                        |nlri| MpReach::Ipv4Unicast {
+                            next_hop: next_hop.into(),
+                            next_hop_local: None,
+                            nlri,
+                        },
+                    )?);
+                }
+            }
+        }
+        if !ipv6.is_empty() {
+            let IpAddr::V6(next_hop_global) = attributes.next_hop else {
+                return Err(UpdateBuilderError::UnsupportedNextHop(
+                    AddressType::Ipv6Unicast,
+                    attributes.next_hop,
+                ));
+            };
+            let path_attributes = self.path_attributes(attributes, None)?;
+            updates.extend(self.pack_mp_reach(
+                ipv6,
+                &path_attributes,
+                |x| x.len(),
+                |nlri| MpReach::Ipv6Unicast {
+                    next_hop_global,
+                    next_hop_local: None,
+                    nlri,
+                },
+            )?);
+        }
+        Ok(updates)
+    }
+
+    /// Update messages withdrawing the prefixes. IPv4 prefixes are carried in
+    /// the withdrawn routes field, the others in `MP_UNREACH_NLRI`.
+    pub fn withdraw(
+        &self,
+        prefixes: &[IpNet],
+    ) -> Result<Vec<BgpUpdateMessage>, UpdateBuilderError> {
+        let (ipv4, ipv6) = self.nlri(prefixes)?;
+        let mut updates = vec![];
+        if !ipv4.is_empty() {
+            let update = |withdrawn| BgpUpdateMessage::new(withdrawn, vec![], vec![]);
+            let available = self.max_message_length() - message_len(&update(vec![]));
+            updates.extend(pack(ipv4, available, |x| x.len()).into_iter().map(update));
+        }
+        if !ipv6.is_empty() {
+            let update = |nlri| {
+                let unreach = path_attribute_extended(
+                    true,
+                    false,
+                    PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast { nlri }),
+                )?;
+                Ok::<_, UpdateBuilderError>(BgpUpdateMessage::new(vec![], vec![unreach], vec![]))
+            };
+            let available = self.max_message_length() - message_len(&update(vec![])?);
+            for nlri in pack(ipv6, available, |x| x.len()) {
+                updates.push(update(nlri)?);
+            }
+        }
+        Ok(updates)
+    }
+
+    /// End-of-RIB marker of the address type as defined in
+    /// [RFC4724](https://datatracker.ietf.org/doc/html/rfc4724), an empty
+    /// update message for IPv4 unicast and an empty `MP_UNREACH_NLRI`
+    /// otherwise.
+    pub fn end_of_rib(
+        &self,
+        address_type: AddressType,
+    ) -> Result<BgpUpdateMessage, UpdateBuilderError> {
+        if !self.capabilities.address_types().contains(&address_type) {
+            return Err(UpdateBuilderError::AddressTypeNotNegotiated(address_type));
+        }
+        let unreach = match address_type {
+            AddressType::Ipv4Unicast => return Ok(BgpUpdateMessage::new(vec![], vec![], vec![])),
+            AddressType::Ipv4Multicast => MpUnreach::Ipv4Multicast { nlri: vec![] },
+            AddressType::Ipv4NlriMplsLabels => MpUnreach::Ipv4NlriMplsLabels { nlri: vec![] },
+            AddressType::Ipv4MplsLabeledVpn => MpUnreach::Ipv4MplsVpnUnicast { nlri: vec![] },
+            AddressType::Ipv6Unicast => MpUnreach::Ipv6Unicast { nlri: vec![] },
+            AddressType::Ipv6Multicast => MpUnreach::Ipv6Multicast { nlri: vec![] },
+            AddressType::Ipv6NlriMplsLabels => MpUnreach::Ipv6NlriMplsLabels { nlri: vec![] },
+            AddressType::Ipv6MplsLabeledVpn => MpUnreach::Ipv6MplsVpnUnicast { nlri: vec![] },
+            AddressType::L2VpnBgpEvpn => MpUnreach::L2Evpn { nlri: vec![] },
+            AddressType::RouteTargetConstrains => MpUnreach::RouteTargetMembership { nlri: vec![] },
+            AddressType::BgpLs => MpUnreach::BgpLs { nlri: vec![] },
+            AddressType::BgpLsVpn => MpUnreach::BgpLsVpn { nlri: vec![] },
+            _ => MpUnreach::Unknown {
+                afi: address_type.address_family(),
+                safi: address_type.subsequent_address_family(),
+                nlri: vec![],
+            },
+        };
+        let unreach = path_attribute_extended(true, false, PathAttributeValue::MpUnreach(unreach))?;
+        Ok(BgpUpdateMessage::new(vec![], vec![unreach], vec![]))
+    }
+
+    /// Pack the NLRI in `MP_REACH_NLRI` attributes, one per update message
+    fn pack_mp_reach<T>(
+        &self,
+        nlri: Vec<T>,
+        path_attributes: &[PathAttribute],
+        len: impl Fn(&T) -> usize,
+        mp_reach: impl Fn(Vec<T>) -> MpReach,
+    ) -> Result<Vec<BgpUpdateMessage>, UpdateBuilderError> {
+        let update = |nlri| {
+            // RFC7606 recommends sending MP_REACH_NLRI as the first attribute.
+            // The extended length is always used, so the length of the
+            // attribute header doesn't depend on the number of prefixes.
+            let mut attributes = vec![path_attribute_extended(
+                true,
+                false,
+                PathAttributeValue::MpReach(mp_reach(nlri)),
+            )?];
+            attributes.extend_from_slice(path_attributes);
+            Ok(BgpUpdateMessage::new(vec![], attributes, vec![]))
+        };
+        let available = self.max_message_length() - message_len(&update(vec![])?);
+        pack(nlri, available, len).into_iter().map(update).collect()
+    }
+
+    fn nlri(
+        &self,
+        prefixes: &[IpNet],
+    ) -> Result<(Vec<Ipv4UnicastAddress>, Vec<Ipv6UnicastAddress>), UpdateBuilderError> {
+        let mut ipv4 = vec![];
+        let mut ipv6 = vec![];
+        for prefix in prefixes {
+            match prefix.trunc() {
+                IpNet::V4(net) => {
+                    let path_id = self.path_id(AddressType::Ipv4Unicast)?;
+                    ipv4.push(Ipv4UnicastAddress::new(
+                        path_id,
+                        Ipv4Unicast::from_net(net)?,
+                    ));
+                }
+                IpNet::V6(net) => {
+                    let path_id = self.path_id(AddressType::Ipv6Unicast)?;
+                    ipv6.push(Ipv6UnicastAddress::new(
+                        path_id,
+                        Ipv6Unicast::from_net(net)?,
+                    ));
+                }
+            }
+        }
+        Ok((ipv4, ipv6))
+    }
+
+    fn path_id(&self, address_type: AddressType) -> Result<Option<u32>, UpdateBuilderError> {
+        if !self.capabilities.address_types().contains(&address_type) {
+            return Err(UpdateBuilderError::AddressTypeNotNegotiated(address_type));
+        }
+        Ok(self
+            .capabilities
+            .add_path_send(address_type)
+            .then_some(ORIGINATED_PATH_ID))
+    }
+
+    fn extended_next_hop(&self, address_type: AddressType, next_hop_afi: AddressFamily) -> bool {
+        self.capabilities
+            .extended_next_hop_encodings()
+            .iter()
+            .any(|encoding| {
+                encoding.address_type() == address_type && encoding.next_hop_afi() == next_hop_afi
+            })
+    }
+
+    /// Path attributes of the routes in the order of their type codes. The
+    /// `NEXT_HOP` attribute is only used for IPv4 NLRI with an IPv4 next hop.
+    fn path_attributes(
+        &self,
+        attributes: &RouteAttributes,
+        next_hop: Option<Ipv4Addr>,
+    ) -> Result<Vec<PathAttribute>, UpdateBuilderError> {
+        // The originated routes don't carry confederation segments, the
+        // external peers of a confederation only see its identifier as my_asn
+        let mut as_path = attributes.as_path.clone();
+        let mut segments = vec![];
+        if self.is_confederation_peer() {
+            segments.push(As4PathSegment::new(
+                AsPathSegmentType::AsConfedSequence,
+                vec![self.my_asn],
+            ));
+        } else if self.is_ebgp() {
+            as_path.insert(0, self.my_asn);
+        }
+        if !as_path.is_empty() {
+            segments.push(As4PathSegment::new(AsPathSegmentType::AsSequence, as_path));
+        }
+        // Peers without four-octet AS support get the AS numbers that don't fit
+        // in two octets replaced by AS_TRANS, and the full path in AS4_PATH.
+        // AS4_PATH doesn't carry the confederation segments.
+        let mut as4_path = None;
+        let as_path = if self.capabilities.four_octet_as() {
+            AsPath::As4PathSegments(segments)
+        } else {
+            let as2_segments = segments
+                .iter()
+                .map(|segment| {
+                    let as_numbers = segment
+                        .as_numbers()
+                        .iter()
+                        .map(|asn| u16::try_from(*asn).unwrap_or(AS_TRANS))
+                        .collect();
+                    As2PathSegment::new(segment.segment_type(), as_numbers)
+                })
+                .collect();
+            let as4_segments: Vec<_> = segments
+                .into_iter()
+                .filter(|segment| !segment.segment_type().is_confederation())
+                .collect();
+            if as4_segments
+                .iter()
+                .flat_map(|segment| segment.as_numbers())
+                .any(|asn| u16::try_from(*asn).is_err())
+            {
+                as4_path = Some(As4Path::new(as4_segments));
+            }
+            AsPath::As2PathSegments(as2_segments)
+        };
+
+        let mut path_attributes = vec![
+            path_attribute(false, true, PathAttributeValue::Origin(attributes.origin))?,
+            path_attribute(false, true, PathAttributeValue::AsPath(as_path))?,
+        ];
+        if let Some(next_hop) = next_hop {
+            path_attributes.push(path_attribute(
+                false,
+                true,
+                PathAttributeValue::NextHop(NextHop::new(next_hop)),
+            )?);
+        }
+        if let Some(med) = attributes.med {
+            path_attributes.push(path_attribute(
+                true,
+                false,
+                PathAttributeValue::MultiExitDiscriminator(MultiExitDiscriminator::new(med)),
+            )?);
+        }
+        if let Some(local_preference) = attributes
+            .local_preference
+            .filter(|_| !self.is_ebgp() || self.is_confederation_peer())
+        {
+            path_attributes.push(path_attribute(
+                false,
+                true,
+                PathAttributeValue::LocalPreference(LocalPreference::new(local_preference)),
+            )?);
+        }
+        // The AS of the aggregator is replaced by AS_TRANS the same way as in
+        // the AS path, with the full AS in AS4_AGGREGATOR
+        let mut as4_aggregator = None;
+        if let Some(aggregator) = &attributes.aggregator {
+            let aggregator = if self.capabilities.four_octet_as() {
+                Aggregator::As4Aggregator(aggregator.clone())
+            } else {
+                let asn = u16::try_from(*aggregator.asn()).unwrap_or_else(|_| {
+                    as4_aggregator = Some(aggregator.clone());
+                    AS_TRANS
+                });
+                Aggregator::As2Aggregator(As2Aggregator::new(asn, aggregator.origin()))
+            };
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::Aggregator(aggregator),
+            )?);
+        }
+        if !attributes.communities.is_empty() {
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::Communities(Communities::new(attributes.communities.clone())),
+            )?);
+        }
+        if !attributes.extended_communities.is_empty() {
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::ExtendedCommunities(ExtendedCommunities::new(
+                    attributes.extended_communities.clone(),
+                )),
+            )?);
+        }
+        if let Some(as4_path) = as4_path {
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::As4Path(as4_path),
+            )?);
+        }
+        if let Some(as4_aggregator) = as4_aggregator {
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::As4Aggregator(as4_aggregator),
+            )?);
+        }
+        if !attributes.large_communities.is_empty() {
+            path_attributes.push(path_attribute(
+                true,
+                true,
+                PathAttributeValue::LargeCommunities(LargeCommunities::new(
+                    attributes.large_communities.clone(),
+                )),
+            )?);
+        }
+        Ok(path_attributes)
+    }
+}
+
+/// Path attribute using the extended length only when the value doesn't fit
+/// in one octet length
+pub(crate) fn path_attribute(
+    optional: bool,
+    transitive: bool,
+    value: PathAttributeValue,
+) -> Result<PathAttribute, UpdateBuilderError> {
+    let attribute = PathAttribute::from(optional, transitive, false, false, value.clone())?;
+    if attribute.len() <= PathAttribute::BASE_LENGTH + 1 + u8::MAX as usize {
+        return Ok(attribute);
+    }
+    path_attribute_extended(optional, transitive, value)
+}
+
+fn path_attribute_extended(
+    optional: bool,
+    transitive: bool,
+    value: PathAttributeValue,
+) -> Result<PathAttribute, UpdateBuilderError> {
+    Ok(PathAttribute::from(
+        optional, transitive, false, true, value,
+    )?)
+}
+
+fn message_len(update: &BgpUpdateMessage) -> usize {
+    BGP_MIN_MESSAGE_LENGTH as usize + update.len()
+}
+
+/// Split the NLRI in chunks that fit in the `available` octets of a message
+fn pack<T>(nlri: Vec<T>, available: usize, len: impl Fn(&T) -> usize) -> Vec<Vec<T>> {
+    let mut chunks = vec![];
+    let mut chunk = vec![];
+    let mut chunk_len = 0;
+    for value in nlri {
+        let value_len = len(&value);
+        if !chunk.is_empty() && chunk_len + value_len > available {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_len = 0;
+        }
+        chunk_len += value_len;
+        chunk.push(value);
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    chunks
+}
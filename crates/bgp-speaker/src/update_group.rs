@@ -0,0 +1,152 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Update groups of the peers receiving the same Update messages for the
+//! originated routes.
+//!
+//! The peers with the same export policy and the same [`UpdateBuilder`]
+//! parameters, i.e., local AS, iBGP or eBGP session, confederation and
+//! negotiated capabilities, belong to the same [`UpdateGroup`]. When routes
+//! are originated to many peers at once, e.g., by
+//! [`crate::supervisor::PeersSupervisor::announce`], the Update messages are
+//! generated by the first peer of every group and shared with the other
+//! members through [`SharedUpdates`], instead of generating a copy for every
+//! peer.
+
+use std::{
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
+};
+
+use ipnet::IpNet;
+use netgauze_bgp_pkt::update::BgpUpdateMessage;
+
+use crate::{
+    route_policy::RoutePolicy,
+    update::{RouteAttributes, UpdateBuilder},
+};
+
+/// Parameters determining the Update messages generated for a peer, see the
+/// [module](self) docs
+#[derive(Debug, Clone)]
+pub struct UpdateGroup {
+    builder: UpdateBuilder,
+    export_policy: Option<Arc<dyn RoutePolicy>>,
+}
+
+impl UpdateGroup {
+    pub const fn new(builder: UpdateBuilder, export_policy: Option<Arc<dyn RoutePolicy>>) -> Self {
+        Self {
+            builder,
+            export_policy,
+        }
+    }
+
+    pub const fn builder(&self) -> &UpdateBuilder {
+        &self.builder
+    }
+
+    pub const fn export_policy(&self) -> Option<&Arc<dyn RoutePolicy>> {
+        self.export_policy.as_ref()
+    }
+}
+
+/// The peer AS only tells apart the iBGP and eBGP sessions, and the export
+/// policies are the same when they're the same instance
+impl PartialEq for UpdateGroup {
+    fn eq(&self, other: &Self) -> bool {
+        let same_policy = match (&self.export_policy, &other.export_policy) {
+            (None, None) => true,
+            (Some(policy), Some(other)) => {
+                std::ptr::addr_eq(Arc::as_ptr(policy), Arc::as_ptr(other))
+            }
+            _ => false,
+        };
+        same_policy
+            && self.builder.my_asn() == other.builder.my_asn()
+            && self.builder.is_ebgp() == other.builder.is_ebgp()
+            && self.builder.confederation() == other.builder.confederation()
+            && self.builder.is_confederation_peer() == other.builder.is_confederation_peer()
+            && self.builder.capabilities() == other.builder.capabilities()
+    }
+}
+
+impl Eq for UpdateGroup {}
+
+struct GeneratedUpdates {
+    group: UpdateGroup,
+    announced: Vec<(IpNet, RouteAttributes)>,
+    withdrawn: Vec<IpNet>,
+    updates: Arc<Vec<BgpUpdateMessage>>,
+}
+
+/// Update messages generated for the groups of the peers the same routes are
+/// originated to, see the [module](self) docs. The clones share the messages,
+/// which are dropped with the last clone.
+#[derive(Clone, Default)]
+pub struct SharedUpdates {
+    generated: Arc<Mutex<Vec<GeneratedUpdates>>>,
+}
+
+impl Debug for SharedUpdates {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "SharedUpdates")
+    }
+}
+
+impl SharedUpdates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Messages announcing and withdrawing the routes to the members of the
+    /// group, `generate` is only called when no member generated them yet.
+    /// The routes exported to a member can still differ from the ones of the
+    /// group, e.g., when they were originated to the member alone before, in
+    /// which case its messages are generated separately.
+    pub fn get_or_generate(
+        &self,
+        group: &UpdateGroup,
+        announced: &[(IpNet, RouteAttributes)],
+        withdrawn: &[IpNet],
+        generate: impl FnOnce() -> Vec<BgpUpdateMessage>,
+    ) -> Arc<Vec<BgpUpdateMessage>> {
+        let Ok(mut generated) = self.generated.lock() else {
+            return Arc::new(generate());
+        };
+        if let Some(shared) = generated.iter().find(|shared| {
+            shared.group == *group && shared.announced == announced && shared.withdrawn == withdrawn
+        }) {
+            return Arc::clone(&shared.updates);
+        }
+        let updates = Arc::new(generate());
+        generated.push(GeneratedUpdates {
+            group: group.clone(),
+            announced: announced.to_vec(),
+            withdrawn: withdrawn.to_vec(),
+            updates: Arc::clone(&updates),
+        });
+        updates
+    }
+
+    /// Number of times the messages were generated, i.e., the number of update
+    /// groups unless the routes exported to some members differ
+    pub fn generated_len(&self) -> usize {
+        self.generated
+            .lock()
+            .map(|generated| generated.len())
+            .unwrap_or_default()
+    }
+}
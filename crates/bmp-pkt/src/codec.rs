@@ -18,14 +18,12 @@
 use crate::{
     iana::BmpVersion,
     wire::{deserializer::BmpMessageParsingError, serializer::BmpMessageWritingError},
-    BmpMessage, BmpMessageValue, PeerKey,
+    BmpMessage, BmpMessageValue,
 };
 use byteorder::{ByteOrder, NetworkEndian};
-use bytes::{Buf, BufMut, BytesMut};
-use netgauze_bgp_pkt::{capabilities::BgpCapability, BgpMessage};
+use bytes::{Buf, BytesMut};
 
 use crate::wire::deserializer::BmpParsingContext;
-use netgauze_bgp_pkt::capabilities::{AddPathCapability, MultipleLabel};
 use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu};
 use nom::Needed;
 use serde::{Deserialize, Serialize};
@@ -34,11 +32,22 @@ use tokio_util::codec::{Decoder, Encoder};
 /// Min length for a valid BMP Message: 1-octet version + 4-octet length
 pub const BMP_MESSAGE_MIN_LENGTH: usize = 5;
 
+/// Default max length accepted by [`BmpCodec`] for a single BMP message. This
+/// is well above what's needed to carry a BGP extended message (65535 octets)
+/// with the BMP headers.
+pub const BMP_MESSAGE_DEFAULT_MAX_LENGTH: usize = 1024 * 1024;
+
 #[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum BmpCodecDecoderError {
     IoError(String),
     Incomplete(Option<usize>),
     BmpMessageParsingError(BmpMessageParsingError),
+    /// The BMP message length is larger than the configured max length of the
+    /// codec, the message is skipped
+    MessageTooLarge {
+        length: usize,
+        max_length: usize,
+    },
 }
 
 impl From<std::io::Error> for BmpCodecDecoderError {
@@ -48,43 +57,48 @@ impl From<std::io::Error> for BmpCodecDecoderError {
 }
 
 /// Encoder and Decoder for [`BmpMessage`]
-#[derive(Debug, Default)]
+///
+/// The codec frames BMP messages based on their length field, and keeps a
+/// [`BmpParsingContext`] that is updated from the Peer Up/Down and
+/// Termination messages seen on the stream. Hence, one codec should be used
+/// per BMP connection.
+#[derive(Debug)]
 pub struct BmpCodec {
-    /// Helper to track in the decoder if we are inside a BMP message or not
-    in_message: bool,
+    /// Max length accepted for a BMP message
+    max_message_length: usize,
+    /// Number of octets still to be skipped from a message that exceeded
+    /// `max_message_length`
+    discard: usize,
     ctx: BmpParsingContext,
 }
 
-#[inline]
-fn get_caps(
-    capabilities: Vec<&BgpCapability>,
-) -> (Vec<AddPathCapability>, Vec<Vec<MultipleLabel>>) {
-    let add_path_caps = capabilities
-        .iter()
-        .flat_map(|cap| {
-            if let BgpCapability::AddPath(add_path) = cap {
-                Some(add_path)
-            } else {
-                None
-            }
-        })
-        .cloned()
-        .collect::<Vec<AddPathCapability>>();
-    let multiple_labels_caps = capabilities
-        .iter()
-        .flat_map(|cap| {
-            if let BgpCapability::MultipleLabels(value) = cap {
-                Some(value)
-            } else {
-                None
-            }
-        })
-        .cloned()
-        .collect::<Vec<Vec<MultipleLabel>>>();
-    (add_path_caps, multiple_labels_caps)
+impl Default for BmpCodec {
+    fn default() -> Self {
+        Self::new(BMP_MESSAGE_DEFAULT_MAX_LENGTH)
+    }
 }
 
 impl BmpCodec {
+    pub fn new(max_message_length: usize) -> Self {
+        Self {
+            max_message_length,
+            discard: 0,
+            ctx: BmpParsingContext::default(),
+        }
+    }
+
+    pub const fn max_message_length(&self) -> usize {
+        self.max_message_length
+    }
+
+    pub fn set_max_message_length(&mut self, max_message_length: usize) {
+        self.max_message_length = max_message_length;
+    }
+
+    pub const fn parsing_ctx(&self) -> &BmpParsingContext {
+        &self.ctx
+    }
+
     pub fn update_parsing_ctx(&mut self, msg: &BmpMessage) {
         self.ctx.update(msg)
     }
@@ -98,66 +112,13 @@ impl BmpParsingContext {
         match msg {
             BmpMessage::V3(value) => match value {
                 BmpMessageValue::PeerDownNotification(peer_down) => {
-                    let peer_key = PeerKey::from_peer_header(peer_down.peer_header());
-                    self.remove(&peer_key);
+                    self.peer_down(peer_down.peer_header());
                 }
                 BmpMessageValue::Termination(termination) => {
-                    let peer_key = PeerKey::from_peer_header(termination.peer_header());
-                    self.remove(&peer_key);
+                    self.peer_down(termination.peer_header());
                 }
                 BmpMessageValue::PeerUpNotification(peer_up) => {
-                    if let BgpMessage::Open(open) = peer_up.sent_message() {
-                        let capabilities = open.capabilities();
-                        let (add_path_caps, multiple_labels_caps) = get_caps(capabilities);
-                        let peer_key = PeerKey::from_peer_header(peer_up.peer_header());
-                        let bgp_ctx = self.entry(peer_key).or_default();
-                        bgp_ctx.add_path_mut().clear();
-                        bgp_ctx.multiple_labels_mut().clear();
-                        for add_path in add_path_caps {
-                            for add_path_family in add_path.address_families() {
-                                bgp_ctx.add_path_mut().insert(
-                                    add_path_family.address_type(),
-                                    add_path_family.receive(),
-                                );
-                            }
-                        }
-                        for labels in multiple_labels_caps {
-                            for label in labels {
-                                bgp_ctx
-                                    .multiple_labels_mut()
-                                    .insert(label.address_type(), label.count());
-                            }
-                        }
-                    }
-                    if let BgpMessage::Open(open) = peer_up.received_message() {
-                        let capabilities = open.capabilities();
-                        let (add_path_caps, multiple_labels_caps) = get_caps(capabilities);
-                        let peer_key = PeerKey::new(
-                            peer_up.peer_header().address(),
-                            peer_up.peer_header().peer_type(),
-                            peer_up.peer_header().rd(),
-                            peer_up.peer_header().peer_as(),
-                            open.bgp_id(),
-                        );
-                        let bgp_ctx = self.entry(peer_key).or_default();
-                        bgp_ctx.add_path_mut().clear();
-                        bgp_ctx.multiple_labels_mut().clear();
-                        for add_path in add_path_caps {
-                            for add_path_family in add_path.address_families() {
-                                bgp_ctx.add_path_mut().insert(
-                                    add_path_family.address_type(),
-                                    add_path_family.receive(),
-                                );
-                            }
-                        }
-                        for multiple_labels in multiple_labels_caps {
-                            for label in multiple_labels {
-                                bgp_ctx
-                                    .multiple_labels_mut()
-                                    .insert(label.address_type(), label.count());
-                            }
-                        }
-                    }
+                    self.peer_up(peer_up);
                 }
                 _ => {}
             },
@@ -169,10 +130,7 @@ impl Encoder<BmpMessage> for BmpCodec {
     type Error = BmpMessageWritingError;
 
     fn encode(&mut self, bmp_msg: BmpMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        dst.reserve(bmp_msg.len());
-        let mut writer = dst.writer();
-        bmp_msg.write(&mut writer)?;
-        Ok(())
+        bmp_msg.write_into(dst)
     }
 }
 
@@ -181,55 +139,73 @@ impl Decoder for BmpCodec {
     type Error = BmpCodecDecoderError;
 
     fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
-        if self.in_message || buf.len() >= BMP_MESSAGE_MIN_LENGTH {
-            let version: u8 = buf[0];
-            // Fail early if the version is invalid
-            if let Err(e) = BmpVersion::try_from(version) {
-                buf.advance(1);
-                return Err(BmpCodecDecoderError::BmpMessageParsingError(
-                    BmpMessageParsingError::UndefinedBmpVersion(e),
-                ));
+        // Skip the remaining of a message that was too large
+        if self.discard > 0 {
+            let skip = self.discard.min(buf.len());
+            buf.advance(skip);
+            self.discard -= skip;
+            if self.discard > 0 {
+                return Ok(None);
             }
-            // Read the length, starting form after the version
-            let length = NetworkEndian::read_u32(&buf[1..BMP_MESSAGE_MIN_LENGTH]) as usize;
-            if buf.len() < length {
-                // We still didn't read all the bytes for the message yet
-                self.in_message = true;
-                Ok(None)
-            } else {
-                self.in_message = false;
-                let msg = match BmpMessage::from_wire(Span::new(buf), &mut self.ctx) {
-                    Ok((span, msg)) => {
-                        self.update_parsing_ctx(&msg);
-                        buf.advance(span.location_offset());
-                        msg
-                    }
-                    Err(error) => {
-                        let err = match error {
-                            nom::Err::Incomplete(needed) => {
-                                let needed = match needed {
-                                    Needed::Unknown => None,
-                                    Needed::Size(size) => Some(size.get()),
-                                };
-                                BmpCodecDecoderError::Incomplete(needed)
-                            }
-                            nom::Err::Error(error) | nom::Err::Failure(error) => {
-                                BmpCodecDecoderError::BmpMessageParsingError(error.error().clone())
-                            }
+        }
+        if buf.len() < BMP_MESSAGE_MIN_LENGTH {
+            // We don't have enough data yet to start processing
+            return Ok(None);
+        }
+        let version: u8 = buf[0];
+        // Fail early if the version is invalid
+        if let Err(e) = BmpVersion::try_from(version) {
+            buf.advance(1);
+            return Err(BmpCodecDecoderError::BmpMessageParsingError(
+                BmpMessageParsingError::UndefinedBmpVersion(e),
+            ));
+        }
+        // Read the length, starting form after the version
+        let length = NetworkEndian::read_u32(&buf[1..BMP_MESSAGE_MIN_LENGTH]) as usize;
+        if length > self.max_message_length {
+            let skip = length.min(buf.len());
+            buf.advance(skip);
+            self.discard = length - skip;
+            return Err(BmpCodecDecoderError::MessageTooLarge {
+                length,
+                max_length: self.max_message_length,
+            });
+        }
+        if buf.len() < length {
+            // We still didn't read all the bytes for the message yet, reserve the space for
+            // the rest of it to avoid multiple reallocations while reading
+            buf.reserve(length - buf.len());
+            return Ok(None);
+        }
+        // Parse only the current message, invalid lengths less than the min length are
+        // reported by the parser
+        let frame_length = length.max(BMP_MESSAGE_MIN_LENGTH);
+        // Take the whole message out of the buffer before parsing, so we don't get stuck
+        // on an error value.
+        // Unfortunately, BMP doesn't have synchronization values like in BGP
+        // to understand we are in a new message.
+        let frame = buf.split_to(frame_length);
+        let result = BmpMessage::from_wire(Span::new(&frame), &mut self.ctx);
+        match result {
+            Ok((_, msg)) => {
+                self.update_parsing_ctx(&msg);
+                Ok(Some(msg))
+            }
+            Err(error) => {
+                let err = match error {
+                    nom::Err::Incomplete(needed) => {
+                        let needed = match needed {
+                            Needed::Unknown => None,
+                            Needed::Size(size) => Some(size.get()),
                         };
-                        // Make sure we advance the buffer far enough, so we don't get stuck on an
-                        // error value.
-                        // Unfortunately, BMP doesn't have synchronization values like in BGP
-                        // to understand we are in a new message.
-                        buf.advance(if length < 5 { 5 } else { length });
-                        return Err(err);
+                        BmpCodecDecoderError::Incomplete(needed)
+                    }
+                    nom::Err::Error(error) | nom::Err::Failure(error) => {
+                        BmpCodecDecoderError::BmpMessageParsingError(error.error().clone())
                     }
                 };
-                Ok(Some(msg))
+                Err(err)
             }
-        } else {
-            // We don't have enough data yet to start processing
-            Ok(None)
         }
     }
 }
@@ -238,12 +214,17 @@ impl Decoder for BmpCodec {
 mod tests {
     use super::*;
     use crate::*;
+    use ipnet::Ipv4Net;
     use netgauze_bgp_pkt::{
         capabilities::{
-            ExtendedNextHopEncoding, ExtendedNextHopEncodingCapability, FourOctetAsCapability,
+            AddPathAddressFamily, AddPathCapability, BgpCapability, ExtendedNextHopEncoding,
+            ExtendedNextHopEncodingCapability, FourOctetAsCapability,
             MultiProtocolExtensionsCapability,
         },
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
         open::{BgpOpenMessage, BgpOpenMessageParameter},
+        update::BgpUpdateMessage,
+        BgpMessage,
     };
     use netgauze_iana::address_family::AddressFamily;
     use std::{net::Ipv6Addr, str::FromStr};
@@ -272,6 +253,66 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_codec_incremental() -> Result<(), BmpMessageWritingError> {
+        let msg = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemDescription("test11".to_string()),
+            InitiationInformation::SystemName("PE2".to_string()),
+        ])));
+        let mut codec = BmpCodec::default();
+        let mut wire = BytesMut::new();
+        codec.encode(msg.clone(), &mut wire)?;
+        codec.encode(msg.clone(), &mut wire)?;
+
+        // Feed the two messages one octet at a time
+        let mut buf = BytesMut::new();
+        let mut decoded = vec![];
+        for byte in &wire {
+            buf.extend_from_slice(&[*byte]);
+            if let Some(value) = codec.decode(&mut buf).unwrap() {
+                decoded.push(value);
+            }
+        }
+        assert_eq!(decoded, vec![msg.clone(), msg]);
+        assert!(buf.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_codec_max_message_length() -> Result<(), BmpMessageWritingError> {
+        let large = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemDescription("a".repeat(100)),
+        ])));
+        let small = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("PE2".to_string()),
+        ])));
+        let mut codec = BmpCodec::new(50);
+        assert_eq!(codec.max_message_length(), 50);
+        let mut wire = BytesMut::new();
+        codec.encode(large.clone(), &mut wire)?;
+        codec.encode(small.clone(), &mut wire)?;
+
+        // Only part of the large message is available when it's rejected, the rest
+        // is skipped as it arrives
+        let mut buf = BytesMut::from(&wire[..20]);
+        assert_eq!(
+            codec.decode(&mut buf),
+            Err(BmpCodecDecoderError::MessageTooLarge {
+                length: large.len(),
+                max_length: 50,
+            })
+        );
+        buf.extend_from_slice(&wire[20..]);
+        assert_eq!(codec.decode(&mut buf), Ok(Some(small)));
+        assert!(buf.is_empty());
+
+        codec.set_max_message_length(BMP_MESSAGE_DEFAULT_MAX_LENGTH);
+        let mut buf = BytesMut::new();
+        codec.encode(large.clone(), &mut buf)?;
+        assert_eq!(codec.decode(&mut buf), Ok(Some(large)));
+        Ok(())
+    }
+
     #[test]
     fn test_peer_key_add_remove() -> Result<(), BmpMessageWritingError> {
         let peer_header = PeerHeader::new(
@@ -404,4 +445,90 @@ mod tests {
         assert!(!codec.ctx.contains_key(&peer_key));
         Ok(())
     }
+
+    #[test]
+    fn test_add_path_post_policy_route_monitoring() -> Result<(), BmpMessageWritingError> {
+        let peer_type = |post_policy, adj_rib_out| BmpPeerType::GlobalInstancePeer {
+            ipv6: false,
+            post_policy,
+            asn2: false,
+            adj_rib_out,
+        };
+        let peer_header = |peer_type| {
+            PeerHeader::new(
+                peer_type,
+                None,
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+                65001,
+                Ipv4Addr::new(192, 0, 2, 2),
+                None,
+            )
+        };
+        let open = |bgp_id, send, receive| {
+            BgpMessage::Open(BgpOpenMessage::new(
+                23456,
+                180,
+                bgp_id,
+                vec![BgpOpenMessageParameter::Capabilities(vec![
+                    BgpCapability::FourOctetAs(FourOctetAsCapability::new(65001)),
+                    BgpCapability::AddPath(AddPathCapability::new(vec![
+                        AddPathAddressFamily::new(AddressType::Ipv4Unicast, send, receive),
+                    ])),
+                ])],
+            ))
+        };
+        // The monitored router only receives path identifiers, while the peer can do
+        // both
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header(peer_type(false, false)),
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                Some(179),
+                Some(50000),
+                open(Ipv4Addr::new(192, 0, 2, 1), false, true),
+                open(Ipv4Addr::new(192, 0, 2, 2), true, true),
+                vec![],
+            )
+            .unwrap(),
+        ));
+        let route_monitoring = |peer_type, path_id| {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+                RouteMonitoringMessage::build(
+                    peer_header(peer_type),
+                    BgpMessage::Update(BgpUpdateMessage::new(
+                        vec![Ipv4UnicastAddress::new(
+                            path_id,
+                            Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap())
+                                .unwrap(),
+                        )],
+                        vec![],
+                        vec![],
+                    )),
+                )
+                .unwrap(),
+            ))
+        };
+        let post_policy = route_monitoring(peer_type(true, false), Some(7));
+        let adj_rib_out = route_monitoring(peer_type(true, true), None);
+
+        let mut codec = BmpCodec::default();
+        codec.update_parsing_ctx(&peer_up);
+        let adj_rib_in_ctx = codec
+            .parsing_ctx()
+            .get(&PeerKey::from_peer_header(&peer_header(peer_type(
+                false, false,
+            ))))
+            .unwrap();
+        assert_eq!(
+            adj_rib_in_ctx.add_path().get(&AddressType::Ipv4Unicast),
+            Some(&true)
+        );
+
+        let mut buf = BytesMut::new();
+        codec.encode(post_policy.clone(), &mut buf)?;
+        codec.encode(adj_rib_out.clone(), &mut buf)?;
+        assert_eq!(codec.decode(&mut buf), Ok(Some(post_policy)));
+        assert_eq!(codec.decode(&mut buf), Ok(Some(adj_rib_out)));
+        Ok(())
+    }
 }
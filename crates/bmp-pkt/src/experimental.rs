@@ -0,0 +1,224 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Application defined decoding of experimental BMP payloads.
+//!
+//! The BMP deserializer keeps the payload of the experimental message types,
+//! Peer Down reason codes, and the experimental and unknown statistics types
+//! as raw bytes. [`BmpExperimentalDecoders`] lets an application register a
+//! decoder per code to turn these payloads into its own typed values.
+//!
+//! ```
+//! use netgauze_bmp_pkt::{experimental::BmpExperimentalDecoders, iana::BmpMessageType};
+//! use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue};
+//!
+//! let mut decoders = BmpExperimentalDecoders::<u32, String>::new();
+//! decoders.register_message(BmpMessageType::Experimental251, |data| {
+//!     <[u8; 4]>::try_from(data)
+//!         .map(u32::from_be_bytes)
+//!         .map_err(|err| err.to_string())
+//! });
+//! let msg = BmpMessage::V3(BmpMessageValue::Experimental251(vec![0, 0, 0, 1]));
+//! assert_eq!(decoders.decode(&msg), vec![Ok(1)]);
+//! ```
+
+use std::{collections::HashMap, fmt};
+
+use crate::{
+    iana::{BmpMessageType, PeerDownReasonCode},
+    BmpMessage, BmpMessageValue, PeerDownNotificationReason, StatisticsCounter,
+};
+
+type Decoder<T, E> = Box<dyn Fn(&[u8]) -> Result<T, E> + Send + Sync>;
+
+/// Registry of the application decoders of experimental BMP payloads, see
+/// the [module docs](self)
+pub struct BmpExperimentalDecoders<T, E> {
+    messages: HashMap<u8, Decoder<T, E>>,
+    peer_down_reasons: HashMap<u8, Decoder<T, E>>,
+    statistics: HashMap<u16, Decoder<T, E>>,
+}
+
+impl<T, E> fmt::Debug for BmpExperimentalDecoders<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BmpExperimentalDecoders")
+            .field("messages", &self.messages.keys())
+            .field("peer_down_reasons", &self.peer_down_reasons.keys())
+            .field("statistics", &self.statistics.keys())
+            .finish()
+    }
+}
+
+impl<T, E> Default for BmpExperimentalDecoders<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E> BmpExperimentalDecoders<T, E> {
+    pub fn new() -> Self {
+        Self {
+            messages: HashMap::new(),
+            peer_down_reasons: HashMap::new(),
+            statistics: HashMap::new(),
+        }
+    }
+
+    /// Register the decoder of the body of an experimental message type,
+    /// replacing any previously registered decoder for the same type
+    pub fn register_message<F>(&mut self, msg_type: BmpMessageType, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+    {
+        self.messages.insert(msg_type.into(), Box::new(decoder));
+    }
+
+    /// Register the decoder of the data of an experimental Peer Down reason
+    /// code, replacing any previously registered decoder for the same code
+    pub fn register_peer_down_reason<F>(&mut self, reason: PeerDownReasonCode, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+    {
+        self.peer_down_reasons
+            .insert(reason.into(), Box::new(decoder));
+    }
+
+    /// Register the decoder of an experimental or unknown statistics type.
+    /// `stat_type` is the IANA code, so the codes that are not known to
+    /// [`crate::iana::BmpStatisticsType`] can be registered as well.
+    pub fn register_statistics<F>(&mut self, stat_type: u16, decoder: F)
+    where
+        F: Fn(&[u8]) -> Result<T, E> + Send + Sync + 'static,
+    {
+        self.statistics.insert(stat_type, Box::new(decoder));
+    }
+
+    /// Decode the body of an experimental message, [`None`] if the message is
+    /// not experimental or no decoder is registered for its type
+    pub fn decode_message(&self, msg: &BmpMessageValue) -> Option<Result<T, E>> {
+        let data = msg.experimental_data()?;
+        let decoder = self.messages.get(&u8::from(msg.get_type()))?;
+        Some(decoder(data))
+    }
+
+    /// Decode the data of an experimental Peer Down reason, [`None`] if the
+    /// reason is not experimental or no decoder is registered for its code
+    pub fn decode_peer_down_reason(
+        &self,
+        reason: &PeerDownNotificationReason,
+    ) -> Option<Result<T, E>> {
+        let data = reason.experimental_data()?;
+        let decoder = self.peer_down_reasons.get(&u8::from(reason.get_type()))?;
+        Some(decoder(data))
+    }
+
+    /// Decode an experimental or unknown statistics counter, [`None`] if the
+    /// counter has a well-known type or no decoder is registered for its code
+    pub fn decode_statistics_counter(&self, counter: &StatisticsCounter) -> Option<Result<T, E>> {
+        let data = counter.raw_value()?;
+        let code = counter
+            .get_type()
+            .map(u16::from)
+            .unwrap_or_else(|code| code);
+        let decoder = self.statistics.get(&code)?;
+        Some(decoder(data))
+    }
+
+    /// Decode all the experimental payloads carried in a BMP message that
+    /// have a registered decoder
+    pub fn decode(&self, msg: &BmpMessage) -> Vec<Result<T, E>> {
+        let BmpMessage::V3(value) = msg;
+        match value {
+            BmpMessageValue::PeerDownNotification(peer_down) => self
+                .decode_peer_down_reason(peer_down.reason())
+                .into_iter()
+                .collect(),
+            BmpMessageValue::StatisticsReport(stats) => stats
+                .counters()
+                .iter()
+                .filter_map(|counter| self.decode_statistics_counter(counter))
+                .collect(),
+            value => self.decode_message(value).into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BmpPeerType, CounterU32, PeerDownNotificationMessage, PeerHeader, StatisticsReportMessage,
+    };
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_experimental_decoders() {
+        let mut decoders = BmpExperimentalDecoders::<String, ()>::new();
+        decoders.register_message(BmpMessageType::Experimental252, |data| {
+            String::from_utf8(data.to_vec()).map_err(|_| ())
+        });
+        decoders.register_peer_down_reason(PeerDownReasonCode::Experimental251, |data| {
+            Ok(format!("reason {}", data.len()))
+        });
+        decoders.register_statistics(100, |data| Ok(format!("stats {data:?}")));
+
+        let message = BmpMessageValue::Experimental252(b"hello".to_vec());
+        let not_registered = BmpMessageValue::Experimental253(b"hello".to_vec());
+        let invalid = BmpMessageValue::Experimental252(vec![0xff]);
+        assert_eq!(
+            decoders.decode_message(&message),
+            Some(Ok("hello".to_string()))
+        );
+        assert_eq!(decoders.decode_message(&not_registered), None);
+        assert_eq!(decoders.decode_message(&invalid), Some(Err(())));
+
+        let peer_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            None,
+            65000,
+            Ipv4Addr::new(192, 0, 2, 1),
+            None,
+        );
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header.clone(),
+                PeerDownNotificationReason::Experimental251(vec![1, 2, 3]),
+            )
+            .unwrap(),
+        ));
+        assert_eq!(
+            decoders.decode(&peer_down),
+            vec![Ok("reason 3".to_string())]
+        );
+
+        let stats = BmpMessage::V3(BmpMessageValue::StatisticsReport(
+            StatisticsReportMessage::new(
+                peer_header,
+                vec![
+                    StatisticsCounter::NumberOfDuplicateWithdraws(CounterU32::new(1)),
+                    StatisticsCounter::Unknown(100, vec![7]),
+                    StatisticsCounter::Unknown(101, vec![8]),
+                ],
+            ),
+        ));
+        assert_eq!(decoders.decode(&stats), vec![Ok("stats [7]".to_string())]);
+    }
+}
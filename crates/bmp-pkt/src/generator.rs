@@ -0,0 +1,473 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Router side generation of BMP messages.
+//!
+//! [`BmpGenerator`] emulates a monitored router: it keeps track of the peers
+//! that are up and builds the messages of a BMP session (Initiation, Peer Up,
+//! Route Monitoring, Statistics Report, Peer Down, and Termination) with
+//! consistent per-peer headers and timestamps. It's meant to synthesize BMP
+//! feeds for integration tests and traffic generators.
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr},
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use netgauze_bgp_pkt::{
+    capabilities::{negotiate, BgpCapability},
+    nlri::RouteDistinguisher,
+    open::BgpOpenMessage,
+    update::BgpUpdateMessage,
+    BgpMessage,
+};
+use netgauze_parse_utils::WritablePdu;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    wire::serializer::BmpMessageWritingError, BmpMessage, BmpMessageValue, BmpPeerType,
+    InitiationInformation, InitiationMessage, PeerDownNotificationMessage,
+    PeerDownNotificationMessageError, PeerDownNotificationReason, PeerHeader, PeerKey,
+    PeerUpNotificationMessage, PeerUpNotificationMessageError, RouteMonitoringMessage,
+    RouteMonitoringMessageError, StatisticsCounter, StatisticsReportMessage,
+    TerminationInformation, TerminationMessage,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BmpGeneratorError {
+    /// A Peer Up is generated for a peer that is already up
+    PeerAlreadyUp(PeerKey),
+
+    /// A message is generated for a peer that is not up
+    PeerNotUp(PeerKey),
+
+    PeerUpMessageError(PeerUpNotificationMessageError),
+    PeerDownMessageError(PeerDownNotificationMessageError),
+    RouteMonitoringMessageError(RouteMonitoringMessageError),
+}
+
+/// BGP session between the emulated router and one of its peers
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BmpEmulatedPeer {
+    peer_address: IpAddr,
+    rd: Option<RouteDistinguisher>,
+    local_address: IpAddr,
+    local_port: u16,
+    remote_port: u16,
+    sent_open: BgpOpenMessage,
+    received_open: BgpOpenMessage,
+}
+
+impl BmpEmulatedPeer {
+    /// The peer is a [`BmpPeerType::GlobalInstancePeer`] when `rd` is
+    /// [`None`], otherwise a [`BmpPeerType::RdInstancePeer`]. The peer AS and
+    /// BGP ID are taken from the OPEN message received from the peer.
+    pub const fn new(
+        peer_address: IpAddr,
+        rd: Option<RouteDistinguisher>,
+        local_address: IpAddr,
+        local_port: u16,
+        remote_port: u16,
+        sent_open: BgpOpenMessage,
+        received_open: BgpOpenMessage,
+    ) -> Self {
+        Self {
+            peer_address,
+            rd,
+            local_address,
+            local_port,
+            remote_port,
+            sent_open,
+            received_open,
+        }
+    }
+
+    pub const fn peer_address(&self) -> IpAddr {
+        self.peer_address
+    }
+
+    pub const fn rd(&self) -> Option<RouteDistinguisher> {
+        self.rd
+    }
+
+    pub const fn local_address(&self) -> IpAddr {
+        self.local_address
+    }
+
+    pub const fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    pub const fn remote_port(&self) -> u16 {
+        self.remote_port
+    }
+
+    pub const fn sent_open(&self) -> &BgpOpenMessage {
+        &self.sent_open
+    }
+
+    pub const fn received_open(&self) -> &BgpOpenMessage {
+        &self.received_open
+    }
+
+    /// Key of the peer, as used by [`BmpGenerator`] to refer to it
+    pub fn peer_key(&self) -> PeerKey {
+        PeerKey::new(
+            Some(self.peer_address),
+            self.peer_type(false, false),
+            self.rd,
+            self.received_open.my_asn4(),
+            self.received_open.bgp_id(),
+        )
+    }
+
+    fn peer_type(&self, post_policy: bool, asn2: bool) -> BmpPeerType {
+        let ipv6 = self.peer_address.is_ipv6();
+        match self.rd {
+            None => BmpPeerType::GlobalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out: false,
+            },
+            Some(_) => BmpPeerType::RdInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out: false,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EmulatedPeerState {
+    peer: BmpEmulatedPeer,
+    /// The BGP session negotiated 4-octet AS numbers, the AS_PATH in the
+    /// Route Monitoring messages are then encoded with 4-octet AS numbers.
+    asn4: bool,
+}
+
+/// Generates the messages of one BMP session, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct BmpGenerator {
+    timestamp: DateTime<Utc>,
+    router_as: u32,
+    router_bgp_id: Ipv4Addr,
+    initiation: Vec<InitiationInformation>,
+    peers: HashMap<PeerKey, EmulatedPeerState>,
+}
+
+impl BmpGenerator {
+    /// Create a generator for a router with the given AS and BGP ID, the
+    /// messages are stamped starting from `timestamp`
+    pub fn new(
+        router_as: u32,
+        router_bgp_id: Ipv4Addr,
+        initiation: Vec<InitiationInformation>,
+        timestamp: DateTime<Utc>,
+    ) -> Self {
+        Self {
+            timestamp,
+            router_as,
+            router_bgp_id,
+            initiation,
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Timestamp used in the peer headers of the generated messages
+    pub const fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    pub fn set_timestamp(&mut self, timestamp: DateTime<Utc>) {
+        self.timestamp = timestamp;
+    }
+
+    /// Move the timestamp of the next generated messages forward
+    pub fn advance(&mut self, delta: TimeDelta) {
+        self.timestamp += delta;
+    }
+
+    /// Keys of the peers that are currently up
+    pub fn peers(&self) -> impl Iterator<Item = &PeerKey> {
+        self.peers.keys()
+    }
+
+    pub fn initiation(&self) -> BmpMessage {
+        BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(
+            self.initiation.clone(),
+        )))
+    }
+
+    pub fn peer_up(&mut self, peer: BmpEmulatedPeer) -> Result<BmpMessage, BmpGeneratorError> {
+        let peer_key = peer.peer_key();
+        if self.peers.contains_key(&peer_key) {
+            return Err(BmpGeneratorError::PeerAlreadyUp(peer_key));
+        }
+        let sent_capabilities: Vec<BgpCapability> =
+            peer.sent_open.capabilities().into_iter().cloned().collect();
+        let received_capabilities: Vec<BgpCapability> = peer
+            .received_open
+            .capabilities()
+            .into_iter()
+            .cloned()
+            .collect();
+        let asn4 = negotiate(&sent_capabilities, &received_capabilities).four_octet_as();
+        let peer_up = PeerUpNotificationMessage::build(
+            self.peer_header(&peer, false, !asn4),
+            Some(peer.local_address),
+            Some(peer.local_port),
+            Some(peer.remote_port),
+            BgpMessage::Open(peer.sent_open.clone()),
+            BgpMessage::Open(peer.received_open.clone()),
+            vec![],
+        )
+        .map_err(BmpGeneratorError::PeerUpMessageError)?;
+        self.peers
+            .insert(peer_key, EmulatedPeerState { peer, asn4 });
+        Ok(BmpMessage::V3(BmpMessageValue::PeerUpNotification(peer_up)))
+    }
+
+    /// Route Monitoring message for the pre- or post-policy Adj-RIB-In of a
+    /// peer that is up
+    pub fn route_monitoring(
+        &self,
+        peer_key: &PeerKey,
+        post_policy: bool,
+        update: BgpUpdateMessage,
+    ) -> Result<BmpMessage, BmpGeneratorError> {
+        let state = self.peer_state(peer_key)?;
+        let route_monitoring = RouteMonitoringMessage::build(
+            self.peer_header(&state.peer, post_policy, !state.asn4),
+            BgpMessage::Update(update),
+        )
+        .map_err(BmpGeneratorError::RouteMonitoringMessageError)?;
+        Ok(BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            route_monitoring,
+        )))
+    }
+
+    pub fn statistics_report(
+        &self,
+        peer_key: &PeerKey,
+        counters: Vec<StatisticsCounter>,
+    ) -> Result<BmpMessage, BmpGeneratorError> {
+        let state = self.peer_state(peer_key)?;
+        Ok(BmpMessage::V3(BmpMessageValue::StatisticsReport(
+            StatisticsReportMessage::new(
+                self.peer_header(&state.peer, false, !state.asn4),
+                counters,
+            ),
+        )))
+    }
+
+    pub fn peer_down(
+        &mut self,
+        peer_key: &PeerKey,
+        reason: PeerDownNotificationReason,
+    ) -> Result<BmpMessage, BmpGeneratorError> {
+        let state = self.peer_state(peer_key)?;
+        let peer_down = PeerDownNotificationMessage::build(
+            self.peer_header(&state.peer, false, !state.asn4),
+            reason,
+        )
+        .map_err(BmpGeneratorError::PeerDownMessageError)?;
+        self.peers.remove(peer_key);
+        Ok(BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            peer_down,
+        )))
+    }
+
+    /// Terminate the session, all the peers are considered down afterward
+    pub fn termination(&mut self, information: Vec<TerminationInformation>) -> BmpMessage {
+        self.peers.clear();
+        let peer_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            None,
+            self.router_as,
+            self.router_bgp_id,
+            Some(self.timestamp),
+        );
+        BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header,
+            information,
+        )))
+    }
+
+    /// Serialize a sequence of generated messages into a BMP byte stream
+    pub fn encode(messages: &[BmpMessage]) -> Result<Vec<u8>, BmpMessageWritingError> {
+        let mut buf = Vec::with_capacity(messages.iter().map(|msg| msg.len()).sum());
+        for msg in messages {
+            msg.write(&mut buf)?;
+        }
+        Ok(buf)
+    }
+
+    fn peer_state(&self, peer_key: &PeerKey) -> Result<&EmulatedPeerState, BmpGeneratorError> {
+        self.peers
+            .get(peer_key)
+            .ok_or(BmpGeneratorError::PeerNotUp(*peer_key))
+    }
+
+    fn peer_header(&self, peer: &BmpEmulatedPeer, post_policy: bool, asn2: bool) -> PeerHeader {
+        PeerHeader::new(
+            peer.peer_type(post_policy, asn2),
+            peer.rd,
+            Some(peer.peer_address),
+            peer.received_open.my_asn4(),
+            peer.received_open.bgp_id(),
+            Some(self.timestamp),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        session::BmpSessionTracker, wire::deserializer::BmpParsingContext, CounterU32,
+        InitiationInformation,
+    };
+    use chrono::TimeZone;
+    use ipnet::Ipv4Net;
+    use netgauze_bgp_pkt::{
+        capabilities::{AddPathAddressFamily, AddPathCapability, FourOctetAsCapability},
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        open::BgpOpenMessageParameter,
+    };
+    use netgauze_iana::address_family::AddressType;
+    use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+    use std::str::FromStr;
+
+    fn open(my_as: u32, bgp_id: Ipv4Addr) -> BgpOpenMessage {
+        BgpOpenMessage::new(
+            23456,
+            180,
+            bgp_id,
+            vec![BgpOpenMessageParameter::Capabilities(vec![
+                BgpCapability::FourOctetAs(FourOctetAsCapability::new(my_as)),
+                BgpCapability::AddPath(AddPathCapability::new(vec![AddPathAddressFamily::new(
+                    AddressType::Ipv4Unicast,
+                    true,
+                    true,
+                )])),
+            ])],
+        )
+    }
+
+    #[test]
+    fn test_generator() {
+        let timestamp = Utc.timestamp_opt(1700000000, 0).unwrap();
+        let mut generator = BmpGenerator::new(
+            65000,
+            Ipv4Addr::new(192, 0, 2, 1),
+            vec![InitiationInformation::SystemName("PE1".to_string())],
+            timestamp,
+        );
+        let peer = BmpEmulatedPeer::new(
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            None,
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            179,
+            50000,
+            open(65000, Ipv4Addr::new(192, 0, 2, 1)),
+            open(4200000000, Ipv4Addr::new(192, 0, 2, 2)),
+        );
+        let peer_key = peer.peer_key();
+        assert_eq!(peer_key.asn(), 4200000000);
+        assert_eq!(
+            generator.route_monitoring(
+                &peer_key,
+                false,
+                BgpUpdateMessage::new(vec![], vec![], vec![])
+            ),
+            Err(BmpGeneratorError::PeerNotUp(peer_key))
+        );
+
+        let update = BgpUpdateMessage::new(
+            vec![],
+            vec![],
+            vec![Ipv4UnicastAddress::new(
+                Some(3),
+                Ipv4Unicast::from_net(Ipv4Net::from_str("198.51.100.0/24").unwrap()).unwrap(),
+            )],
+        );
+        let mut messages = vec![generator.initiation()];
+        messages.push(generator.peer_up(peer.clone()).unwrap());
+        assert_eq!(
+            generator.peer_up(peer),
+            Err(BmpGeneratorError::PeerAlreadyUp(peer_key))
+        );
+        generator.advance(TimeDelta::seconds(1));
+        messages.push(
+            generator
+                .route_monitoring(&peer_key, true, update.clone())
+                .unwrap(),
+        );
+        messages.push(
+            generator
+                .statistics_report(
+                    &peer_key,
+                    vec![StatisticsCounter::NumberOfPrefixesRejectedByInboundPolicy(
+                        CounterU32::new(1),
+                    )],
+                )
+                .unwrap(),
+        );
+        messages.push(
+            generator
+                .peer_down(
+                    &peer_key,
+                    PeerDownNotificationReason::RemoteSystemClosedNoData,
+                )
+                .unwrap(),
+        );
+        messages.push(generator.termination(vec![]));
+        assert_eq!(generator.peers().count(), 0);
+
+        let route_monitoring = match &messages[2] {
+            BmpMessage::V3(BmpMessageValue::RouteMonitoring(msg)) => msg,
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        assert_eq!(
+            route_monitoring.peer_header().timestamp(),
+            Some(&(timestamp + TimeDelta::seconds(1)))
+        );
+        assert!(route_monitoring.peer_header().is_asn4());
+
+        // The generated stream is parsed back with the capabilities of the Peer Up
+        let wire = BmpGenerator::encode(&messages).unwrap();
+        let mut buf = Span::new(&wire);
+        let mut tracker = BmpSessionTracker::new();
+        let mut ctx = BmpParsingContext::default();
+        for expected in &messages {
+            let (tmp, msg) = BmpMessage::from_wire(buf, &mut ctx).unwrap();
+            assert_eq!(&msg, expected);
+            assert_eq!(tracker.update(&msg), Ok(()));
+            ctx = tracker.bmp_parsing_ctx();
+            buf = tmp;
+        }
+        assert!(buf.is_empty());
+    }
+}
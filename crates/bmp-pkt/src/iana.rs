@@ -16,7 +16,7 @@
 //! Contains BMP codes that are registered at IANA [BGP Monitoring Protocol (BMP) Parameters](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml)
 
 use serde::{Deserialize, Serialize};
-use strum_macros::{Display, FromRepr};
+use strum_macros::{Display, FromRepr, IntoStaticStr};
 
 /// Corresponds to the V flag. If set indicates that the Peer address is an IPv6
 /// address. See [RFC7854](https://datatracker.ietf.org/doc/html/rfc7854)
@@ -295,6 +295,68 @@ impl TryFrom<u8> for PeerDownReasonCode {
     }
 }
 
+/// BGP Finite State Machine events as defined in
+/// [RFC4271 Section 8.1](https://datatracker.ietf.org/doc/html/rfc4271#section-8.1),
+/// carried by [`PeerDownReasonCode::LocalSystemClosedFsmEventFollows`].
+///
+/// The value `0` is used to indicate that no relevant event code is defined
+/// and hence is not part of this enum.
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub enum BgpFsmEventCode {
+    ManualStart = 1,
+    ManualStop = 2,
+    AutomaticStart = 3,
+    ManualStartWithPassiveTcpEstablishment = 4,
+    AutomaticStartWithPassiveTcpEstablishment = 5,
+    AutomaticStartWithDampPeerOscillations = 6,
+    AutomaticStartWithDampPeerOscillationsAndPassiveTcpEstablishment = 7,
+    AutomaticStop = 8,
+    ConnectRetryTimerExpires = 9,
+    HoldTimerExpires = 10,
+    KeepaliveTimerExpires = 11,
+    DelayOpenTimerExpires = 12,
+    IdleHoldTimerExpires = 13,
+    TcpConnectionValid = 14,
+    TcpCrInvalid = 15,
+    TcpCrAcked = 16,
+    TcpConnectionConfirmed = 17,
+    TcpConnectionFails = 18,
+    BgpOpen = 19,
+    BgpOpenWithDelayOpenTimerRunning = 20,
+    BgpHeaderErr = 21,
+    BgpOpenMsgErr = 22,
+    OpenCollisionDump = 23,
+    NotifMsgVerErr = 24,
+    NotifMsg = 25,
+    KeepAliveMsg = 26,
+    UpdateMsg = 27,
+    UpdateMsgErr = 28,
+}
+
+/// BGP FSM event code is not one of [`BgpFsmEventCode`], the carried value is
+/// the undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedBgpFsmEventCode(pub u16);
+
+impl From<BgpFsmEventCode> for u16 {
+    fn from(value: BgpFsmEventCode) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for BgpFsmEventCode {
+    type Error = UndefinedBgpFsmEventCode;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedBgpFsmEventCode(value)),
+        }
+    }
+}
+
 /// [BMP Route Mirroring TLVs](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#route-mirroring-tlvs)
 #[repr(u16)]
 #[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -366,34 +428,107 @@ impl TryFrom<u16> for RouteMirroringInformation {
 }
 
 /// [BMP Statistics Types](https://www.iana.org/assignments/bmp-parameters/bmp-parameters.xhtml#statistics-types)
+///
+/// The [`core::fmt::Display`] representation is the IANA description of the
+/// statistics type.
 #[repr(u16)]
-#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(
+    Display, FromRepr, IntoStaticStr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize,
+)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpStatisticsType {
+    #[strum(to_string = "Number of prefixes rejected by inbound policy")]
     NumberOfPrefixesRejectedByInboundPolicy = 0,
+    #[strum(to_string = "Number of (known) duplicate prefix advertisements")]
     NumberOfDuplicatePrefixAdvertisements = 1,
+    #[strum(to_string = "Number of (known) duplicate withdraws")]
     NumberOfDuplicateWithdraws = 2,
+    #[strum(to_string = "Number of updates invalidated due to CLUSTER_LIST loop")]
     NumberOfUpdatesInvalidatedDueToClusterListLoop = 3,
+    #[strum(to_string = "Number of updates invalidated due to AS_PATH loop")]
     NumberOfUpdatesInvalidatedDueToAsPathLoop = 4,
+    #[strum(to_string = "Number of updates invalidated due to ORIGINATOR_ID")]
     NumberOfUpdatesInvalidatedDueToOriginatorId = 5,
+    #[strum(
+        to_string = "Number of updates invalidated due to a loop found in AS_CONFED_SEQUENCE or AS_CONFED_SET"
+    )]
     NumberOfUpdatesInvalidatedDueToAsConfederationLoop = 6,
+    #[strum(to_string = "Number of routes in Adj-RIBs-In")]
     NumberOfRoutesInAdjRibIn = 7,
+    #[strum(to_string = "Number of routes in Loc-RIB")]
     NumberOfRoutesInLocRib = 8,
+    #[strum(to_string = "Number of routes in per-AFI/SAFI Adj-RIB-In")]
     NumberOfRoutesInPerAfiSafiAdjRibIn = 9,
+    #[strum(to_string = "Number of routes in per-AFI/SAFI Loc-RIB")]
     NumberOfRoutesInPerAfiSafiLocRib = 10,
+    #[strum(to_string = "Number of updates subjected to treat-as-withdraw")]
     NumberOfUpdatesSubjectedToTreatAsWithdraw = 11,
+    #[strum(to_string = "Number of prefixes subjected to treat-as-withdraw")]
     NumberOfPrefixesSubjectedToTreatAsWithdraw = 12,
+    #[strum(to_string = "Number of duplicate update messages received")]
     NumberOfDuplicateUpdateMessagesReceived = 13,
+    #[strum(to_string = "Number of routes in pre-policy Adj-RIB-Out")]
     NumberOfRoutesInPrePolicyAdjRibOut = 14,
+    #[strum(to_string = "Number of routes in post-policy Adj-RIB-Out")]
     NumberOfRoutesInPostPolicyAdjRibOut = 15,
+    #[strum(to_string = "Number of routes in per-AFI/SAFI pre-policy Adj-RIB-Out")]
     NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut = 16,
+    #[strum(to_string = "Number of routes in per-AFI/SAFI post-policy Adj-RIB-Out")]
     NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut = 17,
+    #[strum(to_string = "Experimental")]
     Experimental65531 = 65531,
+    #[strum(to_string = "Experimental")]
     Experimental65532 = 65532,
+    #[strum(to_string = "Experimental")]
     Experimental65533 = 65533,
+    #[strum(to_string = "Experimental")]
     Experimental65534 = 65534,
 }
 
+impl BmpStatisticsType {
+    /// IANA description of the statistics type
+    pub fn description(&self) -> &'static str {
+        self.into()
+    }
+
+    /// The statistics value is a 64-bit gauge, otherwise it's a 32-bit
+    /// counter (or opaque data for the experimental types)
+    pub const fn is_gauge(&self) -> bool {
+        matches!(
+            self,
+            Self::NumberOfRoutesInAdjRibIn
+                | Self::NumberOfRoutesInLocRib
+                | Self::NumberOfRoutesInPerAfiSafiAdjRibIn
+                | Self::NumberOfRoutesInPerAfiSafiLocRib
+                | Self::NumberOfRoutesInPrePolicyAdjRibOut
+                | Self::NumberOfRoutesInPostPolicyAdjRibOut
+                | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut
+                | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut
+        )
+    }
+
+    /// The statistics value is prefixed by an AFI/SAFI
+    pub const fn is_per_afi_safi(&self) -> bool {
+        matches!(
+            self,
+            Self::NumberOfRoutesInPerAfiSafiAdjRibIn
+                | Self::NumberOfRoutesInPerAfiSafiLocRib
+                | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut
+                | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut
+        )
+    }
+
+    pub const fn is_experimental(&self) -> bool {
+        matches!(
+            self,
+            Self::Experimental65531
+                | Self::Experimental65532
+                | Self::Experimental65533
+                | Self::Experimental65534
+        )
+    }
+}
+
 /// Code is not one of [`BmpStatisticsType`], the carried value is the undefined
 /// code.
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
@@ -513,6 +648,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bgp_fsm_event_code() {
+        let undefined_code = 0;
+        let defined_code = 10;
+        let defined_value = BgpFsmEventCode::try_from(defined_code);
+        let undefined = BgpFsmEventCode::try_from(undefined_code);
+        let defined_code_u16: u16 = BgpFsmEventCode::HoldTimerExpires.into();
+        assert_eq!(defined_value, Ok(BgpFsmEventCode::HoldTimerExpires));
+        assert_eq!(defined_code_u16, defined_code);
+        assert_eq!(undefined, Err(UndefinedBgpFsmEventCode(undefined_code)));
+        assert_eq!(
+            BgpFsmEventCode::try_from(28),
+            Ok(BgpFsmEventCode::UpdateMsgErr)
+        );
+        assert_eq!(
+            BgpFsmEventCode::try_from(29),
+            Err(UndefinedBgpFsmEventCode(29))
+        );
+    }
+
     #[test]
     fn test_route_mirroring_information() {
         let undefined_code = 255;
@@ -542,4 +697,25 @@ mod tests {
         assert_eq!(defined_code_u16, defined_code);
         assert_eq!(undefined, Err(UndefinedBmpStatisticsType(undefined_code)));
     }
+
+    #[test]
+    fn test_bmp_statistics_type_classification() {
+        let adj_rib_in = BmpStatisticsType::NumberOfRoutesInPerAfiSafiAdjRibIn;
+        let as_path_loop = BmpStatisticsType::NumberOfUpdatesInvalidatedDueToAsPathLoop;
+        assert_eq!(
+            adj_rib_in.description(),
+            "Number of routes in per-AFI/SAFI Adj-RIB-In"
+        );
+        assert_eq!(
+            as_path_loop.to_string(),
+            "Number of updates invalidated due to AS_PATH loop"
+        );
+        assert!(adj_rib_in.is_gauge());
+        assert!(adj_rib_in.is_per_afi_safi());
+        assert!(!adj_rib_in.is_experimental());
+        assert!(!as_path_loop.is_gauge());
+        assert!(!as_path_loop.is_per_afi_safi());
+        assert!(BmpStatisticsType::Experimental65533.is_experimental());
+        assert!(!BmpStatisticsType::Experimental65533.is_gauge());
+    }
 }
@@ -15,28 +15,38 @@
 
 use std::{
     net::{IpAddr, Ipv4Addr},
-    ops::Deref,
+    ops::{BitOr, Deref},
 };
 
 #[cfg(feature = "fuzz")]
 use chrono::TimeZone;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
 
-use netgauze_bgp_pkt::{iana::BgpMessageType, nlri::RouteDistinguisher, BgpMessage};
+use netgauze_bgp_pkt::{
+    iana::BgpMessageType, nlri::RouteDistinguisher, notification::BgpNotificationMessage,
+    BgpMessage,
+};
 use netgauze_iana::address_family::AddressType;
 
 use crate::iana::{
-    BmpMessageType, BmpPeerTypeCode, BmpStatisticsType, BmpVersion, InitiationInformationTlvType,
-    PeerDownReasonCode, PeerTerminationCode, RouteMirroringInformation, RouteMirroringTlvType,
-    TerminationInformationTlvType,
+    BgpFsmEventCode, BmpMessageType, BmpPeerTypeCode, BmpStatisticsType, BmpVersion,
+    InitiationInformationTlvType, PeerDownReasonCode, PeerTerminationCode,
+    RouteMirroringInformation, RouteMirroringTlvType, TerminationInformationTlvType,
+    PEER_FLAGS_IS_ADJ_RIB_OUT, PEER_FLAGS_IS_ASN2, PEER_FLAGS_IS_FILTERED, PEER_FLAGS_IS_IPV6,
+    PEER_FLAGS_IS_POST_POLICY,
 };
 
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "codec")]
 pub mod codec;
+pub mod experimental;
+#[cfg(feature = "serde")]
+pub mod generator;
 pub mod iana;
 #[cfg(feature = "serde")]
+pub mod session;
+#[cfg(feature = "serde")]
 pub mod wire;
 
 /// ```text
@@ -74,6 +84,87 @@ impl BmpMessage {
     }
 }
 
+/// A [`BmpMessage`] along with the exact bytes it was parsed from.
+///
+/// Parsing into a [`BmpRawMessage`] instead of a [`BmpMessage`] keeps the
+/// original bytes around, so the message can be re-exported (or logged) as
+/// received even when the parsed value is modified downstream. Writing a
+/// [`BmpRawMessage`] writes the original bytes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BmpRawMessage {
+    message: BmpMessage,
+    raw: Vec<u8>,
+    tlvs: Vec<BmpTlvOffset>,
+}
+
+impl BmpRawMessage {
+    pub const fn new(message: BmpMessage, raw: Vec<u8>, tlvs: Vec<BmpTlvOffset>) -> Self {
+        Self { message, raw, tlvs }
+    }
+
+    pub const fn message(&self) -> &BmpMessage {
+        &self.message
+    }
+
+    pub fn message_mut(&mut self) -> &mut BmpMessage {
+        &mut self.message
+    }
+
+    /// Original bytes of the message, including the BMP common header
+    pub const fn raw(&self) -> &Vec<u8> {
+        &self.raw
+    }
+
+    /// Location of the information TLVs (Initiation, Termination, Peer Up,
+    /// Peer Down, Route Mirroring, and Statistics Report TLVs) in
+    /// [`BmpRawMessage::raw`]
+    pub const fn tlvs(&self) -> &Vec<BmpTlvOffset> {
+        &self.tlvs
+    }
+
+    /// Bytes of a TLV (header included) in [`BmpRawMessage::raw`]
+    pub fn tlv_bytes(&self, tlv: &BmpTlvOffset) -> Option<&[u8]> {
+        self.raw
+            .get(tlv.offset()..tlv.offset() + 4 + tlv.length() as usize)
+    }
+
+    pub fn into_parts(self) -> (BmpMessage, Vec<u8>) {
+        (self.message, self.raw)
+    }
+}
+
+/// Location of a TLV in the bytes of a [`BmpRawMessage`]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BmpTlvOffset {
+    tlv_type: u16,
+    offset: usize,
+    length: u16,
+}
+
+impl BmpTlvOffset {
+    pub const fn new(tlv_type: u16, offset: usize, length: u16) -> Self {
+        Self {
+            tlv_type,
+            offset,
+            length,
+        }
+    }
+
+    pub const fn tlv_type(&self) -> u16 {
+        self.tlv_type
+    }
+
+    /// Offset of the TLV header from the start of the BMP message
+    pub const fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Length of the TLV value, not including the 4-octet TLV header
+    pub const fn length(&self) -> u16 {
+        self.length
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum BmpMessageValue {
@@ -107,6 +198,17 @@ impl BmpMessageValue {
             Self::Experimental254(_) => BmpMessageType::Experimental254,
         }
     }
+
+    /// Body of the experimental message types
+    pub const fn experimental_data(&self) -> Option<&Vec<u8>> {
+        match self {
+            Self::Experimental251(value)
+            | Self::Experimental252(value)
+            | Self::Experimental253(value)
+            | Self::Experimental254(value) => Some(value),
+            _ => None,
+        }
+    }
 }
 
 ///  The per-peer header follows the common header for most BMP messages.
@@ -174,6 +276,29 @@ impl PeerHeader {
         self.rd
     }
 
+    /// Raw value of the Peer Distinguisher field, zero when no
+    /// [`RouteDistinguisher`] is set
+    pub fn peer_distinguisher(&self) -> u64 {
+        self.rd.map(u64::from).unwrap_or(0)
+    }
+
+    /// Flags octet of the peer header
+    pub const fn flags(&self) -> BmpPeerFlags {
+        self.peer_type.flags()
+    }
+
+    pub const fn is_post_policy(&self) -> bool {
+        self.peer_type.is_post_policy()
+    }
+
+    pub const fn is_adj_rib_out(&self) -> bool {
+        self.peer_type.is_adj_rib_out()
+    }
+
+    pub const fn is_filtered(&self) -> bool {
+        self.peer_type.is_filtered()
+    }
+
     pub const fn address(&self) -> Option<IpAddr> {
         self.address
     }
@@ -190,6 +315,22 @@ impl PeerHeader {
         self.timestamp.as_ref()
     }
 
+    /// Timestamp as encoded on the wire: seconds and microseconds since the
+    /// Unix epoch, both are zero when no timestamp is available
+    pub fn raw_timestamp(&self) -> (u32, u32) {
+        match self.timestamp {
+            None => (0, 0),
+            Some(time) => (time.timestamp() as u32, time.timestamp_subsec_micros()),
+        }
+    }
+
+    /// Time elapsed between the event reported by the monitored router and
+    /// `received_at`, usually the time the message is received by the
+    /// collector. [`None`] when the router didn't provide a timestamp.
+    pub fn latency(&self, received_at: DateTime<Utc>) -> Option<TimeDelta> {
+        self.timestamp.map(|time| received_at - time)
+    }
+
     pub const fn is_asn4(&self) -> bool {
         match self.peer_type {
             BmpPeerType::GlobalInstancePeer { asn2, .. } => !asn2,
@@ -279,6 +420,229 @@ impl BmpPeerType {
             Self::Experimental254 { .. } => BmpPeerTypeCode::Experimental254,
         }
     }
+
+    /// Flags octet of the peer type, the experimental peer types flags are
+    /// returned as is
+    pub const fn flags(&self) -> BmpPeerFlags {
+        match self {
+            Self::GlobalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            }
+            | Self::RdInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            }
+            | Self::LocalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            } => {
+                let mut flags = BmpPeerFlags::empty();
+                if *ipv6 {
+                    flags = flags.union(BmpPeerFlags::IPV6);
+                }
+                if *post_policy {
+                    flags = flags.union(BmpPeerFlags::POST_POLICY);
+                }
+                if *asn2 {
+                    flags = flags.union(BmpPeerFlags::ASN2);
+                }
+                if *adj_rib_out {
+                    flags = flags.union(BmpPeerFlags::ADJ_RIB_OUT);
+                }
+                flags
+            }
+            Self::LocRibInstancePeer { filtered } => {
+                if *filtered {
+                    BmpPeerFlags::FILTERED
+                } else {
+                    BmpPeerFlags::empty()
+                }
+            }
+            Self::Experimental251 { flags }
+            | Self::Experimental252 { flags }
+            | Self::Experimental253 { flags }
+            | Self::Experimental254 { flags } => BmpPeerFlags::from_bits(*flags),
+        }
+    }
+
+    /// True when the peer type carries the L flag
+    pub const fn is_post_policy(&self) -> bool {
+        match self {
+            Self::GlobalInstancePeer { post_policy, .. }
+            | Self::RdInstancePeer { post_policy, .. }
+            | Self::LocalInstancePeer { post_policy, .. } => *post_policy,
+            _ => false,
+        }
+    }
+
+    /// True when the peer type is a Loc-RIB peer carrying the F flag
+    pub const fn is_filtered(&self) -> bool {
+        matches!(self, Self::LocRibInstancePeer { filtered: true })
+    }
+
+    /// True when the peer type carries the O flag, i.e. the message is about
+    /// the Adj-RIB-Out of the monitored router
+    pub const fn is_adj_rib_out(&self) -> bool {
+        match self {
+            Self::GlobalInstancePeer { adj_rib_out, .. }
+            | Self::RdInstancePeer { adj_rib_out, .. }
+            | Self::LocalInstancePeer { adj_rib_out, .. } => *adj_rib_out,
+            _ => false,
+        }
+    }
+
+    /// Same peer type with the O flag set to `adj_rib_out`. Peer types that
+    /// don't define the flag are returned unchanged.
+    pub const fn with_adj_rib_out(self, adj_rib_out: bool) -> Self {
+        match self {
+            Self::GlobalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                ..
+            } => Self::GlobalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            },
+            Self::RdInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                ..
+            } => Self::RdInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            },
+            Self::LocalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                ..
+            } => Self::LocalInstancePeer {
+                ipv6,
+                post_policy,
+                asn2,
+                adj_rib_out,
+            },
+            other => other,
+        }
+    }
+
+    /// Same peer type with the flags that vary from one message to another
+    /// (post-policy, legacy 2-octet AS_PATH, and Adj-RIB-Out) cleared, so it
+    /// identifies the peer itself rather than a given RIB view of it.
+    pub const fn without_message_flags(self) -> Self {
+        match self {
+            Self::GlobalInstancePeer { ipv6, .. } => Self::GlobalInstancePeer {
+                ipv6,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            Self::RdInstancePeer { ipv6, .. } => Self::RdInstancePeer {
+                ipv6,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            Self::LocalInstancePeer { ipv6, .. } => Self::LocalInstancePeer {
+                ipv6,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            other => other,
+        }
+    }
+}
+
+/// Flags octet of the [`PeerHeader`].
+///
+/// The meaning of the bits depends on the peer type: V, L, A, and O flags are
+/// defined for the Global, RD, and Local Instance peers, while the F flag is
+/// defined for the Loc-RIB Instance peer and shares the same bit as V.
+#[derive(Debug, Default, Hash, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
+pub struct BmpPeerFlags(u8);
+
+impl BmpPeerFlags {
+    pub const IPV6: Self = Self(PEER_FLAGS_IS_IPV6);
+    pub const POST_POLICY: Self = Self(PEER_FLAGS_IS_POST_POLICY);
+    pub const ASN2: Self = Self(PEER_FLAGS_IS_ASN2);
+    pub const ADJ_RIB_OUT: Self = Self(PEER_FLAGS_IS_ADJ_RIB_OUT);
+    pub const FILTERED: Self = Self(PEER_FLAGS_IS_FILTERED);
+
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    pub const fn from_bits(bits: u8) -> Self {
+        Self(bits)
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// True if all the bits set in `other` are set in `self`
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn union(self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    pub const fn is_ipv6(&self) -> bool {
+        self.contains(Self::IPV6)
+    }
+
+    pub const fn is_post_policy(&self) -> bool {
+        self.contains(Self::POST_POLICY)
+    }
+
+    pub const fn is_asn2(&self) -> bool {
+        self.contains(Self::ASN2)
+    }
+
+    pub const fn is_adj_rib_out(&self) -> bool {
+        self.contains(Self::ADJ_RIB_OUT)
+    }
+
+    pub const fn is_filtered(&self) -> bool {
+        self.contains(Self::FILTERED)
+    }
+}
+
+impl BitOr for BmpPeerFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        self.union(rhs)
+    }
+}
+
+impl From<u8> for BmpPeerFlags {
+    fn from(value: u8) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BmpPeerFlags> for u8 {
+    fn from(value: BmpPeerFlags) -> Self {
+        value.0
+    }
 }
 
 /// The initiation message provides a means for the monitored router to
@@ -384,6 +748,22 @@ impl InitiationInformation {
             }
         }
     }
+
+    /// Get the string value of the TLV, [`None`] for the experimental types
+    /// since their value is opaque
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            InitiationInformation::String(value)
+            | InitiationInformation::SystemDescription(value)
+            | InitiationInformation::SystemName(value)
+            | InitiationInformation::VrfTableName(value)
+            | InitiationInformation::AdminLabel(value) => Some(value.as_str()),
+            InitiationInformation::Experimental65531(_)
+            | InitiationInformation::Experimental65532(_)
+            | InitiationInformation::Experimental65533(_)
+            | InitiationInformation::Experimental65534(_) => None,
+        }
+    }
 }
 
 /// The termination message provides a way for a monitored router to indicate
@@ -521,13 +901,70 @@ impl RouteMirroringMessage {
     pub const fn mirrored(&self) -> &Vec<RouteMirroringValue> {
         &self.mirrored
     }
+
+    /// Information codes carried by the message
+    pub fn information(&self) -> impl Iterator<Item = RouteMirroringInformation> + '_ {
+        self.mirrored.iter().filter_map(|value| match value {
+            RouteMirroringValue::Information(info) => Some(*info),
+            _ => None,
+        })
+    }
+
+    /// The mirrored BGP PDU, it MUST be the last TLV in the message
+    pub fn bgp_message(&self) -> Option<&MirroredBgpMessage> {
+        self.mirrored.iter().rev().find_map(|value| match value {
+            RouteMirroringValue::BgpMessage(msg) => Some(msg),
+            _ => None,
+        })
+    }
+
+    /// The router flagged the mirrored BGP PDU as errored
+    pub fn is_errored_pdu(&self) -> bool {
+        self.information()
+            .any(|info| info == RouteMirroringInformation::ErroredPdu)
+    }
+
+    /// The router indicated that one or more messages were lost
+    pub fn is_messages_lost(&self) -> bool {
+        self.information()
+            .any(|info| info == RouteMirroringInformation::MessagesLost)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "fuzz", derive(arbitrary::Arbitrary))]
 pub enum MirroredBgpMessage {
     Parsed(BgpMessage),
+
+    /// The BGP PDU couldn't be parsed, the raw PDU is kept as is.
     Raw(Vec<u8>),
+
+    /// BGP PDU the router flagged with [`RouteMirroringInformation::ErroredPdu`].
+    /// The raw PDU is kept as sent by the router, along with a best-effort
+    /// parsed view of it when the PDU can be parsed.
+    Errored {
+        raw: Vec<u8>,
+        parsed: Option<BgpMessage>,
+    },
+}
+
+impl MirroredBgpMessage {
+    /// Parsed view of the BGP PDU, if available
+    pub const fn parsed(&self) -> Option<&BgpMessage> {
+        match self {
+            Self::Parsed(msg) => Some(msg),
+            Self::Raw(_) => None,
+            Self::Errored { parsed, .. } => parsed.as_ref(),
+        }
+    }
+
+    /// Raw bytes of the BGP PDU, if they were kept
+    pub const fn raw(&self) -> Option<&Vec<u8>> {
+        match self {
+            Self::Parsed(_) => None,
+            Self::Raw(raw) | Self::Errored { raw, .. } => Some(raw),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -666,6 +1103,39 @@ impl PeerUpNotificationMessage {
     pub const fn information(&self) -> &Vec<InitiationInformation> {
         &self.information
     }
+
+    /// Value of the first [`InitiationInformation::SystemDescription`] TLV
+    pub fn system_description(&self) -> Option<&str> {
+        self.information.iter().find_map(|info| match info {
+            InitiationInformation::SystemDescription(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Value of the first [`InitiationInformation::SystemName`] TLV
+    pub fn system_name(&self) -> Option<&str> {
+        self.information.iter().find_map(|info| match info {
+            InitiationInformation::SystemName(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Value of the first [`InitiationInformation::VrfTableName`] TLV
+    pub fn vrf_table_name(&self) -> Option<&str> {
+        self.information.iter().find_map(|info| match info {
+            InitiationInformation::VrfTableName(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// All the [`InitiationInformation::AdminLabel`] TLVs in the order they
+    /// were sent by the router
+    pub fn admin_labels(&self) -> impl Iterator<Item = &str> {
+        self.information.iter().filter_map(|info| match info {
+            InitiationInformation::AdminLabel(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
 }
 
 /// Runtime errors when constructing a [`PeerDownNotificationMessage`]
@@ -817,6 +1287,49 @@ impl PeerDownNotificationReason {
             Self::Experimental254(_) => PeerDownReasonCode::Experimental254,
         }
     }
+
+    /// Data of the experimental reason codes
+    pub const fn experimental_data(&self) -> Option<&Vec<u8>> {
+        match self {
+            Self::Experimental251(value)
+            | Self::Experimental252(value)
+            | Self::Experimental253(value)
+            | Self::Experimental254(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// BGP NOTIFICATION message sent or received when closing the session
+    pub const fn notification(&self) -> Option<&BgpNotificationMessage> {
+        match self {
+            Self::LocalSystemClosedNotificationPduFollows(BgpMessage::Notification(msg))
+            | Self::RemoteSystemClosedNotificationPduFollows(BgpMessage::Notification(msg)) => {
+                Some(msg)
+            }
+            _ => None,
+        }
+    }
+
+    /// The FSM event that caused the local system to close the session,
+    /// [`None`] if no relevant event code is defined or the reason is not
+    /// [`PeerDownNotificationReason::LocalSystemClosedFsmEventFollows`]
+    pub fn fsm_event(&self) -> Option<BgpFsmEventCode> {
+        match self {
+            Self::LocalSystemClosedFsmEventFollows(code) => BgpFsmEventCode::try_from(*code).ok(),
+            _ => None,
+        }
+    }
+
+    /// VRF/Table name TLV carried by
+    /// [`PeerDownNotificationReason::LocalSystemClosedTlvDataFollows`]
+    pub fn vrf_table_name(&self) -> Option<&str> {
+        match self {
+            Self::LocalSystemClosedTlvDataFollows(InitiationInformation::VrfTableName(value)) => {
+                Some(value.as_str())
+            }
+            _ => None,
+        }
+    }
 }
 
 /// These messages contain information that could be used by the
@@ -854,6 +1367,115 @@ impl StatisticsReportMessage {
     pub const fn counters(&self) -> &Vec<StatisticsCounter> {
         &self.counters
     }
+
+    /// Compute the change of each statistics value since a `previous` report
+    /// of the same peer.
+    ///
+    /// Values are matched by statistics type (and AFI/SAFI for the per-AFI/SAFI
+    /// types). Values that are not present in both reports, as well as
+    /// experimental and unknown types are skipped.
+    pub fn delta(
+        &self,
+        previous: &StatisticsReportMessage,
+    ) -> Result<Vec<StatisticsCounterDelta>, StatisticsDeltaError> {
+        let peer_key = PeerKey::from_peer_header(&self.peer_header).without_message_flags();
+        let previous_peer_key =
+            PeerKey::from_peer_header(&previous.peer_header).without_message_flags();
+        if peer_key != previous_peer_key {
+            return Err(StatisticsDeltaError::DifferentPeers(
+                previous_peer_key,
+                peer_key,
+            ));
+        }
+        let deltas = self
+            .counters
+            .iter()
+            .filter_map(|counter| {
+                let stat_type = counter.get_type().ok()?;
+                let current = counter.value()?;
+                let previous = previous
+                    .counters
+                    .iter()
+                    .find(|prev| {
+                        prev.get_type() == Ok(stat_type)
+                            && prev.address_type() == counter.address_type()
+                    })?
+                    .value()?;
+                let delta = if stat_type.is_gauge() {
+                    StatisticsDelta::Gauge(current.wrapping_sub(previous) as i64)
+                } else {
+                    StatisticsDelta::Counter((current as u32).wrapping_sub(previous as u32))
+                };
+                Some(StatisticsCounterDelta::new(
+                    stat_type,
+                    counter.address_type(),
+                    delta,
+                ))
+            })
+            .collect();
+        Ok(deltas)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum StatisticsDeltaError {
+    /// The two statistics reports are for different peers (previous, current)
+    DifferentPeers(PeerKey, PeerKey),
+}
+
+/// Change of a statistics value between two successive
+/// [`StatisticsReportMessage`] of a peer
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub enum StatisticsDelta {
+    /// Increase of a 32-bit counter. A counter lower than its previous value
+    /// is assumed to have wrapped around once.
+    Counter(u32),
+
+    /// Change of a 64-bit gauge, negative when the gauge decreased
+    Gauge(i64),
+}
+
+impl StatisticsDelta {
+    pub const fn value(&self) -> i64 {
+        match self {
+            Self::Counter(value) => *value as i64,
+            Self::Gauge(value) => *value,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StatisticsCounterDelta {
+    stat_type: BmpStatisticsType,
+    address_type: Option<AddressType>,
+    delta: StatisticsDelta,
+}
+
+impl StatisticsCounterDelta {
+    pub const fn new(
+        stat_type: BmpStatisticsType,
+        address_type: Option<AddressType>,
+        delta: StatisticsDelta,
+    ) -> Self {
+        Self {
+            stat_type,
+            address_type,
+            delta,
+        }
+    }
+
+    pub const fn stat_type(&self) -> BmpStatisticsType {
+        self.stat_type
+    }
+
+    /// AFI/SAFI of the per-AFI/SAFI statistics types
+    pub const fn address_type(&self) -> Option<AddressType> {
+        self.address_type
+    }
+
+    pub const fn delta(&self) -> StatisticsDelta {
+        self.delta
+    }
 }
 
 /// [`StatisticsReportMessage`] value
@@ -956,6 +1578,70 @@ impl StatisticsCounter {
             Self::Unknown(code, _) => Err(*code),
         }
     }
+
+    /// Numeric value of the counter or gauge, [`None`] for experimental and
+    /// unknown statistics types since their value is opaque
+    pub const fn value(&self) -> Option<u64> {
+        match self {
+            Self::NumberOfPrefixesRejectedByInboundPolicy(value)
+            | Self::NumberOfDuplicatePrefixAdvertisements(value)
+            | Self::NumberOfDuplicateWithdraws(value)
+            | Self::NumberOfUpdatesInvalidatedDueToClusterListLoop(value)
+            | Self::NumberOfUpdatesInvalidatedDueToAsPathLoop(value)
+            | Self::NumberOfUpdatesInvalidatedDueToOriginatorId(value)
+            | Self::NumberOfUpdatesInvalidatedDueToAsConfederationLoop(value)
+            | Self::NumberOfUpdatesSubjectedToTreatAsWithdraw(value)
+            | Self::NumberOfPrefixesSubjectedToTreatAsWithdraw(value)
+            | Self::NumberOfDuplicateUpdateMessagesReceived(value) => Some(value.value() as u64),
+            Self::NumberOfRoutesInAdjRibIn(value)
+            | Self::NumberOfRoutesInLocRib(value)
+            | Self::NumberOfRoutesInPrePolicyAdjRibOut(value)
+            | Self::NumberOfRoutesInPostPolicyAdjRibOut(value)
+            | Self::NumberOfRoutesInPerAfiSafiAdjRibIn(_, value)
+            | Self::NumberOfRoutesInPerAfiSafiLocRib(_, value)
+            | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(_, value)
+            | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut(_, value) => Some(value.value()),
+            Self::Experimental65531(_)
+            | Self::Experimental65532(_)
+            | Self::Experimental65533(_)
+            | Self::Experimental65534(_)
+            | Self::Unknown(_, _) => None,
+        }
+    }
+
+    /// AFI/SAFI of the per-AFI/SAFI statistics types
+    pub const fn address_type(&self) -> Option<AddressType> {
+        match self {
+            Self::NumberOfRoutesInPerAfiSafiAdjRibIn(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiLocRib(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiPrePolicyAdjRibOut(address_type, _)
+            | Self::NumberOfRoutesInPerAfiSafiPostPolicyAdjRibOut(address_type, _) => {
+                Some(*address_type)
+            }
+            _ => None,
+        }
+    }
+
+    /// Raw data of the experimental and unknown statistics types
+    pub const fn raw_value(&self) -> Option<&Vec<u8>> {
+        match self {
+            Self::Experimental65531(value)
+            | Self::Experimental65532(value)
+            | Self::Experimental65533(value)
+            | Self::Experimental65534(value)
+            | Self::Unknown(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// IANA description of the statistics type, `"Unknown"` for unassigned
+    /// codes
+    pub fn name(&self) -> &'static str {
+        match self.get_type() {
+            Ok(code) => code.description(),
+            Err(_) => "Unknown",
+        }
+    }
 }
 
 /// A non-negative integer that monotonically increases
@@ -1063,6 +1749,25 @@ impl PeerKey {
         )
     }
 
+    /// Key of the same peer with [`BmpPeerType::without_message_flags`]
+    /// applied to the peer type, so that the pre- and post-policy messages of
+    /// a peer map to the key of its Peer Up notification
+    pub const fn without_message_flags(&self) -> Self {
+        Self {
+            peer_type: self.peer_type.without_message_flags(),
+            ..*self
+        }
+    }
+
+    /// Key of the same peer with [`BmpPeerType::with_adj_rib_out`] applied to
+    /// the peer type
+    pub const fn with_adj_rib_out(&self, adj_rib_out: bool) -> Self {
+        Self {
+            peer_type: self.peer_type.with_adj_rib_out(adj_rib_out),
+            ..*self
+        }
+    }
+
     pub const fn peer_address(&self) -> Option<IpAddr> {
         self.peer_address
     }
@@ -0,0 +1,514 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Collector side tracking of the state of a BMP session.
+//!
+//! [`BmpSessionTracker`] consumes the parsed messages of one BMP session
+//! (i.e., one TCP connection from a monitored router) and keeps track of the
+//! Initiation/Termination of the session and the Up/Down state of each
+//! monitored peer along with the BGP capabilities exchanged in the OPEN
+//! messages carried by the Peer Up notification. The per-peer
+//! [`BgpParsingContext`] derived from these capabilities is then used to
+//! parse the BGP PDUs carried in the subsequent Route Monitoring messages.
+
+use std::collections::HashMap;
+
+use netgauze_bgp_pkt::{
+    capabilities::{negotiate, BgpCapability, NegotiatedCapabilities},
+    wire::deserializer::BgpParsingContext,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    iana::BmpMessageType,
+    wire::deserializer::{open_capabilities, update_peer_parsing_ctx, BmpParsingContext},
+    BmpMessage, BmpMessageValue, InitiationInformation, PeerDownNotificationReason, PeerHeader,
+    PeerKey, PeerUpNotificationMessage, TerminationInformation,
+};
+
+/// State of the BMP session as seen by the collector
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BmpSessionState {
+    /// No message has been received yet
+    Connected,
+
+    /// Initiation message is received, the session is ready to carry
+    /// other messages
+    Initiated,
+
+    /// Termination message is received, no more messages are expected
+    Terminated,
+}
+
+/// State of a peer monitored by the router
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BmpPeerState {
+    Up,
+    Down,
+}
+
+/// Protocol violations detected while tracking a BMP session. The tracker
+/// still applies the offending message to its state on a best-effort basis.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BmpSessionTrackerError {
+    /// The first message of the session MUST be an Initiation message
+    NotInitiated(BmpMessageType),
+
+    /// A message is received after the session has been terminated
+    Terminated(BmpMessageType),
+
+    /// Initiation message is received more than once
+    AlreadyInitiated,
+
+    /// A Peer Up is received for a peer that is already up
+    PeerAlreadyUp(PeerKey),
+
+    /// A message that requires the peer to be up is received for a peer that
+    /// is unknown or down
+    PeerNotUp(BmpMessageType, PeerKey),
+}
+
+/// State of a single monitored peer
+#[derive(Debug, Clone)]
+pub struct BmpPeerSession {
+    state: BmpPeerState,
+    peer_header: PeerHeader,
+    sent_capabilities: Vec<BgpCapability>,
+    received_capabilities: Vec<BgpCapability>,
+    negotiated: NegotiatedCapabilities,
+    parsing_ctx: BgpParsingContext,
+    adj_rib_out_parsing_ctx: BgpParsingContext,
+    down_reason: Option<PeerDownNotificationReason>,
+}
+
+impl BmpPeerSession {
+    fn new(peer_up: &PeerUpNotificationMessage) -> Self {
+        let sent_capabilities = open_capabilities(peer_up.sent_message());
+        let received_capabilities = open_capabilities(peer_up.received_message());
+        // The sent OPEN is the one sent by the monitored router, hence the local side
+        // of the session when decoding the messages received from the peer
+        let negotiated = negotiate(&sent_capabilities, &received_capabilities);
+        let mut parsing_ctx = BgpParsingContext::default();
+        update_peer_parsing_ctx(&mut parsing_ctx, &negotiated, false);
+        let mut adj_rib_out_parsing_ctx = BgpParsingContext::default();
+        update_peer_parsing_ctx(&mut adj_rib_out_parsing_ctx, &negotiated, true);
+        // The AS number length in the BMP messages is given by the peer header
+        parsing_ctx.set_asn4(peer_up.peer_header().is_asn4());
+        adj_rib_out_parsing_ctx.set_asn4(peer_up.peer_header().is_asn4());
+        Self {
+            state: BmpPeerState::Up,
+            peer_header: peer_up.peer_header().clone(),
+            sent_capabilities,
+            received_capabilities,
+            negotiated,
+            parsing_ctx,
+            adj_rib_out_parsing_ctx,
+            down_reason: None,
+        }
+    }
+
+    pub const fn state(&self) -> BmpPeerState {
+        self.state
+    }
+
+    /// Peer header of the last Peer Up or Peer Down notification
+    pub const fn peer_header(&self) -> &PeerHeader {
+        &self.peer_header
+    }
+
+    /// Capabilities in the OPEN message sent by the monitored router
+    pub const fn sent_capabilities(&self) -> &Vec<BgpCapability> {
+        &self.sent_capabilities
+    }
+
+    /// Capabilities in the OPEN message received from the peer
+    pub const fn received_capabilities(&self) -> &Vec<BgpCapability> {
+        &self.received_capabilities
+    }
+
+    /// Capabilities negotiated between the monitored router and the peer, from
+    /// the point of view of the monitored router
+    pub const fn negotiated_capabilities(&self) -> &NegotiatedCapabilities {
+        &self.negotiated
+    }
+
+    /// Context to parse the BGP PDUs received from this peer
+    pub const fn parsing_ctx(&self) -> &BgpParsingContext {
+        &self.parsing_ctx
+    }
+
+    /// Context to parse the BGP PDUs sent to this peer, as reported in the
+    /// Adj-RIB-Out Route Monitoring messages
+    pub const fn adj_rib_out_parsing_ctx(&self) -> &BgpParsingContext {
+        &self.adj_rib_out_parsing_ctx
+    }
+
+    /// Reason of the last Peer Down notification, if the peer is down
+    pub const fn down_reason(&self) -> Option<&PeerDownNotificationReason> {
+        self.down_reason.as_ref()
+    }
+}
+
+/// Tracks the state of one BMP session, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct BmpSessionTracker {
+    state: BmpSessionState,
+    initiation: Vec<InitiationInformation>,
+    termination: Vec<TerminationInformation>,
+    peers: HashMap<PeerKey, BmpPeerSession>,
+}
+
+impl Default for BmpSessionTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BmpSessionTracker {
+    pub fn new() -> Self {
+        Self {
+            state: BmpSessionState::Connected,
+            initiation: vec![],
+            termination: vec![],
+            peers: HashMap::new(),
+        }
+    }
+
+    pub const fn state(&self) -> BmpSessionState {
+        self.state
+    }
+
+    /// Information TLVs of the Initiation message
+    pub const fn initiation(&self) -> &Vec<InitiationInformation> {
+        &self.initiation
+    }
+
+    /// Information TLVs of the Termination message
+    pub const fn termination(&self) -> &Vec<TerminationInformation> {
+        &self.termination
+    }
+
+    /// System name of the monitored router as advertised in the Initiation
+    /// message
+    pub fn system_name(&self) -> Option<&str> {
+        self.initiation.iter().find_map(|info| match info {
+            InitiationInformation::SystemName(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    /// All the peers seen in the session, including the ones that went down.
+    /// Peers are keyed with [`PeerKey::without_message_flags`].
+    pub const fn peers(&self) -> &HashMap<PeerKey, BmpPeerSession> {
+        &self.peers
+    }
+
+    pub fn peer(&self, peer_key: &PeerKey) -> Option<&BmpPeerSession> {
+        self.peers.get(&peer_key.without_message_flags())
+    }
+
+    /// Peers that are currently up
+    pub fn peers_up(&self) -> impl Iterator<Item = (&PeerKey, &BmpPeerSession)> {
+        self.peers
+            .iter()
+            .filter(|(_, peer)| peer.state == BmpPeerState::Up)
+    }
+
+    /// Context to parse the BGP PDUs of the given peer, [`None`] if the peer
+    /// is not up
+    pub fn parsing_ctx(&self, peer_key: &PeerKey) -> Option<&BgpParsingContext> {
+        self.peer(peer_key)
+            .filter(|peer| peer.state == BmpPeerState::Up)
+            .map(|peer| {
+                if peer_key.peer_type().is_adj_rib_out() {
+                    peer.adj_rib_out_parsing_ctx()
+                } else {
+                    peer.parsing_ctx()
+                }
+            })
+    }
+
+    /// Build a [`BmpParsingContext`] with the contexts of the peers that are
+    /// currently up, to be used when parsing the subsequent messages of the
+    /// session
+    pub fn bmp_parsing_ctx(&self) -> BmpParsingContext {
+        BmpParsingContext::new(
+            self.peers_up()
+                .flat_map(|(peer_key, peer)| {
+                    [
+                        (peer_key.with_adj_rib_out(false), peer.parsing_ctx.clone()),
+                        (
+                            peer_key.with_adj_rib_out(true),
+                            peer.adj_rib_out_parsing_ctx.clone(),
+                        ),
+                    ]
+                })
+                .collect(),
+        )
+    }
+
+    /// Update the session state with a newly received BMP message
+    pub fn update(&mut self, msg: &BmpMessage) -> Result<(), BmpSessionTrackerError> {
+        match msg {
+            BmpMessage::V3(value) => self.update_value(value),
+        }
+    }
+
+    fn update_value(&mut self, value: &BmpMessageValue) -> Result<(), BmpSessionTrackerError> {
+        let msg_type = value.get_type();
+        let session_check = match (self.state, value) {
+            (BmpSessionState::Terminated, _) => Err(BmpSessionTrackerError::Terminated(msg_type)),
+            (BmpSessionState::Initiated, BmpMessageValue::Initiation(_)) => {
+                Err(BmpSessionTrackerError::AlreadyInitiated)
+            }
+            (BmpSessionState::Connected, BmpMessageValue::Initiation(_))
+            | (BmpSessionState::Initiated, _) => Ok(()),
+            (BmpSessionState::Connected, _) => Err(BmpSessionTrackerError::NotInitiated(msg_type)),
+        };
+        let peer_check = match value {
+            BmpMessageValue::Initiation(initiation) => {
+                self.state = BmpSessionState::Initiated;
+                self.initiation = initiation.information().clone();
+                Ok(())
+            }
+            BmpMessageValue::Termination(termination) => {
+                self.state = BmpSessionState::Terminated;
+                self.termination = termination.information().clone();
+                for peer in self.peers.values_mut() {
+                    peer.state = BmpPeerState::Down;
+                }
+                Ok(())
+            }
+            BmpMessageValue::PeerUpNotification(peer_up) => {
+                let peer_key =
+                    PeerKey::from_peer_header(peer_up.peer_header()).without_message_flags();
+                let previous = self.peers.insert(peer_key, BmpPeerSession::new(peer_up));
+                match previous {
+                    Some(previous) if previous.state == BmpPeerState::Up => {
+                        Err(BmpSessionTrackerError::PeerAlreadyUp(peer_key))
+                    }
+                    _ => Ok(()),
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let peer_key =
+                    PeerKey::from_peer_header(peer_down.peer_header()).without_message_flags();
+                match self.peers.get_mut(&peer_key) {
+                    Some(peer) if peer.state == BmpPeerState::Up => {
+                        peer.state = BmpPeerState::Down;
+                        peer.peer_header = peer_down.peer_header().clone();
+                        peer.down_reason = Some(peer_down.reason().clone());
+                        Ok(())
+                    }
+                    _ => Err(BmpSessionTrackerError::PeerNotUp(msg_type, peer_key)),
+                }
+            }
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                self.check_peer_up(msg_type, route_monitoring.peer_header())
+            }
+            BmpMessageValue::RouteMirroring(route_mirroring) => {
+                self.check_peer_up(msg_type, route_mirroring.peer_header())
+            }
+            BmpMessageValue::StatisticsReport(stats) => {
+                self.check_peer_up(msg_type, stats.peer_header())
+            }
+            BmpMessageValue::Experimental251(_)
+            | BmpMessageValue::Experimental252(_)
+            | BmpMessageValue::Experimental253(_)
+            | BmpMessageValue::Experimental254(_) => Ok(()),
+        };
+        session_check.and(peer_check)
+    }
+
+    fn check_peer_up(
+        &self,
+        msg_type: BmpMessageType,
+        peer_header: &PeerHeader,
+    ) -> Result<(), BmpSessionTrackerError> {
+        let peer_key = PeerKey::from_peer_header(peer_header).without_message_flags();
+        match self.peers.get(&peer_key) {
+            Some(peer) if peer.state == BmpPeerState::Up => Ok(()),
+            _ => Err(BmpSessionTrackerError::PeerNotUp(msg_type, peer_key)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        BmpPeerType, InitiationMessage, PeerDownNotificationMessage, RouteMonitoringMessage,
+        TerminationMessage,
+    };
+    use netgauze_bgp_pkt::{
+        capabilities::{AddPathAddressFamily, AddPathCapability, FourOctetAsCapability},
+        open::{BgpOpenMessage, BgpOpenMessageParameter},
+        update::BgpUpdateMessage,
+        BgpMessage,
+    };
+    use netgauze_iana::address_family::AddressType;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    fn open(bgp_id: Ipv4Addr, send: bool, receive: bool) -> BgpMessage {
+        BgpMessage::Open(BgpOpenMessage::new(
+            23456,
+            180,
+            bgp_id,
+            vec![BgpOpenMessageParameter::Capabilities(vec![
+                BgpCapability::FourOctetAs(FourOctetAsCapability::new(65001)),
+                BgpCapability::AddPath(AddPathCapability::new(vec![AddPathAddressFamily::new(
+                    AddressType::Ipv4Unicast,
+                    send,
+                    receive,
+                )])),
+            ])],
+        ))
+    }
+
+    #[test]
+    fn test_session_tracker() {
+        let peer_header = PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2))),
+            65001,
+            Ipv4Addr::new(192, 0, 2, 2),
+            None,
+        );
+        let peer_key = PeerKey::from_peer_header(&peer_header);
+        let initiation = BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![
+            InitiationInformation::SystemName("PE1".to_string()),
+        ])));
+        let peer_up = BmpMessage::V3(BmpMessageValue::PeerUpNotification(
+            PeerUpNotificationMessage::build(
+                peer_header.clone(),
+                Some(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))),
+                Some(179),
+                Some(50000),
+                open(Ipv4Addr::new(192, 0, 2, 1), false, true),
+                open(Ipv4Addr::new(192, 0, 2, 2), true, false),
+                vec![],
+            )
+            .unwrap(),
+        ));
+        let route_monitoring = BmpMessage::V3(BmpMessageValue::RouteMonitoring(
+            RouteMonitoringMessage::build(
+                peer_header.clone(),
+                BgpMessage::Update(BgpUpdateMessage::new(vec![], vec![], vec![])),
+            )
+            .unwrap(),
+        ));
+        let peer_down = BmpMessage::V3(BmpMessageValue::PeerDownNotification(
+            PeerDownNotificationMessage::build(
+                peer_header.clone(),
+                PeerDownNotificationReason::RemoteSystemClosedNoData,
+            )
+            .unwrap(),
+        ));
+        let termination = BmpMessage::V3(BmpMessageValue::Termination(TerminationMessage::new(
+            peer_header,
+            vec![],
+        )));
+
+        let mut tracker = BmpSessionTracker::new();
+        assert_eq!(tracker.state(), BmpSessionState::Connected);
+        assert_eq!(
+            tracker.update(&route_monitoring),
+            Err(BmpSessionTrackerError::NotInitiated(
+                BmpMessageType::RouteMonitoring
+            ))
+        );
+
+        assert_eq!(tracker.update(&initiation), Ok(()));
+        assert_eq!(tracker.state(), BmpSessionState::Initiated);
+        assert_eq!(tracker.system_name(), Some("PE1"));
+        assert_eq!(
+            tracker.update(&initiation),
+            Err(BmpSessionTrackerError::AlreadyInitiated)
+        );
+        assert_eq!(
+            tracker.update(&route_monitoring),
+            Err(BmpSessionTrackerError::PeerNotUp(
+                BmpMessageType::RouteMonitoring,
+                peer_key
+            ))
+        );
+
+        assert_eq!(tracker.update(&peer_up), Ok(()));
+        assert_eq!(tracker.update(&route_monitoring), Ok(()));
+        let peer = tracker.peer(&peer_key).unwrap();
+        assert_eq!(peer.state(), BmpPeerState::Up);
+        assert_eq!(peer.sent_capabilities().len(), 2);
+        assert!(peer.negotiated_capabilities().four_octet_as());
+        let ctx = tracker.parsing_ctx(&peer_key).unwrap();
+        assert!(ctx.asn4());
+        assert_eq!(ctx.add_path().get(&AddressType::Ipv4Unicast), Some(&true));
+        assert!(tracker.bmp_parsing_ctx().contains_key(&peer_key));
+        // Post-policy and Adj-RIB-Out messages belong to the same peer, the
+        // monitored router doesn't send path identifiers to that peer
+        let adj_rib_out_key = PeerKey::new(
+            peer_key.peer_address(),
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: true,
+                asn2: false,
+                adj_rib_out: true,
+            },
+            None,
+            peer_key.asn(),
+            peer_key.bgp_id(),
+        );
+        assert!(tracker.peer(&adj_rib_out_key).is_some());
+        let ctx = tracker.parsing_ctx(&adj_rib_out_key).unwrap();
+        assert_eq!(ctx.add_path().get(&AddressType::Ipv4Unicast), Some(&false));
+        assert_eq!(
+            tracker.update(&peer_up),
+            Err(BmpSessionTrackerError::PeerAlreadyUp(peer_key))
+        );
+
+        assert_eq!(tracker.update(&peer_down), Ok(()));
+        let peer = tracker.peer(&peer_key).unwrap();
+        assert_eq!(peer.state(), BmpPeerState::Down);
+        assert_eq!(
+            peer.down_reason(),
+            Some(&PeerDownNotificationReason::RemoteSystemClosedNoData)
+        );
+        assert!(tracker.parsing_ctx(&peer_key).is_none());
+        assert!(tracker.bmp_parsing_ctx().is_empty());
+        assert_eq!(
+            tracker.update(&peer_down),
+            Err(BmpSessionTrackerError::PeerNotUp(
+                BmpMessageType::PeerDownNotification,
+                peer_key
+            ))
+        );
+
+        assert_eq!(tracker.update(&peer_up), Ok(()));
+        assert_eq!(tracker.update(&termination), Ok(()));
+        assert_eq!(tracker.state(), BmpSessionState::Terminated);
+        assert_eq!(tracker.peers_up().count(), 0);
+        assert_eq!(
+            tracker.update(&initiation),
+            Err(BmpSessionTrackerError::Terminated(
+                BmpMessageType::Initiation
+            ))
+        );
+    }
+}
@@ -21,8 +21,12 @@ use chrono::TimeZone;
 
 use std::{collections::HashMap, net::Ipv6Addr, ops::DerefMut, string::FromUtf8Error};
 
-use netgauze_bgp_pkt::wire::deserializer::{
-    nlri::RouteDistinguisherParsingError, BgpMessageParsingError, BgpParsingContext,
+use netgauze_bgp_pkt::{
+    capabilities::{negotiate, BgpCapability, NegotiatedCapabilities},
+    wire::deserializer::{
+        nlri::RouteDistinguisherParsingError, BgpMessageParsingError, BgpParsingContext,
+        LocatedBgpMessageParsingError,
+    },
 };
 use netgauze_iana::address_family::{
     AddressFamily, InvalidAddressType, SubsequentAddressFamily, UndefinedAddressFamily,
@@ -58,6 +62,77 @@ impl BmpParsingContext {
     pub fn new(map: HashMap<PeerKey, BgpParsingContext>) -> Self {
         Self(map)
     }
+
+    /// Key of the BGP parsing context used for the messages of the given peer
+    /// header. Pre- and post-policy messages share the same context, while
+    /// Adj-RIB-Out messages are sent by the monitored router and have their
+    /// own.
+    pub const fn peer_ctx_key(peer_header: &PeerHeader) -> PeerKey {
+        PeerKey::from_peer_header(peer_header)
+            .without_message_flags()
+            .with_adj_rib_out(peer_header.peer_type().is_adj_rib_out())
+    }
+
+    /// Get the BGP parsing context for the given peer header, the ASN4 flag is
+    /// always taken from the peer header of the message being parsed.
+    pub fn peer_ctx(&mut self, peer_header: &PeerHeader) -> &mut BgpParsingContext {
+        let bgp_ctx = self.entry(Self::peer_ctx_key(peer_header)).or_default();
+        bgp_ctx.set_asn4(peer_header.is_asn4());
+        bgp_ctx
+    }
+
+    /// Set up the BGP parsing contexts of a peer from the capabilities
+    /// exchanged in its Peer Up notification. The sent OPEN is the one of the
+    /// monitored router, i.e. the local side of the BGP session.
+    pub fn peer_up(&mut self, peer_up: &PeerUpNotificationMessage) {
+        let negotiated = negotiate(
+            &open_capabilities(peer_up.sent_message()),
+            &open_capabilities(peer_up.received_message()),
+        );
+        let peer_key = Self::peer_ctx_key(peer_up.peer_header());
+        for adj_rib_out in [false, true] {
+            let bgp_ctx = self
+                .entry(peer_key.with_adj_rib_out(adj_rib_out))
+                .or_default();
+            update_peer_parsing_ctx(bgp_ctx, &negotiated, adj_rib_out);
+        }
+    }
+
+    /// Remove all the BGP parsing contexts of a peer
+    pub fn peer_down(&mut self, peer_header: &PeerHeader) {
+        let peer_key = Self::peer_ctx_key(peer_header);
+        self.remove(&peer_key.with_adj_rib_out(false));
+        self.remove(&peer_key.with_adj_rib_out(true));
+    }
+}
+
+/// Capabilities advertised in a BGP OPEN message, empty for other messages
+pub(crate) fn open_capabilities(msg: &BgpMessage) -> Vec<BgpCapability> {
+    match msg {
+        BgpMessage::Open(open) => open.capabilities().into_iter().cloned().collect(),
+        _ => vec![],
+    }
+}
+
+/// Update a BGP parsing context with the capabilities negotiated between the
+/// monitored router and its peer.
+///
+/// ADD-PATH is directional: Adj-RIB-In messages are received by the monitored
+/// router and carry path identifiers when it negotiated to receive them, while
+/// Adj-RIB-Out messages carry them when it negotiated to send them.
+pub fn update_peer_parsing_ctx(
+    bgp_ctx: &mut BgpParsingContext,
+    negotiated: &NegotiatedCapabilities,
+    adj_rib_out: bool,
+) {
+    bgp_ctx.update_capabilities(negotiated);
+    if adj_rib_out {
+        *bgp_ctx.add_path_mut() = negotiated
+            .add_path()
+            .iter()
+            .map(|(address_type, family)| (*address_type, family.send()))
+            .collect();
+    }
 }
 
 impl Deref for BmpParsingContext {
@@ -110,6 +185,74 @@ impl<'a> ReadablePduWithOneInput<'a, &mut BmpParsingContext, LocatedBmpMessagePa
     }
 }
 
+impl<'a> ReadablePduWithOneInput<'a, &mut BmpParsingContext, LocatedBmpMessageParsingError<'a>>
+    for BmpRawMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+        ctx: &mut BmpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedBmpMessageParsingError<'a>> {
+        let input = buf;
+        let (buf, message) = BmpMessage::from_wire(buf, ctx)?;
+        let raw = input.fragment()[..input.len() - buf.len()].to_vec();
+        let tlvs = tlv_offsets(&message, &raw);
+        Ok((buf, BmpRawMessage::new(message, raw, tlvs)))
+    }
+}
+
+/// Find the information TLVs in the raw bytes of an already parsed BMP message
+fn tlv_offsets(message: &BmpMessage, raw: &[u8]) -> Vec<BmpTlvOffset> {
+    // 1-octet version, 4-octet length, and 1-octet message type
+    const COMMON_HEADER_LENGTH: usize = 6;
+    const PEER_HEADER_LENGTH: usize = 42;
+    // Offset of the length field in the BGP message header
+    const BGP_LENGTH_OFFSET: usize = 16;
+    let after_peer_header = COMMON_HEADER_LENGTH + PEER_HEADER_LENGTH;
+    let bgp_message_length = |offset: usize| {
+        raw.get(offset + BGP_LENGTH_OFFSET..offset + BGP_LENGTH_OFFSET + 2)
+            .map(|len| u16::from_be_bytes([len[0], len[1]]) as usize)
+    };
+    let BmpMessage::V3(value) = message;
+    let start = match value {
+        BmpMessageValue::Initiation(_) => Some(COMMON_HEADER_LENGTH),
+        BmpMessageValue::Termination(_) | BmpMessageValue::RouteMirroring(_) => {
+            Some(after_peer_header)
+        }
+        // 4-octet stats count
+        BmpMessageValue::StatisticsReport(_) => Some(after_peer_header + 4),
+        BmpMessageValue::PeerDownNotification(peer_down) => match peer_down.reason() {
+            // 1-octet reason code
+            PeerDownNotificationReason::LocalSystemClosedTlvDataFollows(_) => {
+                Some(after_peer_header + 1)
+            }
+            _ => None,
+        },
+        BmpMessageValue::PeerUpNotification(_) => {
+            // 16-octet local address, 2-octet local port, and 2-octet remote port
+            let sent = after_peer_header + 20;
+            bgp_message_length(sent)
+                .and_then(|sent_len| Some(sent + sent_len + bgp_message_length(sent + sent_len)?))
+        }
+        BmpMessageValue::RouteMonitoring(_)
+        | BmpMessageValue::Experimental251(_)
+        | BmpMessageValue::Experimental252(_)
+        | BmpMessageValue::Experimental253(_)
+        | BmpMessageValue::Experimental254(_) => None,
+    };
+    let mut tlvs = vec![];
+    let mut offset = match start {
+        Some(offset) => offset,
+        None => return tlvs,
+    };
+    while let Some(header) = raw.get(offset..offset + 4) {
+        let tlv_type = u16::from_be_bytes([header[0], header[1]]);
+        let length = u16::from_be_bytes([header[2], header[3]]);
+        tlvs.push(BmpTlvOffset::new(tlv_type, offset, length));
+        offset += 4 + length as usize;
+    }
+    tlvs
+}
+
 #[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum BmpMessageValueParsingError {
     #[serde(with = "ErrorKindSerdeDeref")]
@@ -304,9 +447,7 @@ impl<'a>
         ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedRouteMonitoringMessageParsingError<'a>> {
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_ctx(&peer_header);
         let input = buf;
         let (buf, update_message): (Span<'_>, BgpMessage) =
             parse_into_located_one_input(buf, bgp_ctx)?;
@@ -346,11 +487,12 @@ impl<'a> ReadablePdu<'a, LocatedBmpPeerTypeParsingError<'a>> for BmpPeerType {
         let (buf, peer_type_code) =
             nom::combinator::map_res(be_u8, BmpPeerTypeCode::try_from)(buf)?;
         let (buf, flags) = be_u8(buf)?;
-        let ipv6 = flags & PEER_FLAGS_IS_IPV6 == PEER_FLAGS_IS_IPV6;
-        let post_policy = flags & PEER_FLAGS_IS_POST_POLICY == PEER_FLAGS_IS_POST_POLICY;
-        let asn2 = flags & PEER_FLAGS_IS_ASN2 == PEER_FLAGS_IS_ASN2;
-        let adj_rib_out = flags & PEER_FLAGS_IS_ADJ_RIB_OUT == PEER_FLAGS_IS_ADJ_RIB_OUT;
-        let filtered = flags & PEER_FLAGS_IS_FILTERED == PEER_FLAGS_IS_FILTERED;
+        let peer_flags = BmpPeerFlags::from_bits(flags);
+        let ipv6 = peer_flags.is_ipv6();
+        let post_policy = peer_flags.is_post_policy();
+        let asn2 = peer_flags.is_asn2();
+        let adj_rib_out = peer_flags.is_adj_rib_out();
+        let filtered = peer_flags.is_filtered();
         let peer_type = match peer_type_code {
             BmpPeerTypeCode::GlobalInstancePeer => BmpPeerType::GlobalInstancePeer {
                 ipv6,
@@ -481,7 +623,6 @@ impl<'a>
     ) -> IResult<Span<'a>, Self, LocatedPeerUpNotificationMessageParsingError<'a>> {
         let input = buf;
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
         let ipv6 = match check_is_ipv6(&peer_header.peer_type) {
             Ok(ipv6) => ipv6,
             Err(code) => {
@@ -514,11 +655,8 @@ impl<'a>
         } else {
             Some(remote_port)
         };
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
-        let (buf, sent_message) = parse_into_located_one_input(buf, bgp_ctx)?;
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_ctx(&peer_header);
+        let (buf, sent_message) = parse_into_located_one_input(buf, &mut *bgp_ctx)?;
         let (buf, received_message) = parse_into_located_one_input(buf, bgp_ctx)?;
         let (buf, information) = parse_till_empty_into_located(buf)?;
         let peer_up_msg = PeerUpNotificationMessage::build(
@@ -566,9 +704,7 @@ impl<'a>
     ) -> IResult<Span<'a>, Self, LocatedPeerDownNotificationMessageParsingError<'a>> {
         let input = buf;
         let (buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_ctx(&peer_header);
         let (buf, reason) = parse_into_located_one_input(buf, bgp_ctx)?;
         let msg = PeerDownNotificationMessage::build(peer_header, reason);
         match msg {
@@ -694,12 +830,29 @@ impl<'a>
         ctx: &mut BmpParsingContext,
     ) -> IResult<Span<'a>, Self, LocatedRouteMirroringMessageParsingError<'a>> {
         let (mut buf, peer_header): (Span<'_>, PeerHeader) = parse_into_located(buf)?;
-        let peer_key = PeerKey::from_peer_header(&peer_header);
-        let bgp_ctx = ctx.entry(peer_key).or_default();
-        bgp_ctx.set_asn4(peer_header.is_asn4());
+        let bgp_ctx = ctx.peer_ctx(&peer_header);
         let mut mirrored = Vec::new();
+        let mut errored_pdu = false;
         while !buf.is_empty() {
             let (tmp, element) = parse_into_located_one_input(buf, &mut *bgp_ctx)?;
+            let element = match element {
+                RouteMirroringValue::Information(RouteMirroringInformation::ErroredPdu) => {
+                    errored_pdu = true;
+                    element
+                }
+                RouteMirroringValue::BgpMessage(msg) if errored_pdu => {
+                    // Keep the PDU exactly as sent by the router, skipping the 2-octets type
+                    // and 2-octets length of the TLV
+                    let consumed = buf.len() - tmp.len();
+                    let raw = buf.fragment()[4..consumed].to_vec();
+                    let parsed = match msg {
+                        MirroredBgpMessage::Parsed(parsed) => Some(parsed),
+                        _ => None,
+                    };
+                    RouteMirroringValue::BgpMessage(MirroredBgpMessage::Errored { raw, parsed })
+                }
+                element => element,
+            };
             mirrored.push(element);
             buf = tmp;
         }
@@ -731,11 +884,23 @@ impl<'a>
         let (reminder, buf) = nom::multi::length_data(be_u16)(buf)?;
         let (buf, value) = match code {
             RouteMirroringTlvType::BgpMessage => {
-                let (buf, msg) = parse_into_located_one_input(buf, bgp_ctx)?;
-                (
-                    buf,
-                    RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(msg)),
-                )
+                // Mirrored messages could be malformed, so when the BGP PDU cannot be parsed
+                // the raw bytes are kept instead of failing the whole BMP message
+                let parsed: IResult<Span<'a>, BgpMessage, LocatedBgpMessageParsingError<'a>> =
+                    parse_into_located_one_input(buf, bgp_ctx);
+                match parsed {
+                    Ok((buf, msg)) => (
+                        buf,
+                        RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(msg)),
+                    ),
+                    Err(_) => {
+                        let (buf, data) = nom::bytes::complete::take(length)(buf)?;
+                        (
+                            buf,
+                            RouteMirroringValue::BgpMessage(MirroredBgpMessage::Raw(data.to_vec())),
+                        )
+                    }
+                }
             }
             RouteMirroringTlvType::Information => {
                 let (buf, information) =
@@ -53,6 +53,21 @@ impl WritablePdu<BmpMessageWritingError> for BmpMessage {
     }
 }
 
+impl WritablePdu<BmpMessageWritingError> for BmpRawMessage {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self) -> usize {
+        self.raw().len()
+    }
+
+    /// Write the original bytes of the message, modifications to the parsed
+    /// message are not reflected
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), BmpMessageWritingError> {
+        writer.write_all(self.raw())?;
+        Ok(())
+    }
+}
+
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum BmpMessageValueWritingError {
     StdIOError(#[from_std_io_error] String),
@@ -130,29 +145,6 @@ impl WritablePdu<RouteMirroringMessageWritingError> for RouteMirroringMessage {
     }
 }
 
-#[inline]
-const fn compute_peer_flags_value(
-    ipv6: bool,
-    post_policy: bool,
-    asn2: bool,
-    adj_rib_out: bool,
-) -> u8 {
-    let mut flags = 0;
-    if ipv6 {
-        flags |= PEER_FLAGS_IS_IPV6;
-    }
-    if post_policy {
-        flags |= PEER_FLAGS_IS_POST_POLICY;
-    }
-    if asn2 {
-        flags |= PEER_FLAGS_IS_ASN2
-    }
-    if adj_rib_out {
-        flags |= PEER_FLAGS_IS_ADJ_RIB_OUT
-    }
-    flags
-}
-
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum BmpPeerTypeWritingError {
     StdIOError(#[from_std_io_error] String),
@@ -160,37 +152,7 @@ pub enum BmpPeerTypeWritingError {
 
 impl BmpPeerType {
     pub fn get_flags_value(&self) -> u8 {
-        match self {
-            Self::GlobalInstancePeer {
-                ipv6,
-                post_policy,
-                asn2,
-                adj_rib_out,
-            }
-            | Self::RdInstancePeer {
-                ipv6,
-                post_policy,
-                asn2,
-                adj_rib_out,
-            }
-            | Self::LocalInstancePeer {
-                ipv6,
-                post_policy,
-                asn2,
-                adj_rib_out,
-            } => compute_peer_flags_value(*ipv6, *post_policy, *asn2, *adj_rib_out),
-            Self::LocRibInstancePeer { filtered } => {
-                if *filtered {
-                    PEER_FLAGS_IS_FILTERED
-                } else {
-                    0
-                }
-            }
-            Self::Experimental251 { flags }
-            | Self::Experimental252 { flags }
-            | Self::Experimental253 { flags }
-            | Self::Experimental254 { flags } => *flags,
-        }
+        self.flags().bits()
     }
 }
 
@@ -249,13 +211,9 @@ impl WritablePdu<PeerHeaderWritingError> for PeerHeader {
         }
         writer.write_u32::<NetworkEndian>(self.peer_as())?;
         writer.write_all(&self.bgp_id().octets())?;
-        match self.timestamp() {
-            None => writer.write_u64::<NetworkEndian>(0)?,
-            Some(time) => {
-                writer.write_u32::<NetworkEndian>(time.timestamp() as u32)?;
-                writer.write_u32::<NetworkEndian>(time.timestamp_subsec_micros())?;
-            }
-        }
+        let (secs, micros) = self.raw_timestamp();
+        writer.write_u32::<NetworkEndian>(secs)?;
+        writer.write_u32::<NetworkEndian>(micros)?;
         Ok(())
     }
 }
@@ -276,6 +234,7 @@ impl WritablePdu<RouteMirroringValueWritingError> for RouteMirroringValue {
                 Self::BgpMessage(msg) => match msg {
                     MirroredBgpMessage::Parsed(msg) => msg.len(),
                     MirroredBgpMessage::Raw(msg) => msg.len(),
+                    MirroredBgpMessage::Errored { raw, .. } => raw.len(),
                 },
                 Self::Information(_) => 2, // Information are always 2-octets
                 Self::Experimental65531(value) => value.len(),
@@ -292,6 +251,7 @@ impl WritablePdu<RouteMirroringValueWritingError> for RouteMirroringValue {
             Self::BgpMessage(msg) => match msg {
                 MirroredBgpMessage::Parsed(msg) => msg.write(writer)?,
                 MirroredBgpMessage::Raw(raw) => writer.write_all(raw)?,
+                MirroredBgpMessage::Errored { raw, .. } => writer.write_all(raw)?,
             },
             Self::Information(info) => writer.write_u16::<NetworkEndian>((*info).into())?,
             Self::Experimental65531(value) => writer.write_all(value)?,
@@ -14,7 +14,7 @@
 // limitations under the License.
 
 #[cfg(not(feature = "fuzz"))]
-use chrono::TimeZone;
+use chrono::{TimeDelta, TimeZone};
 use ipnet::Ipv4Net;
 use netgauze_bgp_pkt::{
     capabilities::{
@@ -42,7 +42,7 @@ use netgauze_parse_utils::{
         test_parse_error, test_parse_error_with_one_input, test_parsed_completely,
         test_parsed_completely_with_one_input, test_write,
     },
-    Span,
+    ReadablePduWithOneInput, Span,
 };
 use nom::error::ErrorKind;
 use std::{net::Ipv6Addr, str::FromStr};
@@ -180,6 +180,93 @@ fn test_peer_type() -> Result<(), PeerHeaderWritingError> {
     Ok(())
 }
 
+#[test]
+fn test_peer_flags() {
+    let rd_instance = BmpPeerType::RdInstancePeer {
+        ipv6: true,
+        post_policy: false,
+        asn2: true,
+        adj_rib_out: true,
+    };
+    let flags = rd_instance.flags();
+    assert_eq!(flags.bits(), 0xb0);
+    assert_eq!(
+        flags,
+        BmpPeerFlags::IPV6 | BmpPeerFlags::ASN2 | BmpPeerFlags::ADJ_RIB_OUT
+    );
+    assert!(flags.is_ipv6());
+    assert!(!flags.is_post_policy());
+    assert!(flags.is_adj_rib_out());
+    assert_eq!(rd_instance.get_flags_value(), flags.bits());
+
+    let loc_rib = BmpPeerType::LocRibInstancePeer { filtered: true };
+    assert_eq!(loc_rib.flags(), BmpPeerFlags::FILTERED);
+    assert!(loc_rib.is_filtered());
+    assert!(!loc_rib.is_post_policy());
+    assert_eq!(
+        BmpPeerType::Experimental251 { flags: 0x0f }.flags(),
+        BmpPeerFlags::from_bits(0x0f)
+    );
+
+    let peer_header = PeerHeader::new(
+        rd_instance,
+        Some(RouteDistinguisher::As2Administrator {
+            asn2: 100,
+            number: 1,
+        }),
+        None,
+        200,
+        Ipv4Addr::new(172, 16, 0, 20),
+        None,
+    );
+    assert!(peer_header.is_adj_rib_out());
+    assert!(!peer_header.is_post_policy());
+    assert!(!peer_header.is_asn4());
+    assert_eq!(peer_header.peer_distinguisher(), 0x0000_0064_0000_0001);
+    let peer_header = PeerHeader::new(loc_rib, None, None, 200, Ipv4Addr::UNSPECIFIED, None);
+    assert!(peer_header.is_filtered());
+    assert_eq!(peer_header.peer_distinguisher(), 0);
+}
+
+#[test]
+fn test_peer_header_timestamp() -> Result<(), PeerHeaderWritingError> {
+    let timestamp = Utc.timestamp_opt(1664821826, 645593000).unwrap();
+    let peer_header = |timestamp| {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+            200,
+            Ipv4Addr::new(172, 16, 0, 20),
+            timestamp,
+        )
+    };
+    let with_timestamp = peer_header(Some(timestamp));
+    let without_timestamp = peer_header(None);
+
+    assert_eq!(with_timestamp.raw_timestamp(), (1664821826, 645593));
+    assert_eq!(without_timestamp.raw_timestamp(), (0, 0));
+    assert_eq!(
+        with_timestamp.latency(timestamp + TimeDelta::microseconds(1500)),
+        Some(TimeDelta::microseconds(1500))
+    );
+    assert_eq!(without_timestamp.latency(timestamp), None);
+
+    let good_wire = [
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10, 0x00, 0x14, 0x00, 0x00, 0x00, 0xc8,
+        0xac, 0x10, 0x00, 0x14, 0x63, 0x3b, 0x2a, 0x42, 0x00, 0x09, 0xd9, 0xd9,
+    ];
+    test_parsed_completely(&good_wire, &with_timestamp);
+    test_write(&with_timestamp, &good_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_peer_header() -> Result<(), PeerHeaderWritingError> {
     let good_ipv4_wire = [
@@ -802,6 +889,54 @@ fn test_bmp_value_peer_up_notification() -> Result<(), BmpMessageValueWritingErr
     Ok(())
 }
 
+#[test]
+fn test_bmp_raw_message() -> Result<(), BmpMessageWritingError> {
+    let peer_up_value = [
+        0x03, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xfc, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0xfc,
+        0x00, 0x0a, 0x00, 0x00, 0x01, 0x63, 0x3b, 0x2a, 0x42, 0x00, 0x09, 0xd9, 0xd9, 0xfc, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00,
+        0xb3, 0x74, 0x8a, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0x00, 0x1d, 0x01, 0x04, 0xfc, 0x00, 0x00, 0xb4, 0x0a, 0x00, 0x00,
+        0x03, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0x00, 0x1d, 0x01, 0x04, 0xfc, 0x00, 0x00, 0xb4, 0x0a, 0x00, 0x00, 0x01,
+        0x00, 0x00, 0x00, 0x00, 0x02, 0x68, 0x69, 0x00, 0x02, 0x00, 0x03, 0x50, 0x45, 0x31,
+    ];
+    let mut good_wire = vec![0x03];
+    good_wire.extend_from_slice(&(5 + peer_up_value.len() as u32).to_be_bytes());
+    good_wire.extend_from_slice(&peer_up_value);
+    let tlvs_offset = good_wire.len() - 13;
+
+    let (remainder, raw) =
+        BmpRawMessage::from_wire(Span::new(&good_wire), &mut BmpParsingContext::default()).unwrap();
+    assert!(remainder.is_empty());
+    assert_eq!(raw.raw(), &good_wire);
+    assert_eq!(
+        raw.tlvs(),
+        &vec![
+            BmpTlvOffset::new(0, tlvs_offset, 2),
+            BmpTlvOffset::new(2, tlvs_offset + 6, 3),
+        ]
+    );
+    assert_eq!(
+        raw.tlv_bytes(&raw.tlvs()[1]),
+        Some([0x00, 0x02, 0x00, 0x03, 0x50, 0x45, 0x31].as_slice())
+    );
+    match raw.message() {
+        BmpMessage::V3(BmpMessageValue::PeerUpNotification(peer_up)) => {
+            assert_eq!(peer_up.system_name(), Some("PE1"));
+        }
+        msg => panic!("unexpected message {msg:?}"),
+    }
+
+    // The original bytes are written even when the parsed message is modified
+    let mut modified = raw.clone();
+    *modified.message_mut() =
+        BmpMessage::V3(BmpMessageValue::Initiation(InitiationMessage::new(vec![])));
+    test_write(&modified, &good_wire)?;
+    Ok(())
+}
+
 #[test]
 fn test_bmp_peer_up_loc_rib_notification() -> Result<(), BmpMessageWritingError> {
     let good_wire = [
@@ -866,6 +1001,81 @@ fn test_bmp_peer_up_loc_rib_notification() -> Result<(), BmpMessageWritingError>
     Ok(())
 }
 
+#[test]
+fn test_peer_up_information_values() {
+    let open = BgpMessage::Open(BgpOpenMessage::new(
+        64512,
+        180,
+        Ipv4Addr::new(192, 0, 2, 1),
+        vec![],
+    ));
+    let peer_up = PeerUpNotificationMessage::build(
+        PeerHeader::new(
+            BmpPeerType::LocRibInstancePeer { filtered: false },
+            None,
+            None,
+            64512,
+            Ipv4Addr::new(192, 0, 2, 1),
+            None,
+        ),
+        None,
+        None,
+        None,
+        open.clone(),
+        open,
+        vec![
+            InitiationInformation::VrfTableName("vrf1".to_string()),
+            InitiationInformation::AdminLabel("core".to_string()),
+            InitiationInformation::SystemName("router1".to_string()),
+            InitiationInformation::AdminLabel("edge".to_string()),
+        ],
+    )
+    .unwrap();
+
+    assert_eq!(peer_up.vrf_table_name(), Some("vrf1"));
+    assert_eq!(peer_up.system_name(), Some("router1"));
+    assert_eq!(peer_up.system_description(), None);
+    assert_eq!(
+        peer_up.admin_labels().collect::<Vec<_>>(),
+        vec!["core", "edge"]
+    );
+}
+
+#[test]
+fn test_peer_down_reason_values() {
+    let cease = BgpNotificationMessage::CeaseError(CeaseError::PeerDeConfigured { value: vec![] });
+    let local_pdu = PeerDownNotificationReason::LocalSystemClosedNotificationPduFollows(
+        BgpMessage::Notification(cease.clone()),
+    );
+    let hold_timer = PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(10);
+    let no_event = PeerDownNotificationReason::LocalSystemClosedFsmEventFollows(0);
+    let vrf = PeerDownNotificationReason::LocalSystemClosedTlvDataFollows(
+        InitiationInformation::VrfTableName("vrf1".to_string()),
+    );
+
+    assert_eq!(local_pdu.notification(), Some(&cease));
+    assert_eq!(local_pdu.fsm_event(), None);
+    assert_eq!(
+        hold_timer.fsm_event(),
+        Some(BgpFsmEventCode::HoldTimerExpires)
+    );
+    assert_eq!(hold_timer.notification(), None);
+    assert_eq!(no_event.fsm_event(), None);
+    assert_eq!(vrf.vrf_table_name(), Some("vrf1"));
+    assert_eq!(
+        PeerDownNotificationReason::PeerDeConfigured.vrf_table_name(),
+        None
+    );
+    assert_eq!(
+        InitiationInformation::AdminLabel("core".to_string()).as_str(),
+        Some("core")
+    );
+    assert_eq!(
+        InitiationInformation::Experimental65531(vec![1]).as_str(),
+        None
+    );
+}
+
 #[test]
 fn test_peer_down_reason() -> Result<(), PeerDownNotificationReasonWritingError> {
     let notif = BgpMessage::Notification(BgpNotificationMessage::CeaseError(
@@ -1180,6 +1390,11 @@ fn test_router_mirroring_value() -> Result<(), RouteMirroringValueWritingError>
         0x00, 0x00, 0x00, 0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
         0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x13, 0x04,
     ];
+    // Undefined BGP message type
+    let good_malformed_bgp_wire = [
+        0x00, 0x00, 0x00, 0x13, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x13, 0xff,
+    ];
     let good_information_wire = [0, 1, 0, 2, 0, 0];
     let good_experimental_65531_wire = [0xff, 0xfb, 0, 2, 1, 2];
     let good_experimental_65532_wire = [0xff, 0xfc, 0, 2, 1, 2];
@@ -1188,6 +1403,9 @@ fn test_router_mirroring_value() -> Result<(), RouteMirroringValueWritingError>
 
     let good_bgp =
         RouteMirroringValue::BgpMessage(MirroredBgpMessage::Parsed(BgpMessage::KeepAlive));
+    let good_malformed_bgp = RouteMirroringValue::BgpMessage(MirroredBgpMessage::Raw(
+        good_malformed_bgp_wire[4..].to_vec(),
+    ));
     let good_information = RouteMirroringValue::Information(RouteMirroringInformation::ErroredPdu);
     let good_experimental_65531 = RouteMirroringValue::Experimental65531(vec![1, 2]);
     let good_experimental_65532 = RouteMirroringValue::Experimental65532(vec![1, 2]);
@@ -1225,7 +1443,14 @@ fn test_router_mirroring_value() -> Result<(), RouteMirroringValueWritingError>
         &good_experimental_65534,
     );
 
+    test_parsed_completely_with_one_input(
+        &good_malformed_bgp_wire,
+        &mut BgpParsingContext::default(),
+        &good_malformed_bgp,
+    );
+
     test_write(&good_bgp, &good_bgp_wire)?;
+    test_write(&good_malformed_bgp, &good_malformed_bgp_wire)?;
     test_write(&good_information, &good_information_wire)?;
     test_write(&good_experimental_65531, &good_experimental_65531_wire)?;
     test_write(&good_experimental_65532, &good_experimental_65532_wire)?;
@@ -1234,6 +1459,79 @@ fn test_router_mirroring_value() -> Result<(), RouteMirroringValueWritingError>
     Ok(())
 }
 
+#[test]
+fn test_bmp_router_mirroring_errored_pdu() -> Result<(), BmpMessageWritingError> {
+    let keepalive = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x13, 0x04,
+    ];
+    let malformed = [
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x13, 0xff,
+    ];
+    let header = [
+        0x03, 0x00, 0x00, 0x00, 0x4d, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xac, 0x10,
+        0x00, 0x14, 0x00, 0x00, 0x00, 0xc8, 0xac, 0x10, 0x00, 0x14, 0x63, 0x3c, 0x98, 0x8b, 0x00,
+        0x04, 0x5a, 0xae, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x13,
+    ];
+    let peer_header = PeerHeader::new(
+        BmpPeerType::GlobalInstancePeer {
+            ipv6: false,
+            post_policy: false,
+            asn2: false,
+            adj_rib_out: false,
+        },
+        None,
+        Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+        200,
+        Ipv4Addr::new(172, 16, 0, 20),
+        Some(Utc.timestamp_opt(1664915595, 285358000).unwrap()),
+    );
+    let good_parsed_wire = [header.as_slice(), keepalive.as_slice()].concat();
+    let good_malformed_wire = [header.as_slice(), malformed.as_slice()].concat();
+
+    let good_parsed = BmpMessage::V3(BmpMessageValue::RouteMirroring(RouteMirroringMessage::new(
+        peer_header.clone(),
+        vec![
+            RouteMirroringValue::Information(RouteMirroringInformation::ErroredPdu),
+            RouteMirroringValue::BgpMessage(MirroredBgpMessage::Errored {
+                raw: keepalive.to_vec(),
+                parsed: Some(BgpMessage::KeepAlive),
+            }),
+        ],
+    )));
+    let good_malformed =
+        BmpMessage::V3(BmpMessageValue::RouteMirroring(RouteMirroringMessage::new(
+            peer_header,
+            vec![
+                RouteMirroringValue::Information(RouteMirroringInformation::ErroredPdu),
+                RouteMirroringValue::BgpMessage(MirroredBgpMessage::Errored {
+                    raw: malformed.to_vec(),
+                    parsed: None,
+                }),
+            ],
+        )));
+
+    test_parsed_completely_with_one_input(&good_parsed_wire, &mut Default::default(), &good_parsed);
+    test_parsed_completely_with_one_input(
+        &good_malformed_wire,
+        &mut Default::default(),
+        &good_malformed,
+    );
+    test_write(&good_parsed, &good_parsed_wire)?;
+    test_write(&good_malformed, &good_malformed_wire)?;
+
+    if let BmpMessage::V3(BmpMessageValue::RouteMirroring(msg)) = good_malformed {
+        assert!(msg.is_errored_pdu());
+        assert!(!msg.is_messages_lost());
+        let bgp = msg.bgp_message().unwrap();
+        assert_eq!(bgp.raw(), Some(&malformed.to_vec()));
+        assert_eq!(bgp.parsed(), None);
+    }
+    Ok(())
+}
+
 #[test]
 fn test_bmp_router_mirroring() -> Result<(), BmpMessageWritingError> {
     let good_wire = [
@@ -1418,6 +1716,122 @@ fn test_statistics_counter() -> Result<(), StatisticsCounterMessageWritingError>
     Ok(())
 }
 
+#[test]
+fn test_statistics_counter_values() {
+    let counter = StatisticsCounter::NumberOfDuplicatePrefixAdvertisements(CounterU32::new(5));
+    let per_afi = StatisticsCounter::NumberOfRoutesInPerAfiSafiLocRib(
+        AddressType::Ipv6Unicast,
+        GaugeU64::new(1000),
+    );
+    let experimental = StatisticsCounter::Experimental65531(vec![0, 1]);
+    let unknown = StatisticsCounter::Unknown(100, vec![0, 1, 2]);
+
+    assert_eq!(counter.value(), Some(5));
+    assert_eq!(counter.address_type(), None);
+    assert_eq!(counter.raw_value(), None);
+    assert_eq!(
+        counter.name(),
+        "Number of (known) duplicate prefix advertisements"
+    );
+    assert_eq!(per_afi.value(), Some(1000));
+    assert_eq!(per_afi.address_type(), Some(AddressType::Ipv6Unicast));
+    assert_eq!(per_afi.name(), "Number of routes in per-AFI/SAFI Loc-RIB");
+    assert_eq!(experimental.value(), None);
+    assert_eq!(experimental.raw_value(), Some(&vec![0, 1]));
+    assert_eq!(experimental.name(), "Experimental");
+    assert_eq!(unknown.value(), None);
+    assert_eq!(unknown.raw_value(), Some(&vec![0, 1, 2]));
+    assert_eq!(unknown.name(), "Unknown");
+}
+
+#[test]
+fn test_statistics_report_delta() {
+    let peer_header = |post_policy, timestamp| {
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 20))),
+            200,
+            Ipv4Addr::new(172, 16, 0, 20),
+            Some(Utc.timestamp_opt(timestamp, 0).unwrap()),
+        )
+    };
+    let previous = StatisticsReportMessage::new(
+        peer_header(false, 1664915020),
+        vec![
+            StatisticsCounter::NumberOfDuplicateWithdraws(CounterU32::new(u32::MAX - 1)),
+            StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(100)),
+            StatisticsCounter::NumberOfRoutesInPerAfiSafiLocRib(
+                AddressType::Ipv4Unicast,
+                GaugeU64::new(10),
+            ),
+            StatisticsCounter::Experimental65531(vec![1]),
+        ],
+    );
+    let current = StatisticsReportMessage::new(
+        peer_header(true, 1664915080),
+        vec![
+            StatisticsCounter::NumberOfDuplicateWithdraws(CounterU32::new(3)),
+            StatisticsCounter::NumberOfRoutesInAdjRibIn(GaugeU64::new(40)),
+            StatisticsCounter::NumberOfRoutesInPerAfiSafiLocRib(
+                AddressType::Ipv4Unicast,
+                GaugeU64::new(15),
+            ),
+            StatisticsCounter::NumberOfRoutesInPerAfiSafiLocRib(
+                AddressType::Ipv6Unicast,
+                GaugeU64::new(5),
+            ),
+            StatisticsCounter::Experimental65531(vec![2]),
+        ],
+    );
+    let other_peer = StatisticsReportMessage::new(
+        PeerHeader::new(
+            BmpPeerType::GlobalInstancePeer {
+                ipv6: false,
+                post_policy: false,
+                asn2: false,
+                adj_rib_out: false,
+            },
+            None,
+            Some(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 30))),
+            200,
+            Ipv4Addr::new(172, 16, 0, 30),
+            None,
+        ),
+        vec![],
+    );
+
+    assert_eq!(
+        current.delta(&previous),
+        Ok(vec![
+            StatisticsCounterDelta::new(
+                BmpStatisticsType::NumberOfDuplicateWithdraws,
+                None,
+                StatisticsDelta::Counter(5),
+            ),
+            StatisticsCounterDelta::new(
+                BmpStatisticsType::NumberOfRoutesInAdjRibIn,
+                None,
+                StatisticsDelta::Gauge(-60),
+            ),
+            StatisticsCounterDelta::new(
+                BmpStatisticsType::NumberOfRoutesInPerAfiSafiLocRib,
+                Some(AddressType::Ipv4Unicast),
+                StatisticsDelta::Gauge(5),
+            ),
+        ])
+    );
+    assert!(matches!(
+        current.delta(&other_peer),
+        Err(StatisticsDeltaError::DifferentPeers(_, _))
+    ));
+}
+
 #[test]
 fn test_bmp_stats() -> Result<(), BmpMessageWritingError> {
     let good_wire = [
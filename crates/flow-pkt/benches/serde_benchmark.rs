@@ -1,8 +1,13 @@
-use std::{cell::RefCell, collections::HashMap, io::Cursor, rc::Rc};
+use std::{cell::RefCell, io::Cursor, rc::Rc};
 
 use criterion::{criterion_group, criterion_main, Criterion};
 
-use netgauze_flow_pkt::{ipfix::IpfixPacket, FieldSpecifier};
+use netgauze_flow_pkt::{
+    ie::{Field, IE},
+    ipfix::{IpfixPacket, Set, TemplatesMap},
+    template_cache::TemplateCache,
+    wire::deserializer::lazy::{LazyIpfixPacket, LazySet},
+};
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePduWithOneInput};
 
 const IPFIX_PKT_TEMPLATE_RAW: &[u8] = &[
@@ -156,31 +161,88 @@ const IPFIX_PKT_DATA_PKT_ONLY: &[u8] = &[
     0x06, 0x02, 0x04, 0x00
 ];
 
-pub fn test_parse(
-    span: Span<'_>,
-    templates_map: Rc<RefCell<HashMap<u16, Rc<(Vec<FieldSpecifier>, Vec<FieldSpecifier>)>>>>,
-) {
+pub fn test_parse(span: Span<'_>, templates_map: TemplatesMap) {
     let x = IpfixPacket::from_wire(span, templates_map);
     x.unwrap();
 }
 
+/// Read a few fields out of every record with the eager decoding
+pub fn test_parse_fields(span: Span<'_>, templates_map: TemplatesMap) -> usize {
+    let (_, pkt) = IpfixPacket::from_wire(span, templates_map).unwrap();
+    pkt.sets()
+        .iter()
+        .filter_map(|set| match set {
+            Set::Data { records, .. } => Some(records),
+            _ => None,
+        })
+        .flatten()
+        .flat_map(|record| record.fields())
+        .filter(|field| {
+            matches!(
+                field,
+                Field::sourceIPv4Address(_)
+                    | Field::destinationIPv4Address(_)
+                    | Field::octetDeltaCount(_)
+            )
+        })
+        .count()
+}
+
+/// Read a few fields out of every record with the lazy decoding
+pub fn test_parse_fields_lazy(span: Span<'_>, templates_map: TemplatesMap) -> usize {
+    let (_, pkt) = LazyIpfixPacket::from_wire(span, templates_map).unwrap();
+    pkt.sets()
+        .iter()
+        .filter_map(|set| match set {
+            LazySet::Data(data) => Some(data),
+            _ => None,
+        })
+        .flat_map(|data| data.records())
+        .map(|record| {
+            let record = record.unwrap();
+            [
+                IE::sourceIPv4Address,
+                IE::destinationIPv4Address,
+                IE::octetDeltaCount,
+            ]
+            .iter()
+            .filter_map(|ie| record.field(ie))
+            .map(Result::unwrap)
+            .count()
+        })
+        .sum()
+}
+
+/// Decode all the records with the lazy decoding, for comparison with reading
+/// only a few fields
+pub fn test_decode_lazy(span: Span<'_>, templates_map: TemplatesMap) -> usize {
+    let (_, pkt) = LazyIpfixPacket::from_wire(span, templates_map).unwrap();
+    pkt.sets()
+        .iter()
+        .filter_map(|set| match set {
+            LazySet::Data(data) => Some(data.decode().unwrap().len()),
+            _ => None,
+        })
+        .sum()
+}
+
 pub fn test_serialize(pkt: &IpfixPacket, cursor: &mut Cursor<&mut [u8]>) {
     let _x = pkt.write(cursor, None);
 }
 
 pub fn criterion_benchmark(c: &mut Criterion) {
-    let template_span = Span::new(&IPFIX_PKT_TEMPLATE_RAW);
-    let options_template_span = Span::new(&IPFIX_PKT_OPTIONS_TEMPLATE_RAW);
-    let mixed_span = Span::new(&IPFIX_PKT_MIXED);
-    let data_span = Span::new(&IPFIX_PKT_DATA_PKT_ONLY);
+    let template_span = Span::new(IPFIX_PKT_TEMPLATE_RAW);
+    let options_template_span = Span::new(IPFIX_PKT_OPTIONS_TEMPLATE_RAW);
+    let mixed_span = Span::new(IPFIX_PKT_MIXED);
+    let data_span = Span::new(IPFIX_PKT_DATA_PKT_ONLY);
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     c.bench_function("Deserialize IPFIX pkt with template only pkt", |b| {
         b.iter(|| test_parse(template_span, templates_map.clone()))
     });
 
     let (_, pkt) =
-        IpfixPacket::from_wire(template_span, Rc::new(RefCell::new(HashMap::new()))).unwrap();
+        IpfixPacket::from_wire(template_span, Rc::new(RefCell::new(TemplateCache::new()))).unwrap();
     let mut buf: [u8; 1024] = [0; 1024];
     c.bench_function("Serialize IPFIX pkt with template only pkt", |b| {
         b.iter(|| {
@@ -189,14 +251,16 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     c.bench_function("Deserialize IPFIX with options template only pkt", |b| {
         b.iter(|| test_parse(options_template_span, templates_map.clone()))
     });
 
-    let (_, pkt) =
-        IpfixPacket::from_wire(options_template_span, Rc::new(RefCell::new(HashMap::new())))
-            .unwrap();
+    let (_, pkt) = IpfixPacket::from_wire(
+        options_template_span,
+        Rc::new(RefCell::new(TemplateCache::new())),
+    )
+    .unwrap();
     let mut buf: [u8; 1024] = [0; 1024];
     c.bench_function("Serialize IPFIX with options template only pkt", |b| {
         b.iter(|| {
@@ -205,13 +269,13 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     c.bench_function("Deserialize IPFIX mixed with all set types", |b| {
         b.iter(|| test_parse(mixed_span, templates_map.clone()))
     });
 
     let (_, pkt) =
-        IpfixPacket::from_wire(mixed_span, Rc::new(RefCell::new(HashMap::new()))).unwrap();
+        IpfixPacket::from_wire(mixed_span, Rc::new(RefCell::new(TemplateCache::new()))).unwrap();
     let mut buf: [u8; 1024] = [0; 1024];
     c.bench_function("Serialize IPFIX  IPFIX mixed with all set types", |b| {
         b.iter(|| {
@@ -221,12 +285,25 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     });
 
     // Initialize the templates
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     IpfixPacket::from_wire(mixed_span, templates_map.clone()).unwrap();
     c.bench_function("Deserialize IPFIX mixed with data only", |b| {
         b.iter(|| test_parse(data_span, templates_map.clone()))
     });
 
+    c.bench_function("Read 3 fields from IPFIX mixed with data only", |b| {
+        b.iter(|| test_parse_fields(data_span, templates_map.clone()))
+    });
+
+    c.bench_function("Lazy read 3 fields from IPFIX mixed with data only", |b| {
+        b.iter(|| test_parse_fields_lazy(data_span, templates_map.clone()))
+    });
+
+    c.bench_function(
+        "Lazy decode all fields from IPFIX mixed with data only",
+        |b| b.iter(|| test_decode_lazy(data_span, templates_map.clone())),
+    );
+
     let (_, pkt) = IpfixPacket::from_wire(data_span, templates_map).unwrap();
     let mut buf: [u8; 1024] = [0; 1024];
     c.bench_function("Serialize IPFIX mixed with data only", |b| {
@@ -1,13 +1,13 @@
-use std::{cell::RefCell, collections::HashMap, io::Cursor, net::Ipv4Addr, rc::Rc};
+use std::{cell::RefCell, io::Cursor, net::Ipv4Addr, rc::Rc};
 
 use chrono::{TimeZone, Utc};
 
-use netgauze_flow_pkt::{ie, ipfix::*, DataSetId, FieldSpecifier};
+use netgauze_flow_pkt::{ie, ipfix::*, template_cache::TemplateCache, DataSetId, FieldSpecifier};
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePduWithOneInput};
 
 fn main() {
     // Cache to share the templates for decoding data packets
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
 
     // IPFIX template packet
     let ipfix_template = IpfixPacket::new(
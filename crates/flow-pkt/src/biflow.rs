@@ -0,0 +1,464 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bidirectional flows [RFC5103](https://datatracker.ietf.org/doc/html/rfc5103).
+//!
+//! A biflow is either exported as a single record carrying the reverse
+//! direction in the reverse Information Elements (IANA IEs under the
+//! [`REVERSE_PEN`]), or as two unidirectional records with swapped keys.
+//! [`BiflowPairer`] handles both: the first is converted directly, while the
+//! second is paired with the record of the opposite direction seen within a
+//! time window.
+//!
+//! The reverse Information Elements are decoded only after
+//! [`register_reverse_ies`] adds them to the [`crate::ie_registry`].
+
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{EnterpriseValue, Field, InformationElementTemplate, IE},
+    ie_registry::{self, EnterpriseIe, EnterpriseIeRegistrationError},
+    ipfix::DataRecord,
+};
+
+/// Private Enterprise Number of the reverse Information Elements
+pub const REVERSE_PEN: u32 = 29305;
+
+/// Information Elements that are reversible and commonly exported in biflows
+const REVERSIBLE_IES: [IE; 10] = [
+    IE::octetDeltaCount,
+    IE::packetDeltaCount,
+    IE::octetTotalCount,
+    IE::packetTotalCount,
+    IE::flowStartSeconds,
+    IE::flowEndSeconds,
+    IE::flowStartMilliseconds,
+    IE::flowEndMilliseconds,
+    IE::tcpControlBits,
+    IE::ipClassOfService,
+];
+
+/// Register the reverse counterpart of the common reversible Information
+/// Elements, e.g., `reverseOctetDeltaCount`, in the [`crate::ie_registry`]
+pub fn register_reverse_ies() -> Result<(), EnterpriseIeRegistrationError> {
+    for ie in REVERSIBLE_IES {
+        let name = format!("{ie:?}");
+        let mut chars = name.chars();
+        let name = match chars.next() {
+            Some(first) => format!("reverse{}{}", first.to_ascii_uppercase(), chars.as_str()),
+            None => continue,
+        };
+        ie_registry::register(EnterpriseIe::new(
+            REVERSE_PEN,
+            ie.id(),
+            name,
+            ie.data_type(),
+            ie.semantics(),
+        ))?;
+    }
+    Ok(())
+}
+
+/// 5-tuple identifying the direction of a flow
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FlowKey {
+    source_address: IpAddr,
+    destination_address: IpAddr,
+    source_port: u16,
+    destination_port: u16,
+    protocol: u8,
+}
+
+impl FlowKey {
+    pub const fn new(
+        source_address: IpAddr,
+        destination_address: IpAddr,
+        source_port: u16,
+        destination_port: u16,
+        protocol: u8,
+    ) -> Self {
+        Self {
+            source_address,
+            destination_address,
+            source_port,
+            destination_port,
+            protocol,
+        }
+    }
+
+    /// Extract the key from the IPv4 or IPv6 addresses, the transport ports,
+    /// and the protocol of the record. The ports default to zero for the
+    /// protocols without ports.
+    pub fn from_record(record: &DataRecord) -> Option<Self> {
+        let mut source_address = None;
+        let mut destination_address = None;
+        let mut source_port = 0;
+        let mut destination_port = 0;
+        let mut protocol = None;
+        for field in record.scope_fields().iter().chain(record.fields()) {
+            match field {
+                Field::sourceIPv4Address(value) => source_address = Some(IpAddr::V4(value.0)),
+                Field::sourceIPv6Address(value) => source_address = Some(IpAddr::V6(value.0)),
+                Field::destinationIPv4Address(value) => {
+                    destination_address = Some(IpAddr::V4(value.0))
+                }
+                Field::destinationIPv6Address(value) => {
+                    destination_address = Some(IpAddr::V6(value.0))
+                }
+                Field::sourceTransportPort(value) => source_port = value.0,
+                Field::destinationTransportPort(value) => destination_port = value.0,
+                Field::protocolIdentifier(value) => protocol = Some(value.0),
+                _ => {}
+            }
+        }
+        Some(Self::new(
+            source_address?,
+            destination_address?,
+            source_port,
+            destination_port,
+            protocol?,
+        ))
+    }
+
+    /// Key of the opposite direction
+    pub const fn reversed(&self) -> Self {
+        Self {
+            source_address: self.destination_address,
+            destination_address: self.source_address,
+            source_port: self.destination_port,
+            destination_port: self.source_port,
+            protocol: self.protocol,
+        }
+    }
+
+    pub const fn source_address(&self) -> IpAddr {
+        self.source_address
+    }
+
+    pub const fn destination_address(&self) -> IpAddr {
+        self.destination_address
+    }
+
+    pub const fn source_port(&self) -> u16 {
+        self.source_port
+    }
+
+    pub const fn destination_port(&self) -> u16 {
+        self.destination_port
+    }
+
+    pub const fn protocol(&self) -> u8 {
+        self.protocol
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DirectionCounters {
+    octets: u64,
+    packets: u64,
+}
+
+impl DirectionCounters {
+    pub const fn new(octets: u64, packets: u64) -> Self {
+        Self { octets, packets }
+    }
+
+    pub const fn octets(&self) -> u64 {
+        self.octets
+    }
+
+    pub const fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Counters of the forward direction, from the delta or total counters
+    fn forward(record: &DataRecord) -> Self {
+        let mut counters = Self::default();
+        for field in record.fields() {
+            match field {
+                Field::octetDeltaCount(value) => counters.octets = value.0,
+                Field::octetTotalCount(value) if counters.octets == 0 => counters.octets = value.0,
+                Field::packetDeltaCount(value) => counters.packets = value.0,
+                Field::packetTotalCount(value) if counters.packets == 0 => {
+                    counters.packets = value.0
+                }
+                _ => {}
+            }
+        }
+        counters
+    }
+
+    /// Counters of the reverse direction carried by the reverse Information
+    /// Elements, [`None`] if the record doesn't have any
+    fn reverse(record: &DataRecord) -> Option<Self> {
+        let mut counters = None;
+        for field in record.fields() {
+            let field = match field {
+                Field::Enterprise(field) if field.pen() == REVERSE_PEN => field,
+                _ => continue,
+            };
+            let value = match field.value() {
+                EnterpriseValue::Unsigned64(value) => *value,
+                _ => continue,
+            };
+            let counters: &mut Self = counters.get_or_insert_with(Self::default);
+            match IE::try_from((0, field.id())) {
+                Ok(IE::octetDeltaCount) => counters.octets = value,
+                Ok(IE::octetTotalCount) if counters.octets == 0 => counters.octets = value,
+                Ok(IE::packetDeltaCount) => counters.packets = value,
+                Ok(IE::packetTotalCount) if counters.packets == 0 => counters.packets = value,
+                _ => {}
+            }
+        }
+        counters
+    }
+}
+
+/// Both directions of a flow
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Biflow {
+    key: Option<FlowKey>,
+    forward: DirectionCounters,
+    reverse: DirectionCounters,
+    forward_record: DataRecord,
+    reverse_record: Option<DataRecord>,
+}
+
+impl Biflow {
+    /// Convert a single biflow record carrying reverse Information Elements,
+    /// [`None`] if the record doesn't have any
+    pub fn from_record(record: DataRecord) -> Option<Self> {
+        let reverse = DirectionCounters::reverse(&record)?;
+        Some(Self {
+            key: FlowKey::from_record(&record),
+            forward: DirectionCounters::forward(&record),
+            reverse,
+            forward_record: record,
+            reverse_record: None,
+        })
+    }
+
+    /// Pair two unidirectional records, the forward record is the one
+    /// initiating the flow
+    pub fn from_records(forward_record: DataRecord, reverse_record: DataRecord) -> Self {
+        Self {
+            key: FlowKey::from_record(&forward_record),
+            forward: DirectionCounters::forward(&forward_record),
+            reverse: DirectionCounters::forward(&reverse_record),
+            forward_record,
+            reverse_record: Some(reverse_record),
+        }
+    }
+
+    /// Unidirectional flow where the opposite direction was never seen
+    pub fn from_uniflow(record: DataRecord) -> Self {
+        Self {
+            key: FlowKey::from_record(&record),
+            forward: DirectionCounters::forward(&record),
+            reverse: DirectionCounters::default(),
+            forward_record: record,
+            reverse_record: None,
+        }
+    }
+
+    /// Key in the forward direction, [`None`] when the records don't carry
+    /// the 5-tuple
+    pub const fn key(&self) -> Option<FlowKey> {
+        self.key
+    }
+
+    pub const fn forward(&self) -> DirectionCounters {
+        self.forward
+    }
+
+    pub const fn reverse(&self) -> DirectionCounters {
+        self.reverse
+    }
+
+    pub const fn forward_record(&self) -> &DataRecord {
+        &self.forward_record
+    }
+
+    /// The record of the reverse direction when paired from two
+    /// unidirectional records
+    pub const fn reverse_record(&self) -> Option<&DataRecord> {
+        self.reverse_record.as_ref()
+    }
+}
+
+/// Pair the unidirectional records of the same flow, see the
+/// [module docs](self)
+#[derive(Debug, Clone)]
+pub struct BiflowPairer {
+    window: Duration,
+    pending: HashMap<FlowKey, (Instant, DataRecord)>,
+}
+
+impl BiflowPairer {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Maximum time to wait for the record of the opposite direction
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    /// Number of records waiting for the opposite direction
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Returns a [`Biflow`] when the record is a biflow record on its own, or
+    /// completes a pending record of the opposite direction. The records
+    /// without a 5-tuple can't be paired and are returned as uniflows.
+    pub fn push(&mut self, record: DataRecord, now: Instant) -> Option<Biflow> {
+        if DirectionCounters::reverse(&record).is_some() {
+            return Biflow::from_record(record);
+        }
+        let key = match FlowKey::from_record(&record) {
+            Some(key) => key,
+            None => return Some(Biflow::from_uniflow(record)),
+        };
+        match self.pending.remove(&key.reversed()) {
+            Some((seen, forward_record)) if now.saturating_duration_since(seen) <= self.window => {
+                Some(Biflow::from_records(forward_record, record))
+            }
+            Some(expired) => {
+                // The opposite direction was seen too long ago, it's not the same flow
+                self.pending.insert(key.reversed(), expired);
+                self.pending.insert(key, (now, record));
+                None
+            }
+            None => {
+                self.pending.insert(key, (now, record));
+                None
+            }
+        }
+    }
+
+    /// Flush the records that are not paired within the window as uniflows
+    pub fn expire(&mut self, now: Instant) -> Vec<Biflow> {
+        let window = self.window;
+        let mut expired = vec![];
+        self.pending.retain(|_, (seen, record)| {
+            if now.saturating_duration_since(*seen) > window {
+                expired.push(Biflow::from_uniflow(record.clone()));
+                false
+            } else {
+                true
+            }
+        });
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ie::{self, EnterpriseField};
+    use std::net::Ipv4Addr;
+
+    fn record(
+        source: Ipv4Addr,
+        destination: Ipv4Addr,
+        source_port: u16,
+        octets: u64,
+    ) -> DataRecord {
+        DataRecord::new(
+            vec![],
+            vec![
+                Field::sourceIPv4Address(ie::sourceIPv4Address(source)),
+                Field::destinationIPv4Address(ie::destinationIPv4Address(destination)),
+                Field::sourceTransportPort(ie::sourceTransportPort(source_port)),
+                Field::destinationTransportPort(ie::destinationTransportPort(80)),
+                Field::protocolIdentifier(ie::protocolIdentifier(6)),
+                Field::octetDeltaCount(ie::octetDeltaCount(octets)),
+                Field::packetDeltaCount(ie::packetDeltaCount(1)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_biflow_pairing() {
+        let client = Ipv4Addr::new(10, 0, 0, 1);
+        let server = Ipv4Addr::new(10, 0, 0, 2);
+        let now = Instant::now();
+        let mut pairer = BiflowPairer::new(Duration::from_secs(10));
+
+        let request = record(client, server, 5000, 100);
+        let response = DataRecord::new(
+            vec![],
+            vec![
+                Field::sourceIPv4Address(ie::sourceIPv4Address(server)),
+                Field::destinationIPv4Address(ie::destinationIPv4Address(client)),
+                Field::sourceTransportPort(ie::sourceTransportPort(80)),
+                Field::destinationTransportPort(ie::destinationTransportPort(5000)),
+                Field::protocolIdentifier(ie::protocolIdentifier(6)),
+                Field::octetDeltaCount(ie::octetDeltaCount(1500)),
+                Field::packetDeltaCount(ie::packetDeltaCount(2)),
+            ],
+        );
+        assert_eq!(pairer.push(request.clone(), now), None);
+        let biflow = pairer
+            .push(response.clone(), now + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(
+            biflow.key(),
+            Some(FlowKey::new(client.into(), server.into(), 5000, 80, 6))
+        );
+        assert_eq!(biflow.forward(), DirectionCounters::new(100, 1));
+        assert_eq!(biflow.reverse(), DirectionCounters::new(1500, 2));
+        assert_eq!(biflow.reverse_record(), Some(&response));
+        assert_eq!(pairer.pending(), 0);
+
+        // Opposite direction outside the window isn't paired
+        assert_eq!(pairer.push(request.clone(), now), None);
+        assert_eq!(pairer.push(response, now + Duration::from_secs(11)), None);
+        assert_eq!(pairer.expire(now + Duration::from_secs(12)).len(), 1);
+        let uniflows = pairer.expire(now + Duration::from_secs(22));
+        assert_eq!(uniflows.len(), 1);
+        assert_eq!(uniflows[0].reverse(), DirectionCounters::default());
+
+        // Single biflow record with reverse IEs
+        let mut fields = request.fields().clone();
+        fields.push(Field::Enterprise(EnterpriseField::new(
+            REVERSE_PEN,
+            IE::octetDeltaCount.id(),
+            EnterpriseValue::Unsigned64(3000),
+        )));
+        let biflow = pairer.push(DataRecord::new(vec![], fields), now).unwrap();
+        assert_eq!(biflow.forward(), DirectionCounters::new(100, 1));
+        assert_eq!(biflow.reverse(), DirectionCounters::new(3000, 0));
+        assert_eq!(biflow.reverse_record(), None);
+    }
+
+    #[test]
+    fn test_register_reverse_ies() {
+        register_reverse_ies().unwrap();
+        let ie = ie_registry::lookup(REVERSE_PEN, IE::octetDeltaCount.id()).unwrap();
+        assert_eq!(ie.name(), "reverseOctetDeltaCount");
+        assert_eq!(ie.data_type(), IE::octetDeltaCount.data_type());
+    }
+}
@@ -16,16 +16,27 @@
 //! Code to handle decode/encoding of IPFIX and Netflow V9 packets
 //! It works with [`FlowInfo`] which is enum that combine both IPFIX and Netflow
 //! V9 into one object to make it easier to handle.
+//!
+//! [`FlowInfoCodec`] is meant for datagrams, where every buffer holds whole
+//! packets. [`IpfixStreamCodec`] frames the IPFIX messages received over a
+//! byte stream, i.e., TCP or TLS, see
+//! [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-10.4).
 
 use byteorder::{ByteOrder, NetworkEndian};
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BytesMut};
 use nom::Needed;
 use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Instant};
 use tokio_util::codec::{Decoder, Encoder};
 use tracing::instrument;
 
 use crate::{
     ipfix, netflow,
+    options_data::{OptionsDataCache, OptionsDataSnapshot},
+    sequence::{SequenceStats, SequenceTracker},
+    template_cache::{
+        SharedTemplateCache, TemplateCache, TemplateCacheSnapshot, TemplateScope, TemplateSnapshot,
+    },
     wire::{
         deserializer::{
             ipfix::{IpfixPacketParsingError, IPFIX_HEADER_LENGTH},
@@ -46,6 +57,8 @@ pub enum FlowInfoCodecDecoderError {
     IoError(String),
     Incomplete(Option<usize>),
     UnsupportedVersion(u16),
+    /// IPFIX message length is shorter than the message header
+    InvalidLength(u16),
     IpfixParsingError(IpfixPacketParsingError),
     NetFlowV9ParingError(NetFlowV9PacketParsingError),
 }
@@ -65,16 +78,237 @@ pub struct FlowInfoCodec {
     in_message: bool,
     netflow_v9_templates_map: netflow::TemplatesMap,
     ipfix_templates_map: ipfix::TemplatesMap,
+    shared_netflow_v9_templates: Option<SharedTemplateCache<netflow::DecodingTemplate>>,
+    shared_ipfix_templates: Option<SharedTemplateCache<ipfix::DecodingTemplate>>,
+    options_data: OptionsDataCache,
+    sequence_tracker: SequenceTracker,
+}
+
+impl FlowInfoCodec {
+    /// Templates cache used for IPFIX packets; used by the collector to scope
+    /// the templates to the peer, configure expiry, and receive notifications
+    pub const fn ipfix_templates(&self) -> &ipfix::TemplatesMap {
+        &self.ipfix_templates_map
+    }
+
+    /// Templates cache used for NetFlow V9 packets
+    pub const fn netflow_v9_templates(&self) -> &netflow::TemplatesMap {
+        &self.netflow_v9_templates_map
+    }
+
+    /// Options data decoded so far from both IPFIX and NetFlow V9 packets
+    pub const fn options_data(&self) -> &OptionsDataCache {
+        &self.options_data
+    }
+
+    pub fn options_data_mut(&mut self) -> &mut OptionsDataCache {
+        &mut self.options_data
+    }
+
+    /// Lost and reordered packets detected so far from the sequence numbers
+    pub const fn sequence_tracker(&self) -> &SequenceTracker {
+        &self.sequence_tracker
+    }
+
+    pub fn sequence_tracker_mut(&mut self) -> &mut SequenceTracker {
+        &mut self.sequence_tracker
+    }
+
+    /// Copy of the templates and the options data learned so far, to be
+    /// persisted and restored with [`FlowInfoCodec::restore`] after a restart
+    pub fn snapshot(&self) -> FlowInfoCodecSnapshot {
+        FlowInfoCodecSnapshot {
+            ipfix_templates: self.ipfix_templates_map.borrow().snapshot(),
+            netflow_v9_templates: self.netflow_v9_templates_map.borrow().snapshot(),
+            options_data: self.options_data.snapshot(),
+        }
+    }
+
+    /// Restore the templates and the options data of a snapshot, the entries
+    /// already learned are more recent and are kept
+    pub fn restore(&mut self, snapshot: FlowInfoCodecSnapshot) {
+        self.ipfix_templates_map
+            .borrow_mut()
+            .restore(snapshot.ipfix_templates);
+        self.netflow_v9_templates_map
+            .borrow_mut()
+            .restore(snapshot.netflow_v9_templates);
+        self.options_data.restore(snapshot.options_data);
+    }
+
+    /// State learned from every exporter, to debug the records that are not
+    /// decoded, e.g., a missing template or an unexpected field list. The
+    /// exporters are sorted by address and observation domain.
+    pub fn inspect(&self) -> Vec<ExporterInspection> {
+        fn exporter(
+            exporters: &mut HashMap<TemplateScope, ExporterInspection>,
+            scope: TemplateScope,
+        ) -> &mut ExporterInspection {
+            exporters
+                .entry(scope)
+                .or_insert_with(|| ExporterInspection::new(scope))
+        }
+        let mut exporters = HashMap::new();
+        for template in self
+            .ipfix_templates_map
+            .borrow()
+            .snapshot()
+            .into_templates()
+        {
+            exporter(&mut exporters, template.scope())
+                .ipfix_templates
+                .push(template);
+        }
+        for template in self
+            .netflow_v9_templates_map
+            .borrow()
+            .snapshot()
+            .into_templates()
+        {
+            exporter(&mut exporters, template.scope())
+                .netflow_v9_templates
+                .push(template);
+        }
+        for (scope, options_data) in self.options_data.snapshot().split_by_scope() {
+            exporter(&mut exporters, scope).options_data = options_data;
+        }
+        for (scope, stats) in self.sequence_tracker.all_stats() {
+            exporter(&mut exporters, *scope).sequence_stats = Some(*stats);
+        }
+        let mut exporters = exporters.into_values().collect::<Vec<_>>();
+        for exporter in &mut exporters {
+            exporter.ipfix_templates.sort_by_key(|x| x.template_id());
+            exporter
+                .netflow_v9_templates
+                .sort_by_key(|x| x.template_id());
+        }
+        exporters.sort_by_key(|x| (x.scope.peer(), x.scope.observation_domain_id()));
+        exporters
+    }
+
+    /// Share the IPFIX templates with the codecs of the other workers, the
+    /// templates are synced before and after decoding each packet
+    pub fn set_shared_ipfix_templates(
+        &mut self,
+        shared: Option<SharedTemplateCache<ipfix::DecodingTemplate>>,
+    ) {
+        self.shared_ipfix_templates = shared;
+    }
+
+    /// Share the NetFlow V9 templates with the codecs of the other workers, see
+    /// [`FlowInfoCodec::set_shared_ipfix_templates`]
+    pub fn set_shared_netflow_v9_templates(
+        &mut self,
+        shared: Option<SharedTemplateCache<netflow::DecodingTemplate>>,
+    ) {
+        self.shared_netflow_v9_templates = shared;
+    }
+}
+
+/// Persisted state of a [`FlowInfoCodec`], see [`FlowInfoCodec::snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct FlowInfoCodecSnapshot {
+    ipfix_templates: TemplateCacheSnapshot<ipfix::DecodingTemplate>,
+    netflow_v9_templates: TemplateCacheSnapshot<netflow::DecodingTemplate>,
+    options_data: OptionsDataSnapshot,
+}
+
+impl FlowInfoCodecSnapshot {
+    pub const fn ipfix_templates(&self) -> &TemplateCacheSnapshot<ipfix::DecodingTemplate> {
+        &self.ipfix_templates
+    }
+
+    pub const fn netflow_v9_templates(&self) -> &TemplateCacheSnapshot<netflow::DecodingTemplate> {
+        &self.netflow_v9_templates
+    }
+
+    pub const fn options_data(&self) -> &OptionsDataSnapshot {
+        &self.options_data
+    }
+}
+
+/// State of an exporter and observation domain, see [`FlowInfoCodec::inspect`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExporterInspection {
+    scope: TemplateScope,
+    ipfix_templates: Vec<TemplateSnapshot<ipfix::DecodingTemplate>>,
+    netflow_v9_templates: Vec<TemplateSnapshot<netflow::DecodingTemplate>>,
+    options_data: OptionsDataSnapshot,
+    sequence_stats: Option<SequenceStats>,
+}
+
+impl ExporterInspection {
+    fn new(scope: TemplateScope) -> Self {
+        Self {
+            scope,
+            ipfix_templates: vec![],
+            netflow_v9_templates: vec![],
+            options_data: OptionsDataSnapshot::default(),
+            sequence_stats: None,
+        }
+    }
+
+    pub const fn scope(&self) -> TemplateScope {
+        self.scope
+    }
+
+    /// The templates and options templates, sorted by template ID
+    pub const fn ipfix_templates(&self) -> &Vec<TemplateSnapshot<ipfix::DecodingTemplate>> {
+        &self.ipfix_templates
+    }
+
+    pub const fn netflow_v9_templates(&self) -> &Vec<TemplateSnapshot<netflow::DecodingTemplate>> {
+        &self.netflow_v9_templates
+    }
+
+    pub const fn options_data(&self) -> &OptionsDataSnapshot {
+        &self.options_data
+    }
+
+    pub const fn sequence_stats(&self) -> Option<SequenceStats> {
+        self.sequence_stats
+    }
+}
+
+/// Keep the options data tables and the sequence numbers up to date with the
+/// decoded packet
+fn update_exporter_state(
+    options_data: &mut OptionsDataCache,
+    sequence_tracker: &mut SequenceTracker,
+    ipfix_templates: &ipfix::TemplatesMap,
+    netflow_v9_templates: &netflow::TemplatesMap,
+    msg: &Result<Option<FlowInfo>, FlowInfoCodecDecoderError>,
+) {
+    match msg {
+        Ok(Some(FlowInfo::IPFIX(pkt))) => {
+            let peer = ipfix_templates.borrow().scope().peer();
+            options_data.update_ipfix(peer, pkt);
+            sequence_tracker.update_ipfix(peer, pkt);
+        }
+        Ok(Some(FlowInfo::NetFlowV9(pkt))) => {
+            let peer = netflow_v9_templates.borrow().scope().peer();
+            options_data.update_netflow_v9(peer, pkt);
+            sequence_tracker.update_netflow_v9(peer, pkt);
+        }
+        Ok(None) | Err(_) => {}
+    }
+}
+
+/// Sync the local templates with the shared ones, if any
+fn sync_templates<T: Clone>(
+    templates_map: &Rc<RefCell<TemplateCache<T>>>,
+    shared: Option<&SharedTemplateCache<T>>,
+) {
+    if let Some(shared) = shared {
+        templates_map.borrow_mut().sync(shared);
+    }
 }
 
 impl Encoder<ipfix::IpfixPacket> for FlowInfoCodec {
     type Error = IpfixPacketWritingError;
 
     fn encode(&mut self, pkt: ipfix::IpfixPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        dst.reserve(4 + pkt.len(Some(self.ipfix_templates_map.clone())));
-        let mut writer = dst.writer();
-        pkt.write(&mut writer, Some(self.ipfix_templates_map.clone()))?;
-        Ok(())
+        pkt.write_into(dst, Some(self.ipfix_templates_map.clone()))
     }
 }
 
@@ -86,10 +320,7 @@ impl Encoder<netflow::NetFlowV9Packet> for FlowInfoCodec {
         pkt: netflow::NetFlowV9Packet,
         dst: &mut BytesMut,
     ) -> Result<(), Self::Error> {
-        dst.reserve(4 + pkt.len(Some(self.netflow_v9_templates_map.clone())));
-        let mut writer = dst.writer();
-        pkt.write(&mut writer, Some(self.netflow_v9_templates_map.clone()))?;
-        Ok(())
+        pkt.write_into(dst, Some(self.netflow_v9_templates_map.clone()))
     }
 }
 
@@ -193,9 +424,35 @@ impl Decoder for FlowInfoCodec {
             } else {
                 self.in_message = false;
                 if version == ipfix::IPFIX_VERSION {
-                    parse_ipfix(buf, length, self.ipfix_templates_map.clone())
+                    let shared = self.shared_ipfix_templates.as_ref();
+                    sync_templates(&self.ipfix_templates_map, shared);
+                    self.ipfix_templates_map.borrow_mut().expire(Instant::now());
+                    let ret = parse_ipfix(buf, length, self.ipfix_templates_map.clone());
+                    sync_templates(&self.ipfix_templates_map, shared);
+                    update_exporter_state(
+                        &mut self.options_data,
+                        &mut self.sequence_tracker,
+                        &self.ipfix_templates_map,
+                        &self.netflow_v9_templates_map,
+                        &ret,
+                    );
+                    ret
                 } else if version == netflow::NETFLOW_V9_VERSION {
-                    parse_netflow_v9(buf, self.netflow_v9_templates_map.clone())
+                    let shared = self.shared_netflow_v9_templates.as_ref();
+                    sync_templates(&self.netflow_v9_templates_map, shared);
+                    self.netflow_v9_templates_map
+                        .borrow_mut()
+                        .expire(Instant::now());
+                    let ret = parse_netflow_v9(buf, self.netflow_v9_templates_map.clone());
+                    sync_templates(&self.netflow_v9_templates_map, shared);
+                    update_exporter_state(
+                        &mut self.options_data,
+                        &mut self.sequence_tracker,
+                        &self.ipfix_templates_map,
+                        &self.netflow_v9_templates_map,
+                        &ret,
+                    );
+                    ret
                 } else {
                     let err = FlowInfoCodecDecoderError::UnsupportedVersion(version);
                     buf.clear();
@@ -208,3 +465,101 @@ impl Decoder for FlowInfoCodec {
         }
     }
 }
+
+/// IPFIX over a byte stream, each message is framed by the length in its
+/// header. Only IPFIX is accepted, since NetFlow V9 doesn't carry the message
+/// length. The message boundaries are lost after an invalid header, so the
+/// decoder errors out and the stream should be closed.
+#[derive(Debug, Default)]
+pub struct IpfixStreamCodec {
+    templates_map: ipfix::TemplatesMap,
+}
+
+impl IpfixStreamCodec {
+    /// Templates cache of the stream; used by the collector to scope the
+    /// templates to the peer, configure expiry, and receive notifications
+    pub const fn ipfix_templates(&self) -> &ipfix::TemplatesMap {
+        &self.templates_map
+    }
+}
+
+impl Encoder<ipfix::IpfixPacket> for IpfixStreamCodec {
+    type Error = IpfixPacketWritingError;
+
+    fn encode(&mut self, pkt: ipfix::IpfixPacket, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        pkt.write_into(dst, Some(self.templates_map.clone()))
+    }
+}
+
+impl Decoder for IpfixStreamCodec {
+    type Item = FlowInfo;
+    type Error = FlowInfoCodecDecoderError;
+
+    #[instrument(skip_all)]
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let header_length = IPFIX_HEADER_LENGTH as usize;
+        if buf.len() < header_length {
+            return Ok(None);
+        }
+        let version = NetworkEndian::read_u16(&buf[0..2]);
+        if version != ipfix::IPFIX_VERSION {
+            return Err(FlowInfoCodecDecoderError::UnsupportedVersion(version));
+        }
+        let length = NetworkEndian::read_u16(&buf[2..4]);
+        if (length as usize) < header_length {
+            return Err(FlowInfoCodecDecoderError::InvalidLength(length));
+        }
+        let length = length as usize;
+        if buf.len() < length {
+            buf.reserve(length - buf.len());
+            return Ok(None);
+        }
+        // Parse only the current message, a malformed message doesn't affect the
+        // framing of the next ones
+        let mut msg = buf.split_to(length);
+        self.templates_map.borrow_mut().expire(Instant::now());
+        parse_ipfix(&mut msg, length, self.templates_map.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{ie, FieldSpecifier};
+
+    #[test]
+    fn test_inspect() {
+        let pkt = ipfix::IpfixPacket::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            3,
+            7,
+            vec![ipfix::Set::Template(vec![ipfix::TemplateRecord::new(
+                256,
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+            )])],
+        );
+        let mut buf = vec![];
+        pkt.write(&mut buf, None).unwrap();
+
+        let mut codec = FlowInfoCodec::default();
+        assert!(codec.inspect().is_empty());
+        codec.decode(&mut BytesMut::from(buf.as_slice())).unwrap();
+        let exporters = codec.inspect();
+        assert_eq!(exporters.len(), 1);
+        let exporter = &exporters[0];
+        assert_eq!(exporter.scope(), TemplateScope::new(None, 7));
+        assert_eq!(exporter.ipfix_templates().len(), 1);
+        assert_eq!(exporter.ipfix_templates()[0].template_id(), 256);
+        assert_eq!(
+            exporter.ipfix_templates()[0].template(),
+            &(
+                vec![],
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()]
+            )
+        );
+        assert!(exporter.netflow_v9_templates().is_empty());
+        assert_eq!(exporter.sequence_stats().map(|x| x.packets()), Some(1));
+    }
+}
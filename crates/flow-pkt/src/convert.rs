@@ -0,0 +1,402 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Conversion of NetFlow V9 packets to IPFIX messages, so the collector can
+//! normalize all the flows to IPFIX.
+//!
+//! NetFlow V9 and IPFIX share the same Information Elements, the differences
+//! handled by the conversion are:
+//! - The Source ID is used as the Observation Domain ID.
+//! - The IPFIX sequence number counts the data records, instead of the
+//!   packets, sent by each Observation Domain.
+//! - `flowStartSysUpTime` and `flowEndSysUpTime` are relative to the exporter
+//!   `sysUpTime`, they are converted to the absolute `flowStartMilliseconds`
+//!   and `flowEndMilliseconds`.
+//! - The scope fields of the options templates are mapped to IPFIX
+//!   Information Elements
+//!   [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-3.4.2.1):
+//!
+//! | NetFlow V9 scope | IPFIX scope           |
+//! |------------------|-----------------------|
+//! | System           | `exportingProcessId`  |
+//! | Interface        | `ingressInterface`    |
+//! | LineCard         | `lineCardId`          |
+//! | Cache            | `meteringProcessId`   |
+//! | Template         | `templateId`          |
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{self, Field, IE},
+    ipfix::{self, IpfixPacket},
+    netflow::{self, NetFlowV9Packet, ScopeField, ScopeFieldSpecifier, ScopeIE},
+    FieldSpecifier, FieldSpecifierError,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NetFlowV9ConversionError {
+    /// Vendor specific scopes have no IPFIX equivalent
+    UnsupportedScope(ScopeIE),
+
+    /// The scope value is too long for its IPFIX Information Element
+    InvalidScopeLength(ScopeIE, usize),
+
+    FieldSpecifierError(FieldSpecifierError),
+}
+
+impl From<FieldSpecifierError> for NetFlowV9ConversionError {
+    fn from(err: FieldSpecifierError) -> Self {
+        Self::FieldSpecifierError(err)
+    }
+}
+
+/// Converts NetFlow V9 packets to IPFIX messages, while keeping track of the
+/// IPFIX sequence number of each Observation Domain
+#[derive(Debug, Clone, Default)]
+pub struct NetFlowV9Converter {
+    sequence_numbers: HashMap<u32, u32>,
+}
+
+impl NetFlowV9Converter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sequence number of the next IPFIX message of an Observation Domain
+    pub fn sequence_number(&self, observation_domain_id: u32) -> u32 {
+        self.sequence_numbers
+            .get(&observation_domain_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn convert(
+        &mut self,
+        pkt: &NetFlowV9Packet,
+    ) -> Result<IpfixPacket, NetFlowV9ConversionError> {
+        let sets = pkt
+            .sets()
+            .iter()
+            .map(|set| convert_set(pkt, set))
+            .collect::<Result<Vec<_>, _>>()?;
+        let records_count = sets
+            .iter()
+            .map(|set| match set {
+                ipfix::Set::Data { records, .. } => records.len(),
+                _ => 0,
+            })
+            .sum::<usize>();
+        let sequence_number = self.sequence_numbers.entry(pkt.source_id()).or_default();
+        let current = *sequence_number;
+        *sequence_number = sequence_number.wrapping_add(records_count as u32);
+        Ok(IpfixPacket::new(
+            pkt.unix_time(),
+            current,
+            pkt.source_id(),
+            sets,
+        ))
+    }
+}
+
+fn convert_set(
+    pkt: &NetFlowV9Packet,
+    set: &netflow::Set,
+) -> Result<ipfix::Set, NetFlowV9ConversionError> {
+    let set = match set {
+        netflow::Set::Template(templates) => ipfix::Set::Template(
+            templates
+                .iter()
+                .map(|template| {
+                    Ok(ipfix::TemplateRecord::new(
+                        template.id(),
+                        convert_field_specifiers(template.field_specifiers())?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, NetFlowV9ConversionError>>()?,
+        ),
+        netflow::Set::OptionsTemplate(templates) => ipfix::Set::OptionsTemplate(
+            templates
+                .iter()
+                .map(|template| {
+                    Ok(ipfix::OptionsTemplateRecord::new(
+                        template.id(),
+                        template
+                            .scope_field_specifiers()
+                            .iter()
+                            .map(convert_scope_field_specifier)
+                            .collect::<Result<Vec<_>, _>>()?,
+                        convert_field_specifiers(template.field_specifiers())?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, NetFlowV9ConversionError>>()?,
+        ),
+        netflow::Set::Data { id, records } => ipfix::Set::Data {
+            id: *id,
+            records: records
+                .iter()
+                .map(|record| {
+                    Ok(ipfix::DataRecord::new(
+                        record
+                            .scope_fields()
+                            .iter()
+                            .map(convert_scope_field)
+                            .collect::<Result<Vec<_>, _>>()?,
+                        record
+                            .fields()
+                            .iter()
+                            .map(|field| convert_field(pkt, field))
+                            .collect(),
+                    ))
+                })
+                .collect::<Result<Vec<_>, NetFlowV9ConversionError>>()?,
+        },
+    };
+    Ok(set)
+}
+
+fn convert_field_specifiers(
+    specs: &[FieldSpecifier],
+) -> Result<Vec<FieldSpecifier>, FieldSpecifierError> {
+    specs
+        .iter()
+        .map(|spec| match spec.element_id() {
+            IE::flowStartSysUpTime => FieldSpecifier::new(IE::flowStartMilliseconds, 8),
+            IE::flowEndSysUpTime => FieldSpecifier::new(IE::flowEndMilliseconds, 8),
+            _ => Ok(spec.clone()),
+        })
+        .collect()
+}
+
+/// The absolute time of a `sysUpTime` relative timestamp
+fn absolute_time(pkt: &NetFlowV9Packet, sys_up_time: u32) -> DateTime<Utc> {
+    let offset = sys_up_time as i64 - pkt.sys_up_time() as i64;
+    pkt.unix_time() + Duration::milliseconds(offset)
+}
+
+fn convert_field(pkt: &NetFlowV9Packet, field: &Field) -> Field {
+    match field {
+        Field::flowStartSysUpTime(value) => {
+            Field::flowStartMilliseconds(ie::flowStartMilliseconds(absolute_time(pkt, value.0)))
+        }
+        Field::flowEndSysUpTime(value) => {
+            Field::flowEndMilliseconds(ie::flowEndMilliseconds(absolute_time(pkt, value.0)))
+        }
+        field => field.clone(),
+    }
+}
+
+const fn scope_ie(scope: ScopeIE) -> Result<IE, NetFlowV9ConversionError> {
+    match scope {
+        ScopeIE::System => Ok(IE::exportingProcessId),
+        ScopeIE::Interface => Ok(IE::ingressInterface),
+        ScopeIE::LineCard => Ok(IE::lineCardId),
+        ScopeIE::Cache => Ok(IE::meteringProcessId),
+        ScopeIE::Template => Ok(IE::templateId),
+        ScopeIE::Unknown { .. } => Err(NetFlowV9ConversionError::UnsupportedScope(scope)),
+    }
+}
+
+fn convert_scope_field_specifier(
+    spec: &ScopeFieldSpecifier,
+) -> Result<FieldSpecifier, NetFlowV9ConversionError> {
+    Ok(FieldSpecifier::new(
+        scope_ie(spec.element_id())?,
+        spec.length(),
+    )?)
+}
+
+/// Big endian integer value of the scope octets
+fn scope_value(
+    scope: ScopeIE,
+    value: &[u8],
+    max_length: usize,
+) -> Result<u32, NetFlowV9ConversionError> {
+    if value.len() > max_length {
+        return Err(NetFlowV9ConversionError::InvalidScopeLength(
+            scope,
+            value.len(),
+        ));
+    }
+    Ok(value.iter().fold(0u32, |acc, x| (acc << 8) | *x as u32))
+}
+
+fn convert_scope_field(field: &ScopeField) -> Result<Field, NetFlowV9ConversionError> {
+    let field =
+        match field {
+            ScopeField::System(value) => Field::exportingProcessId(ie::exportingProcessId(value.0)),
+            ScopeField::Interface(value) => Field::ingressInterface(ie::ingressInterface(value.0)),
+            ScopeField::LineCard(value) => Field::lineCardId(ie::lineCardId(value.0)),
+            ScopeField::Cache(value) => Field::meteringProcessId(ie::meteringProcessId(
+                scope_value(ScopeIE::Cache, &value.0, 4)?,
+            )),
+            ScopeField::Template(value) => {
+                Field::templateId(ie::templateId(
+                    scope_value(ScopeIE::Template, &value.0, 2)? as u16
+                ))
+            }
+            ScopeField::Unknown { pen, id, .. } => {
+                return Err(NetFlowV9ConversionError::UnsupportedScope(
+                    ScopeIE::Unknown { pen: *pen, id: *id },
+                ))
+            }
+        };
+    Ok(field)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::{
+        netflow::{Cache, Interface},
+        DataSetId,
+    };
+
+    #[test]
+    fn test_netflow_v9_to_ipfix() {
+        let unix_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let data_set_id = DataSetId::new(256).unwrap();
+        let options_set_id = DataSetId::new(257).unwrap();
+        let pkt = NetFlowV9Packet::new(
+            100_000,
+            unix_time,
+            7,
+            3,
+            vec![
+                netflow::Set::Template(vec![netflow::TemplateRecord::new(
+                    256,
+                    vec![
+                        FieldSpecifier::new(IE::octetDeltaCount, 4).unwrap(),
+                        FieldSpecifier::new(IE::flowStartSysUpTime, 4).unwrap(),
+                        FieldSpecifier::new(IE::flowEndSysUpTime, 4).unwrap(),
+                    ],
+                )]),
+                netflow::Set::OptionsTemplate(vec![netflow::OptionsTemplateRecord::new(
+                    257,
+                    vec![
+                        ScopeFieldSpecifier::new(ScopeIE::Interface, 4),
+                        ScopeFieldSpecifier::new(ScopeIE::Cache, 2),
+                    ],
+                    vec![FieldSpecifier::new(IE::samplingInterval, 4).unwrap()],
+                )]),
+                netflow::Set::Data {
+                    id: data_set_id,
+                    records: vec![
+                        netflow::DataRecord::new(
+                            vec![],
+                            vec![
+                                Field::octetDeltaCount(ie::octetDeltaCount(100)),
+                                Field::flowStartSysUpTime(ie::flowStartSysUpTime(90_000)),
+                                Field::flowEndSysUpTime(ie::flowEndSysUpTime(99_500)),
+                            ],
+                        );
+                        2
+                    ],
+                },
+                netflow::Set::Data {
+                    id: options_set_id,
+                    records: vec![netflow::DataRecord::new(
+                        vec![
+                            ScopeField::Interface(Interface(10)),
+                            ScopeField::Cache(Cache(vec![0x01, 0x02])),
+                        ],
+                        vec![Field::samplingInterval(ie::samplingInterval(1000))],
+                    )],
+                },
+            ],
+        );
+
+        let record = ipfix::DataRecord::new(
+            vec![],
+            vec![
+                Field::octetDeltaCount(ie::octetDeltaCount(100)),
+                Field::flowStartMilliseconds(ie::flowStartMilliseconds(
+                    unix_time - Duration::seconds(10),
+                )),
+                Field::flowEndMilliseconds(ie::flowEndMilliseconds(
+                    unix_time - Duration::milliseconds(500),
+                )),
+            ],
+        );
+        let expected = IpfixPacket::new(
+            unix_time,
+            0,
+            3,
+            vec![
+                ipfix::Set::Template(vec![ipfix::TemplateRecord::new(
+                    256,
+                    vec![
+                        FieldSpecifier::new(IE::octetDeltaCount, 4).unwrap(),
+                        FieldSpecifier::new(IE::flowStartMilliseconds, 8).unwrap(),
+                        FieldSpecifier::new(IE::flowEndMilliseconds, 8).unwrap(),
+                    ],
+                )]),
+                ipfix::Set::OptionsTemplate(vec![ipfix::OptionsTemplateRecord::new(
+                    257,
+                    vec![
+                        FieldSpecifier::new(IE::ingressInterface, 4).unwrap(),
+                        FieldSpecifier::new(IE::meteringProcessId, 2).unwrap(),
+                    ],
+                    vec![FieldSpecifier::new(IE::samplingInterval, 4).unwrap()],
+                )]),
+                ipfix::Set::Data {
+                    id: data_set_id,
+                    records: vec![record; 2],
+                },
+                ipfix::Set::Data {
+                    id: options_set_id,
+                    records: vec![ipfix::DataRecord::new(
+                        vec![
+                            Field::ingressInterface(ie::ingressInterface(10)),
+                            Field::meteringProcessId(ie::meteringProcessId(0x0102)),
+                        ],
+                        vec![Field::samplingInterval(ie::samplingInterval(1000))],
+                    )],
+                },
+            ],
+        );
+
+        let mut converter = NetFlowV9Converter::new();
+        assert_eq!(converter.convert(&pkt), Ok(expected));
+        // The sequence number counts the data records
+        assert_eq!(converter.sequence_number(3), 3);
+        assert_eq!(converter.convert(&pkt).map(|x| x.sequence_number()), Ok(3));
+        assert_eq!(converter.sequence_number(3), 6);
+        assert_eq!(converter.sequence_number(4), 0);
+
+        let vendor_scope = ScopeIE::Unknown { pen: 0, id: 100 };
+        let bad = NetFlowV9Packet::new(
+            100_000,
+            unix_time,
+            8,
+            3,
+            vec![netflow::Set::OptionsTemplate(vec![
+                netflow::OptionsTemplateRecord::new(
+                    258,
+                    vec![ScopeFieldSpecifier::new(vendor_scope, 4)],
+                    vec![],
+                ),
+            ])],
+        );
+        assert_eq!(
+            converter.convert(&bad),
+            Err(NetFlowV9ConversionError::UnsupportedScope(vendor_scope))
+        );
+    }
+}
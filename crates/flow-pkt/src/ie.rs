@@ -120,8 +120,10 @@ pub trait InformationElementTemplate {
             InformationElementDataType::signed32 => Some(std::ops::Range { start: 1, end: 5 }),
             InformationElementDataType::signed64 => Some(std::ops::Range { start: 1, end: 9 }),
             InformationElementDataType::float32 => Some(std::ops::Range { start: 4, end: 5 }),
-            InformationElementDataType::float64 => Some(std::ops::Range { start: 8, end: 9 }),
-            InformationElementDataType::boolean => Some(std::ops::Range { start: 1, end: 1 }),
+            // float64 can be encoded as float32 with the reduced-size encoding, lengths in
+            // between are rejected when decoding
+            InformationElementDataType::float64 => Some(std::ops::Range { start: 4, end: 9 }),
+            InformationElementDataType::boolean => Some(std::ops::Range { start: 1, end: 2 }),
             InformationElementDataType::macAddress => Some(std::ops::Range { start: 6, end: 7 }),
             InformationElementDataType::string => None,
             InformationElementDataType::dateTimeSeconds => {
@@ -154,4 +156,414 @@ pub trait InformationElementTemplate {
     fn pen(&self) -> u32;
 }
 
+/// Relationship among the different elements of a structured data list
+/// [RFC6313](https://datatracker.ietf.org/doc/html/rfc6313#section-4.4)
+#[allow(non_camel_case_types)]
+#[repr(u8)]
+#[derive(
+    strum_macros::Display,
+    strum_macros::FromRepr,
+    Copy,
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum ListSemantic {
+    /// None of the elements in the list satisfy the Flow
+    noneOf = 0,
+
+    /// Only a single element from the list satisfies the Flow
+    exactlyOneOf = 1,
+
+    /// One or more elements in the list satisfy the Flow
+    oneOrMoreOf = 2,
+
+    /// All the elements in the list satisfy the Flow
+    allOf = 3,
+
+    /// Elements in the list are ordered
+    ordered = 4,
+
+    /// The semantic is not specified by the Exporting Process
+    undefined = 255,
+}
+
+/// Structured data list of zero or more instances of the same Information
+/// Element
+///
+/// ```text
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    Semantic   |E|   Field ID  |        Element Length         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |        Enterprise Number (only when E bit is set)             |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                   basicList Content ...                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct BasicList {
+    semantic: ListSemantic,
+    field_specifier: crate::FieldSpecifier,
+    elements: Vec<Field>,
+}
+
+impl BasicList {
+    pub const fn new(
+        semantic: ListSemantic,
+        field_specifier: crate::FieldSpecifier,
+        elements: Vec<Field>,
+    ) -> Self {
+        Self {
+            semantic,
+            field_specifier,
+            elements,
+        }
+    }
+
+    pub const fn semantic(&self) -> ListSemantic {
+        self.semantic
+    }
+
+    /// Field specifier of the elements of the list
+    pub const fn field_specifier(&self) -> &crate::FieldSpecifier {
+        &self.field_specifier
+    }
+
+    pub const fn elements(&self) -> &Vec<Field> {
+        &self.elements
+    }
+}
+
+/// Structured data list of zero or more data records of the same template
+///
+/// ```text
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |    Semantic   |         Template ID           |     ...       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                subTemplateList Content    ...                 |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SubTemplateList {
+    semantic: ListSemantic,
+    template_id: u16,
+    records: Vec<crate::ipfix::DataRecord>,
+}
+
+impl SubTemplateList {
+    pub const fn new(
+        semantic: ListSemantic,
+        template_id: u16,
+        records: Vec<crate::ipfix::DataRecord>,
+    ) -> Self {
+        Self {
+            semantic,
+            template_id,
+            records,
+        }
+    }
+
+    pub const fn semantic(&self) -> ListSemantic {
+        self.semantic
+    }
+
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    pub const fn records(&self) -> &Vec<crate::ipfix::DataRecord> {
+        &self.records
+    }
+}
+
+/// Structured data list of data records that are grouped by their templates
+///
+/// ```text
+/// 0                   1                   2                   3
+/// 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Semantic    |         Template ID X         |Data Records...|
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// | Length X      |  Data Records X ...                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Template ID Y         |  Data Records Length Y        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                     Data Records Y ...                        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SubTemplateMultiList {
+    semantic: ListSemantic,
+    entries: Vec<SubTemplateMultiListEntry>,
+}
+
+impl SubTemplateMultiList {
+    pub const fn new(semantic: ListSemantic, entries: Vec<SubTemplateMultiListEntry>) -> Self {
+        Self { semantic, entries }
+    }
+
+    pub const fn semantic(&self) -> ListSemantic {
+        self.semantic
+    }
+
+    pub const fn entries(&self) -> &Vec<SubTemplateMultiListEntry> {
+        &self.entries
+    }
+}
+
+/// Data records of a single template inside a [`SubTemplateMultiList`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SubTemplateMultiListEntry {
+    template_id: u16,
+    records: Vec<crate::ipfix::DataRecord>,
+}
+
+impl SubTemplateMultiListEntry {
+    pub const fn new(template_id: u16, records: Vec<crate::ipfix::DataRecord>) -> Self {
+        Self {
+            template_id,
+            records,
+        }
+    }
+
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    pub const fn records(&self) -> &Vec<crate::ipfix::DataRecord> {
+        &self.records
+    }
+}
+
+/// Typed value of an enterprise-specific Information Element that is defined
+/// at runtime in the [`crate::ie_registry`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum EnterpriseValue {
+    OctetArray(Vec<u8>),
+    Unsigned8(u8),
+    Unsigned16(u16),
+    Unsigned32(u32),
+    Unsigned64(u64),
+    Signed8(i8),
+    Signed16(i16),
+    Signed32(i32),
+    Signed64(i64),
+    Float32(f32),
+    Float64(f64),
+    Boolean(bool),
+    MacAddress([u8; 6]),
+    String(String),
+    DateTimeSeconds(chrono::DateTime<chrono::Utc>),
+    DateTimeMilliseconds(chrono::DateTime<chrono::Utc>),
+    DateTimeMicroseconds(chrono::DateTime<chrono::Utc>),
+    DateTimeNanoseconds(chrono::DateTime<chrono::Utc>),
+    Ipv4Address(std::net::Ipv4Addr),
+    Ipv6Address(std::net::Ipv6Addr),
+}
+
+impl EnterpriseValue {
+    pub const fn data_type(&self) -> InformationElementDataType {
+        match self {
+            Self::OctetArray(_) => InformationElementDataType::octetArray,
+            Self::Unsigned8(_) => InformationElementDataType::unsigned8,
+            Self::Unsigned16(_) => InformationElementDataType::unsigned16,
+            Self::Unsigned32(_) => InformationElementDataType::unsigned32,
+            Self::Unsigned64(_) => InformationElementDataType::unsigned64,
+            Self::Signed8(_) => InformationElementDataType::signed8,
+            Self::Signed16(_) => InformationElementDataType::signed16,
+            Self::Signed32(_) => InformationElementDataType::signed32,
+            Self::Signed64(_) => InformationElementDataType::signed64,
+            Self::Float32(_) => InformationElementDataType::float32,
+            Self::Float64(_) => InformationElementDataType::float64,
+            Self::Boolean(_) => InformationElementDataType::boolean,
+            Self::MacAddress(_) => InformationElementDataType::macAddress,
+            Self::String(_) => InformationElementDataType::string,
+            Self::DateTimeSeconds(_) => InformationElementDataType::dateTimeSeconds,
+            Self::DateTimeMilliseconds(_) => InformationElementDataType::dateTimeMilliseconds,
+            Self::DateTimeMicroseconds(_) => InformationElementDataType::dateTimeMicroseconds,
+            Self::DateTimeNanoseconds(_) => InformationElementDataType::dateTimeNanoseconds,
+            Self::Ipv4Address(_) => InformationElementDataType::ipv4Address,
+            Self::Ipv6Address(_) => InformationElementDataType::ipv6Address,
+        }
+    }
+
+    /// Serialize the value without the data type tag
+    pub(crate) fn serialize_value<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        use serde::Serialize;
+        match self {
+            Self::OctetArray(value) => value.serialize(serializer),
+            Self::Unsigned8(value) => value.serialize(serializer),
+            Self::Unsigned16(value) => value.serialize(serializer),
+            Self::Unsigned32(value) => value.serialize(serializer),
+            Self::Unsigned64(value) => value.serialize(serializer),
+            Self::Signed8(value) => value.serialize(serializer),
+            Self::Signed16(value) => value.serialize(serializer),
+            Self::Signed32(value) => value.serialize(serializer),
+            Self::Signed64(value) => value.serialize(serializer),
+            Self::Float32(value) => value.serialize(serializer),
+            Self::Float64(value) => value.serialize(serializer),
+            Self::Boolean(value) => value.serialize(serializer),
+            Self::MacAddress(value) => value.serialize(serializer),
+            Self::String(value) => value.serialize(serializer),
+            Self::DateTimeSeconds(value)
+            | Self::DateTimeMilliseconds(value)
+            | Self::DateTimeMicroseconds(value)
+            | Self::DateTimeNanoseconds(value) => value.serialize(serializer),
+            Self::Ipv4Address(value) => value.serialize(serializer),
+            Self::Ipv6Address(value) => value.serialize(serializer),
+        }
+    }
+}
+
+/// Value of an enterprise-specific Information Element decoded using its
+/// definition in the [`crate::ie_registry`]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct EnterpriseField {
+    pen: u32,
+    id: u16,
+    value: EnterpriseValue,
+}
+
+impl EnterpriseField {
+    pub const fn new(pen: u32, id: u16, value: EnterpriseValue) -> Self {
+        Self { pen, id, value }
+    }
+
+    pub const fn pen(&self) -> u32 {
+        self.pen
+    }
+
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub const fn value(&self) -> &EnterpriseValue {
+        &self.value
+    }
+}
+
+/// Which end of the flow to look up with [`flow_time`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum FlowBoundary {
+    Start,
+    End,
+}
+
+/// Resolve the flow start or end time from the timestamp fields of a record.
+/// The absolute timestamps are preferred, finest precision first, then the
+/// microseconds delta relative to the `export_time`, then the milliseconds
+/// since `system_init_time` (the sysUpTime based timestamps). The relative
+/// timestamps that fall outside of the representable time range are ignored.
+pub(crate) fn flow_time<'a>(
+    fields: impl Iterator<Item = &'a Field> + Clone,
+    boundary: FlowBoundary,
+    export_time: chrono::DateTime<chrono::Utc>,
+    system_init_time: Option<chrono::DateTime<chrono::Utc>>,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    let absolute = |precision: u8| {
+        fields
+            .clone()
+            .find_map(|field| match (boundary, precision, field) {
+                (FlowBoundary::Start, 0, Field::flowStartNanoseconds(x)) => Some(x.0),
+                (FlowBoundary::Start, 1, Field::flowStartMicroseconds(x)) => Some(x.0),
+                (FlowBoundary::Start, 2, Field::flowStartMilliseconds(x)) => Some(x.0),
+                (FlowBoundary::Start, 3, Field::flowStartSeconds(x)) => Some(x.0),
+                (FlowBoundary::End, 0, Field::flowEndNanoseconds(x)) => Some(x.0),
+                (FlowBoundary::End, 1, Field::flowEndMicroseconds(x)) => Some(x.0),
+                (FlowBoundary::End, 2, Field::flowEndMilliseconds(x)) => Some(x.0),
+                (FlowBoundary::End, 3, Field::flowEndSeconds(x)) => Some(x.0),
+                _ => None,
+            })
+    };
+    let delta = || {
+        fields.clone().find_map(|field| match (boundary, field) {
+            (
+                FlowBoundary::Start,
+                Field::flowStartDeltaMicroseconds(flowStartDeltaMicroseconds(x)),
+            )
+            | (FlowBoundary::End, Field::flowEndDeltaMicroseconds(flowEndDeltaMicroseconds(x))) => {
+                export_time.checked_sub_signed(chrono::Duration::microseconds(*x as i64))
+            }
+            _ => None,
+        })
+    };
+    let sys_up_time = || {
+        let system_init_time = system_init_time?;
+        fields.clone().find_map(|field| match (boundary, field) {
+            (FlowBoundary::Start, Field::flowStartSysUpTime(flowStartSysUpTime(x)))
+            | (FlowBoundary::End, Field::flowEndSysUpTime(flowEndSysUpTime(x))) => {
+                system_init_time.checked_add_signed(chrono::Duration::milliseconds(*x as i64))
+            }
+            _ => None,
+        })
+    };
+    (0..4)
+        .find_map(absolute)
+        .or_else(delta)
+        .or_else(sys_up_time)
+}
+
+/// Serializes a list of fields as a map keyed by the IE names, see
+/// [`IE::name`], to the values of the fields. Vendor specific IEs are
+/// prefixed with the vendor's name, e.g., `nokia:aluInsideServiceId`,
+/// enterprise IEs that are no longer in the [`crate::ie_registry`] are keyed
+/// as `<pen>:<id>`, and the fields that were not decoded are keyed by their
+/// position in the list as `unknown_<index>`.
+///
+/// Note: an IE that occurs more than once in the list results in duplicate
+/// keys.
+#[derive(Debug, Clone, Copy)]
+pub struct NamedFields<'a> {
+    scope_fields: &'a [Field],
+    fields: &'a [Field],
+}
+
+impl<'a> NamedFields<'a> {
+    /// The scope fields are rendered before the other fields
+    pub const fn new(scope_fields: &'a [Field], fields: &'a [Field]) -> Self {
+        Self {
+            scope_fields,
+            fields,
+        }
+    }
+}
+
+impl serde::Serialize for NamedFields<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+
+        /// Adapter to serialize the field's value without its IE name
+        struct Value<'a>(&'a Field);
+
+        impl serde::Serialize for Value<'_> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                self.0.serialize_value(serializer)
+            }
+        }
+
+        let mut map =
+            serializer.serialize_map(Some(self.scope_fields.len() + self.fields.len()))?;
+        for (index, field) in self.scope_fields.iter().chain(self.fields).enumerate() {
+            let name = match field.ie() {
+                Some(ie) => ie
+                    .name()
+                    .unwrap_or_else(|| format!("{}:{}", ie.pen(), ie.id())),
+                None => format!("unknown_{index}"),
+            };
+            map.serialize_entry(&name, &Value(field))?;
+        }
+        map.end()
+    }
+}
+
 include!(concat!(env!("OUT_DIR"), "/ie_generated.rs"));
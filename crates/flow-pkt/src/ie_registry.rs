@@ -0,0 +1,219 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Runtime registry of enterprise-specific Information Elements.
+//!
+//! Only the IANA and the vendor Information Elements known at build time have
+//! generated types, the fields of other enterprises are decoded as
+//! [`crate::ie::Field::Unknown`] octets. Registering the definition of such
+//! an Information Element lets the parsers decode it into a typed
+//! [`crate::ie::EnterpriseValue`] carried by [`crate::ie::Field::Enterprise`]
+//! without regenerating the code. The registry is shared by all the parsers
+//! in the process.
+//!
+//! ```
+//! use netgauze_flow_pkt::{
+//!     ie::{InformationElementDataType, InformationElementSemantics},
+//!     ie_registry::{self, EnterpriseIe},
+//! };
+//!
+//! let ie = EnterpriseIe::new(
+//!     64_000,
+//!     1,
+//!     "customerId".to_string(),
+//!     InformationElementDataType::unsigned32,
+//!     Some(InformationElementSemantics::identifier),
+//! );
+//! ie_registry::register(ie.clone()).unwrap();
+//! assert_eq!(ie_registry::lookup(64_000, 1), Some(ie));
+//! ```
+
+use std::{collections::HashMap, sync::RwLock};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::ie::{
+    InformationElementDataType, InformationElementSemantics, InformationElementUnits, IE,
+};
+
+/// The enterprise bit is carried in the Information Element identifier on
+/// the wire
+pub(crate) const ENTERPRISE_BIT: u16 = 0x8000;
+
+lazy_static! {
+    static ref REGISTRY: RwLock<HashMap<(u32, u16), EnterpriseIe>> = RwLock::new(HashMap::new());
+}
+
+/// Definition of an enterprise-specific Information Element
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnterpriseIe {
+    pen: u32,
+    id: u16,
+    name: String,
+    data_type: InformationElementDataType,
+    semantics: Option<InformationElementSemantics>,
+    units: Option<InformationElementUnits>,
+    value_range: Option<std::ops::Range<u64>>,
+}
+
+impl EnterpriseIe {
+    /// `id` is the Information Element identifier without the enterprise bit
+    pub const fn new(
+        pen: u32,
+        id: u16,
+        name: String,
+        data_type: InformationElementDataType,
+        semantics: Option<InformationElementSemantics>,
+    ) -> Self {
+        Self {
+            pen,
+            id: id & !ENTERPRISE_BIT,
+            name,
+            data_type,
+            semantics,
+            units: None,
+            value_range: None,
+        }
+    }
+
+    pub const fn with_units(mut self, units: InformationElementUnits) -> Self {
+        self.units = Some(units);
+        self
+    }
+
+    /// Range of the valid values, the end is exclusive
+    pub const fn with_value_range(mut self, value_range: std::ops::Range<u64>) -> Self {
+        self.value_range = Some(value_range);
+        self
+    }
+
+    pub const fn pen(&self) -> u32 {
+        self.pen
+    }
+
+    pub const fn id(&self) -> u16 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn data_type(&self) -> InformationElementDataType {
+        self.data_type
+    }
+
+    pub const fn semantics(&self) -> Option<InformationElementSemantics> {
+        self.semantics
+    }
+
+    pub const fn units(&self) -> Option<InformationElementUnits> {
+        self.units
+    }
+
+    pub const fn value_range(&self) -> Option<&std::ops::Range<u64>> {
+        self.value_range.as_ref()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnterpriseIeRegistrationError {
+    /// The Information Element is already decoded by the generated code, i.e.,
+    /// it's an IANA or a known vendor Information Element
+    NotAnEnterpriseIe { pen: u32, id: u16 },
+
+    /// Structured data types need the templates to be decoded
+    UnsupportedDataType(InformationElementDataType),
+}
+
+/// Register an Information Element definition, returns the previous
+/// definition of the same PEN and ID if any
+pub fn register(ie: EnterpriseIe) -> Result<Option<EnterpriseIe>, EnterpriseIeRegistrationError> {
+    if !matches!(IE::try_from((ie.pen, ie.id)), Ok(IE::Unknown { .. })) {
+        return Err(EnterpriseIeRegistrationError::NotAnEnterpriseIe {
+            pen: ie.pen,
+            id: ie.id,
+        });
+    }
+    match ie.data_type {
+        InformationElementDataType::basicList
+        | InformationElementDataType::subTemplateList
+        | InformationElementDataType::subTemplateMultiList => Err(
+            EnterpriseIeRegistrationError::UnsupportedDataType(ie.data_type),
+        ),
+        _ => Ok(REGISTRY
+            .write()
+            .expect("enterprise IE registry lock is poisoned")
+            .insert((ie.pen, ie.id), ie)),
+    }
+}
+
+/// Remove an Information Element definition, its values are decoded again as
+/// raw octets
+pub fn unregister(pen: u32, id: u16) -> Option<EnterpriseIe> {
+    REGISTRY
+        .write()
+        .expect("enterprise IE registry lock is poisoned")
+        .remove(&(pen, id & !ENTERPRISE_BIT))
+}
+
+pub fn lookup(pen: u32, id: u16) -> Option<EnterpriseIe> {
+    with_ie(pen, id, Clone::clone)
+}
+
+pub fn data_type(pen: u32, id: u16) -> Option<InformationElementDataType> {
+    with_ie(pen, id, |ie| ie.data_type)
+}
+
+pub fn semantics(pen: u32, id: u16) -> Option<InformationElementSemantics> {
+    with_ie(pen, id, |ie| ie.semantics).flatten()
+}
+
+pub fn units(pen: u32, id: u16) -> Option<InformationElementUnits> {
+    with_ie(pen, id, |ie| ie.units).flatten()
+}
+
+pub fn value_range(pen: u32, id: u16) -> Option<std::ops::Range<u64>> {
+    with_ie(pen, id, |ie| ie.value_range.clone()).flatten()
+}
+
+/// Lookup a registered Information Element by its name
+pub fn lookup_by_name(name: &str) -> Option<EnterpriseIe> {
+    REGISTRY
+        .read()
+        .expect("enterprise IE registry lock is poisoned")
+        .values()
+        .find(|ie| ie.name == name)
+        .cloned()
+}
+
+/// All the registered Information Elements
+pub fn registered() -> Vec<EnterpriseIe> {
+    REGISTRY
+        .read()
+        .expect("enterprise IE registry lock is poisoned")
+        .values()
+        .cloned()
+        .collect()
+}
+
+fn with_ie<T>(pen: u32, id: u16, f: impl FnOnce(&EnterpriseIe) -> T) -> Option<T> {
+    REGISTRY
+        .read()
+        .expect("enterprise IE registry lock is poisoned")
+        .get(&(pen, id & !ENTERPRISE_BIT))
+        .map(f)
+}
@@ -15,23 +15,29 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{cell::RefCell, rc::Rc};
 
-use crate::{ie::Field, DataSetId, FieldSpecifier};
+use crate::{
+    ie::{flow_time, Field, FlowBoundary, NamedFields},
+    template_cache::TemplateCache,
+    DataSetId, FieldSpecifier,
+};
 
 pub const IPFIX_VERSION: u16 = 10;
 
-/// A value of 2 is reserved for Template Sets
-pub(crate) const IPFIX_TEMPLATE_SET_ID: u16 = 2;
+/// A value of 2 is reserved for Template Sets, it's also used as the Template
+/// ID to withdraw all the Templates
+pub const IPFIX_TEMPLATE_SET_ID: u16 = 2;
 
-/// A value of 3 is reserved for Options Template Sets
-pub(crate) const IPFIX_OPTIONS_TEMPLATE_SET_ID: u16 = 3;
+/// A value of 3 is reserved for Options Template Sets, it's also used as the
+/// Template ID to withdraw all the Options Templates
+pub const IPFIX_OPTIONS_TEMPLATE_SET_ID: u16 = 3;
 
 /// Simpler template that is used to decode data records
 pub type DecodingTemplate = (Vec<FieldSpecifier>, Vec<FieldSpecifier>);
 
 /// Cache to store templates needed for decoding data packets
-pub type TemplatesMap = Rc<RefCell<HashMap<u16, Rc<DecodingTemplate>>>>;
+pub type TemplatesMap = Rc<RefCell<TemplateCache<DecodingTemplate>>>;
 
 /// IP Flow Information Export (IPFIX) v10 Packet.
 ///
@@ -117,6 +123,52 @@ impl IpfixPacket {
     }
 }
 
+/// Set ID of an IPFIX Set, see
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-3.3.2).
+/// Values 0 and 1 are not used for historical reasons, and values 4 to 255 are
+/// reserved for future use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SetId {
+    Template,
+    OptionsTemplate,
+    Data(DataSetId),
+}
+
+impl SetId {
+    pub const fn id(&self) -> u16 {
+        match self {
+            Self::Template => IPFIX_TEMPLATE_SET_ID,
+            Self::OptionsTemplate => IPFIX_OPTIONS_TEMPLATE_SET_ID,
+            Self::Data(id) => id.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SetIdError {
+    Reserved(u16),
+}
+
+impl TryFrom<u16> for SetId {
+    type Error = SetIdError;
+
+    fn try_from(id: u16) -> Result<Self, Self::Error> {
+        match id {
+            IPFIX_TEMPLATE_SET_ID => Ok(Self::Template),
+            IPFIX_OPTIONS_TEMPLATE_SET_ID => Ok(Self::OptionsTemplate),
+            id => DataSetId::new(id)
+                .map(Self::Data)
+                .map_err(|_| SetIdError::Reserved(id)),
+        }
+    }
+}
+
+impl From<SetId> for u16 {
+    fn from(id: SetId) -> Self {
+        id.id()
+    }
+}
+
 /// Every Set contains a common header. The Sets can be any of these three
 /// possible types: Data Set, Template Set, or Options Template Set.
 ///
@@ -145,6 +197,33 @@ impl Set {
             Self::Data { id, records: _ } => id.0,
         }
     }
+
+    pub const fn set_id(&self) -> SetId {
+        match self {
+            Self::Template(_) => SetId::Template,
+            Self::OptionsTemplate(_) => SetId::OptionsTemplate,
+            Self::Data { id, records: _ } => SetId::Data(*id),
+        }
+    }
+
+    pub const fn is_template(&self) -> bool {
+        matches!(self, Self::Template(_))
+    }
+
+    pub const fn is_options_template(&self) -> bool {
+        matches!(self, Self::OptionsTemplate(_))
+    }
+
+    pub const fn is_data(&self) -> bool {
+        matches!(self, Self::Data { .. })
+    }
+
+    pub const fn data_set_id(&self) -> Option<DataSetId> {
+        match self {
+            Self::Data { id, records: _ } => Some(*id),
+            Self::Template(_) | Self::OptionsTemplate(_) => None,
+        }
+    }
 }
 
 /// Template Records allow the Collecting Process to process
@@ -199,6 +278,12 @@ impl TemplateRecord {
     pub const fn field_specifiers(&self) -> &Vec<FieldSpecifier> {
         &self.field_specifiers
     }
+
+    /// A Template Record without any fields withdraws the Template,
+    /// see [RFC 7011](https://www.rfc-editor.org/rfc/rfc7011#section-8.1)
+    pub fn is_withdrawal(&self) -> bool {
+        self.field_specifiers.is_empty()
+    }
 }
 
 /// An Options Template Record contains any combination of IANA-assigned
@@ -291,6 +376,12 @@ impl OptionsTemplateRecord {
     pub const fn field_specifiers(&self) -> &Vec<FieldSpecifier> {
         &self.field_specifiers
     }
+
+    /// An Options Template Record without any fields withdraws the Options
+    /// Template, see [RFC 7011](https://www.rfc-editor.org/rfc/rfc7011#section-8.1)
+    pub fn is_withdrawal(&self) -> bool {
+        self.scope_field_specifiers.is_empty() && self.field_specifiers.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -307,6 +398,12 @@ impl DataRecord {
         }
     }
 
+    /// View of the record that serializes to a map keyed by the IE names,
+    /// e.g., a JSON object, see [`crate::ie::NamedFields`]
+    pub fn named_fields(&self) -> NamedFields<'_> {
+        NamedFields::new(&self.scope_fields, &self.fields)
+    }
+
     pub const fn scope_fields(&self) -> &Vec<Field> {
         &self.scope_fields
     }
@@ -314,4 +411,30 @@ impl DataRecord {
     pub const fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
+
+    /// Time of the first packet of the flow, resolved from any of the flow
+    /// start timestamps. `export_time` is the export time of the message
+    /// carrying the record, and the sysUpTime based timestamps are only
+    /// resolved when the record carries the `systemInitTimeMilliseconds`.
+    pub fn start_time(&self, export_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.flow_time(FlowBoundary::Start, export_time)
+    }
+
+    /// Time of the last packet of the flow, see [`DataRecord::start_time`]
+    pub fn end_time(&self, export_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.flow_time(FlowBoundary::End, export_time)
+    }
+
+    fn flow_time(
+        &self,
+        boundary: FlowBoundary,
+        export_time: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let fields = self.scope_fields.iter().chain(self.fields.iter());
+        let system_init_time = fields.clone().find_map(|field| match field {
+            Field::systemInitTimeMilliseconds(x) => Some(x.0),
+            _ => None,
+        });
+        flow_time(fields, boundary, export_time, system_init_time)
+    }
 }
@@ -13,11 +13,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+pub mod biflow;
 #[cfg(feature = "codec")]
 pub mod codec;
+pub mod convert;
 pub mod ie;
+pub mod ie_registry;
 pub mod ipfix;
 pub mod netflow;
+pub mod options_data;
+pub mod sequence;
+pub mod template_cache;
 #[cfg(feature = "serde")]
 pub mod wire;
 
@@ -76,14 +82,17 @@ impl FieldSpecifier {
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum DataSetIdError {
+    /// Values below [`DATA_SET_MIN_ID`] are used for the Template Sets or are
+    /// reserved
     InvalidId(u16),
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct DataSetId(u16);
 
-/// Values 256 and above are used for Data Sets
-pub(crate) const DATA_SET_MIN_ID: u16 = 256;
+/// Values 256 and above are used for Data Sets, it's also the first Template
+/// ID that can be used
+pub const DATA_SET_MIN_ID: u16 = 256;
 
 impl DataSetId {
     pub const fn new(id: u16) -> Result<Self, DataSetIdError> {
@@ -100,6 +109,20 @@ impl DataSetId {
     }
 }
 
+impl TryFrom<u16> for DataSetId {
+    type Error = DataSetIdError;
+
+    fn try_from(id: u16) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl From<DataSetId> for u16 {
+    fn from(id: DataSetId) -> Self {
+        id.0
+    }
+}
+
 impl Deref for DataSetId {
     type Target = u16;
 
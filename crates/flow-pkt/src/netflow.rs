@@ -15,28 +15,29 @@
 
 use crate::{
     ie::{
-        Field, InformationElementDataType, InformationElementSemantics, InformationElementTemplate,
-        InformationElementUnits,
+        flow_time, Field, FlowBoundary, InformationElementDataType, InformationElementSemantics,
+        InformationElementTemplate, InformationElementUnits,
     },
+    template_cache::TemplateCache,
     DataSetId, FieldSpecifier,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::{cell::RefCell, collections::HashMap, ops::Range, rc::Rc};
+use std::{cell::RefCell, ops::Range, rc::Rc};
 
 pub const NETFLOW_V9_VERSION: u16 = 9;
 
 /// A value of 0 is reserved for Template Sets
-pub(crate) const NETFLOW_TEMPLATE_SET_ID: u16 = 0;
+pub const NETFLOW_TEMPLATE_SET_ID: u16 = 0;
 
-/// A value of 3 is reserved for Options Template Sets
-pub(crate) const NETFLOW_OPTIONS_TEMPLATE_SET_ID: u16 = 1;
+/// A value of 1 is reserved for Options Template Sets
+pub const NETFLOW_OPTIONS_TEMPLATE_SET_ID: u16 = 1;
 
 /// Simpler template that is used to decode data records
 pub type DecodingTemplate = (Vec<ScopeFieldSpecifier>, Vec<FieldSpecifier>);
 
 /// Cache to store templates needed for decoding data packets
-pub type TemplatesMap = Rc<RefCell<HashMap<u16, Rc<DecodingTemplate>>>>;
+pub type TemplatesMap = Rc<RefCell<TemplateCache<DecodingTemplate>>>;
 
 ///
 /// ```text
@@ -115,6 +116,51 @@ impl NetFlowV9Packet {
     }
 }
 
+/// FlowSet ID of a NetFlow V9 FlowSet, see
+/// [RFC3954](https://www.rfc-editor.org/rfc/rfc3954#section-5.2). Values 2 to
+/// 255 are reserved for future use.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SetId {
+    Template,
+    OptionsTemplate,
+    Data(DataSetId),
+}
+
+impl SetId {
+    pub const fn id(&self) -> u16 {
+        match self {
+            Self::Template => NETFLOW_TEMPLATE_SET_ID,
+            Self::OptionsTemplate => NETFLOW_OPTIONS_TEMPLATE_SET_ID,
+            Self::Data(id) => id.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SetIdError {
+    Reserved(u16),
+}
+
+impl TryFrom<u16> for SetId {
+    type Error = SetIdError;
+
+    fn try_from(id: u16) -> Result<Self, Self::Error> {
+        match id {
+            NETFLOW_TEMPLATE_SET_ID => Ok(Self::Template),
+            NETFLOW_OPTIONS_TEMPLATE_SET_ID => Ok(Self::OptionsTemplate),
+            id => DataSetId::new(id)
+                .map(Self::Data)
+                .map_err(|_| SetIdError::Reserved(id)),
+        }
+    }
+}
+
+impl From<SetId> for u16 {
+    fn from(id: SetId) -> Self {
+        id.id()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Set {
     Template(Vec<TemplateRecord>),
@@ -133,6 +179,33 @@ impl Set {
             Self::Data { id, records: _ } => id.0,
         }
     }
+
+    pub const fn set_id(&self) -> SetId {
+        match self {
+            Self::Template(_) => SetId::Template,
+            Self::OptionsTemplate(_) => SetId::OptionsTemplate,
+            Self::Data { id, records: _ } => SetId::Data(*id),
+        }
+    }
+
+    pub const fn is_template(&self) -> bool {
+        matches!(self, Self::Template(_))
+    }
+
+    pub const fn is_options_template(&self) -> bool {
+        matches!(self, Self::OptionsTemplate(_))
+    }
+
+    pub const fn is_data(&self) -> bool {
+        matches!(self, Self::Data { .. })
+    }
+
+    pub const fn data_set_id(&self) -> Option<DataSetId> {
+        match self {
+            Self::Data { id, records: _ } => Some(*id),
+            Self::Template(_) | Self::OptionsTemplate(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -218,6 +291,29 @@ impl DataRecord {
     pub const fn fields(&self) -> &Vec<Field> {
         &self.fields
     }
+
+    /// Time of the first packet of the flow, resolved from any of the flow
+    /// start timestamps. `sys_up_time` and `unix_time` are from the header of
+    /// the packet carrying the record.
+    pub fn start_time(&self, sys_up_time: u32, unix_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.flow_time(FlowBoundary::Start, sys_up_time, unix_time)
+    }
+
+    /// Time of the last packet of the flow, see [`DataRecord::start_time`]
+    pub fn end_time(&self, sys_up_time: u32, unix_time: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.flow_time(FlowBoundary::End, sys_up_time, unix_time)
+    }
+
+    fn flow_time(
+        &self,
+        boundary: FlowBoundary,
+        sys_up_time: u32,
+        unix_time: DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        let system_init_time =
+            unix_time.checked_sub_signed(chrono::Duration::milliseconds(sys_up_time as i64));
+        flow_time(self.fields.iter(), boundary, unix_time, system_init_time)
+    }
 }
 
 #[derive(Clone, PartialEq, Debug, serde::Serialize, serde::Deserialize)]
@@ -0,0 +1,919 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed tables of the common options data sent by the exporters.
+//!
+//! The exporters describe the samplers, the interfaces, the VRFs, the
+//! applications, and their own statistics in options data records, i.e., the
+//! data records of an options template. [`OptionsDataCache`] decodes these
+//! records into typed tables keyed by the [`TemplateScope`] of the exporter, so
+//! the flow records can be enriched, e.g., with the sampling interval or the
+//! interface name, see [`OptionsDataCache::labels`]. Like the templates, the
+//! tables are updated with every decoded packet, the latest value of each
+//! attribute wins.
+//!
+//! The entries remember the options template they are learned from. When the
+//! exporter withdraws that options template, or announces it again with a
+//! different definition, the entries are removed since their meaning may have
+//! changed, and they are learned again from the next options data records.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{self, Field},
+    ipfix::{self, IpfixPacket, IPFIX_OPTIONS_TEMPLATE_SET_ID},
+    netflow::{self, NetFlowV9Packet, ScopeField, ScopeFieldSpecifier},
+    template_cache::TemplateScope,
+    FieldSpecifier,
+};
+
+/// Sampling algorithm announced for a sampler
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SamplingAlgorithm {
+    Deterministic,
+    Random,
+    /// Value of the deprecated `samplingAlgorithm` or `samplerMode` that is not
+    /// known
+    Unknown(u8),
+    /// PSAMP selector algorithm, see
+    /// [RFC5477](https://datatracker.ietf.org/doc/html/rfc5477#section-8.2.1)
+    Selector(u16),
+}
+
+/// Sampler announced in the options data, keyed by `samplerId` or
+/// `selectorId`
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SamplerOptions {
+    algorithm: Option<SamplingAlgorithm>,
+    interval: Option<u32>,
+    name: Option<String>,
+}
+
+impl SamplerOptions {
+    pub const fn algorithm(&self) -> Option<SamplingAlgorithm> {
+        self.algorithm
+    }
+
+    /// One packet is sampled out of `interval` packets
+    pub const fn interval(&self) -> Option<u32> {
+        self.interval
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    fn update(&mut self, fields: &[&Field]) {
+        let mut packet_interval = None;
+        let mut packet_space = None;
+        let mut size = None;
+        let mut population = None;
+        for field in fields {
+            match field {
+                Field::samplingAlgorithm(ie::samplingAlgorithm(x))
+                | Field::samplerMode(ie::samplerMode(x)) => {
+                    self.algorithm = Some(match x {
+                        1 => SamplingAlgorithm::Deterministic,
+                        2 => SamplingAlgorithm::Random,
+                        x => SamplingAlgorithm::Unknown(*x),
+                    })
+                }
+                Field::selectorAlgorithm(ie::selectorAlgorithm(x)) => {
+                    self.algorithm = Some(SamplingAlgorithm::Selector(*x))
+                }
+                Field::samplingInterval(ie::samplingInterval(x))
+                | Field::samplerRandomInterval(ie::samplerRandomInterval(x)) => {
+                    self.interval = Some(*x)
+                }
+                Field::samplingPacketInterval(ie::samplingPacketInterval(x)) => {
+                    packet_interval = Some(*x)
+                }
+                Field::samplingPacketSpace(ie::samplingPacketSpace(x)) => packet_space = Some(*x),
+                Field::samplingSize(ie::samplingSize(x)) => size = Some(*x),
+                Field::samplingPopulation(ie::samplingPopulation(x)) => population = Some(*x),
+                Field::samplerName(ie::samplerName(x)) => self.name = Some(x.clone()),
+                _ => {}
+            }
+        }
+        // Systematic count-based sampling selects `packet_interval` packets then
+        // skips `packet_space` packets, random n-out-of-N sampling selects `size`
+        // packets out of `population` packets
+        if let (Some(selected), Some(skipped)) = (packet_interval, packet_space) {
+            if let Some(interval) = selected.saturating_add(skipped).checked_div(selected) {
+                self.interval = Some(interval);
+            }
+        }
+        if let (Some(selected), Some(population)) = (size, population) {
+            if let Some(interval) = population.checked_div(selected) {
+                self.interval = Some(interval);
+            }
+        }
+    }
+}
+
+/// Interface announced in the options data, keyed by its ifIndex
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct InterfaceOptions {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+impl InterfaceOptions {
+    /// The ifName of the interface
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// The ifDescr of the interface
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn update(&mut self, fields: &[&Field]) {
+        for field in fields {
+            match field {
+                Field::interfaceName(ie::interfaceName(x)) => self.name = Some(x.clone()),
+                Field::interfaceDescription(ie::interfaceDescription(x)) => {
+                    self.description = Some(x.clone())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Application announced in the options data, keyed by its `applicationId`,
+/// see [RFC6759](https://datatracker.ietf.org/doc/html/rfc6759#section-5)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ApplicationOptions {
+    name: Option<String>,
+    description: Option<String>,
+}
+
+impl ApplicationOptions {
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
+    fn update(&mut self, fields: &[&Field]) {
+        for field in fields {
+            match field {
+                Field::applicationName(ie::applicationName(x)) => self.name = Some(x.clone()),
+                Field::applicationDescription(ie::applicationDescription(x)) => {
+                    self.description = Some(x.clone())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Statistics of the exporting process, see
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-4.3)
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ExporterStats {
+    exported_message_total_count: Option<u64>,
+    exported_flow_record_total_count: Option<u64>,
+    exported_octet_total_count: Option<u64>,
+    ignored_packet_total_count: Option<u64>,
+    ignored_octet_total_count: Option<u64>,
+    not_sent_flow_total_count: Option<u64>,
+}
+
+impl ExporterStats {
+    pub const fn exported_message_total_count(&self) -> Option<u64> {
+        self.exported_message_total_count
+    }
+
+    pub const fn exported_flow_record_total_count(&self) -> Option<u64> {
+        self.exported_flow_record_total_count
+    }
+
+    pub const fn exported_octet_total_count(&self) -> Option<u64> {
+        self.exported_octet_total_count
+    }
+
+    pub const fn ignored_packet_total_count(&self) -> Option<u64> {
+        self.ignored_packet_total_count
+    }
+
+    pub const fn ignored_octet_total_count(&self) -> Option<u64> {
+        self.ignored_octet_total_count
+    }
+
+    pub const fn not_sent_flow_total_count(&self) -> Option<u64> {
+        self.not_sent_flow_total_count
+    }
+
+    fn update(&mut self, fields: &[&Field]) {
+        for field in fields {
+            match field {
+                Field::exportedMessageTotalCount(ie::exportedMessageTotalCount(x)) => {
+                    self.exported_message_total_count = Some(*x)
+                }
+                Field::exportedFlowRecordTotalCount(ie::exportedFlowRecordTotalCount(x)) => {
+                    self.exported_flow_record_total_count = Some(*x)
+                }
+                Field::exportedOctetTotalCount(ie::exportedOctetTotalCount(x)) => {
+                    self.exported_octet_total_count = Some(*x)
+                }
+                Field::ignoredPacketTotalCount(ie::ignoredPacketTotalCount(x)) => {
+                    self.ignored_packet_total_count = Some(*x)
+                }
+                Field::ignoredOctetTotalCount(ie::ignoredOctetTotalCount(x)) => {
+                    self.ignored_octet_total_count = Some(*x)
+                }
+                Field::notSentFlowTotalCount(ie::notSentFlowTotalCount(x)) => {
+                    self.not_sent_flow_total_count = Some(*x)
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Key of an entry learned from an options template
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum LearnedKey {
+    Sampler(u64),
+    Interface(u32),
+    Vrf(u32),
+    Application(Vec<u8>),
+}
+
+/// Last announced definition of an options template, to detect when it changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OptionsTemplateDefinition {
+    Ipfix(Vec<FieldSpecifier>, Vec<FieldSpecifier>),
+    NetFlowV9(Vec<ScopeFieldSpecifier>, Vec<FieldSpecifier>),
+}
+
+/// Typed tables of the options data, see the [module](self) docs
+#[derive(Debug, Clone, Default)]
+pub struct OptionsDataCache {
+    samplers: HashMap<(TemplateScope, u64), SamplerOptions>,
+    interfaces: HashMap<(TemplateScope, u32), InterfaceOptions>,
+    vrfs: HashMap<(TemplateScope, u32), String>,
+    applications: HashMap<(TemplateScope, Vec<u8>), ApplicationOptions>,
+    exporters: HashMap<TemplateScope, ExporterStats>,
+    definitions: HashMap<(TemplateScope, u16), OptionsTemplateDefinition>,
+    learned: HashMap<(TemplateScope, u16), Vec<LearnedKey>>,
+}
+
+impl OptionsDataCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn sampler(&self, scope: &TemplateScope, sampler_id: u64) -> Option<&SamplerOptions> {
+        self.samplers.get(&(*scope, sampler_id))
+    }
+
+    pub fn interface(&self, scope: &TemplateScope, if_index: u32) -> Option<&InterfaceOptions> {
+        self.interfaces.get(&(*scope, if_index))
+    }
+
+    pub fn vrf_name(&self, scope: &TemplateScope, vrf_id: u32) -> Option<&str> {
+        self.vrfs.get(&(*scope, vrf_id)).map(|x| x.as_str())
+    }
+
+    pub fn application(
+        &self,
+        scope: &TemplateScope,
+        application_id: &[u8],
+    ) -> Option<&ApplicationOptions> {
+        self.applications.get(&(*scope, application_id.to_vec()))
+    }
+
+    pub fn exporter_stats(&self, scope: &TemplateScope) -> Option<&ExporterStats> {
+        self.exporters.get(scope)
+    }
+
+    /// Labels to enrich a flow record sent by the exporter of `scope` with the
+    /// names and descriptions of its interfaces, VRFs, and application. Only
+    /// the labels known in the options data are returned.
+    pub fn labels<'a>(
+        &self,
+        scope: &TemplateScope,
+        fields: impl IntoIterator<Item = &'a Field>,
+    ) -> BTreeMap<&'static str, String> {
+        let mut labels = BTreeMap::new();
+        let mut add = |key, value: Option<&str>| {
+            if let Some(value) = value {
+                labels.insert(key, value.to_string());
+            }
+        };
+        for field in fields {
+            match field {
+                Field::ingressInterface(ie::ingressInterface(if_index)) => {
+                    if let Some(interface) = self.interface(scope, *if_index) {
+                        add("ingressInterfaceName", interface.name());
+                        add("ingressInterfaceDescription", interface.description());
+                    }
+                }
+                Field::egressInterface(ie::egressInterface(if_index)) => {
+                    if let Some(interface) = self.interface(scope, *if_index) {
+                        add("egressInterfaceName", interface.name());
+                        add("egressInterfaceDescription", interface.description());
+                    }
+                }
+                Field::ingressVRFID(ie::ingressVRFID(vrf_id)) => {
+                    add("ingressVRFName", self.vrf_name(scope, *vrf_id))
+                }
+                Field::egressVRFID(ie::egressVRFID(vrf_id)) => {
+                    add("egressVRFName", self.vrf_name(scope, *vrf_id))
+                }
+                Field::applicationId(ie::applicationId(application_id)) => {
+                    if let Some(application) = self.application(scope, application_id) {
+                        add("applicationName", application.name());
+                        add("applicationDescription", application.description());
+                    }
+                }
+                _ => {}
+            }
+        }
+        labels
+    }
+
+    /// Copy of all the tables that can be persisted and later restored with
+    /// [`OptionsDataCache::restore`]
+    pub fn snapshot(&self) -> OptionsDataSnapshot {
+        OptionsDataSnapshot {
+            samplers: self
+                .samplers
+                .iter()
+                .map(|((scope, id), sampler)| (*scope, *id, sampler.clone()))
+                .collect(),
+            interfaces: self
+                .interfaces
+                .iter()
+                .map(|((scope, if_index), interface)| (*scope, *if_index, interface.clone()))
+                .collect(),
+            vrfs: self
+                .vrfs
+                .iter()
+                .map(|((scope, vrf_id), name)| (*scope, *vrf_id, name.clone()))
+                .collect(),
+            applications: self
+                .applications
+                .iter()
+                .map(|((scope, id), application)| (*scope, id.clone(), application.clone()))
+                .collect(),
+            exporters: self
+                .exporters
+                .iter()
+                .map(|(scope, stats)| (*scope, stats.clone()))
+                .collect(),
+        }
+    }
+
+    /// Add the entries of a snapshot, the entries already in the tables are
+    /// more recent and are kept
+    pub fn restore(&mut self, snapshot: OptionsDataSnapshot) {
+        for (scope, id, sampler) in snapshot.samplers {
+            self.samplers.entry((scope, id)).or_insert(sampler);
+        }
+        for (scope, if_index, interface) in snapshot.interfaces {
+            self.interfaces
+                .entry((scope, if_index))
+                .or_insert(interface);
+        }
+        for (scope, vrf_id, name) in snapshot.vrfs {
+            self.vrfs.entry((scope, vrf_id)).or_insert(name);
+        }
+        for (scope, id, application) in snapshot.applications {
+            self.applications.entry((scope, id)).or_insert(application);
+        }
+        for (scope, stats) in snapshot.exporters {
+            self.exporters.entry(scope).or_insert(stats);
+        }
+    }
+
+    /// Remove all the options data of a given scope, e.g., when the exporter is
+    /// gone
+    pub fn remove_scope(&mut self, scope: &TemplateScope) {
+        self.samplers.retain(|(s, _), _| s != scope);
+        self.interfaces.retain(|(s, _), _| s != scope);
+        self.vrfs.retain(|(s, _), _| s != scope);
+        self.applications.retain(|(s, _), _| s != scope);
+        self.exporters.remove(scope);
+        self.definitions.retain(|(s, _), _| s != scope);
+        self.learned.retain(|(s, _), _| s != scope);
+    }
+
+    /// Remove the entries learned from the options data records of a given
+    /// options template. Called when the options template is withdrawn or
+    /// redefined, and can be called when it expires in the templates cache.
+    pub fn invalidate(&mut self, scope: &TemplateScope, template_id: u16) {
+        self.definitions.remove(&(*scope, template_id));
+        let Some(keys) = self.learned.remove(&(*scope, template_id)) else {
+            return;
+        };
+        for key in keys {
+            match key {
+                LearnedKey::Sampler(id) => {
+                    self.samplers.remove(&(*scope, id));
+                }
+                LearnedKey::Interface(if_index) => {
+                    self.interfaces.remove(&(*scope, if_index));
+                }
+                LearnedKey::Vrf(vrf_id) => {
+                    self.vrfs.remove(&(*scope, vrf_id));
+                }
+                LearnedKey::Application(id) => {
+                    self.applications.remove(&(*scope, id));
+                }
+            }
+        }
+    }
+
+    /// Invalidate the entries of the options template if its definition
+    /// changed, and remember the new definition
+    fn announce(
+        &mut self,
+        scope: TemplateScope,
+        template_id: u16,
+        definition: OptionsTemplateDefinition,
+    ) {
+        if self.definitions.get(&(scope, template_id)) != Some(&definition) {
+            self.invalidate(&scope, template_id);
+            self.definitions.insert((scope, template_id), definition);
+        }
+    }
+
+    /// Invalidate the entries of all the options templates of the scope
+    fn invalidate_all(&mut self, scope: &TemplateScope) {
+        let template_ids = self
+            .learned
+            .keys()
+            .chain(self.definitions.keys())
+            .filter(|(s, _)| s == scope)
+            .map(|(_, id)| *id)
+            .collect::<Vec<_>>();
+        for template_id in template_ids {
+            self.invalidate(scope, template_id);
+        }
+    }
+
+    /// Update the tables from the options data records of an IPFIX packet sent
+    /// by `peer`
+    pub fn update_ipfix(&mut self, peer: Option<SocketAddr>, pkt: &IpfixPacket) {
+        let scope = TemplateScope::new(peer, pkt.observation_domain_id());
+        for set in pkt.sets() {
+            match set {
+                ipfix::Set::Template(templates) => {
+                    // A template can reuse the ID of an options template
+                    for template in templates {
+                        self.invalidate(&scope, template.id());
+                    }
+                }
+                ipfix::Set::OptionsTemplate(templates) => {
+                    for template in templates {
+                        if template.is_withdrawal() {
+                            if template.id() == IPFIX_OPTIONS_TEMPLATE_SET_ID {
+                                self.invalidate_all(&scope);
+                            } else {
+                                self.invalidate(&scope, template.id());
+                            }
+                        } else {
+                            let definition = OptionsTemplateDefinition::Ipfix(
+                                template.scope_field_specifiers().clone(),
+                                template.field_specifiers().clone(),
+                            );
+                            self.announce(scope, template.id(), definition);
+                        }
+                    }
+                }
+                ipfix::Set::Data { id, records } => {
+                    for record in records.iter().filter(|x| !x.scope_fields().is_empty()) {
+                        let fields = record
+                            .scope_fields()
+                            .iter()
+                            .chain(record.fields())
+                            .collect::<Vec<_>>();
+                        self.update(scope, id.id(), None, &fields);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Update the tables from the options data records of a NetFlow V9 packet
+    /// sent by `peer`
+    pub fn update_netflow_v9(&mut self, peer: Option<SocketAddr>, pkt: &NetFlowV9Packet) {
+        let scope = TemplateScope::new(peer, pkt.source_id());
+        for set in pkt.sets() {
+            match set {
+                netflow::Set::Template(templates) => {
+                    for template in templates {
+                        self.invalidate(&scope, template.id());
+                    }
+                }
+                netflow::Set::OptionsTemplate(templates) => {
+                    for template in templates {
+                        let definition = OptionsTemplateDefinition::NetFlowV9(
+                            template.scope_field_specifiers().clone(),
+                            template.field_specifiers().clone(),
+                        );
+                        self.announce(scope, template.id(), definition);
+                    }
+                }
+                netflow::Set::Data { id, records } => {
+                    for record in records.iter().filter(|x| !x.scope_fields().is_empty()) {
+                        let if_index = record.scope_fields().iter().find_map(|x| match x {
+                            ScopeField::Interface(netflow::Interface(if_index)) => Some(*if_index),
+                            _ => None,
+                        });
+                        let fields = record.fields().iter().collect::<Vec<_>>();
+                        self.update(scope, id.id(), if_index, &fields);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Remember that the entry is learned from the given options template
+    fn learn(&mut self, scope: TemplateScope, template_id: u16, key: LearnedKey) {
+        let keys = self.learned.entry((scope, template_id)).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    fn update(
+        &mut self,
+        scope: TemplateScope,
+        template_id: u16,
+        if_index: Option<u32>,
+        fields: &[&Field],
+    ) {
+        let sampler_id = fields.iter().find_map(|x| match x {
+            Field::samplerId(ie::samplerId(id)) => Some(*id as u64),
+            Field::selectorId(ie::selectorId(id)) => Some(*id),
+            _ => None,
+        });
+        if let Some(sampler_id) = sampler_id {
+            self.samplers
+                .entry((scope, sampler_id))
+                .or_default()
+                .update(fields);
+            self.learn(scope, template_id, LearnedKey::Sampler(sampler_id));
+        }
+
+        let if_index = if_index.or_else(|| {
+            fields.iter().find_map(|x| match x {
+                Field::ingressInterface(ie::ingressInterface(if_index))
+                | Field::egressInterface(ie::egressInterface(if_index)) => Some(*if_index),
+                _ => None,
+            })
+        });
+        let is_interface = fields
+            .iter()
+            .any(|x| matches!(x, Field::interfaceName(_) | Field::interfaceDescription(_)));
+        if let (Some(if_index), true) = (if_index, is_interface) {
+            self.interfaces
+                .entry((scope, if_index))
+                .or_default()
+                .update(fields);
+            self.learn(scope, template_id, LearnedKey::Interface(if_index));
+        }
+
+        let vrf_id = fields.iter().find_map(|x| match x {
+            Field::ingressVRFID(ie::ingressVRFID(vrf_id))
+            | Field::egressVRFID(ie::egressVRFID(vrf_id)) => Some(*vrf_id),
+            _ => None,
+        });
+        let vrf_name = fields.iter().find_map(|x| match x {
+            Field::VRFname(ie::VRFname(name)) => Some(name),
+            _ => None,
+        });
+        if let (Some(vrf_id), Some(vrf_name)) = (vrf_id, vrf_name) {
+            self.vrfs.insert((scope, vrf_id), vrf_name.clone());
+            self.learn(scope, template_id, LearnedKey::Vrf(vrf_id));
+        }
+
+        let application_id = fields.iter().find_map(|x| match x {
+            Field::applicationId(ie::applicationId(id)) => Some(id),
+            _ => None,
+        });
+        let is_application = fields.iter().any(|x| {
+            matches!(
+                x,
+                Field::applicationName(_) | Field::applicationDescription(_)
+            )
+        });
+        if let (Some(application_id), true) = (application_id, is_application) {
+            self.applications
+                .entry((scope, application_id.clone()))
+                .or_default()
+                .update(fields);
+            self.learn(
+                scope,
+                template_id,
+                LearnedKey::Application(application_id.clone()),
+            );
+        }
+
+        let is_stats = fields.iter().any(|x| {
+            matches!(
+                x,
+                Field::exportedMessageTotalCount(_)
+                    | Field::exportedFlowRecordTotalCount(_)
+                    | Field::exportedOctetTotalCount(_)
+                    | Field::ignoredPacketTotalCount(_)
+                    | Field::ignoredOctetTotalCount(_)
+                    | Field::notSentFlowTotalCount(_)
+            )
+        });
+        if is_stats {
+            self.exporters.entry(scope).or_default().update(fields);
+        }
+    }
+}
+
+/// Persisted form of [`OptionsDataCache`], the tables are kept in lists so the
+/// snapshot can be serialized to any format
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct OptionsDataSnapshot {
+    samplers: Vec<(TemplateScope, u64, SamplerOptions)>,
+    interfaces: Vec<(TemplateScope, u32, InterfaceOptions)>,
+    #[serde(default)]
+    vrfs: Vec<(TemplateScope, u32, String)>,
+    #[serde(default)]
+    applications: Vec<(TemplateScope, Vec<u8>, ApplicationOptions)>,
+    exporters: Vec<(TemplateScope, ExporterStats)>,
+}
+
+impl OptionsDataSnapshot {
+    /// Split the snapshot into a snapshot per scope, e.g., to inspect the
+    /// options data of every exporter
+    pub fn split_by_scope(self) -> HashMap<TemplateScope, OptionsDataSnapshot> {
+        let mut scopes = HashMap::<_, OptionsDataSnapshot>::new();
+        for (scope, id, sampler) in self.samplers {
+            let snapshot = scopes.entry(scope).or_default();
+            snapshot.samplers.push((scope, id, sampler));
+        }
+        for (scope, if_index, interface) in self.interfaces {
+            let snapshot = scopes.entry(scope).or_default();
+            snapshot.interfaces.push((scope, if_index, interface));
+        }
+        for (scope, vrf_id, name) in self.vrfs {
+            let snapshot = scopes.entry(scope).or_default();
+            snapshot.vrfs.push((scope, vrf_id, name));
+        }
+        for (scope, id, application) in self.applications {
+            let snapshot = scopes.entry(scope).or_default();
+            snapshot.applications.push((scope, id, application));
+        }
+        for (scope, stats) in self.exporters {
+            let snapshot = scopes.entry(scope).or_default();
+            snapshot.exporters.push((scope, stats));
+        }
+        scopes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::DataSetId;
+
+    #[test]
+    fn test_ipfix_options_data() {
+        let peer = Some("192.0.2.1:4739".parse().unwrap());
+        let record = |scope_fields, fields| ipfix::DataRecord::new(scope_fields, fields);
+        let pkt = IpfixPacket::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            0,
+            7,
+            vec![ipfix::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![
+                    record(
+                        vec![Field::selectorId(ie::selectorId(1))],
+                        vec![
+                            Field::selectorAlgorithm(ie::selectorAlgorithm(1)),
+                            Field::samplingPacketInterval(ie::samplingPacketInterval(1)),
+                            Field::samplingPacketSpace(ie::samplingPacketSpace(99)),
+                        ],
+                    ),
+                    record(
+                        vec![Field::ingressInterface(ie::ingressInterface(3))],
+                        vec![
+                            Field::interfaceName(ie::interfaceName("eth0".to_string())),
+                            Field::interfaceDescription(ie::interfaceDescription(
+                                "uplink".to_string(),
+                            )),
+                        ],
+                    ),
+                    record(
+                        vec![Field::exportingProcessId(ie::exportingProcessId(1))],
+                        vec![
+                            Field::exportedMessageTotalCount(ie::exportedMessageTotalCount(10)),
+                            Field::exportedFlowRecordTotalCount(ie::exportedFlowRecordTotalCount(
+                                100,
+                            )),
+                        ],
+                    ),
+                    // Flow records are not options data
+                    record(
+                        vec![],
+                        vec![
+                            Field::ingressInterface(ie::ingressInterface(4)),
+                            Field::interfaceName(ie::interfaceName("eth1".to_string())),
+                        ],
+                    ),
+                ],
+            }],
+        );
+        let mut cache = OptionsDataCache::new();
+        cache.update_ipfix(peer, &pkt);
+
+        let scope = TemplateScope::new(peer, 7);
+        let sampler = cache.sampler(&scope, 1).unwrap();
+        assert_eq!(sampler.algorithm(), Some(SamplingAlgorithm::Selector(1)));
+        assert_eq!(sampler.interval(), Some(100));
+        let interface = cache.interface(&scope, 3).unwrap();
+        assert_eq!(interface.name(), Some("eth0"));
+        assert_eq!(interface.description(), Some("uplink"));
+        assert_eq!(cache.interface(&scope, 4), None);
+        let stats = cache.exporter_stats(&scope).unwrap();
+        assert_eq!(stats.exported_message_total_count(), Some(10));
+        assert_eq!(stats.exported_flow_record_total_count(), Some(100));
+        assert_eq!(stats.exported_octet_total_count(), None);
+        assert_eq!(cache.sampler(&TemplateScope::new(None, 7), 1), None);
+
+        cache.remove_scope(&scope);
+        assert_eq!(cache.sampler(&scope, 1), None);
+        assert_eq!(cache.interface(&scope, 3), None);
+        assert_eq!(cache.exporter_stats(&scope), None);
+    }
+
+    #[test]
+    fn test_ipfix_options_data_labels() {
+        let peer = Some("192.0.2.1:4739".parse().unwrap());
+        let scope = TemplateScope::new(peer, 7);
+        let options_template = |scope_ie, ie| {
+            ipfix::Set::OptionsTemplate(vec![ipfix::OptionsTemplateRecord::new(
+                258,
+                vec![FieldSpecifier::new(scope_ie, 4).unwrap()],
+                vec![FieldSpecifier::new(ie, 16).unwrap()],
+            )])
+        };
+        let pkt = |sets| {
+            IpfixPacket::new(
+                Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                0,
+                7,
+                sets,
+            )
+        };
+        let data = |records| ipfix::Set::Data {
+            id: DataSetId::new(258).unwrap(),
+            records,
+        };
+        let mut cache = OptionsDataCache::new();
+        cache.update_ipfix(
+            peer,
+            &pkt(vec![
+                options_template(ie::IE::ingressVRFID, ie::IE::VRFname),
+                data(vec![ipfix::DataRecord::new(
+                    vec![Field::ingressVRFID(ie::ingressVRFID(3))],
+                    vec![Field::VRFname(ie::VRFname("blue".to_string()))],
+                )]),
+            ]),
+        );
+        cache.update_ipfix(
+            peer,
+            &pkt(vec![ipfix::Set::Data {
+                id: DataSetId::new(259).unwrap(),
+                records: vec![
+                    ipfix::DataRecord::new(
+                        vec![Field::ingressInterface(ie::ingressInterface(1))],
+                        vec![
+                            Field::interfaceName(ie::interfaceName("eth0".to_string())),
+                            Field::interfaceDescription(ie::interfaceDescription(
+                                "uplink".to_string(),
+                            )),
+                        ],
+                    ),
+                    ipfix::DataRecord::new(
+                        vec![Field::applicationId(ie::applicationId(vec![3, 0, 80]))],
+                        vec![Field::applicationName(ie::applicationName(
+                            "http".to_string(),
+                        ))],
+                    ),
+                ],
+            }]),
+        );
+
+        let flow = vec![
+            Field::ingressInterface(ie::ingressInterface(1)),
+            Field::egressInterface(ie::egressInterface(2)),
+            Field::ingressVRFID(ie::ingressVRFID(3)),
+            Field::applicationId(ie::applicationId(vec![3, 0, 80])),
+        ];
+        assert_eq!(
+            cache.labels(&scope, &flow),
+            BTreeMap::from([
+                ("applicationName", "http".to_string()),
+                ("ingressInterfaceDescription", "uplink".to_string()),
+                ("ingressInterfaceName", "eth0".to_string()),
+                ("ingressVRFName", "blue".to_string()),
+            ])
+        );
+        assert!(cache.labels(&TemplateScope::new(None, 7), &flow).is_empty());
+
+        // Announcing the same definition again keeps the learned entries, while
+        // a new definition invalidates them
+        cache.update_ipfix(
+            peer,
+            &pkt(vec![options_template(
+                ie::IE::ingressVRFID,
+                ie::IE::VRFname,
+            )]),
+        );
+        assert_eq!(cache.vrf_name(&scope, 3), Some("blue"));
+        cache.update_ipfix(
+            peer,
+            &pkt(vec![options_template(ie::IE::egressVRFID, ie::IE::VRFname)]),
+        );
+        assert_eq!(cache.vrf_name(&scope, 3), None);
+        assert!(cache.interface(&scope, 1).is_some());
+
+        // Withdrawing all the options templates invalidates the other entries
+        cache.update_ipfix(
+            peer,
+            &pkt(vec![ipfix::Set::OptionsTemplate(vec![
+                ipfix::OptionsTemplateRecord::new(IPFIX_OPTIONS_TEMPLATE_SET_ID, vec![], vec![]),
+            ])]),
+        );
+        assert!(cache.interface(&scope, 1).is_none());
+        assert!(cache.application(&scope, &[3, 0, 80]).is_none());
+        assert!(cache.labels(&scope, &flow).is_empty());
+    }
+
+    #[test]
+    fn test_netflow_v9_options_data() {
+        let pkt = NetFlowV9Packet::new(
+            0,
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            0,
+            5,
+            vec![netflow::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![
+                    netflow::DataRecord::new(
+                        vec![ScopeField::System(netflow::System(1))],
+                        vec![
+                            Field::samplerId(ie::samplerId(2)),
+                            Field::samplerMode(ie::samplerMode(2)),
+                            Field::samplerRandomInterval(ie::samplerRandomInterval(1000)),
+                        ],
+                    ),
+                    netflow::DataRecord::new(
+                        vec![ScopeField::Interface(netflow::Interface(8))],
+                        vec![Field::interfaceName(ie::interfaceName(
+                            "ge-0/0/0".to_string(),
+                        ))],
+                    ),
+                ],
+            }],
+        );
+        let mut cache = OptionsDataCache::new();
+        cache.update_netflow_v9(None, &pkt);
+
+        let scope = TemplateScope::new(None, 5);
+        let sampler = cache.sampler(&scope, 2).unwrap();
+        assert_eq!(sampler.algorithm(), Some(SamplingAlgorithm::Random));
+        assert_eq!(sampler.interval(), Some(1000));
+        assert_eq!(
+            cache.interface(&scope, 8).and_then(|x| x.name()),
+            Some("ge-0/0/0")
+        );
+        assert_eq!(cache.exporter_stats(&scope), None);
+    }
+}
@@ -0,0 +1,339 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Detection of the lost and reordered export packets.
+//!
+//! [`SequenceTracker`] follows the sequence number of every exporter and
+//! Observation Domain, i.e., [`TemplateScope`]. The IPFIX sequence number
+//! counts the data records sent before the message
+//! [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-3.1), while
+//! the NetFlow V9 sequence number counts the packets
+//! [RFC3954](https://www.rfc-editor.org/rfc/rfc3954#section-5.1). Hence, the
+//! gaps are in data records for IPFIX and in packets for NetFlow V9.
+//!
+//! Every gap, reordered packet, and reset is recorded as a [`SequenceEvent`]
+//! that the collector drains with [`SequenceTracker::take_events`], and is
+//! counted in the [`SequenceStats`] of the scope.
+
+use std::{collections::HashMap, net::SocketAddr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ipfix::{self, IpfixPacket},
+    netflow::NetFlowV9Packet,
+    template_cache::TemplateScope,
+};
+
+/// How far behind the expected sequence number a packet is still considered
+/// reordered rather than a restart of the exporter
+pub const DEFAULT_REORDER_WINDOW: u32 = 1024;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SequenceChange {
+    /// The sequence number is ahead of the expected one, `missing` data
+    /// records (IPFIX) or packets (NetFlow V9) are lost or delayed
+    Gap { missing: u32 },
+    /// The packet is received after the packets that follow it
+    Reordered,
+    /// The sequence number is too far behind the expected one, e.g., the
+    /// exporter restarted, and the tracking starts over
+    Reset,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SequenceEvent {
+    scope: TemplateScope,
+    expected: u32,
+    received: u32,
+    change: SequenceChange,
+}
+
+impl SequenceEvent {
+    pub const fn new(
+        scope: TemplateScope,
+        expected: u32,
+        received: u32,
+        change: SequenceChange,
+    ) -> Self {
+        Self {
+            scope,
+            expected,
+            received,
+            change,
+        }
+    }
+
+    pub const fn scope(&self) -> TemplateScope {
+        self.scope
+    }
+
+    pub const fn expected(&self) -> u32 {
+        self.expected
+    }
+
+    pub const fn received(&self) -> u32 {
+        self.received
+    }
+
+    pub const fn change(&self) -> SequenceChange {
+        self.change
+    }
+}
+
+/// Counters of a scope since its first packet
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SequenceStats {
+    packets: u64,
+    lost: u64,
+    reordered: u64,
+    resets: u64,
+}
+
+impl SequenceStats {
+    pub const fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Data records (IPFIX) or packets (NetFlow V9) missing from the sequence.
+    /// The reordered packets are not counted as lost once received.
+    pub const fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    pub const fn reordered(&self) -> u64 {
+        self.reordered
+    }
+
+    pub const fn resets(&self) -> u64 {
+        self.resets
+    }
+}
+
+#[derive(Debug, Clone)]
+struct SequenceState {
+    expected: u32,
+    stats: SequenceStats,
+}
+
+/// Sequence numbers tracking, see the [module](self) docs
+#[derive(Debug, Clone)]
+pub struct SequenceTracker {
+    reorder_window: u32,
+    scopes: HashMap<TemplateScope, SequenceState>,
+    events: Vec<SequenceEvent>,
+}
+
+impl Default for SequenceTracker {
+    fn default() -> Self {
+        Self {
+            reorder_window: DEFAULT_REORDER_WINDOW,
+            scopes: HashMap::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl SequenceTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub const fn reorder_window(&self) -> u32 {
+        self.reorder_window
+    }
+
+    /// See [`DEFAULT_REORDER_WINDOW`], in data records for IPFIX and in
+    /// packets for NetFlow V9
+    pub fn set_reorder_window(&mut self, reorder_window: u32) {
+        self.reorder_window = reorder_window;
+    }
+
+    pub fn stats(&self, scope: &TemplateScope) -> Option<&SequenceStats> {
+        self.scopes.get(scope).map(|state| &state.stats)
+    }
+
+    /// Stats of all the scopes seen so far
+    pub fn all_stats(&self) -> impl Iterator<Item = (&TemplateScope, &SequenceStats)> {
+        self.scopes
+            .iter()
+            .map(|(scope, state)| (scope, &state.stats))
+    }
+
+    /// Drain the events recorded since the last call
+    pub fn take_events(&mut self) -> Vec<SequenceEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Stop tracking a given scope, e.g., when the exporter is gone
+    pub fn remove_scope(&mut self, scope: &TemplateScope) {
+        self.scopes.remove(scope);
+    }
+
+    /// Track the sequence number of an IPFIX packet sent by `peer`
+    pub fn update_ipfix(&mut self, peer: Option<SocketAddr>, pkt: &IpfixPacket) {
+        let records = pkt
+            .sets()
+            .iter()
+            .map(|set| match set {
+                ipfix::Set::Data { records, .. } => records.len(),
+                ipfix::Set::Template(_) | ipfix::Set::OptionsTemplate(_) => 0,
+            })
+            .sum::<usize>() as u32;
+        let scope = TemplateScope::new(peer, pkt.observation_domain_id());
+        self.update(scope, pkt.sequence_number(), records);
+    }
+
+    /// Track the sequence number of a NetFlow V9 packet sent by `peer`
+    pub fn update_netflow_v9(&mut self, peer: Option<SocketAddr>, pkt: &NetFlowV9Packet) {
+        let scope = TemplateScope::new(peer, pkt.source_id());
+        self.update(scope, pkt.sequence_number(), 1);
+    }
+
+    /// `count` is the number of sequence numbers used by the packet
+    fn update(&mut self, scope: TemplateScope, received: u32, count: u32) {
+        let state = match self.scopes.get_mut(&scope) {
+            Some(state) => state,
+            None => {
+                self.scopes.insert(
+                    scope,
+                    SequenceState {
+                        expected: received.wrapping_add(count),
+                        stats: SequenceStats {
+                            packets: 1,
+                            ..Default::default()
+                        },
+                    },
+                );
+                return;
+            }
+        };
+        state.stats.packets += 1;
+        let expected = state.expected;
+        let ahead = received.wrapping_sub(expected);
+        let behind = expected.wrapping_sub(received);
+        let change = if ahead == 0 {
+            state.expected = received.wrapping_add(count);
+            return;
+        } else if behind <= self.reorder_window {
+            state.stats.reordered += 1;
+            state.stats.lost = state.stats.lost.saturating_sub(count as u64);
+            SequenceChange::Reordered
+        } else if ahead <= i32::MAX as u32 {
+            state.stats.lost += ahead as u64;
+            state.expected = received.wrapping_add(count);
+            SequenceChange::Gap { missing: ahead }
+        } else {
+            state.stats.resets += 1;
+            state.expected = received.wrapping_add(count);
+            SequenceChange::Reset
+        };
+        self.events
+            .push(SequenceEvent::new(scope, expected, received, change));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::{ie, DataSetId};
+
+    fn ipfix_packet(sequence_number: u32, records: usize) -> IpfixPacket {
+        IpfixPacket::new(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            sequence_number,
+            1,
+            vec![ipfix::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![
+                    ipfix::DataRecord::new(
+                        vec![],
+                        vec![ie::Field::ingressInterface(ie::ingressInterface(1))],
+                    );
+                    records
+                ],
+            }],
+        )
+    }
+
+    #[test]
+    fn test_ipfix_sequence() {
+        let peer = Some("192.0.2.1:4739".parse().unwrap());
+        let scope = TemplateScope::new(peer, 1);
+        let mut tracker = SequenceTracker::new();
+        tracker.set_reorder_window(4);
+        tracker.update_ipfix(peer, &ipfix_packet(10, 2));
+        tracker.update_ipfix(peer, &ipfix_packet(12, 3));
+        assert!(tracker.take_events().is_empty());
+
+        // The packet with records 15 and 16 is late
+        tracker.update_ipfix(peer, &ipfix_packet(17, 1));
+        tracker.update_ipfix(peer, &ipfix_packet(15, 2));
+        tracker.update_ipfix(peer, &ipfix_packet(18, 1));
+        // The exporter restarted
+        tracker.update_ipfix(peer, &ipfix_packet(0, 1));
+        tracker.update_ipfix(peer, &ipfix_packet(1, 1));
+
+        assert_eq!(
+            tracker.take_events(),
+            vec![
+                SequenceEvent::new(scope, 15, 17, SequenceChange::Gap { missing: 2 }),
+                SequenceEvent::new(scope, 18, 15, SequenceChange::Reordered),
+                SequenceEvent::new(scope, 19, 0, SequenceChange::Reset),
+            ]
+        );
+        let stats = tracker.stats(&scope).unwrap();
+        assert_eq!(stats.packets(), 7);
+        assert_eq!(stats.lost(), 0);
+        assert_eq!(stats.reordered(), 1);
+        assert_eq!(stats.resets(), 1);
+        assert_eq!(tracker.stats(&TemplateScope::new(None, 1)), None);
+
+        tracker.remove_scope(&scope);
+        assert_eq!(tracker.stats(&scope), None);
+    }
+
+    #[test]
+    fn test_netflow_v9_sequence() {
+        let pkt = |sequence_number| {
+            NetFlowV9Packet::new(
+                0,
+                Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+                sequence_number,
+                5,
+                vec![],
+            )
+        };
+        let scope = TemplateScope::new(None, 5);
+        let mut tracker = SequenceTracker::new();
+        tracker.update_netflow_v9(None, &pkt(u32::MAX));
+        tracker.update_netflow_v9(None, &pkt(0));
+        tracker.update_netflow_v9(None, &pkt(3));
+        assert_eq!(
+            tracker.take_events(),
+            vec![SequenceEvent::new(
+                scope,
+                1,
+                3,
+                SequenceChange::Gap { missing: 2 }
+            )]
+        );
+        let stats = tracker.stats(&scope).unwrap();
+        assert_eq!(stats.packets(), 3);
+        assert_eq!(stats.lost(), 2);
+    }
+}
@@ -0,0 +1,934 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lifecycle management of the templates needed to decode the data records.
+//!
+//! Templates are only unique within the Observation Domain (IPFIX) or the
+//! Source ID (NetFlow V9) of a given exporter. [`TemplateCache`] keeps the
+//! templates of each [`TemplateScope`] apart, and the lookups and updates are
+//! done in the current scope. The packet parsers switch the scope's
+//! observation domain from the packet header, while the collector is
+//! responsible for setting the peer.
+//!
+//! The cache handles the IPFIX template withdrawals
+//! [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-8.1), the
+//! expiry of the templates that are not refreshed by the exporter, and
+//! optionally records a [`TemplateEvent`] for every change, which the
+//! collector drains with [`TemplateCache::take_events`].
+//!
+//! Decoders running concurrently, e.g., one per worker task, each keep their
+//! own [`TemplateCache`] and share the templates through a
+//! [`SharedTemplateCache`] with [`TemplateCache::sync`]. The shared cache is
+//! copy-on-write: checking for updates only takes a read lock, and the rare
+//! template updates replace the templates of a scope without blocking the
+//! readers of the other workers for longer than a pointer swap. Only the
+//! added, redefined and removed templates are published, and the other caches
+//! only pick up these templates. The refreshes are kept local, unless the
+//! template would otherwise expire in the other caches.
+//!
+//! The cache also carries the parsing options that apply to the data records
+//! decoded with its templates, see [`TemplateCache::set_strict`].
+
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::Debug,
+    net::SocketAddr,
+    rc::Rc,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// The templates are scoped by the exporter and the observation domain id (or
+/// source id for NetFlow V9)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct TemplateScope {
+    peer: Option<SocketAddr>,
+    observation_domain_id: u32,
+}
+
+impl TemplateScope {
+    pub const fn new(peer: Option<SocketAddr>, observation_domain_id: u32) -> Self {
+        Self {
+            peer,
+            observation_domain_id,
+        }
+    }
+
+    /// The exporter, [`None`] when the collector doesn't differentiate
+    /// between the exporters
+    pub const fn peer(&self) -> Option<SocketAddr> {
+        self.peer
+    }
+
+    /// Observation Domain ID for IPFIX or Source ID for NetFlow V9
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TemplateKind {
+    Template,
+    OptionsTemplate,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TemplateChange {
+    /// Template ID that was not defined before
+    Added,
+    /// The same template definition is received again
+    Refreshed,
+    /// Template ID is reused with a different definition without being
+    /// withdrawn first
+    Redefined,
+    /// Template is withdrawn by the exporter
+    Withdrawn,
+    /// Template is not refreshed within the configured timeout
+    Expired,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateEvent {
+    scope: TemplateScope,
+    template_id: u16,
+    kind: TemplateKind,
+    change: TemplateChange,
+}
+
+impl TemplateEvent {
+    pub const fn new(
+        scope: TemplateScope,
+        template_id: u16,
+        kind: TemplateKind,
+        change: TemplateChange,
+    ) -> Self {
+        Self {
+            scope,
+            template_id,
+            kind,
+            change,
+        }
+    }
+
+    pub const fn scope(&self) -> TemplateScope {
+        self.scope
+    }
+
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    pub const fn kind(&self) -> TemplateKind {
+        self.kind
+    }
+
+    pub const fn change(&self) -> TemplateChange {
+        self.change
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CachedTemplate<T> {
+    kind: TemplateKind,
+    template: Rc<T>,
+    last_refreshed: Instant,
+    /// Refresh time seen by the other caches sharing the templates
+    published_refresh: Instant,
+}
+
+/// Cache of decoding templates, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct TemplateCache<T> {
+    scope: TemplateScope,
+    scopes: HashMap<TemplateScope, HashMap<u16, CachedTemplate<T>>>,
+    timeout: Option<Duration>,
+    notifications: bool,
+    events: Vec<TemplateEvent>,
+    strict: bool,
+    /// Generation of the shared cache seen by the last sync, [`None`] if
+    /// never synced
+    synced_generation: Option<u64>,
+    /// Changes not yet published to the shared cache
+    dirty: HashSet<(TemplateScope, u16)>,
+}
+
+impl<T> Default for TemplateCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromIterator<(u16, Rc<T>)> for TemplateCache<T> {
+    /// Create a cache with the given templates in the default scope
+    fn from_iter<I: IntoIterator<Item = (u16, Rc<T>)>>(iter: I) -> Self {
+        let now = Instant::now();
+        let mut cache = Self::new();
+        cache.scopes.insert(
+            cache.scope,
+            iter.into_iter()
+                .map(|(id, template)| {
+                    (
+                        id,
+                        CachedTemplate {
+                            kind: TemplateKind::Template,
+                            template,
+                            last_refreshed: now,
+                            published_refresh: now,
+                        },
+                    )
+                })
+                .collect(),
+        );
+        cache
+    }
+}
+
+impl<T> TemplateCache<T> {
+    /// Create an empty cache without expiry, with notifications and strict
+    /// validation disabled
+    pub fn new() -> Self {
+        Self {
+            scope: TemplateScope::default(),
+            scopes: HashMap::new(),
+            timeout: None,
+            notifications: false,
+            events: Vec::new(),
+            strict: false,
+            synced_generation: None,
+            dirty: HashSet::new(),
+        }
+    }
+
+    pub const fn scope(&self) -> TemplateScope {
+        self.scope
+    }
+
+    pub fn set_scope(&mut self, scope: TemplateScope) {
+        self.scope = scope;
+    }
+
+    pub fn set_peer(&mut self, peer: Option<SocketAddr>) {
+        self.scope.peer = peer;
+    }
+
+    pub fn set_observation_domain_id(&mut self, observation_domain_id: u32) {
+        self.scope.observation_domain_id = observation_domain_id;
+    }
+
+    /// Templates that are not refreshed within the timeout are removed by
+    /// [`TemplateCache::expire`], [`None`] to keep them forever
+    pub const fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub const fn notifications(&self) -> bool {
+        self.notifications
+    }
+
+    /// When enabled, every change is recorded as a [`TemplateEvent`] till it's
+    /// drained by [`TemplateCache::take_events`]
+    pub fn set_notifications(&mut self, notifications: bool) {
+        self.notifications = notifications;
+        if !notifications {
+            self.events.clear();
+        }
+    }
+
+    pub const fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// When enabled, the IPFIX data records are validated against their
+    /// template: every fixed length field must consume exactly the length
+    /// given in the template, fixed length strings may only be padded with
+    /// zeros, and all the records of a data set are decoded with only zero
+    /// padding allowed after the last record. Violations are reported as
+    /// errors instead of being silently ignored.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Drain the changes recorded since the last call
+    pub fn take_events(&mut self) -> Vec<TemplateEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn mark_dirty(&mut self, scope: TemplateScope, template_id: u16) {
+        if self.synced_generation.is_some() {
+            self.dirty.insert((scope, template_id));
+        }
+    }
+
+    fn notify(
+        &mut self,
+        scope: TemplateScope,
+        template_id: u16,
+        kind: TemplateKind,
+        change: TemplateChange,
+    ) {
+        if self.notifications {
+            self.events
+                .push(TemplateEvent::new(scope, template_id, kind, change));
+        }
+    }
+
+    /// Get a template in the current scope
+    pub fn get(&self, template_id: &u16) -> Option<&Rc<T>> {
+        self.scopes
+            .get(&self.scope)
+            .and_then(|templates| templates.get(template_id))
+            .map(|cached| &cached.template)
+    }
+
+    pub fn contains_key(&self, template_id: &u16) -> bool {
+        self.get(template_id).is_some()
+    }
+
+    /// Number of templates in the current scope
+    pub fn len(&self) -> usize {
+        self.scopes
+            .get(&self.scope)
+            .map(|templates| templates.len())
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Withdraw a template from the current scope, returns false if the
+    /// template is not defined
+    pub fn withdraw(&mut self, template_id: u16) -> bool {
+        let scope = self.scope;
+        let removed = self
+            .scopes
+            .get_mut(&scope)
+            .and_then(|templates| templates.remove(&template_id));
+        match removed {
+            Some(cached) => {
+                self.mark_dirty(scope, template_id);
+                self.notify(scope, template_id, cached.kind, TemplateChange::Withdrawn);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Withdraw all the templates of the given kind in the current scope,
+    /// returns the number of withdrawn templates
+    pub fn withdraw_all(&mut self, kind: TemplateKind) -> usize {
+        let scope = self.scope;
+        let mut withdrawn = vec![];
+        if let Some(templates) = self.scopes.get_mut(&scope) {
+            templates.retain(|id, cached| {
+                if cached.kind == kind {
+                    withdrawn.push(*id);
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        withdrawn.sort_unstable();
+        for template_id in &withdrawn {
+            self.mark_dirty(scope, *template_id);
+            self.notify(scope, *template_id, kind, TemplateChange::Withdrawn);
+        }
+        withdrawn.len()
+    }
+
+    /// Remove all the templates of a scope, e.g., when the exporter is gone
+    pub fn remove_scope(&mut self, scope: &TemplateScope) -> usize {
+        let removed = self.scopes.remove(scope).unwrap_or_default();
+        for template_id in removed.keys() {
+            self.mark_dirty(*scope, *template_id);
+        }
+        removed.len()
+    }
+
+    /// Remove the templates, across all the scopes, that are not refreshed
+    /// within the timeout, returns the number of expired templates
+    pub fn expire(&mut self, now: Instant) -> usize {
+        let timeout = match self.timeout {
+            Some(timeout) => timeout,
+            None => return 0,
+        };
+        let mut expired = vec![];
+        for (scope, templates) in &mut self.scopes {
+            templates.retain(|id, cached| {
+                if now.saturating_duration_since(cached.last_refreshed) > timeout {
+                    expired.push((*scope, *id, cached.kind));
+                    false
+                } else {
+                    true
+                }
+            });
+        }
+        self.scopes.retain(|_, templates| !templates.is_empty());
+        for (scope, template_id, kind) in &expired {
+            self.mark_dirty(*scope, *template_id);
+            self.notify(*scope, *template_id, *kind, TemplateChange::Expired);
+        }
+        expired.len()
+    }
+}
+
+impl<T: PartialEq> TemplateCache<T> {
+    /// Add or refresh a template in the current scope. Reusing a template ID
+    /// with a different definition replaces the old template and is reported
+    /// as [`TemplateChange::Redefined`].
+    pub fn insert(
+        &mut self,
+        template_id: u16,
+        kind: TemplateKind,
+        template: Rc<T>,
+    ) -> TemplateChange {
+        let scope = self.scope;
+        let now = Instant::now();
+        let timeout = self.timeout;
+        let templates = self.scopes.entry(scope).or_default();
+        let (change, published_refresh) = match templates.get(&template_id) {
+            None => (TemplateChange::Added, now),
+            Some(old) if old.kind == kind && old.template == template => {
+                // The exporters refresh the templates periodically, a refresh
+                // is only published before the template could expire in the
+                // other caches sharing it
+                let stale = timeout.is_some_and(|timeout| {
+                    now.saturating_duration_since(old.published_refresh) >= timeout / 2
+                });
+                let published_refresh = if stale { now } else { old.published_refresh };
+                (TemplateChange::Refreshed, published_refresh)
+            }
+            Some(_) => (TemplateChange::Redefined, now),
+        };
+        templates.insert(
+            template_id,
+            CachedTemplate {
+                kind,
+                template,
+                last_refreshed: now,
+                published_refresh,
+            },
+        );
+        if published_refresh == now {
+            self.mark_dirty(scope, template_id);
+        }
+        self.notify(scope, template_id, kind, change);
+        change
+    }
+}
+
+impl<T: Clone> TemplateCache<T> {
+    /// Publish the local changes to the shared cache, then pick up the changes
+    /// made by the other caches. The first sync publishes all the local
+    /// templates and picks up all the shared ones, the following syncs only
+    /// exchange the changed templates. Called before and after decoding each
+    /// packet, a sync without any changes only compares the generation of the
+    /// shared cache.
+    pub fn sync(&mut self, shared: &SharedTemplateCache<T>) {
+        let dirty = if self.synced_generation.is_none() {
+            self.scopes
+                .iter()
+                .flat_map(|(scope, templates)| templates.keys().map(|id| (*scope, *id)))
+                .collect::<Vec<_>>()
+        } else {
+            self.dirty.drain().collect::<Vec<_>>()
+        };
+        if !dirty.is_empty() {
+            let mut state = shared.write();
+            let up_to_date = self.synced_generation == Some(state.generation);
+            state.generation += 1;
+            let generation = state.generation;
+            for (scope, template_id) in dirty {
+                let cached = self
+                    .scopes
+                    .get(&scope)
+                    .and_then(|templates| templates.get(&template_id));
+                let templates = Arc::make_mut(state.scopes.entry(scope).or_default());
+                match cached {
+                    Some(cached) => {
+                        templates.insert(
+                            template_id,
+                            SharedTemplate {
+                                kind: cached.kind,
+                                template: Arc::new(cached.template.as_ref().clone()),
+                                last_refreshed: cached.published_refresh,
+                            },
+                        );
+                    }
+                    None => {
+                        templates.remove(&template_id);
+                    }
+                }
+                state.changes.push_back((generation, scope, template_id));
+            }
+            state.scopes.retain(|_, templates| !templates.is_empty());
+            while state.changes.len() > MAX_SHARED_CHANGES {
+                state.changes.pop_front();
+            }
+            // Nothing else changed since the last sync, no need to pick up
+            // the templates that were just published
+            if up_to_date {
+                self.synced_generation = Some(generation);
+            }
+        }
+
+        let state = shared.read();
+        if self.synced_generation == Some(state.generation) {
+            return;
+        }
+        // The changes since the last sync are in the log, unless the log was
+        // trimmed past them
+        let changes = self.synced_generation.and_then(|synced| {
+            let oldest = state
+                .changes
+                .front()
+                .map(|(generation, _, _)| *generation)?;
+            (oldest <= synced + 1).then(|| {
+                state
+                    .changes
+                    .iter()
+                    .filter(|(generation, _, _)| *generation > synced)
+                    .map(|(_, scope, template_id)| (*scope, *template_id))
+                    .collect::<HashSet<_>>()
+            })
+        });
+        match changes {
+            Some(changes) => {
+                for (scope, template_id) in changes {
+                    let shared = state
+                        .scopes
+                        .get(&scope)
+                        .and_then(|templates| templates.get(&template_id));
+                    match shared {
+                        Some(shared) => {
+                            self.scopes
+                                .entry(scope)
+                                .or_default()
+                                .insert(template_id, CachedTemplate::from(shared));
+                        }
+                        None => {
+                            if let Some(templates) = self.scopes.get_mut(&scope) {
+                                templates.remove(&template_id);
+                            }
+                        }
+                    }
+                }
+                self.scopes.retain(|_, templates| !templates.is_empty());
+            }
+            None => {
+                self.scopes = state
+                    .scopes
+                    .iter()
+                    .map(|(scope, templates)| {
+                        let templates = templates
+                            .iter()
+                            .map(|(id, shared)| (*id, CachedTemplate::from(shared)))
+                            .collect();
+                        (*scope, templates)
+                    })
+                    .collect();
+            }
+        }
+        self.synced_generation = Some(state.generation);
+    }
+
+    /// Copy of all the templates, across all the scopes, that can be persisted
+    /// and later restored with [`TemplateCache::restore`]
+    pub fn snapshot(&self) -> TemplateCacheSnapshot<T> {
+        let templates = self
+            .scopes
+            .iter()
+            .flat_map(|(scope, templates)| {
+                templates
+                    .iter()
+                    .map(|(template_id, cached)| TemplateSnapshot {
+                        scope: *scope,
+                        template_id: *template_id,
+                        kind: cached.kind,
+                        template: cached.template.as_ref().clone(),
+                    })
+            })
+            .collect();
+        TemplateCacheSnapshot { templates }
+    }
+
+    /// Add the templates of a snapshot, e.g., after a restart, so the data
+    /// records can be decoded before the exporters announce the templates
+    /// again. The templates already in the cache are more recent and are
+    /// kept. The restored templates are considered refreshed now for the
+    /// expiry, and no events are recorded for them. Returns the number of
+    /// restored templates.
+    pub fn restore(&mut self, snapshot: TemplateCacheSnapshot<T>) -> usize {
+        let now = Instant::now();
+        let mut restored = 0;
+        for entry in snapshot.templates {
+            let templates = self.scopes.entry(entry.scope).or_default();
+            if templates.contains_key(&entry.template_id) {
+                continue;
+            }
+            templates.insert(
+                entry.template_id,
+                CachedTemplate {
+                    kind: entry.kind,
+                    template: Rc::new(entry.template),
+                    last_refreshed: now,
+                    published_refresh: now,
+                },
+            );
+            self.mark_dirty(entry.scope, entry.template_id);
+            restored += 1;
+        }
+        restored
+    }
+}
+
+/// Persisted form of a template, see [`TemplateCache::snapshot`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateSnapshot<T> {
+    scope: TemplateScope,
+    template_id: u16,
+    kind: TemplateKind,
+    template: T,
+}
+
+impl<T> TemplateSnapshot<T> {
+    pub const fn scope(&self) -> TemplateScope {
+        self.scope
+    }
+
+    pub const fn template_id(&self) -> u16 {
+        self.template_id
+    }
+
+    pub const fn kind(&self) -> TemplateKind {
+        self.kind
+    }
+
+    pub const fn template(&self) -> &T {
+        &self.template
+    }
+}
+
+/// Templates of a [`TemplateCache`] that can be persisted, the templates are
+/// kept in a list so the snapshot can be serialized to any format
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TemplateCacheSnapshot<T> {
+    templates: Vec<TemplateSnapshot<T>>,
+}
+
+impl<T> TemplateCacheSnapshot<T> {
+    pub const fn templates(&self) -> &Vec<TemplateSnapshot<T>> {
+        &self.templates
+    }
+
+    pub fn into_templates(self) -> Vec<TemplateSnapshot<T>> {
+        self.templates
+    }
+}
+
+#[derive(Debug)]
+struct SharedTemplate<T> {
+    kind: TemplateKind,
+    template: Arc<T>,
+    last_refreshed: Instant,
+}
+
+impl<T: Clone> From<&SharedTemplate<T>> for CachedTemplate<T> {
+    fn from(shared: &SharedTemplate<T>) -> Self {
+        Self {
+            kind: shared.kind,
+            template: Rc::new(shared.template.as_ref().clone()),
+            last_refreshed: shared.last_refreshed,
+            published_refresh: shared.last_refreshed,
+        }
+    }
+}
+
+impl<T> Clone for SharedTemplate<T> {
+    fn clone(&self) -> Self {
+        Self {
+            kind: self.kind,
+            template: Arc::clone(&self.template),
+            last_refreshed: self.last_refreshed,
+        }
+    }
+}
+
+/// Number of published template changes kept for the caches to pick up
+/// incrementally, the caches that are further behind copy all the templates
+const MAX_SHARED_CHANGES: usize = 4096;
+
+#[derive(Debug)]
+struct SharedTemplates<T> {
+    generation: u64,
+    scopes: HashMap<TemplateScope, Arc<HashMap<u16, SharedTemplate<T>>>>,
+    /// Generation at which each template was published, oldest first
+    changes: VecDeque<(u64, TemplateScope, u16)>,
+}
+
+/// Templates shared by several [`TemplateCache`], see the
+/// [module docs](self). Cloning returns a handle to the same templates.
+#[derive(Debug)]
+pub struct SharedTemplateCache<T> {
+    inner: Arc<RwLock<SharedTemplates<T>>>,
+}
+
+impl<T> Clone for SharedTemplateCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Default for SharedTemplateCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SharedTemplateCache<T> {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(SharedTemplates {
+                generation: 0,
+                scopes: HashMap::new(),
+                changes: VecDeque::new(),
+            })),
+        }
+    }
+
+    /// Incremented every time a cache publishes changes
+    pub fn generation(&self) -> u64 {
+        self.read().generation
+    }
+
+    pub fn get(&self, scope: &TemplateScope, template_id: &u16) -> Option<Arc<T>> {
+        self.read()
+            .scopes
+            .get(scope)
+            .and_then(|templates| templates.get(template_id))
+            .map(|shared| Arc::clone(&shared.template))
+    }
+
+    /// Number of templates across all the scopes
+    pub fn len(&self) -> usize {
+        self.read()
+            .scopes
+            .values()
+            .map(|templates| templates.len())
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, SharedTemplates<T>> {
+        self.inner
+            .read()
+            .expect("shared templates cache lock is poisoned")
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, SharedTemplates<T>> {
+        self.inner
+            .write()
+            .expect("shared templates cache lock is poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_cache_lifecycle() {
+        let mut cache = TemplateCache::<Vec<u16>>::new();
+        cache.set_notifications(true);
+        cache.set_timeout(Some(Duration::from_secs(60)));
+
+        let domain1 = TemplateScope::new(None, 1);
+        let domain2 = TemplateScope::new(None, 2);
+        cache.set_scope(domain1);
+        assert_eq!(
+            cache.insert(256, TemplateKind::Template, Rc::new(vec![1, 2])),
+            TemplateChange::Added
+        );
+        assert_eq!(
+            cache.insert(256, TemplateKind::Template, Rc::new(vec![1, 2])),
+            TemplateChange::Refreshed
+        );
+        assert_eq!(
+            cache.insert(256, TemplateKind::Template, Rc::new(vec![3])),
+            TemplateChange::Redefined
+        );
+        cache.insert(257, TemplateKind::OptionsTemplate, Rc::new(vec![4]));
+        cache.insert(258, TemplateKind::Template, Rc::new(vec![5]));
+
+        // Same template id in another observation domain is independent
+        cache.set_observation_domain_id(2);
+        assert_eq!(cache.get(&256), None);
+        cache.insert(256, TemplateKind::Template, Rc::new(vec![6]));
+        assert_eq!(cache.get(&256), Some(&Rc::new(vec![6])));
+
+        cache.set_scope(domain1);
+        assert_eq!(cache.get(&256), Some(&Rc::new(vec![3])));
+        assert!(cache.withdraw(258));
+        assert!(!cache.withdraw(258));
+        assert_eq!(cache.withdraw_all(TemplateKind::Template), 1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key(&257));
+
+        let events = cache.take_events();
+        assert_eq!(
+            events
+                .iter()
+                .map(|event| (event.scope(), event.template_id(), event.change()))
+                .collect::<Vec<_>>(),
+            vec![
+                (domain1, 256, TemplateChange::Added),
+                (domain1, 256, TemplateChange::Refreshed),
+                (domain1, 256, TemplateChange::Redefined),
+                (domain1, 257, TemplateChange::Added),
+                (domain1, 258, TemplateChange::Added),
+                (domain2, 256, TemplateChange::Added),
+                (domain1, 258, TemplateChange::Withdrawn),
+                (domain1, 256, TemplateChange::Withdrawn),
+            ]
+        );
+        assert!(cache.take_events().is_empty());
+
+        assert_eq!(cache.expire(Instant::now()), 0);
+        assert_eq!(cache.expire(Instant::now() + Duration::from_secs(61)), 2);
+        assert!(cache.is_empty());
+        assert_eq!(cache.take_events().len(), 2);
+    }
+
+    #[test]
+    fn test_shared_template_cache() {
+        let shared = SharedTemplateCache::<Vec<u16>>::new();
+        let domain1 = TemplateScope::new(None, 1);
+
+        let mut cache1 = TemplateCache::new();
+        cache1.set_scope(domain1);
+        cache1.insert(256, TemplateKind::Template, Rc::new(vec![1, 2]));
+        cache1.sync(&shared);
+        assert_eq!(shared.get(&domain1, &256), Some(Arc::new(vec![1, 2])));
+        let generation = shared.generation();
+
+        // Syncing without changes doesn't publish anything
+        cache1.sync(&shared);
+        assert_eq!(shared.generation(), generation);
+
+        // Caches in other threads see the templates and publish their changes
+        let worker_shared = shared.clone();
+        std::thread::spawn(move || {
+            let mut cache2 = TemplateCache::new();
+            cache2.set_scope(domain1);
+            cache2.sync(&worker_shared);
+            assert_eq!(cache2.get(&256), Some(&Rc::new(vec![1, 2])));
+            cache2.insert(257, TemplateKind::OptionsTemplate, Rc::new(vec![3]));
+            assert!(cache2.withdraw(256));
+            cache2.sync(&worker_shared);
+        })
+        .join()
+        .unwrap();
+        assert_eq!(shared.len(), 1);
+
+        cache1.sync(&shared);
+        assert_eq!(cache1.get(&256), None);
+        assert_eq!(cache1.get(&257), Some(&Rc::new(vec![3])));
+
+        // Changes made between two syncs are published before picking up the others
+        cache1.insert(258, TemplateKind::Template, Rc::new(vec![4]));
+        assert_eq!(cache1.remove_scope(&domain1), 2);
+        cache1.sync(&shared);
+        assert!(shared.is_empty());
+        assert!(cache1.is_empty());
+    }
+
+    #[test]
+    fn test_shared_template_cache_incremental_sync() {
+        let shared = SharedTemplateCache::<Vec<u16>>::new();
+        let domain1 = TemplateScope::new(None, 1);
+        let mut cache1 = TemplateCache::new();
+        cache1.set_scope(domain1);
+        cache1.set_timeout(Some(Duration::from_secs(60)));
+        let mut cache2 = TemplateCache::new();
+        cache2.set_scope(domain1);
+        cache1.insert(256, TemplateKind::Template, Rc::new(vec![1]));
+        cache1.insert(257, TemplateKind::Template, Rc::new(vec![2]));
+        cache1.sync(&shared);
+        cache2.sync(&shared);
+        let generation = shared.generation();
+
+        // The refreshes are not published
+        cache1.insert(256, TemplateKind::Template, Rc::new(vec![1]));
+        cache1.sync(&shared);
+        assert_eq!(shared.generation(), generation);
+
+        // Only the changed templates are picked up, the others are kept as is
+        let unchanged = Rc::clone(cache2.get(&257).unwrap());
+        cache1.insert(256, TemplateKind::Template, Rc::new(vec![3]));
+        cache1.sync(&shared);
+        cache2.sync(&shared);
+        assert_eq!(cache2.get(&256), Some(&Rc::new(vec![3])));
+        assert!(Rc::ptr_eq(cache2.get(&257).unwrap(), &unchanged));
+
+        // A cache that is behind the published changes copies all the templates
+        for template_id in 0..=MAX_SHARED_CHANGES as u16 {
+            cache1.insert(1024 + template_id, TemplateKind::Template, Rc::new(vec![]));
+            cache1.sync(&shared);
+        }
+        assert!(cache1.withdraw(256));
+        cache1.sync(&shared);
+        cache2.sync(&shared);
+        assert_eq!(cache2.len(), MAX_SHARED_CHANGES + 2);
+        assert_eq!(cache2.get(&256), None);
+        assert!(cache2.contains_key(&257));
+    }
+
+    #[test]
+    fn test_template_cache_snapshot() {
+        let domain1 = TemplateScope::new(None, 1);
+        let domain2 = TemplateScope::new(None, 2);
+        let mut cache = TemplateCache::new();
+        cache.set_scope(domain1);
+        cache.insert(256, TemplateKind::Template, Rc::new(vec![1, 2]));
+        cache.set_scope(domain2);
+        cache.insert(256, TemplateKind::OptionsTemplate, Rc::new(vec![3]));
+        let snapshot = cache.snapshot();
+        assert_eq!(snapshot.templates().len(), 2);
+
+        // Templates learned before the restore are more recent
+        let mut restored = TemplateCache::new();
+        restored.set_notifications(true);
+        restored.set_scope(domain2);
+        restored.insert(256, TemplateKind::Template, Rc::new(vec![4]));
+        restored.take_events();
+        assert_eq!(restored.restore(snapshot), 1);
+        assert_eq!(restored.get(&256), Some(&Rc::new(vec![4])));
+        restored.set_scope(domain1);
+        assert_eq!(restored.get(&256), Some(&Rc::new(vec![1, 2])));
+        assert!(restored.take_events().is_empty());
+    }
+}
@@ -1,3 +1,445 @@
-//! Generated deserialization code
+//! Generated deserialization code, along with the parsers of the structured
+//! data types [RFC6313](https://datatracker.ietf.org/doc/html/rfc6313) that
+//! need the templates to decode the nested data records.
+
+use std::rc::Rc;
+
+use nom::{
+    error::ErrorKind,
+    number::complete::{be_f32, be_f64, be_u128, be_u16, be_u32, be_u64, be_u8},
+    IResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{
+        BasicList, EnterpriseField, EnterpriseValue, InformationElementDataType, ListSemantic,
+        SubTemplateList, SubTemplateMultiList, SubTemplateMultiListEntry,
+    },
+    ie_registry::ENTERPRISE_BIT,
+    ipfix::{DataRecord, TemplatesMap},
+    wire::deserializer::{
+        ipfix::{DataRecordParsingError, LocatedDataRecordParsingError},
+        FieldSpecifierParsingError,
+    },
+    FieldSpecifier,
+};
+use netgauze_parse_utils::{
+    parse_into_located, parse_into_located_three_inputs, parse_into_located_two_inputs,
+    ErrorKindSerdeDeref, LocatedParsingError, ReadablePduWithTwoInputs, Span,
+};
+use netgauze_serde_macros::LocatedError;
 
 include!(concat!(env!("OUT_DIR"), "/ie_deser_generated.rs"));
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum ListParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UndefinedSemantic(u8),
+    InvalidLength(u16),
+    NoTemplateDefinedFor(u16),
+    FieldSpecifierError(
+        #[from_located(module = "crate::wire::deserializer")] FieldSpecifierParsingError,
+    ),
+    FieldError(Box<FieldParsingError>),
+    DataRecordError(Box<DataRecordParsingError>),
+}
+
+impl<'a> From<LocatedFieldParsingError<'a>> for LocatedListParsingError<'a> {
+    fn from(value: LocatedFieldParsingError<'a>) -> Self {
+        LocatedListParsingError::new(
+            *value.span(),
+            ListParsingError::FieldError(Box::new(value.error().clone())),
+        )
+    }
+}
+
+impl<'a> From<LocatedDataRecordParsingError<'a>> for LocatedListParsingError<'a> {
+    fn from(value: LocatedDataRecordParsingError<'a>) -> Self {
+        LocatedListParsingError::new(
+            *value.span(),
+            ListParsingError::DataRecordError(Box::new(value.error().clone())),
+        )
+    }
+}
+
+/// Structured data are usually encoded with variable length, where the
+/// length is carried in the first octet or the two octets following 255
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-7)
+#[inline]
+fn parse_list_length(
+    buf: Span<'_>,
+    length: u16,
+) -> IResult<Span<'_>, u16, LocatedListParsingError<'_>> {
+    if length != u16::MAX {
+        return Ok((buf, length));
+    }
+    let (buf, short_length) = be_u8(buf)?;
+    if short_length < u8::MAX {
+        Ok((buf, short_length as u16))
+    } else {
+        be_u16(buf)
+    }
+}
+
+#[inline]
+fn parse_list_semantic(
+    buf: Span<'_>,
+) -> IResult<Span<'_>, ListSemantic, LocatedListParsingError<'_>> {
+    nom::combinator::map_res(be_u8, |semantic| {
+        ListSemantic::from_repr(semantic).ok_or(ListParsingError::UndefinedSemantic(semantic))
+    })(buf)
+}
+
+/// Parse the data records of the given template till the end of the buffer
+fn parse_template_records<'a>(
+    input: Span<'a>,
+    mut buf: Span<'a>,
+    template_id: u16,
+    templates_map: Option<&TemplatesMap>,
+) -> IResult<Span<'a>, Vec<DataRecord>, LocatedListParsingError<'a>> {
+    let template = templates_map.and_then(|map| map.borrow().get(&template_id).cloned());
+    let template = if let Some(template) = template {
+        template
+    } else {
+        return Err(nom::Err::Error(LocatedListParsingError::new(
+            input,
+            ListParsingError::NoTemplateDefinedFor(template_id),
+        )));
+    };
+    let mut records = Vec::new();
+    while buf.len() > 0 {
+        let (t, record) = parse_into_located_two_inputs(buf, Rc::clone(&template), templates_map)?;
+        // A template of only zero length fields doesn't consume any input
+        if t.len() == buf.len() {
+            break;
+        }
+        buf = t;
+        records.push(record);
+    }
+    Ok((buf, records))
+}
+
+impl<'a> ReadablePduWithTwoInputs<'a, u16, Option<&TemplatesMap>, LocatedListParsingError<'a>>
+    for BasicList
+{
+    fn from_wire(
+        buf: Span<'a>,
+        length: u16,
+        templates_map: Option<&TemplatesMap>,
+    ) -> IResult<Span<'a>, Self, LocatedListParsingError<'a>> {
+        let (buf, length) = parse_list_length(buf, length)?;
+        let (reminder, buf) = nom::bytes::complete::take(length)(buf)?;
+        let (buf, semantic) = parse_list_semantic(buf)?;
+        let input = buf;
+        let (mut buf, field_specifier): (Span<'_>, FieldSpecifier) = parse_into_located(buf)?;
+        if field_specifier.length() == 0 {
+            return Err(nom::Err::Error(LocatedListParsingError::new(
+                input,
+                ListParsingError::InvalidLength(field_specifier.length()),
+            )));
+        }
+        let mut elements = Vec::new();
+        while buf.len() > 0 {
+            let (t, element) = parse_into_located_three_inputs(
+                buf,
+                &field_specifier.element_id(),
+                field_specifier.length(),
+                templates_map,
+            )?;
+            buf = t;
+            elements.push(element);
+        }
+        Ok((
+            reminder,
+            BasicList::new(semantic, field_specifier, elements),
+        ))
+    }
+}
+
+impl<'a> ReadablePduWithTwoInputs<'a, u16, Option<&TemplatesMap>, LocatedListParsingError<'a>>
+    for SubTemplateList
+{
+    fn from_wire(
+        buf: Span<'a>,
+        length: u16,
+        templates_map: Option<&TemplatesMap>,
+    ) -> IResult<Span<'a>, Self, LocatedListParsingError<'a>> {
+        let (buf, length) = parse_list_length(buf, length)?;
+        let (reminder, buf) = nom::bytes::complete::take(length)(buf)?;
+        let (buf, semantic) = parse_list_semantic(buf)?;
+        let input = buf;
+        let (buf, template_id) = be_u16(buf)?;
+        let (_, records) = parse_template_records(input, buf, template_id, templates_map)?;
+        Ok((
+            reminder,
+            SubTemplateList::new(semantic, template_id, records),
+        ))
+    }
+}
+
+impl<'a> ReadablePduWithTwoInputs<'a, u16, Option<&TemplatesMap>, LocatedListParsingError<'a>>
+    for SubTemplateMultiList
+{
+    fn from_wire(
+        buf: Span<'a>,
+        length: u16,
+        templates_map: Option<&TemplatesMap>,
+    ) -> IResult<Span<'a>, Self, LocatedListParsingError<'a>> {
+        let (buf, length) = parse_list_length(buf, length)?;
+        let (reminder, buf) = nom::bytes::complete::take(length)(buf)?;
+        let (mut buf, semantic) = parse_list_semantic(buf)?;
+        let mut entries = Vec::new();
+        while buf.len() > 0 {
+            let input = buf;
+            let (t, template_id) = be_u16(buf)?;
+            let (t, entry_length) = be_u16(t)?;
+            // Data records length includes the template id and length fields
+            if entry_length < 4 {
+                return Err(nom::Err::Error(LocatedListParsingError::new(
+                    input,
+                    ListParsingError::InvalidLength(entry_length),
+                )));
+            }
+            let (t, records_buf) = nom::bytes::complete::take(entry_length - 4)(t)?;
+            let (_, records) =
+                parse_template_records(input, records_buf, template_id, templates_map)?;
+            buf = t;
+            entries.push(SubTemplateMultiListEntry::new(template_id, records));
+        }
+        Ok((reminder, SubTemplateMultiList::new(semantic, entries)))
+    }
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum EnterpriseValueParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    InvalidLength(InformationElementDataType, u16),
+    Utf8Error(String),
+    /// Seconds or milliseconds, depending on the data type, that are out of
+    /// the supported range
+    InvalidTimestamp(u64),
+}
+
+/// Read an unsigned integer encoded with the reduced-size encoding
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-6.2)
+#[inline]
+fn parse_reduced_size(
+    buf: Span<'_>,
+    data_type: InformationElementDataType,
+    length: u16,
+    max_length: u16,
+) -> IResult<Span<'_>, u64, LocatedEnterpriseValueParsingError<'_>> {
+    if length == 0 || length > max_length {
+        return Err(nom::Err::Error(LocatedEnterpriseValueParsingError::new(
+            buf,
+            EnterpriseValueParsingError::InvalidLength(data_type, length),
+        )));
+    }
+    let (buf, bytes) = nom::bytes::complete::take(length)(buf)?;
+    let value = bytes
+        .fragment()
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) + *byte as u64);
+    Ok((buf, value))
+}
+
+/// Same as [`parse_reduced_size`], with the sign extended to 64-bits
+#[inline]
+fn parse_reduced_size_signed(
+    buf: Span<'_>,
+    data_type: InformationElementDataType,
+    length: u16,
+    max_length: u16,
+) -> IResult<Span<'_>, i64, LocatedEnterpriseValueParsingError<'_>> {
+    let (buf, value) = parse_reduced_size(buf, data_type, length, max_length)?;
+    let shift = 64 - 8 * length as u32;
+    Ok((buf, ((value << shift) as i64) >> shift))
+}
+
+impl<'a>
+    ReadablePduWithTwoInputs<
+        'a,
+        InformationElementDataType,
+        u16,
+        LocatedEnterpriseValueParsingError<'a>,
+    > for EnterpriseValue
+{
+    fn from_wire(
+        buf: Span<'a>,
+        data_type: InformationElementDataType,
+        length: u16,
+    ) -> IResult<Span<'a>, Self, LocatedEnterpriseValueParsingError<'a>> {
+        let input = buf;
+        let invalid_length = || {
+            nom::Err::Error(LocatedEnterpriseValueParsingError::new(
+                input,
+                EnterpriseValueParsingError::InvalidLength(data_type, length),
+            ))
+        };
+        let invalid_timestamp = |value: u64| {
+            nom::Err::Error(LocatedEnterpriseValueParsingError::new(
+                input,
+                EnterpriseValueParsingError::InvalidTimestamp(value),
+            ))
+        };
+        match data_type {
+            InformationElementDataType::octetArray | InformationElementDataType::string => {
+                let (buf, length) = if length == u16::MAX {
+                    let (buf, short_length) = be_u8(buf)?;
+                    if short_length < u8::MAX {
+                        (buf, short_length as u16)
+                    } else {
+                        be_u16(buf)?
+                    }
+                } else {
+                    (buf, length)
+                };
+                let (buf, value) = nom::bytes::complete::take(length)(buf)?;
+                if data_type == InformationElementDataType::octetArray {
+                    return Ok((buf, EnterpriseValue::OctetArray(value.to_vec())));
+                }
+                // Fixed length strings are padded with zeros
+                let end = value.iter().position(|c| *c == 0).unwrap_or(value.len());
+                match std::str::from_utf8(&value[..end]) {
+                    Ok(value) => Ok((buf, EnterpriseValue::String(value.to_string()))),
+                    Err(err) => Err(nom::Err::Error(LocatedEnterpriseValueParsingError::new(
+                        input,
+                        EnterpriseValueParsingError::Utf8Error(err.to_string()),
+                    ))),
+                }
+            }
+            InformationElementDataType::unsigned8 => {
+                let (buf, value) = parse_reduced_size(buf, data_type, length, 1)?;
+                Ok((buf, EnterpriseValue::Unsigned8(value as u8)))
+            }
+            InformationElementDataType::unsigned16 => {
+                let (buf, value) = parse_reduced_size(buf, data_type, length, 2)?;
+                Ok((buf, EnterpriseValue::Unsigned16(value as u16)))
+            }
+            InformationElementDataType::unsigned32 => {
+                let (buf, value) = parse_reduced_size(buf, data_type, length, 4)?;
+                Ok((buf, EnterpriseValue::Unsigned32(value as u32)))
+            }
+            InformationElementDataType::unsigned64 => {
+                let (buf, value) = parse_reduced_size(buf, data_type, length, 8)?;
+                Ok((buf, EnterpriseValue::Unsigned64(value)))
+            }
+            InformationElementDataType::signed8 => {
+                let (buf, value) = parse_reduced_size_signed(buf, data_type, length, 1)?;
+                Ok((buf, EnterpriseValue::Signed8(value as i8)))
+            }
+            InformationElementDataType::signed16 => {
+                let (buf, value) = parse_reduced_size_signed(buf, data_type, length, 2)?;
+                Ok((buf, EnterpriseValue::Signed16(value as i16)))
+            }
+            InformationElementDataType::signed32 => {
+                let (buf, value) = parse_reduced_size_signed(buf, data_type, length, 4)?;
+                Ok((buf, EnterpriseValue::Signed32(value as i32)))
+            }
+            InformationElementDataType::signed64 => {
+                let (buf, value) = parse_reduced_size_signed(buf, data_type, length, 8)?;
+                Ok((buf, EnterpriseValue::Signed64(value)))
+            }
+            InformationElementDataType::float32 if length == 4 => {
+                let (buf, value) = be_f32(buf)?;
+                Ok((buf, EnterpriseValue::Float32(value)))
+            }
+            // float64 could be encoded in 4 octets with the reduced-size encoding
+            InformationElementDataType::float64 if length == 4 => {
+                let (buf, value) = be_f32(buf)?;
+                Ok((buf, EnterpriseValue::Float64(value as f64)))
+            }
+            InformationElementDataType::float64 if length == 8 => {
+                let (buf, value) = be_f64(buf)?;
+                Ok((buf, EnterpriseValue::Float64(value)))
+            }
+            InformationElementDataType::boolean if length == 1 => {
+                let (buf, value) = be_u8(buf)?;
+                Ok((buf, EnterpriseValue::Boolean(value != 0)))
+            }
+            InformationElementDataType::macAddress if length == 6 => {
+                let (buf, value) = nom::bytes::complete::take(6usize)(buf)?;
+                let mut mac = [0u8; 6];
+                mac.copy_from_slice(value.fragment());
+                Ok((buf, EnterpriseValue::MacAddress(mac)))
+            }
+            InformationElementDataType::dateTimeSeconds if length == 4 => {
+                let (buf, secs) = be_u32(buf)?;
+                match chrono::Utc.timestamp_opt(secs as i64, 0) {
+                    chrono::LocalResult::Single(value) => {
+                        Ok((buf, EnterpriseValue::DateTimeSeconds(value)))
+                    }
+                    _ => Err(invalid_timestamp(secs as u64)),
+                }
+            }
+            InformationElementDataType::dateTimeMilliseconds if length == 8 => {
+                let (buf, millis) = be_u64(buf)?;
+                match chrono::Utc.timestamp_millis_opt(millis as i64) {
+                    chrono::LocalResult::Single(value) => {
+                        Ok((buf, EnterpriseValue::DateTimeMilliseconds(value)))
+                    }
+                    _ => Err(invalid_timestamp(millis)),
+                }
+            }
+            InformationElementDataType::dateTimeMicroseconds
+            | InformationElementDataType::dateTimeNanoseconds
+                if length == 8 =>
+            {
+                let (buf, secs) = be_u32(buf)?;
+                let (buf, fraction) = be_u32(buf)?;
+                // Convert 1/2^32 of a second to nanoseconds
+                let nanos = (1_000_000_000f64 * (fraction as f64 / u32::MAX as f64)) as u32;
+                let value = match chrono::Utc.timestamp_opt(secs as i64, nanos) {
+                    chrono::LocalResult::Single(value) => value,
+                    _ => return Err(invalid_timestamp(secs as u64)),
+                };
+                if data_type == InformationElementDataType::dateTimeMicroseconds {
+                    Ok((buf, EnterpriseValue::DateTimeMicroseconds(value)))
+                } else {
+                    Ok((buf, EnterpriseValue::DateTimeNanoseconds(value)))
+                }
+            }
+            InformationElementDataType::ipv4Address if length == 4 => {
+                let (buf, value) = be_u32(buf)?;
+                Ok((buf, EnterpriseValue::Ipv4Address(value.into())))
+            }
+            InformationElementDataType::ipv6Address if length == 16 => {
+                let (buf, value) = be_u128(buf)?;
+                Ok((buf, EnterpriseValue::Ipv6Address(value.into())))
+            }
+            _ => Err(invalid_length()),
+        }
+    }
+}
+
+/// Decode the value of an enterprise Information Element that is unknown at
+/// build time using its definition in the [`crate::ie_registry`], the value is
+/// kept as raw octets when the Information Element is not registered
+#[inline]
+fn parse_unknown_field(
+    buf: Span<'_>,
+    pen: u32,
+    id: u16,
+    length: u16,
+) -> IResult<Span<'_>, Field, LocatedFieldParsingError<'_>> {
+    match crate::ie_registry::data_type(pen, id) {
+        Some(data_type) => {
+            let (buf, value) = parse_into_located_two_inputs(buf, data_type, length)?;
+            Ok((
+                buf,
+                Field::Enterprise(EnterpriseField::new(pen, id & !ENTERPRISE_BIT, value)),
+            ))
+        }
+        None => {
+            let (buf, value) =
+                parse_into_located_two_inputs(buf, InformationElementDataType::octetArray, length)?;
+            match value {
+                EnterpriseValue::OctetArray(value) => Ok((buf, Field::Unknown(value))),
+                _ => unreachable!("octetArray is always decoded into EnterpriseValue::OctetArray"),
+            }
+        }
+    }
+}
@@ -13,25 +13,27 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{cell::RefMut, rc::Rc};
+use std::rc::Rc;
 
 use chrono::{LocalResult, TimeZone, Utc};
 use nom::{
     error::ErrorKind,
     number::complete::{be_u16, be_u32, be_u8},
-    IResult,
+    IResult, Slice,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    ie::{InformationElementDataType, InformationElementTemplate, IE},
     ipfix::*,
-    wire::deserializer::{ie, FieldSpecifierParsingError},
-    DataSetId, DATA_SET_MIN_ID,
+    template_cache::TemplateKind,
+    wire::deserializer::{ie, skip_set, FieldSpecifierParsingError},
+    FieldSpecifier, DATA_SET_MIN_ID,
 };
 use netgauze_parse_utils::{
-    parse_into_located, parse_into_located_one_input, parse_into_located_two_inputs,
-    parse_till_empty_into_with_one_input_located, ErrorKindSerdeDeref, ReadablePduWithOneInput,
-    Span,
+    parse_into_located, parse_into_located_one_input, parse_into_located_three_inputs,
+    parse_into_located_two_inputs, parse_till_empty_into_with_one_input_located,
+    ErrorKindSerdeDeref, ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
 };
 use netgauze_serde_macros::LocatedError;
 
@@ -49,6 +51,63 @@ pub enum IpfixPacketParsingError {
     SetParsingError(#[from_located(module = "self")] SetParsingError),
 }
 
+/// Parsed message header, along with the sets octets
+pub(crate) struct IpfixHeader<'a> {
+    pub(crate) export_time: chrono::DateTime<Utc>,
+    pub(crate) sequence_number: u32,
+    pub(crate) observation_domain_id: u32,
+    pub(crate) sets: Span<'a>,
+}
+
+#[inline]
+pub(crate) fn parse_header<'a>(
+    buf: Span<'a>,
+    templates_map: &TemplatesMap,
+) -> IResult<Span<'a>, IpfixHeader<'a>, LocatedIpfixPacketParsingError<'a>> {
+    let input = buf;
+    let (buf, version) = be_u16(buf)?;
+    if version != IPFIX_VERSION {
+        return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+            input,
+            IpfixPacketParsingError::UnsupportedVersion(version),
+        )));
+    }
+    let input = buf;
+    let (buf, length) = be_u16(buf)?;
+    if length < IPFIX_HEADER_LENGTH {
+        return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+            input,
+            IpfixPacketParsingError::InvalidLength(length),
+        )));
+    }
+    let (reminder, buf) = nom::bytes::complete::take(length - 4)(buf)?;
+    let (buf, export_time) = be_u32(buf)?;
+    let export_time = match Utc.timestamp_opt(export_time as i64, 0) {
+        LocalResult::Single(time) => time,
+        _ => {
+            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
+                input,
+                IpfixPacketParsingError::InvalidExportTime(export_time),
+            )));
+        }
+    };
+    let (buf, sequence_number) = be_u32(buf)?;
+    let (buf, observation_domain_id) = be_u32(buf)?;
+    // Templates are scoped to the observation domain
+    templates_map
+        .borrow_mut()
+        .set_observation_domain_id(observation_domain_id);
+    Ok((
+        reminder,
+        IpfixHeader {
+            export_time,
+            sequence_number,
+            observation_domain_id,
+            sets: buf,
+        },
+    ))
+}
+
 impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedIpfixPacketParsingError<'a>>
     for IpfixPacket
 {
@@ -56,43 +115,68 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedIpfixPacketParsingErro
         buf: Span<'a>,
         templates_map: TemplatesMap,
     ) -> IResult<Span<'a>, Self, LocatedIpfixPacketParsingError<'a>> {
-        let input = buf;
-        let (buf, version) = be_u16(buf)?;
-        if version != IPFIX_VERSION {
-            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                input,
-                IpfixPacketParsingError::UnsupportedVersion(version),
-            )));
-        }
-        let input = buf;
-        let (buf, length) = be_u16(buf)?;
-        if length < IPFIX_HEADER_LENGTH {
-            return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                input,
-                IpfixPacketParsingError::InvalidLength(length),
-            )));
-        }
-        let (reminder, buf) = nom::bytes::complete::take(length - 4)(buf)?;
-        let (buf, export_time) = be_u32(buf)?;
-        let export_time = match Utc.timestamp_opt(export_time as i64, 0) {
-            LocalResult::Single(time) => time,
-            _ => {
-                return Err(nom::Err::Error(LocatedIpfixPacketParsingError::new(
-                    input,
-                    IpfixPacketParsingError::InvalidExportTime(export_time),
-                )));
-            }
-        };
-        let (buf, sequence_number) = be_u32(buf)?;
-        let (buf, observation_domain_id) = be_u32(buf)?;
-        let (_, payload) = parse_till_empty_into_with_one_input_located(buf, templates_map)?;
+        let (reminder, header) = parse_header(buf, &templates_map)?;
+        let (_, payload) =
+            parse_till_empty_into_with_one_input_located(header.sets, templates_map)?;
         Ok((
             reminder,
-            IpfixPacket::new(export_time, sequence_number, observation_domain_id, payload),
+            IpfixPacket::new(
+                header.export_time,
+                header.sequence_number,
+                header.observation_domain_id,
+                payload,
+            ),
         ))
     }
 }
 
+/// Parse an IPFIX packet without giving up on the first malformed set. A set
+/// that fails to parse is skipped using the length in its header, and its
+/// error is returned along with the other sets. The parsing of the remaining
+/// sets stops when the set header itself is malformed. Errors in the message
+/// header are still fatal.
+pub fn parse_ipfix_packet_lenient<'a>(
+    buf: Span<'a>,
+    templates_map: TemplatesMap,
+) -> IResult<
+    Span<'a>,
+    (IpfixPacket, Vec<LocatedSetParsingError<'a>>),
+    LocatedIpfixPacketParsingError<'a>,
+> {
+    let (reminder, header) = parse_header(buf, &templates_map)?;
+    let mut buf = header.sets;
+    let mut sets = vec![];
+    let mut errors = vec![];
+    while !buf.is_empty() {
+        match Set::from_wire(buf, Rc::clone(&templates_map)) {
+            Ok((t, set)) => {
+                buf = t;
+                sets.push(set);
+            }
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                errors.push(err);
+                match skip_set(buf) {
+                    Some(t) => buf = t,
+                    None => break,
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => break,
+        }
+    }
+    Ok((
+        reminder,
+        (
+            IpfixPacket::new(
+                header.export_time,
+                header.sequence_number,
+                header.observation_domain_id,
+                sets,
+            ),
+            errors,
+        ),
+    ))
+}
+
 #[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum SetParsingError {
     #[serde(with = "ErrorKindSerdeDeref")]
@@ -112,15 +196,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
         templates_map: TemplatesMap,
     ) -> IResult<Span<'a>, Self, LocatedSetParsingError<'a>> {
         let input = buf;
-        let (buf, id) = nom::combinator::map_res(be_u16, |id| {
-            if id != IPFIX_TEMPLATE_SET_ID
-                && id != IPFIX_OPTIONS_TEMPLATE_SET_ID
-                && id < DATA_SET_MIN_ID
-            {
-                Err(SetParsingError::InvalidSetId(id))
-            } else {
-                Ok(id)
-            }
+        let (buf, set_id) = nom::combinator::map_res(be_u16, |id| {
+            SetId::try_from(id).map_err(|_| SetParsingError::InvalidSetId(id))
         })(buf)?;
         let (buf, length) = nom::combinator::map_res(be_u16, |length| {
             if length < 4 {
@@ -130,13 +207,13 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
             }
         })(buf)?;
         let (reminder, mut buf) = nom::bytes::complete::take(length - 4)(buf)?;
-        let set = match id {
-            IPFIX_TEMPLATE_SET_ID => {
+        let set = match set_id {
+            SetId::Template => {
                 let (_buf, templates) =
                     parse_till_empty_into_with_one_input_located(buf, templates_map)?;
                 Set::Template(templates)
             }
-            IPFIX_OPTIONS_TEMPLATE_SET_ID => {
+            SetId::OptionsTemplate => {
                 let mut option_templates = vec![];
                 // THE RFC is not super clear about
                 // length allowed in the Options
@@ -152,8 +229,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
                 check_padding_value(buf)?;
                 Set::OptionsTemplate(option_templates)
             }
-            // We don't need to check for valid Set ID again, since we already checked
-            id => {
+            SetId::Data(data_set_id) => {
+                let id = data_set_id.id();
                 // Temp variable to keep the borrowed value from RC
                 let binding = templates_map.as_ref().borrow();
                 let template = if let Some(fields) = binding.get(&id) {
@@ -165,24 +242,47 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
                     )));
                 };
                 let (scope_field_specs, field_specs) = template.as_ref();
-                let mut total_record_count = scope_field_specs.len() + field_specs.len();
                 let mut records = Vec::new();
-                while total_record_count > 0 {
-                    let (t, record): (Span<'_>, DataRecord) =
-                        parse_into_located_one_input(buf, Rc::clone(template))?;
-                    buf = t;
-                    total_record_count -= record.scope_fields().len() + record.fields().len();
-                    records.push(record);
-                }
-                // buf could be a non zero value for padding
-                while buf.len() > 0 && nom::combinator::peek(be_u8)(buf)?.1 == 0 {
-                    let (t, _) = be_u8(buf)?;
-                    buf = t;
+                if binding.strict() {
+                    // Decode records as long as there is room for one, anything left must
+                    // be zero padding
+                    let min_record_length = scope_field_specs
+                        .iter()
+                        .chain(field_specs.iter())
+                        .map(|spec| min_field_length(spec.length()))
+                        .sum::<usize>()
+                        .max(1);
+                    while buf.len() >= min_record_length {
+                        let (t, record): (Span<'_>, DataRecord) = parse_into_located_two_inputs(
+                            buf,
+                            Rc::clone(template),
+                            Some(&templates_map),
+                        )?;
+                        buf = t;
+                        records.push(record);
+                    }
+                    check_padding_value(buf)?;
+                } else {
+                    let mut total_record_count = scope_field_specs.len() + field_specs.len();
+                    while total_record_count > 0 {
+                        let (t, record): (Span<'_>, DataRecord) = parse_into_located_two_inputs(
+                            buf,
+                            Rc::clone(template),
+                            Some(&templates_map),
+                        )?;
+                        buf = t;
+                        total_record_count -= record.scope_fields().len() + record.fields().len();
+                        records.push(record);
+                    }
+                    // buf could be a non zero value for padding
+                    while buf.len() > 0 && nom::combinator::peek(be_u8)(buf)?.1 == 0 {
+                        let (t, _) = be_u8(buf)?;
+                        buf = t;
+                    }
                 }
 
-                // We can safely unwrap DataSetId here since we already checked the range
                 Set::Data {
-                    id: DataSetId::new(id).unwrap(),
+                    id: data_set_id,
                     records,
                 }
             }
@@ -225,9 +325,18 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
     ) -> IResult<Span<'a>, Self, LocatedOptionsTemplateRecordParsingError<'a>> {
         let input = buf;
         let (buf, template_id) = be_u16(buf)?;
+        let (buf, total_fields_count) = be_u16(buf)?;
+        // Options Template Withdrawal Record has no scope fields count, and the
+        // Options Template Set ID is used to withdraw all the Options Templates
+        if total_fields_count == 0 && template_id == IPFIX_OPTIONS_TEMPLATE_SET_ID {
+            templates_map
+                .borrow_mut()
+                .withdraw_all(TemplateKind::OptionsTemplate);
+            return Ok((buf, OptionsTemplateRecord::new(template_id, vec![], vec![])));
+        }
         // from RFC7011: Each Template Record is given a unique Template ID in the range
         // 256 to 65535.
-        if template_id < 256 {
+        if template_id < DATA_SET_MIN_ID {
             return Err(nom::Err::Error(
                 LocatedOptionsTemplateRecordParsingError::new(
                     input,
@@ -235,7 +344,10 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
                 ),
             ));
         }
-        let (buf, total_fields_count) = be_u16(buf)?;
+        if total_fields_count == 0 {
+            templates_map.borrow_mut().withdraw(template_id);
+            return Ok((buf, OptionsTemplateRecord::new(template_id, vec![], vec![])));
+        }
         let input = buf;
         let (mut buf, scope_fields_count) = be_u16(buf)?;
         if scope_fields_count > total_fields_count {
@@ -259,10 +371,11 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
             fields.push(field);
             buf = t;
         }
-        {
-            let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((scope_fields.clone(), fields.clone())));
-        }
+        templates_map.borrow_mut().insert(
+            template_id,
+            TemplateKind::OptionsTemplate,
+            Rc::new((scope_fields.clone(), fields.clone())),
+        );
         Ok((
             buf,
             OptionsTemplateRecord::new(template_id, scope_fields, fields),
@@ -270,32 +383,125 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
     }
 }
 
+/// Variable length fields carry at least their one-octet length
+#[inline]
+const fn min_field_length(length: u16) -> usize {
+    if length == u16::MAX {
+        1
+    } else {
+        length as usize
+    }
+}
+
 #[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum DataRecordParsingError {
     FieldError(#[from_located(module = "")] ie::FieldParsingError),
+
+    /// Strict mode: the value of the field at `index` (counting the scope
+    /// fields first) didn't consume the length given in the template
+    FieldLengthMismatch {
+        index: usize,
+        ie: IE,
+        expected: u16,
+        actual: usize,
+    },
+
+    /// Strict mode: the fixed length string at `index` (counting the scope
+    /// fields first) has non-zero octets after its terminating zero
+    InvalidStringPadding {
+        index: usize,
+        ie: IE,
+    },
+}
+
+/// Check a decoded field against its specifier in strict mode, `value` are the
+/// octets consumed by the field
+fn check_strict_field(
+    index: usize,
+    spec: &FieldSpecifier,
+    value: Span<'_>,
+) -> Result<(), DataRecordParsingError> {
+    let ie = spec.element_id();
+    if spec.length() == u16::MAX {
+        return Ok(());
+    }
+    if value.len() != spec.length() as usize {
+        return Err(DataRecordParsingError::FieldLengthMismatch {
+            index,
+            ie,
+            expected: spec.length(),
+            actual: value.len(),
+        });
+    }
+    if ie.data_type() == InformationElementDataType::string {
+        if let Some(nul) = value.iter().position(|x| *x == 0) {
+            if value[nul..].iter().any(|x| *x != 0) {
+                return Err(DataRecordParsingError::InvalidStringPadding { index, ie });
+            }
+        }
+    }
+    Ok(())
 }
 
 impl<'a> ReadablePduWithOneInput<'a, Rc<DecodingTemplate>, LocatedDataRecordParsingError<'a>>
     for DataRecord
+{
+    /// Parse a data record without any templates, structured data fields
+    /// that refer to other templates fail to be parsed
+    fn from_wire(
+        buf: Span<'a>,
+        field_specifiers: Rc<DecodingTemplate>,
+    ) -> IResult<Span<'a>, Self, LocatedDataRecordParsingError<'a>> {
+        parse_into_located_two_inputs(buf, field_specifiers, None)
+    }
+}
+
+impl<'a>
+    ReadablePduWithTwoInputs<
+        'a,
+        Rc<DecodingTemplate>,
+        Option<&TemplatesMap>,
+        LocatedDataRecordParsingError<'a>,
+    > for DataRecord
 {
     fn from_wire(
         buf: Span<'a>,
         field_specifiers: Rc<DecodingTemplate>,
+        templates_map: Option<&TemplatesMap>,
     ) -> IResult<Span<'a>, Self, LocatedDataRecordParsingError<'a>> {
         let mut buf = buf;
         let (scope_fields_specs, field_specs) = field_specifiers.as_ref();
+        let strict = templates_map.is_some_and(|templates_map| templates_map.borrow().strict());
 
         let mut scope_fields = Vec::<crate::ie::Field>::with_capacity(scope_fields_specs.len());
-        for spec in scope_fields_specs {
-            let (t, scope_field) =
-                parse_into_located_two_inputs(buf, &spec.element_id(), spec.length)?;
+        for (index, spec) in scope_fields_specs.iter().enumerate() {
+            let (t, scope_field) = parse_into_located_three_inputs(
+                buf,
+                &spec.element_id(),
+                spec.length,
+                templates_map,
+            )?;
+            if strict {
+                check_strict_field(index, spec, buf.slice(..buf.len() - t.len()))
+                    .map_err(|err| nom::Err::Error(LocatedDataRecordParsingError::new(buf, err)))?;
+            }
             buf = t;
             scope_fields.push(scope_field);
         }
 
         let mut fields = Vec::<crate::ie::Field>::with_capacity(field_specs.len());
-        for spec in field_specs {
-            let (t, field) = parse_into_located_two_inputs(buf, &spec.element_id(), spec.length)?;
+        for (index, spec) in field_specs.iter().enumerate() {
+            let (t, field) = parse_into_located_three_inputs(
+                buf,
+                &spec.element_id(),
+                spec.length,
+                templates_map,
+            )?;
+            if strict {
+                let index = scope_fields_specs.len() + index;
+                check_strict_field(index, spec, buf.slice(..buf.len() - t.len()))
+                    .map_err(|err| nom::Err::Error(LocatedDataRecordParsingError::new(buf, err)))?;
+            }
             buf = t;
             fields.push(field);
         }
@@ -322,25 +528,37 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedTemplateRecordParsingE
     ) -> IResult<Span<'a>, Self, LocatedTemplateRecordParsingError<'a>> {
         let input = buf;
         let (buf, template_id) = be_u16(buf)?;
+        let (mut buf, field_count) = be_u16(buf)?;
+        // The Template Set ID is used to withdraw all the Templates
+        if field_count == 0 && template_id == IPFIX_TEMPLATE_SET_ID {
+            templates_map
+                .borrow_mut()
+                .withdraw_all(TemplateKind::Template);
+            return Ok((buf, TemplateRecord::new(template_id, vec![])));
+        }
         // from RFC7011: Each Template Record is given a unique Template ID in the range
         // 256 to 65535.
-        if template_id < 256 {
+        if template_id < DATA_SET_MIN_ID {
             return Err(nom::Err::Error(LocatedTemplateRecordParsingError::new(
                 input,
                 TemplateRecordParsingError::InvalidTemplateId(template_id),
             )));
         }
-        let (mut buf, field_count) = be_u16(buf)?;
+        if field_count == 0 {
+            templates_map.borrow_mut().withdraw(template_id);
+            return Ok((buf, TemplateRecord::new(template_id, vec![])));
+        }
         let mut fields = Vec::with_capacity(field_count as usize);
         for _ in 0..field_count {
             let (t, field) = parse_into_located(buf)?;
             fields.push(field);
             buf = t;
         }
-        {
-            let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((vec![], fields.clone())));
-        }
+        templates_map.borrow_mut().insert(
+            template_id,
+            TemplateKind::Template,
+            Rc::new((vec![], fields.clone())),
+        );
         Ok((buf, TemplateRecord::new(template_id, fields)))
     }
 }
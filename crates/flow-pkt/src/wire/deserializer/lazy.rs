@@ -0,0 +1,394 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Lazy decoding of the IPFIX data sets.
+//!
+//! [`LazyIpfixPacket`] decodes the templates as usual, but keeps the data sets
+//! as raw octets along with their template. The fields are only decoded when
+//! requested, which saves most of the allocations for the pipelines that only
+//! read a few fields out of large records.
+//!
+//! ```
+//! use std::{cell::RefCell, rc::Rc};
+//!
+//! use netgauze_flow_pkt::{
+//!     ie::{self, Field, IE},
+//!     template_cache::TemplateCache,
+//!     wire::deserializer::lazy::{LazyIpfixPacket, LazySet},
+//! };
+//! use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+//!
+//! let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+//! let wire = [
+//!     0x00, 0x0a, 0x00, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+//!     0x00, // Header
+//!     0x00, 0x02, 0x00, 0x10, 0x01, 0x00, 0x00, 0x02, 0x00, 0x0a, 0x00, 0x04, 0x00, 0x02, 0x00,
+//!     0x04, // Template set
+//!     0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00,
+//!     0x02, 0x00, 0x00, 0x00, 0x07, // Data set with two records
+//! ];
+//! let (_, pkt) = LazyIpfixPacket::from_wire(Span::new(&wire), templates_map).unwrap();
+//! let packets = pkt
+//!     .sets()
+//!     .iter()
+//!     .filter_map(|set| match set {
+//!         LazySet::Data(data) => Some(data),
+//!         _ => None,
+//!     })
+//!     .flat_map(|data| data.records())
+//!     .map(|record| record.unwrap().field(&IE::packetDeltaCount).unwrap().unwrap())
+//!     .collect::<Vec<_>>();
+//! assert_eq!(
+//!     packets,
+//!     vec![
+//!         Field::packetDeltaCount(ie::packetDeltaCount(5)),
+//!         Field::packetDeltaCount(ie::packetDeltaCount(7)),
+//!     ]
+//! );
+//! ```
+
+use std::rc::Rc;
+
+use chrono::{DateTime, Utc};
+use nom::{
+    error::ErrorKind,
+    number::complete::{be_u16, be_u8},
+    IResult, Slice,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ie::{Field, IE},
+    ipfix::*,
+    wire::deserializer::{
+        ie,
+        ipfix::{
+            parse_header, DataRecordParsingError, LocatedIpfixPacketParsingError,
+            LocatedSetParsingError, SetParsingError,
+        },
+    },
+    DataSetId, FieldSpecifier, DATA_SET_MIN_ID,
+};
+use netgauze_parse_utils::{
+    parse_into_located_one_input, parse_into_located_three_inputs, parse_into_located_two_inputs,
+    parse_till_empty_into_with_one_input_located, ErrorKindSerdeDeref, ReadablePduWithOneInput,
+    Span,
+};
+use netgauze_serde_macros::LocatedError;
+
+/// IPFIX packet with the data sets decoded lazily, see the
+/// [module docs](self)
+#[derive(Debug, Clone)]
+pub struct LazyIpfixPacket<'a> {
+    export_time: DateTime<Utc>,
+    sequence_number: u32,
+    observation_domain_id: u32,
+    sets: Vec<LazySet<'a>>,
+}
+
+impl<'a> LazyIpfixPacket<'a> {
+    pub const fn export_time(&self) -> DateTime<Utc> {
+        self.export_time
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+
+    pub const fn sets(&self) -> &Vec<LazySet<'a>> {
+        &self.sets
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum LazySet<'a> {
+    Template(Vec<TemplateRecord>),
+    OptionsTemplate(Vec<OptionsTemplateRecord>),
+    Data(LazyDataSet<'a>),
+}
+
+/// Raw data records of a data set along with the template to decode them
+#[derive(Debug, Clone)]
+pub struct LazyDataSet<'a> {
+    id: DataSetId,
+    template: Rc<DecodingTemplate>,
+    templates_map: TemplatesMap,
+    buf: Span<'a>,
+}
+
+impl<'a> LazyDataSet<'a> {
+    pub const fn id(&self) -> DataSetId {
+        self.id
+    }
+
+    pub const fn template(&self) -> &Rc<DecodingTemplate> {
+        &self.template
+    }
+
+    /// Raw octets of the records, including the set padding
+    pub fn raw(&self) -> &'a [u8] {
+        self.buf.fragment()
+    }
+
+    /// Iterate over the records without decoding them. The iteration stops
+    /// after the first malformed record.
+    pub fn records(&self) -> LazyDataRecords<'a, '_> {
+        let min_record_length = self
+            .template
+            .0
+            .iter()
+            .chain(self.template.1.iter())
+            .map(|spec| {
+                if spec.length() == u16::MAX {
+                    1
+                } else {
+                    spec.length() as usize
+                }
+            })
+            .sum::<usize>()
+            .max(1);
+        LazyDataRecords {
+            set: self,
+            buf: self.buf,
+            min_record_length,
+            done: false,
+        }
+    }
+
+    /// Decode all the records of the set
+    pub fn decode(&self) -> Result<Vec<DataRecord>, LocatedLazyDataRecordParsingError<'a>> {
+        self.records()
+            .map(|record| record.and_then(|record| record.decode()))
+            .collect()
+    }
+}
+
+/// Iterator over the records of a [`LazyDataSet`]
+#[derive(Debug, Clone)]
+pub struct LazyDataRecords<'a, 's> {
+    set: &'s LazyDataSet<'a>,
+    buf: Span<'a>,
+    min_record_length: usize,
+    done: bool,
+}
+
+impl<'a, 's> Iterator for LazyDataRecords<'a, 's> {
+    type Item = Result<LazyDataRecord<'a, 's>, LocatedLazyDataRecordParsingError<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Anything shorter than a record is padding
+        if self.done || self.buf.len() < self.min_record_length {
+            return None;
+        }
+        let (scope_field_specs, field_specs) = self.set.template.as_ref();
+        let mut buf = self.buf;
+        for spec in scope_field_specs.iter().chain(field_specs.iter()) {
+            match finish(buf, take_field(buf, spec.length())) {
+                Ok((t, _)) => buf = t,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        let record = LazyDataRecord {
+            set: self.set,
+            buf: self.buf.slice(..self.buf.len() - buf.len()),
+        };
+        self.buf = buf;
+        Some(Ok(record))
+    }
+}
+
+/// Raw data record, the fields are decoded on demand
+#[derive(Debug, Clone, Copy)]
+pub struct LazyDataRecord<'a, 's> {
+    set: &'s LazyDataSet<'a>,
+    buf: Span<'a>,
+}
+
+impl<'a, 's> LazyDataRecord<'a, 's> {
+    /// Raw octets of the whole record
+    pub fn raw(&self) -> &'a [u8] {
+        self.buf.fragment()
+    }
+
+    /// Raw octets of the first field of the given Information Element,
+    /// without the variable length prefix
+    pub fn raw_field(&self, ie: &IE) -> Option<&'a [u8]> {
+        self.find(ie).map(|(_, _, value)| *value.fragment())
+    }
+
+    /// Decode the first field of the given Information Element, scope fields
+    /// included
+    pub fn field(&self, ie: &IE) -> Option<Result<Field, LocatedLazyDataRecordParsingError<'a>>> {
+        let (spec, buf, _) = self.find(ie)?;
+        let templates_map = Some(&self.set.templates_map);
+        Some(
+            finish(
+                buf,
+                parse_into_located_three_inputs(buf, ie, spec.length(), templates_map),
+            )
+            .map(|(_, field)| field),
+        )
+    }
+
+    /// Decode the whole record
+    pub fn decode(&self) -> Result<DataRecord, LocatedLazyDataRecordParsingError<'a>> {
+        finish(
+            self.buf,
+            parse_into_located_two_inputs(
+                self.buf,
+                Rc::clone(&self.set.template),
+                Some(&self.set.templates_map),
+            ),
+        )
+        .map(|(_, record)| record)
+    }
+
+    /// The specifier, the octets including the variable length prefix, and
+    /// the octets of the value of a field
+    fn find(&self, ie: &IE) -> Option<(&'s FieldSpecifier, Span<'a>, Span<'a>)> {
+        let (scope_field_specs, field_specs) = self.set.template.as_ref();
+        let mut buf = self.buf;
+        for spec in scope_field_specs.iter().chain(field_specs.iter()) {
+            // The record bounds are already checked when iterating over the set
+            let (t, value) = take_field(buf, spec.length()).ok()?;
+            if spec.element_id() == *ie {
+                return Some((spec, buf.slice(..buf.len() - t.len()), value));
+            }
+            buf = t;
+        }
+        None
+    }
+}
+
+/// Unwrap the nom result, incomplete input is reported at `buf`
+fn finish<'a, T>(
+    buf: Span<'a>,
+    result: IResult<Span<'a>, T, LocatedLazyDataRecordParsingError<'a>>,
+) -> Result<(Span<'a>, T), LocatedLazyDataRecordParsingError<'a>> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(nom::Err::Error(err) | nom::Err::Failure(err)) => Err(err),
+        Err(nom::Err::Incomplete(_)) => Err(LocatedLazyDataRecordParsingError::new(
+            buf,
+            LazyDataRecordParsingError::NomError(ErrorKind::Eof),
+        )),
+    }
+}
+
+/// Take the octets of the next field, returns the value without the variable
+/// length prefix if any
+fn take_field(
+    buf: Span<'_>,
+    length: u16,
+) -> IResult<Span<'_>, Span<'_>, LocatedLazyDataRecordParsingError<'_>> {
+    let (buf, length) = if length == u16::MAX {
+        let (buf, short_length) = be_u8(buf)?;
+        if short_length == u8::MAX {
+            be_u16(buf)?
+        } else {
+            (buf, short_length as u16)
+        }
+    } else {
+        (buf, length)
+    };
+    nom::bytes::complete::take(length)(buf)
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum LazyDataRecordParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    FieldError(#[from_located(module = "")] ie::FieldParsingError),
+    DataRecordError(
+        #[from_located(module = "crate::wire::deserializer::ipfix")] DataRecordParsingError,
+    ),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedIpfixPacketParsingError<'a>>
+    for LazyIpfixPacket<'a>
+{
+    fn from_wire(
+        buf: Span<'a>,
+        templates_map: TemplatesMap,
+    ) -> IResult<Span<'a>, Self, LocatedIpfixPacketParsingError<'a>> {
+        let (reminder, header) = parse_header(buf, &templates_map)?;
+        let (_, sets) = parse_till_empty_into_with_one_input_located(header.sets, templates_map)?;
+        Ok((
+            reminder,
+            LazyIpfixPacket {
+                export_time: header.export_time,
+                sequence_number: header.sequence_number,
+                observation_domain_id: header.observation_domain_id,
+                sets,
+            },
+        ))
+    }
+}
+
+impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> for LazySet<'a> {
+    fn from_wire(
+        buf: Span<'a>,
+        templates_map: TemplatesMap,
+    ) -> IResult<Span<'a>, Self, LocatedSetParsingError<'a>> {
+        let input = buf;
+        let (_, id) = nom::combinator::peek(be_u16)(buf)?;
+        if id < DATA_SET_MIN_ID {
+            // Templates are needed to decode the following sets, so they're decoded
+            // eagerly
+            let (buf, set) = parse_into_located_one_input(buf, templates_map)?;
+            let set = match set {
+                Set::Template(templates) => LazySet::Template(templates),
+                Set::OptionsTemplate(templates) => LazySet::OptionsTemplate(templates),
+                Set::Data { .. } => unreachable!("data sets IDs are above {DATA_SET_MIN_ID}"),
+            };
+            return Ok((buf, set));
+        }
+        let (buf, _) = be_u16(buf)?;
+        let (buf, length) = nom::combinator::map_res(be_u16, |length| {
+            if length < 4 {
+                Err(SetParsingError::InvalidLength(length))
+            } else {
+                Ok(length)
+            }
+        })(buf)?;
+        let (reminder, buf) = nom::bytes::complete::take(length - 4)(buf)?;
+        let template = match templates_map.borrow().get(&id) {
+            Some(template) => Rc::clone(template),
+            None => {
+                return Err(nom::Err::Error(LocatedSetParsingError::new(
+                    input,
+                    SetParsingError::NoTemplateDefinedFor(id),
+                )))
+            }
+        };
+        Ok((
+            reminder,
+            LazySet::Data(LazyDataSet {
+                // Safe to unwrap since the ID is already checked
+                id: DataSetId::new(id).unwrap(),
+                template,
+                templates_map,
+                buf,
+            }),
+        ))
+    }
+}
@@ -16,7 +16,7 @@
 use nom::{
     error::ErrorKind,
     number::complete::{be_u16, be_u32},
-    IResult,
+    IResult, Slice,
 };
 use serde::{Deserialize, Serialize};
 
@@ -28,6 +28,7 @@ use crate::ie::{IEError, IE};
 
 pub mod ie;
 pub mod ipfix;
+pub mod lazy;
 pub mod netflow;
 
 #[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
@@ -36,6 +37,20 @@ pub enum FlowParsingError {
     NetFlowV9ParsingError(netflow::NetFlowV9PacketParsingError),
 }
 
+/// Skip a set that failed to parse using the length in its header, returns
+/// [`None`] when the length doesn't fit the remaining octets
+pub(crate) fn skip_set(buf: Span<'_>) -> Option<Span<'_>> {
+    let length = match nom::sequence::preceded(be_u16::<_, ()>, be_u16)(buf) {
+        Ok((_, length)) => length as usize,
+        Err(_) => return None,
+    };
+    if length < 4 || length > buf.len() {
+        None
+    } else {
+        Some(buf.slice(length..))
+    }
+}
+
 #[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum FieldSpecifierParsingError {
     #[serde(with = "ErrorKindSerdeDeref")]
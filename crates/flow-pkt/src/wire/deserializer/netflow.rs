@@ -13,7 +13,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::{cell::RefMut, rc::Rc};
+use std::rc::Rc;
 
 use chrono::{LocalResult, TimeZone, Utc};
 use nom::{
@@ -31,8 +31,11 @@ use netgauze_parse_utils::{
 use netgauze_serde_macros::LocatedError;
 
 use crate::{
-    ie::InformationElementTemplate, netflow::*, wire::deserializer::FieldSpecifierParsingError,
-    DataSetId, FieldSpecifier, DATA_SET_MIN_ID,
+    ie::InformationElementTemplate,
+    netflow::*,
+    template_cache::TemplateKind,
+    wire::deserializer::{skip_set, FieldSpecifierParsingError},
+    FieldSpecifier, DATA_SET_MIN_ID,
 };
 
 /// 2-octets version, 2-octets count, 4-octets * 4 (sysUpTime, UNIX time, seq
@@ -49,6 +52,68 @@ pub enum NetFlowV9PacketParsingError {
     SetError(#[from_located(module = "self")] SetParsingError),
 }
 
+/// Parsed packet header, the remaining octets are the sets
+struct NetFlowV9Header {
+    count: u16,
+    sys_up_time: u32,
+    unix_time: chrono::DateTime<Utc>,
+    sequence_number: u32,
+    source_id: u32,
+}
+
+#[inline]
+fn parse_header<'a>(
+    buf: Span<'a>,
+    templates_map: &TemplatesMap,
+) -> IResult<Span<'a>, NetFlowV9Header, LocatedNetFlowV9PacketParsingError<'a>> {
+    let input = buf;
+    let (buf, version) = be_u16(buf)?;
+    if version != NETFLOW_V9_VERSION {
+        return Err(nom::Err::Error(LocatedNetFlowV9PacketParsingError::new(
+            input,
+            NetFlowV9PacketParsingError::UnsupportedVersion(version),
+        )));
+    }
+    let input = buf;
+    let (buf, count) = be_u16(buf)?;
+    let (buf, sys_up_time) = be_u32(buf)?;
+    let (buf, unix_time) = be_u32(buf)?;
+    let unix_time = match Utc.timestamp_opt(unix_time as i64, 0) {
+        LocalResult::Single(time) => time,
+        _ => {
+            return Err(nom::Err::Error(LocatedNetFlowV9PacketParsingError::new(
+                input,
+                NetFlowV9PacketParsingError::InvalidUnixTime(unix_time),
+            )));
+        }
+    };
+    let (buf, sequence_number) = be_u32(buf)?;
+    let (buf, source_id) = be_u32(buf)?;
+    // Templates are scoped to the source id
+    templates_map
+        .borrow_mut()
+        .set_observation_domain_id(source_id);
+    Ok((
+        buf,
+        NetFlowV9Header {
+            count,
+            sys_up_time,
+            unix_time,
+            sequence_number,
+            source_id,
+        },
+    ))
+}
+
+/// Number of records carried by a set, as counted by the packet header
+#[inline]
+fn records_count(set: &Set) -> usize {
+    match set {
+        Set::Template(_) | Set::OptionsTemplate(_) => 1,
+        Set::Data { records, .. } => records.len(),
+    }
+}
+
 impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedNetFlowV9PacketParsingError<'a>>
     for NetFlowV9Packet
 {
@@ -56,49 +121,80 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedNetFlowV9PacketParsing
         buf: Span<'a>,
         templates_map: TemplatesMap,
     ) -> IResult<Span<'a>, Self, LocatedNetFlowV9PacketParsingError<'a>> {
-        let input = buf;
-        let (buf, version) = be_u16(buf)?;
-        if version != NETFLOW_V9_VERSION {
-            return Err(nom::Err::Error(LocatedNetFlowV9PacketParsingError::new(
-                input,
-                NetFlowV9PacketParsingError::UnsupportedVersion(version),
-            )));
-        }
-        let input = buf;
-        let (buf, count) = be_u16(buf)?;
-        let (buf, sys_up_time) = be_u32(buf)?;
-        let (buf, unix_time) = be_u32(buf)?;
-        let unix_time = match Utc.timestamp_opt(unix_time as i64, 0) {
-            LocalResult::Single(time) => time,
-            _ => {
-                return Err(nom::Err::Error(LocatedNetFlowV9PacketParsingError::new(
-                    input,
-                    NetFlowV9PacketParsingError::InvalidUnixTime(unix_time),
-                )));
-            }
-        };
-        let (buf, sequence_number) = be_u32(buf)?;
-        let (mut buf, source_id) = be_u32(buf)?;
-        let mut payload = Vec::with_capacity(count as usize);
-        let mut i = count as usize;
+        let (mut buf, header) = parse_header(buf, &templates_map)?;
+        let mut payload = Vec::with_capacity(header.count as usize);
+        let mut i = header.count as usize;
         while i > 0 && buf.len() > 3 {
             let (tmp, set): (_, Set) =
                 parse_into_located_one_input(buf, Rc::clone(&templates_map))?;
             buf = tmp;
-            match set {
-                Set::Template(_) => i -= 1,
-                Set::OptionsTemplate(_) => i -= 1,
-                Set::Data { id: _, ref records } => i -= records.len(),
-            }
+            i = i.saturating_sub(records_count(&set));
             payload.push(set);
         }
         Ok((
             buf,
-            NetFlowV9Packet::new(sys_up_time, unix_time, sequence_number, source_id, payload),
+            NetFlowV9Packet::new(
+                header.sys_up_time,
+                header.unix_time,
+                header.sequence_number,
+                header.source_id,
+                payload,
+            ),
         ))
     }
 }
 
+/// Parse a NetFlow V9 packet without giving up on the first malformed set. A
+/// set that fails to parse is skipped using the length in its header, and its
+/// error is returned along with the other sets. The parsing of the remaining
+/// sets stops when the set header itself is malformed. Errors in the packet
+/// header are still fatal.
+pub fn parse_netflow_v9_packet_lenient<'a>(
+    buf: Span<'a>,
+    templates_map: TemplatesMap,
+) -> IResult<
+    Span<'a>,
+    (NetFlowV9Packet, Vec<LocatedSetParsingError<'a>>),
+    LocatedNetFlowV9PacketParsingError<'a>,
+> {
+    let (mut buf, header) = parse_header(buf, &templates_map)?;
+    let mut sets = Vec::with_capacity(header.count as usize);
+    let mut errors = vec![];
+    let mut i = header.count as usize;
+    while i > 0 && buf.len() > 3 {
+        match Set::from_wire(buf, Rc::clone(&templates_map)) {
+            Ok((t, set)) => {
+                buf = t;
+                i = i.saturating_sub(records_count(&set));
+                sets.push(set);
+            }
+            Err(nom::Err::Error(err) | nom::Err::Failure(err)) => {
+                errors.push(err);
+                // The records of the skipped set are unknown
+                i -= 1;
+                match skip_set(buf) {
+                    Some(t) => buf = t,
+                    None => break,
+                }
+            }
+            Err(nom::Err::Incomplete(_)) => break,
+        }
+    }
+    Ok((
+        buf,
+        (
+            NetFlowV9Packet::new(
+                header.sys_up_time,
+                header.unix_time,
+                header.sequence_number,
+                header.source_id,
+                sets,
+            ),
+            errors,
+        ),
+    ))
+}
+
 #[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
 pub enum SetParsingError {
     #[serde(with = "ErrorKindSerdeDeref")]
@@ -118,15 +214,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
         templates_map: TemplatesMap,
     ) -> IResult<Span<'a>, Self, LocatedSetParsingError<'a>> {
         let input = buf;
-        let (buf, id) = nom::combinator::map_res(be_u16, |id| {
-            if id != NETFLOW_TEMPLATE_SET_ID
-                && id != NETFLOW_OPTIONS_TEMPLATE_SET_ID
-                && id < DATA_SET_MIN_ID
-            {
-                Err(SetParsingError::InvalidSetId(id))
-            } else {
-                Ok(id)
-            }
+        let (buf, set_id) = nom::combinator::map_res(be_u16, |id| {
+            SetId::try_from(id).map_err(|_| SetParsingError::InvalidSetId(id))
         })(buf)?;
         let (buf, length) = nom::combinator::map_res(be_u16, |length| {
             if length < 4 {
@@ -136,13 +225,13 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
             }
         })(buf)?;
         let (reminder, mut buf) = nom::bytes::complete::take(length - 4)(buf)?;
-        let set = match id {
-            NETFLOW_TEMPLATE_SET_ID => {
+        let set = match set_id {
+            SetId::Template => {
                 let (_buf, templates) =
                     parse_till_empty_into_with_one_input_located(buf, templates_map)?;
                 Set::Template(templates)
             }
-            NETFLOW_OPTIONS_TEMPLATE_SET_ID => {
+            SetId::OptionsTemplate => {
                 let mut option_templates = vec![];
                 // THE RFC is not super clear about padding length allowed in the Options
                 // Template set. Like Wireshark implementation, we assume anything
@@ -157,8 +246,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
                 check_padding_value(buf)?;
                 Set::OptionsTemplate(option_templates)
             }
-            // We don't need to check for valid Set ID again, since we already checked
-            id => {
+            SetId::Data(data_set_id) => {
+                let id = data_set_id.id();
                 // Temp variable to keep the borrowed value from RC
                 let binding = templates_map.as_ref().borrow();
                 let template = if let Some(fields) = binding.get(&id) {
@@ -187,9 +276,8 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedSetParsingError<'a>> f
                 }
                 // buf could be a non zero value for padding
                 check_padding_value(buf)?;
-                // We can safely unwrap DataSetId here since we already checked the range
                 Set::Data {
-                    id: DataSetId::new(id).unwrap(),
+                    id: data_set_id,
                     records,
                 }
             }
@@ -237,7 +325,7 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
         let (buf, template_id) = be_u16(buf)?;
         // from RFC7011: Each Template Record is given a unique Template ID in the range
         // 256 to 65535.
-        if template_id < 256 {
+        if template_id < DATA_SET_MIN_ID {
             return Err(nom::Err::Error(
                 LocatedOptionsTemplateRecordParsingError::new(
                     input,
@@ -261,10 +349,11 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedOptionsTemplateRecordP
         for a in &options_fields {
             fields.push(a.clone());
         }
-        {
-            let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((scope_fields.clone(), fields.clone())));
-        }
+        templates_map.borrow_mut().insert(
+            template_id,
+            TemplateKind::OptionsTemplate,
+            Rc::new((scope_fields.clone(), fields.clone())),
+        );
         Ok((
             buf,
             OptionsTemplateRecord::new(template_id, scope_fields, fields),
@@ -293,7 +382,7 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedTemplateRecordParsingE
         let (buf, template_id) = be_u16(buf)?;
         // from RFC7011: Each Template Record is given a unique Template ID in the range
         // 256 to 65535.
-        if template_id < 256 {
+        if template_id < DATA_SET_MIN_ID {
             return Err(nom::Err::Error(LocatedTemplateRecordParsingError::new(
                 input,
                 TemplateRecordParsingError::InvalidTemplateId(template_id),
@@ -306,10 +395,11 @@ impl<'a> ReadablePduWithOneInput<'a, TemplatesMap, LocatedTemplateRecordParsingE
             fields.push(field);
             buf = t;
         }
-        {
-            let mut map: RefMut<'_, _> = templates_map.borrow_mut();
-            map.insert(template_id, Rc::new((vec![], fields.clone())));
-        }
+        templates_map.borrow_mut().insert(
+            template_id,
+            TemplateKind::Template,
+            Rc::new((vec![], fields.clone())),
+        );
         Ok((buf, TemplateRecord::new(template_id, fields)))
     }
 }
@@ -1,3 +1,363 @@
-//! Generated deserialization code
+//! Generated serialization code, along with the serializers of the structured
+//! data types [RFC6313](https://datatracker.ietf.org/doc/html/rfc6313).
+
+use byteorder::NetworkEndian;
+use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
+use netgauze_serde_macros::WritingError;
+use std::io::Write;
+
+use crate::{
+    ie::{BasicList, EnterpriseValue, SubTemplateList, SubTemplateMultiList},
+    ipfix::DataRecord,
+    wire::serializer::{ipfix::DataRecordWritingError, FieldSpecifierWritingError},
+};
 
 include!(concat!(env!("OUT_DIR"), "/ie_ser_generated.rs"));
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum ListWritingError {
+    StdIOError(#[from_std_io_error] String),
+    FieldSpecifierError(#[from] FieldSpecifierWritingError),
+    FieldError(Box<FieldWritingError>),
+    DataRecordError(Box<DataRecordWritingError>),
+}
+
+impl From<FieldWritingError> for ListWritingError {
+    fn from(value: FieldWritingError) -> Self {
+        ListWritingError::FieldError(Box::new(value))
+    }
+}
+
+impl From<DataRecordWritingError> for ListWritingError {
+    fn from(value: DataRecordWritingError) -> Self {
+        ListWritingError::DataRecordError(Box::new(value))
+    }
+}
+
+/// Length of the prefix of a variable-length encoded IE, one octet for
+/// lengths less than 255, otherwise 255 followed by two octets length
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-7)
+#[inline]
+pub fn variable_length_prefix_len(len: usize) -> usize {
+    if len < u8::MAX as usize {
+        1
+    } else {
+        3
+    }
+}
+
+/// Write the prefix of a variable-length encoded IE
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-7)
+#[inline]
+pub fn write_variable_length<T: Write>(writer: &mut T, len: usize) -> std::io::Result<()> {
+    if len < u8::MAX as usize {
+        writer.write_u8(len as u8)
+    } else {
+        let len = u16::try_from(len).map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "variable length IE is longer than 65535 octets",
+            )
+        })?;
+        writer.write_u8(u8::MAX)?;
+        writer.write_u16::<NetworkEndian>(len)
+    }
+}
+
+/// Errors of the reduced-size encoding
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-6.2)
+#[derive(Copy, Eq, PartialEq, Clone, Debug)]
+pub enum ReducedSizeWritingError {
+    /// The length is zero or longer than the natural length of the type
+    InvalidLength(u16),
+    /// The value can't be represented in the given length
+    ValueDoesNotFit(u16),
+}
+
+/// The least significant octets of an unsigned number encoded in `length`
+/// octets, the dropped octets must be zeros
+#[inline]
+pub fn reduced_size_unsigned(
+    be_bytes: &[u8],
+    length: u16,
+) -> Result<&[u8], ReducedSizeWritingError> {
+    let len = length as usize;
+    if len == 0 || len > be_bytes.len() {
+        return Err(ReducedSizeWritingError::InvalidLength(length));
+    }
+    let (dropped, kept) = be_bytes.split_at(be_bytes.len() - len);
+    if dropped.iter().any(|byte| *byte != 0) {
+        return Err(ReducedSizeWritingError::ValueDoesNotFit(length));
+    }
+    Ok(kept)
+}
+
+/// The least significant octets of a two's complement signed number encoded in
+/// `length` octets, the dropped octets must be the sign extension of the kept
+/// ones
+#[inline]
+pub fn reduced_size_signed(be_bytes: &[u8], length: u16) -> Result<&[u8], ReducedSizeWritingError> {
+    let len = length as usize;
+    if len == 0 || len > be_bytes.len() {
+        return Err(ReducedSizeWritingError::InvalidLength(length));
+    }
+    let (dropped, kept) = be_bytes.split_at(be_bytes.len() - len);
+    let sign_extension = if kept[0] & 0x80 == 0 { 0x00 } else { 0xff };
+    if dropped.iter().any(|byte| *byte != sign_extension) {
+        return Err(ReducedSizeWritingError::ValueDoesNotFit(length));
+    }
+    Ok(kept)
+}
+
+/// A float64 can be encoded as float32 only when no precision is lost
+#[inline]
+pub fn reduced_size_float64(
+    value: f64,
+    length: u16,
+) -> Result<Option<f32>, ReducedSizeWritingError> {
+    match length {
+        8 => Ok(None),
+        4 if value.is_nan() || (value as f32) as f64 == value => Ok(Some(value as f32)),
+        4 => Err(ReducedSizeWritingError::ValueDoesNotFit(length)),
+        _ => Err(ReducedSizeWritingError::InvalidLength(length)),
+    }
+}
+
+/// Total length of a structured data list, including the variable length
+/// prefix when the list is not encoded with a fixed length
+#[inline]
+fn list_len(content_len: usize, length: Option<u16>) -> usize {
+    match length {
+        Some(u16::MAX) | None => content_len + variable_length_prefix_len(content_len),
+        Some(_) => content_len,
+    }
+}
+
+#[inline]
+fn write_list_length<T: Write>(
+    writer: &mut T,
+    content_len: usize,
+    length: Option<u16>,
+) -> Result<(), ListWritingError> {
+    match length {
+        Some(u16::MAX) | None => write_variable_length(writer, content_len)?,
+        Some(_) => {}
+    }
+    Ok(())
+}
+
+#[inline]
+fn records_len(records: &[DataRecord]) -> usize {
+    records.iter().map(|record| record.len(None)).sum()
+}
+
+impl BasicList {
+    fn content_len(&self) -> usize {
+        let element_length = Some(self.field_specifier().length());
+        1 + self.field_specifier().len()
+            + self
+                .elements()
+                .iter()
+                .map(|element| element.len(element_length))
+                .sum::<usize>()
+    }
+}
+
+impl WritablePduWithOneInput<Option<u16>, ListWritingError> for BasicList {
+    /// 1-octet semantic
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self, length: Option<u16>) -> usize {
+        list_len(self.content_len(), length)
+    }
+
+    fn write<T: Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), ListWritingError> {
+        write_list_length(writer, self.content_len(), length)?;
+        writer.write_u8(self.semantic() as u8)?;
+        self.field_specifier().write(writer)?;
+        let element_length = Some(self.field_specifier().length());
+        for element in self.elements() {
+            element.write(writer, element_length)?;
+        }
+        Ok(())
+    }
+}
+
+impl WritablePduWithOneInput<Option<u16>, ListWritingError> for SubTemplateList {
+    /// 1-octet semantic and 2-octets template id
+    const BASE_LENGTH: usize = 3;
+
+    fn len(&self, length: Option<u16>) -> usize {
+        list_len(Self::BASE_LENGTH + records_len(self.records()), length)
+    }
+
+    fn write<T: Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), ListWritingError> {
+        write_list_length(
+            writer,
+            Self::BASE_LENGTH + records_len(self.records()),
+            length,
+        )?;
+        writer.write_u8(self.semantic() as u8)?;
+        writer.write_u16::<NetworkEndian>(self.template_id())?;
+        for record in self.records() {
+            record.write(writer, None)?;
+        }
+        Ok(())
+    }
+}
+
+impl SubTemplateMultiList {
+    /// Each entry is prefixed with 2-octets template id and 2-octets length
+    fn content_len(&self) -> usize {
+        1 + self
+            .entries()
+            .iter()
+            .map(|entry| 4 + records_len(entry.records()))
+            .sum::<usize>()
+    }
+}
+
+impl WritablePduWithOneInput<Option<u16>, ListWritingError> for SubTemplateMultiList {
+    /// 1-octet semantic
+    const BASE_LENGTH: usize = 1;
+
+    fn len(&self, length: Option<u16>) -> usize {
+        list_len(self.content_len(), length)
+    }
+
+    fn write<T: Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), ListWritingError> {
+        write_list_length(writer, self.content_len(), length)?;
+        writer.write_u8(self.semantic() as u8)?;
+        for entry in self.entries() {
+            writer.write_u16::<NetworkEndian>(entry.template_id())?;
+            writer.write_u16::<NetworkEndian>(4 + records_len(entry.records()) as u16)?;
+            for record in entry.records() {
+                record.write(writer, None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum EnterpriseValueWritingError {
+    StdIOError(#[from_std_io_error] String),
+    ReducedSizeError(#[from] ReducedSizeWritingError),
+}
+
+/// Write variable length octets or fixed length octets padded with zeros
+#[inline]
+fn write_octets<T: Write>(
+    writer: &mut T,
+    value: &[u8],
+    length: Option<u16>,
+) -> std::io::Result<()> {
+    match length {
+        Some(u16::MAX) | None => {
+            write_variable_length(writer, value.len())?;
+            writer.write_all(value)
+        }
+        Some(len) => {
+            writer.write_all(value)?;
+            for _ in value.len()..(len as usize) {
+                writer.write_u8(0)?
+            }
+            Ok(())
+        }
+    }
+}
+
+impl WritablePduWithOneInput<Option<u16>, EnterpriseValueWritingError> for EnterpriseValue {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self, length: Option<u16>) -> usize {
+        let fixed_len = |natural_len: usize| match length {
+            Some(len) if len != u16::MAX => len as usize,
+            _ => natural_len,
+        };
+        match self {
+            Self::OctetArray(value) => match length {
+                Some(u16::MAX) | None => value.len() + variable_length_prefix_len(value.len()),
+                Some(len) => len as usize,
+            },
+            Self::String(value) => match length {
+                Some(u16::MAX) | None => value.len() + variable_length_prefix_len(value.len()),
+                Some(len) => len as usize,
+            },
+            Self::Unsigned8(_) | Self::Signed8(_) => fixed_len(1),
+            Self::Unsigned16(_) | Self::Signed16(_) => fixed_len(2),
+            Self::Unsigned32(_) | Self::Signed32(_) => fixed_len(4),
+            Self::Unsigned64(_) | Self::Signed64(_) => fixed_len(8),
+            Self::Float64(_) if length == Some(4) => 4,
+            Self::Float64(_) => 8,
+            Self::Float32(_) => 4,
+            Self::Boolean(_) => 1,
+            Self::MacAddress(_) => 6,
+            Self::DateTimeSeconds(_) => 4,
+            Self::DateTimeMilliseconds(_)
+            | Self::DateTimeMicroseconds(_)
+            | Self::DateTimeNanoseconds(_) => 8,
+            Self::Ipv4Address(_) => 4,
+            Self::Ipv6Address(_) => 16,
+        }
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+        length: Option<u16>,
+    ) -> Result<(), EnterpriseValueWritingError> {
+        let len = self.len(length) as u16;
+        match self {
+            Self::OctetArray(value) => write_octets(writer, value, length)?,
+            Self::String(value) => write_octets(writer, value.as_bytes(), length)?,
+            Self::Unsigned8(value) => {
+                writer.write_all(reduced_size_unsigned(&value.to_be_bytes(), len)?)?
+            }
+            Self::Unsigned16(value) => {
+                writer.write_all(reduced_size_unsigned(&value.to_be_bytes(), len)?)?
+            }
+            Self::Unsigned32(value) => {
+                writer.write_all(reduced_size_unsigned(&value.to_be_bytes(), len)?)?
+            }
+            Self::Unsigned64(value) => {
+                writer.write_all(reduced_size_unsigned(&value.to_be_bytes(), len)?)?
+            }
+            Self::Signed8(value) => {
+                writer.write_all(reduced_size_signed(&value.to_be_bytes(), len)?)?
+            }
+            Self::Signed16(value) => {
+                writer.write_all(reduced_size_signed(&value.to_be_bytes(), len)?)?
+            }
+            Self::Signed32(value) => {
+                writer.write_all(reduced_size_signed(&value.to_be_bytes(), len)?)?
+            }
+            Self::Signed64(value) => {
+                writer.write_all(reduced_size_signed(&value.to_be_bytes(), len)?)?
+            }
+            Self::Float32(value) => writer.write_f32::<NetworkEndian>(*value)?,
+            Self::Float64(value) => match reduced_size_float64(*value, len)? {
+                Some(value) => writer.write_f32::<NetworkEndian>(value)?,
+                None => writer.write_f64::<NetworkEndian>(*value)?,
+            },
+            Self::Boolean(value) => writer.write_u8((*value).into())?,
+            Self::MacAddress(value) => writer.write_all(value)?,
+            Self::DateTimeSeconds(value) => {
+                writer.write_u32::<NetworkEndian>(value.timestamp() as u32)?
+            }
+            Self::DateTimeMilliseconds(value) => {
+                writer.write_u64::<NetworkEndian>(value.timestamp_millis() as u64)?
+            }
+            Self::DateTimeMicroseconds(value) | Self::DateTimeNanoseconds(value) => {
+                writer.write_u32::<NetworkEndian>(value.timestamp() as u32)?;
+                // Convert nanoseconds to 1/2^32 of a second
+                let fraction =
+                    (value.timestamp_subsec_nanos() as u64 * u32::MAX as u64) / 1_000_000_000;
+                writer.write_u32::<NetworkEndian>(fraction as u32)?;
+            }
+            Self::Ipv4Address(value) => writer.write_all(&value.octets())?,
+            Self::Ipv6Address(value) => writer.write_all(&value.octets())?,
+        }
+        Ok(())
+    }
+}
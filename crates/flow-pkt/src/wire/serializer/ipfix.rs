@@ -14,32 +14,96 @@
 // limitations under the License.
 
 use byteorder::{NetworkEndian, WriteBytesExt};
-use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
+use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput, WritablePduWithTwoInputs};
 use netgauze_serde_macros::WritingError;
-use std::{io::Write, rc::Rc};
+use std::{borrow::Cow, collections::HashSet, io::Write, rc::Rc};
 
 use crate::{
     ipfix::*,
-    wire::serializer::{ie::FieldWritingError, FieldSpecifierWritingError},
+    wire::serializer::{ie::FieldWritingError, FieldSpecifierWritingError, SerializationOptions},
 };
 
+/// IPFIX sets are padded by default, see
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-3.3.1)
+const DEFAULT_OPTIONS: SerializationOptions = SerializationOptions::new().with_align_sets(true);
+
+/// Templates are looked up in the observation domain of the packet
+#[inline]
+fn set_templates_scope(templates_map: Option<&TemplatesMap>, observation_domain_id: u32) {
+    if let Some(templates_map) = templates_map {
+        templates_map
+            .borrow_mut()
+            .set_observation_domain_id(observation_domain_id);
+    }
+}
+
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum IpfixPacketWritingError {
     StdIOError(#[from_std_io_error] String),
     SetError(#[from] SetWritingError),
 }
 
-impl WritablePduWithOneInput<Option<TemplatesMap>, IpfixPacketWritingError> for IpfixPacket {
+/// The sets of the packet, with the templates of the data sets added before
+/// them if requested by the options. The templates defined anywhere in the
+/// packet are not added again.
+fn sets_to_write<'a>(
+    sets: &'a [Set],
+    templates_map: Option<&TemplatesMap>,
+    options: SerializationOptions,
+) -> Cow<'a, [Set]> {
+    let templates_map = match templates_map {
+        Some(templates_map) if options.inline_templates() => templates_map,
+        _ => return Cow::Borrowed(sets),
+    };
+    let templates = templates_map.borrow();
+    let mut defined = sets
+        .iter()
+        .flat_map(|set| match set {
+            Set::Template(records) => records.iter().map(|x| x.id()).collect(),
+            Set::OptionsTemplate(records) => records.iter().map(|x| x.id()).collect(),
+            Set::Data { .. } => vec![],
+        })
+        .collect::<HashSet<_>>();
+    let mut ret = Vec::with_capacity(sets.len());
+    for set in sets {
+        if let Set::Data { id, .. } = set {
+            if let Some(template) = templates.get(&id.id()).filter(|_| defined.insert(id.id())) {
+                let (scope_fields, fields) = template.as_ref().clone();
+                ret.push(if scope_fields.is_empty() {
+                    Set::Template(vec![TemplateRecord::new(id.id(), fields)])
+                } else {
+                    Set::OptionsTemplate(vec![OptionsTemplateRecord::new(
+                        id.id(),
+                        scope_fields,
+                        fields,
+                    )])
+                });
+            }
+        }
+        ret.push(set.clone());
+    }
+    Cow::Owned(ret)
+}
+
+impl WritablePduWithTwoInputs<Option<TemplatesMap>, SerializationOptions, IpfixPacketWritingError>
+    for IpfixPacket
+{
     /// 2-octets version, 2-octets length, 4-octets * 3 (export time, seq no,
     /// observation domain id)
     const BASE_LENGTH: usize = 16;
 
-    fn len(&self, templates_map: Option<TemplatesMap>) -> usize {
-        Self::BASE_LENGTH
-            + self
-                .sets()
+    fn len(&self, templates_map: Option<TemplatesMap>, options: SerializationOptions) -> usize {
+        set_templates_scope(templates_map.as_ref(), self.observation_domain_id());
+        <Self as WritablePduWithTwoInputs<_, _, _>>::BASE_LENGTH
+            + sets_to_write(self.sets(), templates_map.as_ref(), options)
                 .iter()
-                .map(|x| x.len(templates_map.clone()))
+                .map(|x| {
+                    <Set as WritablePduWithTwoInputs<_, _, _>>::len(
+                        x,
+                        templates_map.clone(),
+                        options,
+                    )
+                })
                 .sum::<usize>()
     }
 
@@ -47,19 +111,61 @@ impl WritablePduWithOneInput<Option<TemplatesMap>, IpfixPacketWritingError> for
         &self,
         writer: &mut T,
         templates_map: Option<TemplatesMap>,
+        options: SerializationOptions,
     ) -> Result<(), IpfixPacketWritingError> {
+        set_templates_scope(templates_map.as_ref(), self.observation_domain_id());
+        let sets = sets_to_write(self.sets(), templates_map.as_ref(), options);
+        let length = <Self as WritablePduWithTwoInputs<_, _, _>>::BASE_LENGTH
+            + sets
+                .iter()
+                .map(|x| {
+                    <Set as WritablePduWithTwoInputs<_, _, _>>::len(
+                        x,
+                        templates_map.clone(),
+                        options,
+                    )
+                })
+                .sum::<usize>();
         writer.write_u16::<NetworkEndian>(self.version())?;
-        writer.write_u16::<NetworkEndian>(self.len(templates_map.clone()) as u16)?;
+        writer.write_u16::<NetworkEndian>(length as u16)?;
         writer.write_u32::<NetworkEndian>(self.export_time().timestamp() as u32)?;
         writer.write_u32::<NetworkEndian>(self.sequence_number())?;
         writer.write_u32::<NetworkEndian>(self.observation_domain_id())?;
-        for set in self.sets() {
-            set.write(writer, templates_map.clone())?;
+        for set in sets.iter() {
+            <Set as WritablePduWithTwoInputs<_, _, _>>::write(
+                set,
+                writer,
+                templates_map.clone(),
+                options,
+            )?;
         }
         Ok(())
     }
 }
 
+/// Sets are padded to 4-octets boundaries, and the templates are not written
+/// inline
+impl WritablePduWithOneInput<Option<TemplatesMap>, IpfixPacketWritingError> for IpfixPacket {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self, templates_map: Option<TemplatesMap>) -> usize {
+        <Self as WritablePduWithTwoInputs<_, _, _>>::len(self, templates_map, DEFAULT_OPTIONS)
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+        templates_map: Option<TemplatesMap>,
+    ) -> Result<(), IpfixPacketWritingError> {
+        <Self as WritablePduWithTwoInputs<_, _, _>>::write(
+            self,
+            writer,
+            templates_map,
+            DEFAULT_OPTIONS,
+        )
+    }
+}
+
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum TemplateRecordWritingError {
     StdIOError(#[from_std_io_error] String),
@@ -100,6 +206,10 @@ impl WritablePdu<OptionsTemplateRecordWritingError> for OptionsTemplateRecord {
     const BASE_LENGTH: usize = 6;
 
     fn len(&self) -> usize {
+        // Options Template Withdrawal Record has no scope fields count
+        if self.is_withdrawal() {
+            return Self::BASE_LENGTH - 2;
+        }
         Self::BASE_LENGTH
             + self
                 .scope_field_specifiers()
@@ -118,6 +228,9 @@ impl WritablePdu<OptionsTemplateRecordWritingError> for OptionsTemplateRecord {
         writer.write_u16::<NetworkEndian>(
             (self.scope_field_specifiers().len() + self.field_specifiers().len()) as u16,
         )?;
+        if self.is_withdrawal() {
+            return Ok(());
+        }
         writer.write_u16::<NetworkEndian>(self.scope_field_specifiers().len() as u16)?;
         for field in self.scope_field_specifiers() {
             field.write(writer)?;
@@ -223,9 +336,10 @@ impl WritablePduWithOneInput<Option<Rc<DecodingTemplate>>, DataRecordWritingErro
 #[inline]
 fn calculate_set_size_with_padding(
     templates_map: Option<TemplatesMap>,
+    options: SerializationOptions,
     set: &Set,
 ) -> (usize, usize) {
-    let length = Set::BASE_LENGTH
+    let length = <Set as WritablePduWithTwoInputs<_, _, _>>::BASE_LENGTH
         + match set {
             Set::Template(records) => records.iter().map(|x| x.len()).sum::<usize>(),
             Set::OptionsTemplate(records) => records.iter().map(|x| x.len()).sum::<usize>(),
@@ -238,7 +352,7 @@ fn calculate_set_size_with_padding(
                     .sum::<usize>()
             }
         };
-    (length, length % 4)
+    (length, options.padding(length))
 }
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
@@ -249,12 +363,35 @@ pub enum SetWritingError {
     OptionsTemplateRecordError(#[from] OptionsTemplateRecordWritingError),
 }
 
+/// Sets are padded to 4-octets boundaries
 impl WritablePduWithOneInput<Option<TemplatesMap>, SetWritingError> for Set {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self, templates_map: Option<TemplatesMap>) -> usize {
+        <Self as WritablePduWithTwoInputs<_, _, _>>::len(self, templates_map, DEFAULT_OPTIONS)
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+        templates_map: Option<TemplatesMap>,
+    ) -> Result<(), SetWritingError> {
+        <Self as WritablePduWithTwoInputs<_, _, _>>::write(
+            self,
+            writer,
+            templates_map,
+            DEFAULT_OPTIONS,
+        )
+    }
+}
+
+/// The templates are never written inline for a single set
+impl WritablePduWithTwoInputs<Option<TemplatesMap>, SerializationOptions, SetWritingError> for Set {
     /// 2-octets set id + 2-octet set length
     const BASE_LENGTH: usize = 4;
 
-    fn len(&self, templates_map: Option<TemplatesMap>) -> usize {
-        let (length, padding) = calculate_set_size_with_padding(templates_map, self);
+    fn len(&self, templates_map: Option<TemplatesMap>, options: SerializationOptions) -> usize {
+        let (length, padding) = calculate_set_size_with_padding(templates_map, options, self);
         length + padding
     }
 
@@ -262,8 +399,10 @@ impl WritablePduWithOneInput<Option<TemplatesMap>, SetWritingError> for Set {
         &self,
         writer: &mut T,
         templates_map: Option<TemplatesMap>,
+        options: SerializationOptions,
     ) -> Result<(), SetWritingError> {
-        let (length, padding) = calculate_set_size_with_padding(templates_map.clone(), self);
+        let (length, padding) =
+            calculate_set_size_with_padding(templates_map.clone(), options, self);
         let length = (length + padding) as u16;
         match self {
             Self::Template(records) => {
@@ -21,8 +21,59 @@ use crate::{ie::InformationElementTemplate, FieldSpecifier};
 use byteorder::{NetworkEndian, WriteBytesExt};
 use netgauze_parse_utils::WritablePdu;
 use netgauze_serde_macros::WritingError;
+use serde::{Deserialize, Serialize};
 use std::io::Write;
 
+/// Options to lay out the IPFIX and NetFlow V9 packets on the wire, e.g., to
+/// match the output of a specific exporter bit-for-bit
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct SerializationOptions {
+    align_sets: bool,
+    inline_templates: bool,
+}
+
+impl SerializationOptions {
+    /// No padding and no inline templates
+    pub const fn new() -> Self {
+        Self {
+            align_sets: false,
+            inline_templates: false,
+        }
+    }
+
+    /// Pad the sets with zeros so the next set starts at a 4-octet boundary
+    pub const fn with_align_sets(mut self, align_sets: bool) -> Self {
+        self.align_sets = align_sets;
+        self
+    }
+
+    /// Write the template of every data set before the data set, unless the
+    /// template is already defined earlier in the same packet. Otherwise, the
+    /// templates are assumed to be known by the collector already.
+    pub const fn with_inline_templates(mut self, inline_templates: bool) -> Self {
+        self.inline_templates = inline_templates;
+        self
+    }
+
+    pub const fn align_sets(&self) -> bool {
+        self.align_sets
+    }
+
+    pub const fn inline_templates(&self) -> bool {
+        self.inline_templates
+    }
+
+    /// Number of padding octets needed after a set of the given length
+    #[inline]
+    pub(crate) const fn padding(&self, length: usize) -> usize {
+        if self.align_sets {
+            (4 - length % 4) % 4
+        } else {
+            0
+        }
+    }
+}
+
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum FlowWritingError {
     StdIOError(#[from_std_io_error] String),
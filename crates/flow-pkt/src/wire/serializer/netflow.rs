@@ -18,13 +18,13 @@ use crate::{
     netflow::*,
     wire::{
         deserializer::netflow::NETFLOW_V9_HEADER_LENGTH,
-        serializer::{ie::FieldWritingError, FieldSpecifierWritingError},
+        serializer::{ie::FieldWritingError, FieldSpecifierWritingError, SerializationOptions},
     },
 };
 use byteorder::{NetworkEndian, WriteBytesExt};
 use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput, WritablePduWithTwoInputs};
 use netgauze_serde_macros::WritingError;
-use std::{io::Write, rc::Rc};
+use std::{borrow::Cow, collections::HashSet, io::Write, rc::Rc};
 
 #[derive(WritingError, Eq, PartialEq, Clone, Debug)]
 pub enum NetFlowV9WritingError {
@@ -32,21 +32,73 @@ pub enum NetFlowV9WritingError {
     SetError(#[from] SetWritingError),
 }
 
+/// Templates are looked up in the source id of the packet
+#[inline]
+fn set_templates_scope(templates_map: Option<&TemplatesMap>, source_id: u32) {
+    if let Some(templates_map) = templates_map {
+        templates_map
+            .borrow_mut()
+            .set_observation_domain_id(source_id);
+    }
+}
+
+/// The sets of the packet, with the templates of the data sets added before
+/// them if requested by the options. The templates defined anywhere in the
+/// packet are not added again.
+fn sets_to_write<'a>(
+    sets: &'a [Set],
+    templates_map: Option<&TemplatesMap>,
+    options: SerializationOptions,
+) -> Cow<'a, [Set]> {
+    let templates_map = match templates_map {
+        Some(templates_map) if options.inline_templates() => templates_map,
+        _ => return Cow::Borrowed(sets),
+    };
+    let templates = templates_map.borrow();
+    let mut defined = sets
+        .iter()
+        .flat_map(|set| match set {
+            Set::Template(records) => records.iter().map(|x| x.id()).collect(),
+            Set::OptionsTemplate(records) => records.iter().map(|x| x.id()).collect(),
+            Set::Data { .. } => vec![],
+        })
+        .collect::<HashSet<_>>();
+    let mut ret = Vec::with_capacity(sets.len());
+    for set in sets {
+        if let Set::Data { id, .. } = set {
+            if let Some(template) = templates.get(&id.id()).filter(|_| defined.insert(id.id())) {
+                let (scope_fields, fields) = template.as_ref().clone();
+                ret.push(if scope_fields.is_empty() {
+                    Set::Template(vec![TemplateRecord::new(id.id(), fields)])
+                } else {
+                    Set::OptionsTemplate(vec![OptionsTemplateRecord::new(
+                        id.id(),
+                        scope_fields,
+                        fields,
+                    )])
+                });
+            }
+        }
+        ret.push(set.clone());
+    }
+    Cow::Owned(ret)
+}
+
 /// [RFC 3954](https://www.rfc-editor.org/rfc/rfc3954) defines padding to 4-bytes start as
-/// SHOULD (optional). The second option is a boolean to indicate if padding
-/// should be included in the output.
-impl WritablePduWithTwoInputs<Option<TemplatesMap>, bool, NetFlowV9WritingError>
+/// SHOULD (optional). The [`SerializationOptions`] control if padding should
+/// be included in the output and if the templates are written inline.
+impl WritablePduWithTwoInputs<Option<TemplatesMap>, SerializationOptions, NetFlowV9WritingError>
     for NetFlowV9Packet
 {
     /// 2-octets version, 2-octets count, 4-octets * 4 for meta data
     const BASE_LENGTH: usize = NETFLOW_V9_HEADER_LENGTH as usize;
 
-    fn len(&self, templates_map: Option<TemplatesMap>, align_to_4_bytes: bool) -> usize {
+    fn len(&self, templates_map: Option<TemplatesMap>, options: SerializationOptions) -> usize {
+        set_templates_scope(templates_map.as_ref(), self.source_id());
         <Self as WritablePduWithTwoInputs<_, _, _>>::BASE_LENGTH
-            + self
-                .sets()
+            + sets_to_write(self.sets(), templates_map.as_ref(), options)
                 .iter()
-                .map(|x| x.len(templates_map.clone(), align_to_4_bytes))
+                .map(|x| x.len(templates_map.clone(), options))
                 .sum::<usize>()
     }
 
@@ -54,10 +106,11 @@ impl WritablePduWithTwoInputs<Option<TemplatesMap>, bool, NetFlowV9WritingError>
         &self,
         writer: &mut T,
         templates_map: Option<TemplatesMap>,
-        align_to_4_bytes: bool,
+        options: SerializationOptions,
     ) -> Result<(), NetFlowV9WritingError> {
-        let count = self
-            .sets()
+        set_templates_scope(templates_map.as_ref(), self.source_id());
+        let sets = sets_to_write(self.sets(), templates_map.as_ref(), options);
+        let count = sets
             .iter()
             .map(|x| match &x {
                 Set::Data { id: _, records } => records.len(),
@@ -71,8 +124,8 @@ impl WritablePduWithTwoInputs<Option<TemplatesMap>, bool, NetFlowV9WritingError>
         writer.write_u32::<NetworkEndian>(self.unix_time().timestamp() as u32)?;
         writer.write_u32::<NetworkEndian>(self.sequence_number())?;
         writer.write_u32::<NetworkEndian>(self.source_id())?;
-        for set in self.sets() {
-            set.write(writer, templates_map.clone(), align_to_4_bytes)?;
+        for set in sets.iter() {
+            set.write(writer, templates_map.clone(), options)?;
         }
         Ok(())
     }
@@ -82,7 +135,11 @@ impl WritablePduWithOneInput<Option<TemplatesMap>, NetFlowV9WritingError> for Ne
     const BASE_LENGTH: usize = 0;
 
     fn len(&self, templates_map: Option<TemplatesMap>) -> usize {
-        <Self as WritablePduWithTwoInputs<_, _, _>>::len(self, templates_map, false)
+        <Self as WritablePduWithTwoInputs<_, _, _>>::len(
+            self,
+            templates_map,
+            SerializationOptions::new(),
+        )
     }
 
     fn write<T: Write>(
@@ -90,7 +147,12 @@ impl WritablePduWithOneInput<Option<TemplatesMap>, NetFlowV9WritingError> for Ne
         writer: &mut T,
         templates_map: Option<TemplatesMap>,
     ) -> Result<(), NetFlowV9WritingError> {
-        <Self as WritablePduWithTwoInputs<_, _, _>>::write(self, writer, templates_map, false)
+        <Self as WritablePduWithTwoInputs<_, _, _>>::write(
+            self,
+            writer,
+            templates_map,
+            SerializationOptions::new(),
+        )
     }
 }
 
@@ -106,7 +168,7 @@ pub enum SetWritingError {
 #[inline]
 fn calculate_set_size_with_padding(
     templates_map: Option<TemplatesMap>,
-    align_to_4_bytes: bool,
+    options: SerializationOptions,
     set: &Set,
 ) -> (usize, usize) {
     let length = Set::BASE_LENGTH
@@ -122,20 +184,15 @@ fn calculate_set_size_with_padding(
                     .sum::<usize>()
             }
         };
-    if align_to_4_bytes {
-        (length, length % 4)
-    } else {
-        (length, 0)
-    }
+    (length, options.padding(length))
 }
 
-impl WritablePduWithTwoInputs<Option<TemplatesMap>, bool, SetWritingError> for Set {
+impl WritablePduWithTwoInputs<Option<TemplatesMap>, SerializationOptions, SetWritingError> for Set {
     /// 2-octets set id + 2-octet set length
     const BASE_LENGTH: usize = 4;
 
-    fn len(&self, template_map: Option<TemplatesMap>, align_to_4_bytes: bool) -> usize {
-        let (length, padding) =
-            calculate_set_size_with_padding(template_map, align_to_4_bytes, self);
+    fn len(&self, template_map: Option<TemplatesMap>, options: SerializationOptions) -> usize {
+        let (length, padding) = calculate_set_size_with_padding(template_map, options, self);
         length + padding
     }
 
@@ -143,10 +200,10 @@ impl WritablePduWithTwoInputs<Option<TemplatesMap>, bool, SetWritingError> for S
         &self,
         writer: &mut T,
         templates_map: Option<TemplatesMap>,
-        align_to_4_bytes: bool,
+        options: SerializationOptions,
     ) -> Result<(), SetWritingError> {
         let (length, padding) =
-            calculate_set_size_with_padding(templates_map.clone(), align_to_4_bytes, self);
+            calculate_set_size_with_padding(templates_map.clone(), options, self);
         let length = (length + padding) as u16;
         match self {
             Self::Template(records) => {
@@ -14,14 +14,21 @@
 // limitations under the License.
 
 use crate::{
-    ie,
+    ie::{self, InformationElementTemplate},
+    ie_registry::{self, EnterpriseIe, EnterpriseIeRegistrationError},
     ipfix::*,
-    wire::{deserializer::ipfix::*, serializer::ipfix::*},
-    DataSetId, FieldSpecifier,
+    template_cache::{TemplateCache, TemplateChange, TemplateKind},
+    wire::{
+        deserializer::{ie::*, ipfix::*},
+        serializer::{ipfix::*, SerializationOptions},
+    },
+    DataSetId, DataSetIdError, FieldSpecifier,
 };
-use chrono::{TimeZone, Timelike, Utc};
-use netgauze_parse_utils::{test_helpers::*, ReadablePduWithOneInput, Span};
-use std::{cell::RefCell, collections::HashMap, net::Ipv4Addr, rc::Rc};
+use chrono::{DateTime, TimeZone, Timelike, Utc};
+use netgauze_parse_utils::{
+    test_helpers::*, LocatedParsingError, ReadablePduWithOneInput, ReadablePduWithTwoInputs, Span,
+};
+use std::{cell::RefCell, net::Ipv4Addr, rc::Rc};
 
 #[test]
 fn test_ipfix_header() -> Result<(), IpfixPacketWritingError> {
@@ -111,7 +118,7 @@ fn test_ipfix_header() -> Result<(), IpfixPacketWritingError> {
         IpfixPacketParsingError::InvalidLength(0),
     );
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
     assert!(templates_map.borrow().contains_key(&307));
 
@@ -181,7 +188,7 @@ fn test_template_packet() -> Result<(), IpfixPacketWritingError> {
         )])],
     );
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
     assert!(templates_map.borrow().contains_key(&307));
 
@@ -232,7 +239,7 @@ fn test_data_packet() -> Result<(), IpfixPacketWritingError> {
         FieldSpecifier::new(ie::IE::flowEndMilliseconds, 8).unwrap(),
     ];
     let fields = Rc::new((vec![], f.clone()));
-    let templates_map = Rc::new(RefCell::new(HashMap::from([(307, fields)])));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::from_iter([(307, fields)])));
     let good = IpfixPacket::new(
         Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 57).unwrap(),
         3812,
@@ -321,16 +328,93 @@ fn test_options_template_packet() -> Result<(), IpfixPacketWritingError> {
         )])],
     );
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
     test_write_with_one_input(&good, None, &good_wire)?;
     Ok(())
 }
 
+#[test]
+fn test_template_withdrawal() -> Result<(), IpfixPacketWritingError> {
+    let withdraw_one_wire = [
+        0x00, 0x0a, // version
+        0x00, 0x18, // length
+        0x58, 0x3d, 0xe0, 0x57, // timestamp
+        0x00, 0x00, 0x0e, 0xcf, // Seq
+        0x00, 0x00, 0x00, 0x00, // Domain
+        0x00, 0x02, // Set ID
+        0x00, 0x08, // Set length
+        0x01, 0x00, // Template ID
+        0x00, 0x00, // Field count
+    ];
+    let withdraw_all_wire = [
+        0x00, 0x0a, // version
+        0x00, 0x20, // length
+        0x58, 0x3d, 0xe0, 0x58, // timestamp
+        0x00, 0x00, 0x0e, 0xd0, // Seq
+        0x00, 0x00, 0x00, 0x00, // Domain
+        0x00, 0x02, // Set ID
+        0x00, 0x08, // Set length
+        0x00, 0x02, // All Data Templates
+        0x00, 0x00, // Field count
+        0x00, 0x03, // Set ID
+        0x00, 0x08, // Set length
+        0x01, 0x02, // Template ID
+        0x00, 0x00, // Field count
+    ];
+
+    let withdraw_one = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 55).unwrap(),
+        3791,
+        0,
+        vec![Set::Template(vec![TemplateRecord::new(256, vec![])])],
+    );
+    let withdraw_all = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 56).unwrap(),
+        3792,
+        0,
+        vec![
+            Set::Template(vec![TemplateRecord::new(2, vec![])]),
+            Set::OptionsTemplate(vec![OptionsTemplateRecord::new(258, vec![], vec![])]),
+        ],
+    );
+
+    let fields = Rc::new((
+        vec![],
+        vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+    ));
+    let mut templates = TemplateCache::new();
+    templates.set_notifications(true);
+    templates.insert(256, TemplateKind::Template, fields.clone());
+    templates.insert(257, TemplateKind::Template, fields.clone());
+    templates.insert(258, TemplateKind::OptionsTemplate, fields);
+    templates.take_events();
+    let templates_map = Rc::new(RefCell::new(templates));
+
+    test_parsed_completely_with_one_input(&withdraw_one_wire, templates_map.clone(), &withdraw_one);
+    assert!(!templates_map.borrow().contains_key(&256));
+    assert!(templates_map.borrow().contains_key(&257));
+
+    test_parsed_completely_with_one_input(&withdraw_all_wire, templates_map.clone(), &withdraw_all);
+    assert!(templates_map.borrow().is_empty());
+    let withdrawn = templates_map
+        .borrow_mut()
+        .take_events()
+        .iter()
+        .filter(|event| event.change() == TemplateChange::Withdrawn)
+        .map(|event| event.template_id())
+        .collect::<Vec<_>>();
+    assert_eq!(withdrawn, vec![256, 257, 258]);
+
+    test_write_with_one_input(&withdraw_one, None, &withdraw_one_wire)?;
+    test_write_with_one_input(&withdraw_all, None, &withdraw_all_wire)?;
+    Ok(())
+}
+
 #[test]
 #[rustfmt::skip]
 fn test_complex_sequence() -> Result<(), IpfixPacketWritingError> {
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     let pkt1_wire = [
         0x00, 0x0a, 0x02, 0x24, 0x63, 0x4a, 0xe2, 0x9d, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x00,
         0x00, 0x02, 0x00, 0x40, 0x04, 0x00, 0x00, 0x0e, 0x00, 0x08, 0x00, 0x04, 0x00, 0x0c, 0x00, 0x04,
@@ -506,7 +590,7 @@ fn test_example() -> Result<(), IpfixPacketWritingError> {
             )])],
     );
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     //let (_, pkt1) = IpfixPacket::from_wire(Span::new(&good), templates_map.clone()).unwrap();
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
 
@@ -574,7 +658,7 @@ fn test_with_variable_string_length() -> Result<(), IpfixPacketWritingError> {
         }],
     );
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(
         &good_template_wire,
         templates_map.clone(),
@@ -590,3 +674,905 @@ fn test_with_variable_string_length() -> Result<(), IpfixPacketWritingError> {
     test_write_with_one_input(&good_data, Some(templates_map.clone()), &good_data_wire)?;
     Ok(())
 }
+
+#[test]
+fn test_with_long_variable_length() -> Result<(), DataRecordWritingError> {
+    let name = "a".repeat(300);
+    let section = vec![0xab; 255];
+    let good_wire = combine(vec![
+        &[0xff, 0x01, 0x2c], // 255 escape followed by 2-octets length
+        name.as_bytes(),
+        &[0xff, 0x00, 0xff], // the length 255 is also encoded with 3 octets
+        &section,
+        &[0x02, 0xcd, 0xef], // short octet array
+    ]);
+    let template: Rc<DecodingTemplate> = Rc::new((
+        vec![],
+        vec![
+            FieldSpecifier::new(ie::IE::applicationName, u16::MAX).unwrap(),
+            FieldSpecifier::new(ie::IE::ipPayloadPacketSection, u16::MAX).unwrap(),
+            FieldSpecifier::new(ie::IE::dataLinkFrameSection, u16::MAX).unwrap(),
+        ],
+    ));
+    let good = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::applicationName(ie::applicationName(name)),
+            ie::Field::ipPayloadPacketSection(ie::ipPayloadPacketSection(section)),
+            ie::Field::dataLinkFrameSection(ie::dataLinkFrameSection(vec![0xcd, 0xef])),
+        ],
+    );
+
+    test_parsed_completely_with_one_input::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), &good);
+    test_write_with_one_input(&good, Some(template), &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_enterprise_registered_ie() -> Result<(), DataRecordWritingError> {
+    let pen = 64_512;
+    ie_registry::register(EnterpriseIe::new(
+        pen,
+        1,
+        "customerId".to_string(),
+        ie::InformationElementDataType::unsigned32,
+        Some(ie::InformationElementSemantics::identifier),
+    ))
+    .unwrap();
+    ie_registry::register(EnterpriseIe::new(
+        pen,
+        2,
+        "customerName".to_string(),
+        ie::InformationElementDataType::string,
+        None,
+    ))
+    .unwrap();
+    assert_eq!(
+        ie_registry::register(EnterpriseIe::new(
+            0,
+            1,
+            "octetDeltaCount".to_string(),
+            ie::InformationElementDataType::unsigned64,
+            None,
+        )),
+        Err(EnterpriseIeRegistrationError::NotAnEnterpriseIe { pen: 0, id: 1 })
+    );
+
+    let good_wire = [
+        0x01, 0x02, // customerId in two octets
+        0x03, 0x61, 0x62, 0x63, // variable length customerName
+        0xab, 0xcd, // not registered
+    ];
+    let customer_id = ie::IE::try_from((pen, 0x8001)).unwrap();
+    assert_eq!(
+        customer_id.data_type(),
+        ie::InformationElementDataType::unsigned32
+    );
+    assert_eq!(
+        customer_id.semantics(),
+        Some(ie::InformationElementSemantics::identifier)
+    );
+    let template: Rc<DecodingTemplate> = Rc::new((
+        vec![],
+        vec![
+            FieldSpecifier::new(customer_id, 2).unwrap(),
+            FieldSpecifier::new(ie::IE::try_from((pen, 0x8002)).unwrap(), u16::MAX).unwrap(),
+            FieldSpecifier::new(ie::IE::try_from((pen, 0x8003)).unwrap(), 2).unwrap(),
+        ],
+    ));
+    let good = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::Enterprise(ie::EnterpriseField::new(
+                pen,
+                1,
+                ie::EnterpriseValue::Unsigned32(258),
+            )),
+            ie::Field::Enterprise(ie::EnterpriseField::new(
+                pen,
+                2,
+                ie::EnterpriseValue::String("abc".to_string()),
+            )),
+            ie::Field::Unknown(vec![0xab, 0xcd]),
+        ],
+    );
+
+    test_parsed_completely_with_one_input::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), &good);
+    test_write_with_one_input(&good, Some(template), &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_ie_names() {
+    let pen = 64_513;
+    assert_eq!(
+        ie::IE::from_name("octetDeltaCount"),
+        Some(ie::IE::octetDeltaCount)
+    );
+    assert_eq!(
+        ie::IE::octetDeltaCount.name(),
+        Some("octetDeltaCount".to_string())
+    );
+    assert_eq!(
+        ie::IE::octetDeltaCount.semantics(),
+        Some(ie::InformationElementSemantics::deltaCounter)
+    );
+    assert_eq!(
+        ie::IE::octetDeltaCount.units(),
+        Some(ie::InformationElementUnits::octets)
+    );
+    assert_eq!(
+        ie::IE::from_name("nokia:aluInsideServiceId"),
+        Some(ie::IE::Nokia(ie::nokia::IE::aluInsideServiceId))
+    );
+    assert_eq!(
+        ie::IE::Nokia(ie::nokia::IE::aluInsideServiceId).name(),
+        Some("nokia:aluInsideServiceId".to_string())
+    );
+    assert_eq!(ie::IE::from_name("aluInsideServiceId"), None);
+    assert_eq!(ie::IE::from_name("noSuchElement"), None);
+    assert_eq!(ie::IE::Unknown { pen, id: 1 }.name(), None);
+
+    ie_registry::register(
+        EnterpriseIe::new(
+            pen,
+            1,
+            "customerRate".to_string(),
+            ie::InformationElementDataType::unsigned32,
+            Some(ie::InformationElementSemantics::quantity),
+        )
+        .with_units(ie::InformationElementUnits::bits)
+        .with_value_range(0..1_000_001),
+    )
+    .unwrap();
+    let customer_rate = ie::IE::from_name("customerRate").unwrap();
+    assert_eq!(customer_rate, ie::IE::Unknown { pen, id: 1 });
+    assert_eq!(customer_rate.name(), Some("customerRate".to_string()));
+    assert_eq!(
+        customer_rate.units(),
+        Some(ie::InformationElementUnits::bits)
+    );
+    assert_eq!(customer_rate.value_range(), Some(0..1_000_001));
+}
+
+#[test]
+fn test_named_fields_json() {
+    let pen = 64_514;
+    ie_registry::register(EnterpriseIe::new(
+        pen,
+        1,
+        "customerName".to_string(),
+        ie::InformationElementDataType::string,
+        None,
+    ))
+    .unwrap();
+    let record = DataRecord::new(
+        vec![ie::Field::ingressInterface(ie::ingressInterface(3))],
+        vec![
+            ie::Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::new(10, 0, 0, 1))),
+            ie::Field::octetDeltaCount(ie::octetDeltaCount(1500)),
+            ie::Field::flowStartMilliseconds(ie::flowStartMilliseconds(
+                Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap(),
+            )),
+            ie::Field::Nokia(ie::nokia::Field::aluInsideServiceId(
+                ie::nokia::aluInsideServiceId(7),
+            )),
+            ie::Field::Enterprise(ie::EnterpriseField::new(
+                pen,
+                1,
+                ie::EnterpriseValue::String("acme".to_string()),
+            )),
+            ie::Field::Enterprise(ie::EnterpriseField::new(
+                pen,
+                2,
+                ie::EnterpriseValue::Unsigned16(5),
+            )),
+            ie::Field::Unknown(vec![0xab, 0xcd]),
+        ],
+    );
+    assert_eq!(
+        serde_json::to_value(record.named_fields()).unwrap(),
+        serde_json::json!({
+            "ingressInterface": 3,
+            "sourceIPv4Address": "10.0.0.1",
+            "octetDeltaCount": 1500,
+            "flowStartMilliseconds": "2024-01-01T12:00:00Z",
+            "nokia:aluInsideServiceId": 7,
+            "customerName": "acme",
+            "64514:2": 5,
+            "unknown_7": [0xab, 0xcd],
+        })
+    );
+}
+
+#[test]
+fn test_basic_list() -> Result<(), crate::wire::serializer::ie::FieldWritingError> {
+    let good_wire = [
+        0x0d, // variable length
+        0x03, // semantic allOf
+        0x01, 0xe3, 0x00, 0x04, // bgpCommunity field specifier
+        0xfd, 0xe8, 0x00, 0x01, // 65000:1
+        0xfd, 0xe8, 0x00, 0x02, // 65000:2
+    ];
+    let bad_semantic_wire = [0x05, 0x07, 0x01, 0xe3, 0x00, 0x04];
+
+    let good = ie::Field::bgpSourceCommunityList(ie::bgpSourceCommunityList(ie::BasicList::new(
+        ie::ListSemantic::allOf,
+        FieldSpecifier::new(ie::IE::bgpCommunity, 4).unwrap(),
+        vec![
+            ie::Field::bgpCommunity(ie::bgpCommunity(0xfde80001)),
+            ie::Field::bgpCommunity(ie::bgpCommunity(0xfde80002)),
+        ],
+    )));
+
+    test_parsed_completely_with_three_inputs::<
+        ie::Field,
+        &ie::IE,
+        u16,
+        Option<&TemplatesMap>,
+        LocatedFieldParsingError<'_>,
+    >(
+        &good_wire,
+        &ie::IE::bgpSourceCommunityList,
+        u16::MAX,
+        None,
+        &good,
+    );
+    let parsed =
+        <ie::Field as ReadablePduWithTwoInputs<_, _, LocatedFieldParsingError<'_>>>::from_wire(
+            Span::new(&bad_semantic_wire),
+            &ie::IE::bgpSourceCommunityList,
+            u16::MAX,
+        );
+    assert_eq!(
+        parsed.map_err(|err| match err {
+            nom::Err::Error(err) => err.error().clone(),
+            err => panic!("unexpected error {err:?}"),
+        }),
+        Err(FieldParsingError::bgpSourceCommunityListError(
+            bgpSourceCommunityListParsingError::ListError(ListParsingError::UndefinedSemantic(7))
+        ))
+    );
+    test_write_with_one_input(&good, Some(u16::MAX), &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_sub_template_list() -> Result<(), DataRecordWritingError> {
+    let good_wire = [
+        0x13, // variable length
+        0x03, // semantic allOf
+        0x01, 0x2c, // template id 300
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, // first record
+        0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x04, // second record
+    ];
+    let template: Rc<DecodingTemplate> = Rc::new((
+        vec![],
+        vec![FieldSpecifier::new(ie::IE::subTemplateList, u16::MAX).unwrap()],
+    ));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    templates_map.borrow_mut().insert(
+        300,
+        TemplateKind::Template,
+        Rc::new((
+            vec![],
+            vec![
+                FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap(),
+                FieldSpecifier::new(ie::IE::egressInterface, 4).unwrap(),
+            ],
+        )),
+    );
+    let good = DataRecord::new(
+        vec![],
+        vec![ie::Field::subTemplateList(ie::subTemplateList(
+            ie::SubTemplateList::new(
+                ie::ListSemantic::allOf,
+                300,
+                vec![
+                    DataRecord::new(
+                        vec![],
+                        vec![
+                            ie::Field::ingressInterface(ie::ingressInterface(1)),
+                            ie::Field::egressInterface(ie::egressInterface(2)),
+                        ],
+                    ),
+                    DataRecord::new(
+                        vec![],
+                        vec![
+                            ie::Field::ingressInterface(ie::ingressInterface(3)),
+                            ie::Field::egressInterface(ie::egressInterface(4)),
+                        ],
+                    ),
+                ],
+            ),
+        ))],
+    );
+    let no_template = LocatedDataRecordParsingError::new(
+        unsafe { Span::new_from_raw_offset(2, &good_wire[2..]) },
+        DataRecordParsingError::FieldError(FieldParsingError::subTemplateListError(
+            subTemplateListParsingError::ListError(ListParsingError::NoTemplateDefinedFor(300)),
+        )),
+    );
+
+    test_parsed_completely_with_two_inputs::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        Option<&TemplatesMap>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), Some(&templates_map), &good);
+    test_parse_error_with_one_input::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), &no_template);
+    test_write_with_one_input(&good, Some(template), &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_sub_template_multi_list() -> Result<(), DataRecordWritingError> {
+    let good_wire = [
+        0x16, // variable length
+        0x04, // semantic ordered
+        0x01, 0x2c, 0x00, 0x0c, // template id 300 and length
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, // template 300 record
+        0x01, 0x2d, 0x00, 0x09, // template id 301 and length
+        0x04, 0x65, 0x74, 0x68, 0x30, // template 301 record
+    ];
+    let template: Rc<DecodingTemplate> = Rc::new((
+        vec![],
+        vec![FieldSpecifier::new(ie::IE::subTemplateMultiList, u16::MAX).unwrap()],
+    ));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    templates_map.borrow_mut().insert(
+        300,
+        TemplateKind::Template,
+        Rc::new((
+            vec![],
+            vec![
+                FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap(),
+                FieldSpecifier::new(ie::IE::egressInterface, 4).unwrap(),
+            ],
+        )),
+    );
+    templates_map.borrow_mut().insert(
+        301,
+        TemplateKind::Template,
+        Rc::new((
+            vec![],
+            vec![FieldSpecifier::new(ie::IE::interfaceName, u16::MAX).unwrap()],
+        )),
+    );
+    let good = DataRecord::new(
+        vec![],
+        vec![ie::Field::subTemplateMultiList(ie::subTemplateMultiList(
+            ie::SubTemplateMultiList::new(
+                ie::ListSemantic::ordered,
+                vec![
+                    ie::SubTemplateMultiListEntry::new(
+                        300,
+                        vec![DataRecord::new(
+                            vec![],
+                            vec![
+                                ie::Field::ingressInterface(ie::ingressInterface(1)),
+                                ie::Field::egressInterface(ie::egressInterface(2)),
+                            ],
+                        )],
+                    ),
+                    ie::SubTemplateMultiListEntry::new(
+                        301,
+                        vec![DataRecord::new(
+                            vec![],
+                            vec![ie::Field::interfaceName(ie::interfaceName(
+                                "eth0".to_string(),
+                            ))],
+                        )],
+                    ),
+                ],
+            ),
+        ))],
+    );
+
+    test_parsed_completely_with_two_inputs::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        Option<&TemplatesMap>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), Some(&templates_map), &good);
+    test_write_with_one_input(&good, Some(template), &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_reduced_size_encoding() -> Result<(), DataRecordWritingError> {
+    use crate::wire::serializer::ie as ie_ser;
+    use netgauze_parse_utils::WritablePduWithOneInput;
+
+    let good_wire = [
+        0x01, 0x2c, // octetDeltaCount on 2 octets
+        0xff, 0x85, // mibObjectValueInteger on 2 octets
+        0x3f, 0x00, 0x00, 0x00, // samplingProbability on 4 octets
+    ];
+    let template = Rc::new((
+        vec![],
+        vec![
+            FieldSpecifier::new(ie::IE::octetDeltaCount, 2).unwrap(),
+            FieldSpecifier::new(ie::IE::mibObjectValueInteger, 2).unwrap(),
+            FieldSpecifier::new(ie::IE::samplingProbability, 4).unwrap(),
+        ],
+    ));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let good = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::octetDeltaCount(ie::octetDeltaCount(300)),
+            ie::Field::mibObjectValueInteger(ie::mibObjectValueInteger(-123)),
+            ie::Field::samplingProbability(ie::samplingProbability(0.5)),
+        ],
+    );
+    test_parsed_completely_with_two_inputs::<
+        DataRecord,
+        Rc<DecodingTemplate>,
+        Option<&TemplatesMap>,
+        LocatedDataRecordParsingError<'_>,
+    >(&good_wire, template.clone(), Some(&templates_map), &good);
+    test_write_with_one_input(&good, Some(template.clone()), &good_wire)?;
+
+    let reduced_size_error = |error| Err(DataRecordWritingError::FieldError(error));
+    let mut buf = vec![];
+    let too_large = DataRecord::new(
+        vec![],
+        vec![ie::Field::octetDeltaCount(ie::octetDeltaCount(70000))],
+    );
+    assert_eq!(
+        too_large.write(&mut buf, Some(template.clone())),
+        reduced_size_error(ie_ser::FieldWritingError::octetDeltaCountError(
+            ie_ser::octetDeltaCountWritingError::ReducedSizeError(
+                ie_ser::ReducedSizeWritingError::ValueDoesNotFit(2)
+            )
+        ))
+    );
+    let too_small = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::octetDeltaCount(ie::octetDeltaCount(1)),
+            ie::Field::mibObjectValueInteger(ie::mibObjectValueInteger(-40000)),
+        ],
+    );
+    assert_eq!(
+        too_small.write(&mut buf, Some(template.clone())),
+        reduced_size_error(ie_ser::FieldWritingError::mibObjectValueIntegerError(
+            ie_ser::mibObjectValueIntegerWritingError::ReducedSizeError(
+                ie_ser::ReducedSizeWritingError::ValueDoesNotFit(2)
+            )
+        ))
+    );
+    let precision_loss = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::octetDeltaCount(ie::octetDeltaCount(1)),
+            ie::Field::mibObjectValueInteger(ie::mibObjectValueInteger(1)),
+            ie::Field::samplingProbability(ie::samplingProbability(0.1)),
+        ],
+    );
+    assert_eq!(
+        precision_loss.write(&mut buf, Some(template)),
+        reduced_size_error(ie_ser::FieldWritingError::samplingProbabilityError(
+            ie_ser::samplingProbabilityWritingError::ReducedSizeError(
+                ie_ser::ReducedSizeWritingError::ValueDoesNotFit(4)
+            )
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_strict_data_set() {
+    let good_wire = [
+        0x01, 0x00, // Set ID
+        0x00, 0x16, // Length
+        0x00, 0x00, 0x00, 0x01, b'e', b't', b'h', b'0', // First record
+        0x00, 0x00, 0x00, 0x02, b'l', b'o', 0x00, 0x00, // Second record
+        0x00, 0x00, // Padding
+    ];
+    let bad_string_wire = [
+        0x01, 0x00, // Set ID
+        0x00, 0x16, // Length
+        0x00, 0x00, 0x00, 0x01, b'e', b't', b'h', b'0', // First record
+        0x00, 0x00, 0x00, 0x02, b'l', b'o', 0x00, b'x', // Second record
+        0x00, 0x00, // Padding
+    ];
+    let bad_padding_wire = [
+        0x01, 0x00, // Set ID
+        0x00, 0x16, // Length
+        0x00, 0x00, 0x00, 0x01, b'e', b't', b'h', b'0', // First record
+        0x00, 0x00, 0x00, 0x02, b'l', b'o', 0x00, 0x00, // Second record
+        0x00, 0x01, // Padding
+    ];
+    let templates_map = Rc::new(RefCell::new(TemplateCache::from_iter([(
+        256,
+        Rc::new((
+            vec![],
+            vec![
+                FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap(),
+                FieldSpecifier::new(ie::IE::interfaceName, 4).unwrap(),
+            ],
+        )),
+    )])));
+    let record = |interface, name: &str| {
+        DataRecord::new(
+            vec![],
+            vec![
+                ie::Field::ingressInterface(ie::ingressInterface(interface)),
+                ie::Field::interfaceName(ie::interfaceName(name.to_string())),
+            ],
+        )
+    };
+    let lenient = Set::Data {
+        id: DataSetId::new(256).unwrap(),
+        records: vec![record(1, "eth0")],
+    };
+    let strict = Set::Data {
+        id: DataSetId::new(256).unwrap(),
+        records: vec![record(1, "eth0"), record(2, "lo")],
+    };
+    let bad_string = LocatedSetParsingError::new(
+        unsafe { Span::new_from_raw_offset(16, &bad_string_wire[16..]) },
+        SetParsingError::DataRecordError(DataRecordParsingError::InvalidStringPadding {
+            index: 1,
+            ie: ie::IE::interfaceName,
+        }),
+    );
+    let bad_padding = LocatedSetParsingError::new(
+        unsafe { Span::new_from_raw_offset(21, &bad_padding_wire[21..]) },
+        SetParsingError::InvalidPaddingValue(1),
+    );
+
+    // Without strict validation, the malformed values are accepted
+    test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &lenient);
+    test_parsed_completely_with_one_input(&bad_string_wire, templates_map.clone(), &lenient);
+    test_parsed_completely_with_one_input(&bad_padding_wire, templates_map.clone(), &lenient);
+
+    templates_map.borrow_mut().set_strict(true);
+    test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &strict);
+    test_parse_error_with_one_input::<Set, TemplatesMap, LocatedSetParsingError<'_>>(
+        &bad_string_wire,
+        templates_map.clone(),
+        &bad_string,
+    );
+    test_parse_error_with_one_input::<Set, TemplatesMap, LocatedSetParsingError<'_>>(
+        &bad_padding_wire,
+        templates_map,
+        &bad_padding,
+    );
+}
+
+#[test]
+fn test_data_record_flow_time() {
+    let export_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let start = export_time - chrono::Duration::seconds(30);
+    let precise_start = start + chrono::Duration::nanoseconds(1500);
+    let system_init_time = export_time - chrono::Duration::hours(1);
+
+    let absolute = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::flowStartSeconds(ie::flowStartSeconds(start)),
+            ie::Field::flowStartNanoseconds(ie::flowStartNanoseconds(precise_start)),
+            ie::Field::flowEndMilliseconds(ie::flowEndMilliseconds(export_time)),
+        ],
+    );
+    assert_eq!(absolute.start_time(export_time), Some(precise_start));
+    assert_eq!(absolute.end_time(export_time), Some(export_time));
+
+    let delta = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::flowStartDeltaMicroseconds(ie::flowStartDeltaMicroseconds(30_000_000)),
+            ie::Field::flowEndDeltaMicroseconds(ie::flowEndDeltaMicroseconds(1_000)),
+        ],
+    );
+    assert_eq!(delta.start_time(export_time), Some(start));
+    assert_eq!(
+        delta.end_time(export_time),
+        Some(export_time - chrono::Duration::milliseconds(1))
+    );
+
+    let sys_up_time = vec![
+        ie::Field::flowStartSysUpTime(ie::flowStartSysUpTime(1_000)),
+        ie::Field::flowEndSysUpTime(ie::flowEndSysUpTime(2_000)),
+    ];
+    let without_init_time = DataRecord::new(vec![], sys_up_time.clone());
+    assert_eq!(without_init_time.start_time(export_time), None);
+    assert_eq!(without_init_time.end_time(export_time), None);
+    let with_init_time = DataRecord::new(
+        vec![ie::Field::systemInitTimeMilliseconds(
+            ie::systemInitTimeMilliseconds(system_init_time),
+        )],
+        sys_up_time,
+    );
+    assert_eq!(
+        with_init_time.start_time(export_time),
+        Some(system_init_time + chrono::Duration::seconds(1))
+    );
+    assert_eq!(
+        with_init_time.end_time(export_time),
+        Some(system_init_time + chrono::Duration::seconds(2))
+    );
+
+    // The relative timestamps beyond the representable time range are ignored
+    let end_of_time = DataRecord::new(
+        vec![ie::Field::systemInitTimeMilliseconds(
+            ie::systemInitTimeMilliseconds(DateTime::<Utc>::MAX_UTC),
+        )],
+        vec![
+            ie::Field::flowStartSysUpTime(ie::flowStartSysUpTime(1_000)),
+            ie::Field::flowEndDeltaMicroseconds(ie::flowEndDeltaMicroseconds(1_000)),
+        ],
+    );
+    assert_eq!(end_of_time.start_time(export_time), None);
+    assert_eq!(end_of_time.end_time(DateTime::<Utc>::MIN_UTC), None);
+}
+
+#[test]
+fn test_lazy_data_set() {
+    use crate::wire::deserializer::lazy::*;
+
+    let good_wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x36, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x01, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x00, 0x02, 0x00, 0x10, // Template set
+        0x01, 0x00, 0x00, 0x02, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+        0x00, 0x52, 0xff, 0xff, // interfaceName, variable length
+        0x01, 0x00, 0x00, 0x16, // Data set
+        0x00, 0x00, 0x00, 0x01, 0x04, b'e', b't', b'h', b'0', // First record
+        0x00, 0x00, 0x00, 0x02, 0x02, b'l', b'o', // Second record
+        0x00, 0x00, // Padding
+    ];
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let (_, pkt) = LazyIpfixPacket::from_wire(Span::new(&good_wire), templates_map).unwrap();
+    assert_eq!(pkt.sequence_number(), 1);
+    assert!(matches!(pkt.sets()[0], LazySet::Template(_)));
+    let data = match &pkt.sets()[1] {
+        LazySet::Data(data) => data,
+        set => panic!("expected a data set, found {set:?}"),
+    };
+    assert_eq!(data.id(), DataSetId::new(256).unwrap());
+
+    let records = data.records().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].raw(), &good_wire[36..45]);
+    assert_eq!(
+        records[1].raw_field(&ie::IE::interfaceName),
+        Some(&b"lo"[..])
+    );
+    assert_eq!(records[1].raw_field(&ie::IE::octetDeltaCount), None);
+    assert_eq!(
+        records[0].field(&ie::IE::interfaceName),
+        Some(Ok(ie::Field::interfaceName(ie::interfaceName(
+            "eth0".to_string()
+        ))))
+    );
+    assert_eq!(
+        data.decode(),
+        Ok(vec![
+            DataRecord::new(
+                vec![],
+                vec![
+                    ie::Field::ingressInterface(ie::ingressInterface(1)),
+                    ie::Field::interfaceName(ie::interfaceName("eth0".to_string())),
+                ],
+            ),
+            DataRecord::new(
+                vec![],
+                vec![
+                    ie::Field::ingressInterface(ie::ingressInterface(2)),
+                    ie::Field::interfaceName(ie::interfaceName("lo".to_string())),
+                ],
+            ),
+        ])
+    );
+
+    // The record is cut in the middle of the variable length field
+    let truncated = &good_wire[..good_wire.len() - 4];
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let mut truncated_wire = truncated.to_vec();
+    truncated_wire[3] = truncated.len() as u8;
+    truncated_wire[35] = 0x12;
+    let (_, pkt) = LazyIpfixPacket::from_wire(Span::new(&truncated_wire), templates_map).unwrap();
+    let data = match &pkt.sets()[1] {
+        LazySet::Data(data) => data,
+        set => panic!("expected a data set, found {set:?}"),
+    };
+    let mut records = data.records();
+    assert!(records.next().unwrap().is_ok());
+    let err = records.next().unwrap().unwrap_err();
+    assert_eq!(
+        err.error(),
+        &LazyDataRecordParsingError::NomError(nom::error::ErrorKind::Eof)
+    );
+    assert!(records.next().is_none());
+}
+
+#[test]
+fn test_lenient_packet() {
+    let wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x2c, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x01, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x01, 0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, // Data set without template
+        0x00, 0x02, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+    ];
+    let good = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 57).unwrap(),
+        1,
+        0,
+        vec![
+            Set::Template(vec![TemplateRecord::new(
+                256,
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+            )]),
+            Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![DataRecord::new(
+                    vec![],
+                    vec![ie::Field::ingressInterface(ie::ingressInterface(2))],
+                )],
+            },
+        ],
+    );
+    let missing_template = LocatedSetParsingError::new(
+        unsafe { Span::new_from_raw_offset(16, &wire[16..]) },
+        SetParsingError::NoTemplateDefinedFor(257),
+    );
+
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    assert!(IpfixPacket::from_wire(Span::new(&wire), templates_map.clone()).is_err());
+
+    let (remaining, (pkt, errors)) =
+        parse_ipfix_packet_lenient(Span::new(&wire), templates_map).unwrap();
+    assert!(remaining.is_empty());
+    assert_eq!(pkt, good);
+    assert_eq!(errors, vec![missing_template]);
+}
+
+#[test]
+fn test_inline_templates() -> Result<(), IpfixPacketWritingError> {
+    let template_wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x1c, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x01, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x00, 0x02, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+    ];
+    let good_wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x24, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x02, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x00, 0x02, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+    ];
+    let no_templates_wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x18, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x02, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+    ];
+    let good = IpfixPacket::new(
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 57).unwrap(),
+        2,
+        0,
+        vec![Set::Data {
+            id: DataSetId::new(256).unwrap(),
+            records: vec![DataRecord::new(
+                vec![],
+                vec![ie::Field::ingressInterface(ie::ingressInterface(2))],
+            )],
+        }],
+    );
+
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    IpfixPacket::from_wire(Span::new(&template_wire), templates_map.clone()).unwrap();
+
+    let options = SerializationOptions::new().with_align_sets(true);
+    test_write_with_two_inputs(
+        &good,
+        Some(templates_map.clone()),
+        options.with_inline_templates(true),
+        &good_wire,
+    )?;
+    test_write_with_two_inputs(
+        &good,
+        Some(templates_map.clone()),
+        options,
+        &no_templates_wire,
+    )?;
+
+    // The template defined after its data set is not added again
+    let template_after_wire = [
+        0x00, 0x0a, // Version
+        0x00, 0x24, // Length
+        0x58, 0x3d, 0xe0, 0x59, // Export time
+        0x00, 0x00, 0x00, 0x02, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Observation domain
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+        0x00, 0x02, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+    ];
+    let mut sets = good.sets().clone();
+    sets.push(Set::Template(vec![TemplateRecord::new(
+        256,
+        vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+    )]));
+    let template_after = IpfixPacket::new(good.export_time(), 2, 0, sets);
+    test_write_with_two_inputs(
+        &template_after,
+        Some(templates_map),
+        options.with_inline_templates(true),
+        &template_after_wire,
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_set_id() {
+    assert_eq!(SetId::try_from(2), Ok(SetId::Template));
+    assert_eq!(SetId::try_from(3), Ok(SetId::OptionsTemplate));
+    assert_eq!(
+        SetId::try_from(256),
+        Ok(SetId::Data(DataSetId::new(256).unwrap()))
+    );
+    assert_eq!(SetId::try_from(0), Err(SetIdError::Reserved(0)));
+    assert_eq!(SetId::try_from(255), Err(SetIdError::Reserved(255)));
+    assert_eq!(DataSetId::try_from(3), Err(DataSetIdError::InvalidId(3)));
+    assert_eq!(u16::from(SetId::OptionsTemplate), 3);
+
+    let set = Set::Data {
+        id: DataSetId::new(300).unwrap(),
+        records: vec![],
+    };
+    assert_eq!(set.set_id(), SetId::Data(DataSetId::new(300).unwrap()));
+    assert_eq!(set.data_set_id().map(u16::from), Some(300));
+    assert!(set.is_data());
+    assert!(!set.is_template());
+    let set = Set::OptionsTemplate(vec![]);
+    assert_eq!(set.set_id().id(), set.id());
+    assert!(set.is_options_template());
+    assert_eq!(set.data_set_id(), None);
+
+    let invalid_wire = [0x00, 0x04, 0x00, 0x04];
+    let invalid =
+        LocatedSetParsingError::new(Span::new(&invalid_wire), SetParsingError::InvalidSetId(4));
+    test_parse_error_with_one_input::<Set, TemplatesMap, LocatedSetParsingError<'_>>(
+        &invalid_wire,
+        Rc::new(RefCell::new(TemplateCache::new())),
+        &invalid,
+    );
+}
@@ -21,11 +21,12 @@ pub mod pcap_tests;
 
 use chrono::{TimeZone, Timelike, Utc};
 use netgauze_parse_utils::{test_helpers::*, Span};
-use std::{cell::RefCell, collections::HashMap, net::Ipv4Addr, rc::Rc};
+use std::{cell::RefCell, net::Ipv4Addr, rc::Rc};
 
 use crate::{
     ie,
     ipfix::*,
+    template_cache::TemplateCache,
     wire::{
         deserializer::{ie as ie_desr, ipfix::*},
         serializer::{ie as ie_ser, ipfix::*, *},
@@ -54,11 +55,11 @@ fn test_template_record() -> Result<(), TemplateRecordWritingError> {
         Span::new(&bad_template_id_wire),
         TemplateRecordParsingError::InvalidTemplateId(0),
     );
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
     test_parse_error_with_one_input::<
         TemplateRecord,
-        Rc<RefCell<HashMap<u16, Rc<(Vec<FieldSpecifier>, Vec<FieldSpecifier>)>>>>,
+        TemplatesMap,
         LocatedTemplateRecordParsingError<'_>,
     >(&bad_template_id_wire, templates_map, &bad_template_id);
     test_write(&good, &good_wire)?;
@@ -336,7 +337,7 @@ fn test_set_template() -> Result<(), SetWritingError> {
             FieldSpecifier::new(ie::IE::flowEndMilliseconds, 8).unwrap(),
         ],
     )]);
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map, &good);
     test_write_with_one_input(&good, None, &good_wire)?;
     Ok(())
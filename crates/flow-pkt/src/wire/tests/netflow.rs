@@ -15,23 +15,24 @@
 
 use std::{
     cell::RefCell,
-    collections::HashMap,
     net::{Ipv4Addr, Ipv6Addr},
     rc::Rc,
 };
 
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
 
 use netgauze_parse_utils::test_helpers::*;
 
 use crate::{
     netflow::*,
+    template_cache::{TemplateCache, TemplateKind},
     wire::{
         deserializer::netflow::{
-            LocatedNetFlowV9PacketParsingError, NetFlowV9PacketParsingError, SetParsingError,
+            parse_netflow_v9_packet_lenient, LocatedNetFlowV9PacketParsingError,
+            LocatedSetParsingError, NetFlowV9PacketParsingError, SetParsingError,
         },
-        serializer::netflow::*,
+        serializer::{netflow::*, SerializationOptions},
     },
     *,
 };
@@ -71,9 +72,14 @@ fn test_netflow9_template_record() -> Result<(), NetFlowV9WritingError> {
             ],
         )])],
     );
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map, &good);
-    test_write_with_two_inputs(&good, None, true, &good_wire)?;
+    test_write_with_two_inputs(
+        &good,
+        None,
+        SerializationOptions::new().with_align_sets(true),
+        &good_wire,
+    )?;
     Ok(())
 }
 
@@ -110,7 +116,7 @@ fn test_netflow9_data_record() -> Result<(), NetFlowV9WritingError> {
         FieldSpecifier::new(IE::ipVersion, 1).unwrap(),
     ];
 
-    let templates_map = Rc::new(RefCell::new(HashMap::from([(
+    let templates_map = Rc::new(RefCell::new(TemplateCache::from_iter([(
         1024,
         Rc::new((vec![], fields.clone())),
     )])));
@@ -216,7 +222,12 @@ fn test_netflow9_data_record() -> Result<(), NetFlowV9WritingError> {
     );
 
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
-    test_write_with_two_inputs(&good, Some(Rc::clone(&templates_map)), true, &good_wire)?;
+    test_write_with_two_inputs(
+        &good,
+        Some(Rc::clone(&templates_map)),
+        SerializationOptions::new().with_align_sets(true),
+        &good_wire,
+    )?;
     Ok(())
 }
 
@@ -278,7 +289,10 @@ fn test_data_packet() -> Result<(), NetFlowV9WritingError> {
     ];
 
     let fields = Rc::new((vec![], field_specifiers.clone()));
-    let templates_map = Rc::new(RefCell::new(HashMap::from([(313, fields)])));
+    let mut templates = TemplateCache::new();
+    templates.set_observation_domain_id(2081);
+    templates.insert(313, TemplateKind::Template, fields);
+    let templates_map = Rc::new(RefCell::new(templates));
 
     let good = NetFlowV9Packet::new(
         201984782,
@@ -397,7 +411,12 @@ fn test_data_packet() -> Result<(), NetFlowV9WritingError> {
     );
 
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
-    test_write_with_two_inputs(&good, Some(Rc::clone(&templates_map)), true, &good_wire)?;
+    test_write_with_two_inputs(
+        &good,
+        Some(Rc::clone(&templates_map)),
+        SerializationOptions::new().with_align_sets(true),
+        &good_wire,
+    )?;
     Ok(())
 }
 
@@ -417,9 +436,14 @@ fn test_mix_option_template_set() -> Result<(), SetWritingError> {
         ],
     )]);
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
-    test_write_with_two_inputs(&good, Some(Rc::clone(&templates_map)), false, &good_wire)?;
+    test_write_with_two_inputs(
+        &good,
+        Some(Rc::clone(&templates_map)),
+        SerializationOptions::new().with_align_sets(false),
+        &good_wire,
+    )?;
     Ok(())
 }
 
@@ -438,9 +462,14 @@ fn test_mix_option_template_set2() -> Result<(), SetWritingError> {
         ],
     )]);
 
-    let templates_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
     test_parsed_completely_with_one_input(&good_wire, templates_map.clone(), &good);
-    test_write_with_two_inputs(&good, Some(Rc::clone(&templates_map)), true, &good_wire)?;
+    test_write_with_two_inputs(
+        &good,
+        Some(Rc::clone(&templates_map)),
+        SerializationOptions::new().with_align_sets(true),
+        &good_wire,
+    )?;
     Ok(())
 }
 
@@ -495,7 +524,7 @@ fn test_padding() -> Result<(), NetFlowV9WritingError> {
         0x00, 0x31, 0x00, 0x01, // Field
         0x00, 0x32, 0x00, 0x02, 0x00, 0x00, // Padding
         0x01, 0x02, // Flow Set ID
-        0x00, 0x3a, // Flow Set Length
+        0x00, 0x38, // Flow Set Length
         0xd5, 0x03, 0xdf, 0x23, // Scope System
         0x00, 0x00, 0x00, 0x02, // Sampler ID
         0x4e, 0x45, 0x54, 0x46, // Sampler Name
@@ -510,7 +539,7 @@ fn test_padding() -> Result<(), NetFlowV9WritingError> {
         0x00, 0x00, 0x00, 0x00, // Sampler Name
         0x02, // Random
         0x01, 0x00, // Sampler Random Interval
-        0x00, 0x00, 0x00, // Padding
+        0x00, // Padding
     ];
 
     let bad_padding_options_wire = [
@@ -594,9 +623,9 @@ fn test_padding() -> Result<(), NetFlowV9WritingError> {
         unsafe { Span::new_from_raw_offset(107, &[0x01, 0x00, 0x00]) },
         NetFlowV9PacketParsingError::SetError(SetParsingError::InvalidPaddingValue(1)),
     );
-    let templates_no_padding_map = Rc::new(RefCell::new(HashMap::new()));
-    let templates_with_padding_map = Rc::new(RefCell::new(HashMap::new()));
-    let template_bad_map = Rc::new(RefCell::new(HashMap::new()));
+    let templates_no_padding_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let templates_with_padding_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let template_bad_map = Rc::new(RefCell::new(TemplateCache::new()));
 
     let (_, good_no_padding) = NetFlowV9Packet::from_wire(
         Span::new(&good_no_padding_wire),
@@ -643,14 +672,142 @@ fn test_padding() -> Result<(), NetFlowV9WritingError> {
     test_write_with_two_inputs(
         &good_no_padding,
         Some(Rc::clone(&templates_no_padding_map)),
-        false,
+        SerializationOptions::new().with_align_sets(false),
         &good_no_padding_wire,
     )?;
     test_write_with_two_inputs(
         &good_with_padding,
         Some(Rc::clone(&templates_with_padding_map)),
-        true,
+        SerializationOptions::new().with_align_sets(true),
         &good_with_padding_wire,
     )?;
     Ok(())
 }
+
+#[test]
+fn test_netflow9_data_record_flow_time() {
+    let unix_time = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+    let record = DataRecord::new(
+        vec![],
+        vec![
+            ie::Field::flowStartSysUpTime(ie::flowStartSysUpTime(90_000)),
+            ie::Field::flowEndMilliseconds(ie::flowEndMilliseconds(unix_time)),
+        ],
+    );
+    assert_eq!(
+        record.start_time(100_000, unix_time),
+        Some(unix_time - chrono::Duration::seconds(10))
+    );
+    assert_eq!(record.end_time(100_000, unix_time), Some(unix_time));
+    assert_eq!(
+        DataRecord::new(vec![], vec![]).start_time(100_000, unix_time),
+        None
+    );
+    assert_eq!(record.start_time(100_000, DateTime::<Utc>::MIN_UTC), None);
+}
+
+#[test]
+fn test_netflow9_lenient_packet() {
+    let wire = [
+        0x00, 0x09, // Version
+        0x00, 0x03, // Count
+        0x00, 0x00, 0x03, 0xe8, // Sys up time
+        0x58, 0x3d, 0xe0, 0x59, // Unix time
+        0x00, 0x00, 0x00, 0x01, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Source ID
+        0x01, 0x01, 0x00, 0x08, 0x00, 0x00, 0x00, 0x01, // Data set without template
+        0x00, 0x00, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+    ];
+    let good = NetFlowV9Packet::new(
+        1000,
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 57).unwrap(),
+        1,
+        0,
+        vec![
+            Set::Template(vec![TemplateRecord::new(
+                256,
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+            )]),
+            Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![DataRecord::new(
+                    vec![],
+                    vec![ie::Field::ingressInterface(ie::ingressInterface(2))],
+                )],
+            },
+        ],
+    );
+    let missing_template = LocatedSetParsingError::new(
+        unsafe { Span::new_from_raw_offset(20, &wire[20..]) },
+        SetParsingError::NoTemplateDefinedFor(257),
+    );
+
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    assert!(NetFlowV9Packet::from_wire(Span::new(&wire), templates_map.clone()).is_err());
+
+    let (remaining, (pkt, errors)) =
+        parse_netflow_v9_packet_lenient(Span::new(&wire), templates_map).unwrap();
+    assert!(remaining.is_empty());
+    assert_eq!(pkt, good);
+    assert_eq!(errors, vec![missing_template]);
+}
+
+#[test]
+fn test_netflow9_inline_templates_after_data() -> Result<(), NetFlowV9WritingError> {
+    let template_wire = [
+        0x00, 0x09, // Version
+        0x00, 0x01, // Count
+        0x00, 0x00, 0x03, 0xe8, // Sys up time
+        0x58, 0x3d, 0xe0, 0x59, // Unix time
+        0x00, 0x00, 0x00, 0x01, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Source ID
+        0x00, 0x00, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+    ];
+    // The template defined after its data set is not added again
+    let good_wire = [
+        0x00, 0x09, // Version
+        0x00, 0x02, // Count
+        0x00, 0x00, 0x03, 0xe8, // Sys up time
+        0x58, 0x3d, 0xe0, 0x59, // Unix time
+        0x00, 0x00, 0x00, 0x02, // Seq number
+        0x00, 0x00, 0x00, 0x00, // Source ID
+        0x01, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x02, // Data set
+        0x00, 0x00, 0x00, 0x0c, // Template set
+        0x01, 0x00, 0x00, 0x01, // Template ID and field count
+        0x00, 0x0a, 0x00, 0x04, // ingressInterface
+    ];
+    let good = NetFlowV9Packet::new(
+        1000,
+        Utc.with_ymd_and_hms(2016, 11, 29, 20, 8, 57).unwrap(),
+        2,
+        0,
+        vec![
+            Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![DataRecord::new(
+                    vec![],
+                    vec![ie::Field::ingressInterface(ie::ingressInterface(2))],
+                )],
+            },
+            Set::Template(vec![TemplateRecord::new(
+                256,
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+            )]),
+        ],
+    );
+
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    NetFlowV9Packet::from_wire(Span::new(&template_wire), templates_map.clone()).unwrap();
+    test_write_with_two_inputs(
+        &good,
+        Some(templates_map),
+        SerializationOptions::new().with_inline_templates(true),
+        &good_wire,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,26 @@
+//! Print the flow messages of a pcap or pcapng capture as JSON lines
+//!
+//! Usage: `cargo run --example pcap-replay --features pcap -- <file> [ports]`,
+//! the ports are comma separated and default to 4739 (IPFIX) and 2055
+//! (NetFlow V9).
+
+use netgauze_flow_service::replay::FlowReplay;
+
+fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().ok_or("missing pcap file")?;
+    let ports = match args.next() {
+        Some(ports) => ports
+            .split(',')
+            .map(|port| port.parse::<u16>())
+            .collect::<Result<Vec<_>, _>>()?,
+        None => vec![4739, 2055],
+    };
+    for request in FlowReplay::open(path, ports)? {
+        match request {
+            Ok((peer, msg)) => println!("{peer} {}", serde_json::to_string(&msg)?),
+            Err((peer, err)) => eprintln!("{peer} error decoding packet: {err:?}"),
+        }
+    }
+    Ok(())
+}
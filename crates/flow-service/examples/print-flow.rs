@@ -1,5 +1,3 @@
-use std::sync::Arc;
-
 use bytes::Bytes;
 use dashmap::DashMap;
 use futures_util::{stream::SplitSink, StreamExt};
@@ -26,11 +24,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>
     init_tracing();
     let listen_addr = "0.0.0.0:8080";
     let socket = UdpSocket::bind(&listen_addr).await?;
-    println!("Listening on addr: {}", listen_addr);
+    println!("Listening on addr: {listen_addr}");
 
     let framed = UdpFramed::new(socket, BytesCodec::default());
     let (_tx, mut stream): (SplitSink<_, (Bytes, _)>, _) = framed.split();
-    let clients = Arc::new(DashMap::new());
+    let clients = DashMap::new();
     while let Some(next) = stream.next().await {
         match next {
             Ok((mut buf, addr)) => {
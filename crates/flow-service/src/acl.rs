@@ -0,0 +1,153 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Source address access control of the exporters, so unknown devices can't
+//! pollute the templates and the metrics.
+//!
+//! An [`ExporterAcl`] has a list of allowed and a list of denied prefixes. The
+//! deny list takes precedence, and an empty allow list allows all the
+//! addresses that are not denied. Every prefix counts the addresses it
+//! matched, see [`ExporterAcl::counters`].
+
+use std::{
+    net::IpAddr,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+
+/// Allow and deny lists of the exporters addresses, see the [module](self)
+/// docs. The counters are atomic, so the ACL can be shared between the
+/// listeners and read while they are running.
+#[derive(Debug, Default)]
+pub struct ExporterAcl {
+    allow: Vec<(IpNet, AtomicU64)>,
+    deny: Vec<(IpNet, AtomicU64)>,
+    not_allowed: AtomicU64,
+}
+
+impl ExporterAcl {
+    pub fn new(allow: Vec<IpNet>, deny: Vec<IpNet>) -> Self {
+        let counted = |prefixes: Vec<IpNet>| {
+            prefixes
+                .into_iter()
+                .map(|prefix| (prefix.trunc(), AtomicU64::new(0)))
+                .collect()
+        };
+        Self {
+            allow: counted(allow),
+            deny: counted(deny),
+            not_allowed: AtomicU64::new(0),
+        }
+    }
+
+    /// Check an exporter address and count it in the first matching prefix.
+    /// IPv4-mapped IPv6 addresses are matched as IPv4.
+    pub fn is_allowed(&self, address: IpAddr) -> bool {
+        let address = address.to_canonical();
+        let matching = |prefixes: &[(IpNet, AtomicU64)]| {
+            prefixes
+                .iter()
+                .find(|(prefix, _)| prefix.contains(&address))
+                .map(|(_, counter)| counter.fetch_add(1, Ordering::Relaxed))
+                .is_some()
+        };
+        if matching(&self.deny) {
+            return false;
+        }
+        if self.allow.is_empty() || matching(&self.allow) {
+            return true;
+        }
+        self.not_allowed.fetch_add(1, Ordering::Relaxed);
+        false
+    }
+
+    /// Current value of the counters
+    pub fn counters(&self) -> AclCounters {
+        let load = |prefixes: &[(IpNet, AtomicU64)]| {
+            prefixes
+                .iter()
+                .map(|(prefix, counter)| (*prefix, counter.load(Ordering::Relaxed)))
+                .collect()
+        };
+        AclCounters {
+            allow: load(&self.allow),
+            deny: load(&self.deny),
+            not_allowed: self.not_allowed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Snapshot of the [`ExporterAcl`] counters
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AclCounters {
+    allow: Vec<(IpNet, u64)>,
+    deny: Vec<(IpNet, u64)>,
+    not_allowed: u64,
+}
+
+impl AclCounters {
+    /// Number of allowed addresses matched by every allowed prefix
+    pub const fn allow(&self) -> &Vec<(IpNet, u64)> {
+        &self.allow
+    }
+
+    /// Number of rejected addresses matched by every denied prefix
+    pub const fn deny(&self) -> &Vec<(IpNet, u64)> {
+        &self.deny
+    }
+
+    /// Number of rejected addresses that are not in the allow list
+    pub const fn not_allowed(&self) -> u64 {
+        self.not_allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::*;
+
+    #[test]
+    fn test_exporter_acl() {
+        let prefix = |x: &str| x.parse::<IpNet>().unwrap();
+        let acl = ExporterAcl::new(
+            vec![prefix("192.0.2.0/24"), prefix("2001:db8::/32")],
+            vec![prefix("192.0.2.128/25")],
+        );
+        assert!(acl.is_allowed([192, 0, 2, 1].into()));
+        assert!(acl.is_allowed(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into()));
+        assert!(!acl.is_allowed([192, 0, 2, 200].into()));
+        assert!(!acl.is_allowed([198, 51, 100, 1].into()));
+        // IPv4-mapped address of a dual stack socket
+        assert!(acl.is_allowed(Ipv4Addr::new(192, 0, 2, 4).to_ipv6_mapped().into()));
+
+        let counters = acl.counters();
+        assert_eq!(
+            counters.allow(),
+            &vec![(prefix("192.0.2.0/24"), 2), (prefix("2001:db8::/32"), 1)]
+        );
+        assert_eq!(counters.deny(), &vec![(prefix("192.0.2.128/25"), 1)]);
+        assert_eq!(counters.not_allowed(), 1);
+
+        // Without an allow list, only the denied addresses are rejected
+        let acl = ExporterAcl::new(vec![], vec![prefix("198.51.100.0/24")]);
+        assert!(acl.is_allowed([192, 0, 2, 1].into()));
+        assert!(!acl.is_allowed([198, 51, 100, 1].into()));
+        assert_eq!(acl.counters().not_allowed(), 0);
+    }
+}
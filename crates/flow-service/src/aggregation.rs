@@ -0,0 +1,689 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Time-windowed aggregation of the flow records.
+//!
+//! [`FlowAggregator`] groups the flow records into fixed (tumbling) windows
+//! of [`AggregationConfig::window`] by the configured [`KeyDimension`]s, e.g.,
+//! the source and destination AS of every exporter, and sums the configured
+//! [`Aggregate`]s of each group.
+//!
+//! The windows follow the event time of the records, i.e., the flow end time
+//! resolved from any of the flow end timestamps (see
+//! [`ipfix::DataRecord::end_time`] and [`netflow::DataRecord::end_time`]) when
+//! present, otherwise the export time of the packet. The watermark is the latest event time seen minus the
+//! [`AggregationConfig::allowed_lateness`]; a window is closed and its
+//! [`AggregatedRecord`]s are emitted once the watermark passes its end. The
+//! records that belong to an already closed window are late, they are dropped
+//! and counted in [`FlowAggregator::late_records`]. A window that ends after
+//! the latest representable time is never closed by the watermark, only by
+//! [`FlowAggregator::flush`].
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_flow_pkt::{
+    ie::{self, Field},
+    ipfix, netflow, FlowInfo,
+};
+
+/// Attribute of a flow record that is part of the aggregation key
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum KeyDimension {
+    /// IP address of the exporter that sent the record
+    Exporter,
+    /// IPFIX Observation Domain ID or NetFlow V9 Source ID
+    ObservationDomain,
+    SourceAs,
+    DestinationAs,
+    SourceAddress,
+    DestinationAddress,
+    SourcePort,
+    DestinationPort,
+    Protocol,
+    IngressInterface,
+    EgressInterface,
+}
+
+/// Value of a [`KeyDimension`] in a flow record
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum KeyValue {
+    Address(IpAddr),
+    Number(u32),
+}
+
+/// Value summed for every aggregation key
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Aggregate {
+    /// Sum of `octetDeltaCount`
+    Octets,
+    /// Sum of `packetDeltaCount`
+    Packets,
+    /// Number of flow records
+    Flows,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregationConfig {
+    window: Duration,
+    #[serde(default)]
+    allowed_lateness: Duration,
+    dimensions: Vec<KeyDimension>,
+    aggregates: Vec<Aggregate>,
+}
+
+impl AggregationConfig {
+    pub const fn new(
+        window: Duration,
+        dimensions: Vec<KeyDimension>,
+        aggregates: Vec<Aggregate>,
+    ) -> Self {
+        Self {
+            window,
+            allowed_lateness: Duration::ZERO,
+            dimensions,
+            aggregates,
+        }
+    }
+
+    /// How long a window is kept open after the latest event time passed its
+    /// end, to account for the records delayed by the exporters
+    pub const fn with_allowed_lateness(mut self, allowed_lateness: Duration) -> Self {
+        self.allowed_lateness = allowed_lateness;
+        self
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub const fn allowed_lateness(&self) -> Duration {
+        self.allowed_lateness
+    }
+
+    pub const fn dimensions(&self) -> &Vec<KeyDimension> {
+        &self.dimensions
+    }
+
+    pub const fn aggregates(&self) -> &Vec<Aggregate> {
+        &self.aggregates
+    }
+}
+
+/// Aggregates of a key in a closed window
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregatedRecord {
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    key: Vec<(KeyDimension, Option<KeyValue>)>,
+    values: BTreeMap<Aggregate, u64>,
+}
+
+impl AggregatedRecord {
+    pub const fn window_start(&self) -> DateTime<Utc> {
+        self.window_start
+    }
+
+    /// Saturated to [`DateTime::<Utc>::MAX_UTC`] when the window ends after the
+    /// latest representable time
+    pub const fn window_end(&self) -> DateTime<Utc> {
+        self.window_end
+    }
+
+    /// Value of every configured dimension, [`None`] when the records don't
+    /// have it
+    pub const fn key(&self) -> &Vec<(KeyDimension, Option<KeyValue>)> {
+        &self.key
+    }
+
+    pub const fn values(&self) -> &BTreeMap<Aggregate, u64> {
+        &self.values
+    }
+
+    pub fn value(&self, aggregate: Aggregate) -> Option<u64> {
+        self.values.get(&aggregate).copied()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AggregationConfigError {
+    /// The window is shorter than a millisecond or too long to be represented
+    InvalidWindow(Duration),
+    /// The allowed lateness is too long to be represented
+    InvalidAllowedLateness(Duration),
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct Counters {
+    octets: u64,
+    packets: u64,
+    flows: u64,
+}
+
+/// Time-windowed aggregation, see the [module](self) docs
+#[derive(Debug, Clone)]
+pub struct FlowAggregator {
+    config: AggregationConfig,
+    window: TimeDelta,
+    allowed_lateness: TimeDelta,
+    windows: BTreeMap<DateTime<Utc>, HashMap<Vec<Option<KeyValue>>, Counters>>,
+    watermark: Option<DateTime<Utc>>,
+    late_records: u64,
+}
+
+impl FlowAggregator {
+    pub fn new(config: AggregationConfig) -> Result<Self, AggregationConfigError> {
+        // Windows shorter than a millisecond can't be aligned
+        let window = TimeDelta::from_std(config.window)
+            .ok()
+            .filter(|window| *window >= TimeDelta::milliseconds(1))
+            .ok_or(AggregationConfigError::InvalidWindow(config.window))?;
+        let allowed_lateness = TimeDelta::from_std(config.allowed_lateness)
+            .map_err(|_| AggregationConfigError::InvalidAllowedLateness(config.allowed_lateness))?;
+        Ok(Self {
+            config,
+            window,
+            allowed_lateness,
+            windows: BTreeMap::new(),
+            watermark: None,
+            late_records: 0,
+        })
+    }
+
+    pub const fn config(&self) -> &AggregationConfig {
+        &self.config
+    }
+
+    /// Windows ending before the watermark are closed
+    pub const fn watermark(&self) -> Option<DateTime<Utc>> {
+        self.watermark
+    }
+
+    /// Number of records dropped since their window was already closed
+    pub const fn late_records(&self) -> u64 {
+        self.late_records
+    }
+
+    /// Aggregate the flow records of a message sent by `exporter`, and return
+    /// the records of the windows closed by it. Options data records are
+    /// ignored.
+    pub fn push(&mut self, exporter: SocketAddr, msg: &FlowInfo) -> Vec<AggregatedRecord> {
+        for record in flow_records(msg) {
+            self.add(exporter, &record);
+        }
+        let Some(watermark) = self.watermark else {
+            return vec![];
+        };
+        let mut closed = vec![];
+        while let Some(entry) = self.windows.first_entry() {
+            // Windows ending after the latest representable time are never closed
+            let window_end = entry.key().checked_add_signed(self.window);
+            if window_end.is_none_or(|window_end| window_end > watermark) {
+                break;
+            }
+            let (window_start, groups) = entry.remove_entry();
+            closed.extend(self.emit(window_start, groups));
+        }
+        closed
+    }
+
+    /// Close all the open windows, e.g., on shutdown
+    pub fn flush(&mut self) -> Vec<AggregatedRecord> {
+        std::mem::take(&mut self.windows)
+            .into_iter()
+            .flat_map(|(window_start, groups)| self.emit(window_start, groups))
+            .collect()
+    }
+
+    fn add(&mut self, exporter: SocketAddr, record: &FlowRecord<'_>) {
+        let event_time = record.event_time;
+        let window_start = self.window_start(event_time);
+        let window_end = self.window_end(window_start);
+        if self
+            .watermark
+            .zip(window_end)
+            .is_some_and(|(watermark, window_end)| window_end <= watermark)
+        {
+            self.late_records += 1;
+            return;
+        }
+        // Before the earliest representable time, the watermark can't move
+        let watermark = event_time.checked_sub_signed(self.allowed_lateness);
+        if self.watermark < watermark {
+            self.watermark = watermark;
+        }
+
+        let key = self
+            .config
+            .dimensions
+            .iter()
+            .map(|dimension| key_value(*dimension, exporter, record.domain, record.fields))
+            .collect();
+        let counters = self
+            .windows
+            .entry(window_start)
+            .or_default()
+            .entry(key)
+            .or_default();
+        counters.flows = counters.flows.saturating_add(1);
+        counters.octets = counters
+            .octets
+            .saturating_add(aggregate_value(Aggregate::Octets, record.fields));
+        counters.packets = counters
+            .packets
+            .saturating_add(aggregate_value(Aggregate::Packets, record.fields));
+    }
+
+    fn window_start(&self, event_time: DateTime<Utc>) -> DateTime<Utc> {
+        let window = self.window.num_milliseconds();
+        let millis = event_time.timestamp_millis();
+        DateTime::from_timestamp_millis(millis - millis.rem_euclid(window)).unwrap_or(event_time)
+    }
+
+    /// [`None`] when the window ends after the latest representable time
+    fn window_end(&self, window_start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        window_start.checked_add_signed(self.window)
+    }
+
+    fn emit(
+        &self,
+        window_start: DateTime<Utc>,
+        groups: HashMap<Vec<Option<KeyValue>>, Counters>,
+    ) -> Vec<AggregatedRecord> {
+        let window_end = self
+            .window_end(window_start)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let mut records = groups
+            .into_iter()
+            .map(|(key, counters)| AggregatedRecord {
+                window_start,
+                window_end,
+                key: self.config.dimensions.iter().copied().zip(key).collect(),
+                values: self
+                    .config
+                    .aggregates
+                    .iter()
+                    .map(|aggregate| {
+                        let value = match aggregate {
+                            Aggregate::Octets => counters.octets,
+                            Aggregate::Packets => counters.packets,
+                            Aggregate::Flows => counters.flows,
+                        };
+                        (*aggregate, value)
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        records.sort_by(|a, b| a.key.cmp(&b.key));
+        records
+    }
+}
+
+/// Flow record of a message with its event time, see the [module](self) docs
+pub(crate) struct FlowRecord<'a> {
+    pub(crate) domain: u32,
+    pub(crate) event_time: DateTime<Utc>,
+    pub(crate) fields: &'a [Field],
+}
+
+/// The flow records of a message, the options data records are skipped
+pub(crate) fn flow_records(msg: &FlowInfo) -> Vec<FlowRecord<'_>> {
+    let mut flow_records = vec![];
+    match msg {
+        FlowInfo::IPFIX(pkt) => {
+            for set in pkt.sets() {
+                if let ipfix::Set::Data { records, .. } = set {
+                    for record in records.iter().filter(|x| x.scope_fields().is_empty()) {
+                        flow_records.push(FlowRecord {
+                            domain: pkt.observation_domain_id(),
+                            event_time: record
+                                .end_time(pkt.export_time())
+                                .unwrap_or(pkt.export_time()),
+                            fields: record.fields(),
+                        });
+                    }
+                }
+            }
+        }
+        FlowInfo::NetFlowV9(pkt) => {
+            for set in pkt.sets() {
+                if let netflow::Set::Data { records, .. } = set {
+                    for record in records.iter().filter(|x| x.scope_fields().is_empty()) {
+                        flow_records.push(FlowRecord {
+                            domain: pkt.source_id(),
+                            event_time: record
+                                .end_time(pkt.sys_up_time(), pkt.unix_time())
+                                .unwrap_or(pkt.unix_time()),
+                            fields: record.fields(),
+                        });
+                    }
+                }
+            }
+        }
+    }
+    flow_records
+}
+
+/// Value of the aggregate in a single flow record, saturated at [`u64::MAX`]
+pub(crate) fn aggregate_value(aggregate: Aggregate, fields: &[Field]) -> u64 {
+    match aggregate {
+        Aggregate::Octets => fields
+            .iter()
+            .filter_map(|field| match field {
+                Field::octetDeltaCount(ie::octetDeltaCount(x)) => Some(*x),
+                _ => None,
+            })
+            .fold(0u64, u64::saturating_add),
+        Aggregate::Packets => fields
+            .iter()
+            .filter_map(|field| match field {
+                Field::packetDeltaCount(ie::packetDeltaCount(x)) => Some(*x),
+                _ => None,
+            })
+            .fold(0u64, u64::saturating_add),
+        Aggregate::Flows => 1,
+    }
+}
+
+pub(crate) fn key_value(
+    dimension: KeyDimension,
+    exporter: SocketAddr,
+    domain: u32,
+    fields: &[Field],
+) -> Option<KeyValue> {
+    let number = |x: u32| Some(KeyValue::Number(x));
+    let address = |x: IpAddr| Some(KeyValue::Address(x));
+    match dimension {
+        KeyDimension::Exporter => return address(exporter.ip()),
+        KeyDimension::ObservationDomain => return number(domain),
+        _ => {}
+    }
+    fields.iter().find_map(|field| match (dimension, field) {
+        (KeyDimension::SourceAs, Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(x))) => number(*x),
+        (
+            KeyDimension::DestinationAs,
+            Field::bgpDestinationAsNumber(ie::bgpDestinationAsNumber(x)),
+        ) => number(*x),
+        (KeyDimension::SourceAddress, Field::sourceIPv4Address(ie::sourceIPv4Address(x))) => {
+            address((*x).into())
+        }
+        (KeyDimension::SourceAddress, Field::sourceIPv6Address(ie::sourceIPv6Address(x))) => {
+            address((*x).into())
+        }
+        (
+            KeyDimension::DestinationAddress,
+            Field::destinationIPv4Address(ie::destinationIPv4Address(x)),
+        ) => address((*x).into()),
+        (
+            KeyDimension::DestinationAddress,
+            Field::destinationIPv6Address(ie::destinationIPv6Address(x)),
+        ) => address((*x).into()),
+        (KeyDimension::SourcePort, Field::sourceTransportPort(ie::sourceTransportPort(x))) => {
+            number(*x as u32)
+        }
+        (
+            KeyDimension::DestinationPort,
+            Field::destinationTransportPort(ie::destinationTransportPort(x)),
+        ) => number(*x as u32),
+        (KeyDimension::Protocol, Field::protocolIdentifier(ie::protocolIdentifier(x))) => {
+            number(*x as u32)
+        }
+        (KeyDimension::IngressInterface, Field::ingressInterface(ie::ingressInterface(x))) => {
+            number(*x)
+        }
+        (KeyDimension::EgressInterface, Field::egressInterface(ie::egressInterface(x))) => {
+            number(*x)
+        }
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::{ipfix::IpfixPacket, DataSetId};
+
+    use super::*;
+
+    fn flow(end_seconds: u32, source_as: u32, octets: u64) -> ipfix::DataRecord {
+        ipfix::DataRecord::new(
+            vec![],
+            vec![
+                Field::flowEndSeconds(ie::flowEndSeconds(
+                    Utc.timestamp_opt(end_seconds as i64, 0).unwrap(),
+                )),
+                Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(source_as)),
+                Field::octetDeltaCount(ie::octetDeltaCount(octets)),
+                Field::packetDeltaCount(ie::packetDeltaCount(1)),
+            ],
+        )
+    }
+
+    fn pkt(records: Vec<ipfix::DataRecord>) -> FlowInfo {
+        FlowInfo::IPFIX(IpfixPacket::new(
+            Utc.timestamp_opt(0, 0).unwrap(),
+            0,
+            1,
+            vec![ipfix::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records,
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_aggregation() {
+        let exporter = "192.0.2.1:4739".parse().unwrap();
+        let config = AggregationConfig::new(
+            Duration::from_secs(60),
+            vec![KeyDimension::Exporter, KeyDimension::SourceAs],
+            vec![Aggregate::Octets, Aggregate::Flows],
+        )
+        .with_allowed_lateness(Duration::from_secs(10));
+        let mut aggregator = FlowAggregator::new(config).unwrap();
+
+        let closed = aggregator.push(
+            exporter,
+            &pkt(vec![
+                flow(10, 65000, 100),
+                flow(20, 65000, 50),
+                flow(30, 65001, 10),
+            ]),
+        );
+        assert!(closed.is_empty());
+        // Within the allowed lateness, the first window is still open
+        let closed = aggregator.push(exporter, &pkt(vec![flow(65, 65000, 1), flow(59, 65001, 5)]));
+        assert!(closed.is_empty());
+
+        let closed = aggregator.push(exporter, &pkt(vec![flow(70, 65000, 1)]));
+        let key = |source_as| {
+            vec![
+                (
+                    KeyDimension::Exporter,
+                    Some(KeyValue::Address(exporter.ip())),
+                ),
+                (KeyDimension::SourceAs, Some(KeyValue::Number(source_as))),
+            ]
+        };
+        let window_start = Utc.timestamp_opt(0, 0).unwrap();
+        assert_eq!(
+            closed,
+            vec![
+                AggregatedRecord {
+                    window_start,
+                    window_end: window_start + TimeDelta::seconds(60),
+                    key: key(65000),
+                    values: BTreeMap::from([(Aggregate::Octets, 150), (Aggregate::Flows, 2)]),
+                },
+                AggregatedRecord {
+                    window_start,
+                    window_end: window_start + TimeDelta::seconds(60),
+                    key: key(65001),
+                    values: BTreeMap::from([(Aggregate::Octets, 15), (Aggregate::Flows, 2)]),
+                },
+            ]
+        );
+        assert_eq!(
+            aggregator.watermark(),
+            Some(Utc.timestamp_opt(60, 0).unwrap())
+        );
+
+        // The first window is closed
+        assert!(aggregator
+            .push(exporter, &pkt(vec![flow(50, 65000, 1)]))
+            .is_empty());
+        assert_eq!(aggregator.late_records(), 1);
+
+        let flushed = aggregator.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].value(Aggregate::Octets), Some(2));
+        assert_eq!(flushed[0].value(Aggregate::Packets), None);
+        assert!(aggregator.flush().is_empty());
+    }
+
+    #[test]
+    fn test_aggregation_time_overflow() {
+        let exporter = "192.0.2.1:4739".parse().unwrap();
+        let config = AggregationConfig::new(
+            Duration::from_secs(60),
+            vec![KeyDimension::SourceAs],
+            vec![Aggregate::Flows],
+        )
+        .with_allowed_lateness(Duration::from_secs(10));
+        let mut aggregator = FlowAggregator::new(config).unwrap();
+
+        let end_of_time = ipfix::DataRecord::new(
+            vec![],
+            vec![
+                Field::flowEndMilliseconds(ie::flowEndMilliseconds(DateTime::<Utc>::MAX_UTC)),
+                Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(65000)),
+            ],
+        );
+        let closed = aggregator.push(exporter, &pkt(vec![flow(10, 65000, 1)]));
+        assert!(closed.is_empty());
+        // Closes the earlier window, while its own window is never closed
+        let closed = aggregator.push(exporter, &pkt(vec![end_of_time.clone()]));
+        assert_eq!(closed.len(), 1);
+        assert!(aggregator
+            .push(exporter, &pkt(vec![end_of_time]))
+            .is_empty());
+        assert_eq!(aggregator.late_records(), 0);
+
+        let flushed = aggregator.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].window_end(), DateTime::<Utc>::MAX_UTC);
+        assert_eq!(flushed[0].value(Aggregate::Flows), Some(2));
+    }
+
+    #[test]
+    fn test_aggregation_netflow_v9() {
+        let exporter = "192.0.2.1:2055".parse().unwrap();
+        let config = AggregationConfig::new(
+            Duration::from_secs(60),
+            vec![KeyDimension::SourceAs],
+            vec![Aggregate::Flows],
+        );
+        let mut aggregator = FlowAggregator::new(config).unwrap();
+        let flow = |end_sys_up_time, source_as| {
+            netflow::DataRecord::new(
+                vec![],
+                vec![
+                    Field::flowEndSysUpTime(ie::flowEndSysUpTime(end_sys_up_time)),
+                    Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(source_as)),
+                ],
+            )
+        };
+        // The exporter booted 20 seconds after the epoch
+        let pkt = FlowInfo::NetFlowV9(netflow::NetFlowV9Packet::new(
+            100_000,
+            Utc.timestamp_opt(120, 0).unwrap(),
+            1,
+            0,
+            vec![netflow::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: vec![flow(10_000, 65000), flow(50_000, 65001)],
+            }],
+        ));
+
+        let closed = aggregator.push(exporter, &pkt);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].window_start(), Utc.timestamp_opt(0, 0).unwrap());
+        assert_eq!(
+            closed[0].key(),
+            &vec![(KeyDimension::SourceAs, Some(KeyValue::Number(65000)))]
+        );
+        let flushed = aggregator.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].window_start(), Utc.timestamp_opt(60, 0).unwrap());
+    }
+
+    #[test]
+    fn test_aggregation_counter_overflow() {
+        let exporter = "192.0.2.1:4739".parse().unwrap();
+        let config = AggregationConfig::new(
+            Duration::from_secs(60),
+            vec![KeyDimension::SourceAs],
+            vec![Aggregate::Octets, Aggregate::Packets],
+        );
+        let mut aggregator = FlowAggregator::new(config).unwrap();
+        // Two counters of each in the record itself, and two records
+        let large = ipfix::DataRecord::new(
+            vec![],
+            vec![
+                Field::octetDeltaCount(ie::octetDeltaCount(u64::MAX)),
+                Field::octetDeltaCount(ie::octetDeltaCount(1)),
+                Field::packetDeltaCount(ie::packetDeltaCount(u64::MAX)),
+                Field::packetDeltaCount(ie::packetDeltaCount(1)),
+            ],
+        );
+        aggregator.push(exporter, &pkt(vec![large.clone(), large]));
+        let flushed = aggregator.flush();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0].value(Aggregate::Octets), Some(u64::MAX));
+        assert_eq!(flushed[0].value(Aggregate::Packets), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_aggregation_config_out_of_range() {
+        let config = |window| {
+            AggregationConfig::new(window, vec![KeyDimension::SourceAs], vec![Aggregate::Flows])
+        };
+        assert_eq!(
+            FlowAggregator::new(config(Duration::MAX)).err(),
+            Some(AggregationConfigError::InvalidWindow(Duration::MAX))
+        );
+        assert_eq!(
+            FlowAggregator::new(config(Duration::from_micros(10))).err(),
+            Some(AggregationConfigError::InvalidWindow(
+                Duration::from_micros(10)
+            ))
+        );
+        assert_eq!(
+            FlowAggregator::new(
+                config(Duration::from_secs(60)).with_allowed_lateness(Duration::MAX)
+            )
+            .err(),
+            Some(AggregationConfigError::InvalidAllowedLateness(
+                Duration::MAX
+            ))
+        );
+    }
+}
@@ -0,0 +1,372 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Threshold alerting over the aggregated flows, a basic DDoS and anomaly
+//! detection layer.
+//!
+//! [`ThresholdAlerting`] evaluates user-defined [`AlertRule`]s over the
+//! [`AggregatedRecord`]s of every closed window emitted by
+//! [`crate::aggregation::FlowAggregator`], and returns an [`AlertEvent`] for
+//! every violation. For instance, more than 100 Mbps to a single destination
+//! is a [`AlertCondition::RateAbove`] of `12_500_000` octets per second over
+//! records aggregated by [`KeyDimension::DestinationAddress`].
+//!
+//! The rules only see the dimensions kept by the aggregation, so the
+//! aggregation must be configured with the dimensions used by the rules.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::aggregation::{Aggregate, AggregatedRecord, KeyDimension, KeyValue};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AlertCondition {
+    /// The aggregate of a key, per second of the window, is above the
+    /// threshold
+    RateAbove {
+        aggregate: Aggregate,
+        threshold: f64,
+    },
+    /// The share of a protocol in the aggregate changed by more than
+    /// `max_change` (between 0 and 1) since the previous window, the records
+    /// must be aggregated by [`KeyDimension::Protocol`]
+    ProtocolShift {
+        aggregate: Aggregate,
+        max_change: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    name: String,
+    #[serde(default)]
+    filter: Vec<(KeyDimension, KeyValue)>,
+    condition: AlertCondition,
+}
+
+impl AlertRule {
+    /// The rule applies to the records matching all the `filter` values, or
+    /// to all the records if the filter is empty
+    pub const fn new(
+        name: String,
+        filter: Vec<(KeyDimension, KeyValue)>,
+        condition: AlertCondition,
+    ) -> Self {
+        Self {
+            name,
+            filter,
+            condition,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub const fn filter(&self) -> &Vec<(KeyDimension, KeyValue)> {
+        &self.filter
+    }
+
+    pub const fn condition(&self) -> &AlertCondition {
+        &self.condition
+    }
+
+    fn matches(&self, record: &AggregatedRecord) -> bool {
+        self.filter.iter().all(|(dimension, value)| {
+            record
+                .key()
+                .iter()
+                .any(|(d, v)| d == dimension && v.as_ref() == Some(value))
+        })
+    }
+}
+
+/// Violation of a rule in a window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertEvent {
+    rule: String,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    key: Vec<(KeyDimension, Option<KeyValue>)>,
+    observed: f64,
+    threshold: f64,
+}
+
+impl AlertEvent {
+    pub fn rule(&self) -> &str {
+        &self.rule
+    }
+
+    pub const fn window_start(&self) -> DateTime<Utc> {
+        self.window_start
+    }
+
+    pub const fn window_end(&self) -> DateTime<Utc> {
+        self.window_end
+    }
+
+    /// Key of the aggregated record, or only the protocol for
+    /// [`AlertCondition::ProtocolShift`]
+    pub const fn key(&self) -> &Vec<(KeyDimension, Option<KeyValue>)> {
+        &self.key
+    }
+
+    /// The rate, or the change of the protocol share
+    pub const fn observed(&self) -> f64 {
+        self.observed
+    }
+
+    pub const fn threshold(&self) -> f64 {
+        self.threshold
+    }
+}
+
+/// Evaluate the alert rules over the aggregated flows, see the
+/// [module](self) docs
+#[derive(Debug, Clone)]
+pub struct ThresholdAlerting {
+    rules: Vec<AlertRule>,
+    /// Protocol shares of the previous window of every rule
+    protocol_shares: HashMap<usize, HashMap<Option<KeyValue>, f64>>,
+}
+
+impl ThresholdAlerting {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            protocol_shares: HashMap::new(),
+        }
+    }
+
+    pub const fn rules(&self) -> &Vec<AlertRule> {
+        &self.rules
+    }
+
+    /// Evaluate the rules over the records of the closed windows, the windows
+    /// are expected in order
+    pub fn evaluate(&mut self, records: &[AggregatedRecord]) -> Vec<AlertEvent> {
+        let mut windows = BTreeMap::<_, Vec<_>>::new();
+        for record in records {
+            windows
+                .entry((record.window_start(), record.window_end()))
+                .or_default()
+                .push(record);
+        }
+        let mut events = vec![];
+        for ((window_start, window_end), records) in windows {
+            for (index, rule) in self.rules.iter().enumerate() {
+                let records = records
+                    .iter()
+                    .copied()
+                    .filter(|record| rule.matches(record))
+                    .collect::<Vec<_>>();
+                let event = |key, observed, threshold| AlertEvent {
+                    rule: rule.name.clone(),
+                    window_start,
+                    window_end,
+                    key,
+                    observed,
+                    threshold,
+                };
+                match rule.condition {
+                    AlertCondition::RateAbove {
+                        aggregate,
+                        threshold,
+                    } => {
+                        let seconds =
+                            (window_end - window_start).num_milliseconds() as f64 / 1000.0;
+                        for record in records {
+                            let rate = record.value(aggregate).unwrap_or(0) as f64 / seconds;
+                            if rate > threshold {
+                                events.push(event(record.key().clone(), rate, threshold));
+                            }
+                        }
+                    }
+                    AlertCondition::ProtocolShift {
+                        aggregate,
+                        max_change,
+                    } => {
+                        let shares = protocol_shares(&records, aggregate);
+                        if let Some(previous) = self.protocol_shares.get(&index) {
+                            let mut protocols =
+                                shares.keys().chain(previous.keys()).collect::<Vec<_>>();
+                            protocols.sort_unstable();
+                            protocols.dedup();
+                            for protocol in protocols {
+                                let change = (shares.get(protocol).unwrap_or(&0.0)
+                                    - previous.get(protocol).unwrap_or(&0.0))
+                                .abs();
+                                if change > max_change {
+                                    let key = vec![(KeyDimension::Protocol, *protocol)];
+                                    events.push(event(key, change, max_change));
+                                }
+                            }
+                        }
+                        self.protocol_shares.insert(index, shares);
+                    }
+                }
+            }
+        }
+        events
+    }
+}
+
+/// Share of every protocol in the total of the aggregate
+fn protocol_shares(
+    records: &[&AggregatedRecord],
+    aggregate: Aggregate,
+) -> HashMap<Option<KeyValue>, f64> {
+    let mut totals = HashMap::<_, u64>::new();
+    for record in records {
+        let protocol = record
+            .key()
+            .iter()
+            .find(|(dimension, _)| *dimension == KeyDimension::Protocol)
+            .and_then(|(_, value)| *value);
+        let total = totals.entry(protocol).or_default();
+        *total = total.saturating_add(record.value(aggregate).unwrap_or(0));
+    }
+    let total = totals.values().sum::<u64>();
+    if total == 0 {
+        return HashMap::new();
+    }
+    totals
+        .into_iter()
+        .map(|(protocol, value)| (protocol, value as f64 / total as f64))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::SocketAddr, time::Duration};
+
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::{
+        ie::{self, Field},
+        ipfix::{self, IpfixPacket},
+        DataSetId, FlowInfo,
+    };
+
+    use super::*;
+    use crate::aggregation::{AggregationConfig, FlowAggregator};
+
+    fn pkt(records: Vec<(u32, [u8; 4], u8, u64)>) -> FlowInfo {
+        FlowInfo::IPFIX(IpfixPacket::new(
+            Utc.timestamp_opt(0, 0).unwrap(),
+            0,
+            1,
+            vec![ipfix::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: records
+                    .into_iter()
+                    .map(|(end_seconds, destination, protocol, octets)| {
+                        ipfix::DataRecord::new(
+                            vec![],
+                            vec![
+                                Field::flowEndSeconds(ie::flowEndSeconds(
+                                    Utc.timestamp_opt(end_seconds as i64, 0).unwrap(),
+                                )),
+                                Field::destinationIPv4Address(ie::destinationIPv4Address(
+                                    destination.into(),
+                                )),
+                                Field::protocolIdentifier(ie::protocolIdentifier(protocol)),
+                                Field::octetDeltaCount(ie::octetDeltaCount(octets)),
+                            ],
+                        )
+                    })
+                    .collect(),
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_alerting() {
+        let exporter: SocketAddr = "192.0.2.1:4739".parse().unwrap();
+        let mut aggregator = FlowAggregator::new(AggregationConfig::new(
+            Duration::from_secs(10),
+            vec![KeyDimension::DestinationAddress, KeyDimension::Protocol],
+            vec![Aggregate::Octets],
+        ))
+        .unwrap();
+        let victim = KeyValue::Address([198, 51, 100, 1].into());
+        let mut alerting = ThresholdAlerting::new(vec![
+            AlertRule::new(
+                "volumetric".to_string(),
+                vec![],
+                AlertCondition::RateAbove {
+                    aggregate: Aggregate::Octets,
+                    threshold: 1000.0,
+                },
+            ),
+            AlertRule::new(
+                "protocol-mix".to_string(),
+                vec![(KeyDimension::DestinationAddress, victim)],
+                AlertCondition::ProtocolShift {
+                    aggregate: Aggregate::Octets,
+                    max_change: 0.5,
+                },
+            ),
+        ]);
+
+        let records = aggregator.push(
+            exporter,
+            &pkt(vec![
+                (1, [198, 51, 100, 1], 6, 9000),
+                (2, [198, 51, 100, 1], 17, 1000),
+                (3, [198, 51, 100, 2], 6, 500),
+                (12, [198, 51, 100, 1], 6, 1000),
+            ]),
+        );
+        assert!(alerting.evaluate(&records).is_empty());
+
+        // UDP traffic to the victim goes from 10% to 95% of the octets
+        let records = aggregator.push(
+            exporter,
+            &pkt(vec![(15, [198, 51, 100, 1], 17, 19000), (21, [0; 4], 6, 1)]),
+        );
+        let events = alerting.evaluate(&records);
+        assert!(events.iter().all(|event| event.window_start()
+            == Utc.timestamp_opt(10, 0).unwrap()
+            && event.window_end() == Utc.timestamp_opt(20, 0).unwrap()));
+        let summary = events
+            .iter()
+            .map(|event| {
+                (
+                    event.rule(),
+                    event.key().clone(),
+                    (event.observed() * 100.0).round() as u64,
+                )
+            })
+            .collect::<Vec<_>>();
+        let protocol = |x| (KeyDimension::Protocol, Some(KeyValue::Number(x)));
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "volumetric",
+                    vec![
+                        (KeyDimension::DestinationAddress, Some(victim)),
+                        protocol(17)
+                    ],
+                    190000
+                ),
+                ("protocol-mix", vec![protocol(6)], 85),
+                ("protocol-mix", vec![protocol(17)], 85),
+            ]
+        );
+    }
+}
@@ -0,0 +1,770 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IPFIX Exporting Process
+//! [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-10).
+//!
+//! [`IpfixExporter`] keeps the state the exporter is responsible for in every
+//! Observation Domain: allocating the template IDs, announcing the templates
+//! before the data records that use them, refreshing the templates
+//! periodically over UDP, withdrawing them over TCP, and counting the
+//! data records in the sequence number. The data records are packed into as
+//! few IPFIX messages as the maximum message size allows.
+//!
+//! The exporter itself doesn't do any I/O, [`IpfixExporter::export`] returns
+//! the IPFIX messages to send and [`IpfixExporter::encode`] serializes them.
+//! [`IpfixExporter::send_udp`] and [`IpfixExporter::send_tcp`] do both and
+//! send the result over a tokio socket.
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    net::UdpSocket,
+};
+
+use netgauze_flow_pkt::{
+    ipfix::{
+        DataRecord, DecodingTemplate, IpfixPacket, OptionsTemplateRecord, Set, TemplateRecord,
+        TemplatesMap,
+    },
+    template_cache::{TemplateCache, TemplateKind},
+    wire::serializer::{ipfix::IpfixPacketWritingError, SerializationOptions},
+    DataSetId, FieldSpecifier, DATA_SET_MIN_ID,
+};
+use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput, WritablePduWithTwoInputs};
+
+/// Template IDs 0-255 are reserved for the Set IDs
+pub const IPFIX_MIN_TEMPLATE_ID: u16 = DATA_SET_MIN_ID;
+
+/// Default value of `templateRefreshTimeout`, see
+/// [RFC6615](https://datatracker.ietf.org/doc/html/rfc6615)
+pub const DEFAULT_TEMPLATE_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+pub const DEFAULT_MTU: u16 = 1500;
+
+/// IPv6 header (the larger of IPv4 and IPv6 headers) and UDP header
+const UDP_OVERHEAD: usize = 40 + 8;
+
+/// 2-octets version, 2-octets length, 4-octets * 3 (export time, seq no,
+/// observation domain id)
+const MESSAGE_HEADER_LENGTH: usize = 16;
+
+/// 2-octets set id + 2-octet set length, and a worst case padding of 3 octets
+const SET_OVERHEAD: usize = 4 + 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExportTransport {
+    /// Templates are sent periodically and never withdrawn, messages are
+    /// limited by the path MTU
+    Udp,
+    /// Templates are sent once and withdrawn explicitly, messages are only
+    /// limited by the IPFIX length field
+    Tcp,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum IpfixExporterError {
+    IoError(String),
+    TemplateIdsExhausted {
+        observation_domain_id: u32,
+    },
+    UnknownTemplate {
+        observation_domain_id: u32,
+        template_id: u16,
+    },
+    /// A single record doesn't fit in an IPFIX message of the maximum size
+    RecordTooLarge {
+        template_id: u16,
+        length: usize,
+    },
+    WritingError(IpfixPacketWritingError),
+}
+
+impl From<std::io::Error> for IpfixExporterError {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(error.to_string())
+    }
+}
+
+impl From<IpfixPacketWritingError> for IpfixExporterError {
+    fn from(error: IpfixPacketWritingError) -> Self {
+        Self::WritingError(error)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ExportedTemplate {
+    kind: TemplateKind,
+    template: DecodingTemplate,
+    /// The template is sent at least once to the collector
+    announced: bool,
+}
+
+impl ExportedTemplate {
+    fn record(&self, template_id: u16) -> Record {
+        let (scope_fields, fields) = self.template.clone();
+        match self.kind {
+            TemplateKind::Template => Record::Template(TemplateRecord::new(template_id, fields)),
+            TemplateKind::OptionsTemplate => Record::OptionsTemplate(OptionsTemplateRecord::new(
+                template_id,
+                scope_fields,
+                fields,
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ObservationDomain {
+    next_template_id: u16,
+    sequence_number: u32,
+    templates: BTreeMap<u16, ExportedTemplate>,
+    withdrawals: Vec<(u16, TemplateKind)>,
+    last_refresh: Option<Instant>,
+}
+
+impl ObservationDomain {
+    const fn new() -> Self {
+        Self {
+            next_template_id: IPFIX_MIN_TEMPLATE_ID,
+            sequence_number: 0,
+            templates: BTreeMap::new(),
+            withdrawals: Vec::new(),
+            last_refresh: None,
+        }
+    }
+
+    /// Template IDs are allocated incrementally and wrap around, so a
+    /// withdrawn ID is reused as late as possible
+    fn allocate_template_id(&mut self) -> Option<u16> {
+        for _ in IPFIX_MIN_TEMPLATE_ID..=u16::MAX {
+            let template_id = self.next_template_id;
+            self.next_template_id = if template_id == u16::MAX {
+                IPFIX_MIN_TEMPLATE_ID
+            } else {
+                template_id + 1
+            };
+            if !self.templates.contains_key(&template_id) {
+                return Some(template_id);
+            }
+        }
+        None
+    }
+}
+
+/// Record to be packed in a set of the matching type
+#[derive(Debug, Clone)]
+enum Record {
+    Template(TemplateRecord),
+    OptionsTemplate(OptionsTemplateRecord),
+    Data(DataSetId, DataRecord),
+}
+
+impl Record {
+    fn set_id(&self) -> u16 {
+        match self {
+            Self::Template(_) => Set::Template(vec![]).id(),
+            Self::OptionsTemplate(_) => Set::OptionsTemplate(vec![]).id(),
+            Self::Data(id, _) => **id,
+        }
+    }
+}
+
+/// IPFIX Exporting Process, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct IpfixExporter {
+    transport: ExportTransport,
+    mtu: u16,
+    template_refresh_interval: Duration,
+    serialization_options: SerializationOptions,
+    domains: HashMap<u32, ObservationDomain>,
+}
+
+impl IpfixExporter {
+    pub fn new(transport: ExportTransport) -> Self {
+        Self {
+            transport,
+            mtu: DEFAULT_MTU,
+            template_refresh_interval: DEFAULT_TEMPLATE_REFRESH_INTERVAL,
+            serialization_options: SerializationOptions::new().with_align_sets(true),
+            domains: HashMap::new(),
+        }
+    }
+
+    pub const fn transport(&self) -> ExportTransport {
+        self.transport
+    }
+
+    /// Path MTU, only used to limit the message size over UDP
+    pub const fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    pub fn set_mtu(&mut self, mtu: u16) {
+        self.mtu = mtu;
+    }
+
+    /// Interval to resend all the templates over UDP
+    pub const fn template_refresh_interval(&self) -> Duration {
+        self.template_refresh_interval
+    }
+
+    pub fn set_template_refresh_interval(&mut self, interval: Duration) {
+        self.template_refresh_interval = interval;
+    }
+
+    /// Options used by [`IpfixExporter::encode`], sets are padded by default
+    pub const fn serialization_options(&self) -> SerializationOptions {
+        self.serialization_options
+    }
+
+    /// The templates are already announced by the exporter, inlining them
+    /// again makes the messages larger than [`IpfixExporter::max_message_size`]
+    pub fn set_serialization_options(&mut self, options: SerializationOptions) {
+        self.serialization_options = options;
+    }
+
+    /// Maximum length of an IPFIX message
+    pub fn max_message_size(&self) -> usize {
+        match self.transport {
+            ExportTransport::Udp => (self.mtu as usize).saturating_sub(UDP_OVERHEAD),
+            ExportTransport::Tcp => u16::MAX as usize,
+        }
+    }
+
+    /// Sequence number of the next IPFIX message in the Observation Domain
+    pub fn sequence_number(&self, observation_domain_id: u32) -> u32 {
+        self.domains
+            .get(&observation_domain_id)
+            .map(|domain| domain.sequence_number)
+            .unwrap_or_default()
+    }
+
+    /// Add a template to the Observation Domain and return its ID. Adding an
+    /// identical template returns the ID of the existing one.
+    pub fn add_template(
+        &mut self,
+        observation_domain_id: u32,
+        field_specifiers: Vec<FieldSpecifier>,
+    ) -> Result<u16, IpfixExporterError> {
+        self.add(
+            observation_domain_id,
+            TemplateKind::Template,
+            (vec![], field_specifiers),
+        )
+    }
+
+    /// Add an options template to the Observation Domain and return its ID.
+    /// Adding an identical options template returns the ID of the existing
+    /// one.
+    pub fn add_options_template(
+        &mut self,
+        observation_domain_id: u32,
+        scope_field_specifiers: Vec<FieldSpecifier>,
+        field_specifiers: Vec<FieldSpecifier>,
+    ) -> Result<u16, IpfixExporterError> {
+        self.add(
+            observation_domain_id,
+            TemplateKind::OptionsTemplate,
+            (scope_field_specifiers, field_specifiers),
+        )
+    }
+
+    fn add(
+        &mut self,
+        observation_domain_id: u32,
+        kind: TemplateKind,
+        template: DecodingTemplate,
+    ) -> Result<u16, IpfixExporterError> {
+        let domain = self
+            .domains
+            .entry(observation_domain_id)
+            .or_insert_with(ObservationDomain::new);
+        if let Some((template_id, _)) = domain
+            .templates
+            .iter()
+            .find(|(_, exported)| exported.kind == kind && exported.template == template)
+        {
+            return Ok(*template_id);
+        }
+        let template_id =
+            domain
+                .allocate_template_id()
+                .ok_or(IpfixExporterError::TemplateIdsExhausted {
+                    observation_domain_id,
+                })?;
+        domain.templates.insert(
+            template_id,
+            ExportedTemplate {
+                kind,
+                template,
+                announced: false,
+            },
+        );
+        Ok(template_id)
+    }
+
+    /// Remove a template from the Observation Domain. Over TCP, the
+    /// withdrawal is sent with the next messages. Over UDP, template
+    /// withdrawals must not be sent and the collector lets the template
+    /// expire.
+    pub fn withdraw_template(
+        &mut self,
+        observation_domain_id: u32,
+        template_id: u16,
+    ) -> Result<(), IpfixExporterError> {
+        let exported = self
+            .domains
+            .get_mut(&observation_domain_id)
+            .and_then(|domain| domain.templates.remove(&template_id))
+            .ok_or(IpfixExporterError::UnknownTemplate {
+                observation_domain_id,
+                template_id,
+            })?;
+        if self.transport == ExportTransport::Tcp && exported.announced {
+            if let Some(domain) = self.domains.get_mut(&observation_domain_id) {
+                domain.withdrawals.push((template_id, exported.kind));
+            }
+        }
+        Ok(())
+    }
+
+    /// The template records that are due: the withdrawals, the templates
+    /// that are not sent yet, and all the templates when the refresh
+    /// interval elapsed over UDP
+    fn due_templates(&mut self, observation_domain_id: u32, now: Instant) -> Vec<Record> {
+        let transport = self.transport;
+        let refresh_interval = self.template_refresh_interval;
+        let domain = match self.domains.get_mut(&observation_domain_id) {
+            Some(domain) => domain,
+            None => return vec![],
+        };
+        let refresh = transport == ExportTransport::Udp
+            && domain.last_refresh.is_none_or(|last_refresh| {
+                now.saturating_duration_since(last_refresh) >= refresh_interval
+            });
+        if refresh {
+            domain.last_refresh = Some(now);
+        }
+        let mut records = domain
+            .withdrawals
+            .drain(..)
+            .map(|(template_id, kind)| match kind {
+                TemplateKind::Template => {
+                    Record::Template(TemplateRecord::new(template_id, vec![]))
+                }
+                TemplateKind::OptionsTemplate => {
+                    Record::OptionsTemplate(OptionsTemplateRecord::new(template_id, vec![], vec![]))
+                }
+            })
+            .collect::<Vec<_>>();
+        for (template_id, exported) in &mut domain.templates {
+            if refresh || !exported.announced {
+                exported.announced = true;
+                records.push(exported.record(*template_id));
+            }
+        }
+        records
+    }
+
+    /// IPFIX messages carrying the due templates, if any, followed by the
+    /// data records of the given template
+    pub fn export(
+        &mut self,
+        observation_domain_id: u32,
+        template_id: u16,
+        records: Vec<DataRecord>,
+        export_time: DateTime<Utc>,
+        now: Instant,
+    ) -> Result<Vec<IpfixPacket>, IpfixExporterError> {
+        let decoding_template = self
+            .domains
+            .get(&observation_domain_id)
+            .and_then(|domain| domain.templates.get(&template_id))
+            .map(|exported| Rc::new(exported.template.clone()))
+            .ok_or(IpfixExporterError::UnknownTemplate {
+                observation_domain_id,
+                template_id,
+            })?;
+        // Template IDs are always valid data set IDs
+        let data_set_id = DataSetId::new(template_id).expect("invalid template id");
+        let max_message_size = self.max_message_size();
+        let mut sized = vec![];
+        for record in records {
+            let length = record.len(Some(Rc::clone(&decoding_template)));
+            if MESSAGE_HEADER_LENGTH + SET_OVERHEAD + length > max_message_size {
+                return Err(IpfixExporterError::RecordTooLarge {
+                    template_id,
+                    length,
+                });
+            }
+            sized.push((Record::Data(data_set_id, record), length));
+        }
+        let mut packed = self.sized_templates(observation_domain_id, now)?;
+        packed.extend(sized);
+        Ok(self.pack(observation_domain_id, packed, export_time))
+    }
+
+    /// IPFIX messages carrying only the due templates, used to refresh the
+    /// templates when there's no data to export
+    pub fn flush_templates(
+        &mut self,
+        observation_domain_id: u32,
+        export_time: DateTime<Utc>,
+        now: Instant,
+    ) -> Result<Vec<IpfixPacket>, IpfixExporterError> {
+        let packed = self.sized_templates(observation_domain_id, now)?;
+        Ok(self.pack(observation_domain_id, packed, export_time))
+    }
+
+    fn sized_templates(
+        &mut self,
+        observation_domain_id: u32,
+        now: Instant,
+    ) -> Result<Vec<(Record, usize)>, IpfixExporterError> {
+        let max_message_size = self.max_message_size();
+        self.due_templates(observation_domain_id, now)
+            .into_iter()
+            .map(|record| {
+                let (template_id, length) = match &record {
+                    Record::Template(template) => (template.id(), template.len()),
+                    Record::OptionsTemplate(template) => (template.id(), template.len()),
+                    Record::Data(_, _) => unreachable!("only templates are due"),
+                };
+                if MESSAGE_HEADER_LENGTH + SET_OVERHEAD + length > max_message_size {
+                    Err(IpfixExporterError::RecordTooLarge {
+                        template_id,
+                        length,
+                    })
+                } else {
+                    Ok((record, length))
+                }
+            })
+            .collect()
+    }
+
+    /// Pack the records in order into sets and messages that don't exceed
+    /// the maximum message size
+    fn pack(
+        &mut self,
+        observation_domain_id: u32,
+        records: Vec<(Record, usize)>,
+        export_time: DateTime<Utc>,
+    ) -> Vec<IpfixPacket> {
+        let max_message_size = self.max_message_size();
+        let mut messages: Vec<Vec<Set>> = vec![];
+        let mut current: Vec<Set> = vec![];
+        let mut current_length = MESSAGE_HEADER_LENGTH;
+        for (record, length) in records {
+            let same_set = current
+                .last()
+                .is_some_and(|set| set.id() == record.set_id());
+            if !(same_set && current_length + length <= max_message_size) {
+                if current_length + SET_OVERHEAD + length > max_message_size {
+                    messages.push(std::mem::take(&mut current));
+                    current_length = MESSAGE_HEADER_LENGTH;
+                }
+                current_length += SET_OVERHEAD;
+                current.push(match &record {
+                    Record::Template(_) => Set::Template(vec![]),
+                    Record::OptionsTemplate(_) => Set::OptionsTemplate(vec![]),
+                    Record::Data(id, _) => Set::Data {
+                        id: *id,
+                        records: vec![],
+                    },
+                });
+            }
+            current_length += length;
+            match (current.last_mut(), record) {
+                (Some(Set::Template(records)), Record::Template(record)) => records.push(record),
+                (Some(Set::OptionsTemplate(records)), Record::OptionsTemplate(record)) => {
+                    records.push(record)
+                }
+                (Some(Set::Data { records, .. }), Record::Data(_, record)) => records.push(record),
+                _ => unreachable!("the last set matches the record type"),
+            }
+        }
+        if !current.is_empty() {
+            messages.push(current);
+        }
+
+        let domain = self
+            .domains
+            .entry(observation_domain_id)
+            .or_insert_with(ObservationDomain::new);
+        messages
+            .into_iter()
+            .map(|sets| {
+                let data_records = sets
+                    .iter()
+                    .map(|set| match set {
+                        Set::Data { records, .. } => records.len() as u32,
+                        _ => 0,
+                    })
+                    .sum::<u32>();
+                let packet = IpfixPacket::new(
+                    export_time,
+                    domain.sequence_number,
+                    observation_domain_id,
+                    sets,
+                );
+                domain.sequence_number = domain.sequence_number.wrapping_add(data_records);
+                packet
+            })
+            .collect()
+    }
+
+    /// Serialize an IPFIX message using the templates of its Observation
+    /// Domain
+    pub fn encode(&self, packet: &IpfixPacket) -> Result<Vec<u8>, IpfixExporterError> {
+        let mut cache = TemplateCache::new();
+        cache.set_observation_domain_id(packet.observation_domain_id());
+        if let Some(domain) = self.domains.get(&packet.observation_domain_id()) {
+            for (template_id, exported) in &domain.templates {
+                cache.insert(
+                    *template_id,
+                    exported.kind,
+                    Rc::new(exported.template.clone()),
+                );
+            }
+        }
+        let templates_map: TemplatesMap = Rc::new(RefCell::new(cache));
+        let options = self.serialization_options;
+        let mut buf = Vec::with_capacity(<IpfixPacket as WritablePduWithTwoInputs<_, _, _>>::len(
+            packet,
+            Some(Rc::clone(&templates_map)),
+            options,
+        ));
+        <IpfixPacket as WritablePduWithTwoInputs<_, _, _>>::write(
+            packet,
+            &mut buf,
+            Some(templates_map),
+            options,
+        )?;
+        Ok(buf)
+    }
+
+    fn export_encoded(
+        &mut self,
+        observation_domain_id: u32,
+        template_id: u16,
+        records: Vec<DataRecord>,
+    ) -> Result<Vec<Vec<u8>>, IpfixExporterError> {
+        let packets = self.export(
+            observation_domain_id,
+            template_id,
+            records,
+            Utc::now(),
+            Instant::now(),
+        )?;
+        packets.iter().map(|packet| self.encode(packet)).collect()
+    }
+
+    /// Export the data records over a connected UDP socket, returns the
+    /// number of sent messages
+    pub async fn send_udp(
+        &mut self,
+        socket: &UdpSocket,
+        observation_domain_id: u32,
+        template_id: u16,
+        records: Vec<DataRecord>,
+    ) -> Result<usize, IpfixExporterError> {
+        let messages = self.export_encoded(observation_domain_id, template_id, records)?;
+        for message in &messages {
+            socket.send(message).await?;
+        }
+        Ok(messages.len())
+    }
+
+    /// Export the data records over a TCP stream, returns the number of sent
+    /// messages
+    pub async fn send_tcp<W: AsyncWrite + Unpin>(
+        &mut self,
+        writer: &mut W,
+        observation_domain_id: u32,
+        template_id: u16,
+        records: Vec<DataRecord>,
+    ) -> Result<usize, IpfixExporterError> {
+        let messages = self.export_encoded(observation_domain_id, template_id, records)?;
+        for message in &messages {
+            writer.write_all(message).await?;
+        }
+        writer.flush().await?;
+        Ok(messages.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::ie;
+    use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+    use std::net::Ipv4Addr;
+
+    fn fields() -> Vec<FieldSpecifier> {
+        vec![
+            FieldSpecifier::new(ie::IE::sourceIPv4Address, 4).unwrap(),
+            FieldSpecifier::new(ie::IE::octetDeltaCount, 8).unwrap(),
+        ]
+    }
+
+    fn record(count: u64) -> DataRecord {
+        DataRecord::new(
+            vec![],
+            vec![
+                ie::Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::new(10, 0, 0, 1))),
+                ie::Field::octetDeltaCount(ie::octetDeltaCount(count)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_udp_export() {
+        let export_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let now = Instant::now();
+        let mut exporter = IpfixExporter::new(ExportTransport::Udp);
+        exporter.set_mtu(200);
+        let template_id = exporter.add_template(1, fields()).unwrap();
+        assert_eq!(template_id, IPFIX_MIN_TEMPLATE_ID);
+        assert_eq!(exporter.add_template(1, fields()), Ok(template_id));
+        assert_eq!(exporter.add_template(2, fields()), Ok(template_id));
+
+        let records = (0..20).map(record).collect::<Vec<_>>();
+        let packets = exporter
+            .export(1, template_id, records.clone(), export_time, now)
+            .unwrap();
+        assert_eq!(
+            packets
+                .iter()
+                .map(|packet| packet.sequence_number())
+                .collect::<Vec<_>>(),
+            vec![0, 9, 19]
+        );
+        assert_eq!(exporter.sequence_number(1), 20);
+        assert_eq!(exporter.sequence_number(2), 0);
+        assert_eq!(
+            packets[0].sets()[0],
+            Set::Template(vec![TemplateRecord::new(template_id, fields())])
+        );
+
+        let exported = packets
+            .iter()
+            .flat_map(|packet| packet.sets())
+            .filter_map(|set| match set {
+                Set::Data { records, .. } => Some(records.clone()),
+                _ => None,
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        assert_eq!(exported, records);
+
+        // The messages fit in the MTU and the collector learns the template
+        // from the first one
+        let templates_map = TemplatesMap::default();
+        for packet in &packets {
+            let wire = exporter.encode(packet).unwrap();
+            assert!(wire.len() <= exporter.max_message_size());
+            assert_eq!(u16::from_be_bytes([wire[2], wire[3]]) as usize, wire.len());
+            let (_, parsed) =
+                IpfixPacket::from_wire(Span::new(&wire), templates_map.clone()).unwrap();
+            assert_eq!(parsed.sequence_number(), packet.sequence_number());
+        }
+
+        // Templates are only resent after the refresh interval
+        let packets = exporter
+            .export(1, template_id, vec![record(20)], export_time, now)
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].sets().len(), 1);
+        assert!(exporter
+            .flush_templates(1, export_time, now)
+            .unwrap()
+            .is_empty());
+        let refresh = now + DEFAULT_TEMPLATE_REFRESH_INTERVAL;
+        let packets = exporter.flush_templates(1, export_time, refresh).unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].sequence_number(), 21);
+
+        // Withdrawals are not sent over UDP
+        assert_eq!(exporter.withdraw_template(1, template_id), Ok(()));
+        assert!(exporter
+            .flush_templates(1, export_time, refresh)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            exporter.export(1, template_id, vec![], export_time, refresh),
+            Err(IpfixExporterError::UnknownTemplate {
+                observation_domain_id: 1,
+                template_id
+            })
+        );
+    }
+
+    #[test]
+    fn test_tcp_export() {
+        let export_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let now = Instant::now();
+        let mut exporter = IpfixExporter::new(ExportTransport::Tcp);
+        exporter.set_mtu(100);
+        assert_eq!(exporter.max_message_size(), u16::MAX as usize);
+        let template_id = exporter.add_template(1, fields()).unwrap();
+        let options_template_id = exporter
+            .add_options_template(1, fields()[..1].to_vec(), fields()[1..].to_vec())
+            .unwrap();
+        assert_eq!(options_template_id, template_id + 1);
+
+        let records = (0..100).map(record).collect::<Vec<_>>();
+        let packets = exporter
+            .export(1, template_id, records, export_time, now)
+            .unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(packets[0].sets().len(), 3);
+
+        // Templates are never refreshed over TCP, but withdrawn
+        let later = now + DEFAULT_TEMPLATE_REFRESH_INTERVAL;
+        assert!(exporter
+            .flush_templates(1, export_time, later)
+            .unwrap()
+            .is_empty());
+        exporter.withdraw_template(1, options_template_id).unwrap();
+        let packets = exporter.flush_templates(1, export_time, later).unwrap();
+        assert_eq!(
+            packets,
+            vec![IpfixPacket::new(
+                export_time,
+                100,
+                1,
+                vec![Set::OptionsTemplate(vec![OptionsTemplateRecord::new(
+                    options_template_id,
+                    vec![],
+                    vec![]
+                )])]
+            )]
+        );
+        assert_eq!(
+            exporter.add_template(1, fields()[..1].to_vec()),
+            Ok(options_template_id + 1)
+        );
+    }
+}
@@ -13,4 +13,15 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-// TODO
+pub mod acl;
+pub mod aggregation;
+pub mod alerting;
+pub mod exporter;
+pub mod mediator;
+pub mod persistence;
+#[cfg(feature = "pcap")]
+pub mod replay;
+#[cfg(feature = "routing")]
+pub mod routing;
+pub mod server;
+pub mod topn;
@@ -0,0 +1,333 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IPFIX Mediator re-exporting the collected flows to downstream collectors,
+//! see [RFC6183](https://datatracker.ietf.org/doc/html/rfc6183).
+//!
+//! [`IpfixMediator`] takes the data records of the collected IPFIX and
+//! NetFlow V9 messages, and re-exports them in its own Observation Domain
+//! with an [`IpfixExporter`]. The templates of the original exporters are not
+//! reused, the mediator derives its own templates from the fields of the
+//! records. The records can be limited to a selection of Information
+//! Elements, see [`IpfixMediator::with_fields`].
+//!
+//! NetFlow V9 records are converted to IPFIX first, see
+//! [`NetFlowV9Converter`], and the fields that aren't decoded, e.g., from an
+//! unknown template, are dropped since their Information Element is unknown.
+
+use std::{borrow::Cow, time::Instant};
+
+use chrono::{DateTime, Utc};
+
+use netgauze_flow_pkt::{
+    convert::{NetFlowV9ConversionError, NetFlowV9Converter},
+    ie::{Field, InformationElementTemplate, IE},
+    ipfix::{DataRecord, IpfixPacket, Set},
+    FieldSpecifier, FieldSpecifierError, FlowInfo,
+};
+use netgauze_parse_utils::WritablePduWithOneInput;
+
+use crate::exporter::{ExportTransport, IpfixExporter, IpfixExporterError};
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum IpfixMediatorError {
+    NetFlowV9ConversionError(NetFlowV9ConversionError),
+    FieldSpecifierError(FieldSpecifierError),
+    ExporterError(IpfixExporterError),
+}
+
+impl From<NetFlowV9ConversionError> for IpfixMediatorError {
+    fn from(error: NetFlowV9ConversionError) -> Self {
+        Self::NetFlowV9ConversionError(error)
+    }
+}
+
+impl From<FieldSpecifierError> for IpfixMediatorError {
+    fn from(error: FieldSpecifierError) -> Self {
+        Self::FieldSpecifierError(error)
+    }
+}
+
+impl From<IpfixExporterError> for IpfixMediatorError {
+    fn from(error: IpfixExporterError) -> Self {
+        Self::ExporterError(error)
+    }
+}
+
+/// IPFIX Mediator, see the [module docs](self)
+#[derive(Debug, Clone)]
+pub struct IpfixMediator {
+    exporter: IpfixExporter,
+    observation_domain_id: u32,
+    fields: Option<Vec<IE>>,
+    converter: NetFlowV9Converter,
+}
+
+impl IpfixMediator {
+    /// Re-export all the records in the `observation_domain_id`
+    pub fn new(transport: ExportTransport, observation_domain_id: u32) -> Self {
+        Self {
+            exporter: IpfixExporter::new(transport),
+            observation_domain_id,
+            fields: None,
+            converter: NetFlowV9Converter::new(),
+        }
+    }
+
+    /// Only re-export the given Information Elements of the data records, in
+    /// this order. The records without any of them are dropped. The options
+    /// data records are re-exported with all their fields, since their scope
+    /// is what identifies them.
+    pub fn with_fields(mut self, fields: Vec<IE>) -> Self {
+        self.fields = Some(fields);
+        self
+    }
+
+    pub const fn observation_domain_id(&self) -> u32 {
+        self.observation_domain_id
+    }
+
+    pub const fn fields(&self) -> Option<&Vec<IE>> {
+        self.fields.as_ref()
+    }
+
+    /// The exporter managing the templates of the mediator, used to encode
+    /// the IPFIX messages
+    pub const fn exporter(&self) -> &IpfixExporter {
+        &self.exporter
+    }
+
+    /// Used to change the MTU or the template refresh interval
+    pub fn exporter_mut(&mut self) -> &mut IpfixExporter {
+        &mut self.exporter
+    }
+
+    /// IPFIX messages re-exporting the data records of a collected message,
+    /// preceded by the due templates of the mediator
+    pub fn process(
+        &mut self,
+        msg: &FlowInfo,
+        export_time: DateTime<Utc>,
+        now: Instant,
+    ) -> Result<Vec<IpfixPacket>, IpfixMediatorError> {
+        let pkt = match msg {
+            FlowInfo::IPFIX(pkt) => Cow::Borrowed(pkt),
+            FlowInfo::NetFlowV9(pkt) => Cow::Owned(self.converter.convert(pkt)?),
+        };
+        let mut exported: Vec<(u16, Vec<DataRecord>)> = vec![];
+        for set in pkt.sets() {
+            let Set::Data { records, .. } = set else {
+                continue;
+            };
+            for record in records {
+                let Some(record) = self.mediated_record(record) else {
+                    continue;
+                };
+                let template_id = self.template_id(&record)?;
+                match exported.iter_mut().find(|(id, _)| *id == template_id) {
+                    Some((_, records)) => records.push(record),
+                    None => exported.push((template_id, vec![record])),
+                }
+            }
+        }
+        let mut packets = vec![];
+        for (template_id, records) in exported {
+            packets.extend(self.exporter.export(
+                self.observation_domain_id,
+                template_id,
+                records,
+                export_time,
+                now,
+            )?);
+        }
+        Ok(packets)
+    }
+
+    /// IPFIX messages carrying only the due templates, used to refresh the
+    /// templates when there's no data to re-export
+    pub fn flush_templates(
+        &mut self,
+        export_time: DateTime<Utc>,
+        now: Instant,
+    ) -> Result<Vec<IpfixPacket>, IpfixMediatorError> {
+        Ok(self
+            .exporter
+            .flush_templates(self.observation_domain_id, export_time, now)?)
+    }
+
+    /// The record with only the selected fields that have a known
+    /// Information Element
+    fn mediated_record(&self, record: &DataRecord) -> Option<DataRecord> {
+        let known = |fields: &Vec<Field>| {
+            fields
+                .iter()
+                .filter(|field| field.ie().is_some())
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let scope_fields = known(record.scope_fields());
+        let fields = match &self.fields {
+            Some(selection) if scope_fields.is_empty() => selection
+                .iter()
+                .filter_map(|ie| {
+                    record
+                        .fields()
+                        .iter()
+                        .find(|field| field.ie() == Some(*ie))
+                        .cloned()
+                })
+                .collect(),
+            _ => known(record.fields()),
+        };
+        if scope_fields.is_empty() && fields.is_empty() {
+            return None;
+        }
+        Some(DataRecord::new(scope_fields, fields))
+    }
+
+    /// The ID of the template matching the record fields, the template is
+    /// added to the exporter if it's new
+    fn template_id(&mut self, record: &DataRecord) -> Result<u16, IpfixMediatorError> {
+        let scope_field_specifiers = field_specifiers(record.scope_fields())?;
+        let field_specifiers = field_specifiers(record.fields())?;
+        let template_id = if scope_field_specifiers.is_empty() {
+            self.exporter
+                .add_template(self.observation_domain_id, field_specifiers)?
+        } else {
+            self.exporter.add_options_template(
+                self.observation_domain_id,
+                scope_field_specifiers,
+                field_specifiers,
+            )?
+        };
+        Ok(template_id)
+    }
+}
+
+/// The variable length Information Elements are always encoded with a
+/// variable length, so records with values of different lengths share the
+/// same template.
+fn field_specifiers(fields: &[Field]) -> Result<Vec<FieldSpecifier>, FieldSpecifierError> {
+    fields
+        .iter()
+        .filter_map(|field| field.ie().map(|ie| (ie, field)))
+        .map(|(ie, field)| {
+            let length = match ie.length_range() {
+                Some(_) => field.len(None) as u16,
+                None => u16::MAX,
+            };
+            FieldSpecifier::new(ie, length)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::{ie, ipfix::TemplatesMap, DataSetId};
+    use netgauze_parse_utils::{ReadablePduWithOneInput, Span};
+
+    use super::*;
+
+    fn data_record(source: [u8; 4], octets: u64, application: &str) -> DataRecord {
+        DataRecord::new(
+            vec![],
+            vec![
+                Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::from(source))),
+                Field::octetDeltaCount(ie::octetDeltaCount(octets)),
+                Field::applicationName(ie::applicationName(application.to_string())),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_mediator() {
+        let export_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let now = Instant::now();
+        let mut mediator = IpfixMediator::new(ExportTransport::Udp, 100)
+            .with_fields(vec![IE::applicationName, IE::octetDeltaCount]);
+        let collected = FlowInfo::IPFIX(IpfixPacket::new(
+            export_time,
+            0,
+            1,
+            vec![
+                Set::Data {
+                    id: DataSetId::new(256).unwrap(),
+                    records: vec![
+                        data_record([192, 0, 2, 1], 100, "http"),
+                        data_record([192, 0, 2, 2], 200, "dns"),
+                        DataRecord::new(
+                            vec![],
+                            vec![Field::ingressInterface(ie::ingressInterface(1))],
+                        ),
+                    ],
+                },
+                Set::Data {
+                    id: DataSetId::new(257).unwrap(),
+                    records: vec![DataRecord::new(
+                        vec![Field::ingressInterface(ie::ingressInterface(1))],
+                        vec![Field::interfaceName(ie::interfaceName("eth0".to_string()))],
+                    )],
+                },
+            ],
+        ));
+        let packets = mediator.process(&collected, export_time, now).unwrap();
+        assert_eq!(packets.len(), 2);
+        assert!(packets
+            .iter()
+            .all(|packet| packet.observation_domain_id() == 100));
+
+        // A collector decodes the records with the templates of the mediator
+        let templates_map = TemplatesMap::default();
+        templates_map.borrow_mut().set_strict(true);
+        let mut records = vec![];
+        for packet in &packets {
+            let wire = mediator.exporter().encode(packet).unwrap();
+            let (_, parsed) =
+                IpfixPacket::from_wire(Span::new(&wire), templates_map.clone()).unwrap();
+            for set in parsed.sets() {
+                if let Set::Data { records: data, .. } = set {
+                    records.extend(data.clone());
+                }
+            }
+        }
+        assert_eq!(
+            records,
+            vec![
+                DataRecord::new(
+                    vec![],
+                    vec![
+                        Field::applicationName(ie::applicationName("http".to_string())),
+                        Field::octetDeltaCount(ie::octetDeltaCount(100)),
+                    ]
+                ),
+                DataRecord::new(
+                    vec![],
+                    vec![
+                        Field::applicationName(ie::applicationName("dns".to_string())),
+                        Field::octetDeltaCount(ie::octetDeltaCount(200)),
+                    ]
+                ),
+                DataRecord::new(
+                    vec![Field::ingressInterface(ie::ingressInterface(1))],
+                    vec![Field::interfaceName(ie::interfaceName("eth0".to_string()))],
+                ),
+            ]
+        );
+        assert_eq!(mediator.exporter().sequence_number(100), 3);
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Persistence of the state learned from the exporters across restarts.
+//!
+//! Some exporters announce their templates only every few minutes, and the
+//! data records received in the meantime can't be decoded. The collector
+//! periodically saves a snapshot of its templates and options data, e.g.,
+//! [`netgauze_flow_pkt::codec::FlowInfoCodec::snapshot`], with a
+//! [`SnapshotWriter`], and restores it with [`load_snapshot`] on start.
+//! Snapshots are stored as JSON.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Load a snapshot saved by [`SnapshotWriter`], [`None`] if there is no
+/// snapshot yet
+pub fn load_snapshot<S: DeserializeOwned>(path: impl AsRef<Path>) -> io::Result<Option<S>> {
+    let data = match fs::read(path) {
+        Ok(data) => data,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    serde_json::from_slice(&data)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+/// Save snapshots to a file at most once every `interval`
+#[derive(Debug, Clone)]
+pub struct SnapshotWriter {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Option<Instant>,
+}
+
+impl SnapshotWriter {
+    pub fn new(path: impl Into<PathBuf>, interval: Duration) -> Self {
+        Self {
+            path: path.into(),
+            interval,
+            last_saved: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub const fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Save the snapshot returned by `snapshot` if the interval elapsed since
+    /// the last save, returns `true` if it was saved. Meant to be called from
+    /// the decoding loop, so the snapshot is only taken when it's needed.
+    pub fn maybe_save<S: Serialize>(
+        &mut self,
+        now: Instant,
+        snapshot: impl FnOnce() -> S,
+    ) -> io::Result<bool> {
+        if self
+            .last_saved
+            .is_some_and(|last_saved| now.duration_since(last_saved) < self.interval)
+        {
+            return Ok(false);
+        }
+        self.save(&snapshot())?;
+        self.last_saved = Some(now);
+        Ok(true)
+    }
+
+    /// Save the snapshot now, e.g., on shutdown. The snapshot is written to a
+    /// temporary file first, so a crash while saving doesn't corrupt the
+    /// previous snapshot.
+    pub fn save<S: Serialize>(&self, snapshot: &S) -> io::Result<()> {
+        let data = serde_json::to_vec(snapshot)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use chrono::Utc;
+    use tokio_util::codec::Decoder;
+
+    use netgauze_flow_pkt::{codec::FlowInfoCodec, ie, FieldSpecifier, FlowInfo};
+
+    use super::*;
+    use crate::exporter::{ExportTransport, IpfixExporter};
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut exporter = IpfixExporter::new(ExportTransport::Udp);
+        let template_id = exporter
+            .add_template(
+                1,
+                vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+            )
+            .unwrap();
+        let record = netgauze_flow_pkt::ipfix::DataRecord::new(
+            vec![],
+            vec![ie::Field::ingressInterface(ie::ingressInterface(10))],
+        );
+        let now = Instant::now();
+        let templates = exporter.flush_templates(1, Utc::now(), now).unwrap();
+        let template_wire = exporter.encode(&templates[0]).unwrap();
+        let packets = exporter
+            .export(1, template_id, vec![record], Utc::now(), now)
+            .unwrap();
+
+        let mut codec = FlowInfoCodec::default();
+        codec
+            .decode(&mut BytesMut::from(template_wire.as_slice()))
+            .unwrap();
+
+        let path = std::env::temp_dir().join(format!("flow-snapshot-{}.json", std::process::id()));
+        let mut writer = SnapshotWriter::new(&path, Duration::from_secs(60));
+        assert!(writer.maybe_save(now, || codec.snapshot()).unwrap());
+        assert!(!writer
+            .maybe_save(now + Duration::from_secs(1), || codec.snapshot())
+            .unwrap());
+
+        // A restarted collector decodes the data records right away
+        let mut restarted = FlowInfoCodec::default();
+        restarted.restore(load_snapshot(&path).unwrap().unwrap());
+        assert_eq!(restarted.snapshot(), codec.snapshot());
+        let data_wire = exporter.encode(&packets[0]).unwrap();
+        assert!(FlowInfoCodec::default()
+            .decode(&mut BytesMut::from(data_wire.as_slice()))
+            .is_err());
+        let msg = restarted
+            .decode(&mut BytesMut::from(data_wire.as_slice()))
+            .unwrap();
+        assert!(matches!(msg, Some(FlowInfo::IPFIX(_))));
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(load_snapshot::<()>(&path).unwrap(), None);
+    }
+}
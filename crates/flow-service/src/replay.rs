@@ -0,0 +1,183 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Replay of the flow packets in pcap and pcapng captures (with the `pcap`
+//! feature), for offline analysis and regression testing with real captures.
+//!
+//! [`FlowReplay`] extracts the payloads sent to the configured ports and
+//! decodes them the same way the collector does: a [`FlowInfoCodec`] per
+//! exporter for NetFlow V9 and IPFIX over UDP, and an [`IpfixStreamCodec`] per
+//! connection for IPFIX over TCP. The decoded messages are the same
+//! [`FlowRequest`] received from [`crate::server::FlowTcpServer`], so they can
+//! be sent to the rest of the pipeline.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io,
+    net::SocketAddr,
+    path::Path,
+};
+
+use bytes::BytesMut;
+use pcap_parser::create_reader;
+use tokio_util::codec::Decoder;
+
+use netgauze_flow_pkt::codec::{FlowInfoCodec, IpfixStreamCodec};
+use netgauze_pcap_reader::{PcapIter, TransportProtocol};
+
+use crate::server::FlowRequest;
+
+/// Size of the buffer used to read the capture files
+const READ_BUFFER_SIZE: usize = 165536;
+
+/// Iterator over the flow messages decoded from a capture, see the
+/// [module](self) docs
+pub struct FlowReplay<'a> {
+    packets: PcapIter<'a>,
+    ports: Vec<u16>,
+    udp_codecs: HashMap<(SocketAddr, SocketAddr), FlowInfoCodec>,
+    tcp_codecs: HashMap<(SocketAddr, SocketAddr), (IpfixStreamCodec, BytesMut)>,
+    pending: VecDeque<FlowRequest>,
+}
+
+impl<'a> FlowReplay<'a> {
+    /// Replay the packets sent to any of the `ports`
+    pub fn new(packets: PcapIter<'a>, ports: Vec<u16>) -> Self {
+        Self {
+            packets,
+            ports,
+            udp_codecs: HashMap::new(),
+            tcp_codecs: HashMap::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn ports(&self) -> &[u16] {
+        &self.ports
+    }
+
+    /// Decode the UDP datagram, only whole packets are expected
+    fn decode_udp(&mut self, exporter: SocketAddr, collector: SocketAddr, payload: &[u8]) {
+        let codec = self
+            .udp_codecs
+            .entry((exporter, collector))
+            .or_insert_with(|| {
+                let codec = FlowInfoCodec::default();
+                codec
+                    .ipfix_templates()
+                    .borrow_mut()
+                    .set_peer(Some(exporter));
+                codec
+                    .netflow_v9_templates()
+                    .borrow_mut()
+                    .set_peer(Some(exporter));
+                codec
+            });
+        let mut buf = BytesMut::from(payload);
+        while !buf.is_empty() {
+            match codec.decode(&mut buf) {
+                Ok(Some(msg)) => self.pending.push_back(Ok((exporter, msg))),
+                Ok(None) => break,
+                Err(err) => self.pending.push_back(Err((exporter, err))),
+            }
+        }
+    }
+
+    /// Append the TCP segment to the stream of the connection, and decode the
+    /// messages completed by it. Like the TCP server, the connection is ignored
+    /// after the first decoding error.
+    fn decode_tcp(&mut self, exporter: SocketAddr, collector: SocketAddr, payload: &[u8]) {
+        let (codec, buf) = self
+            .tcp_codecs
+            .entry((exporter, collector))
+            .or_insert_with(|| {
+                let codec = IpfixStreamCodec::default();
+                codec
+                    .ipfix_templates()
+                    .borrow_mut()
+                    .set_peer(Some(exporter));
+                (codec, BytesMut::new())
+            });
+        buf.extend_from_slice(payload);
+        loop {
+            match codec.decode(buf) {
+                Ok(Some(msg)) => self.pending.push_back(Ok((exporter, msg))),
+                Ok(None) => break,
+                Err(err) => {
+                    self.pending.push_back(Err((exporter, err)));
+                    self.tcp_codecs.remove(&(exporter, collector));
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl FlowReplay<'static> {
+    /// Open a pcap or pcapng file
+    pub fn open(path: impl AsRef<Path>, ports: Vec<u16>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let reader = create_reader(READ_BUFFER_SIZE, file)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{err:?}")))?;
+        Ok(Self::new(PcapIter::new(reader), ports))
+    }
+}
+
+impl Iterator for FlowReplay<'_> {
+    type Item = FlowRequest;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(request) = self.pending.pop_front() {
+                return Some(request);
+            }
+            let (src_ip, src_port, dst_ip, dst_port, protocol, payload) = self.packets.next()?;
+            if !self.ports.contains(&dst_port) {
+                continue;
+            }
+            let exporter = SocketAddr::new(src_ip, src_port);
+            let collector = SocketAddr::new(dst_ip, dst_port);
+            match protocol {
+                TransportProtocol::UDP => self.decode_udp(exporter, collector, &payload),
+                TransportProtocol::TCP => self.decode_tcp(exporter, collector, &payload),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use netgauze_flow_pkt::FlowInfo;
+
+    use super::*;
+
+    #[test]
+    fn test_replay() {
+        let mut path = env!("CARGO_MANIFEST_DIR").to_owned();
+        path.push_str("/../../assets/pcaps/pmacct-tests/100-IPFIXv10-CISCO/traffic-00.pcap");
+
+        assert_eq!(FlowReplay::open(&path, vec![4739]).unwrap().count(), 0);
+
+        let messages = FlowReplay::open(&path, vec![9991])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), 6);
+        assert!(messages
+            .iter()
+            .all(|(_, msg)| matches!(msg, FlowInfo::IPFIX(_))));
+    }
+}
@@ -0,0 +1,535 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Enrichment of the flows with the live routing state (with the `routing`
+//! feature).
+//!
+//! [`PrefixRib`] keeps the IPv4 and IPv6 unicast routes learned from a BGP
+//! feed, [`PrefixRib::update_bgp`], or from the route monitoring of a BMP
+//! feed, [`PrefixRib::update_bmp`]. The source and destination addresses of
+//! the flows are matched against the RIB with a longest prefix match to
+//! annotate them with the origin AS, the AS path, and the communities of the
+//! covering routes, see [`PrefixRib::labels`].
+//!
+//! When several peers announce the same prefix, the route with the shortest
+//! AS path is used.
+
+use std::{
+    collections::{btree_map, hash_map, BTreeMap, HashMap},
+    net::{IpAddr, SocketAddr},
+};
+
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use serde::{Deserialize, Serialize};
+
+use netgauze_bgp_pkt::{
+    community::{Community, LargeCommunity},
+    path_attribute::{AsPath, AsPathSegmentType, MpReach, MpUnreach, PathAttributeValue},
+    BgpMessage,
+};
+use netgauze_bmp_pkt::{BmpMessage, BmpMessageValue, PeerHeader};
+use netgauze_flow_pkt::ie::{self, Field};
+
+/// Attributes of a route used to annotate the flows
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RouteInfo {
+    as_path: Vec<u32>,
+    origin_as: Option<u32>,
+    communities: Vec<Community>,
+    large_communities: Vec<LargeCommunity>,
+}
+
+impl RouteInfo {
+    pub const fn new(
+        as_path: Vec<u32>,
+        origin_as: Option<u32>,
+        communities: Vec<Community>,
+        large_communities: Vec<LargeCommunity>,
+    ) -> Self {
+        Self {
+            as_path,
+            origin_as,
+            communities,
+            large_communities,
+        }
+    }
+
+    /// The AS numbers of all the segments, including the AS sets
+    pub const fn as_path(&self) -> &Vec<u32> {
+        &self.as_path
+    }
+
+    /// Last AS of the path, [`None`] when the path is empty or ends with an AS
+    /// set, e.g., for an aggregate
+    pub const fn origin_as(&self) -> Option<u32> {
+        self.origin_as
+    }
+
+    pub const fn communities(&self) -> &Vec<Community> {
+        &self.communities
+    }
+
+    pub const fn large_communities(&self) -> &Vec<LargeCommunity> {
+        &self.large_communities
+    }
+
+    fn from_attributes<'a>(attributes: impl IntoIterator<Item = &'a PathAttributeValue>) -> Self {
+        let mut as_path = None;
+        let mut as4_path = None;
+        let mut route = Self::default();
+        for attribute in attributes {
+            match attribute {
+                PathAttributeValue::AsPath(AsPath::As2PathSegments(segments)) => {
+                    as_path = Some(
+                        segments
+                            .iter()
+                            .map(|segment| {
+                                let as_numbers = segment.as_numbers().iter();
+                                let as_numbers = as_numbers.map(|x| *x as u32).collect();
+                                (segment.segment_type(), as_numbers)
+                            })
+                            .collect::<Vec<(_, Vec<_>)>>(),
+                    )
+                }
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(segments)) => {
+                    as_path = Some(
+                        segments
+                            .iter()
+                            .map(|segment| (segment.segment_type(), segment.as_numbers().clone()))
+                            .collect(),
+                    )
+                }
+                // The AS4_PATH of a 2-octets AS speaker has the actual AS numbers
+                PathAttributeValue::As4Path(path) => {
+                    as4_path = Some(
+                        path.segments()
+                            .iter()
+                            .map(|segment| (segment.segment_type(), segment.as_numbers().clone()))
+                            .collect(),
+                    )
+                }
+                PathAttributeValue::Communities(communities) => {
+                    route.communities = communities.communities().clone()
+                }
+                PathAttributeValue::LargeCommunities(communities) => {
+                    route.large_communities = communities.communities().clone()
+                }
+                _ => {}
+            }
+        }
+        let segments = as4_path.or(as_path).unwrap_or_default();
+        route.origin_as = match segments.last() {
+            Some((AsPathSegmentType::AsSequence, as_numbers)) => as_numbers.last().copied(),
+            _ => None,
+        };
+        route.as_path = segments
+            .into_iter()
+            .flat_map(|(_, as_numbers)| as_numbers)
+            .collect();
+        route
+    }
+}
+
+/// Peer that announced a route, the router is the BMP speaker or the BGP peer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct RouteSource {
+    router: IpAddr,
+    peer_distinguisher: u64,
+    peer: Option<IpAddr>,
+    path_id: Option<u32>,
+}
+
+/// Longest prefix match RIB of the IPv4 and IPv6 unicast routes, see the
+/// [module](self) docs
+#[derive(Debug, Clone, Default)]
+pub struct PrefixRib {
+    routes: HashMap<IpNet, BTreeMap<RouteSource, RouteInfo>>,
+    /// Number of prefixes of every length, so the lookup only tries the
+    /// lengths in the RIB
+    ipv4_lengths: BTreeMap<u8, usize>,
+    ipv6_lengths: BTreeMap<u8, usize>,
+}
+
+impl PrefixRib {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of prefixes in the RIB
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// The most specific prefix covering the address and its route
+    pub fn lookup(&self, address: IpAddr) -> Option<(IpNet, &RouteInfo)> {
+        let lengths = match address {
+            IpAddr::V4(_) => &self.ipv4_lengths,
+            IpAddr::V6(_) => &self.ipv6_lengths,
+        };
+        lengths.keys().rev().find_map(|length| {
+            let prefix = IpNet::new(address, *length).ok()?.trunc();
+            let route = self
+                .routes
+                .get(&prefix)?
+                .values()
+                .min_by_key(|route| route.as_path.len())?;
+            Some((prefix, route))
+        })
+    }
+
+    /// Labels to enrich a flow record with the routes of its source and
+    /// destination addresses. Only the labels of the addresses covered by the
+    /// RIB are returned.
+    pub fn labels<'a>(
+        &self,
+        fields: impl IntoIterator<Item = &'a Field>,
+    ) -> BTreeMap<&'static str, String> {
+        let mut labels = BTreeMap::new();
+        for field in fields {
+            let (address, names) = match field {
+                Field::sourceIPv4Address(ie::sourceIPv4Address(x)) => {
+                    (IpAddr::from(*x), SOURCE_LABELS)
+                }
+                Field::sourceIPv6Address(ie::sourceIPv6Address(x)) => {
+                    (IpAddr::from(*x), SOURCE_LABELS)
+                }
+                Field::destinationIPv4Address(ie::destinationIPv4Address(x)) => {
+                    (IpAddr::from(*x), DESTINATION_LABELS)
+                }
+                Field::destinationIPv6Address(ie::destinationIPv6Address(x)) => {
+                    (IpAddr::from(*x), DESTINATION_LABELS)
+                }
+                _ => continue,
+            };
+            let Some((prefix, route)) = self.lookup(address) else {
+                continue;
+            };
+            let [prefix_label, origin_label, path_label, communities_label] = names;
+            labels.insert(prefix_label, prefix.to_string());
+            if let Some(origin_as) = route.origin_as {
+                labels.insert(origin_label, origin_as.to_string());
+            }
+            labels.insert(path_label, join(route.as_path.iter()));
+            let communities = route
+                .communities
+                .iter()
+                .map(|x| format!("{}:{}", x.collection_asn(), x.collection_value()))
+                .chain(route.large_communities.iter().map(|x| {
+                    format!(
+                        "{}:{}:{}",
+                        x.global_admin(),
+                        x.local_data1(),
+                        x.local_data2()
+                    )
+                }));
+            labels.insert(communities_label, join(communities));
+        }
+        labels
+    }
+
+    /// Update the RIB with a BGP message received from `peer`
+    pub fn update_bgp(&mut self, peer: IpAddr, msg: &BgpMessage) {
+        let source = RouteSource {
+            router: peer,
+            peer_distinguisher: 0,
+            peer: None,
+            path_id: None,
+        };
+        self.update(source, msg);
+    }
+
+    /// Update the RIB with a BMP message sent by `router`. The routes of the
+    /// Adj-RIB-Out are ignored, and the routes of a peer are removed when it
+    /// goes down.
+    pub fn update_bmp(&mut self, router: SocketAddr, msg: &BmpMessage) {
+        let source = |peer_header: &PeerHeader| RouteSource {
+            router: router.ip(),
+            peer_distinguisher: peer_header.peer_distinguisher(),
+            peer: peer_header.address(),
+            path_id: None,
+        };
+        let BmpMessage::V3(value) = msg;
+        match value {
+            BmpMessageValue::RouteMonitoring(route_monitoring) => {
+                let peer_header = route_monitoring.peer_header();
+                if !peer_header.is_adj_rib_out() {
+                    self.update(source(peer_header), route_monitoring.update_message());
+                }
+            }
+            BmpMessageValue::PeerDownNotification(peer_down) => {
+                let source = source(peer_down.peer_header());
+                self.remove_routes(|x| {
+                    x.router == source.router
+                        && x.peer_distinguisher == source.peer_distinguisher
+                        && x.peer == source.peer
+                });
+            }
+            BmpMessageValue::Termination(_) => self.remove_router(router.ip()),
+            _ => {}
+        }
+    }
+
+    /// Remove all the routes learned from a router, e.g., when the BGP or BMP
+    /// session is closed
+    pub fn remove_router(&mut self, router: IpAddr) {
+        self.remove_routes(|x| x.router == router);
+    }
+
+    fn update(&mut self, source: RouteSource, msg: &BgpMessage) {
+        let BgpMessage::Update(update) = msg else {
+            return;
+        };
+        let mut withdrawn = update
+            .withdraw_routes()
+            .iter()
+            .map(|x| (x.path_id(), IpNet::V4(x.network().address())))
+            .collect::<Vec<_>>();
+        let mut announced = update
+            .nlri()
+            .iter()
+            .map(|x| (x.path_id(), IpNet::V4(x.network().address())))
+            .collect::<Vec<_>>();
+        for attribute in update.path_attributes() {
+            match attribute.value() {
+                PathAttributeValue::MpReach(MpReach::Ipv4Unicast { nlri, .. }) => {
+                    announced.extend(nlri.iter().map(|x| {
+                        let prefix: Ipv4Net = x.network().address();
+                        (x.path_id(), prefix.into())
+                    }))
+                }
+                PathAttributeValue::MpReach(MpReach::Ipv6Unicast { nlri, .. }) => {
+                    announced.extend(nlri.iter().map(|x| {
+                        let prefix: Ipv6Net = x.network().address();
+                        (x.path_id(), prefix.into())
+                    }))
+                }
+                PathAttributeValue::MpUnreach(MpUnreach::Ipv4Unicast { nlri }) => withdrawn.extend(
+                    nlri.iter()
+                        .map(|x| (x.path_id(), IpNet::V4(x.network().address()))),
+                ),
+                PathAttributeValue::MpUnreach(MpUnreach::Ipv6Unicast { nlri }) => withdrawn.extend(
+                    nlri.iter()
+                        .map(|x| (x.path_id(), IpNet::V6(x.network().address()))),
+                ),
+                _ => {}
+            }
+        }
+        for (path_id, prefix) in withdrawn {
+            let prefix = prefix.trunc();
+            let source = RouteSource { path_id, ..source };
+            if let Some(routes) = self.routes.get_mut(&prefix) {
+                routes.remove(&source);
+                if routes.is_empty() {
+                    self.remove_prefix(&prefix);
+                }
+            }
+        }
+        if announced.is_empty() {
+            return;
+        }
+        let route = RouteInfo::from_attributes(update.path_attributes().iter().map(|x| x.value()));
+        for (path_id, prefix) in announced {
+            let source = RouteSource { path_id, ..source };
+            let routes = match self.routes.entry(prefix.trunc()) {
+                hash_map::Entry::Occupied(entry) => entry.into_mut(),
+                hash_map::Entry::Vacant(entry) => {
+                    let lengths = match prefix {
+                        IpNet::V4(_) => &mut self.ipv4_lengths,
+                        IpNet::V6(_) => &mut self.ipv6_lengths,
+                    };
+                    *lengths.entry(prefix.prefix_len()).or_default() += 1;
+                    entry.insert(BTreeMap::new())
+                }
+            };
+            routes.insert(source, route.clone());
+        }
+    }
+
+    fn remove_routes(&mut self, predicate: impl Fn(&RouteSource) -> bool) {
+        let mut removed = vec![];
+        for (prefix, routes) in &mut self.routes {
+            routes.retain(|source, _| !predicate(source));
+            if routes.is_empty() {
+                removed.push(*prefix);
+            }
+        }
+        for prefix in removed {
+            self.remove_prefix(&prefix);
+        }
+    }
+
+    /// Remove a prefix and forget its length if no other prefix has it
+    fn remove_prefix(&mut self, prefix: &IpNet) {
+        if self.routes.remove(prefix).is_none() {
+            return;
+        }
+        let lengths = match prefix {
+            IpNet::V4(_) => &mut self.ipv4_lengths,
+            IpNet::V6(_) => &mut self.ipv6_lengths,
+        };
+        if let btree_map::Entry::Occupied(mut entry) = lengths.entry(prefix.prefix_len()) {
+            *entry.get_mut() -= 1;
+            if *entry.get() == 0 {
+                entry.remove();
+            }
+        }
+    }
+}
+
+const SOURCE_LABELS: [&str; 4] = [
+    "sourcePrefix",
+    "sourceOriginAS",
+    "sourceASPath",
+    "sourceCommunities",
+];
+
+const DESTINATION_LABELS: [&str; 4] = [
+    "destinationPrefix",
+    "destinationOriginAS",
+    "destinationASPath",
+    "destinationCommunities",
+];
+
+fn join(values: impl Iterator<Item = impl ToString>) -> String {
+    values.map(|x| x.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use netgauze_bgp_pkt::{
+        nlri::{Ipv4Unicast, Ipv4UnicastAddress},
+        path_attribute::{As4PathSegment, Communities, Origin, PathAttribute},
+        update::BgpUpdateMessage,
+    };
+
+    use super::*;
+
+    fn update(
+        withdrawn: Vec<&str>,
+        as_path: Vec<u32>,
+        communities: Vec<u32>,
+        nlri: Vec<&str>,
+    ) -> BgpMessage {
+        let prefixes = |prefixes: Vec<&str>| {
+            prefixes
+                .into_iter()
+                .map(|x| {
+                    Ipv4UnicastAddress::new_no_path_id(
+                        Ipv4Unicast::from_net(x.parse().unwrap()).unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let attributes = vec![
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::Origin(Origin::IGP),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                false,
+                true,
+                false,
+                false,
+                PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![As4PathSegment::new(
+                    AsPathSegmentType::AsSequence,
+                    as_path,
+                )])),
+            )
+            .unwrap(),
+            PathAttribute::from(
+                true,
+                true,
+                false,
+                false,
+                PathAttributeValue::Communities(Communities::new(
+                    communities.into_iter().map(Community::new).collect(),
+                )),
+            )
+            .unwrap(),
+        ];
+        BgpMessage::Update(BgpUpdateMessage::new(
+            prefixes(withdrawn),
+            attributes,
+            prefixes(nlri),
+        ))
+    }
+
+    #[test]
+    fn test_prefix_rib() {
+        let peer1 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let peer2 = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2));
+        let mut rib = PrefixRib::new();
+        rib.update_bgp(
+            peer1,
+            &update(
+                vec![],
+                vec![65001, 65002],
+                vec![0xfde8_0001],
+                vec!["10.0.0.0/8"],
+            ),
+        );
+        rib.update_bgp(
+            peer1,
+            &update(
+                vec![],
+                vec![65001, 65003, 65004],
+                vec![],
+                vec!["10.1.0.0/16"],
+            ),
+        );
+        rib.update_bgp(
+            peer2,
+            &update(vec![], vec![65005], vec![], vec!["10.1.0.0/16"]),
+        );
+        assert_eq!(rib.len(), 2);
+
+        let flow = vec![
+            Field::sourceIPv4Address(ie::sourceIPv4Address(Ipv4Addr::new(10, 1, 2, 3))),
+            Field::destinationIPv4Address(ie::destinationIPv4Address(Ipv4Addr::new(10, 2, 0, 1))),
+        ];
+        assert_eq!(
+            rib.labels(&flow),
+            BTreeMap::from([
+                ("destinationASPath", "65001 65002".to_string()),
+                ("destinationCommunities", "65000:1".to_string()),
+                ("destinationOriginAS", "65002".to_string()),
+                ("destinationPrefix", "10.0.0.0/8".to_string()),
+                ("sourceASPath", "65005".to_string()),
+                ("sourceCommunities", "".to_string()),
+                ("sourceOriginAS", "65005".to_string()),
+                ("sourcePrefix", "10.1.0.0/16".to_string()),
+            ])
+        );
+
+        // The shortest path is withdrawn, then the other peer is gone
+        rib.update_bgp(peer2, &update(vec!["10.1.0.0/16"], vec![], vec![], vec![]));
+        let (prefix, route) = rib.lookup(Ipv4Addr::new(10, 1, 2, 3).into()).unwrap();
+        assert_eq!(prefix, "10.1.0.0/16".parse::<IpNet>().unwrap());
+        assert_eq!(route.origin_as(), Some(65004));
+        rib.remove_router(peer1);
+        assert!(rib.is_empty());
+        assert_eq!(rib.lookup(Ipv4Addr::new(10, 1, 2, 3).into()), None);
+    }
+}
@@ -0,0 +1,255 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! IPFIX collection over TCP and optionally TLS (with the `tls` feature),
+//! see [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-10.4).
+
+use std::{fmt::Debug, io, net::SocketAddr, sync::Arc};
+
+use futures_util::StreamExt;
+use tokio::{
+    io::AsyncRead,
+    net::{TcpListener, ToSocketAddrs},
+    sync::mpsc,
+    task::LocalSet,
+};
+use tokio_util::codec::FramedRead;
+
+use netgauze_flow_pkt::{
+    codec::{FlowInfoCodecDecoderError, IpfixStreamCodec},
+    FlowInfo,
+};
+
+use crate::acl::ExporterAcl;
+
+#[cfg(feature = "tls")]
+use tokio_rustls::TlsAcceptor;
+
+/// Flow message or decoding error tagged with the exporter address
+pub type FlowRequest = Result<(SocketAddr, FlowInfo), (SocketAddr, FlowInfoCodecDecoderError)>;
+
+/// Listen and serve IPFIX over TCP
+pub struct FlowTcpServer {
+    listener: TcpListener,
+    acl: Option<Arc<ExporterAcl>>,
+    #[cfg(feature = "tls")]
+    tls_acceptor: Option<TlsAcceptor>,
+}
+
+impl Debug for FlowTcpServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("FlowTcpServer");
+        debug.field("listener", &self.listener);
+        debug.field("acl", &self.acl);
+        #[cfg(feature = "tls")]
+        debug.field("tls", &self.tls_acceptor.is_some());
+        debug.finish()
+    }
+}
+
+impl FlowTcpServer {
+    pub async fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr).await?,
+            acl: None,
+            #[cfg(feature = "tls")]
+            tls_acceptor: None,
+        })
+    }
+
+    /// Close the connections of the exporters rejected by the ACL
+    pub fn with_acl(mut self, acl: Arc<ExporterAcl>) -> Self {
+        self.acl = Some(acl);
+        self
+    }
+
+    /// Require TLS from the exporters
+    #[cfg(feature = "tls")]
+    pub fn with_tls(mut self, tls_acceptor: TlsAcceptor) -> Self {
+        self.tls_acceptor = Some(tls_acceptor);
+        self
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accept the exporters and send their messages to `tx`. Every connection
+    /// has its own templates cache. A connection is closed after the first
+    /// decoding error, since the message boundaries can't be trusted anymore.
+    ///
+    /// Note: the templates cache isn't [`Send`], so the connections are handled
+    /// on a [`LocalSet`] and the returned future isn't [`Send`] either.
+    #[tracing::instrument(skip_all, fields(local_addr=?self.listener.local_addr().ok()))]
+    pub async fn serve(self, tx: mpsc::Sender<FlowRequest>) -> io::Result<()> {
+        let local = LocalSet::new();
+        local
+            .run_until(async move {
+                loop {
+                    let (tcp_stream, remote_addr) = self.listener.accept().await?;
+                    if let Some(acl) = &self.acl {
+                        if !acl.is_allowed(remote_addr.ip()) {
+                            tracing::warn!("rejected connection: {:?}", remote_addr);
+                            continue;
+                        }
+                    }
+                    tracing::info!("accepted new connection: {:?}", remote_addr);
+                    let tx = tx.clone();
+                    #[cfg(feature = "tls")]
+                    if let Some(tls_acceptor) = self.tls_acceptor.clone() {
+                        tokio::task::spawn_local(async move {
+                            match tls_acceptor.accept(tcp_stream).await {
+                                Ok(tls_stream) => {
+                                    Self::handle_connection(remote_addr, tls_stream, tx).await
+                                }
+                                Err(err) => tracing::warn!(
+                                    "TLS handshake with {:?} failed: {:?}",
+                                    remote_addr,
+                                    err
+                                ),
+                            }
+                        });
+                        continue;
+                    }
+                    tokio::task::spawn_local(Self::handle_connection(remote_addr, tcp_stream, tx));
+                }
+            })
+            .await
+    }
+
+    async fn handle_connection<S: AsyncRead + Unpin>(
+        remote_addr: SocketAddr,
+        stream: S,
+        tx: mpsc::Sender<FlowRequest>,
+    ) {
+        let codec = IpfixStreamCodec::default();
+        codec
+            .ipfix_templates()
+            .borrow_mut()
+            .set_peer(Some(remote_addr));
+        let mut framed = FramedRead::new(stream, codec);
+        while let Some(result) = framed.next().await {
+            let is_err = result.is_err();
+            let request = result
+                .map(|msg| (remote_addr, msg))
+                .map_err(|err| (remote_addr, err));
+            if tx.send(request).await.is_err() || is_err {
+                break;
+            }
+        }
+        tracing::info!("connection closed: {:?}", remote_addr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use chrono::Utc;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+    };
+
+    use netgauze_flow_pkt::{ie, ipfix::Set, FieldSpecifier};
+
+    use super::*;
+    use crate::exporter::{ExportTransport, IpfixExporter};
+
+    #[tokio::test]
+    async fn test_tcp_collection() {
+        let server = FlowTcpServer::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let (tx, mut rx) = mpsc::channel::<FlowRequest>(10);
+
+        let client = async move {
+            let mut exporter = IpfixExporter::new(ExportTransport::Tcp);
+            let template_id = exporter
+                .add_template(
+                    1,
+                    vec![FieldSpecifier::new(ie::IE::ingressInterface, 4).unwrap()],
+                )
+                .unwrap();
+            let records = vec![ipfix_record(10), ipfix_record(20)];
+            let packets = exporter
+                .export(1, template_id, records, Utc::now(), Instant::now())
+                .unwrap();
+            let mut wire = exporter.encode(&packets[0]).unwrap();
+            wire.extend(exporter.encode(&packets[0]).unwrap());
+
+            // Messages are framed regardless of how the stream is segmented
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let local_addr = stream.local_addr().unwrap();
+            for chunk in wire.chunks(7) {
+                stream.write_all(chunk).await.unwrap();
+                stream.flush().await.unwrap();
+            }
+            for _ in 0..2 {
+                let (peer, msg) = rx.recv().await.unwrap().unwrap();
+                assert_eq!(peer, local_addr);
+                match msg {
+                    FlowInfo::IPFIX(pkt) => {
+                        assert_eq!(pkt.observation_domain_id(), 1);
+                        assert!(matches!(pkt.sets().last(), Some(Set::Data { .. })));
+                    }
+                    FlowInfo::NetFlowV9(_) => panic!("expected IPFIX"),
+                }
+            }
+
+            // NetFlow V9 can't be framed
+            stream.write_all(&[0, 9, 0, 1]).await.unwrap();
+            stream.write_all(&[0; 16]).await.unwrap();
+            let (peer, err) = rx.recv().await.unwrap().unwrap_err();
+            assert_eq!(peer, local_addr);
+            assert_eq!(err, FlowInfoCodecDecoderError::UnsupportedVersion(9));
+        };
+        tokio::select! {
+            ret = server.serve(tx) => panic!("server stopped: {ret:?}"),
+            _ = client => {},
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tcp_collection_acl() {
+        let acl = Arc::new(ExporterAcl::new(
+            vec![],
+            vec!["127.0.0.0/8".parse().unwrap()],
+        ));
+        let server = FlowTcpServer::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .with_acl(acl.clone());
+        let addr = server.local_addr().unwrap();
+        let (tx, _rx) = mpsc::channel::<FlowRequest>(10);
+
+        let client = async move {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            let mut buf = [0; 1];
+            assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+        };
+        tokio::select! {
+            ret = server.serve(tx) => panic!("server stopped: {ret:?}"),
+            _ = client => {},
+        }
+        assert_eq!(acl.counters().deny()[0].1, 1);
+    }
+
+    fn ipfix_record(interface: u32) -> netgauze_flow_pkt::ipfix::DataRecord {
+        netgauze_flow_pkt::ipfix::DataRecord::new(
+            vec![],
+            vec![ie::Field::ingressInterface(ie::ingressInterface(interface))],
+        )
+    }
+}
@@ -0,0 +1,686 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Streaming Top-N talkers over a sliding window.
+//!
+//! Keeping a counter for every talker isn't possible at line rate, so
+//! [`TopNTalkers`] estimates the heaviest ones with the Space-Saving sketch
+//! ([`SpaceSaving`]), which keeps a fixed number of counters and reports for
+//! each talker an upper bound of its value and the maximum overestimation.
+//!
+//! The sliding window of [`TopNConfig::window`] is divided into
+//! [`TopNConfig::panes`] panes with a sketch each, following the event time of
+//! the records like [`crate::aggregation`]. The window slides one pane at a
+//! time, and [`TopNTalkers::top`] merges the sketches of the panes in the
+//! window. The talkers are ranked either globally or per exporter. The panes
+//! are never slid out by event times outside of the representable time range.
+
+use std::{
+    collections::{BTreeSet, HashMap, VecDeque},
+    hash::Hash,
+    net::{IpAddr, SocketAddr},
+    time::Duration,
+};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use netgauze_flow_pkt::FlowInfo;
+
+use crate::aggregation::{
+    aggregate_value, flow_records, key_value, Aggregate, KeyDimension, KeyValue,
+};
+
+/// Space-Saving sketch, see
+/// [Efficient Computation of Frequent and Top-k Elements in Data Streams](https://doi.org/10.1007/978-3-540-30570-5_27).
+///
+/// At most `capacity` keys are counted; when full, a new key replaces the key
+/// with the smallest count and inherits that count as its error. Any key
+/// whose actual value is above the smallest count is guaranteed to be counted.
+/// The keys are also ordered by their counts, so that updating a key or
+/// replacing the smallest one takes `O(log capacity)`.
+#[derive(Debug, Clone)]
+pub struct SpaceSaving<K> {
+    capacity: usize,
+    counters: HashMap<K, (u64, u64)>,
+    by_count: BTreeSet<(u64, K)>,
+}
+
+impl<K: Clone + Eq + Hash + Ord> SpaceSaving<K> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            counters: HashMap::new(),
+            by_count: BTreeSet::new(),
+        }
+    }
+
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.counters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counters.is_empty()
+    }
+
+    /// Smallest count, the bound of the value of any key that isn't counted,
+    /// or zero while the sketch isn't full
+    pub fn min_count(&self) -> u64 {
+        if self.counters.len() < self.capacity {
+            0
+        } else {
+            self.by_count.first().map_or(0, |(count, _)| *count)
+        }
+    }
+
+    pub fn insert(&mut self, key: K, weight: u64) {
+        if let Some((count, _)) = self.counters.get_mut(&key) {
+            self.by_count.remove(&(*count, key.clone()));
+            *count = count.saturating_add(weight);
+            self.by_count.insert((*count, key));
+            return;
+        }
+        if self.counters.len() < self.capacity {
+            self.counters.insert(key.clone(), (weight, 0));
+            self.by_count.insert((weight, key));
+            return;
+        }
+        if let Some((min_count, min_key)) = self.by_count.pop_first() {
+            self.counters.remove(&min_key);
+            let count = min_count.saturating_add(weight);
+            self.counters.insert(key.clone(), (count, min_count));
+            self.by_count.insert((count, key));
+        }
+    }
+
+    /// The estimated count and the error of a key, the actual value is
+    /// between `count - error` and `count`
+    pub fn get(&self, key: &K) -> Option<(u64, u64)> {
+        self.counters.get(key).copied()
+    }
+
+    /// The `n` keys with the largest counts, with their count and error
+    pub fn top(&self, n: usize) -> Vec<(&K, u64, u64)> {
+        self.by_count
+            .iter()
+            .rev()
+            .take(n)
+            .map(|(count, key)| (key, *count, self.counters[key].1))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopNConfig {
+    n: usize,
+    capacity: usize,
+    window: Duration,
+    panes: u32,
+    dimensions: Vec<KeyDimension>,
+    metric: Aggregate,
+    per_exporter: bool,
+}
+
+impl TopNConfig {
+    /// Rank the `n` talkers, identified by the `dimensions`, with the largest
+    /// `metric` over the last `window`. By default, the sketch has `10 * n`
+    /// counters, the window slides in 10 panes, and the talkers are ranked
+    /// globally.
+    pub fn new(
+        n: usize,
+        window: Duration,
+        dimensions: Vec<KeyDimension>,
+        metric: Aggregate,
+    ) -> Self {
+        Self {
+            n,
+            capacity: n.saturating_mul(10),
+            window,
+            panes: 10,
+            dimensions,
+            metric,
+            per_exporter: false,
+        }
+    }
+
+    /// Number of counters of the sketch of every pane, larger values make the
+    /// estimates more accurate
+    pub const fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    pub const fn with_panes(mut self, panes: u32) -> Self {
+        self.panes = panes;
+        self
+    }
+
+    /// Rank the talkers of every exporter separately
+    pub const fn with_per_exporter(mut self, per_exporter: bool) -> Self {
+        self.per_exporter = per_exporter;
+        self
+    }
+
+    pub const fn n(&self) -> usize {
+        self.n
+    }
+
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub const fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub const fn panes(&self) -> u32 {
+        self.panes
+    }
+
+    pub const fn dimensions(&self) -> &Vec<KeyDimension> {
+        &self.dimensions
+    }
+
+    pub const fn metric(&self) -> Aggregate {
+        self.metric
+    }
+
+    pub const fn per_exporter(&self) -> bool {
+        self.per_exporter
+    }
+}
+
+/// Talker in a [`TopNReport`], its actual value is between `value - error`
+/// and `value`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopNEntry {
+    key: Vec<(KeyDimension, Option<KeyValue>)>,
+    value: u64,
+    error: u64,
+}
+
+impl TopNEntry {
+    pub const fn key(&self) -> &Vec<(KeyDimension, Option<KeyValue>)> {
+        &self.key
+    }
+
+    pub const fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub const fn error(&self) -> u64 {
+        self.error
+    }
+}
+
+/// The top talkers of an exporter, or of all of them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TopNReport {
+    exporter: Option<IpAddr>,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    metric: Aggregate,
+    entries: Vec<TopNEntry>,
+}
+
+impl TopNReport {
+    /// [`None`] when the talkers are ranked globally
+    pub const fn exporter(&self) -> Option<IpAddr> {
+        self.exporter
+    }
+
+    pub const fn window_start(&self) -> DateTime<Utc> {
+        self.window_start
+    }
+
+    /// Saturated to [`DateTime::<Utc>::MAX_UTC`] when the window ends after the
+    /// latest representable time
+    pub const fn window_end(&self) -> DateTime<Utc> {
+        self.window_end
+    }
+
+    pub const fn metric(&self) -> Aggregate {
+        self.metric
+    }
+
+    /// The talkers sorted by decreasing value
+    pub const fn entries(&self) -> &Vec<TopNEntry> {
+        &self.entries
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TopNConfigError {
+    /// A window without any panes
+    InvalidPanes(u32),
+    /// The panes of the window are shorter than a millisecond, or the window
+    /// is too long to be represented
+    InvalidWindow(Duration),
+}
+
+type TalkerKey = Vec<Option<KeyValue>>;
+
+#[derive(Debug, Clone)]
+struct Pane {
+    start: DateTime<Utc>,
+    sketches: HashMap<Option<IpAddr>, SpaceSaving<TalkerKey>>,
+}
+
+/// Streaming Top-N talkers, see the [module](self) docs
+#[derive(Debug, Clone)]
+pub struct TopNTalkers {
+    config: TopNConfig,
+    pane_length: TimeDelta,
+    /// Time between the start of the oldest and the newest panes of the window
+    span: TimeDelta,
+    panes: VecDeque<Pane>,
+    late_records: u64,
+}
+
+impl TopNTalkers {
+    pub fn new(config: TopNConfig) -> Result<Self, TopNConfigError> {
+        if config.panes == 0 {
+            return Err(TopNConfigError::InvalidPanes(config.panes));
+        }
+        let invalid_window = TopNConfigError::InvalidWindow(config.window);
+        // Panes shorter than a millisecond can't be aligned
+        let pane_length = config.window / config.panes;
+        if TimeDelta::from_std(config.window).is_err() || pane_length < Duration::from_millis(1) {
+            return Err(invalid_window);
+        }
+        // Both are shorter than the window, hence representable
+        let span = pane_length
+            .checked_mul(config.panes - 1)
+            .and_then(|span| TimeDelta::from_std(span).ok())
+            .ok_or(invalid_window)?;
+        let pane_length = TimeDelta::from_std(pane_length).map_err(|_| invalid_window)?;
+        Ok(Self {
+            config,
+            pane_length,
+            span,
+            panes: VecDeque::new(),
+            late_records: 0,
+        })
+    }
+
+    pub const fn config(&self) -> &TopNConfig {
+        &self.config
+    }
+
+    /// Number of records dropped since they are older than the window
+    pub const fn late_records(&self) -> u64 {
+        self.late_records
+    }
+
+    /// Count the flow records of a message sent by `exporter`. Options data
+    /// records are ignored.
+    pub fn push(&mut self, exporter: SocketAddr, msg: &FlowInfo) {
+        let scope = self.config.per_exporter.then_some(exporter.ip());
+        for record in flow_records(msg) {
+            let key = self
+                .config
+                .dimensions
+                .iter()
+                .map(|dimension| key_value(*dimension, exporter, record.domain, record.fields))
+                .collect();
+            let value = aggregate_value(self.config.metric, record.fields);
+            let capacity = self.config.capacity;
+            let pane_start = self.pane_start(record.event_time);
+            let Some(pane) = self.pane(pane_start) else {
+                self.late_records += 1;
+                continue;
+            };
+            pane.sketches
+                .entry(scope)
+                .or_insert_with(|| SpaceSaving::new(capacity))
+                .insert(key, value);
+        }
+    }
+
+    /// The current top talkers, one report per exporter if
+    /// [`TopNConfig::per_exporter`] is set, otherwise a single global report.
+    /// Nothing is reported before the first record.
+    pub fn top(&self) -> Vec<TopNReport> {
+        let (Some(first), Some(last)) = (self.panes.front(), self.panes.back()) else {
+            return vec![];
+        };
+        let window_start = first.start;
+        let window_end = last
+            .start
+            .checked_add_signed(self.pane_length)
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let mut scopes = self
+            .panes
+            .iter()
+            .flat_map(|pane| pane.sketches.keys().copied())
+            .collect::<Vec<_>>();
+        scopes.sort_unstable();
+        scopes.dedup();
+        scopes
+            .into_iter()
+            .map(|scope| TopNReport {
+                exporter: scope,
+                window_start,
+                window_end,
+                metric: self.config.metric,
+                entries: self.merge(&scope),
+            })
+            .collect()
+    }
+
+    /// Merge the sketches of a scope in all the panes. A talker that isn't
+    /// counted in a full sketch may have up to its smallest count there.
+    fn merge(&self, scope: &Option<IpAddr>) -> Vec<TopNEntry> {
+        let sketches = self
+            .panes
+            .iter()
+            .filter_map(|pane| pane.sketches.get(scope))
+            .collect::<Vec<_>>();
+        let mut keys = sketches
+            .iter()
+            .flat_map(|sketch| sketch.counters.keys())
+            .collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        let mut entries = keys
+            .into_iter()
+            .map(|key| {
+                let (value, error) =
+                    sketches
+                        .iter()
+                        .fold((0u64, 0u64), |(value, error), sketch| {
+                            let (count, count_error) = sketch.get(key).unwrap_or_else(|| {
+                                let min_count = sketch.min_count();
+                                (min_count, min_count)
+                            });
+                            (
+                                value.saturating_add(count),
+                                error.saturating_add(count_error),
+                            )
+                        });
+                TopNEntry {
+                    key: self
+                        .config
+                        .dimensions
+                        .iter()
+                        .copied()
+                        .zip(key.iter().copied())
+                        .collect(),
+                    value,
+                    error,
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.value.cmp(&a.value).then_with(|| a.key.cmp(&b.key)));
+        entries.truncate(self.config.n);
+        entries
+    }
+
+    fn pane_start(&self, event_time: DateTime<Utc>) -> DateTime<Utc> {
+        let pane_length = self.pane_length.num_milliseconds();
+        let millis = event_time.timestamp_millis();
+        DateTime::from_timestamp_millis(millis - millis.rem_euclid(pane_length))
+            .unwrap_or(event_time)
+    }
+
+    /// The pane starting at `pane_start`, the window slides when the pane is
+    /// newer than the window, [`None`] when the pane is older than the window
+    fn pane(&mut self, pane_start: DateTime<Utc>) -> Option<&mut Pane> {
+        match self.panes.back() {
+            Some(last) if last.start >= pane_start => {
+                // Nothing is older than the window when it starts before the
+                // earliest representable time
+                if last
+                    .start
+                    .checked_sub_signed(self.span)
+                    .is_some_and(|oldest| pane_start < oldest)
+                {
+                    return None;
+                }
+                let index = self.panes.partition_point(|pane| pane.start < pane_start);
+                if self.panes[index].start != pane_start {
+                    self.panes.insert(
+                        index,
+                        Pane {
+                            start: pane_start,
+                            sketches: HashMap::new(),
+                        },
+                    );
+                }
+                self.panes.get_mut(index)
+            }
+            _ => {
+                if let Some(oldest) = pane_start.checked_sub_signed(self.span) {
+                    while self.panes.front().is_some_and(|pane| pane.start < oldest) {
+                        self.panes.pop_front();
+                    }
+                }
+                self.panes.push_back(Pane {
+                    start: pane_start,
+                    sketches: HashMap::new(),
+                });
+                self.panes.back_mut()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use netgauze_flow_pkt::{
+        ie::{self, Field},
+        ipfix::{self, IpfixPacket},
+        netflow::{self, NetFlowV9Packet},
+        DataSetId,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_space_saving() {
+        let mut sketch = SpaceSaving::new(2);
+        sketch.insert("a", 10);
+        sketch.insert("b", 3);
+        assert_eq!(sketch.min_count(), 3);
+        sketch.insert("c", 1);
+        assert_eq!(sketch.len(), 2);
+        assert_eq!(sketch.get(&"b"), None);
+        assert_eq!(sketch.top(2), vec![(&"a", 10, 0), (&"c", 4, 3)]);
+        // The updated key is no longer the smallest one
+        sketch.insert("c", 10);
+        assert_eq!(sketch.min_count(), 10);
+        sketch.insert("d", 1);
+        assert_eq!(sketch.get(&"a"), None);
+        assert_eq!(sketch.top(2), vec![(&"c", 14, 3), (&"d", 11, 10)]);
+    }
+
+    fn pkt(records: Vec<(u32, u32, u64)>) -> FlowInfo {
+        FlowInfo::IPFIX(IpfixPacket::new(
+            Utc.timestamp_opt(0, 0).unwrap(),
+            0,
+            1,
+            vec![ipfix::Set::Data {
+                id: DataSetId::new(256).unwrap(),
+                records: records
+                    .into_iter()
+                    .map(|(end_seconds, source_as, octets)| {
+                        ipfix::DataRecord::new(
+                            vec![],
+                            vec![
+                                Field::flowEndSeconds(ie::flowEndSeconds(
+                                    Utc.timestamp_opt(end_seconds as i64, 0).unwrap(),
+                                )),
+                                Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(source_as)),
+                                Field::octetDeltaCount(ie::octetDeltaCount(octets)),
+                            ],
+                        )
+                    })
+                    .collect(),
+            }],
+        ))
+    }
+
+    #[test]
+    fn test_top_talkers() {
+        let exporter1 = "192.0.2.1:4739".parse().unwrap();
+        let exporter2 = "192.0.2.2:4739".parse().unwrap();
+        let config = TopNConfig::new(
+            2,
+            Duration::from_secs(60),
+            vec![KeyDimension::SourceAs],
+            Aggregate::Octets,
+        )
+        .with_panes(3);
+        let mut talkers = TopNTalkers::new(config.clone()).unwrap();
+        assert!(talkers.top().is_empty());
+
+        talkers.push(exporter1, &pkt(vec![(0, 1, 100), (10, 2, 50), (20, 3, 10)]));
+        talkers.push(exporter2, &pkt(vec![(30, 3, 80), (45, 1, 5)]));
+        let entry = |source_as, value| TopNEntry {
+            key: vec![(KeyDimension::SourceAs, Some(KeyValue::Number(source_as)))],
+            value,
+            error: 0,
+        };
+        let top = talkers.top();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].exporter(), None);
+        assert_eq!(top[0].window_start(), Utc.timestamp_opt(0, 0).unwrap());
+        assert_eq!(top[0].window_end(), Utc.timestamp_opt(60, 0).unwrap());
+        assert_eq!(top[0].entries(), &vec![entry(1, 105), entry(3, 90)]);
+
+        // The window slides by one pane, the first pane is dropped
+        talkers.push(exporter1, &pkt(vec![(65, 2, 1)]));
+        let top = talkers.top();
+        assert_eq!(top[0].window_start(), Utc.timestamp_opt(20, 0).unwrap());
+        assert_eq!(top[0].entries(), &vec![entry(3, 90), entry(1, 5)]);
+        talkers.push(exporter1, &pkt(vec![(5, 2, 1000)]));
+        assert_eq!(talkers.late_records(), 1);
+
+        let mut talkers = TopNTalkers::new(config.with_per_exporter(true)).unwrap();
+        talkers.push(exporter1, &pkt(vec![(0, 1, 100), (10, 2, 50)]));
+        talkers.push(exporter2, &pkt(vec![(30, 3, 80)]));
+        let top = talkers.top();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].exporter(), Some(exporter1.ip()));
+        assert_eq!(top[0].entries(), &vec![entry(1, 100), entry(2, 50)]);
+        assert_eq!(top[1].exporter(), Some(exporter2.ip()));
+        assert_eq!(top[1].entries(), &vec![entry(3, 80)]);
+    }
+
+    #[test]
+    fn test_top_talkers_netflow_v9() {
+        let exporter = "192.0.2.1:2055".parse().unwrap();
+        let config = TopNConfig::new(
+            2,
+            Duration::from_secs(60),
+            vec![KeyDimension::SourceAs],
+            Aggregate::Octets,
+        )
+        .with_panes(3);
+        let mut talkers = TopNTalkers::new(config).unwrap();
+        let record = |end_sys_up_time, source_as| {
+            netflow::DataRecord::new(
+                vec![],
+                vec![
+                    Field::flowEndSysUpTime(ie::flowEndSysUpTime(end_sys_up_time)),
+                    Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(source_as)),
+                    Field::octetDeltaCount(ie::octetDeltaCount(u64::MAX)),
+                ],
+            )
+        };
+        // The exporter booted 20 seconds after the epoch
+        talkers.push(
+            exporter,
+            &FlowInfo::NetFlowV9(NetFlowV9Packet::new(
+                100_000,
+                Utc.timestamp_opt(120, 0).unwrap(),
+                1,
+                0,
+                vec![netflow::Set::Data {
+                    id: DataSetId::new(256).unwrap(),
+                    records: vec![record(10_000, 1), record(30_000, 1)],
+                }],
+            )),
+        );
+        let top = talkers.top();
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].window_start(), Utc.timestamp_opt(20, 0).unwrap());
+        assert_eq!(top[0].window_end(), Utc.timestamp_opt(60, 0).unwrap());
+        assert_eq!(top[0].entries()[0].value(), u64::MAX);
+    }
+
+    #[test]
+    fn test_top_talkers_time_overflow() {
+        let exporter = "192.0.2.1:4739".parse().unwrap();
+        let record = |end_time, source_as| {
+            FlowInfo::IPFIX(IpfixPacket::new(
+                Utc.timestamp_opt(0, 0).unwrap(),
+                0,
+                1,
+                vec![ipfix::Set::Data {
+                    id: DataSetId::new(256).unwrap(),
+                    records: vec![ipfix::DataRecord::new(
+                        vec![],
+                        vec![
+                            Field::flowEndMilliseconds(ie::flowEndMilliseconds(end_time)),
+                            Field::bgpSourceAsNumber(ie::bgpSourceAsNumber(source_as)),
+                            Field::octetDeltaCount(ie::octetDeltaCount(1)),
+                        ],
+                    )],
+                }],
+            ))
+        };
+        // The longest window with the most panes
+        let config = TopNConfig::new(
+            2,
+            TimeDelta::MAX.to_std().unwrap(),
+            vec![KeyDimension::SourceAs],
+            Aggregate::Octets,
+        )
+        .with_panes(u32::MAX);
+        for config in [config.clone(), config.with_panes(1)] {
+            let mut talkers = TopNTalkers::new(config).unwrap();
+            talkers.push(exporter, &record(DateTime::<Utc>::MIN_UTC, 1));
+            talkers.push(exporter, &record(DateTime::<Utc>::MAX_UTC, 2));
+            talkers.push(exporter, &record(DateTime::<Utc>::MIN_UTC, 3));
+            let top = talkers.top();
+            assert_eq!(top.len(), 1);
+            assert_eq!(top[0].window_end(), DateTime::<Utc>::MAX_UTC);
+        }
+    }
+
+    #[test]
+    fn test_top_n_config_out_of_range() {
+        let config =
+            |window| TopNConfig::new(2, window, vec![KeyDimension::SourceAs], Aggregate::Octets);
+        assert_eq!(
+            TopNTalkers::new(config(Duration::MAX)).err(),
+            Some(TopNConfigError::InvalidWindow(Duration::MAX))
+        );
+        assert_eq!(
+            TopNTalkers::new(config(Duration::from_millis(10)).with_panes(20)).err(),
+            Some(TopNConfigError::InvalidWindow(Duration::from_millis(10)))
+        );
+        assert_eq!(
+            TopNTalkers::new(config(Duration::from_secs(60)).with_panes(0)).err(),
+            Some(TopNConfigError::InvalidPanes(0))
+        );
+    }
+}
@@ -263,6 +263,24 @@ pub(crate) fn generate_information_element_ids(ie: &Vec<InformationElement>) ->
 
     ret.push_str(generate_impl_ie_template_for_ie(ie).as_str());
     ret.push_str(generate_from_for_ie().as_str());
+    ret.push_str(generate_ie_from_name(ie).as_str());
+    ret
+}
+
+/// Reverse lookup of a vendor specific IE by its name in the registry
+fn generate_ie_from_name(ie: &Vec<InformationElement>) -> String {
+    let mut ret = String::new();
+    ret.push_str("impl IE {\n");
+    ret.push_str("    /// Lookup an Information Element by its name in the registry\n");
+    ret.push_str("    pub fn from_name(name: &str) -> Option<Self> {\n");
+    ret.push_str("        match name {\n");
+    for ie in ie {
+        ret.push_str(format!("            \"{}\" => Some(Self::{}),\n", ie.name, ie.name).as_str());
+    }
+    ret.push_str("            _ => None,\n");
+    ret.push_str("        }\n");
+    ret.push_str("    }\n");
+    ret.push_str("}\n\n");
     ret
 }
 
@@ -335,7 +353,9 @@ fn generate_ie_template_trait_for_main(
     ret.push_str("impl super::InformationElementTemplate for IE {\n");
     ret.push_str("    fn semantics(&self) -> Option<InformationElementSemantics> {\n");
     ret.push_str("        match self {\n");
-    ret.push_str("            Self::Unknown{..} => None,\n");
+    ret.push_str(
+        "            Self::Unknown{pen, id} => crate::ie_registry::semantics(*pen, *id),\n",
+    );
     for (name, _, _) in vendors {
         ret.push_str(format!("            Self::{name}(ie) => ie.semantics(),\n").as_str());
     }
@@ -358,7 +378,7 @@ fn generate_ie_template_trait_for_main(
 
     ret.push_str("    fn data_type(&self) -> InformationElementDataType {\n");
     ret.push_str("        match self {\n");
-    ret.push_str("            Self::Unknown{..} => InformationElementDataType::octetArray,\n");
+    ret.push_str("            Self::Unknown{pen, id} => crate::ie_registry::data_type(*pen, *id).unwrap_or(InformationElementDataType::octetArray),\n");
     for (name, _, _) in vendors {
         ret.push_str(format!("            Self::{name}(ie) => ie.data_type(),\n").as_str());
     }
@@ -376,7 +396,7 @@ fn generate_ie_template_trait_for_main(
 
     ret.push_str("    fn units(&self) -> Option<InformationElementUnits> {\n");
     ret.push_str("        match self {\n");
-    ret.push_str("            Self::Unknown{..} => None,\n");
+    ret.push_str("            Self::Unknown{pen, id} => crate::ie_registry::units(*pen, *id),\n");
     for (name, _, _) in vendors {
         ret.push_str(format!("            Self::{name}(ie) => ie.units(),\n").as_str());
     }
@@ -397,7 +417,9 @@ fn generate_ie_template_trait_for_main(
 
     ret.push_str("    fn value_range(&self) -> Option<std::ops::Range<u64>> {\n");
     ret.push_str("        match self {\n");
-    ret.push_str("            Self::Unknown{..} => None,\n");
+    ret.push_str(
+        "            Self::Unknown{pen, id} => crate::ie_registry::value_range(*pen, *id),\n",
+    );
     for (name, _, _) in vendors {
         ret.push_str(format!("            Self::{name}(ie) => ie.value_range(),\n").as_str());
     }
@@ -458,6 +480,72 @@ fn generate_ie_template_trait_for_main(
     ret
 }
 
+/// Lookup the IEs by their names. Vendor specific IEs are prefixed by the
+/// vendor's module name, e.g., `nokia:aluInsideServiceId`.
+fn generate_ie_names_for_main(
+    iana_ies: &Vec<InformationElement>,
+    vendors: &Vec<(String, String, u32)>,
+) -> String {
+    let mut ret = String::new();
+    ret.push_str("impl IE {\n");
+    ret.push_str("    /// Lookup an Information Element by its name in the registry. Vendor\n");
+    ret.push_str("    /// specific Information Elements are prefixed by the vendor's module\n");
+    ret.push_str("    /// name, e.g., `nokia:aluInsideServiceId`. The names of the\n");
+    ret.push_str("    /// enterprise Information Elements registered in the\n");
+    ret.push_str("    /// [`crate::ie_registry`] are resolved to [`IE::Unknown`].\n");
+    ret.push_str("    pub fn from_name(name: &str) -> Option<Self> {\n");
+    ret.push_str("        match name {\n");
+    for ie in iana_ies {
+        ret.push_str(
+            format!(
+                "            \"{}\" => return Some(Self::{}),\n",
+                ie.name, ie.name
+            )
+            .as_str(),
+        );
+    }
+    ret.push_str("            _ => {}\n");
+    ret.push_str("        }\n");
+    for (name, pkg, _) in vendors {
+        ret.push_str(
+            format!("        if let Some(vendor_name) = name.strip_prefix(\"{pkg}:\") {{\n")
+                .as_str(),
+        );
+        ret.push_str(
+            format!("            return {pkg}::IE::from_name(vendor_name).map(Self::{name});\n")
+                .as_str(),
+        );
+        ret.push_str("        }\n");
+    }
+    ret.push_str("        crate::ie_registry::lookup_by_name(name).map(|ie| Self::Unknown{pen: ie.pen(), id: ie.id()})\n");
+    ret.push_str("    }\n\n");
+
+    ret.push_str("    /// Name of the Information Element as accepted by [`IE::from_name`],\n");
+    ret.push_str("    /// `None` for unknown Information Elements that are not registered in\n");
+    ret.push_str("    /// the [`crate::ie_registry`]\n");
+    ret.push_str("    pub fn name(&self) -> Option<String> {\n");
+    ret.push_str("        match self {\n");
+    ret.push_str("            Self::Unknown{pen, id} => crate::ie_registry::lookup(*pen, *id).map(|ie| ie.name().to_string()),\n");
+    for (name, pkg, _) in vendors {
+        ret.push_str(
+            format!("            Self::{name}(ie) => Some(format!(\"{pkg}:{{ie}}\")),\n").as_str(),
+        );
+    }
+    for ie in iana_ies {
+        ret.push_str(
+            format!(
+                "            Self::{} => Some(\"{}\".to_string()),\n",
+                ie.name, ie.name
+            )
+            .as_str(),
+        );
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
 fn generate_ie_field_enum_for_ie(
     iana_ies: &Vec<InformationElement>,
     vendors: &Vec<(String, String, u32)>,
@@ -467,6 +555,7 @@ fn generate_ie_field_enum_for_ie(
     ret.push_str(generate_derive(false, false, false).as_str());
     ret.push_str("pub enum Field {\n");
     ret.push_str("    Unknown(Vec<u8>),\n");
+    ret.push_str("    Enterprise(EnterpriseField),\n");
     for (name, pkg, _) in vendors {
         ret.push_str(format!("    {name}({pkg}::Field),\n").as_str());
     }
@@ -474,6 +563,47 @@ fn generate_ie_field_enum_for_ie(
         ret.push_str(format!("    {}({}),\n", ie.name, ie.name).as_str());
     }
     ret.push_str("}\n\n");
+
+    ret.push_str("impl Field {\n");
+    ret.push_str("    /// The Information Element of the field, `None` for the fields that are\n");
+    ret.push_str("    /// not decoded since their raw value doesn't carry the IE\n");
+    ret.push_str("    pub fn ie(&self) -> Option<IE> {\n");
+    ret.push_str("        match self {\n");
+    ret.push_str("            Self::Unknown(_) => None,\n");
+    ret.push_str("            Self::Enterprise(field) => Some(IE::Unknown{pen: field.pen(), id: field.id()}),\n");
+    for (name, _, _) in vendors {
+        ret.push_str(
+            format!("            Self::{name}(field) => Some(IE::{name}(field.ie())),\n").as_str(),
+        );
+    }
+    for ie in iana_ies {
+        ret.push_str(
+            format!(
+                "            Self::{}(_) => Some(IE::{}),\n",
+                ie.name, ie.name
+            )
+            .as_str(),
+        );
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n\n");
+    let mut arms = vec![
+        "Self::Unknown(value) => value.serialize(serializer)".to_string(),
+        "Self::Enterprise(field) => field.value().serialize_value(serializer)".to_string(),
+    ];
+    for (name, _, _) in vendors {
+        arms.push(format!(
+            "Self::{name}(field) => field.serialize_value(serializer)"
+        ));
+    }
+    for ie in iana_ies {
+        arms.push(format!(
+            "Self::{}(value) => value.serialize(serializer)",
+            ie.name
+        ));
+    }
+    ret.push_str(generate_field_serialize_value(arms.into_iter()).as_str());
+    ret.push_str("}\n\n");
     ret
 }
 
@@ -513,6 +643,7 @@ pub(crate) fn generate_ie_ids(
 
     ret.push_str(generate_ie_try_from_pen_code(iana_ies, vendors).as_str());
     ret.push_str(generate_ie_template_trait_for_main(iana_ies, vendors).as_str());
+    ret.push_str(generate_ie_names_for_main(iana_ies, vendors).as_str());
     ret.push_str(generate_ie_field_enum_for_ie(iana_ies, vendors).as_str());
 
     ret
@@ -863,7 +994,7 @@ fn generate_f32_deserializer(ie_name: &String) -> String {
     ret.push_str(std_error.as_str());
     ret.push_str(header.as_str());
     ret.push_str("        let (buf, value) = match length {\n");
-    ret.push_str("            1 => nom::number::complete::be_f32(buf)?,\n");
+    ret.push_str("            4 => nom::number::complete::be_f32(buf)?,\n");
     ret.push_str(format!("            _ => return Err(nom::Err::Error(Located{ie_name}ParsingError::new(buf, {ie_name}ParsingError::InvalidLength(length))))\n").as_str());
     ret.push_str("        };\n");
     ret.push_str(format!("        Ok((buf, {ie_name}(value)))\n").as_str());
@@ -879,7 +1010,11 @@ fn generate_f64_deserializer(ie_name: &String) -> String {
     ret.push_str(std_error.as_str());
     ret.push_str(header.as_str());
     ret.push_str("        let (buf, value) = match length {\n");
-    ret.push_str("            1 => nom::number::complete::be_f64(buf)?,\n");
+    // float64 may be encoded as float32 with the reduced-size encoding
+    ret.push_str(
+        "            4 => nom::combinator::map(nom::number::complete::be_f32, f64::from)(buf)?,\n",
+    );
+    ret.push_str("            8 => nom::number::complete::be_f64(buf)?,\n");
     ret.push_str(format!("            _ => return Err(nom::Err::Error(Located{ie_name}ParsingError::new(buf, {ie_name}ParsingError::InvalidLength(length))))\n").as_str());
     ret.push_str("        };\n");
     ret.push_str(format!("        Ok((buf, {ie_name}(value)))\n").as_str());
@@ -925,6 +1060,21 @@ fn generate_mac_address_deserializer(ie_name: &String) -> String {
     ret
 }
 
+/// Read the length of a variable-length encoded IE into `variable_length`.
+/// Lengths less than 255 are carried in one octet, otherwise the first octet
+/// is 255 followed by two octets carrying the length
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-7)
+fn generate_variable_length_deserializer() -> String {
+    let mut ret = String::new();
+    ret.push_str("            let (buf, short_length) = nom::number::complete::be_u8(buf)?;\n");
+    ret.push_str("            let (buf, variable_length) = if short_length == u8::MAX {\n");
+    ret.push_str("                nom::number::complete::be_u16(buf)?\n");
+    ret.push_str("            } else {\n");
+    ret.push_str("                (buf, short_length as u16)\n");
+    ret.push_str("            };\n");
+    ret
+}
+
 fn generate_string_deserializer(ie_name: &String) -> String {
     let mut ret = String::new();
     let header = get_deserializer_header(ie_name.as_str());
@@ -954,19 +1104,7 @@ fn generate_string_deserializer(ie_name: &String) -> String {
     ret.push_str(header.as_str());
 
     ret.push_str("        if length == u16::MAX {\n");
-    ret.push_str("            let (buf, short_length) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("            let (buf, variable_length) = if short_length == u8::MAX {\n");
-    ret.push_str("                let mut variable_length: u32= 0;\n");
-    ret.push_str("                let (buf, part1) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                let (buf, part2) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                let (buf, part3) = nom::number::complete::be_u8(buf)?;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part1  as u32;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part2  as u32;\n");
-    ret.push_str("                variable_length = (variable_length << 8) + part3  as u32;\n");
-    ret.push_str("                (buf, variable_length)\n");
-    ret.push_str("            } else {\n");
-    ret.push_str("                (buf, short_length as u32)\n");
-    ret.push_str("            };\n");
+    ret.push_str(generate_variable_length_deserializer().as_str());
     ret.push_str("            let (buf, value) = nom::combinator::map_res(nom::bytes::complete::take(variable_length), |str_buf: netgauze_parse_utils::Span<'_>| {\n");
     ret.push_str("                let result = ::std::str::from_utf8(&str_buf);\n");
     ret.push_str("                result.map(|x| x.to_string())\n");
@@ -1102,6 +1240,12 @@ fn generate_vec_u8_deserializer(ie_name: &String) -> String {
     let header = get_deserializer_header(ie_name.as_str());
     ret.push_str(std_error.as_str());
     ret.push_str(header.as_str());
+    ret.push_str("        let (buf, length) = if length == u16::MAX {\n");
+    ret.push_str(generate_variable_length_deserializer().as_str());
+    ret.push_str("            (buf, variable_length)\n");
+    ret.push_str("        } else {\n");
+    ret.push_str("            (buf, length)\n");
+    ret.push_str("        };\n");
     ret.push_str("        let (buf, value) = nom::multi::count(nom::number::complete::be_u8, length as usize)(buf)?;\n");
     ret.push_str(format!("        Ok((buf, {ie_name}(value)))\n").as_str());
     ret.push_str("    }\n");
@@ -1109,6 +1253,27 @@ fn generate_vec_u8_deserializer(ie_name: &String) -> String {
     ret
 }
 
+/// Structured data are decoded by the hand-written parsers in
+/// `crate::wire::deserializer::ie`, since they need the IPFIX templates
+fn generate_list_deserializer(ie_name: &String) -> String {
+    let mut ret = String::new();
+    ret.push_str("#[allow(non_camel_case_types)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::LocatedError, Eq, PartialEq, Clone, Debug, serde::Serialize, serde::Deserialize)]\n");
+    ret.push_str(format!("pub enum {ie_name}ParsingError {{\n").as_str());
+    ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
+    ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
+    ret.push_str("    ListError(#[from_located(module = \"crate::wire::deserializer::ie\")] ListParsingError),\n");
+    ret.push_str("}\n\n");
+    ret.push_str(format!("impl<'a> netgauze_parse_utils::ReadablePduWithTwoInputs<'a, u16, Option<&crate::ipfix::TemplatesMap>, Located{ie_name}ParsingError<'a>> for {ie_name} {{\n").as_str());
+    ret.push_str("    #[inline]\n");
+    ret.push_str(format!("    fn from_wire(buf: netgauze_parse_utils::Span<'a>, length: u16, templates_map: Option<&crate::ipfix::TemplatesMap>) -> nom::IResult<netgauze_parse_utils::Span<'a>, Self, Located{ie_name}ParsingError<'a>> {{\n").as_str());
+    ret.push_str("        let (buf, value) = netgauze_parse_utils::parse_into_located_two_inputs(buf, length, templates_map)?;\n");
+    ret.push_str(format!("        Ok((buf, {ie_name}(value)))\n").as_str());
+    ret.push_str("    }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
 fn generate_ie_deserializer(data_type: &str, ie_name: &String) -> String {
     let mut ret = String::new();
     let gen = match data_type {
@@ -1134,10 +1299,9 @@ fn generate_ie_deserializer(data_type: &str, ie_name: &String) -> String {
         "dateTimeNanoseconds" => generate_date_time_micro(ie_name),
         "ipv4Address" => generate_ipv4_deserializer(ie_name),
         "ipv6Address" => generate_ipv6_deserializer(ie_name),
-        // TODO: better parsing for IPFIX structured Data
-        "basicList" => generate_vec_u8_deserializer(ie_name),
-        "subTemplateList" => generate_vec_u8_deserializer(ie_name),
-        "subTemplateMultiList" => generate_vec_u8_deserializer(ie_name),
+        "basicList" | "subTemplateList" | "subTemplateMultiList" => {
+            generate_list_deserializer(ie_name)
+        }
         ty => todo!("Unsupported deserialization for type: {}", ty),
     };
     ret.push_str(gen.as_str());
@@ -1149,8 +1313,17 @@ pub(crate) fn generate_pkg_ie_deserializers(
     ies: &Vec<InformationElement>,
 ) -> String {
     let mut ret = String::new();
-    // Not every vendor is using time based values
-    if ies.iter().any(|x| x.data_type.contains("String")) {
+    // Reduced-size encoding of the wider integers reads the input octet by
+    // octet
+    if ies.iter().any(|x| {
+        matches!(
+            x.data_type.as_str(),
+            "unsigned32" | "unsigned64" | "signed16" | "signed32" | "signed64"
+        )
+    }) {
+        ret.push_str("use nom::{InputLength, InputIter, Slice};\n");
+    } else if ies.iter().any(|x| x.data_type.contains("String")) {
+        // Not every vendor is using time based values
         ret.push_str("use nom::InputIter;\n");
     }
     if ies.iter().any(|x| x.data_type.contains("chrono")) {
@@ -1230,20 +1403,66 @@ pub(crate) fn generate_fields_enum(ies: &Vec<InformationElement>) -> String {
     let mut ret = String::new();
     ret.push_str("#[allow(non_camel_case_types)]\n");
     let not_copy = ies.iter().any(|x| {
-        get_rust_type(&x.data_type) == "Vec<u8>" || get_rust_type(&x.data_type) == "String"
+        get_rust_type(&x.data_type) == "Vec<u8>"
+            || get_rust_type(&x.data_type) == "String"
+            || is_structured_data_type(&x.data_type)
+    });
+    let not_eq = ies.iter().any(|x| {
+        get_rust_type(&x.data_type) == "f32"
+            || get_rust_type(&x.data_type) == "f64"
+            || is_structured_data_type(&x.data_type)
     });
-    let not_eq = ies
-        .iter()
-        .any(|x| get_rust_type(&x.data_type) == "f32" || get_rust_type(&x.data_type) == "f64");
     ret.push_str(generate_derive(false, !not_copy, !not_eq).as_str());
     ret.push_str("pub enum Field {\n");
     for ie in ies {
         ret.push_str(format!("    {}({}),\n", ie.name, ie.name).as_str());
     }
+    ret.push_str("}\n\n");
+
+    ret.push_str("impl Field {\n");
+    ret.push_str("    pub const fn ie(&self) -> IE {\n");
+    ret.push_str("        match self {\n");
+    for ie in ies {
+        ret.push_str(format!("            Self::{}(_) => IE::{},\n", ie.name, ie.name).as_str());
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n\n");
+    ret.push_str(
+        generate_field_serialize_value(
+            ies.iter()
+                .map(|ie| format!("Self::{}(value) => value.serialize(serializer)", ie.name)),
+        )
+        .as_str(),
+    );
     ret.push_str("}\n");
     ret
 }
 
+/// Serialize only the value of a field, i.e., without the IE name, given the
+/// match arms for each field variant.
+fn generate_field_serialize_value(arms: impl Iterator<Item = String>) -> String {
+    let mut ret = String::new();
+    ret.push_str("    /// Serialize the value of the field without the name of the IE\n");
+    ret.push_str("    pub(crate) fn serialize_value<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {\n");
+    ret.push_str("        use serde::Serialize;\n");
+    ret.push_str("        match self {\n");
+    for arm in arms {
+        ret.push_str(format!("            {arm},\n").as_str());
+    }
+    ret.push_str("        }\n");
+    ret.push_str("    }\n");
+    ret
+}
+
+/// Structured data types [RFC6313](https://datatracker.ietf.org/doc/html/rfc6313)
+/// need the IPFIX templates to be decoded
+fn is_structured_data_type(data_type: &str) -> bool {
+    matches!(
+        data_type,
+        "basicList" | "subTemplateList" | "subTemplateMultiList"
+    )
+}
+
 fn get_rust_type(data_type: &str) -> String {
     let rust_type = match data_type {
         "octetArray" => "Vec<u8>",
@@ -1266,7 +1485,9 @@ fn get_rust_type(data_type: &str) -> String {
         | "dateTimeNanoseconds" => "chrono::DateTime<chrono::Utc>",
         "ipv4Address" => "std::net::Ipv4Addr",
         "ipv6Address" => "std::net::Ipv6Addr",
-        "basicList" | "subTemplateList" | "subTemplateMultiList" => "Vec<u8>",
+        "basicList" => "crate::ie::BasicList",
+        "subTemplateList" => "crate::ie::SubTemplateList",
+        "subTemplateMultiList" => "crate::ie::SubTemplateMultiList",
         other => todo!("Implement rust data type conversion for {}", other),
     };
     rust_type.to_string()
@@ -1277,10 +1498,11 @@ pub(crate) fn generate_ie_values(ies: &Vec<InformationElement>) -> String {
     for ie in ies {
         let rust_type = get_rust_type(&ie.data_type);
         ret.push_str("#[allow(non_camel_case_types)]\n");
+        let structured = is_structured_data_type(&ie.data_type);
         let generate_derive = generate_derive(
             false,
-            rust_type != "Vec<u8>" && rust_type != "String",
-            rust_type != "f32" && rust_type != "f64",
+            rust_type != "Vec<u8>" && rust_type != "String" && !structured,
+            rust_type != "f32" && rust_type != "f64" && !structured,
         );
         ret.push_str(generate_derive.as_str());
         ret.push_str(format!("pub struct {}(pub {});\n\n", ie.name, rust_type).as_str());
@@ -1292,6 +1514,47 @@ pub(crate) fn generate_ie_values(ies: &Vec<InformationElement>) -> String {
     ret
 }
 
+/// The templates map is needed to decode the IEs with structured data types,
+/// see [RFC6313](https://datatracker.ietf.org/doc/html/rfc6313)
+fn generate_field_deserializer_header(uses_templates: bool) -> String {
+    let templates_map = if uses_templates {
+        "templates_map"
+    } else {
+        "_templates_map"
+    };
+    let mut ret = String::new();
+    ret.push_str("impl<'a> netgauze_parse_utils::ReadablePduWithThreeInputs<'a, &IE, u16, Option<&crate::ipfix::TemplatesMap>, LocatedFieldParsingError<'a>>\n");
+    ret.push_str("for Field {\n");
+    ret.push_str("    #[inline]\n");
+    ret.push_str("    fn from_wire(\n");
+    ret.push_str("        buf: netgauze_parse_utils::Span<'a>,\n");
+    ret.push_str("        ie: &IE,\n");
+    ret.push_str("        length: u16,\n");
+    ret.push_str(
+        format!("        {templates_map}: Option<&crate::ipfix::TemplatesMap>,\n").as_str(),
+    );
+    ret.push_str("    ) -> nom::IResult<netgauze_parse_utils::Span<'a>, Self, LocatedFieldParsingError<'a>> {\n");
+    ret
+}
+
+/// Decode a field without any templates, structured data types that refer to
+/// a template fail to be decoded
+fn generate_field_deserializer_without_templates() -> String {
+    let mut ret = String::new();
+    ret.push_str("impl<'a> netgauze_parse_utils::ReadablePduWithTwoInputs<'a, &IE, u16, LocatedFieldParsingError<'a>>\n");
+    ret.push_str("for Field {\n");
+    ret.push_str("    #[inline]\n");
+    ret.push_str("    fn from_wire(\n");
+    ret.push_str("        buf: netgauze_parse_utils::Span<'a>,\n");
+    ret.push_str("        ie: &IE,\n");
+    ret.push_str("        length: u16,\n");
+    ret.push_str("    ) -> nom::IResult<netgauze_parse_utils::Span<'a>, Self, LocatedFieldParsingError<'a>> {\n");
+    ret.push_str("        <Self as netgauze_parse_utils::ReadablePduWithThreeInputs<'a, &IE, u16, Option<&crate::ipfix::TemplatesMap>, LocatedFieldParsingError<'a>>>::from_wire(buf, ie, length, None)\n");
+    ret.push_str("    }\n");
+    ret.push_str("}\n");
+    ret
+}
+
 fn generate_ie_values_deserializers(ies: &Vec<InformationElement>) -> String {
     let mut ret = String::new();
     let ty_name = "Field";
@@ -1312,25 +1575,24 @@ fn generate_ie_values_deserializers(ies: &Vec<InformationElement>) -> String {
     ret.push_str("}\n");
     ret.push_str("\n\n");
 
-    ret.push_str(format!("impl<'a> netgauze_parse_utils::ReadablePduWithTwoInputs<'a, &IE, u16, Located{ty_name}ParsingError<'a>>\n").as_str());
-    ret.push_str(format!("for {ty_name} {{\n").as_str());
-    ret.push_str("    #[inline]\n");
-    ret.push_str("    fn from_wire(\n");
-    ret.push_str("        buf: netgauze_parse_utils::Span<'a>,\n");
-    ret.push_str("        ie: &IE,\n");
-    ret.push_str("        length: u16,\n");
-    ret.push_str(format!("    ) -> nom::IResult<netgauze_parse_utils::Span<'a>, Self, Located{ty_name}ParsingError<'a>> {{\n").as_str());
+    let uses_templates = ies.iter().any(|x| is_structured_data_type(&x.data_type));
+    ret.push_str(generate_field_deserializer_header(uses_templates).as_str());
     ret.push_str("        let (buf, value) = match ie {\n");
     for ie in ies {
         ret.push_str(format!("            IE::{} => {{\n", ie.name).as_str());
-        ret.push_str(format!("                let (buf, value) = netgauze_parse_utils::parse_into_located_one_input::<'_, u16, Located{}ParsingError<'_>, Located{}ParsingError<'_>, {}>(buf, length)?;\n", ie.name, ty_name, ie.name).as_str());
+        if is_structured_data_type(&ie.data_type) {
+            ret.push_str(format!("                let (buf, value) = netgauze_parse_utils::parse_into_located_two_inputs::<'_, u16, Option<&crate::ipfix::TemplatesMap>, Located{}ParsingError<'_>, Located{}ParsingError<'_>, {}>(buf, length, templates_map)?;\n", ie.name, ty_name, ie.name).as_str());
+        } else {
+            ret.push_str(format!("                let (buf, value) = netgauze_parse_utils::parse_into_located_one_input::<'_, u16, Located{}ParsingError<'_>, Located{}ParsingError<'_>, {}>(buf, length)?;\n", ie.name, ty_name, ie.name).as_str());
+        }
         ret.push_str(format!("                (buf, Field::{}(value))\n", ie.name).as_str());
         ret.push_str("            }\n");
     }
     ret.push_str("        };\n");
     ret.push_str("       Ok((buf, value))\n");
     ret.push_str("    }\n");
-    ret.push_str("}\n");
+    ret.push_str("}\n\n");
+    ret.push_str(generate_field_deserializer_without_templates().as_str());
 
     ret
 }
@@ -1353,6 +1615,9 @@ pub(crate) fn generate_ie_deser_main(
     ret.push_str(format!("pub enum {ty_name}ParsingError {{\n").as_str());
     ret.push_str("    #[serde(with = \"netgauze_parse_utils::ErrorKindSerdeDeref\")]\n");
     ret.push_str("    NomError(#[from_nom] nom::error::ErrorKind),\n");
+    ret.push_str(
+        "    EnterpriseError(#[from_located(module = \"self\")] EnterpriseValueParsingError),\n",
+    );
     for (name, pkg, _) in vendor_prefixes {
         let value_name = format!("{pkg}::FieldParsingError");
         ret.push_str(
@@ -1371,24 +1636,21 @@ pub(crate) fn generate_ie_deser_main(
     ret.push_str("}\n");
     ret.push_str("\n\n");
 
-    ret.push_str("impl<'a> netgauze_parse_utils::ReadablePduWithTwoInputs<'a, &IE, u16, LocatedFieldParsingError<'a>>\n");
-    ret.push_str("for Field {\n");
-    ret.push_str("    #[inline]\n");
-    ret.push_str("    fn from_wire(\n");
-    ret.push_str("        buf: netgauze_parse_utils::Span<'a>,\n");
-    ret.push_str("        ie: &IE,\n");
-    ret.push_str("        length: u16,\n");
-    ret.push_str("    ) -> nom::IResult<netgauze_parse_utils::Span<'a>, Self, LocatedFieldParsingError<'a>> {\n");
+    ret.push_str(generate_field_deserializer_header(true).as_str());
     ret.push_str("        let (buf, value) = match ie {\n");
     for (name, _, _) in vendor_prefixes {
         ret.push_str(format!("            IE::{name}(value_ie) => {{\n").as_str());
-        ret.push_str("                let (buf, value) = netgauze_parse_utils::parse_into_located_two_inputs(buf, value_ie, length)?;\n");
+        ret.push_str("                let (buf, value) = netgauze_parse_utils::parse_into_located_three_inputs(buf, value_ie, length, templates_map)?;\n");
         ret.push_str(format!("                (buf, crate::ie::Field::{name}(value))\n").as_str());
         ret.push_str("            }\n");
     }
     for ie in iana_ies {
         ret.push_str(format!("            IE::{} => {{\n", ie.name).as_str());
-        ret.push_str("                let (buf, value) = netgauze_parse_utils::parse_into_located_one_input(buf, length)?;\n");
+        if is_structured_data_type(&ie.data_type) {
+            ret.push_str("                let (buf, value) = netgauze_parse_utils::parse_into_located_two_inputs(buf, length, templates_map)?;\n");
+        } else {
+            ret.push_str("                let (buf, value) = netgauze_parse_utils::parse_into_located_one_input(buf, length)?;\n");
+        }
         ret.push_str(
             format!(
                 "                (buf, crate::ie::Field::{}(value))\n",
@@ -1398,11 +1660,14 @@ pub(crate) fn generate_ie_deser_main(
         );
         ret.push_str("            }\n");
     }
-    ret.push_str("            _ => todo!(\"Handle unknown IEs\")\n");
+    ret.push_str(
+        "            IE::Unknown{pen, id} => parse_unknown_field(buf, *pen, *id, length)?,\n",
+    );
     ret.push_str("        };\n");
     ret.push_str("        Ok((buf, value))\n");
     ret.push_str("    }\n");
-    ret.push_str("}\n");
+    ret.push_str("}\n\n");
+    ret.push_str(generate_field_deserializer_without_templates().as_str());
 
     ret
 }
@@ -1417,9 +1682,24 @@ fn get_std_serializer_error(ty_name: &str) -> String {
     ret
 }
 
+/// Numbers can be encoded with fewer octets than their natural length
+/// [RFC7011](https://datatracker.ietf.org/doc/html/rfc7011#section-6.2)
+fn get_reduced_size_serializer_error(ty_name: &str) -> String {
+    let mut ret = String::new();
+    ret.push_str("#[allow(non_camel_case_types)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::WritingError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(format!("pub enum {ty_name}WritingError {{\n").as_str());
+    ret.push_str("    StdIOError(#[from_std_io_error] String),\n");
+    ret.push_str(
+        "    ReducedSizeError(#[from] crate::wire::serializer::ie::ReducedSizeWritingError),\n",
+    );
+    ret.push_str("}\n\n");
+    ret
+}
+
 fn generate_num8_serializer(num_type: &str, ie_name: &String) -> String {
     let mut ret = String::new();
-    ret.push_str(get_std_serializer_error(ie_name.as_str()).as_str());
+    ret.push_str(get_reduced_size_serializer_error(ie_name.as_str()).as_str());
     ret.push_str(
         format!(
             "impl netgauze_parse_utils::WritablePduWithOneInput<Option<u16>, {ie_name}WritingError> for {ie_name} {{\n"
@@ -1430,7 +1710,12 @@ fn generate_num8_serializer(num_type: &str, ie_name: &String) -> String {
     ret.push_str("     fn len(&self, _length: Option<u16>) -> usize {\n");
     ret.push_str("         Self::BASE_LENGTH\n");
     ret.push_str("     }\n\n");
-    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, _length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str("         if let Some(len) = length {\n");
+    ret.push_str("             if len != 1 {\n");
+    ret.push_str("                 return Err(crate::wire::serializer::ie::ReducedSizeWritingError::InvalidLength(len).into());\n");
+    ret.push_str("             }\n");
+    ret.push_str("         }\n");
     ret.push_str(format!("         writer.write_{num_type}(self.0)?;\n").as_str());
     ret.push_str("         Ok(())\n");
     ret.push_str("     }\n");
@@ -1438,9 +1723,11 @@ fn generate_num8_serializer(num_type: &str, ie_name: &String) -> String {
     ret
 }
 
+/// Integers are written with the reduced-size encoding when the template
+/// gives a shorter length, as long as the value fits
 fn generate_num_serializer(num_type: &str, length: u16, ie_name: &str) -> String {
     let mut ret = String::new();
-    ret.push_str(get_std_serializer_error(ie_name).as_str());
+    ret.push_str(get_reduced_size_serializer_error(ie_name).as_str());
     ret.push_str(
         format!(
             "impl netgauze_parse_utils::WritablePduWithOneInput<Option<u16>, {ie_name}WritingError> for {ie_name} {{\n"
@@ -1463,10 +1750,19 @@ fn generate_num_serializer(num_type: &str, length: u16, ie_name: &str) -> String
         )
         .as_str(),
     );
+    let reduced_size = if num_type.starts_with('i') {
+        "reduced_size_signed"
+    } else {
+        "reduced_size_unsigned"
+    };
     ret.push_str("             Some(len) => {\n");
     ret.push_str("                 let be_bytes = self.0.to_be_bytes();\n");
-    ret.push_str("                 let begin_offset = be_bytes.len() - len as usize;\n");
-    ret.push_str("                 writer.write_all(&be_bytes[begin_offset..])?;\n");
+    ret.push_str(
+        format!(
+            "                 writer.write_all(crate::wire::serializer::ie::{reduced_size}(&be_bytes, len)?)?;\n"
+        )
+        .as_str(),
+    );
     ret.push_str("             }\n");
     ret.push_str("         }\n");
     ret.push_str("         Ok(())\n");
@@ -1475,6 +1771,48 @@ fn generate_num_serializer(num_type: &str, length: u16, ie_name: &str) -> String
     ret
 }
 
+/// float64 can be written as float32 when no precision is lost, float32 can't
+/// be reduced any further
+fn generate_float_serializer(num_type: &str, ie_name: &str) -> String {
+    let length = if num_type == "f32" { 4 } else { 8 };
+    let mut ret = String::new();
+    ret.push_str(get_reduced_size_serializer_error(ie_name).as_str());
+    ret.push_str(
+        format!(
+            "impl netgauze_parse_utils::WritablePduWithOneInput<Option<u16>, {ie_name}WritingError> for {ie_name} {{\n"
+        )
+        .as_str(),
+    );
+    ret.push_str(format!("    const BASE_LENGTH: usize = {length};\n\n").as_str());
+    ret.push_str("     fn len(&self, length: Option<u16>) -> usize {\n");
+    ret.push_str("         match length {\n");
+    ret.push_str("             None => Self::BASE_LENGTH,\n");
+    ret.push_str("             Some(len) => len as usize,\n");
+    ret.push_str("         }\n");
+    ret.push_str("     }\n\n");
+    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str("         match length {\n");
+    if num_type == "f32" {
+        ret.push_str("             None | Some(4) => writer.write_f32::<byteorder::NetworkEndian>(self.0)?,\n");
+        ret.push_str("             Some(len) => return Err(crate::wire::serializer::ie::ReducedSizeWritingError::InvalidLength(len).into()),\n");
+    } else {
+        ret.push_str(
+            "             None => writer.write_f64::<byteorder::NetworkEndian>(self.0)?,\n",
+        );
+        ret.push_str("             Some(len) => match crate::wire::serializer::ie::reduced_size_float64(self.0, len)? {\n");
+        ret.push_str("                 Some(value) => writer.write_f32::<byteorder::NetworkEndian>(value)?,\n");
+        ret.push_str(
+            "                 None => writer.write_f64::<byteorder::NetworkEndian>(self.0)?,\n",
+        );
+        ret.push_str("             },\n");
+    }
+    ret.push_str("         }\n");
+    ret.push_str("         Ok(())\n");
+    ret.push_str("     }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
 fn generate_array_serializer(ie_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str(get_std_serializer_error(ie_name).as_str());
@@ -1496,6 +1834,63 @@ fn generate_array_serializer(ie_name: &str) -> String {
     ret
 }
 
+fn generate_list_serializer(ie_name: &str) -> String {
+    let mut ret = String::new();
+    ret.push_str("#[allow(non_camel_case_types)]\n");
+    ret.push_str("#[derive(netgauze_serde_macros::WritingError, Eq, PartialEq, Clone, Debug)]\n");
+    ret.push_str(format!("pub enum {ie_name}WritingError {{\n").as_str());
+    ret.push_str("    StdIOError(#[from_std_io_error] String),\n");
+    ret.push_str("    ListError(#[from] crate::wire::serializer::ie::ListWritingError),\n");
+    ret.push_str("}\n\n");
+    ret.push_str(
+        format!(
+            "impl netgauze_parse_utils::WritablePduWithOneInput<Option<u16>, {ie_name}WritingError> for {ie_name} {{\n"
+        )
+        .as_str(),
+    );
+    ret.push_str("    const BASE_LENGTH: usize = 0;\n\n");
+    ret.push_str("     fn len(&self, length: Option<u16>) -> usize {\n");
+    ret.push_str("         netgauze_parse_utils::WritablePduWithOneInput::len(&self.0, length)\n");
+    ret.push_str("     }\n\n");
+    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str("         netgauze_parse_utils::WritablePduWithOneInput::write(&self.0, writer, length)?;\n");
+    ret.push_str("         Ok(())\n");
+    ret.push_str("     }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
+/// Octet arrays are written with variable length, unless a fixed length is
+/// given by the template
+fn generate_vec_u8_serializer(ie_name: &str) -> String {
+    let mut ret = String::new();
+    ret.push_str(get_std_serializer_error(ie_name).as_str());
+    ret.push_str(
+        format!(
+            "impl netgauze_parse_utils::WritablePduWithOneInput<Option<u16>, {ie_name}WritingError> for {ie_name} {{\n"
+        )
+        .as_str(),
+    );
+    ret.push_str("    const BASE_LENGTH: usize = 0;\n\n");
+    ret.push_str("     fn len(&self, length: Option<u16>) -> usize {\n");
+    ret.push_str("         match length {\n");
+    ret.push_str("             Some(u16::MAX) => self.0.len() + crate::wire::serializer::ie::variable_length_prefix_len(self.0.len()),\n");
+    ret.push_str("             _ => self.0.len(),\n");
+    ret.push_str("         }\n");
+    ret.push_str("     }\n\n");
+    ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
+    ret.push_str("         if length == Some(u16::MAX) {\n");
+    ret.push_str(
+        "             crate::wire::serializer::ie::write_variable_length(writer, self.0.len())?;\n",
+    );
+    ret.push_str("         }\n");
+    ret.push_str("         writer.write_all(&self.0)?;\n");
+    ret.push_str("         Ok(())\n");
+    ret.push_str("     }\n");
+    ret.push_str("}\n\n");
+    ret
+}
+
 fn generate_ip_serializer(length: u16, ie_name: &str) -> String {
     let mut ret = String::new();
     ret.push_str(get_std_serializer_error(ie_name).as_str());
@@ -1530,37 +1925,22 @@ fn generate_string_serializer(ie_name: &str) -> String {
     ret.push('\n');
     ret.push_str("    fn len(&self, length: Option<u16>) -> usize {\n");
     ret.push_str("        match length {\n");
-    ret.push_str("            None => self.0.len(),\n");
-    ret.push_str("            Some(len) => if len == u16::MAX {\n");
-    ret.push_str("                if self.0.len() < u8::MAX as usize {\n");
-    ret.push_str("                    // One octet for the length field\n");
-    ret.push_str("                    self.0.len() + 1\n");
-    ret.push_str("                } else {\n");
-    ret.push_str("                    // 4 octets for the length field, first is 255 and other three carries the len\n");
-    ret.push_str("                    self.0.len() + 4\n");
-    ret.push_str("                }\n");
-    ret.push_str("            } else {\n");
-    ret.push_str("                len as usize\n");
-    ret.push_str("            },\n");
+    // Without a given length the string is written with variable length
+    ret.push_str("            Some(u16::MAX) | None => self.0.len() + crate::wire::serializer::ie::variable_length_prefix_len(self.0.len()),\n");
+    ret.push_str("            Some(len) => len as usize,\n");
     ret.push_str("        }\n");
     ret.push_str("    }\n");
     ret.push('\n');
     ret.push_str(format!("    fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ie_name}WritingError> {{\n").as_str());
     ret.push_str("        match length {\n");
     ret.push_str("            Some(u16::MAX) | None => {\n");
-    ret.push_str("                let bytes = self.0.as_bytes();\n");
-    ret.push_str("                if bytes.len() < u8::MAX as usize {\n");
-    ret.push_str("                    writer.write_u8(bytes.len() as u8)?;\n");
-    ret.push_str("                } else {\n");
-    ret.push_str("                    writer.write_u8(u8::MAX)?;\n");
-    ret.push_str("                    writer.write_all(&bytes.len().to_be_bytes()[1..])?;\n");
-    ret.push_str("                }\n");
+    ret.push_str("                crate::wire::serializer::ie::write_variable_length(writer, self.0.len())?;\n");
     ret.push_str("                writer.write_all(self.0.as_bytes())?;\n");
     ret.push_str("            }\n");
     ret.push_str("            Some(len) => {\n");
     ret.push_str("                writer.write_all(self.0.as_bytes())?;\n");
     ret.push_str("                // fill the rest with zeros\n");
-    ret.push_str("                for _ in self.0.as_bytes().len()..(len as usize) {\n");
+    ret.push_str("                for _ in self.0.len()..(len as usize) {\n");
     ret.push_str("                    writer.write_u8(0)?\n");
     ret.push_str("                }\n");
     ret.push_str("            }\n");
@@ -1668,7 +2048,7 @@ fn generate_fraction_serializer(ie_name: &String) -> String {
 fn generate_ie_serializer(data_type: &str, ie_name: &String) -> String {
     let mut ret = String::new();
     let gen = match data_type {
-        "octetArray" => generate_array_serializer(ie_name),
+        "octetArray" => generate_vec_u8_serializer(ie_name),
         "unsigned8" => generate_num8_serializer("u8", ie_name),
         "unsigned16" => generate_num_serializer("u16", 2, ie_name),
         "unsigned32" => generate_num_serializer("u32", 4, ie_name),
@@ -1677,8 +2057,8 @@ fn generate_ie_serializer(data_type: &str, ie_name: &String) -> String {
         "signed16" => generate_num_serializer("i16", 2, ie_name),
         "signed32" => generate_num_serializer("i32", 4, ie_name),
         "signed64" => generate_num_serializer("i64", 8, ie_name),
-        "float32" => generate_num_serializer("f32", 4, ie_name),
-        "float64" => generate_num_serializer("f64", 8, ie_name),
+        "float32" => generate_float_serializer("f32", ie_name),
+        "float64" => generate_float_serializer("f64", ie_name),
         "boolean" => generate_bool_serializer(ie_name),
         "macAddress" => generate_array_serializer(ie_name),
         "string" => generate_string_serializer(ie_name),
@@ -1690,10 +2070,9 @@ fn generate_ie_serializer(data_type: &str, ie_name: &String) -> String {
         "dateTimeNanoseconds" => generate_fraction_serializer(ie_name),
         "ipv4Address" => generate_ip_serializer(4, ie_name),
         "ipv6Address" => generate_ip_serializer(16, ie_name),
-        //// TODO: better parsing for IPFIX structured Data
-        "basicList" => generate_array_serializer(ie_name),
-        "subTemplateList" => generate_array_serializer(ie_name),
-        "subTemplateMultiList" => generate_array_serializer(ie_name),
+        "basicList" | "subTemplateList" | "subTemplateMultiList" => {
+            generate_list_serializer(ie_name)
+        }
         ty => todo!("Unsupported serialization for type: {}", ty),
     };
     ret.push_str(gen.as_str());
@@ -1716,6 +2095,7 @@ pub(crate) fn generate_ie_ser_main(
     ret.push_str("#[derive(netgauze_serde_macros::WritingError, Eq, PartialEq, Clone, Debug)]\n");
     ret.push_str(format!("pub enum {ty_name}WritingError {{\n").as_str());
     ret.push_str("    StdIOError(#[from_std_io_error] String),\n");
+    ret.push_str("    EnterpriseError(#[from] EnterpriseValueWritingError),\n");
     for (name, pkg, _) in vendor_prefixes {
         ret.push_str(format!("    {name}Error(#[from] {pkg}::FieldWritingError),\n").as_str());
     }
@@ -1734,6 +2114,7 @@ pub(crate) fn generate_ie_ser_main(
     ret.push_str("    fn len(&self, length: Option<u16>) -> usize {\n");
     ret.push_str("        match self {\n");
     ret.push_str("            Self::Unknown(value) => value.len(),\n");
+    ret.push_str("            Self::Enterprise(value) => value.value().len(length),\n");
     for (name, _, _) in vendor_prefixes {
         ret.push_str(format!("            Self::{name}(value) => value.len(length),\n").as_str());
     }
@@ -1752,6 +2133,7 @@ pub(crate) fn generate_ie_ser_main(
     ret.push_str(format!("     fn write<T:  std::io::Write>(&self, writer: &mut T, length: Option<u16>) -> Result<(), {ty_name}WritingError> {{\n").as_str());
     ret.push_str("        match self {\n");
     ret.push_str("            Self::Unknown(value) => writer.write_all(value)?,\n");
+    ret.push_str("            Self::Enterprise(value) => value.value().write(writer, length)?,\n");
     for (name, _pkg, _) in vendor_prefixes {
         ret.push_str(
             format!("            Self::{name}(value) => value.write(writer, length)?,\n").as_str(),
@@ -1,8 +1,10 @@
+#![cfg_attr(not(test), no_std)]
+
 #[cfg(test)]
 mod tests;
 
+use core::ops::{RangeFrom, RangeTo};
 use nom::{AsBytes, Compare, CompareResult, InputIter, InputLength, InputTake, Offset, Slice};
-use std::ops::{RangeFrom, RangeTo};
 
 /// Cloned from the crate `nom_locate` but with the omission of computing
 /// the line & column number since we don't care about them in binary protocols,
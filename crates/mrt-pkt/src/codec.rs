@@ -0,0 +1,161 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Codecs to decode and encode MRT records from byte streams, such as MRT dump
+//! files read with [`tokio_util::codec::FramedRead`].
+
+use crate::{
+    wire::{deserializer::MrtMessageParsingError, serializer::MrtMessageWritingError},
+    MrtMessage,
+};
+use byteorder::{ByteOrder, NetworkEndian};
+use bytes::{Buf, BytesMut};
+use netgauze_bgp_pkt::wire::deserializer::BgpParsingContext;
+use netgauze_parse_utils::{LocatedParsingError, ReadablePduWithOneInput, Span, WritablePdu};
+use nom::Needed;
+use serde::{Deserialize, Serialize};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Length of the MRT common header: 4-octets timestamp, 2-octets type,
+/// 2-octets subtype, and 4-octets length
+pub const MRT_HEADER_LENGTH: usize = 12;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub enum MrtCodecDecoderError {
+    IoError(String),
+    Incomplete(Option<usize>),
+    MrtMessageParsingError(MrtMessageParsingError),
+}
+
+impl From<std::io::Error> for MrtCodecDecoderError {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(error.to_string())
+    }
+}
+
+/// Encoder and Decoder for [`MrtMessage`]
+#[derive(Debug, Default)]
+pub struct MrtCodec {
+    ctx: BgpParsingContext,
+}
+
+impl MrtCodec {
+    /// The AS size and ADD-PATH settings of the context are overridden for each
+    /// record based on its type and subtype.
+    pub const fn new(ctx: BgpParsingContext) -> Self {
+        Self { ctx }
+    }
+}
+
+impl Encoder<MrtMessage> for MrtCodec {
+    type Error = MrtMessageWritingError;
+
+    fn encode(&mut self, msg: MrtMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        msg.write_into(dst)
+    }
+}
+
+impl Decoder for MrtCodec {
+    type Item = MrtMessage;
+    type Error = MrtCodecDecoderError;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if buf.len() < MRT_HEADER_LENGTH {
+            // We don't have enough data yet to start processing
+            return Ok(None);
+        }
+        let length =
+            MRT_HEADER_LENGTH + NetworkEndian::read_u32(&buf[8..MRT_HEADER_LENGTH]) as usize;
+        if buf.len() < length {
+            // We still didn't read all the bytes for the record yet
+            buf.reserve(length - buf.len());
+            return Ok(None);
+        }
+        match MrtMessage::from_wire(Span::new(&buf[..length]), &mut self.ctx) {
+            Ok((_, msg)) => {
+                buf.advance(length);
+                Ok(Some(msg))
+            }
+            Err(error) => {
+                let err = match error {
+                    nom::Err::Incomplete(needed) => {
+                        let needed = match needed {
+                            Needed::Unknown => None,
+                            Needed::Size(size) => Some(size.get()),
+                        };
+                        MrtCodecDecoderError::Incomplete(needed)
+                    }
+                    nom::Err::Error(error) | nom::Err::Failure(error) => {
+                        MrtCodecDecoderError::MrtMessageParsingError(error.error().clone())
+                    }
+                };
+                // Skip the whole record, since the length in the header is still valid, the
+                // next record could be parsed correctly.
+                buf.advance(length);
+                Err(err)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{iana::BgpState, Bgp4mp, Bgp4mpPeer, Bgp4mpStateChange, MrtValue};
+    use chrono::{TimeZone, Utc};
+    use netgauze_bgp_pkt::BgpMessage;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    #[test]
+    fn test_codec() -> Result<(), MrtMessageWritingError> {
+        let peer = Bgp4mpPeer::new(
+            65001,
+            65000,
+            0,
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+        );
+        let timestamp = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        let state_change = MrtMessage::new(
+            timestamp,
+            MrtValue::Bgp4mp(Bgp4mp::StateChange(Bgp4mpStateChange::new(
+                peer,
+                BgpState::OpenConfirm,
+                BgpState::Established,
+            ))),
+        );
+        let keepalive = MrtMessage::new(
+            timestamp,
+            MrtValue::Bgp4mp(Bgp4mp::MessageAs4(crate::Bgp4mpMessage::new(
+                peer,
+                BgpMessage::KeepAlive,
+            ))),
+        );
+        let mut codec = MrtCodec::default();
+        let mut buf = BytesMut::new();
+        codec.encode(state_change.clone(), &mut buf)?;
+        codec.encode(keepalive.clone(), &mut buf)?;
+        let mut partial = buf.split_to(MRT_HEADER_LENGTH + 1);
+
+        assert_eq!(codec.decode(&mut partial), Ok(None));
+        partial.unsplit(buf);
+        let mut buf = partial;
+        assert_eq!(codec.decode(&mut buf), Ok(Some(state_change)));
+        assert_eq!(codec.decode(&mut buf), Ok(Some(keepalive)));
+        assert_eq!(codec.decode(&mut buf), Ok(None));
+        assert!(buf.is_empty());
+        Ok(())
+    }
+}
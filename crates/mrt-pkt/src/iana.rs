@@ -0,0 +1,200 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Contains MRT codes that are registered at IANA [Multi-Threaded Routing Toolkit (MRT)](https://www.iana.org/assignments/mrt/mrt.xhtml)
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, FromRepr};
+
+/// Peer Type bit in the [`crate::PeerEntry`] indicating the peer address is an
+/// IPv6 address. See [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396#section-4.3.1)
+pub const PEER_TYPE_IS_IPV6: u8 = 0b00000001;
+
+/// Peer Type bit in the [`crate::PeerEntry`] indicating the peer AS is encoded
+/// in 4-octets. See [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396#section-4.3.1)
+pub const PEER_TYPE_IS_ASN4: u8 = 0b00000010;
+
+/// MRT Types as registered in IANA [MRT Types](https://www.iana.org/assignments/mrt/mrt.xhtml#type-codes)
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MrtType {
+    Ospfv2 = 11,
+    TableDump = 12,
+    TableDumpV2 = 13,
+    Bgp4mp = 16,
+    Bgp4mpEt = 17,
+    Isis = 32,
+    IsisEt = 33,
+    Ospfv3 = 48,
+    Ospfv3Et = 49,
+}
+
+/// MRT Type is not one of [`MrtType`], the carried value is the undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedMrtType(pub u16);
+
+impl From<MrtType> for u16 {
+    fn from(value: MrtType) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for MrtType {
+    type Error = UndefinedMrtType;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedMrtType(value)),
+        }
+    }
+}
+
+/// TABLE_DUMP_V2 Subtypes as registered in IANA [TABLE_DUMP_V2 Subtype Codes](https://www.iana.org/assignments/mrt/mrt.xhtml#table-dump-v2-subtype-codes)
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum TableDumpV2SubType {
+    PeerIndexTable = 1,
+    RibIpv4Unicast = 2,
+    RibIpv4Multicast = 3,
+    RibIpv6Unicast = 4,
+    RibIpv6Multicast = 5,
+    RibGeneric = 6,
+    GeoPeerTable = 7,
+    RibIpv4UnicastAddPath = 8,
+    RibIpv4MulticastAddPath = 9,
+    RibIpv6UnicastAddPath = 10,
+    RibIpv6MulticastAddPath = 11,
+    RibGenericAddPath = 12,
+}
+
+/// TABLE_DUMP_V2 Subtype is not one of [`TableDumpV2SubType`], the carried
+/// value is the undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedTableDumpV2SubType(pub u16);
+
+impl From<TableDumpV2SubType> for u16 {
+    fn from(value: TableDumpV2SubType) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for TableDumpV2SubType {
+    type Error = UndefinedTableDumpV2SubType;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedTableDumpV2SubType(value)),
+        }
+    }
+}
+
+/// BGP4MP and BGP4MP_ET Subtypes as registered in IANA [BGP4MP Subtype Codes](https://www.iana.org/assignments/mrt/mrt.xhtml#BGP4MP-codes)
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Bgp4mpSubType {
+    StateChange = 0,
+    Message = 1,
+    MessageAs4 = 4,
+    StateChangeAs4 = 5,
+    MessageLocal = 6,
+    MessageAs4Local = 7,
+    MessageAddPath = 8,
+    MessageAs4AddPath = 9,
+    MessageLocalAddPath = 10,
+    MessageAs4LocalAddPath = 11,
+}
+
+/// BGP4MP Subtype is not one of [`Bgp4mpSubType`], the carried value is the
+/// undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedBgp4mpSubType(pub u16);
+
+impl From<Bgp4mpSubType> for u16 {
+    fn from(value: Bgp4mpSubType) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for Bgp4mpSubType {
+    type Error = UndefinedBgp4mpSubType;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedBgp4mpSubType(value)),
+        }
+    }
+}
+
+/// BGP FSM states as encoded in the BGP4MP_STATE_CHANGE messages.
+/// See [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396#section-4.4.1)
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BgpState {
+    Idle = 1,
+    Connect = 2,
+    Active = 3,
+    OpenSent = 4,
+    OpenConfirm = 5,
+    Established = 6,
+}
+
+/// BGP State is not one of [`BgpState`], the carried value is the undefined
+/// code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedBgpState(pub u16);
+
+impl From<BgpState> for u16 {
+    fn from(value: BgpState) -> Self {
+        value as u16
+    }
+}
+
+impl TryFrom<u16> for BgpState {
+    type Error = UndefinedBgpState;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedBgpState(value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mrt_type() {
+        let undefined_code = 0;
+        let table_dump_v2 = MrtType::try_from(13);
+        let undefined = MrtType::try_from(undefined_code);
+        assert_eq!(table_dump_v2, Ok(MrtType::TableDumpV2));
+        assert_eq!(u16::from(MrtType::Bgp4mpEt), 17);
+        assert_eq!(undefined, Err(UndefinedMrtType(undefined_code)));
+    }
+
+    #[test]
+    fn test_bgp4mp_sub_type() {
+        let undefined_code = 2;
+        let message_as4 = Bgp4mpSubType::try_from(4);
+        let undefined = Bgp4mpSubType::try_from(undefined_code);
+        assert_eq!(message_as4, Ok(Bgp4mpSubType::MessageAs4));
+        assert_eq!(undefined, Err(UndefinedBgp4mpSubType(undefined_code)));
+    }
+}
@@ -0,0 +1,560 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Representation of the MRT routing information export format as defined in
+//! [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396). Only the
+//! `TABLE_DUMP_V2`, `BGP4MP`, and `BGP4MP_ET` types are supported, which are
+//! the ones used by public route collectors such as RouteViews and RIPE RIS.
+
+use std::net::{IpAddr, Ipv4Addr};
+
+use chrono::{DateTime, Utc};
+use ipnet::{Ipv4Net, Ipv6Net};
+use netgauze_bgp_pkt::{path_attribute::PathAttribute, BgpMessage};
+use serde::{Deserialize, Serialize};
+
+use crate::iana::{Bgp4mpSubType, BgpState, MrtType, TableDumpV2SubType};
+
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod iana;
+#[cfg(feature = "serde")]
+pub mod wire;
+
+/// MRT Common Header, for the extended timestamp types (`_ET`) the
+/// microseconds field is carried in the [`MrtMessage::timestamp`].
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                           Timestamp                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |             Type              |            Subtype            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                             Length                            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Message... (variable)
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MrtMessage {
+    timestamp: DateTime<Utc>,
+    value: MrtValue,
+}
+
+impl MrtMessage {
+    pub const fn new(timestamp: DateTime<Utc>, value: MrtValue) -> Self {
+        Self { timestamp, value }
+    }
+
+    /// For non-extended types the sub-second part of the timestamp is not
+    /// carried on the wire
+    pub const fn timestamp(&self) -> &DateTime<Utc> {
+        &self.timestamp
+    }
+
+    pub const fn value(&self) -> &MrtValue {
+        &self.value
+    }
+
+    pub const fn get_type(&self) -> MrtType {
+        self.value.get_type()
+    }
+
+    pub const fn get_subtype(&self) -> u16 {
+        self.value.get_subtype()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MrtValue {
+    TableDumpV2(TableDumpV2),
+    Bgp4mp(Bgp4mp),
+    /// Same as [`MrtValue::Bgp4mp`] but with microseconds resolution
+    /// timestamp
+    Bgp4mpEt(Bgp4mp),
+}
+
+impl MrtValue {
+    /// Get the IANA MRT type
+    pub const fn get_type(&self) -> MrtType {
+        match self {
+            Self::TableDumpV2(_) => MrtType::TableDumpV2,
+            Self::Bgp4mp(_) => MrtType::Bgp4mp,
+            Self::Bgp4mpEt(_) => MrtType::Bgp4mpEt,
+        }
+    }
+
+    /// Get the numerical value of the type specific MRT subtype
+    pub const fn get_subtype(&self) -> u16 {
+        match self {
+            Self::TableDumpV2(value) => value.get_subtype() as u16,
+            Self::Bgp4mp(value) | Self::Bgp4mpEt(value) => value.get_subtype() as u16,
+        }
+    }
+}
+
+/// `TABLE_DUMP_V2` records as defined in [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396#section-4.3)
+/// and the ADD-PATH extensions defined in [RFC8050](https://datatracker.ietf.org/doc/html/rfc8050).
+///
+/// For the ADD-PATH variants, every [`RibEntry`] carries a path identifier.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TableDumpV2 {
+    PeerIndexTable(PeerIndexTable),
+    RibIpv4Unicast(RibIpv4),
+    RibIpv4Multicast(RibIpv4),
+    RibIpv6Unicast(RibIpv6),
+    RibIpv6Multicast(RibIpv6),
+    RibIpv4UnicastAddPath(RibIpv4),
+    RibIpv4MulticastAddPath(RibIpv4),
+    RibIpv6UnicastAddPath(RibIpv6),
+    RibIpv6MulticastAddPath(RibIpv6),
+}
+
+impl TableDumpV2 {
+    /// Get the IANA TABLE_DUMP_V2 subtype
+    pub const fn get_subtype(&self) -> TableDumpV2SubType {
+        match self {
+            Self::PeerIndexTable(_) => TableDumpV2SubType::PeerIndexTable,
+            Self::RibIpv4Unicast(_) => TableDumpV2SubType::RibIpv4Unicast,
+            Self::RibIpv4Multicast(_) => TableDumpV2SubType::RibIpv4Multicast,
+            Self::RibIpv6Unicast(_) => TableDumpV2SubType::RibIpv6Unicast,
+            Self::RibIpv6Multicast(_) => TableDumpV2SubType::RibIpv6Multicast,
+            Self::RibIpv4UnicastAddPath(_) => TableDumpV2SubType::RibIpv4UnicastAddPath,
+            Self::RibIpv4MulticastAddPath(_) => TableDumpV2SubType::RibIpv4MulticastAddPath,
+            Self::RibIpv6UnicastAddPath(_) => TableDumpV2SubType::RibIpv6UnicastAddPath,
+            Self::RibIpv6MulticastAddPath(_) => TableDumpV2SubType::RibIpv6MulticastAddPath,
+        }
+    }
+}
+
+/// The PEER_INDEX_TABLE provides the BGP ID of the collector, an optional view
+/// name, and a list of indexed peers referenced by [`RibEntry::peer_index`].
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Collector BGP ID                         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |       View Name Length        |     View Name (variable)      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |          Peer Count           |    Peer Entries (variable)
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerIndexTable {
+    collector_bgp_id: Ipv4Addr,
+    view_name: String,
+    peers: Vec<PeerEntry>,
+}
+
+impl PeerIndexTable {
+    pub const fn new(collector_bgp_id: Ipv4Addr, view_name: String, peers: Vec<PeerEntry>) -> Self {
+        Self {
+            collector_bgp_id,
+            view_name,
+            peers,
+        }
+    }
+
+    pub const fn collector_bgp_id(&self) -> Ipv4Addr {
+        self.collector_bgp_id
+    }
+
+    pub const fn view_name(&self) -> &String {
+        &self.view_name
+    }
+
+    pub const fn peers(&self) -> &Vec<PeerEntry> {
+        &self.peers
+    }
+}
+
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |   Peer Type   |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Peer BGP ID                           |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                   Peer IP Address (variable)                  |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        Peer AS (variable)                     |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+///
+/// The IPv6 bit of the peer type is derived from the peer address, while the
+/// AS size bit is kept in `asn4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerEntry {
+    bgp_id: Ipv4Addr,
+    address: IpAddr,
+    peer_as: u32,
+    asn4: bool,
+}
+
+impl PeerEntry {
+    pub const fn new(bgp_id: Ipv4Addr, address: IpAddr, peer_as: u32, asn4: bool) -> Self {
+        Self {
+            bgp_id,
+            address,
+            peer_as,
+            asn4,
+        }
+    }
+
+    pub const fn bgp_id(&self) -> Ipv4Addr {
+        self.bgp_id
+    }
+
+    pub const fn address(&self) -> IpAddr {
+        self.address
+    }
+
+    pub const fn peer_as(&self) -> u32 {
+        self.peer_as
+    }
+
+    pub const fn asn4(&self) -> bool {
+        self.asn4
+    }
+}
+
+/// AFI/SAFI-Specific RIB for IPv4 prefixes
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Sequence Number                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// | Prefix Length |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                        Prefix (variable)                      |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Entry Count           |  RIB Entries (variable)
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RibIpv4 {
+    sequence_number: u32,
+    prefix: Ipv4Net,
+    entries: Vec<RibEntry>,
+}
+
+impl RibIpv4 {
+    pub const fn new(sequence_number: u32, prefix: Ipv4Net, entries: Vec<RibEntry>) -> Self {
+        Self {
+            sequence_number,
+            prefix,
+            entries,
+        }
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub const fn prefix(&self) -> Ipv4Net {
+        self.prefix
+    }
+
+    pub const fn entries(&self) -> &Vec<RibEntry> {
+        &self.entries
+    }
+}
+
+/// AFI/SAFI-Specific RIB for IPv6 prefixes, same layout as [`RibIpv4`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RibIpv6 {
+    sequence_number: u32,
+    prefix: Ipv6Net,
+    entries: Vec<RibEntry>,
+}
+
+impl RibIpv6 {
+    pub const fn new(sequence_number: u32, prefix: Ipv6Net, entries: Vec<RibEntry>) -> Self {
+        Self {
+            sequence_number,
+            prefix,
+            entries,
+        }
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub const fn prefix(&self) -> Ipv6Net {
+        self.prefix
+    }
+
+    pub const fn entries(&self) -> &Vec<RibEntry> {
+        &self.entries
+    }
+}
+
+/// A single route to a prefix as learned from the peer at `peer_index` in the
+/// [`PeerIndexTable`].
+///
+/// The `AS_PATH` attribute is always encoded with 4-octet AS numbers, and
+/// `MP_REACH_NLRI` is encoded in the abbreviated form that carries only the
+/// next hop. In the latter case, the parsed [`PathAttribute`] has an empty
+/// NLRI list.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Peer Index            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                         Originated Time                       |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                Path Identifier (only for ADD-PATH)            |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |      Attribute Length         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                    BGP Attributes... (variable)
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RibEntry {
+    peer_index: u16,
+    originated_time: DateTime<Utc>,
+    path_id: Option<u32>,
+    path_attributes: Vec<PathAttribute>,
+}
+
+impl RibEntry {
+    pub const fn new(
+        peer_index: u16,
+        originated_time: DateTime<Utc>,
+        path_id: Option<u32>,
+        path_attributes: Vec<PathAttribute>,
+    ) -> Self {
+        Self {
+            peer_index,
+            originated_time,
+            path_id,
+            path_attributes,
+        }
+    }
+
+    pub const fn peer_index(&self) -> u16 {
+        self.peer_index
+    }
+
+    pub const fn originated_time(&self) -> &DateTime<Utc> {
+        &self.originated_time
+    }
+
+    pub const fn path_id(&self) -> Option<u32> {
+        self.path_id
+    }
+
+    pub const fn path_attributes(&self) -> &Vec<PathAttribute> {
+        &self.path_attributes
+    }
+}
+
+/// `BGP4MP` and `BGP4MP_ET` records as defined in [RFC6396](https://datatracker.ietf.org/doc/html/rfc6396#section-4.4)
+/// and the ADD-PATH extensions defined in [RFC8050](https://datatracker.ietf.org/doc/html/rfc8050).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Bgp4mp {
+    StateChange(Bgp4mpStateChange),
+    Message(Bgp4mpMessage),
+    MessageAs4(Bgp4mpMessage),
+    StateChangeAs4(Bgp4mpStateChange),
+    MessageLocal(Bgp4mpMessage),
+    MessageAs4Local(Bgp4mpMessage),
+    MessageAddPath(Bgp4mpMessage),
+    MessageAs4AddPath(Bgp4mpMessage),
+    MessageLocalAddPath(Bgp4mpMessage),
+    MessageAs4LocalAddPath(Bgp4mpMessage),
+}
+
+impl Bgp4mp {
+    /// Get the IANA BGP4MP subtype
+    pub const fn get_subtype(&self) -> Bgp4mpSubType {
+        match self {
+            Self::StateChange(_) => Bgp4mpSubType::StateChange,
+            Self::Message(_) => Bgp4mpSubType::Message,
+            Self::MessageAs4(_) => Bgp4mpSubType::MessageAs4,
+            Self::StateChangeAs4(_) => Bgp4mpSubType::StateChangeAs4,
+            Self::MessageLocal(_) => Bgp4mpSubType::MessageLocal,
+            Self::MessageAs4Local(_) => Bgp4mpSubType::MessageAs4Local,
+            Self::MessageAddPath(_) => Bgp4mpSubType::MessageAddPath,
+            Self::MessageAs4AddPath(_) => Bgp4mpSubType::MessageAs4AddPath,
+            Self::MessageLocalAddPath(_) => Bgp4mpSubType::MessageLocalAddPath,
+            Self::MessageAs4LocalAddPath(_) => Bgp4mpSubType::MessageAs4LocalAddPath,
+        }
+    }
+
+    /// The peer and local AS numbers are encoded in 4-octets, and the BGP
+    /// message `AS_PATH` consists only of 4-octet AS numbers
+    pub const fn is_asn4(&self) -> bool {
+        matches!(
+            self,
+            Self::MessageAs4(_)
+                | Self::StateChangeAs4(_)
+                | Self::MessageAs4Local(_)
+                | Self::MessageAs4AddPath(_)
+                | Self::MessageAs4LocalAddPath(_)
+        )
+    }
+
+    /// The BGP message NLRI carries ADD-PATH path identifiers
+    pub const fn is_add_path(&self) -> bool {
+        matches!(
+            self,
+            Self::MessageAddPath(_)
+                | Self::MessageAs4AddPath(_)
+                | Self::MessageLocalAddPath(_)
+                | Self::MessageAs4LocalAddPath(_)
+        )
+    }
+
+    pub const fn peer(&self) -> &Bgp4mpPeer {
+        match self {
+            Self::StateChange(value) | Self::StateChangeAs4(value) => value.peer(),
+            Self::Message(value)
+            | Self::MessageAs4(value)
+            | Self::MessageLocal(value)
+            | Self::MessageAs4Local(value)
+            | Self::MessageAddPath(value)
+            | Self::MessageAs4AddPath(value)
+            | Self::MessageLocalAddPath(value)
+            | Self::MessageAs4LocalAddPath(value) => value.peer(),
+        }
+    }
+}
+
+/// The common fields of all the BGP4MP subtypes. The peer and local addresses
+/// MUST be of the same address family.
+///
+/// ```text
+///  0                   1                   2                   3
+///  0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |         Peer AS Number        |        Local AS Number        |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |        Interface Index        |        Address Family         |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Peer IP Address (variable)               |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// |                      Local IP Address (variable)              |
+/// +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bgp4mpPeer {
+    peer_as: u32,
+    local_as: u32,
+    interface_index: u16,
+    peer_address: IpAddr,
+    local_address: IpAddr,
+}
+
+impl Bgp4mpPeer {
+    pub const fn new(
+        peer_as: u32,
+        local_as: u32,
+        interface_index: u16,
+        peer_address: IpAddr,
+        local_address: IpAddr,
+    ) -> Self {
+        Self {
+            peer_as,
+            local_as,
+            interface_index,
+            peer_address,
+            local_address,
+        }
+    }
+
+    pub const fn peer_as(&self) -> u32 {
+        self.peer_as
+    }
+
+    pub const fn local_as(&self) -> u32 {
+        self.local_as
+    }
+
+    pub const fn interface_index(&self) -> u16 {
+        self.interface_index
+    }
+
+    pub const fn peer_address(&self) -> IpAddr {
+        self.peer_address
+    }
+
+    pub const fn local_address(&self) -> IpAddr {
+        self.local_address
+    }
+}
+
+/// Records a change in the BGP FSM state of a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bgp4mpStateChange {
+    peer: Bgp4mpPeer,
+    old_state: BgpState,
+    new_state: BgpState,
+}
+
+impl Bgp4mpStateChange {
+    pub const fn new(peer: Bgp4mpPeer, old_state: BgpState, new_state: BgpState) -> Self {
+        Self {
+            peer,
+            old_state,
+            new_state,
+        }
+    }
+
+    pub const fn peer(&self) -> &Bgp4mpPeer {
+        &self.peer
+    }
+
+    pub const fn old_state(&self) -> BgpState {
+        self.old_state
+    }
+
+    pub const fn new_state(&self) -> BgpState {
+        self.new_state
+    }
+}
+
+/// A BGP message as received from the peer (or sent by the local speaker for
+/// the `_LOCAL` subtypes), including the BGP header.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bgp4mpMessage {
+    peer: Bgp4mpPeer,
+    message: BgpMessage,
+}
+
+impl Bgp4mpMessage {
+    pub const fn new(peer: Bgp4mpPeer, message: BgpMessage) -> Self {
+        Self { peer, message }
+    }
+
+    pub const fn peer(&self) -> &Bgp4mpPeer {
+        &self.peer
+    }
+
+    pub const fn message(&self) -> &BgpMessage {
+        &self.message
+    }
+}
@@ -0,0 +1,724 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deserializer library for MRT's wire protocol
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    string::FromUtf8Error,
+};
+
+use chrono::{DateTime, LocalResult, TimeZone, Utc};
+use ipnet::{Ipv4Net, Ipv6Net};
+use netgauze_bgp_pkt::{
+    iana::PathAttributeType,
+    path_attribute::{InvalidPathAttribute, MpReach, PathAttribute, PathAttributeValue},
+    wire::deserializer::{
+        path_attribute::PathAttributeParsingError,
+        update::{BgpUpdateMessageParsingError, LazyPathAttribute},
+        BgpMessageParsingError, BgpParsingContext, Ipv4PrefixParsingError, Ipv6PrefixParsingError,
+    },
+    BgpMessage,
+};
+use netgauze_iana::address_family::{AddressFamily, AddressType, UndefinedAddressFamily};
+use netgauze_parse_utils::{
+    parse_into_located, parse_into_located_one_input, parse_into_located_three_inputs,
+    parse_into_located_two_inputs, ErrorKindSerdeDeref, ReadablePdu, ReadablePduWithOneInput,
+    ReadablePduWithThreeInputs, ReadablePduWithTwoInputs, Span,
+};
+use netgauze_serde_macros::LocatedError;
+use nom::{
+    error::{ErrorKind, FromExternalError},
+    number::complete::{be_u128, be_u16, be_u32, be_u8},
+    IResult, Slice,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{iana::*, *};
+
+/// Address types for which the ADD-PATH is enabled when parsing the BGP
+/// messages carried in the ADD-PATH BGP4MP subtypes
+const ADD_PATH_ADDRESS_TYPES: [AddressType; 20] = [
+    AddressType::Ipv4Unicast,
+    AddressType::Ipv4Multicast,
+    AddressType::Ipv4MplsLabeledVpn,
+    AddressType::Ipv4MulticastBgpMplsVpn,
+    AddressType::Ipv4Bgp4over6,
+    AddressType::Ipv4FlowSpec,
+    AddressType::Ipv4FlowSpecL3Vpn,
+    AddressType::Ipv4NlriMplsLabels,
+    AddressType::Ipv6Unicast,
+    AddressType::Ipv6Multicast,
+    AddressType::Ipv6MplsLabeledVpn,
+    AddressType::Ipv6MulticastBgpMplsVpn,
+    AddressType::Ipv6Bgp6over4,
+    AddressType::Ipv6FlowSpec,
+    AddressType::Ipv6FlowSpecL3Vpn,
+    AddressType::Ipv6NlriMplsLabels,
+    AddressType::L2VpnBgpEvpn,
+    AddressType::BgpLs,
+    AddressType::BgpLsVpn,
+    AddressType::RouteTargetConstrains,
+];
+
+/// MRT defines the AS size and ADD-PATH per record rather than per BGP session.
+/// Run the parser with these settings, then restore the user's original
+/// settings in the parsing context.
+fn with_bgp_ctx<T>(
+    ctx: &mut BgpParsingContext,
+    asn4: bool,
+    add_path: bool,
+    f: impl FnOnce(&mut BgpParsingContext) -> T,
+) -> T {
+    let original_asn4 = ctx.asn4();
+    let add_path_map = if add_path {
+        ADD_PATH_ADDRESS_TYPES.iter().map(|x| (*x, true)).collect()
+    } else {
+        HashMap::new()
+    };
+    let original_add_path = std::mem::replace(ctx.add_path_mut(), add_path_map);
+    ctx.set_asn4(asn4);
+    let ret = f(ctx);
+    ctx.set_asn4(original_asn4);
+    *ctx.add_path_mut() = original_add_path;
+    ret
+}
+
+#[inline]
+fn parse_time(secs: u32, micros: u32) -> Option<DateTime<Utc>> {
+    match Utc.timestamp_opt(secs.into(), micros.checked_mul(1_000)?) {
+        LocalResult::Single(time) => Some(time),
+        _ => None,
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum MrtMessageParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UndefinedMrtType(#[from_external] UndefinedMrtType),
+    UnsupportedMrtType(MrtType),
+    UndefinedTableDumpV2SubType(UndefinedTableDumpV2SubType),
+    UndefinedBgp4mpSubType(UndefinedBgp4mpSubType),
+    InvalidTime(u32, u32),
+    TableDumpV2Error(#[from_located(module = "self")] TableDumpV2ParsingError),
+    Bgp4mpError(#[from_located(module = "self")] Bgp4mpParsingError),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, &mut BgpParsingContext, LocatedMrtMessageParsingError<'a>>
+    for MrtMessage
+{
+    fn from_wire(
+        buf: Span<'a>,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedMrtMessageParsingError<'a>> {
+        let (buf, timestamp_secs) = be_u32(buf)?;
+        let (buf, mrt_type) = nom::combinator::map_res(be_u16, MrtType::try_from)(buf)?;
+        let subtype_input = buf;
+        let (buf, subtype) = be_u16(buf)?;
+        let (reminder, buf) = nom::multi::length_data(be_u32)(buf)?;
+        let time_input = buf;
+        let (buf, timestamp_micro) = if mrt_type == MrtType::Bgp4mpEt {
+            be_u32(buf)?
+        } else {
+            (buf, 0)
+        };
+        let timestamp = match parse_time(timestamp_secs, timestamp_micro) {
+            Some(timestamp) => timestamp,
+            None => {
+                return Err(nom::Err::Error(LocatedMrtMessageParsingError::new(
+                    time_input,
+                    MrtMessageParsingError::InvalidTime(timestamp_secs, timestamp_micro),
+                )))
+            }
+        };
+        let (buf, value) = match mrt_type {
+            MrtType::TableDumpV2 => {
+                let subtype = match TableDumpV2SubType::try_from(subtype) {
+                    Ok(subtype) => subtype,
+                    Err(err) => {
+                        return Err(nom::Err::Error(LocatedMrtMessageParsingError::new(
+                            subtype_input,
+                            MrtMessageParsingError::UndefinedTableDumpV2SubType(err),
+                        )))
+                    }
+                };
+                let (buf, value) = parse_into_located_two_inputs(buf, subtype, ctx)?;
+                (buf, MrtValue::TableDumpV2(value))
+            }
+            MrtType::Bgp4mp | MrtType::Bgp4mpEt => {
+                let subtype = match Bgp4mpSubType::try_from(subtype) {
+                    Ok(subtype) => subtype,
+                    Err(err) => {
+                        return Err(nom::Err::Error(LocatedMrtMessageParsingError::new(
+                            subtype_input,
+                            MrtMessageParsingError::UndefinedBgp4mpSubType(err),
+                        )))
+                    }
+                };
+                let (buf, value) = parse_into_located_two_inputs(buf, subtype, ctx)?;
+                if mrt_type == MrtType::Bgp4mp {
+                    (buf, MrtValue::Bgp4mp(value))
+                } else {
+                    (buf, MrtValue::Bgp4mpEt(value))
+                }
+            }
+            MrtType::Ospfv2
+            | MrtType::TableDump
+            | MrtType::Isis
+            | MrtType::IsisEt
+            | MrtType::Ospfv3
+            | MrtType::Ospfv3Et => {
+                return Err(nom::Err::Error(LocatedMrtMessageParsingError::new(
+                    subtype_input,
+                    MrtMessageParsingError::UnsupportedMrtType(mrt_type),
+                )))
+            }
+        };
+        // Make sure the MRT record is fully parsed according to its length
+        if !buf.is_empty() {
+            return Err(nom::Err::Error(LocatedMrtMessageParsingError::new(
+                buf,
+                MrtMessageParsingError::NomError(ErrorKind::NonEmpty),
+            )));
+        }
+        Ok((reminder, MrtMessage::new(timestamp, value)))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum TableDumpV2ParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UnsupportedSubType(TableDumpV2SubType),
+    PeerIndexTableError(#[from_located(module = "self")] PeerIndexTableParsingError),
+    RibError(#[from_located(module = "self")] RibParsingError),
+}
+
+impl<'a>
+    ReadablePduWithTwoInputs<
+        'a,
+        TableDumpV2SubType,
+        &mut BgpParsingContext,
+        LocatedTableDumpV2ParsingError<'a>,
+    > for TableDumpV2
+{
+    fn from_wire(
+        buf: Span<'a>,
+        subtype: TableDumpV2SubType,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedTableDumpV2ParsingError<'a>> {
+        // AS_PATH in the RIB entries is always encoded with 4-octet AS numbers
+        with_bgp_ctx(ctx, true, false, |ctx| match subtype {
+            TableDumpV2SubType::PeerIndexTable => {
+                let (buf, value) = parse_into_located(buf)?;
+                Ok((buf, TableDumpV2::PeerIndexTable(value)))
+            }
+            TableDumpV2SubType::RibIpv4Unicast => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv4Unicast, false, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv4Unicast(value)))
+            }
+            TableDumpV2SubType::RibIpv4Multicast => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv4Multicast, false, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv4Multicast(value)))
+            }
+            TableDumpV2SubType::RibIpv6Unicast => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv6Unicast, false, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv6Unicast(value)))
+            }
+            TableDumpV2SubType::RibIpv6Multicast => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv6Multicast, false, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv6Multicast(value)))
+            }
+            TableDumpV2SubType::RibIpv4UnicastAddPath => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv4Unicast, true, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv4UnicastAddPath(value)))
+            }
+            TableDumpV2SubType::RibIpv4MulticastAddPath => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv4Multicast, true, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv4MulticastAddPath(value)))
+            }
+            TableDumpV2SubType::RibIpv6UnicastAddPath => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv6Unicast, true, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv6UnicastAddPath(value)))
+            }
+            TableDumpV2SubType::RibIpv6MulticastAddPath => {
+                let (buf, value) =
+                    parse_into_located_three_inputs(buf, AddressType::Ipv6Multicast, true, ctx)?;
+                Ok((buf, TableDumpV2::RibIpv6MulticastAddPath(value)))
+            }
+            TableDumpV2SubType::RibGeneric
+            | TableDumpV2SubType::GeoPeerTable
+            | TableDumpV2SubType::RibGenericAddPath => {
+                Err(nom::Err::Error(LocatedTableDumpV2ParsingError::new(
+                    buf,
+                    TableDumpV2ParsingError::UnsupportedSubType(subtype),
+                )))
+            }
+        })
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum PeerIndexTableParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    FromUtf8Error(String),
+    PeerEntryError(#[from_located(module = "self")] PeerEntryParsingError),
+}
+
+impl<'a> FromExternalError<Span<'a>, FromUtf8Error> for LocatedPeerIndexTableParsingError<'a> {
+    fn from_external_error(input: Span<'a>, _kind: ErrorKind, error: FromUtf8Error) -> Self {
+        LocatedPeerIndexTableParsingError::new(
+            input,
+            PeerIndexTableParsingError::FromUtf8Error(error.to_string()),
+        )
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedPeerIndexTableParsingError<'a>> for PeerIndexTable {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedPeerIndexTableParsingError<'a>> {
+        let (buf, collector_bgp_id) = be_u32(buf)?;
+        let (buf, view_name) =
+            nom::combinator::map_res(nom::multi::length_data(be_u16), |x: Span<'_>| {
+                String::from_utf8(x.to_vec())
+            })(buf)?;
+        let (mut buf, peer_count) = be_u16(buf)?;
+        let mut peers = Vec::with_capacity(peer_count as usize);
+        for _ in 0..peer_count {
+            let (tmp, peer) = parse_into_located(buf)?;
+            peers.push(peer);
+            buf = tmp;
+        }
+        Ok((
+            buf,
+            PeerIndexTable::new(Ipv4Addr::from(collector_bgp_id), view_name, peers),
+        ))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum PeerEntryParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+}
+
+impl<'a> ReadablePdu<'a, LocatedPeerEntryParsingError<'a>> for PeerEntry {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedPeerEntryParsingError<'a>> {
+        let (buf, peer_type) = be_u8(buf)?;
+        let ipv6 = peer_type & PEER_TYPE_IS_IPV6 == PEER_TYPE_IS_IPV6;
+        let asn4 = peer_type & PEER_TYPE_IS_ASN4 == PEER_TYPE_IS_ASN4;
+        let (buf, bgp_id) = be_u32(buf)?;
+        let (buf, address) = if ipv6 {
+            let (buf, address) = be_u128(buf)?;
+            (buf, IpAddr::V6(Ipv6Addr::from(address)))
+        } else {
+            let (buf, address) = be_u32(buf)?;
+            (buf, IpAddr::V4(Ipv4Addr::from(address)))
+        };
+        let (buf, peer_as) = if asn4 {
+            be_u32(buf)?
+        } else {
+            let (buf, peer_as) = be_u16(buf)?;
+            (buf, peer_as as u32)
+        };
+        Ok((
+            buf,
+            PeerEntry::new(Ipv4Addr::from(bgp_id), address, peer_as, asn4),
+        ))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum RibParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    Ipv4PrefixError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] Ipv4PrefixParsingError,
+    ),
+    Ipv6PrefixError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] Ipv6PrefixParsingError,
+    ),
+    RibEntryError(#[from_located(module = "self")] RibEntryParsingError),
+}
+
+/// Parse the list of RIB entries that is prefixed by a 2-octet count
+#[inline]
+fn parse_rib_entries<'a>(
+    buf: Span<'a>,
+    address_type: AddressType,
+    add_path: bool,
+    ctx: &mut BgpParsingContext,
+) -> IResult<Span<'a>, Vec<RibEntry>, LocatedRibParsingError<'a>> {
+    let (mut buf, entry_count) = be_u16(buf)?;
+    let mut entries = Vec::with_capacity(entry_count as usize);
+    for _ in 0..entry_count {
+        let (tmp, entry) = parse_into_located_three_inputs(buf, address_type, add_path, &mut *ctx)?;
+        entries.push(entry);
+        buf = tmp;
+    }
+    Ok((buf, entries))
+}
+
+impl<'a>
+    ReadablePduWithThreeInputs<
+        'a,
+        AddressType,
+        bool,
+        &mut BgpParsingContext,
+        LocatedRibParsingError<'a>,
+    > for RibIpv4
+{
+    fn from_wire(
+        buf: Span<'a>,
+        address_type: AddressType,
+        add_path: bool,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedRibParsingError<'a>> {
+        let (buf, sequence_number) = be_u32(buf)?;
+        let (buf, prefix): (Span<'_>, Ipv4Net) = parse_into_located(buf)?;
+        let (buf, entries) = parse_rib_entries(buf, address_type, add_path, ctx)?;
+        Ok((buf, RibIpv4::new(sequence_number, prefix, entries)))
+    }
+}
+
+impl<'a>
+    ReadablePduWithThreeInputs<
+        'a,
+        AddressType,
+        bool,
+        &mut BgpParsingContext,
+        LocatedRibParsingError<'a>,
+    > for RibIpv6
+{
+    fn from_wire(
+        buf: Span<'a>,
+        address_type: AddressType,
+        add_path: bool,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedRibParsingError<'a>> {
+        let (buf, sequence_number) = be_u32(buf)?;
+        let (buf, prefix): (Span<'_>, Ipv6Net) = parse_into_located(buf)?;
+        let (buf, entries) = parse_rib_entries(buf, address_type, add_path, ctx)?;
+        Ok((buf, RibIpv6::new(sequence_number, prefix, entries)))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum RibEntryParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    InvalidTime(u32),
+    InvalidMpReachNextHopLength(u8),
+    InvalidPathAttribute(InvalidPathAttribute),
+    PathAttributeBoundariesError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer::update")]
+        BgpUpdateMessageParsingError,
+    ),
+    PathAttributeError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer::path_attribute")]
+        PathAttributeParsingError,
+    ),
+}
+
+/// Parse the abbreviated `MP_REACH_NLRI` used in the RIB entries, which only
+/// carries the next hop length and the next hop address. The returned
+/// [`MpReach`] has an empty NLRI list.
+fn parse_abbreviated_mp_reach(
+    buf: Span<'_>,
+    address_type: AddressType,
+) -> IResult<Span<'_>, MpReach, LocatedRibEntryParsingError<'_>> {
+    let input = buf;
+    let (buf, next_hop_len) = be_u8(buf)?;
+    let (buf, next_hop, next_hop_local) = match next_hop_len {
+        4 => {
+            let (buf, next_hop) = be_u32(buf)?;
+            (buf, IpAddr::V4(Ipv4Addr::from(next_hop)), None)
+        }
+        16 => {
+            let (buf, next_hop) = be_u128(buf)?;
+            (buf, IpAddr::V6(Ipv6Addr::from(next_hop)), None)
+        }
+        32 => {
+            let (buf, next_hop) = be_u128(buf)?;
+            let (buf, next_hop_local) = be_u128(buf)?;
+            (
+                buf,
+                IpAddr::V6(Ipv6Addr::from(next_hop)),
+                Some(Ipv6Addr::from(next_hop_local)),
+            )
+        }
+        _ => {
+            return Err(nom::Err::Error(LocatedRibEntryParsingError::new(
+                input,
+                RibEntryParsingError::InvalidMpReachNextHopLength(next_hop_len),
+            )))
+        }
+    };
+    let mp_reach = match (address_type, next_hop) {
+        (AddressType::Ipv4Unicast, _) => MpReach::Ipv4Unicast {
+            next_hop,
+            next_hop_local,
+            nlri: vec![],
+        },
+        (AddressType::Ipv4Multicast, _) => MpReach::Ipv4Multicast {
+            next_hop,
+            next_hop_local,
+            nlri: vec![],
+        },
+        (AddressType::Ipv6Unicast, IpAddr::V6(next_hop_global)) => MpReach::Ipv6Unicast {
+            next_hop_global,
+            next_hop_local,
+            nlri: vec![],
+        },
+        (AddressType::Ipv6Multicast, IpAddr::V6(next_hop_global)) => MpReach::Ipv6Multicast {
+            next_hop_global,
+            next_hop_local,
+            nlri: vec![],
+        },
+        _ => {
+            return Err(nom::Err::Error(LocatedRibEntryParsingError::new(
+                input,
+                RibEntryParsingError::InvalidMpReachNextHopLength(next_hop_len),
+            )))
+        }
+    };
+    Ok((buf, mp_reach))
+}
+
+/// Decode a path attribute in a RIB entry, `MP_REACH_NLRI` is handled in both
+/// the abbreviated form defined by RFC6396 and the full form written by some
+/// older implementations.
+fn decode_rib_path_attribute<'a>(
+    attr: &LazyPathAttribute<'a>,
+    address_type: AddressType,
+    ctx: &mut BgpParsingContext,
+) -> Result<PathAttribute, nom::Err<LocatedRibEntryParsingError<'a>>> {
+    let value = attr.value_bytes();
+    let is_abbreviated = attr.code() == PathAttributeType::MpReachNlri as u8
+        && !value.is_empty()
+        && value[0] as usize + 1 == value.len();
+    if !is_abbreviated {
+        return attr.decode(ctx).map_err(|err| nom::Err::Error(err.into()));
+    }
+    let header_len = attr.span().len() - value.len();
+    let value_span = attr.span().slice(header_len..);
+    let (_, mp_reach) = parse_abbreviated_mp_reach(value_span, address_type)?;
+    PathAttribute::from(
+        attr.optional(),
+        attr.transitive(),
+        attr.partial(),
+        attr.extended_length(),
+        PathAttributeValue::MpReach(mp_reach),
+    )
+    .map_err(|(_, err)| {
+        nom::Err::Error(LocatedRibEntryParsingError::new(
+            attr.span(),
+            RibEntryParsingError::InvalidPathAttribute(err),
+        ))
+    })
+}
+
+impl<'a>
+    ReadablePduWithThreeInputs<
+        'a,
+        AddressType,
+        bool,
+        &mut BgpParsingContext,
+        LocatedRibEntryParsingError<'a>,
+    > for RibEntry
+{
+    fn from_wire(
+        buf: Span<'a>,
+        address_type: AddressType,
+        add_path: bool,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedRibEntryParsingError<'a>> {
+        let (buf, peer_index) = be_u16(buf)?;
+        let time_input = buf;
+        let (buf, originated_time) = be_u32(buf)?;
+        let originated_time = match parse_time(originated_time, 0) {
+            Some(time) => time,
+            None => {
+                return Err(nom::Err::Error(LocatedRibEntryParsingError::new(
+                    time_input,
+                    RibEntryParsingError::InvalidTime(originated_time),
+                )))
+            }
+        };
+        let (buf, path_id) = if add_path {
+            let (buf, path_id) = be_u32(buf)?;
+            (buf, Some(path_id))
+        } else {
+            (buf, None)
+        };
+        let (buf, mut attributes_buf) = nom::multi::length_data(be_u16)(buf)?;
+        let mut path_attributes = vec![];
+        while !attributes_buf.is_empty() {
+            let (tmp, attr) = parse_into_located::<_, _, LazyPathAttribute<'_>>(attributes_buf)?;
+            let attr = decode_rib_path_attribute(&attr, address_type, ctx)?;
+            path_attributes.push(attr);
+            attributes_buf = tmp;
+        }
+        Ok((
+            buf,
+            RibEntry::new(peer_index, originated_time, path_id, path_attributes),
+        ))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum Bgp4mpParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    PeerError(#[from_located(module = "self")] Bgp4mpPeerParsingError),
+    UndefinedBgpState(#[from_external] UndefinedBgpState),
+    BgpMessageError(
+        #[from_located(module = "netgauze_bgp_pkt::wire::deserializer")] BgpMessageParsingError,
+    ),
+}
+
+impl<'a>
+    ReadablePduWithTwoInputs<
+        'a,
+        Bgp4mpSubType,
+        &mut BgpParsingContext,
+        LocatedBgp4mpParsingError<'a>,
+    > for Bgp4mp
+{
+    fn from_wire(
+        buf: Span<'a>,
+        subtype: Bgp4mpSubType,
+        ctx: &mut BgpParsingContext,
+    ) -> IResult<Span<'a>, Self, LocatedBgp4mpParsingError<'a>> {
+        let (asn4, add_path) = match subtype {
+            Bgp4mpSubType::StateChange | Bgp4mpSubType::Message | Bgp4mpSubType::MessageLocal => {
+                (false, false)
+            }
+            Bgp4mpSubType::MessageAs4
+            | Bgp4mpSubType::StateChangeAs4
+            | Bgp4mpSubType::MessageAs4Local => (true, false),
+            Bgp4mpSubType::MessageAddPath | Bgp4mpSubType::MessageLocalAddPath => (false, true),
+            Bgp4mpSubType::MessageAs4AddPath | Bgp4mpSubType::MessageAs4LocalAddPath => {
+                (true, true)
+            }
+        };
+        let (buf, peer) = parse_into_located_one_input(buf, asn4)?;
+        if matches!(
+            subtype,
+            Bgp4mpSubType::StateChange | Bgp4mpSubType::StateChangeAs4
+        ) {
+            let (buf, old_state) = nom::combinator::map_res(be_u16, BgpState::try_from)(buf)?;
+            let (buf, new_state) = nom::combinator::map_res(be_u16, BgpState::try_from)(buf)?;
+            let state_change = Bgp4mpStateChange::new(peer, old_state, new_state);
+            let value = if subtype == Bgp4mpSubType::StateChange {
+                Bgp4mp::StateChange(state_change)
+            } else {
+                Bgp4mp::StateChangeAs4(state_change)
+            };
+            return Ok((buf, value));
+        }
+        let (buf, message) = with_bgp_ctx(ctx, asn4, add_path, |ctx| {
+            // Boxed as the BGP message parsing errors are large
+            BgpMessage::from_wire(buf, ctx).map_err(Box::new)
+        })
+        .map_err(|err| err.map(Into::into))?;
+        let message = Bgp4mpMessage::new(peer, message);
+        let value = match subtype {
+            Bgp4mpSubType::Message => Bgp4mp::Message(message),
+            Bgp4mpSubType::MessageAs4 => Bgp4mp::MessageAs4(message),
+            Bgp4mpSubType::MessageLocal => Bgp4mp::MessageLocal(message),
+            Bgp4mpSubType::MessageAs4Local => Bgp4mp::MessageAs4Local(message),
+            Bgp4mpSubType::MessageAddPath => Bgp4mp::MessageAddPath(message),
+            Bgp4mpSubType::MessageAs4AddPath => Bgp4mp::MessageAs4AddPath(message),
+            Bgp4mpSubType::MessageLocalAddPath => Bgp4mp::MessageLocalAddPath(message),
+            Bgp4mpSubType::MessageAs4LocalAddPath => Bgp4mp::MessageAs4LocalAddPath(message),
+            Bgp4mpSubType::StateChange | Bgp4mpSubType::StateChangeAs4 => unreachable!(),
+        };
+        Ok((buf, value))
+    }
+}
+
+#[derive(LocatedError, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum Bgp4mpPeerParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UndefinedAddressFamily(#[from_external] UndefinedAddressFamily),
+    UnsupportedAddressFamily(AddressFamily),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, bool, LocatedBgp4mpPeerParsingError<'a>> for Bgp4mpPeer {
+    fn from_wire(
+        buf: Span<'a>,
+        asn4: bool,
+    ) -> IResult<Span<'a>, Self, LocatedBgp4mpPeerParsingError<'a>> {
+        let (buf, peer_as, local_as) = if asn4 {
+            let (buf, peer_as) = be_u32(buf)?;
+            let (buf, local_as) = be_u32(buf)?;
+            (buf, peer_as, local_as)
+        } else {
+            let (buf, peer_as) = be_u16(buf)?;
+            let (buf, local_as) = be_u16(buf)?;
+            (buf, peer_as as u32, local_as as u32)
+        };
+        let (buf, interface_index) = be_u16(buf)?;
+        let afi_input = buf;
+        let (buf, afi) = nom::combinator::map_res(be_u16, AddressFamily::try_from)(buf)?;
+        let (buf, peer_address, local_address) = match afi {
+            AddressFamily::IPv4 => {
+                let (buf, peer_address) = be_u32(buf)?;
+                let (buf, local_address) = be_u32(buf)?;
+                (
+                    buf,
+                    IpAddr::V4(Ipv4Addr::from(peer_address)),
+                    IpAddr::V4(Ipv4Addr::from(local_address)),
+                )
+            }
+            AddressFamily::IPv6 => {
+                let (buf, peer_address) = be_u128(buf)?;
+                let (buf, local_address) = be_u128(buf)?;
+                (
+                    buf,
+                    IpAddr::V6(Ipv6Addr::from(peer_address)),
+                    IpAddr::V6(Ipv6Addr::from(local_address)),
+                )
+            }
+            _ => {
+                return Err(nom::Err::Error(LocatedBgp4mpPeerParsingError::new(
+                    afi_input,
+                    Bgp4mpPeerParsingError::UnsupportedAddressFamily(afi),
+                )))
+            }
+        };
+        Ok((
+            buf,
+            Bgp4mpPeer::new(
+                peer_as,
+                local_as,
+                interface_index,
+                peer_address,
+                local_address,
+            ),
+        ))
+    }
+}
@@ -0,0 +1,484 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializer library for MRT's wire protocol
+
+use std::{
+    io::Write,
+    net::{IpAddr, Ipv6Addr},
+};
+
+use byteorder::{NetworkEndian, WriteBytesExt};
+use netgauze_bgp_pkt::{
+    iana::PathAttributeType,
+    path_attribute::{MpReach, PathAttribute, PathAttributeValue},
+    wire::serializer::{path_attribute::PathAttributeWritingError, BgpMessageWritingError},
+};
+use netgauze_iana::address_family::AddressFamily;
+use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
+use netgauze_serde_macros::WritingError;
+
+use crate::{iana::*, *};
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum MrtMessageWritingError {
+    StdIOError(#[from_std_io_error] String),
+    TableDumpV2Error(#[from] TableDumpV2WritingError),
+    Bgp4mpError(#[from] Bgp4mpWritingError),
+}
+
+impl WritablePdu<MrtMessageWritingError> for MrtMessage {
+    /// 4-octets timestamp, 2-octets type, 2-octets subtype, and 4-octets length
+    const BASE_LENGTH: usize = 12;
+
+    fn len(&self) -> usize {
+        let value_len = match self.value() {
+            MrtValue::TableDumpV2(value) => value.len(),
+            MrtValue::Bgp4mp(value) => value.len(),
+            // 4-octets microseconds timestamp
+            MrtValue::Bgp4mpEt(value) => 4 + value.len(),
+        };
+        Self::BASE_LENGTH + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), MrtMessageWritingError> {
+        writer.write_u32::<NetworkEndian>(self.timestamp().timestamp() as u32)?;
+        writer.write_u16::<NetworkEndian>(self.get_type().into())?;
+        writer.write_u16::<NetworkEndian>(self.get_subtype())?;
+        writer.write_u32::<NetworkEndian>((self.len() - Self::BASE_LENGTH) as u32)?;
+        match self.value() {
+            MrtValue::TableDumpV2(value) => value.write(writer)?,
+            MrtValue::Bgp4mp(value) => value.write(writer)?,
+            MrtValue::Bgp4mpEt(value) => {
+                writer.write_u32::<NetworkEndian>(self.timestamp().timestamp_subsec_micros())?;
+                value.write(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum TableDumpV2WritingError {
+    StdIOError(#[from_std_io_error] String),
+    PeerIndexTableError(#[from] PeerIndexTableWritingError),
+    RibError(#[from] RibWritingError),
+}
+
+impl WritablePdu<TableDumpV2WritingError> for TableDumpV2 {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self) -> usize {
+        let len = match self {
+            Self::PeerIndexTable(value) => value.len(),
+            Self::RibIpv4Unicast(value)
+            | Self::RibIpv4Multicast(value)
+            | Self::RibIpv4UnicastAddPath(value)
+            | Self::RibIpv4MulticastAddPath(value) => value.len(),
+            Self::RibIpv6Unicast(value)
+            | Self::RibIpv6Multicast(value)
+            | Self::RibIpv6UnicastAddPath(value)
+            | Self::RibIpv6MulticastAddPath(value) => value.len(),
+        };
+        Self::BASE_LENGTH + len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), TableDumpV2WritingError> {
+        match self {
+            Self::PeerIndexTable(value) => value.write(writer)?,
+            Self::RibIpv4Unicast(value)
+            | Self::RibIpv4Multicast(value)
+            | Self::RibIpv4UnicastAddPath(value)
+            | Self::RibIpv4MulticastAddPath(value) => value.write(writer)?,
+            Self::RibIpv6Unicast(value)
+            | Self::RibIpv6Multicast(value)
+            | Self::RibIpv6UnicastAddPath(value)
+            | Self::RibIpv6MulticastAddPath(value) => value.write(writer)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum PeerIndexTableWritingError {
+    StdIOError(#[from_std_io_error] String),
+    PeerEntryError(#[from] PeerEntryWritingError),
+}
+
+impl WritablePdu<PeerIndexTableWritingError> for PeerIndexTable {
+    /// 4-octets collector BGP ID, 2-octets view name length, and 2-octets peer
+    /// count
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + self.view_name().len()
+            + self.peers().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), PeerIndexTableWritingError> {
+        writer.write_all(&self.collector_bgp_id().octets())?;
+        writer.write_u16::<NetworkEndian>(self.view_name().len() as u16)?;
+        writer.write_all(self.view_name().as_bytes())?;
+        writer.write_u16::<NetworkEndian>(self.peers().len() as u16)?;
+        for peer in self.peers() {
+            peer.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum PeerEntryWritingError {
+    StdIOError(#[from_std_io_error] String),
+    /// The peer AS can't be encoded in 2-octets
+    InvalidAsn2(u32),
+}
+
+impl WritablePdu<PeerEntryWritingError> for PeerEntry {
+    /// 1-octet peer type and 4-octets peer BGP ID
+    const BASE_LENGTH: usize = 5;
+
+    fn len(&self) -> usize {
+        let address_len = match self.address() {
+            IpAddr::V4(_) => 4,
+            IpAddr::V6(_) => 16,
+        };
+        let as_len = if self.asn4() { 4 } else { 2 };
+        Self::BASE_LENGTH + address_len + as_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), PeerEntryWritingError> {
+        let mut peer_type = 0;
+        if self.address().is_ipv6() {
+            peer_type |= PEER_TYPE_IS_IPV6;
+        }
+        if self.asn4() {
+            peer_type |= PEER_TYPE_IS_ASN4;
+        }
+        writer.write_u8(peer_type)?;
+        writer.write_all(&self.bgp_id().octets())?;
+        match self.address() {
+            IpAddr::V4(address) => writer.write_all(&address.octets())?,
+            IpAddr::V6(address) => writer.write_all(&address.octets())?,
+        }
+        if self.asn4() {
+            writer.write_u32::<NetworkEndian>(self.peer_as())?;
+        } else {
+            let peer_as = u16::try_from(self.peer_as())
+                .map_err(|_| PeerEntryWritingError::InvalidAsn2(self.peer_as()))?;
+            writer.write_u16::<NetworkEndian>(peer_as)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum RibWritingError {
+    StdIOError(#[from_std_io_error] String),
+    RibEntryError(#[from] RibEntryWritingError),
+}
+
+#[inline]
+fn prefix_len(prefix_len: u8) -> usize {
+    // 1-octet prefix length + the minimal number of octets to hold the prefix
+    1 + (prefix_len as usize).div_ceil(8)
+}
+
+impl WritablePdu<RibWritingError> for RibIpv4 {
+    /// 4-octets sequence number and 2-octets entry count
+    const BASE_LENGTH: usize = 6;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + prefix_len(self.prefix().prefix_len())
+            + self.entries().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), RibWritingError> {
+        writer.write_u32::<NetworkEndian>(self.sequence_number())?;
+        writer.write_u8(self.prefix().prefix_len())?;
+        let octets = self.prefix().network().octets();
+        writer.write_all(&octets[..prefix_len(self.prefix().prefix_len()) - 1])?;
+        writer.write_u16::<NetworkEndian>(self.entries().len() as u16)?;
+        for entry in self.entries() {
+            entry.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl WritablePdu<RibWritingError> for RibIpv6 {
+    /// 4-octets sequence number and 2-octets entry count
+    const BASE_LENGTH: usize = 6;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + prefix_len(self.prefix().prefix_len())
+            + self.entries().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), RibWritingError> {
+        writer.write_u32::<NetworkEndian>(self.sequence_number())?;
+        writer.write_u8(self.prefix().prefix_len())?;
+        let octets = self.prefix().network().octets();
+        writer.write_all(&octets[..prefix_len(self.prefix().prefix_len()) - 1])?;
+        writer.write_u16::<NetworkEndian>(self.entries().len() as u16)?;
+        for entry in self.entries() {
+            entry.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum RibEntryWritingError {
+    StdIOError(#[from_std_io_error] String),
+    PathAttributeError(#[from] PathAttributeWritingError),
+}
+
+/// If the attribute can be written in the abbreviated `MP_REACH_NLRI` form,
+/// return the next hop and the optional link local next hop
+#[inline]
+fn abbreviated_mp_reach_next_hop(attr: &PathAttribute) -> Option<(IpAddr, Option<Ipv6Addr>)> {
+    let mp_reach = match attr.value() {
+        PathAttributeValue::MpReach(mp_reach) => mp_reach,
+        _ => return None,
+    };
+    match mp_reach {
+        MpReach::Ipv4Unicast {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } if nlri.is_empty() => Some((*next_hop, *next_hop_local)),
+        MpReach::Ipv4Multicast {
+            next_hop,
+            next_hop_local,
+            nlri,
+        } if nlri.is_empty() => Some((*next_hop, *next_hop_local)),
+        MpReach::Ipv6Unicast {
+            next_hop_global,
+            next_hop_local,
+            nlri,
+        } if nlri.is_empty() => Some((IpAddr::V6(*next_hop_global), *next_hop_local)),
+        MpReach::Ipv6Multicast {
+            next_hop_global,
+            next_hop_local,
+            nlri,
+        } if nlri.is_empty() => Some((IpAddr::V6(*next_hop_global), *next_hop_local)),
+        _ => None,
+    }
+}
+
+#[inline]
+fn rib_path_attribute_len(attr: &PathAttribute) -> usize {
+    match abbreviated_mp_reach_next_hop(attr) {
+        Some((next_hop, next_hop_local)) => {
+            // 1-octet flags, 1-octet type, 1 or 2 octets length, 1-octet next hop length
+            let header_len = if attr.extended_length() { 5 } else { 4 };
+            header_len + next_hop_len(next_hop, next_hop_local)
+        }
+        None => attr.len(),
+    }
+}
+
+#[inline]
+const fn next_hop_len(next_hop: IpAddr, next_hop_local: Option<Ipv6Addr>) -> usize {
+    let len = match next_hop {
+        IpAddr::V4(_) => 4,
+        IpAddr::V6(_) => 16,
+    };
+    if next_hop_local.is_some() {
+        len + 16
+    } else {
+        len
+    }
+}
+
+impl WritablePdu<RibEntryWritingError> for RibEntry {
+    /// 2-octets peer index, 4-octets originated time, and 2-octets attribute
+    /// length
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        let path_id_len = if self.path_id().is_some() { 4 } else { 0 };
+        Self::BASE_LENGTH
+            + path_id_len
+            + self
+                .path_attributes()
+                .iter()
+                .map(rib_path_attribute_len)
+                .sum::<usize>()
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), RibEntryWritingError> {
+        writer.write_u16::<NetworkEndian>(self.peer_index())?;
+        writer.write_u32::<NetworkEndian>(self.originated_time().timestamp() as u32)?;
+        if let Some(path_id) = self.path_id() {
+            writer.write_u32::<NetworkEndian>(path_id)?;
+        }
+        let attributes_len = self
+            .path_attributes()
+            .iter()
+            .map(rib_path_attribute_len)
+            .sum::<usize>();
+        writer.write_u16::<NetworkEndian>(attributes_len as u16)?;
+        for attr in self.path_attributes() {
+            let (next_hop, next_hop_local) = match abbreviated_mp_reach_next_hop(attr) {
+                Some(value) => value,
+                None => {
+                    attr.write(writer)?;
+                    continue;
+                }
+            };
+            let mut flags = 0x00u8;
+            if attr.optional() {
+                flags |= 0b10000000;
+            }
+            if attr.transitive() {
+                flags |= 0b01000000;
+            }
+            if attr.partial() {
+                flags |= 0b00100000;
+            }
+            if attr.extended_length() {
+                flags |= 0b00010000;
+            }
+            writer.write_u8(flags)?;
+            writer.write_u8(PathAttributeType::MpReachNlri.into())?;
+            let next_hop_len = next_hop_len(next_hop, next_hop_local);
+            if attr.extended_length() {
+                writer.write_u16::<NetworkEndian>(next_hop_len as u16 + 1)?;
+            } else {
+                writer.write_u8(next_hop_len as u8 + 1)?;
+            }
+            writer.write_u8(next_hop_len as u8)?;
+            match next_hop {
+                IpAddr::V4(next_hop) => writer.write_all(&next_hop.octets())?,
+                IpAddr::V6(next_hop) => writer.write_all(&next_hop.octets())?,
+            }
+            if let Some(next_hop_local) = next_hop_local {
+                writer.write_all(&next_hop_local.octets())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum Bgp4mpWritingError {
+    StdIOError(#[from_std_io_error] String),
+    PeerError(#[from] Bgp4mpPeerWritingError),
+    BgpMessageError(#[from] BgpMessageWritingError),
+}
+
+impl WritablePdu<Bgp4mpWritingError> for Bgp4mp {
+    const BASE_LENGTH: usize = 0;
+
+    fn len(&self) -> usize {
+        let len = match self {
+            // 2-octets old state and 2-octets new state
+            Self::StateChange(value) | Self::StateChangeAs4(value) => {
+                value.peer().len(self.is_asn4()) + 4
+            }
+            Self::Message(value)
+            | Self::MessageAs4(value)
+            | Self::MessageLocal(value)
+            | Self::MessageAs4Local(value)
+            | Self::MessageAddPath(value)
+            | Self::MessageAs4AddPath(value)
+            | Self::MessageLocalAddPath(value)
+            | Self::MessageAs4LocalAddPath(value) => {
+                value.peer().len(self.is_asn4()) + value.message().len()
+            }
+        };
+        Self::BASE_LENGTH + len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), Bgp4mpWritingError> {
+        self.peer().write(writer, self.is_asn4())?;
+        match self {
+            Self::StateChange(value) | Self::StateChangeAs4(value) => {
+                writer.write_u16::<NetworkEndian>(value.old_state().into())?;
+                writer.write_u16::<NetworkEndian>(value.new_state().into())?;
+            }
+            Self::Message(value)
+            | Self::MessageAs4(value)
+            | Self::MessageLocal(value)
+            | Self::MessageAs4Local(value)
+            | Self::MessageAddPath(value)
+            | Self::MessageAs4AddPath(value)
+            | Self::MessageLocalAddPath(value)
+            | Self::MessageAs4LocalAddPath(value) => value.message().write(writer)?,
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum Bgp4mpPeerWritingError {
+    StdIOError(#[from_std_io_error] String),
+    /// The peer or local AS can't be encoded in 2-octets
+    InvalidAsn2(u32),
+    /// The peer and local addresses are not of the same address family
+    AddressFamilyMismatch(IpAddr, IpAddr),
+}
+
+impl WritablePduWithOneInput<bool, Bgp4mpPeerWritingError> for Bgp4mpPeer {
+    /// 2-octets interface index and 2-octets address family
+    const BASE_LENGTH: usize = 4;
+
+    fn len(&self, asn4: bool) -> usize {
+        let as_len = if asn4 { 8 } else { 4 };
+        let address_len = match self.peer_address() {
+            IpAddr::V4(_) => 8,
+            IpAddr::V6(_) => 32,
+        };
+        Self::BASE_LENGTH + as_len + address_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T, asn4: bool) -> Result<(), Bgp4mpPeerWritingError> {
+        if asn4 {
+            writer.write_u32::<NetworkEndian>(self.peer_as())?;
+            writer.write_u32::<NetworkEndian>(self.local_as())?;
+        } else {
+            for asn in [self.peer_as(), self.local_as()] {
+                let asn =
+                    u16::try_from(asn).map_err(|_| Bgp4mpPeerWritingError::InvalidAsn2(asn))?;
+                writer.write_u16::<NetworkEndian>(asn)?;
+            }
+        }
+        writer.write_u16::<NetworkEndian>(self.interface_index())?;
+        match (self.peer_address(), self.local_address()) {
+            (IpAddr::V4(peer_address), IpAddr::V4(local_address)) => {
+                writer.write_u16::<NetworkEndian>(AddressFamily::IPv4.into())?;
+                writer.write_all(&peer_address.octets())?;
+                writer.write_all(&local_address.octets())?;
+            }
+            (IpAddr::V6(peer_address), IpAddr::V6(local_address)) => {
+                writer.write_u16::<NetworkEndian>(AddressFamily::IPv6.into())?;
+                writer.write_all(&peer_address.octets())?;
+                writer.write_all(&local_address.octets())?;
+            }
+            (peer_address, local_address) => {
+                return Err(Bgp4mpPeerWritingError::AddressFamilyMismatch(
+                    peer_address,
+                    local_address,
+                ))
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,389 @@
+// Copyright (C) 2023-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::TimeZone;
+use netgauze_bgp_pkt::{
+    path_attribute::{
+        As4PathSegment, AsPath, AsPathSegmentType, MpReach, NextHop, Origin, PathAttribute,
+        PathAttributeValue,
+    },
+    wire::deserializer::BgpParsingContext,
+    BgpMessage,
+};
+use netgauze_parse_utils::{
+    test_helpers::{
+        test_parse_error_with_one_input, test_parsed_completely_with_one_input, test_write,
+    },
+    Span,
+};
+use std::{net::Ipv6Addr, str::FromStr};
+
+use crate::{
+    iana::*,
+    wire::{deserializer::*, serializer::*},
+    *,
+};
+
+#[test]
+fn test_peer_index_table() -> Result<(), MrtMessageWritingError> {
+    let good_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x0d, // TABLE_DUMP_V2
+        0x00, 0x01, // PEER_INDEX_TABLE
+        0x00, 0x00, 0x00, 0x2c, // length
+        0x0a, 0x00, 0x00, 0x01, // collector BGP ID
+        0x00, 0x00, // view name length
+        0x00, 0x02, // peer count
+        0x00, // peer type: IPv4, 2-octets AS
+        0xc0, 0x00, 0x02, 0x01, // peer BGP ID
+        0xc0, 0x00, 0x02, 0x01, // peer address
+        0xfd, 0xe9, // peer AS
+        0x03, // peer type: IPv6, 4-octets AS
+        0xc0, 0x00, 0x02, 0x02, // peer BGP ID
+        0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x02, // peer address
+        0x00, 0x01, 0x00, 0x00, // peer AS
+    ];
+
+    let good = MrtMessage::new(
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+        MrtValue::TableDumpV2(TableDumpV2::PeerIndexTable(PeerIndexTable::new(
+            Ipv4Addr::new(10, 0, 0, 1),
+            "".to_string(),
+            vec![
+                PeerEntry::new(
+                    Ipv4Addr::new(192, 0, 2, 1),
+                    IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    65001,
+                    false,
+                ),
+                PeerEntry::new(
+                    Ipv4Addr::new(192, 0, 2, 2),
+                    IpAddr::V6(Ipv6Addr::from_str("2001:db8::2").unwrap()),
+                    65536,
+                    true,
+                ),
+            ],
+        ))),
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_rib_ipv4_unicast() -> Result<(), MrtMessageWritingError> {
+    let good_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x0d, // TABLE_DUMP_V2
+        0x00, 0x02, // RIB_IPV4_UNICAST
+        0x00, 0x00, 0x00, 0x2a, // length
+        0x00, 0x00, 0x00, 0x01, // sequence number
+        0x18, 0xc0, 0x00, 0x02, // prefix
+        0x00, 0x01, // entry count
+        0x00, 0x00, // peer index
+        0x65, 0x53, 0xf1, 0x00, // originated time
+        0x00, 0x18, // attributes length
+        0x40, 0x01, 0x01, 0x00, // ORIGIN
+        0x40, 0x02, 0x0a, 0x02, 0x02, 0x00, 0x00, 0xfd, 0xe9, 0x00, 0x01, 0x00,
+        0x00, // AS_PATH
+        0x40, 0x03, 0x04, 0xc0, 0x00, 0x02, 0x01, // NEXT_HOP
+    ];
+
+    let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    let good = MrtMessage::new(
+        time,
+        MrtValue::TableDumpV2(TableDumpV2::RibIpv4Unicast(RibIpv4::new(
+            1,
+            "192.0.2.0/24".parse().unwrap(),
+            vec![RibEntry::new(
+                0,
+                time,
+                None,
+                vec![
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        false,
+                        PathAttributeValue::Origin(Origin::IGP),
+                    )
+                    .unwrap(),
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        false,
+                        PathAttributeValue::AsPath(AsPath::As4PathSegments(vec![
+                            As4PathSegment::new(AsPathSegmentType::AsSequence, vec![65001, 65536]),
+                        ])),
+                    )
+                    .unwrap(),
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        false,
+                        PathAttributeValue::NextHop(NextHop::new(Ipv4Addr::new(192, 0, 2, 1))),
+                    )
+                    .unwrap(),
+                ],
+            )],
+        ))),
+    );
+
+    // AS_PATH in RIB entries is always 4-octets regardless of the context
+    let mut ctx = BgpParsingContext::asn2_default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    assert!(!ctx.asn4());
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_rib_ipv6_unicast_add_path() -> Result<(), MrtMessageWritingError> {
+    let good_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x0d, // TABLE_DUMP_V2
+        0x00, 0x0a, // RIB_IPV6_UNICAST_ADDPATH
+        0x00, 0x00, 0x00, 0x2f, // length
+        0x00, 0x00, 0x00, 0x02, // sequence number
+        0x20, 0x20, 0x01, 0x0d, 0xb8, // prefix
+        0x00, 0x01, // entry count
+        0x00, 0x01, // peer index
+        0x65, 0x53, 0xf1, 0x00, // originated time
+        0x00, 0x00, 0x00, 0x07, // path identifier
+        0x00, 0x18, // attributes length
+        0x40, 0x01, 0x01, 0x00, // ORIGIN
+        0x80, 0x0e, 0x11, 0x10, 0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x01, // abbreviated MP_REACH_NLRI
+    ];
+
+    let time = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    let good = MrtMessage::new(
+        time,
+        MrtValue::TableDumpV2(TableDumpV2::RibIpv6UnicastAddPath(RibIpv6::new(
+            2,
+            "2001:db8::/32".parse().unwrap(),
+            vec![RibEntry::new(
+                1,
+                time,
+                Some(7),
+                vec![
+                    PathAttribute::from(
+                        false,
+                        true,
+                        false,
+                        false,
+                        PathAttributeValue::Origin(Origin::IGP),
+                    )
+                    .unwrap(),
+                    PathAttribute::from(
+                        true,
+                        false,
+                        false,
+                        false,
+                        PathAttributeValue::MpReach(MpReach::Ipv6Unicast {
+                            next_hop_global: Ipv6Addr::from_str("2001:db8::1").unwrap(),
+                            next_hop_local: None,
+                            nlri: vec![],
+                        }),
+                    )
+                    .unwrap(),
+                ],
+            )],
+        ))),
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_bgp4mp_et_message_as4() -> Result<(), MrtMessageWritingError> {
+    let good_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x11, // BGP4MP_ET
+        0x00, 0x04, // BGP4MP_MESSAGE_AS4
+        0x00, 0x00, 0x00, 0x2b, // length
+        0x00, 0x01, 0xe2, 0x40, // microseconds timestamp
+        0x00, 0x00, 0xfd, 0xe9, // peer AS
+        0x00, 0x00, 0xfd, 0xe8, // local AS
+        0x00, 0x00, // interface index
+        0x00, 0x01, // AFI
+        0xc0, 0x00, 0x02, 0x01, // peer address
+        0xc0, 0x00, 0x02, 0x02, // local address
+        0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0x00, 0x13, 0x04, // BGP KEEPALIVE
+    ];
+
+    let good = MrtMessage::new(
+        Utc.timestamp_opt(1_700_000_000, 123_456_000).unwrap(),
+        MrtValue::Bgp4mpEt(Bgp4mp::MessageAs4(Bgp4mpMessage::new(
+            Bgp4mpPeer::new(
+                65001,
+                65000,
+                0,
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            ),
+            BgpMessage::KeepAlive,
+        ))),
+    );
+
+    let mut ctx = BgpParsingContext::asn2_default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    // Make sure the record specific settings didn't leak into the context
+    assert!(!ctx.asn4());
+    assert!(ctx.add_path().is_empty());
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_bgp4mp_state_change() -> Result<(), MrtMessageWritingError> {
+    let good_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x10, // BGP4MP
+        0x00, 0x00, // BGP4MP_STATE_CHANGE
+        0x00, 0x00, 0x00, 0x2c, // length
+        0xfd, 0xe9, // peer AS
+        0xfd, 0xe8, // local AS
+        0x00, 0x03, // interface index
+        0x00, 0x02, // AFI
+        0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, // peer address
+        0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x02, // local address
+        0x00, 0x05, // old state
+        0x00, 0x06, // new state
+    ];
+    let bad_invalid_asn2 = MrtMessage::new(
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+        MrtValue::Bgp4mp(Bgp4mp::StateChange(Bgp4mpStateChange::new(
+            Bgp4mpPeer::new(
+                65536,
+                65000,
+                3,
+                IpAddr::V6(Ipv6Addr::from_str("2001:db8::1").unwrap()),
+                IpAddr::V6(Ipv6Addr::from_str("2001:db8::2").unwrap()),
+            ),
+            BgpState::OpenConfirm,
+            BgpState::Established,
+        ))),
+    );
+
+    let good = MrtMessage::new(
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+        MrtValue::Bgp4mp(Bgp4mp::StateChange(Bgp4mpStateChange::new(
+            Bgp4mpPeer::new(
+                65001,
+                65000,
+                3,
+                IpAddr::V6(Ipv6Addr::from_str("2001:db8::1").unwrap()),
+                IpAddr::V6(Ipv6Addr::from_str("2001:db8::2").unwrap()),
+            ),
+            BgpState::OpenConfirm,
+            BgpState::Established,
+        ))),
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    test_parsed_completely_with_one_input(&good_wire, &mut ctx, &good);
+    test_write(&good, &good_wire)?;
+    assert_eq!(
+        test_write(&bad_invalid_asn2, &good_wire),
+        Err(MrtMessageWritingError::Bgp4mpError(
+            Bgp4mpWritingError::PeerError(Bgp4mpPeerWritingError::InvalidAsn2(65536))
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_mrt_message_errors() {
+    let bad_undefined_type_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x63, // undefined type
+        0x00, 0x00, // subtype
+        0x00, 0x00, 0x00, 0x00, // length
+    ];
+    let bad_unsupported_subtype_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x0d, // TABLE_DUMP_V2
+        0x00, 0x06, // RIB_GENERIC
+        0x00, 0x00, 0x00, 0x00, // length
+    ];
+    let bad_undefined_bgp_state_wire = [
+        0x65, 0x53, 0xf1, 0x00, // timestamp
+        0x00, 0x10, // BGP4MP
+        0x00, 0x00, // BGP4MP_STATE_CHANGE
+        0x00, 0x00, 0x00, 0x14, // length
+        0xfd, 0xe9, // peer AS
+        0xfd, 0xe8, // local AS
+        0x00, 0x00, // interface index
+        0x00, 0x01, // AFI
+        0xc0, 0x00, 0x02, 0x01, // peer address
+        0xc0, 0x00, 0x02, 0x02, // local address
+        0x00, 0x07, // old state
+        0x00, 0x06, // new state
+    ];
+
+    let bad_undefined_type = LocatedMrtMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(4, &bad_undefined_type_wire[4..]) },
+        MrtMessageParsingError::UndefinedMrtType(UndefinedMrtType(0x63)),
+    );
+    let bad_unsupported_subtype = LocatedMrtMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(12, &bad_unsupported_subtype_wire[12..]) },
+        MrtMessageParsingError::TableDumpV2Error(TableDumpV2ParsingError::UnsupportedSubType(
+            TableDumpV2SubType::RibGeneric,
+        )),
+    );
+    let bad_undefined_bgp_state = LocatedMrtMessageParsingError::new(
+        unsafe { Span::new_from_raw_offset(28, &bad_undefined_bgp_state_wire[28..]) },
+        MrtMessageParsingError::Bgp4mpError(Bgp4mpParsingError::UndefinedBgpState(
+            UndefinedBgpState(7),
+        )),
+    );
+
+    let mut ctx = BgpParsingContext::default();
+    test_parse_error_with_one_input::<
+        MrtMessage,
+        &mut BgpParsingContext,
+        LocatedMrtMessageParsingError<'_>,
+    >(&bad_undefined_type_wire, &mut ctx, &bad_undefined_type);
+    test_parse_error_with_one_input::<
+        MrtMessage,
+        &mut BgpParsingContext,
+        LocatedMrtMessageParsingError<'_>,
+    >(
+        &bad_unsupported_subtype_wire,
+        &mut ctx,
+        &bad_unsupported_subtype,
+    );
+    test_parse_error_with_one_input::<
+        MrtMessage,
+        &mut BgpParsingContext,
+        LocatedMrtMessageParsingError<'_>,
+    >(
+        &bad_undefined_bgp_state_wire,
+        &mut ctx,
+        &bad_undefined_bgp_state,
+    );
+}
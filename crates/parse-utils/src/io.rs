@@ -0,0 +1,218 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! I/O primitives used by the [`crate::WritablePdu`] family of traits.
+//!
+//! With the `std` feature (enabled by default) these are re-exports of
+//! [`std::io`] and [`byteorder::WriteBytesExt`]. Without it, a minimal
+//! `no_std` replacement is provided that covers what the serializers need:
+//! writing bytes into a `Vec<u8>` or a `&mut [u8]`.
+
+#[cfg(feature = "std")]
+pub use byteorder::WriteBytesExt;
+#[cfg(feature = "std")]
+pub use std::io::{Error, ErrorKind, Result, Write};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std_io::*;
+
+#[cfg(not(feature = "std"))]
+mod no_std_io {
+    use alloc::vec::Vec;
+    use byteorder::ByteOrder;
+    use core::fmt;
+
+    /// Subset of [`std::io::ErrorKind`] that can be raised while writing
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub enum ErrorKind {
+        /// The writer has no more room for the bytes to be written
+        WriteZero,
+        Other,
+    }
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    pub struct Error {
+        kind: ErrorKind,
+        message: &'static str,
+    }
+
+    impl Error {
+        pub const fn new(kind: ErrorKind, message: &'static str) -> Self {
+            Self { kind, message }
+        }
+
+        pub const fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl fmt::Display for Error {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str(self.message)
+        }
+    }
+
+    impl core::error::Error for Error {}
+
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// `no_std` counterpart of [`std::io::Write`]
+    pub trait Write {
+        fn write(&mut self, buf: &[u8]) -> Result<usize>;
+
+        fn flush(&mut self) -> Result<()>;
+
+        fn write_all(&mut self, mut buf: &[u8]) -> Result<()> {
+            while !buf.is_empty() {
+                match self.write(buf)? {
+                    0 => {
+                        return Err(Error::new(
+                            ErrorKind::WriteZero,
+                            "failed to write whole buffer",
+                        ))
+                    }
+                    n => buf = &buf[n..],
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl<W: Write + ?Sized> Write for &mut W {
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            (**self).write(buf)
+        }
+
+        #[inline]
+        fn flush(&mut self) -> Result<()> {
+            (**self).flush()
+        }
+
+        #[inline]
+        fn write_all(&mut self, buf: &[u8]) -> Result<()> {
+            (**self).write_all(buf)
+        }
+    }
+
+    impl Write for Vec<u8> {
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        #[inline]
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Same semantics as [`std::io::Write`] for `&mut [u8]`: the slice is
+    /// advanced past the written bytes.
+    impl Write for &mut [u8] {
+        #[inline]
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            let amt = core::cmp::min(buf.len(), self.len());
+            let (a, b) = core::mem::take(self).split_at_mut(amt);
+            a.copy_from_slice(&buf[..amt]);
+            *self = b;
+            Ok(amt)
+        }
+
+        #[inline]
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+    }
+
+    /// `no_std` counterpart of [`byteorder::WriteBytesExt`]
+    pub trait WriteBytesExt: Write {
+        #[inline]
+        fn write_u8(&mut self, n: u8) -> Result<()> {
+            self.write_all(&[n])
+        }
+
+        #[inline]
+        fn write_i8(&mut self, n: i8) -> Result<()> {
+            self.write_all(&[n as u8])
+        }
+
+        #[inline]
+        fn write_u16<T: ByteOrder>(&mut self, n: u16) -> Result<()> {
+            let mut buf = [0; 2];
+            T::write_u16(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_i16<T: ByteOrder>(&mut self, n: i16) -> Result<()> {
+            let mut buf = [0; 2];
+            T::write_i16(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_u32<T: ByteOrder>(&mut self, n: u32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_u32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_i32<T: ByteOrder>(&mut self, n: i32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_i32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_u64<T: ByteOrder>(&mut self, n: u64) -> Result<()> {
+            let mut buf = [0; 8];
+            T::write_u64(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_i64<T: ByteOrder>(&mut self, n: i64) -> Result<()> {
+            let mut buf = [0; 8];
+            T::write_i64(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_u128<T: ByteOrder>(&mut self, n: u128) -> Result<()> {
+            let mut buf = [0; 16];
+            T::write_u128(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_f32<T: ByteOrder>(&mut self, n: f32) -> Result<()> {
+            let mut buf = [0; 4];
+            T::write_f32(&mut buf, n);
+            self.write_all(&buf)
+        }
+
+        #[inline]
+        fn write_f64<T: ByteOrder>(&mut self, n: f64) -> Result<()> {
+            let mut buf = [0; 8];
+            T::write_f64(&mut buf, n);
+            self.write_all(&buf)
+        }
+    }
+
+    impl<W: Write + ?Sized> WriteBytesExt for W {}
+}
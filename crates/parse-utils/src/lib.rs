@@ -14,13 +14,23 @@
 // limitations under the License.
 
 //! Traits for Ser/Deser wire protocols
+//!
+//! The crate is `no_std` compatible (requires `alloc`) when the default `std`
+//! feature is disabled, see [`io`] for the writing primitives used in that
+//! case.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod io;
 #[cfg(feature = "test-helpers")]
 pub mod test_helpers;
 
+use alloc::vec::Vec;
+use core::fmt::Debug;
 use netgauze_locate::BinarySpan;
 use nom::IResult;
-use std::fmt::Debug;
 
 pub type Span<'a> = BinarySpan<&'a [u8]>;
 
@@ -61,6 +71,32 @@ pub trait ReadablePduWithThreeInputs<'a, I1, I2, I3, ErrorType> {
         Self: Sized;
 }
 
+/// Thin [`io::Write`] adapter that appends directly to a
+/// [`bytes::BytesMut`]. Unlike [`bytes::buf::Writer`], it doesn't go through the
+/// generic [`bytes::BufMut::put`] path for every small write.
+#[cfg(feature = "bytes")]
+struct BytesMutWriter<'a>(&'a mut bytes::BytesMut);
+
+#[cfg(feature = "bytes")]
+impl io::Write for BytesMutWriter<'_> {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    #[inline]
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.0.extend_from_slice(buf);
+        Ok(())
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Generic trait for Writable Protocol Data Unit that doesn't need any external
 /// input while writing the packet.
 #[allow(clippy::len_without_is_empty)]
@@ -74,9 +110,21 @@ pub trait WritablePdu<ErrorType> {
     /// field in the calculation
     fn len(&self) -> usize;
 
-    fn write<T: std::io::Write>(&self, _writer: &mut T) -> Result<(), ErrorType>
+    fn write<T: io::Write>(&self, _writer: &mut T) -> Result<(), ErrorType>
     where
         Self: Sized;
+
+    /// Write the PDU directly into a [`bytes::BytesMut`] buffer. The needed
+    /// capacity is reserved upfront using [`Self::len`] to avoid repeated
+    /// reallocations while writing.
+    #[cfg(feature = "bytes")]
+    fn write_into(&self, buf: &mut bytes::BytesMut) -> Result<(), ErrorType>
+    where
+        Self: Sized,
+    {
+        buf.reserve(self.len());
+        self.write(&mut BytesMutWriter(buf))
+    }
 }
 
 /// Generic trait for Writable Protocol Data Unit that doesn't need any external
@@ -92,9 +140,22 @@ pub trait WritablePduWithOneInput<I, ErrorType> {
     /// field in the calculation
     fn len(&self, input: I) -> usize;
 
-    fn write<T: std::io::Write>(&self, _writer: &mut T, input: I) -> Result<(), ErrorType>
+    fn write<T: io::Write>(&self, _writer: &mut T, input: I) -> Result<(), ErrorType>
     where
         Self: Sized;
+
+    /// Write the PDU directly into a [`bytes::BytesMut`] buffer. The needed
+    /// capacity is reserved upfront using [`Self::len`] to avoid repeated
+    /// reallocations while writing.
+    #[cfg(feature = "bytes")]
+    fn write_into(&self, buf: &mut bytes::BytesMut, input: I) -> Result<(), ErrorType>
+    where
+        Self: Sized,
+        I: Clone,
+    {
+        buf.reserve(self.len(input.clone()));
+        self.write(&mut BytesMutWriter(buf), input)
+    }
 }
 
 /// Generic trait for Writable Protocol Data Unit that doesn't need any external
@@ -110,14 +171,23 @@ pub trait WritablePduWithTwoInputs<I1, I2, ErrorType> {
     /// field in the calculation
     fn len(&self, input1: I1, input2: I2) -> usize;
 
-    fn write<T: std::io::Write>(
-        &self,
-        _writer: &mut T,
-        input1: I1,
-        input2: I2,
-    ) -> Result<(), ErrorType>
+    fn write<T: io::Write>(&self, _writer: &mut T, input1: I1, input2: I2) -> Result<(), ErrorType>
     where
         Self: Sized;
+
+    /// Write the PDU directly into a [`bytes::BytesMut`] buffer. The needed
+    /// capacity is reserved upfront using [`Self::len`] to avoid repeated
+    /// reallocations while writing.
+    #[cfg(feature = "bytes")]
+    fn write_into(&self, buf: &mut bytes::BytesMut, input1: I1, input2: I2) -> Result<(), ErrorType>
+    where
+        Self: Sized,
+        I1: Clone,
+        I2: Clone,
+    {
+        buf.reserve(self.len(input1.clone(), input2.clone()));
+        self.write(&mut BytesMutWriter(buf), input1, input2)
+    }
 }
 
 /// Located Parsing error is the error raised by parsing a given buffer and a
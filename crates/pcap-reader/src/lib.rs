@@ -18,6 +18,9 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use pcap_parser::{data::PacketData, traits::PcapReaderIterator, *};
 use pdu::{Ethernet, Ipv4, Ipv4Pdu, Ipv6, Ipv6Pdu, Tcp, Udp};
 
+const IPV6_HEADER_LENGTH: usize = 40;
+const UDP_HEADER_LENGTH: usize = 8;
+
 /// Transport Protocol
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 pub enum TransportProtocol {
@@ -139,6 +142,11 @@ impl<'a> PcapIter<'a> {
     ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
         let src_ip = IpAddr::V4(Ipv4Addr::from(ipv4_pdu.source_address()));
         let dst_ip = IpAddr::V4(Ipv4Addr::from(ipv4_pdu.destination_address()));
+        // Total length is zero in packets captured with TCP segmentation offload
+        let ip_payload_len = match ipv4_pdu.total_length() {
+            0 => None,
+            total_length => Some((total_length as usize).saturating_sub(ipv4_pdu.computed_ihl())),
+        };
         match ipv4_pdu.inner() {
             Err(_) => None,
             Ok(ipv4) => match ipv4 {
@@ -154,7 +162,8 @@ impl<'a> PcapIter<'a> {
                             dst_ip,
                             dst_port,
                             TransportProtocol::TCP,
-                            payload.to_vec(),
+                            trim_padding(payload, ip_payload_len, tcp.computed_data_offset())
+                                .to_vec(),
                         )),
                     }
                 }
@@ -169,7 +178,7 @@ impl<'a> PcapIter<'a> {
                             dst_ip,
                             dst_port,
                             TransportProtocol::UDP,
-                            payload.to_vec(),
+                            trim_padding(payload, ip_payload_len, UDP_HEADER_LENGTH).to_vec(),
                         )),
                     }
                 }
@@ -184,6 +193,15 @@ impl<'a> PcapIter<'a> {
     ) -> Option<(IpAddr, u16, IpAddr, u16, TransportProtocol, Vec<u8>)> {
         let src_ip = IpAddr::V6(Ipv6Addr::from(ipv6_pdu.source_address()));
         let dst_ip = IpAddr::V6(Ipv6Addr::from(ipv6_pdu.destination_address()));
+        // Payload length is zero in packets captured with TCP segmentation offload,
+        // the computed header length includes the extension headers
+        let ip_payload_len = match ipv6_pdu.payload_length() {
+            0 => None,
+            payload_length => Some(
+                (IPV6_HEADER_LENGTH + payload_length as usize)
+                    .saturating_sub(ipv6_pdu.computed_ihl()),
+            ),
+        };
         match ipv6_pdu.inner() {
             Err(_) => None,
             Ok(ipv6) => match ipv6 {
@@ -199,7 +217,8 @@ impl<'a> PcapIter<'a> {
                             dst_ip,
                             dst_port,
                             TransportProtocol::TCP,
-                            payload.to_vec(),
+                            trim_padding(payload, ip_payload_len, tcp.computed_data_offset())
+                                .to_vec(),
                         )),
                     }
                 }
@@ -214,7 +233,7 @@ impl<'a> PcapIter<'a> {
                             dst_ip,
                             dst_port,
                             TransportProtocol::UDP,
-                            payload.to_vec(),
+                            trim_padding(payload, ip_payload_len, UDP_HEADER_LENGTH).to_vec(),
                         )),
                     }
                 }
@@ -225,6 +244,22 @@ impl<'a> PcapIter<'a> {
     }
 }
 
+/// Remove the link layer padding that follows the IP packet from the transport
+/// payload, e.g., Ethernet frames are padded to 60 octets, which adds trailing
+/// zeros to short TCP segments such as bare ACKs.
+///
+/// `ip_payload_len` is the length of the transport segment advertised in the IP
+/// header, the payload is kept as is when it's unknown.
+fn trim_padding(payload: &[u8], ip_payload_len: Option<usize>, header_len: usize) -> &[u8] {
+    match ip_payload_len {
+        Some(ip_payload_len) => {
+            let len = ip_payload_len.saturating_sub(header_len).min(payload.len());
+            &payload[..len]
+        }
+        None => payload,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::fs::File;
@@ -262,4 +297,14 @@ mod tests {
         }
         assert_eq!(results.len(), 9)
     }
+
+    #[test]
+    fn test_trim_padding() {
+        // A bare TCP ACK padded to the Ethernet min frame size
+        let payload = [0u8; 6];
+        assert_eq!(trim_padding(&payload, Some(20), 20), &[] as &[u8]);
+        assert_eq!(trim_padding(&payload, Some(23), 20), &payload[..3]);
+        assert_eq!(trim_padding(&payload, Some(40), 20), &payload);
+        assert_eq!(trim_padding(&payload, None, 20), &payload);
+    }
 }
@@ -368,9 +368,12 @@ impl WritingError {
         let mut output = quote! {
             #(
                 #[automatically_derived]
-                impl From<std::io::Error> for #ident {
-                    fn from(err: std::io::Error) -> Self {
-                        #ident::#from_std_io_error_variants(err.to_string())
+                impl From<netgauze_parse_utils::io::Error> for #ident {
+                    fn from(err: netgauze_parse_utils::io::Error) -> Self {
+                        // Formatting directly into `String` works with and without `std`
+                        let mut msg = String::new();
+                        let _ = ::core::fmt::Write::write_fmt(&mut msg, ::core::format_args!("{}", err));
+                        #ident::#from_std_io_error_variants(msg)
                     }
                 }
             )*
@@ -397,7 +400,8 @@ impl WritingError {
 /// provides the following decorations for any members of the enum.
 ///
 /// 1. `#[from_std_io_error]` automatically generate [`From`] implementation
-///    from [`std::io::Error`] to a [`String`].
+///    from `netgauze_parse_utils::io::Error` (i.e. [`std::io::Error`] when
+///    the `std` feature is enabled) to a [`String`].
 ///
 /// 2. `#[from]`, automatically generates a [`From`] implementation for a given
 ///    type.
@@ -0,0 +1,255 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Best effort decoding of the raw packet headers carried in
+//! [`crate::SampledHeader`].
+//!
+//! Agents sample only the first bytes of a packet, hence the headers are
+//! decoded as far as the sampled bytes allow and the layers that are
+//! truncated or not understood are left as [`None`].
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{HeaderProtocol, MacAddress};
+
+pub const ETHER_TYPE_IPV4: u16 = 0x0800;
+pub const ETHER_TYPE_IPV6: u16 = 0x86dd;
+pub const ETHER_TYPE_VLAN: u16 = 0x8100;
+pub const ETHER_TYPE_QINQ: u16 = 0x88a8;
+
+pub const IP_PROTOCOL_TCP: u8 = 6;
+pub const IP_PROTOCOL_UDP: u8 = 17;
+pub const IP_PROTOCOL_SCTP: u8 = 132;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthernetHeader {
+    pub dst_mac: MacAddress,
+    pub src_mac: MacAddress,
+    /// VLAN ID of the outer 802.1Q or 802.1ad tag, if any
+    pub vlan: Option<u16>,
+    /// The Ether type after skipping the VLAN tags
+    pub ether_type: u16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IpHeader {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    /// IPv4 protocol or IPv6 next header
+    pub protocol: u8,
+    /// IPv4 type of service or IPv6 traffic class
+    pub tos: u8,
+    /// IPv4 TTL or IPv6 hop limit
+    pub ttl: u8,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransportHeader {
+    pub src_port: u16,
+    pub dst_port: u16,
+    /// Only set for TCP
+    pub tcp_flags: Option<u8>,
+}
+
+/// Headers decoded from the sampled raw packet header
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct DecodedHeader {
+    pub ethernet: Option<EthernetHeader>,
+    pub ip: Option<IpHeader>,
+    pub transport: Option<TransportHeader>,
+}
+
+impl DecodedHeader {
+    /// Decode the sampled header, only Ethernet, IPv4, and IPv6 headers are
+    /// understood. IPv6 extension headers are not traversed.
+    pub fn decode(protocol: HeaderProtocol, buf: &[u8]) -> Self {
+        let mut decoded = Self::default();
+        let ip_buf = match protocol {
+            HeaderProtocol::EthernetIso88023 => match decode_ethernet(buf) {
+                Some((ethernet, payload)) => {
+                    decoded.ethernet = Some(ethernet);
+                    match ethernet.ether_type {
+                        ETHER_TYPE_IPV4 | ETHER_TYPE_IPV6 => payload,
+                        _ => return decoded,
+                    }
+                }
+                None => return decoded,
+            },
+            HeaderProtocol::Ipv4 | HeaderProtocol::Ipv6 => buf,
+            _ => return decoded,
+        };
+        let (ip, payload) = match decode_ip(ip_buf) {
+            Some(value) => value,
+            None => return decoded,
+        };
+        decoded.ip = Some(ip);
+        if let Some(payload) = payload {
+            decoded.transport = decode_transport(ip.protocol, payload);
+        }
+        decoded
+    }
+}
+
+fn decode_ethernet(buf: &[u8]) -> Option<(EthernetHeader, &[u8])> {
+    let dst_mac = MacAddress(buf.get(0..6)?.try_into().ok()?);
+    let src_mac = MacAddress(buf.get(6..12)?.try_into().ok()?);
+    let mut ether_type = u16::from_be_bytes(buf.get(12..14)?.try_into().ok()?);
+    let mut payload = buf.get(14..)?;
+    let mut vlan = None;
+    while ether_type == ETHER_TYPE_VLAN || ether_type == ETHER_TYPE_QINQ {
+        let tci = u16::from_be_bytes(payload.get(0..2)?.try_into().ok()?);
+        vlan = vlan.or(Some(tci & 0x0fff));
+        ether_type = u16::from_be_bytes(payload.get(2..4)?.try_into().ok()?);
+        payload = payload.get(4..)?;
+    }
+    let ethernet = EthernetHeader {
+        dst_mac,
+        src_mac,
+        vlan,
+        ether_type,
+    };
+    Some((ethernet, payload))
+}
+
+/// Decode IPv4 or IPv6 header based on the version field, the payload is
+/// [`None`] for non-first IPv4 fragments since they don't carry the transport
+/// header
+fn decode_ip(buf: &[u8]) -> Option<(IpHeader, Option<&[u8]>)> {
+    match buf.first()? >> 4 {
+        4 => {
+            let header_len = ((buf[0] & 0x0f) as usize) * 4;
+            let src: [u8; 4] = buf.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = buf.get(16..20)?.try_into().ok()?;
+            let fragment_offset = u16::from_be_bytes(buf.get(6..8)?.try_into().ok()?) & 0x1fff;
+            let ip = IpHeader {
+                src: IpAddr::V4(Ipv4Addr::from(src)),
+                dst: IpAddr::V4(Ipv4Addr::from(dst)),
+                protocol: buf[9],
+                tos: buf[1],
+                ttl: buf[8],
+            };
+            let payload = if fragment_offset == 0 {
+                buf.get(header_len..)
+            } else {
+                None
+            };
+            Some((ip, payload))
+        }
+        6 => {
+            let src: [u8; 16] = buf.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = buf.get(24..40)?.try_into().ok()?;
+            let ip = IpHeader {
+                src: IpAddr::V6(Ipv6Addr::from(src)),
+                dst: IpAddr::V6(Ipv6Addr::from(dst)),
+                protocol: buf[6],
+                tos: ((buf[0] & 0x0f) << 4) | (buf[1] >> 4),
+                ttl: buf[7],
+            };
+            Some((ip, buf.get(40..)))
+        }
+        _ => None,
+    }
+}
+
+fn decode_transport(protocol: u8, buf: &[u8]) -> Option<TransportHeader> {
+    match protocol {
+        IP_PROTOCOL_TCP | IP_PROTOCOL_UDP | IP_PROTOCOL_SCTP => {
+            let src_port = u16::from_be_bytes(buf.get(0..2)?.try_into().ok()?);
+            let dst_port = u16::from_be_bytes(buf.get(2..4)?.try_into().ok()?);
+            let tcp_flags = if protocol == IP_PROTOCOL_TCP {
+                buf.get(13).copied()
+            } else {
+                None
+            };
+            Some(TransportHeader {
+                src_port,
+                dst_port,
+                tcp_flags,
+            })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_ethernet_vlan_ipv4_tcp() {
+        let header = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, // dst mac
+            0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, // src mac
+            0x81, 0x00, 0x00, 0x64, // VLAN 100
+            0x08, 0x00, // IPv4
+            0x45, 0x10, 0x00, 0x28, 0x00, 0x01, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, // IPv4
+            0xc0, 0x00, 0x02, 0x01, // src
+            0xc6, 0x33, 0x64, 0x01, // dst
+            0xc3, 0x50, 0x00, 0x50, // ports
+            0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x50, 0x12, // TCP, flags SYN+ACK
+        ];
+        let decoded = DecodedHeader::decode(HeaderProtocol::EthernetIso88023, &header);
+        assert_eq!(
+            decoded,
+            DecodedHeader {
+                ethernet: Some(EthernetHeader {
+                    dst_mac: MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+                    src_mac: MacAddress([0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb]),
+                    vlan: Some(100),
+                    ether_type: ETHER_TYPE_IPV4,
+                }),
+                ip: Some(IpHeader {
+                    src: IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                    dst: IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)),
+                    protocol: IP_PROTOCOL_TCP,
+                    tos: 0x10,
+                    ttl: 64,
+                }),
+                transport: Some(TransportHeader {
+                    src_port: 50000,
+                    dst_port: 80,
+                    tcp_flags: Some(0x12),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_truncated_ipv6() {
+        let header = [
+            0x60, 0x00, 0x00, 0x00, 0x00, 0x08, 0x11, 0x40, // IPv6, UDP, hop limit 64
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01, // src
+            0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x02, // dst
+            0x00, 0x35, // truncated UDP header
+        ];
+        let decoded = DecodedHeader::decode(HeaderProtocol::Ipv6, &header);
+        assert_eq!(decoded.ethernet, None);
+        assert_eq!(
+            decoded.ip,
+            Some(IpHeader {
+                src: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                dst: IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 2)),
+                protocol: IP_PROTOCOL_UDP,
+                tos: 0,
+                ttl: 64,
+            })
+        );
+        assert_eq!(decoded.transport, None);
+    }
+}
@@ -0,0 +1,779 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Representation of the [sFlow Version 5](https://sflow.org/sflow_version_5.txt)
+//! datagrams. The flow and counter samples are supported in both the compact
+//! and expanded formats, along with the standard flow and counter records.
+//! Samples and records of other formats are kept as raw bytes.
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, FromRepr};
+
+pub mod header;
+#[cfg(feature = "serde")]
+pub mod wire;
+
+/// The only sFlow version supported by this crate
+pub const SFLOW_VERSION_5: u32 = 5;
+
+/// Enterprise number of the formats defined by the sFlow standard
+pub const SFLOW_STANDARD_ENTERPRISE: u32 = 0;
+
+/// Type of the IP addresses carried in sFlow, the unknown address type (0)
+/// is not supported
+#[repr(u32)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum AddressType {
+    Ipv4 = 1,
+    Ipv6 = 2,
+}
+
+/// Address type is not one of [`AddressType`], the carried value is the
+/// undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedAddressType(pub u32);
+
+impl From<AddressType> for u32 {
+    fn from(value: AddressType) -> Self {
+        value as u32
+    }
+}
+
+impl TryFrom<u32> for AddressType {
+    type Error = UndefinedAddressType;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedAddressType(value)),
+        }
+    }
+}
+
+/// sFlow datagram
+///
+/// ```text
+/// struct sample_datagram_v5 {
+///    address agent_address;
+///    unsigned int sub_agent_id;
+///    unsigned int sequence_number;
+///    unsigned int uptime;
+///    sample_record samples<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SFlowDatagram {
+    agent_address: IpAddr,
+    sub_agent_id: u32,
+    sequence_number: u32,
+    uptime: u32,
+    samples: Vec<Sample>,
+}
+
+impl SFlowDatagram {
+    pub const fn new(
+        agent_address: IpAddr,
+        sub_agent_id: u32,
+        sequence_number: u32,
+        uptime: u32,
+        samples: Vec<Sample>,
+    ) -> Self {
+        Self {
+            agent_address,
+            sub_agent_id,
+            sequence_number,
+            uptime,
+            samples,
+        }
+    }
+
+    /// sFlow version is always [`SFLOW_VERSION_5`]
+    pub const fn version(&self) -> u32 {
+        SFLOW_VERSION_5
+    }
+
+    pub const fn agent_address(&self) -> IpAddr {
+        self.agent_address
+    }
+
+    pub const fn sub_agent_id(&self) -> u32 {
+        self.sub_agent_id
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    /// Current time in milliseconds since the device last booted
+    pub const fn uptime(&self) -> u32 {
+        self.uptime
+    }
+
+    pub const fn samples(&self) -> &Vec<Sample> {
+        &self.samples
+    }
+}
+
+/// Data format of the samples and records, it's encoded on the wire as
+/// 20-bits enterprise number followed by 12-bits format number.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DataFormat {
+    enterprise: u32,
+    format: u16,
+}
+
+impl DataFormat {
+    pub const fn new(enterprise: u32, format: u16) -> Self {
+        Self { enterprise, format }
+    }
+
+    /// Format defined by the sFlow standard
+    pub const fn standard(format: u16) -> Self {
+        Self::new(SFLOW_STANDARD_ENTERPRISE, format)
+    }
+
+    pub const fn enterprise(&self) -> u32 {
+        self.enterprise
+    }
+
+    pub const fn format(&self) -> u16 {
+        self.format
+    }
+}
+
+impl From<u32> for DataFormat {
+    fn from(value: u32) -> Self {
+        Self::new(value >> 12, (value & 0xfff) as u16)
+    }
+}
+
+impl From<DataFormat> for u32 {
+    fn from(value: DataFormat) -> Self {
+        (value.enterprise << 12) | (value.format as u32 & 0xfff)
+    }
+}
+
+/// Standard sample formats
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SampleType {
+    FlowSample = 1,
+    CountersSample = 2,
+    FlowSampleExpanded = 3,
+    CountersSampleExpanded = 4,
+}
+
+/// sFlow sample record
+///
+/// ```text
+/// struct sample_record {
+///    data_format sample_type;
+///    opaque sample_data<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Sample {
+    Flow(FlowSample),
+    Counters(CountersSample),
+    /// Same as [`Sample::Flow`] but with the data source and interfaces
+    /// encoded in the expanded format
+    ExpandedFlow(FlowSample),
+    /// Same as [`Sample::Counters`] but with the data source encoded in the
+    /// expanded format
+    ExpandedCounters(CountersSample),
+    Unknown(DataFormat, Vec<u8>),
+}
+
+impl Sample {
+    pub const fn data_format(&self) -> DataFormat {
+        match self {
+            Self::Flow(_) => DataFormat::standard(SampleType::FlowSample as u16),
+            Self::Counters(_) => DataFormat::standard(SampleType::CountersSample as u16),
+            Self::ExpandedFlow(_) => DataFormat::standard(SampleType::FlowSampleExpanded as u16),
+            Self::ExpandedCounters(_) => {
+                DataFormat::standard(SampleType::CountersSampleExpanded as u16)
+            }
+            Self::Unknown(data_format, _) => *data_format,
+        }
+    }
+}
+
+/// The data source of a sample, in the compact format the type is encoded in
+/// 8-bits and the index in 24-bits.
+///
+/// The type is one of: 0 = ifIndex, 1 = smonVlanDataSource,
+/// 2 = entPhysicalEntry
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DataSource {
+    source_type: u32,
+    index: u32,
+}
+
+impl DataSource {
+    pub const fn new(source_type: u32, index: u32) -> Self {
+        Self { source_type, index }
+    }
+
+    pub const fn source_type(&self) -> u32 {
+        self.source_type
+    }
+
+    pub const fn index(&self) -> u32 {
+        self.index
+    }
+}
+
+/// Input or output interface of a flow sample, in the compact format the
+/// format is encoded in 2-bits and the value in 30-bits.
+///
+/// The format is one of: 0 = ifIndex, 1 = packet discarded with the value
+/// being the reason code, 2 = multiple interfaces with the value being the
+/// number of interfaces (0x7fffffff when unknown)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Interface {
+    format: u32,
+    value: u32,
+}
+
+impl Interface {
+    pub const fn new(format: u32, value: u32) -> Self {
+        Self { format, value }
+    }
+
+    pub const fn format(&self) -> u32 {
+        self.format
+    }
+
+    pub const fn value(&self) -> u32 {
+        self.value
+    }
+}
+
+/// Flow sample, used for both the compact and expanded formats
+///
+/// ```text
+/// struct flow_sample {
+///    unsigned int sequence_number;
+///    sflow_data_source source_id;
+///    unsigned int sampling_rate;
+///    unsigned int sample_pool;
+///    unsigned int drops;
+///    interface input;
+///    interface output;
+///    flow_record flow_records<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowSample {
+    pub sequence_number: u32,
+    pub source_id: DataSource,
+    pub sampling_rate: u32,
+    /// Total number of packets that could have been sampled
+    pub sample_pool: u32,
+    /// Number of times a packet was dropped due to lack of resources
+    pub drops: u32,
+    pub input: Interface,
+    pub output: Interface,
+    pub records: Vec<FlowRecord>,
+}
+
+/// Counters sample, used for both the compact and expanded formats
+///
+/// ```text
+/// struct counters_sample {
+///    unsigned int sequence_number;
+///    sflow_data_source source_id;
+///    counter_record counters<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountersSample {
+    sequence_number: u32,
+    source_id: DataSource,
+    records: Vec<CounterRecord>,
+}
+
+impl CountersSample {
+    pub const fn new(
+        sequence_number: u32,
+        source_id: DataSource,
+        records: Vec<CounterRecord>,
+    ) -> Self {
+        Self {
+            sequence_number,
+            source_id,
+            records,
+        }
+    }
+
+    pub const fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+
+    pub const fn source_id(&self) -> DataSource {
+        self.source_id
+    }
+
+    pub const fn records(&self) -> &Vec<CounterRecord> {
+        &self.records
+    }
+}
+
+/// Standard flow record formats
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum FlowRecordType {
+    SampledHeader = 1,
+    SampledEthernet = 2,
+    SampledIpv4 = 3,
+    SampledIpv6 = 4,
+    ExtendedSwitch = 1001,
+    ExtendedRouter = 1002,
+}
+
+/// sFlow flow record
+///
+/// ```text
+/// struct flow_record {
+///    data_format flow_format;
+///    opaque flow_data<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowRecord {
+    SampledHeader(SampledHeader),
+    SampledEthernet(SampledEthernet),
+    SampledIpv4(SampledIpv4),
+    SampledIpv6(SampledIpv6),
+    ExtendedSwitch(ExtendedSwitch),
+    ExtendedRouter(ExtendedRouter),
+    Unknown(DataFormat, Vec<u8>),
+}
+
+impl FlowRecord {
+    pub const fn data_format(&self) -> DataFormat {
+        match self {
+            Self::SampledHeader(_) => DataFormat::standard(FlowRecordType::SampledHeader as u16),
+            Self::SampledEthernet(_) => {
+                DataFormat::standard(FlowRecordType::SampledEthernet as u16)
+            }
+            Self::SampledIpv4(_) => DataFormat::standard(FlowRecordType::SampledIpv4 as u16),
+            Self::SampledIpv6(_) => DataFormat::standard(FlowRecordType::SampledIpv6 as u16),
+            Self::ExtendedSwitch(_) => DataFormat::standard(FlowRecordType::ExtendedSwitch as u16),
+            Self::ExtendedRouter(_) => DataFormat::standard(FlowRecordType::ExtendedRouter as u16),
+            Self::Unknown(data_format, _) => *data_format,
+        }
+    }
+}
+
+/// The protocol of the sampled packet header
+#[repr(u32)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum HeaderProtocol {
+    EthernetIso88023 = 1,
+    Iso88024TokenBus = 2,
+    Iso88025TokenRing = 3,
+    Fddi = 4,
+    FrameRelay = 5,
+    X25 = 6,
+    Ppp = 7,
+    Smds = 8,
+    Aal5 = 9,
+    Aal5Ip = 10,
+    Ipv4 = 11,
+    Ipv6 = 12,
+    Mpls = 13,
+    Pos = 14,
+    Ieee80211Mac = 15,
+    Ieee80211Ampdu = 16,
+    Ieee80211AmsduSubframe = 17,
+}
+
+/// Header protocol is not one of [`HeaderProtocol`], the carried value is the
+/// undefined code.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct UndefinedHeaderProtocol(pub u32);
+
+impl From<HeaderProtocol> for u32 {
+    fn from(value: HeaderProtocol) -> Self {
+        value as u32
+    }
+}
+
+impl TryFrom<u32> for HeaderProtocol {
+    type Error = UndefinedHeaderProtocol;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match Self::from_repr(value) {
+            Some(val) => Ok(val),
+            None => Err(UndefinedHeaderProtocol(value)),
+        }
+    }
+}
+
+/// Raw packet header, the first bytes of the sampled packet as seen on the
+/// wire.
+///
+/// ```text
+/// struct sampled_header {
+///    header_protocol protocol;
+///    unsigned int frame_length;
+///    unsigned int stripped;
+///    opaque header<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledHeader {
+    protocol: HeaderProtocol,
+    frame_length: u32,
+    stripped: u32,
+    header: Vec<u8>,
+}
+
+impl SampledHeader {
+    pub const fn new(
+        protocol: HeaderProtocol,
+        frame_length: u32,
+        stripped: u32,
+        header: Vec<u8>,
+    ) -> Self {
+        Self {
+            protocol,
+            frame_length,
+            stripped,
+            header,
+        }
+    }
+
+    pub const fn protocol(&self) -> HeaderProtocol {
+        self.protocol
+    }
+
+    /// Original length of the packet before sampling
+    pub const fn frame_length(&self) -> u32 {
+        self.frame_length
+    }
+
+    /// Number of octets removed from the packet before extracting the header
+    pub const fn stripped(&self) -> u32 {
+        self.stripped
+    }
+
+    pub const fn header(&self) -> &Vec<u8> {
+        &self.header
+    }
+
+    /// Decode the Ethernet, IP, and transport headers from the sampled header,
+    /// see [`header::DecodedHeader`]
+    pub fn decode(&self) -> header::DecodedHeader {
+        header::DecodedHeader::decode(self.protocol, &self.header)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MacAddress(pub [u8; 6]);
+
+/// Ethernet frame data
+///
+/// ```text
+/// struct sampled_ethernet {
+///    unsigned int length;
+///    mac src_mac;
+///    mac dst_mac;
+///    unsigned int type;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledEthernet {
+    length: u32,
+    src_mac: MacAddress,
+    dst_mac: MacAddress,
+    eth_type: u32,
+}
+
+impl SampledEthernet {
+    pub const fn new(length: u32, src_mac: MacAddress, dst_mac: MacAddress, eth_type: u32) -> Self {
+        Self {
+            length,
+            src_mac,
+            dst_mac,
+            eth_type,
+        }
+    }
+
+    /// The length of the MAC packet received on the network
+    pub const fn length(&self) -> u32 {
+        self.length
+    }
+
+    pub const fn src_mac(&self) -> MacAddress {
+        self.src_mac
+    }
+
+    pub const fn dst_mac(&self) -> MacAddress {
+        self.dst_mac
+    }
+
+    pub const fn eth_type(&self) -> u32 {
+        self.eth_type
+    }
+}
+
+/// Packet IPv4 data
+///
+/// ```text
+/// struct sampled_ipv4 {
+///    unsigned int length;
+///    unsigned int protocol;
+///    ip_v4 src_ip;
+///    ip_v4 dst_ip;
+///    unsigned int src_port;
+///    unsigned int dst_port;
+///    unsigned int tcp_flags;
+///    unsigned int tos;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledIpv4 {
+    /// The length of the IP packet excluding lower layer encapsulations
+    pub length: u32,
+    pub protocol: u32,
+    pub src_ip: Ipv4Addr,
+    pub dst_ip: Ipv4Addr,
+    pub src_port: u32,
+    pub dst_port: u32,
+    pub tcp_flags: u32,
+    pub tos: u32,
+}
+
+/// Packet IPv6 data
+///
+/// ```text
+/// struct sampled_ipv6 {
+///    unsigned int length;
+///    unsigned int protocol;
+///    ip_v6 src_ip;
+///    ip_v6 dst_ip;
+///    unsigned int src_port;
+///    unsigned int dst_port;
+///    unsigned int tcp_flags;
+///    unsigned int priority;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SampledIpv6 {
+    /// The length of the IP packet excluding lower layer encapsulations
+    pub length: u32,
+    pub protocol: u32,
+    pub src_ip: Ipv6Addr,
+    pub dst_ip: Ipv6Addr,
+    pub src_port: u32,
+    pub dst_port: u32,
+    pub tcp_flags: u32,
+    pub priority: u32,
+}
+
+/// Extended switch data
+///
+/// ```text
+/// struct extended_switch {
+///    unsigned int src_vlan;
+///    unsigned int src_priority;
+///    unsigned int dst_vlan;
+///    unsigned int dst_priority;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedSwitch {
+    src_vlan: u32,
+    src_priority: u32,
+    dst_vlan: u32,
+    dst_priority: u32,
+}
+
+impl ExtendedSwitch {
+    pub const fn new(src_vlan: u32, src_priority: u32, dst_vlan: u32, dst_priority: u32) -> Self {
+        Self {
+            src_vlan,
+            src_priority,
+            dst_vlan,
+            dst_priority,
+        }
+    }
+
+    pub const fn src_vlan(&self) -> u32 {
+        self.src_vlan
+    }
+
+    pub const fn src_priority(&self) -> u32 {
+        self.src_priority
+    }
+
+    pub const fn dst_vlan(&self) -> u32 {
+        self.dst_vlan
+    }
+
+    pub const fn dst_priority(&self) -> u32 {
+        self.dst_priority
+    }
+}
+
+/// Extended router data
+///
+/// ```text
+/// struct extended_router {
+///    next_hop nexthop;
+///    unsigned int src_mask_len;
+///    unsigned int dst_mask_len;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedRouter {
+    next_hop: IpAddr,
+    src_mask_len: u32,
+    dst_mask_len: u32,
+}
+
+impl ExtendedRouter {
+    pub const fn new(next_hop: IpAddr, src_mask_len: u32, dst_mask_len: u32) -> Self {
+        Self {
+            next_hop,
+            src_mask_len,
+            dst_mask_len,
+        }
+    }
+
+    pub const fn next_hop(&self) -> IpAddr {
+        self.next_hop
+    }
+
+    pub const fn src_mask_len(&self) -> u32 {
+        self.src_mask_len
+    }
+
+    pub const fn dst_mask_len(&self) -> u32 {
+        self.dst_mask_len
+    }
+}
+
+/// Standard counter record formats
+#[repr(u16)]
+#[derive(Display, FromRepr, Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum CounterRecordType {
+    GenericInterface = 1,
+    EthernetInterface = 2,
+    Processor = 1001,
+}
+
+/// sFlow counter record
+///
+/// ```text
+/// struct counter_record {
+///    data_format counter_format;
+///    opaque counter_data<>;
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CounterRecord {
+    GenericInterface(GenericInterfaceCounters),
+    EthernetInterface(EthernetInterfaceCounters),
+    Processor(ProcessorCounters),
+    Unknown(DataFormat, Vec<u8>),
+}
+
+impl CounterRecord {
+    pub const fn data_format(&self) -> DataFormat {
+        match self {
+            Self::GenericInterface(_) => {
+                DataFormat::standard(CounterRecordType::GenericInterface as u16)
+            }
+            Self::EthernetInterface(_) => {
+                DataFormat::standard(CounterRecordType::EthernetInterface as u16)
+            }
+            Self::Processor(_) => DataFormat::standard(CounterRecordType::Processor as u16),
+            Self::Unknown(data_format, _) => *data_format,
+        }
+    }
+}
+
+/// Generic interface counters, see
+/// [RFC2233](https://datatracker.ietf.org/doc/html/rfc2233)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenericInterfaceCounters {
+    pub if_index: u32,
+    pub if_type: u32,
+    pub if_speed: u64,
+    /// 0 = unknown, 1 = full-duplex, 2 = half-duplex, 3 = in, 4 = out
+    pub if_direction: u32,
+    /// bit 0 is ifAdminStatus (0 = down, 1 = up) and bit 1 is ifOperStatus
+    /// (0 = down, 1 = up)
+    pub if_status: u32,
+    pub if_in_octets: u64,
+    pub if_in_ucast_pkts: u32,
+    pub if_in_multicast_pkts: u32,
+    pub if_in_broadcast_pkts: u32,
+    pub if_in_discards: u32,
+    pub if_in_errors: u32,
+    pub if_in_unknown_protos: u32,
+    pub if_out_octets: u64,
+    pub if_out_ucast_pkts: u32,
+    pub if_out_multicast_pkts: u32,
+    pub if_out_broadcast_pkts: u32,
+    pub if_out_discards: u32,
+    pub if_out_errors: u32,
+    pub if_promiscuous_mode: u32,
+}
+
+/// Ethernet interface counters, see
+/// [RFC2358](https://datatracker.ietf.org/doc/html/rfc2358)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EthernetInterfaceCounters {
+    pub dot3_stats_alignment_errors: u32,
+    pub dot3_stats_fcs_errors: u32,
+    pub dot3_stats_single_collision_frames: u32,
+    pub dot3_stats_multiple_collision_frames: u32,
+    pub dot3_stats_sqe_test_errors: u32,
+    pub dot3_stats_deferred_transmissions: u32,
+    pub dot3_stats_late_collisions: u32,
+    pub dot3_stats_excessive_collisions: u32,
+    pub dot3_stats_internal_mac_transmit_errors: u32,
+    pub dot3_stats_carrier_sense_errors: u32,
+    pub dot3_stats_frame_too_longs: u32,
+    pub dot3_stats_internal_mac_receive_errors: u32,
+    pub dot3_stats_symbol_errors: u32,
+}
+
+/// Processor counters
+///
+/// ```text
+/// struct processor {
+///    percentage 5s_cpu;
+///    percentage 1m_cpu;
+///    percentage 5m_cpu;
+///    unsigned hyper total_memory;
+///    unsigned hyper free_memory;
+/// }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessorCounters {
+    /// Percentage in hundredths of a percent (i.e. 100 = 1%)
+    pub cpu_5s: u32,
+    pub cpu_1m: u32,
+    pub cpu_5m: u32,
+    pub total_memory: u64,
+    pub free_memory: u64,
+}
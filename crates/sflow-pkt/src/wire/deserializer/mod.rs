@@ -0,0 +1,568 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Deserializer library for sFlow's wire protocol
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use netgauze_parse_utils::{
+    parse_into_located, parse_into_located_one_input, ErrorKindSerdeDeref, ReadablePdu,
+    ReadablePduWithOneInput, Span,
+};
+use netgauze_serde_macros::LocatedError;
+use nom::{
+    error::{ErrorKind, FromExternalError, ParseError},
+    number::complete::{be_u128, be_u32, be_u64},
+    IResult,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/// Parse an sFlow address, the address type followed by either IPv4 or IPv6
+/// address
+#[inline]
+fn parse_address<'a, E>(buf: Span<'a>) -> IResult<Span<'a>, IpAddr, E>
+where
+    E: ParseError<Span<'a>> + FromExternalError<Span<'a>, UndefinedAddressType>,
+{
+    let (buf, address_type) = nom::combinator::map_res(be_u32, AddressType::try_from)(buf)?;
+    match address_type {
+        AddressType::Ipv4 => {
+            let (buf, address) = be_u32(buf)?;
+            Ok((buf, IpAddr::V4(Ipv4Addr::from(address))))
+        }
+        AddressType::Ipv6 => {
+            let (buf, address) = be_u128(buf)?;
+            Ok((buf, IpAddr::V6(Ipv6Addr::from(address))))
+        }
+    }
+}
+
+/// Data source is encoded in the compact format as 8-bits type and 24-bits
+/// index, and in the expanded format as two 32-bits values
+#[inline]
+fn parse_data_source<'a, E: ParseError<Span<'a>>>(
+    buf: Span<'a>,
+    expanded: bool,
+) -> IResult<Span<'a>, DataSource, E> {
+    if expanded {
+        let (buf, source_type) = be_u32(buf)?;
+        let (buf, index) = be_u32(buf)?;
+        Ok((buf, DataSource::new(source_type, index)))
+    } else {
+        let (buf, value) = be_u32(buf)?;
+        Ok((buf, DataSource::new(value >> 24, value & 0x00ffffff)))
+    }
+}
+
+/// Interface is encoded in the compact format as 2-bits format and 30-bits
+/// value, and in the expanded format as two 32-bits values
+#[inline]
+fn parse_interface<'a, E: ParseError<Span<'a>>>(
+    buf: Span<'a>,
+    expanded: bool,
+) -> IResult<Span<'a>, Interface, E> {
+    if expanded {
+        let (buf, format) = be_u32(buf)?;
+        let (buf, value) = be_u32(buf)?;
+        Ok((buf, Interface::new(format, value)))
+    } else {
+        let (buf, value) = be_u32(buf)?;
+        Ok((buf, Interface::new(value >> 30, value & 0x3fffffff)))
+    }
+}
+
+/// Parse the records preceded by 4-octets count of these records
+#[inline]
+fn parse_counted<'a, T, E, Lin>(buf: Span<'a>) -> IResult<Span<'a>, Vec<T>, E>
+where
+    T: ReadablePdu<'a, Lin>,
+    Lin: std::fmt::Debug,
+    E: ParseError<Span<'a>> + From<Lin>,
+{
+    let (mut buf, count) = be_u32(buf)?;
+    let mut values = Vec::new();
+    for _ in 0..count {
+        let (t, value) = parse_into_located(buf)?;
+        buf = t;
+        values.push(value);
+    }
+    Ok((buf, values))
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum SFlowDatagramParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UnsupportedVersion(u32),
+    UndefinedAddressType(#[from_external] UndefinedAddressType),
+    SampleError(#[from_located(module = "self")] SampleParsingError),
+}
+
+impl<'a> ReadablePdu<'a, LocatedSFlowDatagramParsingError<'a>> for SFlowDatagram {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedSFlowDatagramParsingError<'a>> {
+        let input = buf;
+        let (buf, version) = be_u32(buf)?;
+        if version != SFLOW_VERSION_5 {
+            return Err(nom::Err::Error(LocatedSFlowDatagramParsingError::new(
+                input,
+                SFlowDatagramParsingError::UnsupportedVersion(version),
+            )));
+        }
+        let (buf, agent_address) = parse_address(buf)?;
+        let (buf, sub_agent_id) = be_u32(buf)?;
+        let (buf, sequence_number) = be_u32(buf)?;
+        let (buf, uptime) = be_u32(buf)?;
+        let (buf, samples) = parse_counted(buf)?;
+        Ok((
+            buf,
+            SFlowDatagram::new(
+                agent_address,
+                sub_agent_id,
+                sequence_number,
+                uptime,
+                samples,
+            ),
+        ))
+    }
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum SampleParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    FlowSampleError(#[from_located(module = "self")] FlowSampleParsingError),
+    CountersSampleError(#[from_located(module = "self")] CountersSampleParsingError),
+}
+
+impl<'a> ReadablePdu<'a, LocatedSampleParsingError<'a>> for Sample {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedSampleParsingError<'a>> {
+        let (buf, data_format) = nom::combinator::map(be_u32, DataFormat::from)(buf)?;
+        let (reminder, buf) = nom::multi::length_data(be_u32)(buf)?;
+        let sample_type = if data_format.enterprise() == SFLOW_STANDARD_ENTERPRISE {
+            SampleType::from_repr(data_format.format())
+        } else {
+            None
+        };
+        let (buf, sample) = match sample_type {
+            Some(SampleType::FlowSample) => {
+                let (buf, value) = parse_into_located_one_input(buf, false)?;
+                (buf, Sample::Flow(value))
+            }
+            Some(SampleType::CountersSample) => {
+                let (buf, value) = parse_into_located_one_input(buf, false)?;
+                (buf, Sample::Counters(value))
+            }
+            Some(SampleType::FlowSampleExpanded) => {
+                let (buf, value) = parse_into_located_one_input(buf, true)?;
+                (buf, Sample::ExpandedFlow(value))
+            }
+            Some(SampleType::CountersSampleExpanded) => {
+                let (buf, value) = parse_into_located_one_input(buf, true)?;
+                (buf, Sample::ExpandedCounters(value))
+            }
+            None => return Ok((reminder, Sample::Unknown(data_format, buf.to_vec()))),
+        };
+        // Make sure the sample is fully parsed according to its length
+        if !buf.is_empty() {
+            return Err(nom::Err::Error(LocatedSampleParsingError::new(
+                buf,
+                SampleParsingError::NomError(ErrorKind::NonEmpty),
+            )));
+        }
+        Ok((reminder, sample))
+    }
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum FlowSampleParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    FlowRecordError(#[from_located(module = "self")] FlowRecordParsingError),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, bool, LocatedFlowSampleParsingError<'a>> for FlowSample {
+    /// `expanded` is true for the flow samples encoded in the expanded format
+    fn from_wire(
+        buf: Span<'a>,
+        expanded: bool,
+    ) -> IResult<Span<'a>, Self, LocatedFlowSampleParsingError<'a>> {
+        let (buf, sequence_number) = be_u32(buf)?;
+        let (buf, source_id) = parse_data_source(buf, expanded)?;
+        let (buf, sampling_rate) = be_u32(buf)?;
+        let (buf, sample_pool) = be_u32(buf)?;
+        let (buf, drops) = be_u32(buf)?;
+        let (buf, input) = parse_interface(buf, expanded)?;
+        let (buf, output) = parse_interface(buf, expanded)?;
+        let (buf, records) = parse_counted(buf)?;
+        Ok((
+            buf,
+            FlowSample {
+                sequence_number,
+                source_id,
+                sampling_rate,
+                sample_pool,
+                drops,
+                input,
+                output,
+                records,
+            },
+        ))
+    }
+}
+
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum CountersSampleParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    CounterRecordError(#[from_located(module = "self")] CounterRecordParsingError),
+}
+
+impl<'a> ReadablePduWithOneInput<'a, bool, LocatedCountersSampleParsingError<'a>>
+    for CountersSample
+{
+    /// `expanded` is true for the counters samples encoded in the expanded
+    /// format
+    fn from_wire(
+        buf: Span<'a>,
+        expanded: bool,
+    ) -> IResult<Span<'a>, Self, LocatedCountersSampleParsingError<'a>> {
+        let (buf, sequence_number) = be_u32(buf)?;
+        let (buf, source_id) = parse_data_source(buf, expanded)?;
+        let (buf, records) = parse_counted(buf)?;
+        Ok((
+            buf,
+            CountersSample::new(sequence_number, source_id, records),
+        ))
+    }
+}
+
+/// Error shared by all the standard flow records
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum FlowRecordParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+    UndefinedHeaderProtocol(#[from_external] UndefinedHeaderProtocol),
+    UndefinedAddressType(#[from_external] UndefinedAddressType),
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for FlowRecord {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, data_format) = nom::combinator::map(be_u32, DataFormat::from)(buf)?;
+        let (reminder, buf) = nom::multi::length_data(be_u32)(buf)?;
+        let record_type = if data_format.enterprise() == SFLOW_STANDARD_ENTERPRISE {
+            FlowRecordType::from_repr(data_format.format())
+        } else {
+            None
+        };
+        let (buf, record) = match record_type {
+            Some(FlowRecordType::SampledHeader) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::SampledHeader(value))
+            }
+            Some(FlowRecordType::SampledEthernet) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::SampledEthernet(value))
+            }
+            Some(FlowRecordType::SampledIpv4) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::SampledIpv4(value))
+            }
+            Some(FlowRecordType::SampledIpv6) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::SampledIpv6(value))
+            }
+            Some(FlowRecordType::ExtendedSwitch) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::ExtendedSwitch(value))
+            }
+            Some(FlowRecordType::ExtendedRouter) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, FlowRecord::ExtendedRouter(value))
+            }
+            None => return Ok((reminder, FlowRecord::Unknown(data_format, buf.to_vec()))),
+        };
+        // Make sure the record is fully parsed according to its length
+        if !buf.is_empty() {
+            return Err(nom::Err::Error(LocatedFlowRecordParsingError::new(
+                buf,
+                FlowRecordParsingError::NomError(ErrorKind::NonEmpty),
+            )));
+        }
+        Ok((reminder, record))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for SampledHeader {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, protocol) = nom::combinator::map_res(be_u32, HeaderProtocol::try_from)(buf)?;
+        let (buf, frame_length) = be_u32(buf)?;
+        let (buf, stripped) = be_u32(buf)?;
+        let (buf, header) = nom::multi::length_data(be_u32)(buf)?;
+        // XDR opaque data is padded to a multiple of four octets
+        let (buf, _) = nom::bytes::complete::take((4 - header.len() % 4) % 4)(buf)?;
+        Ok((
+            buf,
+            SampledHeader::new(protocol, frame_length, stripped, header.to_vec()),
+        ))
+    }
+}
+
+/// XDR encodes the 6-octets MAC as an opaque padded to 8-octets
+#[inline]
+fn parse_mac<'a, E: ParseError<Span<'a>>>(buf: Span<'a>) -> IResult<Span<'a>, MacAddress, E> {
+    let (buf, mac) = nom::bytes::complete::take(6usize)(buf)?;
+    let (buf, _) = nom::bytes::complete::take(2usize)(buf)?;
+    let mut address = [0u8; 6];
+    address.copy_from_slice(&mac);
+    Ok((buf, MacAddress(address)))
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for SampledEthernet {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, length) = be_u32(buf)?;
+        let (buf, src_mac) = parse_mac(buf)?;
+        let (buf, dst_mac) = parse_mac(buf)?;
+        let (buf, eth_type) = be_u32(buf)?;
+        Ok((
+            buf,
+            SampledEthernet::new(length, src_mac, dst_mac, eth_type),
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for SampledIpv4 {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, length) = be_u32(buf)?;
+        let (buf, protocol) = be_u32(buf)?;
+        let (buf, src_ip) = be_u32(buf)?;
+        let (buf, dst_ip) = be_u32(buf)?;
+        let (buf, src_port) = be_u32(buf)?;
+        let (buf, dst_port) = be_u32(buf)?;
+        let (buf, tcp_flags) = be_u32(buf)?;
+        let (buf, tos) = be_u32(buf)?;
+        Ok((
+            buf,
+            SampledIpv4 {
+                length,
+                protocol,
+                src_ip: Ipv4Addr::from(src_ip),
+                dst_ip: Ipv4Addr::from(dst_ip),
+                src_port,
+                dst_port,
+                tcp_flags,
+                tos,
+            },
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for SampledIpv6 {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, length) = be_u32(buf)?;
+        let (buf, protocol) = be_u32(buf)?;
+        let (buf, src_ip) = be_u128(buf)?;
+        let (buf, dst_ip) = be_u128(buf)?;
+        let (buf, src_port) = be_u32(buf)?;
+        let (buf, dst_port) = be_u32(buf)?;
+        let (buf, tcp_flags) = be_u32(buf)?;
+        let (buf, priority) = be_u32(buf)?;
+        Ok((
+            buf,
+            SampledIpv6 {
+                length,
+                protocol,
+                src_ip: Ipv6Addr::from(src_ip),
+                dst_ip: Ipv6Addr::from(dst_ip),
+                src_port,
+                dst_port,
+                tcp_flags,
+                priority,
+            },
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for ExtendedSwitch {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, src_vlan) = be_u32(buf)?;
+        let (buf, src_priority) = be_u32(buf)?;
+        let (buf, dst_vlan) = be_u32(buf)?;
+        let (buf, dst_priority) = be_u32(buf)?;
+        Ok((
+            buf,
+            ExtendedSwitch::new(src_vlan, src_priority, dst_vlan, dst_priority),
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedFlowRecordParsingError<'a>> for ExtendedRouter {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedFlowRecordParsingError<'a>> {
+        let (buf, next_hop) = parse_address(buf)?;
+        let (buf, src_mask_len) = be_u32(buf)?;
+        let (buf, dst_mask_len) = be_u32(buf)?;
+        Ok((
+            buf,
+            ExtendedRouter::new(next_hop, src_mask_len, dst_mask_len),
+        ))
+    }
+}
+
+/// Error shared by all the standard counter records
+#[derive(LocatedError, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+pub enum CounterRecordParsingError {
+    #[serde(with = "ErrorKindSerdeDeref")]
+    NomError(#[from_nom] ErrorKind),
+}
+
+impl<'a> ReadablePdu<'a, LocatedCounterRecordParsingError<'a>> for CounterRecord {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedCounterRecordParsingError<'a>> {
+        let (buf, data_format) = nom::combinator::map(be_u32, DataFormat::from)(buf)?;
+        let (reminder, buf) = nom::multi::length_data(be_u32)(buf)?;
+        let record_type = if data_format.enterprise() == SFLOW_STANDARD_ENTERPRISE {
+            CounterRecordType::from_repr(data_format.format())
+        } else {
+            None
+        };
+        let (buf, record) = match record_type {
+            Some(CounterRecordType::GenericInterface) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, CounterRecord::GenericInterface(value))
+            }
+            Some(CounterRecordType::EthernetInterface) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, CounterRecord::EthernetInterface(value))
+            }
+            Some(CounterRecordType::Processor) => {
+                let (buf, value) = parse_into_located(buf)?;
+                (buf, CounterRecord::Processor(value))
+            }
+            None => return Ok((reminder, CounterRecord::Unknown(data_format, buf.to_vec()))),
+        };
+        // Make sure the record is fully parsed according to its length
+        if !buf.is_empty() {
+            return Err(nom::Err::Error(LocatedCounterRecordParsingError::new(
+                buf,
+                CounterRecordParsingError::NomError(ErrorKind::NonEmpty),
+            )));
+        }
+        Ok((reminder, record))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedCounterRecordParsingError<'a>> for GenericInterfaceCounters {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedCounterRecordParsingError<'a>> {
+        let (buf, if_index) = be_u32(buf)?;
+        let (buf, if_type) = be_u32(buf)?;
+        let (buf, if_speed) = be_u64(buf)?;
+        let (buf, if_direction) = be_u32(buf)?;
+        let (buf, if_status) = be_u32(buf)?;
+        let (buf, if_in_octets) = be_u64(buf)?;
+        let (buf, if_in_ucast_pkts) = be_u32(buf)?;
+        let (buf, if_in_multicast_pkts) = be_u32(buf)?;
+        let (buf, if_in_broadcast_pkts) = be_u32(buf)?;
+        let (buf, if_in_discards) = be_u32(buf)?;
+        let (buf, if_in_errors) = be_u32(buf)?;
+        let (buf, if_in_unknown_protos) = be_u32(buf)?;
+        let (buf, if_out_octets) = be_u64(buf)?;
+        let (buf, if_out_ucast_pkts) = be_u32(buf)?;
+        let (buf, if_out_multicast_pkts) = be_u32(buf)?;
+        let (buf, if_out_broadcast_pkts) = be_u32(buf)?;
+        let (buf, if_out_discards) = be_u32(buf)?;
+        let (buf, if_out_errors) = be_u32(buf)?;
+        let (buf, if_promiscuous_mode) = be_u32(buf)?;
+        Ok((
+            buf,
+            GenericInterfaceCounters {
+                if_index,
+                if_type,
+                if_speed,
+                if_direction,
+                if_status,
+                if_in_octets,
+                if_in_ucast_pkts,
+                if_in_multicast_pkts,
+                if_in_broadcast_pkts,
+                if_in_discards,
+                if_in_errors,
+                if_in_unknown_protos,
+                if_out_octets,
+                if_out_ucast_pkts,
+                if_out_multicast_pkts,
+                if_out_broadcast_pkts,
+                if_out_discards,
+                if_out_errors,
+                if_promiscuous_mode,
+            },
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedCounterRecordParsingError<'a>> for EthernetInterfaceCounters {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedCounterRecordParsingError<'a>> {
+        let (buf, dot3_stats_alignment_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_fcs_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_single_collision_frames) = be_u32(buf)?;
+        let (buf, dot3_stats_multiple_collision_frames) = be_u32(buf)?;
+        let (buf, dot3_stats_sqe_test_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_deferred_transmissions) = be_u32(buf)?;
+        let (buf, dot3_stats_late_collisions) = be_u32(buf)?;
+        let (buf, dot3_stats_excessive_collisions) = be_u32(buf)?;
+        let (buf, dot3_stats_internal_mac_transmit_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_carrier_sense_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_frame_too_longs) = be_u32(buf)?;
+        let (buf, dot3_stats_internal_mac_receive_errors) = be_u32(buf)?;
+        let (buf, dot3_stats_symbol_errors) = be_u32(buf)?;
+        Ok((
+            buf,
+            EthernetInterfaceCounters {
+                dot3_stats_alignment_errors,
+                dot3_stats_fcs_errors,
+                dot3_stats_single_collision_frames,
+                dot3_stats_multiple_collision_frames,
+                dot3_stats_sqe_test_errors,
+                dot3_stats_deferred_transmissions,
+                dot3_stats_late_collisions,
+                dot3_stats_excessive_collisions,
+                dot3_stats_internal_mac_transmit_errors,
+                dot3_stats_carrier_sense_errors,
+                dot3_stats_frame_too_longs,
+                dot3_stats_internal_mac_receive_errors,
+                dot3_stats_symbol_errors,
+            },
+        ))
+    }
+}
+
+impl<'a> ReadablePdu<'a, LocatedCounterRecordParsingError<'a>> for ProcessorCounters {
+    fn from_wire(buf: Span<'a>) -> IResult<Span<'a>, Self, LocatedCounterRecordParsingError<'a>> {
+        let (buf, cpu_5s) = be_u32(buf)?;
+        let (buf, cpu_1m) = be_u32(buf)?;
+        let (buf, cpu_5m) = be_u32(buf)?;
+        let (buf, total_memory) = be_u64(buf)?;
+        let (buf, free_memory) = be_u64(buf)?;
+        Ok((
+            buf,
+            ProcessorCounters {
+                cpu_5s,
+                cpu_1m,
+                cpu_5m,
+                total_memory,
+                free_memory,
+            },
+        ))
+    }
+}
@@ -0,0 +1,512 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Serializer library for sFlow's wire protocol
+
+use std::{io::Write, net::IpAddr};
+
+use byteorder::{NetworkEndian, WriteBytesExt};
+use netgauze_parse_utils::{WritablePdu, WritablePduWithOneInput};
+use netgauze_serde_macros::WritingError;
+
+use crate::*;
+
+#[inline]
+const fn address_len(address: &IpAddr) -> usize {
+    match address {
+        IpAddr::V4(_) => 8,
+        IpAddr::V6(_) => 20,
+    }
+}
+
+#[inline]
+fn write_address<T: Write>(writer: &mut T, address: &IpAddr) -> Result<(), std::io::Error> {
+    match address {
+        IpAddr::V4(address) => {
+            writer.write_u32::<NetworkEndian>(AddressType::Ipv4.into())?;
+            writer.write_all(&address.octets())?;
+        }
+        IpAddr::V6(address) => {
+            writer.write_u32::<NetworkEndian>(AddressType::Ipv6.into())?;
+            writer.write_all(&address.octets())?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the 4-octets data format and 4-octets length of a sample or a record
+#[inline]
+fn write_data_format<T: Write>(
+    writer: &mut T,
+    data_format: DataFormat,
+    length: usize,
+) -> Result<(), std::io::Error> {
+    writer.write_u32::<NetworkEndian>(data_format.into())?;
+    writer.write_u32::<NetworkEndian>(length as u32)
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum SFlowDatagramWritingError {
+    StdIOError(#[from_std_io_error] String),
+    SampleError(#[from] SampleWritingError),
+}
+
+impl WritablePdu<SFlowDatagramWritingError> for SFlowDatagram {
+    /// 4-octets version, 4-octets sub agent id, 4-octets sequence number,
+    /// 4-octets uptime, and 4-octets samples count
+    const BASE_LENGTH: usize = 20;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+            + address_len(&self.agent_address())
+            + self.samples().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), SFlowDatagramWritingError> {
+        writer.write_u32::<NetworkEndian>(self.version())?;
+        write_address(writer, &self.agent_address())?;
+        writer.write_u32::<NetworkEndian>(self.sub_agent_id())?;
+        writer.write_u32::<NetworkEndian>(self.sequence_number())?;
+        writer.write_u32::<NetworkEndian>(self.uptime())?;
+        writer.write_u32::<NetworkEndian>(self.samples().len() as u32)?;
+        for sample in self.samples() {
+            sample.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum SampleWritingError {
+    StdIOError(#[from_std_io_error] String),
+    FlowSampleError(#[from] FlowSampleWritingError),
+    CountersSampleError(#[from] CountersSampleWritingError),
+}
+
+impl WritablePdu<SampleWritingError> for Sample {
+    /// 4-octets data format and 4-octets length
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        let value_len = match self {
+            Self::Flow(value) => value.len(false),
+            Self::Counters(value) => value.len(false),
+            Self::ExpandedFlow(value) => value.len(true),
+            Self::ExpandedCounters(value) => value.len(true),
+            Self::Unknown(_, value) => value.len(),
+        };
+        Self::BASE_LENGTH + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), SampleWritingError> {
+        write_data_format(writer, self.data_format(), self.len() - Self::BASE_LENGTH)?;
+        match self {
+            Self::Flow(value) => value.write(writer, false)?,
+            Self::Counters(value) => value.write(writer, false)?,
+            Self::ExpandedFlow(value) => value.write(writer, true)?,
+            Self::ExpandedCounters(value) => value.write(writer, true)?,
+            Self::Unknown(_, value) => writer.write_all(value)?,
+        }
+        Ok(())
+    }
+}
+
+/// Write the data source in the compact or expanded format, [`None`] if the
+/// data source doesn't fit in the compact format
+#[inline]
+fn write_data_source<T: Write>(
+    writer: &mut T,
+    source_id: DataSource,
+    expanded: bool,
+) -> Result<Option<()>, std::io::Error> {
+    if expanded {
+        writer.write_u32::<NetworkEndian>(source_id.source_type())?;
+        writer.write_u32::<NetworkEndian>(source_id.index())?;
+    } else {
+        if source_id.source_type() > 0xff || source_id.index() > 0x00ffffff {
+            return Ok(None);
+        }
+        writer.write_u32::<NetworkEndian>((source_id.source_type() << 24) | source_id.index())?;
+    }
+    Ok(Some(()))
+}
+
+/// Write the interface in the compact or expanded format, [`None`] if the
+/// interface doesn't fit in the compact format
+#[inline]
+fn write_interface<T: Write>(
+    writer: &mut T,
+    interface: Interface,
+    expanded: bool,
+) -> Result<Option<()>, std::io::Error> {
+    if expanded {
+        writer.write_u32::<NetworkEndian>(interface.format())?;
+        writer.write_u32::<NetworkEndian>(interface.value())?;
+    } else {
+        if interface.format() > 0b11 || interface.value() > 0x3fffffff {
+            return Ok(None);
+        }
+        writer.write_u32::<NetworkEndian>((interface.format() << 30) | interface.value())?;
+    }
+    Ok(Some(()))
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum FlowSampleWritingError {
+    StdIOError(#[from_std_io_error] String),
+    /// The data source is too large to be encoded in the compact format
+    InvalidCompactDataSource(DataSource),
+    /// The interface is too large to be encoded in the compact format
+    InvalidCompactInterface(Interface),
+    FlowRecordError(#[from] FlowRecordWritingError),
+}
+
+impl WritablePduWithOneInput<bool, FlowSampleWritingError> for FlowSample {
+    /// 4-octets sequence number, 4-octets source id, 4-octets sampling rate,
+    /// 4-octets sample pool, 4-octets drops, 4-octets input, 4-octets output,
+    /// and 4-octets records count in the compact format
+    const BASE_LENGTH: usize = 32;
+
+    fn len(&self, expanded: bool) -> usize {
+        // The expanded format adds 4-octets to the source id, input, and output
+        let expanded_len = if expanded { 12 } else { 0 };
+        Self::BASE_LENGTH + expanded_len + self.records.iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+        expanded: bool,
+    ) -> Result<(), FlowSampleWritingError> {
+        writer.write_u32::<NetworkEndian>(self.sequence_number)?;
+        if write_data_source(writer, self.source_id, expanded)?.is_none() {
+            return Err(FlowSampleWritingError::InvalidCompactDataSource(
+                self.source_id,
+            ));
+        }
+        writer.write_u32::<NetworkEndian>(self.sampling_rate)?;
+        writer.write_u32::<NetworkEndian>(self.sample_pool)?;
+        writer.write_u32::<NetworkEndian>(self.drops)?;
+        for interface in [self.input, self.output] {
+            if write_interface(writer, interface, expanded)?.is_none() {
+                return Err(FlowSampleWritingError::InvalidCompactInterface(interface));
+            }
+        }
+        writer.write_u32::<NetworkEndian>(self.records.len() as u32)?;
+        for record in &self.records {
+            record.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum CountersSampleWritingError {
+    StdIOError(#[from_std_io_error] String),
+    /// The data source is too large to be encoded in the compact format
+    InvalidCompactDataSource(DataSource),
+    CounterRecordError(#[from] CounterRecordWritingError),
+}
+
+impl WritablePduWithOneInput<bool, CountersSampleWritingError> for CountersSample {
+    /// 4-octets sequence number, 4-octets source id, and 4-octets records
+    /// count in the compact format
+    const BASE_LENGTH: usize = 12;
+
+    fn len(&self, expanded: bool) -> usize {
+        // The expanded format adds 4-octets to the source id
+        let expanded_len = if expanded { 4 } else { 0 };
+        Self::BASE_LENGTH + expanded_len + self.records().iter().map(|x| x.len()).sum::<usize>()
+    }
+
+    fn write<T: Write>(
+        &self,
+        writer: &mut T,
+        expanded: bool,
+    ) -> Result<(), CountersSampleWritingError> {
+        writer.write_u32::<NetworkEndian>(self.sequence_number())?;
+        if write_data_source(writer, self.source_id(), expanded)?.is_none() {
+            return Err(CountersSampleWritingError::InvalidCompactDataSource(
+                self.source_id(),
+            ));
+        }
+        writer.write_u32::<NetworkEndian>(self.records().len() as u32)?;
+        for record in self.records() {
+            record.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Error shared by all the standard flow records
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum FlowRecordWritingError {
+    StdIOError(#[from_std_io_error] String),
+}
+
+impl WritablePdu<FlowRecordWritingError> for FlowRecord {
+    /// 4-octets data format and 4-octets length
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        let value_len = match self {
+            Self::SampledHeader(value) => value.len(),
+            Self::SampledEthernet(value) => value.len(),
+            Self::SampledIpv4(value) => value.len(),
+            Self::SampledIpv6(value) => value.len(),
+            Self::ExtendedSwitch(value) => value.len(),
+            Self::ExtendedRouter(value) => value.len(),
+            Self::Unknown(_, value) => value.len(),
+        };
+        Self::BASE_LENGTH + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        write_data_format(writer, self.data_format(), self.len() - Self::BASE_LENGTH)?;
+        match self {
+            Self::SampledHeader(value) => value.write(writer),
+            Self::SampledEthernet(value) => value.write(writer),
+            Self::SampledIpv4(value) => value.write(writer),
+            Self::SampledIpv6(value) => value.write(writer),
+            Self::ExtendedSwitch(value) => value.write(writer),
+            Self::ExtendedRouter(value) => value.write(writer),
+            Self::Unknown(_, value) => Ok(writer.write_all(value)?),
+        }
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for SampledHeader {
+    /// 4-octets protocol, 4-octets frame length, 4-octets stripped, and
+    /// 4-octets header length
+    const BASE_LENGTH: usize = 16;
+
+    fn len(&self) -> usize {
+        // XDR opaque data is padded to a multiple of four octets
+        Self::BASE_LENGTH + self.header().len().next_multiple_of(4)
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.protocol().into())?;
+        writer.write_u32::<NetworkEndian>(self.frame_length())?;
+        writer.write_u32::<NetworkEndian>(self.stripped())?;
+        writer.write_u32::<NetworkEndian>(self.header().len() as u32)?;
+        writer.write_all(self.header())?;
+        let padding = self.header().len().next_multiple_of(4) - self.header().len();
+        writer.write_all(&[0u8; 3][..padding])?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for SampledEthernet {
+    /// 4-octets length, 8-octets padded source MAC, 8-octets padded
+    /// destination MAC, and 4-octets type
+    const BASE_LENGTH: usize = 24;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.length())?;
+        writer.write_all(&self.src_mac().0)?;
+        writer.write_u16::<NetworkEndian>(0)?;
+        writer.write_all(&self.dst_mac().0)?;
+        writer.write_u16::<NetworkEndian>(0)?;
+        writer.write_u32::<NetworkEndian>(self.eth_type())?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for SampledIpv4 {
+    /// 8 fields of 4-octets each
+    const BASE_LENGTH: usize = 32;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.length)?;
+        writer.write_u32::<NetworkEndian>(self.protocol)?;
+        writer.write_all(&self.src_ip.octets())?;
+        writer.write_all(&self.dst_ip.octets())?;
+        writer.write_u32::<NetworkEndian>(self.src_port)?;
+        writer.write_u32::<NetworkEndian>(self.dst_port)?;
+        writer.write_u32::<NetworkEndian>(self.tcp_flags)?;
+        writer.write_u32::<NetworkEndian>(self.tos)?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for SampledIpv6 {
+    /// 6 fields of 4-octets each and two 16-octets IPv6 addresses
+    const BASE_LENGTH: usize = 56;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.length)?;
+        writer.write_u32::<NetworkEndian>(self.protocol)?;
+        writer.write_all(&self.src_ip.octets())?;
+        writer.write_all(&self.dst_ip.octets())?;
+        writer.write_u32::<NetworkEndian>(self.src_port)?;
+        writer.write_u32::<NetworkEndian>(self.dst_port)?;
+        writer.write_u32::<NetworkEndian>(self.tcp_flags)?;
+        writer.write_u32::<NetworkEndian>(self.priority)?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for ExtendedSwitch {
+    /// 4 fields of 4-octets each
+    const BASE_LENGTH: usize = 16;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.src_vlan())?;
+        writer.write_u32::<NetworkEndian>(self.src_priority())?;
+        writer.write_u32::<NetworkEndian>(self.dst_vlan())?;
+        writer.write_u32::<NetworkEndian>(self.dst_priority())?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<FlowRecordWritingError> for ExtendedRouter {
+    /// 4-octets source mask length and 4-octets destination mask length
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH + address_len(&self.next_hop())
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), FlowRecordWritingError> {
+        write_address(writer, &self.next_hop())?;
+        writer.write_u32::<NetworkEndian>(self.src_mask_len())?;
+        writer.write_u32::<NetworkEndian>(self.dst_mask_len())?;
+        Ok(())
+    }
+}
+
+/// Error shared by all the standard counter records
+#[derive(WritingError, Eq, PartialEq, Clone, Debug)]
+pub enum CounterRecordWritingError {
+    StdIOError(#[from_std_io_error] String),
+}
+
+impl WritablePdu<CounterRecordWritingError> for CounterRecord {
+    /// 4-octets data format and 4-octets length
+    const BASE_LENGTH: usize = 8;
+
+    fn len(&self) -> usize {
+        let value_len = match self {
+            Self::GenericInterface(value) => value.len(),
+            Self::EthernetInterface(value) => value.len(),
+            Self::Processor(value) => value.len(),
+            Self::Unknown(_, value) => value.len(),
+        };
+        Self::BASE_LENGTH + value_len
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CounterRecordWritingError> {
+        write_data_format(writer, self.data_format(), self.len() - Self::BASE_LENGTH)?;
+        match self {
+            Self::GenericInterface(value) => value.write(writer),
+            Self::EthernetInterface(value) => value.write(writer),
+            Self::Processor(value) => value.write(writer),
+            Self::Unknown(_, value) => Ok(writer.write_all(value)?),
+        }
+    }
+}
+
+impl WritablePdu<CounterRecordWritingError> for GenericInterfaceCounters {
+    /// 16 fields of 4-octets each and 3 fields of 8-octets each
+    const BASE_LENGTH: usize = 88;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CounterRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.if_index)?;
+        writer.write_u32::<NetworkEndian>(self.if_type)?;
+        writer.write_u64::<NetworkEndian>(self.if_speed)?;
+        writer.write_u32::<NetworkEndian>(self.if_direction)?;
+        writer.write_u32::<NetworkEndian>(self.if_status)?;
+        writer.write_u64::<NetworkEndian>(self.if_in_octets)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_ucast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_multicast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_broadcast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_discards)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_errors)?;
+        writer.write_u32::<NetworkEndian>(self.if_in_unknown_protos)?;
+        writer.write_u64::<NetworkEndian>(self.if_out_octets)?;
+        writer.write_u32::<NetworkEndian>(self.if_out_ucast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_out_multicast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_out_broadcast_pkts)?;
+        writer.write_u32::<NetworkEndian>(self.if_out_discards)?;
+        writer.write_u32::<NetworkEndian>(self.if_out_errors)?;
+        writer.write_u32::<NetworkEndian>(self.if_promiscuous_mode)?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<CounterRecordWritingError> for EthernetInterfaceCounters {
+    /// 13 fields of 4-octets each
+    const BASE_LENGTH: usize = 52;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CounterRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_alignment_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_fcs_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_single_collision_frames)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_multiple_collision_frames)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_sqe_test_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_deferred_transmissions)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_late_collisions)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_excessive_collisions)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_internal_mac_transmit_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_carrier_sense_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_frame_too_longs)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_internal_mac_receive_errors)?;
+        writer.write_u32::<NetworkEndian>(self.dot3_stats_symbol_errors)?;
+        Ok(())
+    }
+}
+
+impl WritablePdu<CounterRecordWritingError> for ProcessorCounters {
+    /// 3 fields of 4-octets each and 2 fields of 8-octets each
+    const BASE_LENGTH: usize = 28;
+
+    fn len(&self) -> usize {
+        Self::BASE_LENGTH
+    }
+
+    fn write<T: Write>(&self, writer: &mut T) -> Result<(), CounterRecordWritingError> {
+        writer.write_u32::<NetworkEndian>(self.cpu_5s)?;
+        writer.write_u32::<NetworkEndian>(self.cpu_1m)?;
+        writer.write_u32::<NetworkEndian>(self.cpu_5m)?;
+        writer.write_u64::<NetworkEndian>(self.total_memory)?;
+        writer.write_u64::<NetworkEndian>(self.free_memory)?;
+        Ok(())
+    }
+}
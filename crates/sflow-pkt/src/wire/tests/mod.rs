@@ -0,0 +1,317 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use netgauze_parse_utils::{
+    test_helpers::{test_parse_error, test_parsed_completely, test_write},
+    Span, WritablePdu,
+};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{
+    wire::{deserializer::*, serializer::*},
+    *,
+};
+
+#[test]
+fn test_datagram_flow_sample() -> Result<(), SFlowDatagramWritingError> {
+    let good_wire = [
+        0x00, 0x00, 0x00, 0x05, // version
+        0x00, 0x00, 0x00, 0x01, // IPv4 agent address
+        0xc0, 0x00, 0x02, 0x01, // agent address
+        0x00, 0x00, 0x00, 0x00, // sub agent id
+        0x00, 0x00, 0x00, 0x07, // sequence number
+        0x00, 0x01, 0x86, 0xa0, // uptime
+        0x00, 0x00, 0x00, 0x01, // samples count
+        0x00, 0x00, 0x00, 0x01, // flow sample
+        0x00, 0x00, 0x00, 0x58, // length
+        0x00, 0x00, 0x00, 0x2a, // sequence number
+        0x00, 0x00, 0x00, 0x05, // source id
+        0x00, 0x00, 0x04, 0x00, // sampling rate
+        0x00, 0x01, 0x00, 0x00, // sample pool
+        0x00, 0x00, 0x00, 0x00, // drops
+        0x00, 0x00, 0x00, 0x05, // input
+        0x80, 0x00, 0x00, 0x02, // output
+        0x00, 0x00, 0x00, 0x02, // records count
+        0x00, 0x00, 0x00, 0x01, // sampled header
+        0x00, 0x00, 0x00, 0x18, // length
+        0x00, 0x00, 0x00, 0x01, // Ethernet
+        0x00, 0x00, 0x05, 0xdc, // frame length
+        0x00, 0x00, 0x00, 0x04, // stripped
+        0x00, 0x00, 0x00, 0x06, // header length
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0x00, // header and padding
+        0x00, 0x00, 0x03, 0xe9, // extended switch
+        0x00, 0x00, 0x00, 0x10, // length
+        0x00, 0x00, 0x00, 0x64, // source vlan
+        0x00, 0x00, 0x00, 0x00, // source priority
+        0x00, 0x00, 0x00, 0xc8, // destination vlan
+        0x00, 0x00, 0x00, 0x00, // destination priority
+    ];
+
+    let good = SFlowDatagram::new(
+        IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+        0,
+        7,
+        100000,
+        vec![Sample::Flow(FlowSample {
+            sequence_number: 42,
+            source_id: DataSource::new(0, 5),
+            sampling_rate: 1024,
+            sample_pool: 65536,
+            drops: 0,
+            input: Interface::new(0, 5),
+            output: Interface::new(2, 2),
+            records: vec![
+                FlowRecord::SampledHeader(SampledHeader::new(
+                    HeaderProtocol::EthernetIso88023,
+                    1500,
+                    4,
+                    vec![0x00, 0x11, 0x22, 0x33, 0x44, 0x55],
+                )),
+                FlowRecord::ExtendedSwitch(ExtendedSwitch::new(100, 0, 200, 0)),
+            ],
+        })],
+    );
+
+    test_parsed_completely(&good_wire, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_datagram_expanded_counters_sample() -> Result<(), SFlowDatagramWritingError> {
+    let good_wire = [
+        0x00, 0x00, 0x00, 0x05, // version
+        0x00, 0x00, 0x00, 0x02, // IPv6 agent address
+        0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, // agent address
+        0x00, 0x00, 0x00, 0x01, // sub agent id
+        0x00, 0x00, 0x00, 0x01, // sequence number
+        0x00, 0x00, 0x03, 0xe8, // uptime
+        0x00, 0x00, 0x00, 0x01, // samples count
+        0x00, 0x00, 0x00, 0x04, // expanded counters sample
+        0x00, 0x00, 0x00, 0x7c, // length
+        0x00, 0x00, 0x00, 0x01, // sequence number
+        0x00, 0x00, 0x00, 0x00, // source id type
+        0x01, 0x00, 0x00, 0x00, // source id index
+        0x00, 0x00, 0x00, 0x03, // records count
+        0x00, 0x00, 0x03, 0xe9, // processor
+        0x00, 0x00, 0x00, 0x1c, // length
+        0x00, 0x00, 0x00, 0x64, // 5s cpu
+        0x00, 0x00, 0x00, 0xc8, // 1m cpu
+        0x00, 0x00, 0x01, 0x2c, // 5m cpu
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // total memory
+        0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x00, // free memory
+        0x00, 0x00, 0x00, 0x02, // ethernet interface
+        0x00, 0x00, 0x00, 0x34, // length
+        0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+        0x04, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x06, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00,
+        0x00, 0x08, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x0b, 0x00,
+        0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x0d, // counters
+        0x01, 0x13, 0xd0, 0x05, // enterprise 4413, format 5
+        0x00, 0x00, 0x00, 0x04, // length
+        0xde, 0xad, 0xbe, 0xef, // data
+    ];
+
+    let sample = CountersSample::new(
+        1,
+        DataSource::new(0, 0x01000000),
+        vec![
+            CounterRecord::Processor(ProcessorCounters {
+                cpu_5s: 100,
+                cpu_1m: 200,
+                cpu_5m: 300,
+                total_memory: 0x100000000,
+                free_memory: 0x80000000,
+            }),
+            CounterRecord::EthernetInterface(EthernetInterfaceCounters {
+                dot3_stats_alignment_errors: 1,
+                dot3_stats_fcs_errors: 2,
+                dot3_stats_single_collision_frames: 3,
+                dot3_stats_multiple_collision_frames: 4,
+                dot3_stats_sqe_test_errors: 5,
+                dot3_stats_deferred_transmissions: 6,
+                dot3_stats_late_collisions: 7,
+                dot3_stats_excessive_collisions: 8,
+                dot3_stats_internal_mac_transmit_errors: 9,
+                dot3_stats_carrier_sense_errors: 10,
+                dot3_stats_frame_too_longs: 11,
+                dot3_stats_internal_mac_receive_errors: 12,
+                dot3_stats_symbol_errors: 13,
+            }),
+            CounterRecord::Unknown(DataFormat::new(4413, 5), vec![0xde, 0xad, 0xbe, 0xef]),
+        ],
+    );
+    let good = SFlowDatagram::new(
+        IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        1,
+        1,
+        1000,
+        vec![Sample::ExpandedCounters(sample.clone())],
+    );
+
+    test_parsed_completely(&good_wire, &good);
+    test_write(&good, &good_wire)?;
+
+    // The source id index doesn't fit in the compact format
+    assert_eq!(
+        Sample::Counters(sample).write(&mut Vec::new()),
+        Err(SampleWritingError::CountersSampleError(
+            CountersSampleWritingError::InvalidCompactDataSource(DataSource::new(0, 0x01000000))
+        ))
+    );
+    Ok(())
+}
+
+#[test]
+fn test_expanded_flow_sample() -> Result<(), SampleWritingError> {
+    let good_wire = [
+        0x00, 0x00, 0x00, 0x03, // expanded flow sample
+        0x00, 0x00, 0x00, 0x98, // length
+        0x00, 0x00, 0x00, 0x01, // sequence number
+        0x00, 0x00, 0x00, 0x00, // source id type
+        0x00, 0x00, 0x00, 0x03, // source id index
+        0x00, 0x00, 0x00, 0x64, // sampling rate
+        0x00, 0x00, 0x27, 0x10, // sample pool
+        0x00, 0x00, 0x00, 0x01, // drops
+        0x00, 0x00, 0x00, 0x00, // input format
+        0x00, 0x00, 0x00, 0x03, // input value
+        0x00, 0x00, 0x00, 0x01, // output format
+        0x00, 0x00, 0x01, 0x00, // output value
+        0x00, 0x00, 0x00, 0x03, // records count
+        0x00, 0x00, 0x00, 0x02, // sampled ethernet
+        0x00, 0x00, 0x00, 0x18, // length
+        0x00, 0x00, 0x05, 0xee, // length
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0x00, // source MAC
+        0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0x00, 0x00, // destination MAC
+        0x00, 0x00, 0x08, 0x00, // type
+        0x00, 0x00, 0x00, 0x03, // sampled IPv4
+        0x00, 0x00, 0x00, 0x20, // length
+        0x00, 0x00, 0x05, 0xdc, // length
+        0x00, 0x00, 0x00, 0x06, // protocol
+        0xc0, 0x00, 0x02, 0x01, // source IP
+        0xc6, 0x33, 0x64, 0x01, // destination IP
+        0x00, 0x00, 0xc3, 0x50, // source port
+        0x00, 0x00, 0x00, 0x50, // destination port
+        0x00, 0x00, 0x00, 0x12, // TCP flags
+        0x00, 0x00, 0x00, 0x00, // TOS
+        0x00, 0x00, 0x03, 0xea, // extended router
+        0x00, 0x00, 0x00, 0x1c, // length
+        0x00, 0x00, 0x00, 0x02, // IPv6 next hop
+        0x20, 0x01, 0x0d, 0xb8, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x01, // next hop
+        0x00, 0x00, 0x00, 0x18, // source mask length
+        0x00, 0x00, 0x00, 0x20, // destination mask length
+    ];
+
+    let good = Sample::ExpandedFlow(FlowSample {
+        sequence_number: 1,
+        source_id: DataSource::new(0, 3),
+        sampling_rate: 100,
+        sample_pool: 10000,
+        drops: 1,
+        input: Interface::new(0, 3),
+        output: Interface::new(1, 256),
+        records: vec![
+            FlowRecord::SampledEthernet(SampledEthernet::new(
+                1518,
+                MacAddress([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+                MacAddress([0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb]),
+                0x0800,
+            )),
+            FlowRecord::SampledIpv4(SampledIpv4 {
+                length: 1500,
+                protocol: 6,
+                src_ip: Ipv4Addr::new(192, 0, 2, 1),
+                dst_ip: Ipv4Addr::new(198, 51, 100, 1),
+                src_port: 50000,
+                dst_port: 80,
+                tcp_flags: 0x12,
+                tos: 0,
+            }),
+            FlowRecord::ExtendedRouter(ExtendedRouter::new(
+                IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                24,
+                32,
+            )),
+        ],
+    });
+
+    test_parsed_completely(&good_wire, &good);
+    test_write(&good, &good_wire)?;
+    Ok(())
+}
+
+#[test]
+fn test_datagram_parse_errors() {
+    let bad_version_wire = [0x00, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x01];
+    let bad_address_type_wire = [0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x03];
+
+    let bad_version = LocatedSFlowDatagramParsingError::new(
+        Span::new(&bad_version_wire),
+        SFlowDatagramParsingError::UnsupportedVersion(4),
+    );
+    let bad_address_type = LocatedSFlowDatagramParsingError::new(
+        unsafe { Span::new_from_raw_offset(4, &bad_address_type_wire[4..]) },
+        SFlowDatagramParsingError::UndefinedAddressType(UndefinedAddressType(3)),
+    );
+
+    test_parse_error::<SFlowDatagram, LocatedSFlowDatagramParsingError<'_>>(
+        &bad_version_wire,
+        &bad_version,
+    );
+    test_parse_error::<SFlowDatagram, LocatedSFlowDatagramParsingError<'_>>(
+        &bad_address_type_wire,
+        &bad_address_type,
+    );
+}
+
+#[test]
+fn test_flow_record_parse_errors() {
+    let bad_header_protocol_wire = [
+        0x00, 0x00, 0x00, 0x01, // sampled header
+        0x00, 0x00, 0x00, 0x10, // length
+        0x00, 0x00, 0x00, 0x63, // undefined protocol
+        0x00, 0x00, 0x00, 0x40, // frame length
+        0x00, 0x00, 0x00, 0x00, // stripped
+        0x00, 0x00, 0x00, 0x00, // header length
+    ];
+    let bad_trailing_wire = [
+        0x00, 0x00, 0x03, 0xe9, // extended switch
+        0x00, 0x00, 0x00, 0x14, // length
+        0x00, 0x00, 0x00, 0x64, // source vlan
+        0x00, 0x00, 0x00, 0x00, // source priority
+        0x00, 0x00, 0x00, 0xc8, // destination vlan
+        0x00, 0x00, 0x00, 0x00, // destination priority
+        0x00, 0x00, 0x00, 0x00, // trailing data
+    ];
+
+    let bad_header_protocol = LocatedFlowRecordParsingError::new(
+        unsafe { Span::new_from_raw_offset(8, &bad_header_protocol_wire[8..]) },
+        FlowRecordParsingError::UndefinedHeaderProtocol(UndefinedHeaderProtocol(0x63)),
+    );
+    let bad_trailing = LocatedFlowRecordParsingError::new(
+        unsafe { Span::new_from_raw_offset(24, &bad_trailing_wire[24..]) },
+        FlowRecordParsingError::NomError(nom::error::ErrorKind::NonEmpty),
+    );
+
+    test_parse_error::<FlowRecord, LocatedFlowRecordParsingError<'_>>(
+        &bad_header_protocol_wire,
+        &bad_header_protocol,
+    );
+    test_parse_error::<FlowRecord, LocatedFlowRecordParsingError<'_>>(
+        &bad_trailing_wire,
+        &bad_trailing,
+    );
+}
@@ -390,7 +390,11 @@ pub struct MockActiveConnect {
 
 #[async_trait::async_trait]
 impl ActiveConnect<SocketAddr, Mock, BgpCodec> for MockActiveConnect {
-    async fn connect(&mut self, peer_addr: SocketAddr) -> io::Result<Mock> {
+    async fn connect(
+        &mut self,
+        peer_addr: SocketAddr,
+        _config: &PeerConfig,
+    ) -> io::Result<Mock> {
         assert_eq!(self.peer_addr, peer_addr);
         Ok(self.io_builder.build())
     }
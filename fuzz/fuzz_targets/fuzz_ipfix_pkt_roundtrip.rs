@@ -0,0 +1,47 @@
+// Copyright (C) 2024-present The NetGauze Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or
+// implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use netgauze_flow_pkt::{ipfix::IpfixPacket, template_cache::TemplateCache};
+use netgauze_parse_utils::{ReadablePduWithOneInput, Span, WritablePduWithOneInput};
+use std::{cell::RefCell, rc::Rc};
+
+// Every parsed message is written and parsed again with a separate templates
+// cache, the written messages are compared instead of the parsed ones since
+// NaN floats are never equal
+fuzz_target!(|data: &[u8]| {
+    let mut buf = data;
+    let templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    let written_templates_map = Rc::new(RefCell::new(TemplateCache::new()));
+    while let Ok((retbuf, msg)) = IpfixPacket::from_wire(Span::new(buf), templates_map.clone()) {
+        buf = retbuf.fragment();
+        let mut written = vec![];
+        if msg
+            .write(&mut written, Some(templates_map.clone()))
+            .is_err()
+        {
+            return;
+        }
+        let (_, parsed) =
+            IpfixPacket::from_wire(Span::new(&written), written_templates_map.clone())
+                .expect("failed to parse a written IPFIX message");
+        let mut rewritten = vec![];
+        parsed
+            .write(&mut rewritten, Some(written_templates_map.clone()))
+            .expect("failed to write a parsed IPFIX message");
+        assert_eq!(written, rewritten);
+    }
+});